@@ -0,0 +1,49 @@
+//! ACPI Support
+//!
+//! Structures of the ACPI tables used by firmware, and support code built on them.
+//!
+//! See <https://uefi.org/specs/ACPI/6.5/05_ACPI_Software_Programming_Model.html>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+pub mod pm_timer;
+
+/// Address space IDs of a [`GenericAddress`].
+pub mod address_space {
+    pub const SYSTEM_MEMORY: u8 = 0x00;
+    pub const SYSTEM_IO: u8 = 0x01;
+    pub const PCI_CONFIGURATION: u8 = 0x02;
+}
+
+/// The header common to the ACPI description tables, per ACPI 6.5 5.2.6.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DescriptionHeader {
+    pub signature: [u8; 4],
+    /// The size of the table in bytes, including the header.
+    pub length: u32,
+    pub revision: u8,
+    pub checksum: u8,
+    pub oem_id: [u8; 6],
+    pub oem_table_id: [u8; 8],
+    pub oem_revision: u32,
+    pub creator_id: u32,
+    pub creator_revision: u32,
+}
+
+/// The location of a register (Generic Address Structure), per ACPI 6.5 5.2.3.2.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GenericAddress {
+    /// One of the [`address_space`] IDs.
+    pub address_space_id: u8,
+    pub register_bit_width: u8,
+    pub register_bit_offset: u8,
+    pub access_size: u8,
+    pub address: u64,
+}
@@ -0,0 +1,273 @@
+//! ACPI Power Management Timer
+//!
+//! The PM timer is a free-running counter at 3.579545 MHz in the I/O space of every ACPI platform, which firmware can
+//! use for precise delays before other timers are set up. The counter is 24 bits wide, or 32 bits if the FADT sets
+//! [`FLAG_TMR_VAL_EXT`], and wraps around to 0.
+//!
+//! See <https://uefi.org/specs/ACPI/6.5/04_ACPI_Hardware_Specification.html#power-management-timer>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::mem::{size_of, MaybeUninit};
+
+use super::{DescriptionHeader, GenericAddress};
+
+/// Frequency of the PM timer in Hz.
+pub const PM_TIMER_FREQUENCY: u64 = 3_579_545;
+/// FADT flag set if the PM timer counter is 32 bits wide rather than 24.
+pub const FLAG_TMR_VAL_EXT: u32 = 1 << 8;
+/// Signature of the FADT.
+pub const FADT_SIGNATURE: [u8; 4] = *b"FACP";
+
+/// The Fixed ACPI Description Table (FADT), per ACPI 6.5 5.2.9.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FadtTable {
+    pub header: DescriptionHeader,
+    pub firmware_ctrl: u32,
+    pub dsdt: u32,
+    pub reserved0: u8,
+    pub preferred_pm_profile: u8,
+    pub sci_int: u16,
+    pub smi_cmd: u32,
+    pub acpi_enable: u8,
+    pub acpi_disable: u8,
+    pub s4bios_req: u8,
+    pub pstate_cnt: u8,
+    pub pm1a_evt_blk: u32,
+    pub pm1b_evt_blk: u32,
+    pub pm1a_cnt_blk: u32,
+    pub pm1b_cnt_blk: u32,
+    pub pm2_cnt_blk: u32,
+    /// The I/O port of the PM timer counter, or 0 if [`Self::x_pm_tmr_blk`] should be used.
+    pub pm_tmr_blk: u32,
+    pub gpe0_blk: u32,
+    pub gpe1_blk: u32,
+    pub pm1_evt_len: u8,
+    pub pm1_cnt_len: u8,
+    pub pm2_cnt_len: u8,
+    pub pm_tmr_len: u8,
+    pub gpe0_blk_len: u8,
+    pub gpe1_blk_len: u8,
+    pub gpe1_base: u8,
+    pub cst_cnt: u8,
+    pub p_lvl2_lat: u16,
+    pub p_lvl3_lat: u16,
+    pub flush_size: u16,
+    pub flush_stride: u16,
+    pub duty_offset: u8,
+    pub duty_width: u8,
+    pub day_alrm: u8,
+    pub mon_alrm: u8,
+    pub century: u8,
+    pub iapc_boot_arch: u16,
+    pub reserved1: u8,
+    /// Feature flags, e.g. [`FLAG_TMR_VAL_EXT`].
+    pub flags: u32,
+    pub reset_reg: GenericAddress,
+    pub reset_value: u8,
+    pub arm_boot_arch: u16,
+    pub fadt_minor_version: u8,
+    pub x_firmware_ctrl: u64,
+    pub x_dsdt: u64,
+    pub x_pm1a_evt_blk: GenericAddress,
+    pub x_pm1b_evt_blk: GenericAddress,
+    pub x_pm1a_cnt_blk: GenericAddress,
+    pub x_pm1b_cnt_blk: GenericAddress,
+    pub x_pm2_cnt_blk: GenericAddress,
+    /// The location of the PM timer counter; takes precedence over [`Self::pm_tmr_blk`] if its address is not 0.
+    pub x_pm_tmr_blk: GenericAddress,
+    pub x_gpe0_blk: GenericAddress,
+    pub x_gpe1_blk: GenericAddress,
+    pub sleep_control_reg: GenericAddress,
+    pub sleep_status_reg: GenericAddress,
+    pub hypervisor_vendor_identity: u64,
+}
+
+impl FadtTable {
+    /// Reads the FADT at the start of `bytes`.
+    ///
+    /// Tables of earlier revisions are shorter; the fields past their length read as 0. Returns `None` if the
+    /// signature is not [`FADT_SIGNATURE`], or if the table length is smaller than the header or larger than `bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < size_of::<DescriptionHeader>() || bytes[..4] != FADT_SIGNATURE {
+            return None;
+        }
+        let length = u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize;
+        if length < size_of::<DescriptionHeader>() || length > bytes.len() {
+            return None;
+        }
+        let mut table = MaybeUninit::<Self>::zeroed();
+        let copied = length.min(size_of::<Self>());
+        //Safety: every bit pattern is a valid FadtTable, and at most size_of::<Self>() bytes are copied into it.
+        unsafe {
+            core::ptr::copy_nonoverlapping(bytes.as_ptr(), table.as_mut_ptr() as *mut u8, copied);
+            Some(table.assume_init())
+        }
+    }
+
+    /// Returns the I/O port of the PM timer counter, or `None` if the platform has no PM timer in I/O space.
+    pub fn pm_timer_port(&self) -> Option<u16> {
+        let x_pm_tmr_blk = self.x_pm_tmr_blk;
+        let (space, address) = match x_pm_tmr_blk.address {
+            0 => (super::address_space::SYSTEM_IO, self.pm_tmr_blk as u64),
+            address => (x_pm_tmr_blk.address_space_id, address),
+        };
+        match (space, u16::try_from(address)) {
+            (super::address_space::SYSTEM_IO, Ok(port)) if port != 0 => Some(port),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the number of ticks between the counter values `start` and `end`, allowing for the counter wrapping
+/// around once. `extended` is whether the counter is 32 bits wide rather than 24.
+pub fn elapsed_ticks(start: u32, end: u32, extended: bool) -> u32 {
+    match extended {
+        true => end.wrapping_sub(start),
+        false => end.wrapping_sub(start) & 0x00FF_FFFF,
+    }
+}
+
+/// Returns the microseconds between the counter values `start` and `end`, allowing for the counter wrapping around
+/// once. `extended` is whether the counter is 32 bits wide rather than 24.
+pub fn elapsed_us(start: u32, end: u32, extended: bool) -> u64 {
+    elapsed_ticks(start, end, extended) as u64 * 1_000_000 / PM_TIMER_FREQUENCY
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub use timer::*;
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod timer {
+    use super::{FadtTable, FLAG_TMR_VAL_EXT};
+    use crate::cpu::io_port::IoPort;
+
+    /// The PM timer of the platform.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct PmTimer {
+        counter: IoPort<u32>,
+        extended: bool,
+    }
+
+    impl PmTimer {
+        /// Creates the timer with its counter at `counter`, 32 bits wide if `extended`.
+        pub fn new(counter: IoPort<u32>, extended: bool) -> Self {
+            Self { counter, extended }
+        }
+
+        /// Returns the port of the counter.
+        pub fn counter(&self) -> IoPort<u32> {
+            self.counter
+        }
+
+        /// Returns whether the counter is 32 bits wide rather than 24.
+        pub fn is_extended(&self) -> bool {
+            self.extended
+        }
+
+        /// Reads the counter.
+        pub fn read_counter(&self) -> u32 {
+            match self.extended {
+                true => self.counter.read(),
+                false => self.counter.read() & 0x00FF_FFFF,
+            }
+        }
+
+        /// Returns the microseconds between the counter values `start` and `end` of this timer.
+        pub fn elapsed_us(&self, start: u32, end: u32) -> u64 {
+            super::elapsed_us(start, end, self.extended)
+        }
+    }
+
+    /// Returns the PM timer described by `fadt`, or `None` if the platform has no PM timer in I/O space.
+    pub fn create_from_fadt(fadt: &FadtTable) -> Option<PmTimer> {
+        let port = fadt.pm_timer_port()?;
+        //Safety: reads of the PM timer counter have no side effects.
+        let counter = unsafe { IoPort::new(port) };
+        Some(PmTimer::new(counter, fadt.flags & FLAG_TMR_VAL_EXT != 0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::size_of;
+
+    use super::{elapsed_ticks, elapsed_us, FadtTable, FLAG_TMR_VAL_EXT};
+    use crate::acpi::{address_space, GenericAddress};
+
+    fn fadt_bytes(length: usize, pm_tmr_blk: u32, flags: u32, x_pm_tmr_blk: GenericAddress) -> Vec<u8> {
+        let mut fadt = FadtTable { pm_tmr_blk, flags, x_pm_tmr_blk, ..Default::default() };
+        fadt.header.signature = *b"FACP";
+        fadt.header.length = length as u32;
+        let bytes =
+            unsafe { core::slice::from_raw_parts(&fadt as *const FadtTable as *const u8, size_of::<FadtTable>()) };
+        bytes[..length].to_vec()
+    }
+
+    #[test]
+    fn layout_should_match_spec() {
+        assert_eq!(size_of::<GenericAddress>(), 12);
+        assert_eq!(size_of::<FadtTable>(), 276);
+        let bytes = fadt_bytes(276, 0x408, FLAG_TMR_VAL_EXT, GenericAddress::default());
+        assert_eq!(&bytes[76..80], &0x408u32.to_le_bytes());
+        assert_eq!(&bytes[112..116], &FLAG_TMR_VAL_EXT.to_le_bytes());
+    }
+
+    #[test]
+    fn elapsed_time_should_allow_for_wrap_around() {
+        assert_eq!(elapsed_ticks(100, 250, false), 150);
+        assert_eq!(elapsed_ticks(0x00FF_FFF0, 0x10, false), 0x20);
+        assert_eq!(elapsed_ticks(0xFFFF_FFF0, 0x10, true), 0x20);
+        // A 24-bit counter read as 32 bits after wrapping is the same elapsed time.
+        assert_eq!(elapsed_ticks(0x00FF_FFF0, 0x0000_0010, true), 0xFF00_0020);
+
+        assert_eq!(elapsed_us(0, 3_579_545, true), 1_000_000);
+        assert_eq!(elapsed_us(0x00FF_FFFF - 357, 1, false), 100);
+        assert_eq!(elapsed_us(5, 5, false), 0);
+        // The longest interval of a 24-bit counter is about 4.7 seconds.
+        assert_eq!(elapsed_us(1, 0, false), 4_686_968);
+    }
+
+    #[test]
+    fn fadt_should_locate_the_pm_timer() {
+        let x_io = GenericAddress { address_space_id: address_space::SYSTEM_IO, address: 0x1808, ..Default::default() };
+        let x_mem = GenericAddress {
+            address_space_id: address_space::SYSTEM_MEMORY,
+            address: 0xFED0_0000,
+            ..Default::default()
+        };
+
+        // An ACPI 1.0 table has no extended block.
+        let fadt = FadtTable::from_bytes(&fadt_bytes(116, 0x408, 0, x_io)).unwrap();
+        assert_eq!(fadt.pm_timer_port(), Some(0x408));
+        let fadt = FadtTable::from_bytes(&fadt_bytes(276, 0x408, 0, x_io)).unwrap();
+        assert_eq!(fadt.pm_timer_port(), Some(0x1808));
+        let fadt = FadtTable::from_bytes(&fadt_bytes(276, 0x408, 0, x_mem)).unwrap();
+        assert_eq!(fadt.pm_timer_port(), None);
+        let fadt = FadtTable::from_bytes(&fadt_bytes(276, 0, 0, GenericAddress::default())).unwrap();
+        assert_eq!(fadt.pm_timer_port(), None);
+
+        let mut bytes = fadt_bytes(276, 0x408, 0, x_io);
+        assert!(FadtTable::from_bytes(&bytes[..200]).is_none());
+        bytes[0] = b'X';
+        assert!(FadtTable::from_bytes(&bytes).is_none());
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[test]
+    fn pm_timer_should_be_created_from_fadt() {
+        use super::create_from_fadt;
+
+        let fadt = FadtTable::from_bytes(&fadt_bytes(276, 0x408, FLAG_TMR_VAL_EXT, GenericAddress::default())).unwrap();
+        let timer = create_from_fadt(&fadt).unwrap();
+        assert_eq!((timer.counter().port(), timer.is_extended()), (0x408, true));
+        assert_eq!(timer.elapsed_us(0xFFFF_FFFF, 3_579_544), 1_000_000);
+        assert!(create_from_fadt(&FadtTable::default()).is_none());
+    }
+}
@@ -0,0 +1,175 @@
+//! Boot Mode
+//!
+//! The boot mode determined by the HOB producer phase and reported to DXE in the PHIT HOB.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V1_Boot_Paths.html#boot-mode-services>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::fmt;
+
+/// The system boot mode (EFI_BOOT_MODE).
+///
+/// Values not defined by the specification are preserved in [`BootMode::Unknown`], so converting to and from `u32`
+/// is lossless.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-4.3
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BootMode {
+    FullConfiguration = 0x00,
+    MinimalConfiguration = 0x01,
+    AssumingNoConfigurationChanges = 0x02,
+    FullConfigurationPlusDiagnostics = 0x03,
+    DefaultSettings = 0x04,
+    S4Resume = 0x05,
+    S5Resume = 0x06,
+    MfgModeSettings = 0x07,
+    S2Resume = 0x10,
+    S3Resume = 0x11,
+    FlashUpdate = 0x12,
+    RecoveryMode = 0x20,
+    /// A boot mode not defined by the specification (0x21-0xEFFF are reserved; 0xF000 and above are OEM-defined).
+    Unknown(u32) = 0xFFFFFFFF,
+}
+
+impl BootMode {
+    /// Returns true if the system is resuming from S3 sleep.
+    pub const fn is_s3_resume(&self) -> bool {
+        matches!(self, Self::S3Resume)
+    }
+
+    /// Returns true if the system is booting in recovery mode.
+    pub const fn is_recovery(&self) -> bool {
+        matches!(self, Self::RecoveryMode)
+    }
+
+    /// Returns true if the system is booting to update the flash.
+    pub const fn is_flash_update(&self) -> bool {
+        matches!(self, Self::FlashUpdate)
+    }
+
+    /// Returns the name of the spec constant for this boot mode, e.g. `"BOOT_ON_S3_RESUME"`.
+    pub const fn name(&self) -> Option<&'static str> {
+        match self {
+            Self::FullConfiguration => Some("BOOT_WITH_FULL_CONFIGURATION"),
+            Self::MinimalConfiguration => Some("BOOT_WITH_MINIMAL_CONFIGURATION"),
+            Self::AssumingNoConfigurationChanges => Some("BOOT_ASSUMING_NO_CONFIGURATION_CHANGES"),
+            Self::FullConfigurationPlusDiagnostics => Some("BOOT_WITH_FULL_CONFIGURATION_PLUS_DIAGNOSTICS"),
+            Self::DefaultSettings => Some("BOOT_WITH_DEFAULT_SETTINGS"),
+            Self::S4Resume => Some("BOOT_ON_S4_RESUME"),
+            Self::S5Resume => Some("BOOT_ON_S5_RESUME"),
+            Self::MfgModeSettings => Some("BOOT_WITH_MFG_MODE_SETTINGS"),
+            Self::S2Resume => Some("BOOT_ON_S2_RESUME"),
+            Self::S3Resume => Some("BOOT_ON_S3_RESUME"),
+            Self::FlashUpdate => Some("BOOT_ON_FLASH_UPDATE"),
+            Self::RecoveryMode => Some("BOOT_IN_RECOVERY_MODE"),
+            Self::Unknown(_) => None,
+        }
+    }
+}
+
+impl From<u32> for BootMode {
+    fn from(value: u32) -> Self {
+        match value {
+            0x00 => Self::FullConfiguration,
+            0x01 => Self::MinimalConfiguration,
+            0x02 => Self::AssumingNoConfigurationChanges,
+            0x03 => Self::FullConfigurationPlusDiagnostics,
+            0x04 => Self::DefaultSettings,
+            0x05 => Self::S4Resume,
+            0x06 => Self::S5Resume,
+            0x07 => Self::MfgModeSettings,
+            0x10 => Self::S2Resume,
+            0x11 => Self::S3Resume,
+            0x12 => Self::FlashUpdate,
+            0x20 => Self::RecoveryMode,
+            value => Self::Unknown(value),
+        }
+    }
+}
+
+impl From<BootMode> for u32 {
+    fn from(value: BootMode) -> Self {
+        match value {
+            BootMode::FullConfiguration => 0x00,
+            BootMode::MinimalConfiguration => 0x01,
+            BootMode::AssumingNoConfigurationChanges => 0x02,
+            BootMode::FullConfigurationPlusDiagnostics => 0x03,
+            BootMode::DefaultSettings => 0x04,
+            BootMode::S4Resume => 0x05,
+            BootMode::S5Resume => 0x06,
+            BootMode::MfgModeSettings => 0x07,
+            BootMode::S2Resume => 0x10,
+            BootMode::S3Resume => 0x11,
+            BootMode::FlashUpdate => 0x12,
+            BootMode::RecoveryMode => 0x20,
+            BootMode::Unknown(value) => value,
+        }
+    }
+}
+
+/// Displays the spec constant name (e.g. `BOOT_ON_S3_RESUME`), or `BOOT_MODE(0x...)` for unknown boot modes.
+impl fmt::Display for BootMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => f.write_str(name),
+            None => write!(f, "BOOT_MODE({:#x})", u32::from(*self)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BootMode;
+
+    #[test]
+    fn values_should_match_spec() {
+        let spec = [
+            (0x00, BootMode::FullConfiguration),
+            (0x01, BootMode::MinimalConfiguration),
+            (0x02, BootMode::AssumingNoConfigurationChanges),
+            (0x03, BootMode::FullConfigurationPlusDiagnostics),
+            (0x04, BootMode::DefaultSettings),
+            (0x05, BootMode::S4Resume),
+            (0x06, BootMode::S5Resume),
+            (0x07, BootMode::MfgModeSettings),
+            (0x10, BootMode::S2Resume),
+            (0x11, BootMode::S3Resume),
+            (0x12, BootMode::FlashUpdate),
+            (0x20, BootMode::RecoveryMode),
+        ];
+        for (value, mode) in spec {
+            assert_eq!(BootMode::from(value), mode);
+            assert_eq!(u32::from(mode), value);
+            assert!(mode.name().is_some());
+        }
+    }
+
+    #[test]
+    fn unknown_values_should_round_trip() {
+        for value in [0x08, 0x21, 0xF000, u32::MAX] {
+            assert_eq!(BootMode::from(value), BootMode::Unknown(value));
+            assert_eq!(u32::from(BootMode::from(value)), value);
+        }
+    }
+
+    #[test]
+    fn predicates_and_display_should_use_spec_names() {
+        assert!(BootMode::S3Resume.is_s3_resume());
+        assert!(!BootMode::S4Resume.is_s3_resume());
+        assert!(BootMode::RecoveryMode.is_recovery());
+        assert!(BootMode::FlashUpdate.is_flash_update());
+        assert!(!BootMode::Unknown(0x11).is_s3_resume());
+
+        assert_eq!(std::format!("{}", BootMode::S3Resume), "BOOT_ON_S3_RESUME");
+        assert_eq!(std::format!("{}", BootMode::FullConfiguration), "BOOT_WITH_FULL_CONFIGURATION");
+        assert_eq!(std::format!("{}", BootMode::Unknown(0xF001)), "BOOT_MODE(0xf001)");
+    }
+}
@@ -0,0 +1,857 @@
+//! S3 Boot Script
+//!
+//! Opcodes, table entry layouts and entry encoders for the S3 boot script, the table of register writes and other
+//! operations recorded during a normal boot and replayed on resume from S3. The layouts match the table produced by
+//! the EDK II S3BootScriptLib, which is also the encoding expected by the S3 Save State Protocol.
+//!
+//! Every entry starts with an [`EntryHeader`] holding its opcode and total length in bytes, followed by the rest of the
+//! opcode's fixed structure and any variable length data. All structures are byte packed.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V5_S3_Resume.html>
+//! and <https://github.com/tianocore/edk2/blob/master/MdeModulePkg/Library/PiDxeS3BootScriptLib/BootScriptInternalFormat.h>
+//!
+//! ## Example
+//! ```
+//! use mu_pi::boot_script::{self, Width};
+//!
+//! // Record a 32-bit MMIO write, a 100us stall and the end of the script.
+//! let entries = [
+//!   boot_script::mem_write(Width::Uint32, 0xfed40044, 1, &0x1u32.to_le_bytes()).unwrap(),
+//!   boot_script::stall(100),
+//!   boot_script::terminate(),
+//! ];
+//! assert_eq!(entries[0].opcode(), boot_script::MEM_WRITE_OPCODE);
+//! assert_eq!(entries[0].as_bytes().len(), 23);
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{fmt, mem::size_of, slice};
+
+use r_efi::efi;
+
+use crate::protocols::smbus_hc::SmbusOperation;
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+pub mod diff;
+pub mod poll;
+pub mod smbus;
+pub mod table;
+pub mod writer;
+
+pub use diff::{diff, diff_with_options, DiffOptions, ScriptDiff};
+pub use poll::{io_poll_until, mem_poll_until, pci_config2_poll_until, pci_config_poll_until, PollUntil};
+pub use smbus::smbus_execute;
+pub use table::{Entries, Entry, ParseError, ScriptTable};
+pub use writer::{DispatchPolicy, Placement, Position, ScriptWriter};
+
+// Boot script opcodes (EFI_BOOT_SCRIPT_*_OPCODE), stored as a UINT16 in the table.
+pub const IO_WRITE_OPCODE: u16 = 0x00;
+pub const IO_READ_WRITE_OPCODE: u16 = 0x01;
+pub const MEM_WRITE_OPCODE: u16 = 0x02;
+pub const MEM_READ_WRITE_OPCODE: u16 = 0x03;
+pub const PCI_CONFIG_WRITE_OPCODE: u16 = 0x04;
+pub const PCI_CONFIG_READ_WRITE_OPCODE: u16 = 0x05;
+pub const SMBUS_EXECUTE_OPCODE: u16 = 0x06;
+pub const STALL_OPCODE: u16 = 0x07;
+pub const DISPATCH_OPCODE: u16 = 0x08;
+pub const DISPATCH_2_OPCODE: u16 = 0x09;
+pub const INFORMATION_OPCODE: u16 = 0x0A;
+pub const PCI_CONFIG2_WRITE_OPCODE: u16 = 0x0B;
+pub const PCI_CONFIG2_READ_WRITE_OPCODE: u16 = 0x0C;
+pub const IO_POLL_OPCODE: u16 = 0x0D;
+pub const MEM_POLL_OPCODE: u16 = 0x0E;
+pub const PCI_CONFIG_POLL_OPCODE: u16 = 0x0F;
+pub const PCI_CONFIG2_POLL_OPCODE: u16 = 0x10;
+
+// Opcodes internal to the table format (S3_BOOT_SCRIPT_LIB_*_OPCODE).
+pub const TABLE_OPCODE: u16 = 0xAA;
+pub const LABEL_OPCODE: u16 = 0xFE;
+pub const TERMINATE_OPCODE: u16 = 0xFF;
+
+/// Version of the table format recorded in [`TableHeader`].
+pub const TABLE_VERSION: u16 = 0x0001;
+
+/// Width of each access made by an entry (EFI_BOOT_SCRIPT_WIDTH).
+///
+/// The FIFO variants access the same address `count` times, and the fill variants write the same value `count`
+/// times. Either way the entry holds `count` values of the unit size.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Width {
+    Uint8,
+    Uint16,
+    Uint32,
+    Uint64,
+    FifoUint8,
+    FifoUint16,
+    FifoUint32,
+    FifoUint64,
+    FillUint8,
+    FillUint16,
+    FillUint32,
+    FillUint64,
+}
+
+impl Width {
+    /// Returns the size in bytes of a single access.
+    pub const fn unit_size(self) -> usize {
+        1 << (self as u32 & 0x3)
+    }
+}
+
+impl TryFrom<u32> for Width {
+    type Error = BootScriptError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => Self::Uint8,
+            1 => Self::Uint16,
+            2 => Self::Uint32,
+            3 => Self::Uint64,
+            4 => Self::FifoUint8,
+            5 => Self::FifoUint16,
+            6 => Self::FifoUint32,
+            7 => Self::FifoUint64,
+            8 => Self::FillUint8,
+            9 => Self::FillUint16,
+            10 => Self::FillUint32,
+            11 => Self::FillUint64,
+            _ => Err(BootScriptError::InvalidWidth(value))?,
+        })
+    }
+}
+
+/// Errors reported when encoding boot script entries or writing them to a table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BootScriptError {
+    /// The width is not an EFI_BOOT_SCRIPT_WIDTH value.
+    InvalidWidth(u32),
+    /// The entry would be longer than the 255 bytes its length field can describe.
+    EntryTooLong(usize),
+    /// The data does not hold `count` values of the access width.
+    DataLength { expected: usize, actual: usize },
+    /// A value or mask does not fit in the access width.
+    ValueTooWide(u64),
+    /// A label is not ASCII or contains a null character.
+    InvalidLabel,
+    /// The position does not identify an entry of the script.
+    InvalidPosition,
+    /// The entry has an opcode that only the writer may add to a table, e.g. the table header or terminate.
+    ReservedOpcode(u16),
+    /// The entry point of a dispatch entry is above 4GB, but the script may execute in 32-bit mode.
+    DispatchAbove4Gb(u64),
+    /// The table would be longer than the 4GB its length field can describe.
+    TableTooLong,
+    /// A poll timeout does not fit the timeout fields of the entry.
+    TimeoutTooLong,
+    /// An SMBus slave address does not fit in 7 bits.
+    InvalidSmbusAddress(usize),
+    /// The SMBus data is not a length the operation accepts.
+    SmbusDataLength { operation: SmbusOperation, length: usize },
+    /// The SMBus operation does not send the command or PEC it was given.
+    UnsupportedSmbusCommand(SmbusOperation),
+}
+
+impl fmt::Display for BootScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BootScriptError::InvalidWidth(width) => write!(f, "invalid boot script width {width}"),
+            BootScriptError::EntryTooLong(length) => write!(f, "boot script entry length {length:#x} exceeds 0xff"),
+            BootScriptError::DataLength { expected, actual } => {
+                write!(f, "boot script data is {actual:#x} bytes, expected {expected:#x}")
+            }
+            BootScriptError::ValueTooWide(value) => write!(f, "value {value:#x} does not fit the access width"),
+            BootScriptError::InvalidLabel => write!(f, "boot script label is not a null-free ASCII string"),
+            BootScriptError::InvalidPosition => write!(f, "invalid boot script position"),
+            BootScriptError::ReservedOpcode(opcode) => write!(f, "boot script opcode {opcode:#x} is reserved"),
+            BootScriptError::DispatchAbove4Gb(entry_point) => {
+                write!(f, "dispatch entry point {entry_point:#x} is not reachable in 32-bit mode")
+            }
+            BootScriptError::TableTooLong => write!(f, "boot script table is too long"),
+            BootScriptError::TimeoutTooLong => write!(f, "boot script poll timeout is too long"),
+            BootScriptError::InvalidSmbusAddress(address) => write!(f, "SMBus address {address:#x} exceeds 0x7f"),
+            BootScriptError::SmbusDataLength { operation, length } => {
+                write!(f, "SMBus {operation:?} does not accept {length} data bytes")
+            }
+            BootScriptError::UnsupportedSmbusCommand(operation) => {
+                write!(f, "SMBus {operation:?} does not send a command or PEC")
+            }
+        }
+    }
+}
+
+impl From<BootScriptError> for efi::Status {
+    fn from(error: BootScriptError) -> Self {
+        match error {
+            BootScriptError::EntryTooLong(_) | BootScriptError::TableTooLong => efi::Status::OUT_OF_RESOURCES,
+            BootScriptError::InvalidPosition => efi::Status::NOT_FOUND,
+            BootScriptError::UnsupportedSmbusCommand(_) => efi::Status::UNSUPPORTED,
+            _ => efi::Status::INVALID_PARAMETER,
+        }
+    }
+}
+
+/// Header common to every entry (EFI_BOOT_SCRIPT_COMMON_HEADER).
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntryHeader {
+    /// One of the `*_OPCODE` values.
+    pub opcode: u16,
+    /// Length in bytes of the entry, including this header and any data that follows the fixed structure.
+    pub length: u8,
+}
+
+/// First entry of the table (EFI_BOOT_SCRIPT_TABLE_HEADER).
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TableHeader {
+    /// Opcode [`TABLE_OPCODE`].
+    pub header: EntryHeader,
+    /// [`TABLE_VERSION`].
+    pub version: u16,
+    /// Length in bytes of the table, including this header and the terminate entry.
+    pub table_length: u32,
+    pub reserved: [u16; 2],
+}
+
+/// Writes `count` values to I/O ports (EFI_BOOT_SCRIPT_IO_WRITE). Followed by the values.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IoWrite {
+    pub header: EntryHeader,
+    pub width: u32,
+    pub count: u32,
+    pub address: u64,
+}
+
+/// Read-modify-write of an I/O port (EFI_BOOT_SCRIPT_IO_READ_WRITE). Followed by the data and data mask values; the
+/// port is written with `(read & mask) | data`.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IoReadWrite {
+    pub header: EntryHeader,
+    pub width: u32,
+    pub address: u64,
+}
+
+/// Writes `count` values to memory (EFI_BOOT_SCRIPT_MEM_WRITE). Followed by the values.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemWrite {
+    pub header: EntryHeader,
+    pub width: u32,
+    pub address: u64,
+    pub count: u32,
+}
+
+/// Read-modify-write of memory (EFI_BOOT_SCRIPT_MEM_READ_WRITE). Followed by the data and data mask values.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemReadWrite {
+    pub header: EntryHeader,
+    pub width: u32,
+    pub address: u64,
+}
+
+/// Writes `count` values to PCI configuration space (EFI_BOOT_SCRIPT_PCI_CONFIG_WRITE). Followed by the values.
+///
+/// The address is an EFI_BOOT_SCRIPT_PCI_ADDRESS: register in bits 7:0, function in 15:8, device in 23:16, bus in
+/// 31:24 and extended register in 63:32.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PciConfigWrite {
+    pub header: EntryHeader,
+    pub width: u32,
+    pub address: u64,
+    pub count: u32,
+}
+
+/// Read-modify-write of PCI configuration space (EFI_BOOT_SCRIPT_PCI_CONFIG_READ_WRITE). Followed by the data and
+/// data mask values.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PciConfigReadWrite {
+    pub header: EntryHeader,
+    pub width: u32,
+    pub address: u64,
+}
+
+/// Writes `count` values to PCI configuration space of a PCI segment (EFI_BOOT_SCRIPT_PCI_CONFIG2_WRITE). Followed
+/// by the values.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PciConfig2Write {
+    pub header: EntryHeader,
+    pub width: u32,
+    pub address: u64,
+    pub segment: u16,
+    pub count: u32,
+}
+
+/// Read-modify-write of PCI configuration space of a PCI segment (EFI_BOOT_SCRIPT_PCI_CONFIG2_READ_WRITE). Followed
+/// by the data and data mask values.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PciConfig2ReadWrite {
+    pub header: EntryHeader,
+    pub width: u32,
+    pub segment: u16,
+    pub address: u64,
+}
+
+/// Executes an SMBus operation (EFI_BOOT_SCRIPT_SMBUS_EXECUTE). Followed by `data_size` bytes of data.
+///
+/// The SMBus address holds the slave address, command, data length and PEC flag, as described in [`smbus`].
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmbusExecute {
+    pub header: EntryHeader,
+    pub smbus_address: u64,
+    pub operation: u32,
+    pub data_size: u32,
+}
+
+/// Stalls for `duration` microseconds (EFI_BOOT_SCRIPT_STALL).
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stall {
+    pub header: EntryHeader,
+    pub duration: u64,
+}
+
+/// Calls `entry_point` with no context (EFI_BOOT_SCRIPT_DISPATCH).
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dispatch {
+    pub header: EntryHeader,
+    pub entry_point: u64,
+}
+
+/// Calls `entry_point` with `context` (EFI_BOOT_SCRIPT_DISPATCH_2).
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dispatch2 {
+    pub header: EntryHeader,
+    pub entry_point: u64,
+    pub context: u64,
+}
+
+/// Information only, ignored on resume (EFI_BOOT_SCRIPT_INFORMATION). Followed by `information_length` bytes.
+///
+/// Label entries use the same layout with opcode [`LABEL_OPCODE`] and a null-terminated ASCII label as the data.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Information {
+    pub header: EntryHeader,
+    pub information_length: u32,
+}
+
+/// Polls an I/O port until `(read & mask) == data`, with `delay` in units of 100ns (EFI_BOOT_SCRIPT_IO_POLL).
+/// Followed by the data and data mask values.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IoPoll {
+    pub header: EntryHeader,
+    pub width: u32,
+    pub address: u64,
+    pub delay: u64,
+}
+
+/// Polls memory until `(read & mask) == data`, stalling `duration` microseconds between at most `loop_times` reads
+/// (EFI_BOOT_SCRIPT_MEM_POLL). Followed by the data and data mask values.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemPoll {
+    pub header: EntryHeader,
+    pub width: u32,
+    pub address: u64,
+    pub duration: u64,
+    pub loop_times: u64,
+}
+
+/// Polls PCI configuration space until `(read & mask) == data`, with `delay` in units of 100ns
+/// (EFI_BOOT_SCRIPT_PCI_CONFIG_POLL). Followed by the data and data mask values.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PciConfigPoll {
+    pub header: EntryHeader,
+    pub width: u32,
+    pub address: u64,
+    pub delay: u64,
+}
+
+/// Polls PCI configuration space of a PCI segment until `(read & mask) == data`, with `delay` in units of 100ns
+/// (EFI_BOOT_SCRIPT_PCI_CONFIG2_POLL). Followed by the data and data mask values.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PciConfig2Poll {
+    pub header: EntryHeader,
+    pub width: u32,
+    pub segment: u16,
+    pub address: u64,
+    pub delay: u64,
+}
+
+/// Ends the script (EFI_BOOT_SCRIPT_TERMINATE).
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Terminate {
+    pub header: EntryHeader,
+}
+
+/// Marker for the packed entry structures, which have no padding and can be copied into the table byte for byte.
+trait EntryLayout: Copy {}
+
+impl EntryLayout for TableHeader {}
+impl EntryLayout for IoWrite {}
+impl EntryLayout for IoReadWrite {}
+impl EntryLayout for MemWrite {}
+impl EntryLayout for MemReadWrite {}
+impl EntryLayout for PciConfigWrite {}
+impl EntryLayout for PciConfigReadWrite {}
+impl EntryLayout for PciConfig2Write {}
+impl EntryLayout for PciConfig2ReadWrite {}
+impl EntryLayout for SmbusExecute {}
+impl EntryLayout for Stall {}
+impl EntryLayout for Dispatch {}
+impl EntryLayout for Dispatch2 {}
+impl EntryLayout for Information {}
+impl EntryLayout for IoPoll {}
+impl EntryLayout for MemPoll {}
+impl EntryLayout for PciConfigPoll {}
+impl EntryLayout for PciConfig2Poll {}
+impl EntryLayout for Terminate {}
+
+/// An encoded boot script entry, as stored in the script table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptEntry {
+    bytes: Vec<u8>,
+}
+
+impl ScriptEntry {
+    fn new<T: EntryLayout>(fixed: &T, data: &[&[u8]]) -> Self {
+        //Safety: EntryLayout is only implemented by packed structures of integers, which have no padding.
+        let fixed = unsafe { slice::from_raw_parts(fixed as *const T as *const u8, size_of::<T>()) };
+        let mut bytes = Vec::with_capacity(fixed.len() + data.iter().map(|d| d.len()).sum::<usize>());
+        bytes.extend_from_slice(fixed);
+        data.iter().for_each(|d| bytes.extend_from_slice(d));
+        debug_assert_eq!(bytes.len(), fixed[2] as usize);
+        Self { bytes }
+    }
+
+    /// Returns the opcode of the entry.
+    pub fn opcode(&self) -> u16 {
+        u16::from_le_bytes([self.bytes[0], self.bytes[1]])
+    }
+
+    /// Returns the encoded entry.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the encoded entry.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+impl AsRef<[u8]> for ScriptEntry {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+// Returns the header of an entry with fixed structure T followed by data_length bytes.
+fn header<T>(opcode: u16, data_length: usize) -> Result<EntryHeader, BootScriptError> {
+    let length = size_of::<T>().saturating_add(data_length);
+    let length = u8::try_from(length).map_err(|_| BootScriptError::EntryTooLong(length))?;
+    Ok(EntryHeader { opcode, length })
+}
+
+// Checks that data holds count values of the width.
+fn check_data(width: Width, count: u32, data: &[u8]) -> Result<(), BootScriptError> {
+    let expected = (count as usize).saturating_mul(width.unit_size());
+    if data.len() != expected {
+        Err(BootScriptError::DataLength { expected, actual: data.len() })?;
+    }
+    Ok(())
+}
+
+// Returns the data and data mask values that follow read-write and poll entries.
+fn data_and_mask(width: Width, data: u64, data_mask: u64) -> Result<([u8; 8], [u8; 8]), BootScriptError> {
+    let bits = width.unit_size() * 8;
+    for value in [data, data_mask] {
+        if bits < 64 && value >> bits != 0 {
+            Err(BootScriptError::ValueTooWide(value))?;
+        }
+    }
+    Ok((data.to_le_bytes(), data_mask.to_le_bytes()))
+}
+
+// Encodes a read-write or poll entry, whose fixed structure is followed by the data and data mask values.
+fn masked_entry<T: EntryLayout>(
+    opcode: u16,
+    width: Width,
+    data: u64,
+    data_mask: u64,
+    fixed: impl FnOnce(EntryHeader) -> T,
+) -> Result<ScriptEntry, BootScriptError> {
+    let size = width.unit_size();
+    let (data, data_mask) = data_and_mask(width, data, data_mask)?;
+    let fixed = fixed(header::<T>(opcode, 2 * size)?);
+    Ok(ScriptEntry::new(&fixed, &[&data[..size], &data_mask[..size]]))
+}
+
+/// Encodes the table header for a table of `table_length` bytes.
+pub fn table_header(table_length: u32) -> ScriptEntry {
+    let header = EntryHeader { opcode: TABLE_OPCODE, length: size_of::<TableHeader>() as u8 };
+    ScriptEntry::new(&TableHeader { header, version: TABLE_VERSION, table_length, reserved: [0; 2] }, &[])
+}
+
+/// Encodes an IO_WRITE entry writing the `count` values in `data` to the I/O port at `address`.
+pub fn io_write(width: Width, address: u64, count: u32, data: &[u8]) -> Result<ScriptEntry, BootScriptError> {
+    check_data(width, count, data)?;
+    let header = header::<IoWrite>(IO_WRITE_OPCODE, data.len())?;
+    Ok(ScriptEntry::new(&IoWrite { header, width: width as u32, count, address }, &[data]))
+}
+
+/// Encodes an IO_READ_WRITE entry.
+pub fn io_read_write(width: Width, address: u64, data: u64, data_mask: u64) -> Result<ScriptEntry, BootScriptError> {
+    masked_entry(IO_READ_WRITE_OPCODE, width, data, data_mask, |header| IoReadWrite {
+        header,
+        width: width as u32,
+        address,
+    })
+}
+
+/// Encodes a MEM_WRITE entry writing the `count` values in `data` to memory at `address`.
+pub fn mem_write(width: Width, address: u64, count: u32, data: &[u8]) -> Result<ScriptEntry, BootScriptError> {
+    check_data(width, count, data)?;
+    let header = header::<MemWrite>(MEM_WRITE_OPCODE, data.len())?;
+    Ok(ScriptEntry::new(&MemWrite { header, width: width as u32, address, count }, &[data]))
+}
+
+/// Encodes a MEM_READ_WRITE entry.
+pub fn mem_read_write(width: Width, address: u64, data: u64, data_mask: u64) -> Result<ScriptEntry, BootScriptError> {
+    masked_entry(MEM_READ_WRITE_OPCODE, width, data, data_mask, |header| MemReadWrite {
+        header,
+        width: width as u32,
+        address,
+    })
+}
+
+/// Encodes a PCI_CONFIG_WRITE entry writing the `count` values in `data` to the PCI configuration space `address`.
+pub fn pci_config_write(width: Width, address: u64, count: u32, data: &[u8]) -> Result<ScriptEntry, BootScriptError> {
+    check_data(width, count, data)?;
+    let header = header::<PciConfigWrite>(PCI_CONFIG_WRITE_OPCODE, data.len())?;
+    Ok(ScriptEntry::new(&PciConfigWrite { header, width: width as u32, address, count }, &[data]))
+}
+
+/// Encodes a PCI_CONFIG_READ_WRITE entry.
+pub fn pci_config_read_write(
+    width: Width,
+    address: u64,
+    data: u64,
+    data_mask: u64,
+) -> Result<ScriptEntry, BootScriptError> {
+    masked_entry(PCI_CONFIG_READ_WRITE_OPCODE, width, data, data_mask, |header| PciConfigReadWrite {
+        header,
+        width: width as u32,
+        address,
+    })
+}
+
+/// Encodes a PCI_CONFIG2_WRITE entry writing the `count` values in `data` to the PCI configuration space `address`
+/// of PCI segment `segment`.
+pub fn pci_config2_write(
+    width: Width,
+    segment: u16,
+    address: u64,
+    count: u32,
+    data: &[u8],
+) -> Result<ScriptEntry, BootScriptError> {
+    check_data(width, count, data)?;
+    let header = header::<PciConfig2Write>(PCI_CONFIG2_WRITE_OPCODE, data.len())?;
+    Ok(ScriptEntry::new(&PciConfig2Write { header, width: width as u32, address, segment, count }, &[data]))
+}
+
+/// Encodes a PCI_CONFIG2_READ_WRITE entry.
+pub fn pci_config2_read_write(
+    width: Width,
+    segment: u16,
+    address: u64,
+    data: u64,
+    data_mask: u64,
+) -> Result<ScriptEntry, BootScriptError> {
+    masked_entry(PCI_CONFIG2_READ_WRITE_OPCODE, width, data, data_mask, |header| PciConfig2ReadWrite {
+        header,
+        width: width as u32,
+        segment,
+        address,
+    })
+}
+
+/// Encodes an IO_POLL entry; `delay` is in units of 100ns.
+pub fn io_poll(
+    width: Width,
+    address: u64,
+    data: u64,
+    data_mask: u64,
+    delay: u64,
+) -> Result<ScriptEntry, BootScriptError> {
+    masked_entry(IO_POLL_OPCODE, width, data, data_mask, |header| IoPoll {
+        header,
+        width: width as u32,
+        address,
+        delay,
+    })
+}
+
+/// Encodes a MEM_POLL entry; `duration` is the stall in microseconds between each of at most `loop_times` reads.
+pub fn mem_poll(
+    width: Width,
+    address: u64,
+    data: u64,
+    data_mask: u64,
+    duration: u64,
+    loop_times: u64,
+) -> Result<ScriptEntry, BootScriptError> {
+    masked_entry(MEM_POLL_OPCODE, width, data, data_mask, |header| MemPoll {
+        header,
+        width: width as u32,
+        address,
+        duration,
+        loop_times,
+    })
+}
+
+/// Encodes a PCI_CONFIG_POLL entry; `delay` is in units of 100ns.
+pub fn pci_config_poll(
+    width: Width,
+    address: u64,
+    data: u64,
+    data_mask: u64,
+    delay: u64,
+) -> Result<ScriptEntry, BootScriptError> {
+    masked_entry(PCI_CONFIG_POLL_OPCODE, width, data, data_mask, |header| PciConfigPoll {
+        header,
+        width: width as u32,
+        address,
+        delay,
+    })
+}
+
+/// Encodes a PCI_CONFIG2_POLL entry; `delay` is in units of 100ns.
+pub fn pci_config2_poll(
+    width: Width,
+    segment: u16,
+    address: u64,
+    data: u64,
+    data_mask: u64,
+    delay: u64,
+) -> Result<ScriptEntry, BootScriptError> {
+    masked_entry(PCI_CONFIG2_POLL_OPCODE, width, data, data_mask, |header| PciConfig2Poll {
+        header,
+        width: width as u32,
+        segment,
+        address,
+        delay,
+    })
+}
+
+/// Encodes a STALL entry of `duration` microseconds.
+pub fn stall(duration: u64) -> ScriptEntry {
+    let header = EntryHeader { opcode: STALL_OPCODE, length: size_of::<Stall>() as u8 };
+    ScriptEntry::new(&Stall { header, duration }, &[])
+}
+
+/// Encodes a DISPATCH entry.
+pub fn dispatch(entry_point: u64) -> ScriptEntry {
+    let header = EntryHeader { opcode: DISPATCH_OPCODE, length: size_of::<Dispatch>() as u8 };
+    ScriptEntry::new(&Dispatch { header, entry_point }, &[])
+}
+
+/// Encodes a DISPATCH_2 entry.
+pub fn dispatch2(entry_point: u64, context: u64) -> ScriptEntry {
+    let header = EntryHeader { opcode: DISPATCH_2_OPCODE, length: size_of::<Dispatch2>() as u8 };
+    ScriptEntry::new(&Dispatch2 { header, entry_point, context }, &[])
+}
+
+/// Encodes an INFORMATION entry holding `information`.
+pub fn information(information: &[u8]) -> Result<ScriptEntry, BootScriptError> {
+    let header = header::<Information>(INFORMATION_OPCODE, information.len())?;
+    Ok(ScriptEntry::new(&Information { header, information_length: information.len() as u32 }, &[information]))
+}
+
+/// Encodes a LABEL entry holding `label` and its null terminator.
+pub fn label(label: &str) -> Result<ScriptEntry, BootScriptError> {
+    if !label.is_ascii() || label.contains('\0') {
+        Err(BootScriptError::InvalidLabel)?;
+    }
+    let header = header::<Information>(LABEL_OPCODE, label.len() + 1)?;
+    let information = Information { header, information_length: label.len() as u32 + 1 };
+    Ok(ScriptEntry::new(&information, &[label.as_bytes(), &[0]]))
+}
+
+/// Encodes the TERMINATE entry that ends the script.
+pub fn terminate() -> ScriptEntry {
+    let header = EntryHeader { opcode: TERMINATE_OPCODE, length: size_of::<Terminate>() as u8 };
+    ScriptEntry::new(&Terminate { header }, &[])
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::size_of;
+
+    use r_efi::efi;
+
+    use super::*;
+
+    #[test]
+    fn layouts_should_match_edk2() {
+        assert_eq!(size_of::<EntryHeader>(), 3);
+        assert_eq!(size_of::<TableHeader>(), 13);
+        assert_eq!(offset_of!(TableHeader, table_length), 5);
+        assert_eq!(size_of::<IoWrite>(), 19);
+        assert_eq!(offset_of!(IoWrite, address), 11);
+        assert_eq!(size_of::<IoReadWrite>(), 15);
+        assert_eq!(size_of::<MemWrite>(), 19);
+        assert_eq!(offset_of!(MemWrite, count), 15);
+        assert_eq!(size_of::<MemReadWrite>(), 15);
+        assert_eq!(size_of::<PciConfigWrite>(), 19);
+        assert_eq!(size_of::<PciConfigReadWrite>(), 15);
+        assert_eq!(size_of::<PciConfig2Write>(), 21);
+        assert_eq!(offset_of!(PciConfig2Write, segment), 15);
+        assert_eq!(size_of::<PciConfig2ReadWrite>(), 17);
+        assert_eq!(offset_of!(PciConfig2ReadWrite, address), 9);
+        assert_eq!(size_of::<SmbusExecute>(), 19);
+        assert_eq!(size_of::<Stall>(), 11);
+        assert_eq!(size_of::<Dispatch>(), 11);
+        assert_eq!(size_of::<Dispatch2>(), 19);
+        assert_eq!(size_of::<Information>(), 7);
+        assert_eq!(size_of::<IoPoll>(), 23);
+        assert_eq!(size_of::<MemPoll>(), 31);
+        assert_eq!(size_of::<PciConfigPoll>(), 23);
+        assert_eq!(size_of::<PciConfig2Poll>(), 25);
+        assert_eq!(size_of::<Terminate>(), 3);
+    }
+
+    #[test]
+    fn write_entries_should_match_edk2() {
+        // S3BootScriptSaveIoWrite (S3BootScriptWidthUint8, 0x80, 2, {0x12, 0x34})
+        let entry = io_write(Width::Uint8, 0x80, 2, &[0x12, 0x34]).unwrap();
+        #[rustfmt::skip]
+        assert_eq!(entry.as_bytes(), [
+            0x00, 0x00, 0x15,
+            0x00, 0x00, 0x00, 0x00,
+            0x02, 0x00, 0x00, 0x00,
+            0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x12, 0x34,
+        ]);
+
+        // S3BootScriptSaveMemWrite (S3BootScriptWidthUint32, 0xFED40044, 1, 0x11223344)
+        let entry = mem_write(Width::Uint32, 0xfed40044, 1, &0x11223344u32.to_le_bytes()).unwrap();
+        #[rustfmt::skip]
+        assert_eq!(entry.as_bytes(), [
+            0x02, 0x00, 0x17,
+            0x02, 0x00, 0x00, 0x00,
+            0x44, 0x00, 0xd4, 0xfe, 0x00, 0x00, 0x00, 0x00,
+            0x01, 0x00, 0x00, 0x00,
+            0x44, 0x33, 0x22, 0x11,
+        ]);
+
+        // S3BootScriptSavePciCfg2Write (S3BootScriptWidthUint16, 1, PCI_LIB_ADDRESS (0, 0x1f, 0, 0x40), 1, 0xabcd)
+        let entry = pci_config2_write(Width::Uint16, 1, 0x001f0040, 1, &[0xcd, 0xab]).unwrap();
+        #[rustfmt::skip]
+        assert_eq!(entry.as_bytes(), [
+            0x0b, 0x00, 0x17,
+            0x01, 0x00, 0x00, 0x00,
+            0x40, 0x00, 0x1f, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x01, 0x00,
+            0x01, 0x00, 0x00, 0x00,
+            0xcd, 0xab,
+        ]);
+        assert_eq!(entry.opcode(), PCI_CONFIG2_WRITE_OPCODE);
+    }
+
+    #[test]
+    fn masked_entries_should_match_edk2() {
+        // S3BootScriptSaveIoReadWrite (S3BootScriptWidthUint16, 0x1004, 0x0001, 0xfffe)
+        let entry = io_read_write(Width::Uint16, 0x1004, 0x0001, 0xfffe).unwrap();
+        #[rustfmt::skip]
+        assert_eq!(entry.as_bytes(), [
+            0x01, 0x00, 0x13,
+            0x01, 0x00, 0x00, 0x00,
+            0x04, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x01, 0x00,
+            0xfe, 0xff,
+        ]);
+
+        // S3BootScriptSaveMemPoll (S3BootScriptWidthUint8, 0x1000, BitMask 0x80, BitValue 0x80, 10, 5)
+        let entry = mem_poll(Width::Uint8, 0x1000, 0x80, 0x80, 10, 5).unwrap();
+        #[rustfmt::skip]
+        assert_eq!(entry.as_bytes(), [
+            0x0e, 0x00, 0x21,
+            0x00, 0x00, 0x00, 0x00,
+            0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x0a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x80,
+            0x80,
+        ]);
+
+        let entry = pci_config2_poll(Width::Uint32, 2, 0x10, 1, 3, 1000).unwrap();
+        assert_eq!(entry.as_bytes().len(), size_of::<PciConfig2Poll>() + 8);
+        assert_eq!(&entry.as_bytes()[7..9], [0x02, 0x00]);
+        assert_eq!(&entry.as_bytes()[25..], [1, 0, 0, 0, 3, 0, 0, 0]);
+    }
+
+    #[test]
+    fn fixed_entries_should_match_edk2() {
+        assert_eq!(stall(100).as_bytes(), [0x07, 0x00, 0x0b, 0x64, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(dispatch(0x1234).as_bytes(), [0x08, 0x00, 0x0b, 0x34, 0x12, 0, 0, 0, 0, 0, 0]);
+        #[rustfmt::skip]
+        assert_eq!(dispatch2(0x1234, 0x5678).as_bytes(), [
+            0x09, 0x00, 0x13,
+            0x34, 0x12, 0, 0, 0, 0, 0, 0,
+            0x78, 0x56, 0, 0, 0, 0, 0, 0,
+        ]);
+        assert_eq!(information(&[0xaa, 0xbb]).unwrap().as_bytes(), [0x0a, 0x00, 0x09, 0x02, 0, 0, 0, 0xaa, 0xbb]);
+        assert_eq!(label("S3").unwrap().as_bytes(), [0xfe, 0x00, 0x0a, 0x03, 0, 0, 0, b'S', b'3', 0]);
+        assert_eq!(terminate().as_bytes(), [0xff, 0x00, 0x03]);
+        #[rustfmt::skip]
+        assert_eq!(table_header(0x40).as_bytes(), [
+            0xaa, 0x00, 0x0d,
+            0x01, 0x00,
+            0x40, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00,
+        ]);
+    }
+
+    #[test]
+    fn invalid_entries_should_be_rejected() {
+        assert_eq!(
+            io_write(Width::Uint16, 0x80, 2, &[0; 3]),
+            Err(BootScriptError::DataLength { expected: 4, actual: 3 })
+        );
+        assert_eq!(mem_write(Width::FifoUint8, 0, 237, &[0; 237]), Err(BootScriptError::EntryTooLong(256)));
+        assert!(mem_write(Width::FillUint8, 0, 236, &[0; 236]).is_ok());
+        assert_eq!(mem_read_write(Width::Uint8, 0, 0x100, 0xff), Err(BootScriptError::ValueTooWide(0x100)));
+        assert!(mem_read_write(Width::Uint64, 0, u64::MAX, u64::MAX).is_ok());
+        assert_eq!(label("bad\0label"), Err(BootScriptError::InvalidLabel));
+        assert_eq!(label("caf\u{e9}"), Err(BootScriptError::InvalidLabel));
+        assert_eq!(Width::try_from(12), Err(BootScriptError::InvalidWidth(12)));
+        assert_eq!(Width::try_from(7), Ok(Width::FifoUint64));
+        assert_eq!(Width::FillUint16.unit_size(), 2);
+        assert_eq!(efi::Status::from(BootScriptError::EntryTooLong(256)), efi::Status::OUT_OF_RESOURCES);
+        assert_eq!(efi::Status::from(BootScriptError::InvalidLabel), efi::Status::INVALID_PARAMETER);
+    }
+}
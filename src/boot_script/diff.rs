@@ -0,0 +1,276 @@
+//! Boot Script Differ
+//!
+//! Compares two boot script tables, e.g. the tables of two firmware versions, or the table expected on a platform and
+//! the one captured from memory, and reports the entries inserted, removed and changed between them.
+//!
+//! Entries are matched by opcode and address: the port, memory or PCI configuration address they access, the SMBus
+//! device and command, the name of a label, or the entry point of a dispatch. A matched pair whose other fields or
+//! data differ is reported as changed, e.g. a write of a new value to the same register. Entries without an address,
+//! such as stalls, only match an identical entry, so a changed stall is reported as removed and inserted.
+//!
+//! ## Example
+//! ```
+//! use mu_pi::boot_script::{self, diff, ScriptTable, ScriptWriter, Width};
+//!
+//! let mut old = ScriptWriter::new();
+//! old.append(boot_script::io_write(Width::Uint8, 0x80, 1, &[0x01]).unwrap()).unwrap();
+//! let mut new = old.clone();
+//! new.append(boot_script::stall(100)).unwrap();
+//!
+//! let (old, new) = (old.to_bytes().unwrap(), new.to_bytes().unwrap());
+//! let diffs = diff(&ScriptTable::parse(&old).unwrap(), &ScriptTable::parse(&new).unwrap());
+//! assert_eq!(diffs.len(), 1);
+//! assert_eq!(diffs[0].to_string(), "+ 1: stall 100us");
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::fmt;
+
+extern crate alloc;
+use alloc::{vec, vec::Vec};
+
+use super::{Dispatch2, Entry, ScriptTable};
+
+/// Options of [`diff_with_options`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DiffOptions {
+    /// Ignores the entry points of DISPATCH and DISPATCH_2 entries, which move between builds of the same firmware.
+    pub ignore_dispatch_entry_points: bool,
+}
+
+/// A difference between two boot script tables. Indexes are those of the entries in their table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptDiff<'a> {
+    /// `entry` is only in the second table.
+    Inserted { index: usize, entry: Entry<'a> },
+    /// `entry` is only in the first table.
+    Removed { index: usize, entry: Entry<'a> },
+    /// The entry with the opcode and address of `old` in the first table is `new` in the second.
+    Changed { old_index: usize, old: Entry<'a>, new_index: usize, new: Entry<'a> },
+}
+
+impl fmt::Display for ScriptDiff<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptDiff::Inserted { index, entry } => write!(f, "+ {index}: {entry}"),
+            ScriptDiff::Removed { index, entry } => write!(f, "- {index}: {entry}"),
+            ScriptDiff::Changed { old_index, old, new_index, new } => {
+                write!(f, "~ {old_index}: {old}\n  {new_index}: {new}")
+            }
+        }
+    }
+}
+
+/// Returns the differences between table `a` and table `b`, in table order.
+pub fn diff<'a>(a: &ScriptTable<'a>, b: &ScriptTable<'a>) -> Vec<ScriptDiff<'a>> {
+    diff_with_options(a, b, DiffOptions::default())
+}
+
+/// Returns the differences between table `a` and table `b`, in table order, with `options`.
+pub fn diff_with_options<'a>(a: &ScriptTable<'a>, b: &ScriptTable<'a>, options: DiffOptions) -> Vec<ScriptDiff<'a>> {
+    let a: Vec<Entry> = a.entries().collect();
+    let b: Vec<Entry> = b.entries().collect();
+    let a_keys: Vec<Key> = a.iter().map(|entry| Key::new(entry, options)).collect();
+    let b_keys: Vec<Key> = b.iter().map(|entry| Key::new(entry, options)).collect();
+
+    // Entries at the start and end of both tables are matched without the quadratic table below, since most of two
+    // related scripts is the same.
+    let prefix = a_keys.iter().zip(&b_keys).take_while(|(a, b)| a == b).count();
+    let suffix = a_keys[prefix..].iter().rev().zip(b_keys[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let (a_end, b_end) = (a.len() - suffix, b.len() - suffix);
+
+    // lengths[i][j] is the length of the longest common subsequence of the keys from a[prefix + i] and b[prefix + j].
+    let (rows, columns) = (a_end - prefix + 1, b_end - prefix + 1);
+    let mut lengths = vec![0u32; rows * columns];
+    for i in (0..rows - 1).rev() {
+        for j in (0..columns - 1).rev() {
+            lengths[i * columns + j] = if a_keys[prefix + i] == b_keys[prefix + j] {
+                lengths[(i + 1) * columns + j + 1] + 1
+            } else {
+                lengths[(i + 1) * columns + j].max(lengths[i * columns + j + 1])
+            };
+        }
+    }
+
+    let mut diffs = Vec::new();
+    let matched = |diffs: &mut Vec<ScriptDiff<'a>>, i: usize, j: usize| {
+        if !same(&a[i], &b[j], options) {
+            diffs.push(ScriptDiff::Changed { old_index: i, old: a[i], new_index: j, new: b[j] });
+        }
+    };
+    (0..prefix).for_each(|i| matched(&mut diffs, i, i));
+    let (mut i, mut j) = (prefix, prefix);
+    while i < a_end || j < b_end {
+        let (row, column) = (i - prefix, j - prefix);
+        if i < a_end && j < b_end && a_keys[i] == b_keys[j] {
+            matched(&mut diffs, i, j);
+            i += 1;
+            j += 1;
+        } else if i < a_end
+            && (j == b_end || lengths[(row + 1) * columns + column] >= lengths[row * columns + column + 1])
+        {
+            diffs.push(ScriptDiff::Removed { index: i, entry: a[i] });
+            i += 1;
+        } else {
+            diffs.push(ScriptDiff::Inserted { index: j, entry: b[j] });
+            j += 1;
+        }
+    }
+    (0..suffix).for_each(|k| matched(&mut diffs, a_end + k, b_end + k));
+    diffs
+}
+
+// The fields an entry is matched by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key<'a> {
+    // Entries accessing an address, matched by opcode and address.
+    Address { opcode: u16, segment: u16, address: u64 },
+    // Labels, matched by name.
+    Label(&'a str),
+    // Dispatch entries whose entry points are ignored, matched by opcode.
+    Opcode(u16),
+    // Entries without an address, matched only by an identical entry.
+    Entry(Entry<'a>),
+}
+
+impl<'a> Key<'a> {
+    fn new(entry: &Entry<'a>, options: DiffOptions) -> Self {
+        let (segment, address) = match *entry {
+            Entry::IoWrite { entry, .. } => (0, entry.address),
+            Entry::IoReadWrite { entry, .. } => (0, entry.address),
+            Entry::MemWrite { entry, .. } => (0, entry.address),
+            Entry::MemReadWrite { entry, .. } => (0, entry.address),
+            Entry::PciConfigWrite { entry, .. } => (0, entry.address),
+            Entry::PciConfigReadWrite { entry, .. } => (0, entry.address),
+            Entry::PciConfig2Write { entry, .. } => (entry.segment, entry.address),
+            Entry::PciConfig2ReadWrite { entry, .. } => (entry.segment, entry.address),
+            Entry::SmbusExecute { entry, .. } => (0, (entry.slave_address().0 as u64) << 8 | entry.command() as u64),
+            Entry::IoPoll { entry, .. } => (0, entry.address),
+            Entry::MemPoll { entry, .. } => (0, entry.address),
+            Entry::PciConfigPoll { entry, .. } => (0, entry.address),
+            Entry::PciConfig2Poll { entry, .. } => (entry.segment, entry.address),
+            Entry::Dispatch(_) | Entry::Dispatch2(_) if options.ignore_dispatch_entry_points => {
+                return Key::Opcode(entry.opcode())
+            }
+            Entry::Dispatch(dispatch) => (0, dispatch.entry_point),
+            Entry::Dispatch2(dispatch) => (0, dispatch.entry_point),
+            Entry::Label { label, .. } => return Key::Label(label),
+            _ => return Key::Entry(*entry),
+        };
+        Key::Address { opcode: entry.opcode(), segment, address }
+    }
+}
+
+// Returns whether two matched entries are the same, with the entry points of dispatch entries ignored if requested.
+fn same(a: &Entry, b: &Entry, options: DiffOptions) -> bool {
+    match (*a, *b) {
+        (Entry::Dispatch(_), Entry::Dispatch(_)) if options.ignore_dispatch_entry_points => true,
+        (Entry::Dispatch2(a), Entry::Dispatch2(b)) if options.ignore_dispatch_entry_points => {
+            Dispatch2 { entry_point: 0, ..a } == Dispatch2 { entry_point: 0, ..b }
+        }
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    use alloc::{
+        string::{String, ToString},
+        vec::Vec,
+    };
+
+    use super::{diff, diff_with_options, DiffOptions, ScriptDiff};
+    use crate::boot_script::{self, ScriptEntry, ScriptTable, ScriptWriter, Width};
+
+    fn table(entries: &[ScriptEntry]) -> Vec<u8> {
+        let mut writer = ScriptWriter::new();
+        entries.iter().cloned().for_each(|entry| {
+            writer.append(entry).unwrap();
+        });
+        writer.to_bytes().unwrap()
+    }
+
+    fn io_write(port: u64, value: u8) -> ScriptEntry {
+        boot_script::io_write(Width::Uint8, port, 1, &[value]).unwrap()
+    }
+
+    fn lines(a: &[u8], b: &[u8], options: DiffOptions) -> Vec<String> {
+        let (a, b) = (ScriptTable::parse(a).unwrap(), ScriptTable::parse(b).unwrap());
+        diff_with_options(&a, &b, options).iter().map(|diff| diff.to_string()).collect()
+    }
+
+    #[test]
+    fn identical_tables_should_have_no_differences() {
+        let bytes = table(&[io_write(0x80, 1), boot_script::stall(10), boot_script::dispatch(0x1000)]);
+        let table = ScriptTable::parse(&bytes).unwrap();
+        assert!(diff(&table, &table).is_empty());
+    }
+
+    #[test]
+    fn inserted_and_removed_entries_should_be_reported() {
+        let old = table(&[
+            boot_script::label("Pch").unwrap(),
+            io_write(0x80, 1),
+            io_write(0x81, 2),
+            io_write(0x82, 3),
+            boot_script::stall(10),
+        ]);
+        let new = table(&[
+            boot_script::label("Pch").unwrap(),
+            io_write(0x80, 1),
+            boot_script::mem_write(Width::Uint32, 0xfed00000, 1, &[0; 4]).unwrap(),
+            io_write(0x82, 3),
+            boot_script::stall(10),
+            boot_script::stall(20),
+        ]);
+        assert_eq!(
+            lines(&old, &new, DiffOptions::default()),
+            ["- 2: write IO 0x81 Uint8 [0x2]", "+ 2: write MMIO 0xFED00000 Uint32 [0x0]", "+ 5: stall 20us",]
+        );
+
+        // Reversed, insertions become removals.
+        assert_eq!(
+            lines(&new, &old, DiffOptions::default()),
+            ["- 2: write MMIO 0xFED00000 Uint32 [0x0]", "+ 2: write IO 0x81 Uint8 [0x2]", "- 5: stall 20us",]
+        );
+    }
+
+    #[test]
+    fn changed_values_should_be_reported() {
+        let old = table(&[io_write(0x80, 1), boot_script::mem_read_write(Width::Uint32, 0x1000, 0x1, 0xfe).unwrap()]);
+        let new = table(&[io_write(0x80, 2), boot_script::mem_read_write(Width::Uint32, 0x1000, 0x1, 0xfe).unwrap()]);
+        let (a, b) = (ScriptTable::parse(&old).unwrap(), ScriptTable::parse(&new).unwrap());
+        let diffs = diff(&a, &b);
+        assert_eq!(diffs.len(), 1);
+        assert!(matches!(diffs[0], ScriptDiff::Changed { old_index: 0, new_index: 0, .. }));
+        assert_eq!(diffs[0].to_string(), "~ 0: write IO 0x80 Uint8 [0x1]\n  0: write IO 0x80 Uint8 [0x2]");
+    }
+
+    #[test]
+    fn dispatch_entry_points_should_be_ignored_when_requested() {
+        let old = table(&[boot_script::dispatch(0x1000), boot_script::dispatch2(0x2000, 0x10), io_write(0x80, 1)]);
+        let new = table(&[boot_script::dispatch(0x1800), boot_script::dispatch2(0x2800, 0x20), io_write(0x80, 1)]);
+
+        let options = DiffOptions { ignore_dispatch_entry_points: true };
+        assert_eq!(
+            lines(&old, &new, options),
+            ["~ 1: dispatch 0x2000 context 0x10\n  1: dispatch 0x2800 context 0x20"]
+        );
+        assert_eq!(
+            lines(&old, &new, DiffOptions::default()),
+            [
+                "- 0: dispatch 0x1000",
+                "- 1: dispatch 0x2000 context 0x10",
+                "+ 0: dispatch 0x1800",
+                "+ 1: dispatch 0x2800 context 0x20",
+            ]
+        );
+    }
+}
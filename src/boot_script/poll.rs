@@ -0,0 +1,238 @@
+//! Boot Script Poll Entries
+//!
+//! Constructors for the poll entries that take the condition as a [`PollUntil`] and the timeout as a `Duration`, and
+//! the conversions between the timeout and the opcode-specific encoding.
+//!
+//! The poll opcodes encode their timeout differently:
+//!
+//! | Opcode             | Timeout fields           | Meaning                                                   | Timeout                      |
+//! |--------------------|--------------------------|-----------------------------------------------------------|------------------------------|
+//! | `IO_POLL`          | `delay`                  | Number of reads, with a 100ns stall after each            | `delay` × 100ns              |
+//! | `PCI_CONFIG_POLL`  | `delay`                  | Number of reads, with a 100ns stall after each            | `delay` × 100ns              |
+//! | `PCI_CONFIG2_POLL` | `delay`                  | Number of reads, with a 100ns stall after each            | `delay` × 100ns              |
+//! | `MEM_POLL`         | `duration`, `loop_times` | `loop_times` reads, with a `duration` us stall after each | `duration` × `loop_times` us |
+//!
+//! The constructors round the timeout up to a whole number of stalls, using [`MEM_POLL_INTERVAL`] as the stall of
+//! MEM_POLL entries.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{fmt, time::Duration};
+
+use super::{io_poll, mem_poll, pci_config2_poll, pci_config_poll, BootScriptError, Entry, ScriptEntry, Width};
+
+/// Stall between the reads of IO_POLL, PCI_CONFIG_POLL and PCI_CONFIG2_POLL entries.
+pub const POLL_DELAY_UNIT: Duration = Duration::from_nanos(100);
+
+/// Stall between the reads of MEM_POLL entries created by [`mem_poll_until`].
+pub const MEM_POLL_INTERVAL: Duration = Duration::from_micros(10);
+
+/// The condition a poll entry waits for: `(read & mask) == value`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PollUntil {
+    pub mask: u64,
+    pub value: u64,
+}
+
+impl PollUntil {
+    /// Waits until the bits of `mask` are all set.
+    pub const fn set(mask: u64) -> Self {
+        Self { mask, value: mask }
+    }
+
+    /// Waits until the bits of `mask` are all clear.
+    pub const fn clear(mask: u64) -> Self {
+        Self { mask, value: 0 }
+    }
+}
+
+impl fmt::Display for PollUntil {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "mask {:#X} == {:#X}", self.mask, self.value)
+    }
+}
+
+// Returns the number of `unit` stalls covering `timeout`, rounded up.
+fn stalls(timeout: Duration, unit: Duration) -> Result<u64, BootScriptError> {
+    let stalls = timeout.as_nanos().div_ceil(unit.as_nanos());
+    u64::try_from(stalls).map_err(|_| BootScriptError::TimeoutTooLong)
+}
+
+// Returns `count` stalls of `unit`.
+fn timeout(count: u64, unit: Duration) -> Duration {
+    let nanos = count as u128 * unit.as_nanos();
+    Duration::new((nanos / 1_000_000_000) as u64, (nanos % 1_000_000_000) as u32)
+}
+
+/// Encodes an IO_POLL entry waiting at most `timeout` for the port at `address` to meet `until`.
+pub fn io_poll_until(
+    width: Width,
+    address: u64,
+    until: PollUntil,
+    timeout: Duration,
+) -> Result<ScriptEntry, BootScriptError> {
+    io_poll(width, address, until.value, until.mask, stalls(timeout, POLL_DELAY_UNIT)?)
+}
+
+/// Encodes a MEM_POLL entry waiting at most `timeout` for memory at `address` to meet `until`.
+pub fn mem_poll_until(
+    width: Width,
+    address: u64,
+    until: PollUntil,
+    timeout: Duration,
+) -> Result<ScriptEntry, BootScriptError> {
+    let loop_times = stalls(timeout, MEM_POLL_INTERVAL)?;
+    mem_poll(width, address, until.value, until.mask, MEM_POLL_INTERVAL.as_micros() as u64, loop_times)
+}
+
+/// Encodes a PCI_CONFIG_POLL entry waiting at most `timeout` for PCI configuration space `address` to meet `until`.
+pub fn pci_config_poll_until(
+    width: Width,
+    address: u64,
+    until: PollUntil,
+    timeout: Duration,
+) -> Result<ScriptEntry, BootScriptError> {
+    pci_config_poll(width, address, until.value, until.mask, stalls(timeout, POLL_DELAY_UNIT)?)
+}
+
+/// Encodes a PCI_CONFIG2_POLL entry waiting at most `timeout` for PCI configuration space `address` of PCI segment
+/// `segment` to meet `until`.
+pub fn pci_config2_poll_until(
+    width: Width,
+    segment: u16,
+    address: u64,
+    until: PollUntil,
+    timeout: Duration,
+) -> Result<ScriptEntry, BootScriptError> {
+    pci_config2_poll(width, segment, address, until.value, until.mask, stalls(timeout, POLL_DELAY_UNIT)?)
+}
+
+impl Entry<'_> {
+    /// Returns the condition of a poll entry.
+    pub fn poll_until(&self) -> Option<PollUntil> {
+        match *self {
+            Entry::IoPoll { data, data_mask, .. }
+            | Entry::MemPoll { data, data_mask, .. }
+            | Entry::PciConfigPoll { data, data_mask, .. }
+            | Entry::PciConfig2Poll { data, data_mask, .. } => Some(PollUntil { mask: data_mask, value: data }),
+            _ => None,
+        }
+    }
+
+    /// Returns the longest a poll entry waits for its condition.
+    pub fn poll_timeout(&self) -> Option<Duration> {
+        match *self {
+            Entry::IoPoll { entry, .. } => Some(timeout(entry.delay, POLL_DELAY_UNIT)),
+            Entry::PciConfigPoll { entry, .. } => Some(timeout(entry.delay, POLL_DELAY_UNIT)),
+            Entry::PciConfig2Poll { entry, .. } => Some(timeout(entry.delay, POLL_DELAY_UNIT)),
+            Entry::MemPoll { entry, .. } => {
+                Some(timeout(entry.duration.saturating_mul(entry.loop_times), Duration::from_micros(1)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Displays a duration in the largest of seconds, milliseconds, microseconds and nanoseconds that represents it
+/// exactly, e.g. "100ms".
+pub(crate) struct DisplayDuration(pub Duration);
+
+impl fmt::Display for DisplayDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let nanos = self.0.as_nanos();
+        for (unit, suffix) in [(1_000_000_000, "s"), (1_000_000, "ms"), (1_000, "us")] {
+            if nanos % unit == 0 && nanos != 0 {
+                return write!(f, "{}{suffix}", nanos / unit);
+            }
+        }
+        write!(f, "{nanos}ns")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    use alloc::{string::ToString, vec::Vec};
+    use core::time::Duration;
+
+    use super::{
+        io_poll_until, mem_poll_until, pci_config2_poll_until, pci_config_poll_until, DisplayDuration, PollUntil,
+    };
+    use crate::boot_script::{table_header, terminate, BootScriptError, Entry, ScriptEntry, ScriptTable, Width};
+
+    fn decode(entries: &[ScriptEntry]) -> Vec<u8> {
+        let length = 13 + 3 + entries.iter().map(|entry| entry.as_bytes().len()).sum::<usize>();
+        let mut bytes = table_header(length as u32).into_bytes();
+        entries.iter().for_each(|entry| bytes.extend_from_slice(entry.as_bytes()));
+        bytes.extend_from_slice(terminate().as_bytes());
+        bytes
+    }
+
+    #[test]
+    fn timeouts_should_convert_to_opcode_units() {
+        let until = PollUntil::set(0x1);
+        let entry = io_poll_until(Width::Uint8, 0x64, until, Duration::from_micros(1)).unwrap();
+        // 1us is ten 100ns delays.
+        assert_eq!(&entry.as_bytes()[15..23], 10u64.to_le_bytes());
+        // Partial units round up.
+        let entry = pci_config_poll_until(Width::Uint8, 0, until, Duration::from_nanos(150)).unwrap();
+        assert_eq!(&entry.as_bytes()[15..23], 2u64.to_le_bytes());
+        // MEM_POLL stalls 10us per read: 100ms is 10000 reads.
+        let entry = mem_poll_until(Width::Uint32, 0xfed40044, until, Duration::from_millis(100)).unwrap();
+        assert_eq!(&entry.as_bytes()[15..23], 10u64.to_le_bytes());
+        assert_eq!(&entry.as_bytes()[23..31], 10000u64.to_le_bytes());
+
+        assert_eq!(io_poll_until(Width::Uint8, 0x64, until, Duration::MAX), Err(BootScriptError::TimeoutTooLong));
+        assert_eq!(PollUntil::clear(0x80), PollUntil { mask: 0x80, value: 0 });
+    }
+
+    #[test]
+    fn poll_entries_should_round_trip() {
+        let until = PollUntil { mask: 0x3, value: 0x1 };
+        let entries = [
+            io_poll_until(Width::Uint8, 0x64, until, Duration::from_millis(5)).unwrap(),
+            mem_poll_until(Width::Uint32, 0xfed40044, until, Duration::from_millis(100)).unwrap(),
+            pci_config_poll_until(Width::Uint16, 0x001f0004, until, Duration::from_micros(250)).unwrap(),
+            pci_config2_poll_until(Width::Uint32, 1, 0x10, until, Duration::from_secs(2)).unwrap(),
+        ];
+        let bytes = decode(&entries);
+        let table = ScriptTable::parse(&bytes).unwrap();
+        let decoded: Vec<Entry> = table.entries().take(4).collect();
+
+        let timeouts: Vec<Duration> = decoded.iter().map(|entry| entry.poll_timeout().unwrap()).collect();
+        assert_eq!(
+            timeouts,
+            [Duration::from_millis(5), Duration::from_millis(100), Duration::from_micros(250), Duration::from_secs(2)]
+        );
+        assert!(decoded.iter().all(|entry| entry.poll_until() == Some(until)));
+        assert_eq!(table.entries().last().unwrap().poll_timeout(), None);
+    }
+
+    #[test]
+    fn poll_entries_should_display() {
+        let entries = [
+            mem_poll_until(Width::Uint32, 0xfed40044, PollUntil::set(0x1), Duration::from_millis(100)).unwrap(),
+            io_poll_until(Width::Uint8, 0x64, PollUntil::clear(0x2), Duration::from_micros(1500)).unwrap(),
+            pci_config_poll_until(Width::Uint16, 0x1f0004, PollUntil::set(0x4), Duration::from_nanos(300)).unwrap(),
+            pci_config2_poll_until(Width::Uint32, 1, 0x10, PollUntil::set(0x8), Duration::from_secs(1)).unwrap(),
+        ];
+        let bytes = decode(&entries);
+        let lines: Vec<_> = ScriptTable::parse(&bytes).unwrap().entries().map(|entry| entry.to_string()).collect();
+        assert_eq!(
+            lines,
+            [
+                "poll MMIO 0xFED40044 mask 0x1 == 0x1 timeout 100ms",
+                "poll IO 0x64 mask 0x2 == 0x0 timeout 1500us",
+                "poll PCI 0x1F0004 mask 0x4 == 0x4 timeout 300ns",
+                "poll PCI segment 1 0x10 mask 0x8 == 0x8 timeout 1s",
+                "terminate",
+            ]
+        );
+        assert_eq!(DisplayDuration(Duration::ZERO).to_string(), "0ns");
+    }
+}
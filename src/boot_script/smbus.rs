@@ -0,0 +1,234 @@
+//! Boot Script SMBus Entries
+//!
+//! The SMBUS_EXECUTE entry, which replays an SMBus operation on resume, e.g. to restore the configuration of an
+//! SMBus-attached clock generator.
+//!
+//! The entry stores the SMBus address in the encoding of the EDK II SmbusLib (SMBUS_LIB_ADDRESS):
+//!
+//! | Bits  | Field                              |
+//! |-------|------------------------------------|
+//! | 7:1   | Slave address                      |
+//! | 15:8  | Command                            |
+//! | 21:16 | Length of the data                 |
+//! | 22    | Packet Error Code (PEC) check flag |
+//!
+//! The data length must suit the operation, as checked by the EDK II S3BootScriptLib:
+//!
+//! | Operation                                            | Data bytes |
+//! |------------------------------------------------------|------------|
+//! | `QuickRead`, `QuickWrite`                            | 0          |
+//! | `ReceiveByte`, `SendByte`, `ReadByte`, `WriteByte`   | 1          |
+//! | `ReadWord`, `WriteWord`, `ProcessCall`               | 2          |
+//! | `ReadBlock`, `WriteBlock`, `BWBRProcessCall`         | 1 to 32    |
+//!
+//! The data of a read is overwritten with the bytes read when the script executes, and that of a process call with
+//! the response. The quick operations send no command or PEC, and the receive and send byte operations no command.
+//!
+//! See <https://github.com/tianocore/edk2/blob/master/MdePkg/Include/Library/SmbusLib.h>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::ops::RangeInclusive;
+
+use super::{header, BootScriptError, ScriptEntry, SmbusExecute, SMBUS_EXECUTE_OPCODE};
+use crate::protocols::smbus_hc::{SmbusDeviceAddress, SmbusOperation, MAX_BLOCK_LENGTH};
+
+const SLAVE_ADDRESS_SHIFT: u32 = 1;
+const COMMAND_SHIFT: u32 = 8;
+const LENGTH_SHIFT: u32 = 16;
+const PEC_BIT: u64 = 1 << 22;
+
+/// Returns the number of data bytes `operation` accepts.
+pub fn data_length(operation: SmbusOperation) -> RangeInclusive<usize> {
+    match operation {
+        SmbusOperation::QuickRead | SmbusOperation::QuickWrite => 0..=0,
+        SmbusOperation::ReceiveByte
+        | SmbusOperation::SendByte
+        | SmbusOperation::ReadByte
+        | SmbusOperation::WriteByte => 1..=1,
+        SmbusOperation::ReadWord | SmbusOperation::WriteWord | SmbusOperation::ProcessCall => 2..=2,
+        SmbusOperation::ReadBlock | SmbusOperation::WriteBlock | SmbusOperation::BWBRProcessCall => {
+            1..=MAX_BLOCK_LENGTH
+        }
+    }
+}
+
+// Checks that the operation sends the command and PEC it is given.
+fn check_command(operation: SmbusOperation, command: u8, pec_check: bool) -> Result<(), BootScriptError> {
+    let unsupported = match operation {
+        SmbusOperation::QuickRead | SmbusOperation::QuickWrite => command != 0 || pec_check,
+        SmbusOperation::ReceiveByte | SmbusOperation::SendByte => command != 0,
+        _ => false,
+    };
+    if unsupported {
+        Err(BootScriptError::UnsupportedSmbusCommand(operation))?;
+    }
+    Ok(())
+}
+
+/// Encodes an SMBUS_EXECUTE entry executing `operation` with `command` on the device at `slave_address`, with `data`
+/// as its data buffer.
+pub fn smbus_execute(
+    slave_address: SmbusDeviceAddress,
+    command: u8,
+    operation: SmbusOperation,
+    pec_check: bool,
+    data: &[u8],
+) -> Result<ScriptEntry, BootScriptError> {
+    if slave_address.0 > 0x7f {
+        Err(BootScriptError::InvalidSmbusAddress(slave_address.0))?;
+    }
+    if !data_length(operation).contains(&data.len()) {
+        Err(BootScriptError::SmbusDataLength { operation, length: data.len() })?;
+    }
+    check_command(operation, command, pec_check)?;
+
+    let smbus_address = (slave_address.0 as u64) << SLAVE_ADDRESS_SHIFT
+        | (command as u64) << COMMAND_SHIFT
+        | (data.len() as u64) << LENGTH_SHIFT
+        | if pec_check { PEC_BIT } else { 0 };
+    let header = header::<SmbusExecute>(SMBUS_EXECUTE_OPCODE, data.len())?;
+    let entry = SmbusExecute { header, smbus_address, operation: operation as u32, data_size: data.len() as u32 };
+    Ok(ScriptEntry::new(&entry, &[data]))
+}
+
+impl SmbusExecute {
+    /// Returns the address of the device.
+    pub fn slave_address(&self) -> SmbusDeviceAddress {
+        SmbusDeviceAddress(((self.smbus_address >> SLAVE_ADDRESS_SHIFT) & 0x7f) as usize)
+    }
+
+    /// Returns the command sent to the device.
+    pub fn command(&self) -> u8 {
+        (self.smbus_address >> COMMAND_SHIFT) as u8
+    }
+
+    /// Returns whether the operation checks the Packet Error Code.
+    pub fn pec_check(&self) -> bool {
+        self.smbus_address & PEC_BIT != 0
+    }
+
+    /// Returns the operation, or the raw value if it is not an EFI_SMBUS_OPERATION.
+    pub fn smbus_operation(&self) -> Result<SmbusOperation, u32> {
+        SmbusOperation::try_from(self.operation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    use alloc::{string::ToString, vec::Vec};
+
+    use super::smbus_execute;
+    use crate::{
+        boot_script::{BootScriptError, Entry, ScriptTable, ScriptWriter},
+        protocols::smbus_hc::{SmbusDeviceAddress, SmbusOperation},
+    };
+
+    const CLOCK_GENERATOR: SmbusDeviceAddress = SmbusDeviceAddress(0x69);
+
+    #[test]
+    fn smbus_execute_should_match_edk2() {
+        // S3BootScriptSaveSmbusExecute (SMBUS_LIB_ADDRESS (0x69, 0x80, 2, TRUE), EfiSmbusWriteWord, 2, {0x34, 0x12})
+        let entry = smbus_execute(CLOCK_GENERATOR, 0x80, SmbusOperation::WriteWord, true, &[0x34, 0x12]).unwrap();
+        #[rustfmt::skip]
+        assert_eq!(entry.as_bytes(), [
+            0x06, 0x00, 0x15,
+            0xd2, 0x80, 0x42, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x07, 0x00, 0x00, 0x00,
+            0x02, 0x00, 0x00, 0x00,
+            0x34, 0x12,
+        ]);
+    }
+
+    #[test]
+    fn data_length_should_match_operation() {
+        let cases: [(SmbusOperation, &[usize], &[usize]); 12] = [
+            (SmbusOperation::QuickRead, &[0], &[1]),
+            (SmbusOperation::QuickWrite, &[0], &[1]),
+            (SmbusOperation::ReceiveByte, &[1], &[0, 2]),
+            (SmbusOperation::SendByte, &[1], &[0, 2]),
+            (SmbusOperation::ReadByte, &[1], &[0, 2]),
+            (SmbusOperation::WriteByte, &[1], &[0, 2]),
+            (SmbusOperation::ReadWord, &[2], &[1, 3]),
+            (SmbusOperation::WriteWord, &[2], &[1, 3]),
+            (SmbusOperation::ProcessCall, &[2], &[0, 1, 3, 4]),
+            (SmbusOperation::ReadBlock, &[1, 16, 32], &[0, 33]),
+            (SmbusOperation::WriteBlock, &[1, 16, 32], &[0, 33]),
+            (SmbusOperation::BWBRProcessCall, &[1, 32], &[0, 33]),
+        ];
+        let buffer = [0xa5u8; 64];
+        for (operation, valid, invalid) in cases {
+            // The command is zero since the quick, send byte and receive byte operations send none.
+            for &length in valid {
+                let entry = smbus_execute(CLOCK_GENERATOR, 0, operation, false, &buffer[..length]);
+                assert!(entry.is_ok(), "{operation:?} with {length} bytes");
+            }
+            for &length in invalid {
+                assert_eq!(
+                    smbus_execute(CLOCK_GENERATOR, 0, operation, false, &buffer[..length]),
+                    Err(BootScriptError::SmbusDataLength { operation, length }),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_requests_should_be_rejected() {
+        for operation in [SmbusOperation::QuickRead, SmbusOperation::QuickWrite] {
+            assert_eq!(
+                smbus_execute(CLOCK_GENERATOR, 0, operation, true, &[]),
+                Err(BootScriptError::UnsupportedSmbusCommand(operation))
+            );
+            assert_eq!(
+                smbus_execute(CLOCK_GENERATOR, 1, operation, false, &[]),
+                Err(BootScriptError::UnsupportedSmbusCommand(operation))
+            );
+        }
+        assert_eq!(
+            smbus_execute(CLOCK_GENERATOR, 1, SmbusOperation::SendByte, false, &[0]),
+            Err(BootScriptError::UnsupportedSmbusCommand(SmbusOperation::SendByte))
+        );
+        assert!(smbus_execute(CLOCK_GENERATOR, 0, SmbusOperation::SendByte, true, &[0]).is_ok());
+        assert_eq!(
+            smbus_execute(SmbusDeviceAddress(0x80), 0, SmbusOperation::ReadByte, false, &[0]),
+            Err(BootScriptError::InvalidSmbusAddress(0x80))
+        );
+    }
+
+    #[test]
+    fn smbus_entries_should_round_trip() {
+        let mut writer = ScriptWriter::new();
+        let block: Vec<u8> = (0..8).collect();
+        writer
+            .append(smbus_execute(CLOCK_GENERATOR, 0x00, SmbusOperation::WriteBlock, false, &block).unwrap())
+            .unwrap();
+        writer.append(smbus_execute(CLOCK_GENERATOR, 0x0c, SmbusOperation::WriteByte, true, &[0x5a]).unwrap()).unwrap();
+        let bytes = writer.to_bytes().unwrap();
+        let table = ScriptTable::parse(&bytes).unwrap();
+
+        let entries: Vec<Entry> = table.entries().collect();
+        let Entry::SmbusExecute { entry, data } = entries[1] else { panic!("{}", entries[1]) };
+        assert_eq!(entry.slave_address(), CLOCK_GENERATOR);
+        assert_eq!(entry.command(), 0x0c);
+        assert!(entry.pec_check());
+        assert_eq!(entry.smbus_operation(), Ok(SmbusOperation::WriteByte));
+        assert_eq!(data, [0x5a]);
+        assert!(matches!(entries[0], Entry::SmbusExecute { data, .. } if data == block));
+
+        let lines: Vec<_> = entries.iter().map(|entry| entry.to_string()).collect();
+        assert_eq!(
+            lines,
+            [
+                "smbus 0x69 WriteBlock command 0x0 [0x0, 0x1, 0x2, 0x3, 0x4, 0x5, 0x6, 0x7]",
+                "smbus 0x69 WriteByte command 0xC pec [0x5A]",
+                "terminate",
+            ]
+        );
+    }
+}
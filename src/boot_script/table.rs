@@ -0,0 +1,785 @@
+//! Boot Script Table Parser
+//!
+//! Decodes a boot script table, e.g. one dumped from ACPI NVS memory while debugging an S3 resume failure. The table
+//! may be corrupted, so [`ScriptTable::parse`] checks the header, every entry length and the terminate entry against
+//! the bounds of the table before any entry is decoded.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{fmt, mem::size_of, ptr, str};
+
+use r_efi::efi;
+
+use super::{
+    poll::DisplayDuration, smbus, Dispatch, Dispatch2, EntryHeader, EntryLayout, Information, IoPoll, IoReadWrite,
+    IoWrite, MemPoll, MemReadWrite, MemWrite, PciConfig2Poll, PciConfig2ReadWrite, PciConfig2Write, PciConfigPoll,
+    PciConfigReadWrite, PciConfigWrite, SmbusExecute, Stall, TableHeader, Terminate, Width, DISPATCH_2_OPCODE,
+    DISPATCH_OPCODE, INFORMATION_OPCODE, IO_POLL_OPCODE, IO_READ_WRITE_OPCODE, IO_WRITE_OPCODE, LABEL_OPCODE,
+    MEM_POLL_OPCODE, MEM_READ_WRITE_OPCODE, MEM_WRITE_OPCODE, PCI_CONFIG2_POLL_OPCODE, PCI_CONFIG2_READ_WRITE_OPCODE,
+    PCI_CONFIG2_WRITE_OPCODE, PCI_CONFIG_POLL_OPCODE, PCI_CONFIG_READ_WRITE_OPCODE, PCI_CONFIG_WRITE_OPCODE,
+    SMBUS_EXECUTE_OPCODE, STALL_OPCODE, TABLE_OPCODE, TABLE_VERSION, TERMINATE_OPCODE,
+};
+
+/// Errors reported when parsing a boot script table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    /// The table does not start with a table header entry.
+    InvalidHeader,
+    /// The table header version is not [`TABLE_VERSION`].
+    UnsupportedVersion(u16),
+    /// The table length in the header is smaller than a header and terminate entry, or larger than the buffer.
+    InvalidTableLength(u32),
+    /// The entry at `offset` extends past the end of the table.
+    Truncated { offset: usize },
+    /// The length of the entry at `offset` does not match its opcode and contents.
+    InvalidLength { offset: usize, length: u8 },
+    /// The entry at `offset` has an unknown opcode.
+    UnknownOpcode { offset: usize, opcode: u16 },
+    /// The entry at `offset` has an invalid width.
+    InvalidWidth { offset: usize, width: u32 },
+    /// The label entry at `offset` is not a null-terminated ASCII string.
+    InvalidLabel { offset: usize },
+    /// The SMBus entry at `offset` has an invalid operation.
+    InvalidSmbusOperation { offset: usize, operation: u32 },
+    /// The table ends without a terminate entry.
+    MissingTerminate,
+    /// The terminate entry ends at `offset`, before the end of the table.
+    TrailingData { offset: usize },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::InvalidHeader => write!(f, "missing boot script table header"),
+            ParseError::UnsupportedVersion(version) => write!(f, "unsupported boot script table version {version:#x}"),
+            ParseError::InvalidTableLength(length) => write!(f, "invalid boot script table length {length:#x}"),
+            ParseError::Truncated { offset } => write!(f, "boot script entry at {offset:#x} is truncated"),
+            ParseError::InvalidLength { offset, length } => {
+                write!(f, "boot script entry at {offset:#x} has invalid length {length:#x}")
+            }
+            ParseError::UnknownOpcode { offset, opcode } => {
+                write!(f, "boot script entry at {offset:#x} has unknown opcode {opcode:#x}")
+            }
+            ParseError::InvalidWidth { offset, width } => {
+                write!(f, "boot script entry at {offset:#x} has invalid width {width}")
+            }
+            ParseError::InvalidLabel { offset } => write!(f, "boot script label at {offset:#x} is invalid"),
+            ParseError::InvalidSmbusOperation { offset, operation } => {
+                write!(f, "boot script entry at {offset:#x} has invalid SMBus operation {operation}")
+            }
+            ParseError::MissingTerminate => write!(f, "boot script table has no terminate entry"),
+            ParseError::TrailingData { offset } => {
+                write!(f, "boot script table has data after terminate at {offset:#x}")
+            }
+        }
+    }
+}
+
+impl From<ParseError> for efi::Status {
+    fn from(_: ParseError) -> Self {
+        efi::Status::VOLUME_CORRUPTED
+    }
+}
+
+/// A decoded boot script entry: the opcode structure and the data that follows it.
+///
+/// The data and data mask of read-write and poll entries are zero-extended from the entry width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entry<'a> {
+    IoWrite {
+        entry: IoWrite,
+        data: &'a [u8],
+    },
+    IoReadWrite {
+        entry: IoReadWrite,
+        data: u64,
+        data_mask: u64,
+    },
+    MemWrite {
+        entry: MemWrite,
+        data: &'a [u8],
+    },
+    MemReadWrite {
+        entry: MemReadWrite,
+        data: u64,
+        data_mask: u64,
+    },
+    PciConfigWrite {
+        entry: PciConfigWrite,
+        data: &'a [u8],
+    },
+    PciConfigReadWrite {
+        entry: PciConfigReadWrite,
+        data: u64,
+        data_mask: u64,
+    },
+    PciConfig2Write {
+        entry: PciConfig2Write,
+        data: &'a [u8],
+    },
+    PciConfig2ReadWrite {
+        entry: PciConfig2ReadWrite,
+        data: u64,
+        data_mask: u64,
+    },
+    SmbusExecute {
+        entry: SmbusExecute,
+        data: &'a [u8],
+    },
+    Stall(Stall),
+    Dispatch(Dispatch),
+    Dispatch2(Dispatch2),
+    Information {
+        entry: Information,
+        information: &'a [u8],
+    },
+    /// A label, without its null terminator.
+    Label {
+        entry: Information,
+        label: &'a str,
+    },
+    IoPoll {
+        entry: IoPoll,
+        data: u64,
+        data_mask: u64,
+    },
+    MemPoll {
+        entry: MemPoll,
+        data: u64,
+        data_mask: u64,
+    },
+    PciConfigPoll {
+        entry: PciConfigPoll,
+        data: u64,
+        data_mask: u64,
+    },
+    PciConfig2Poll {
+        entry: PciConfig2Poll,
+        data: u64,
+        data_mask: u64,
+    },
+    Terminate(Terminate),
+}
+
+impl<'a> Entry<'a> {
+    /// Returns the header of the entry.
+    pub fn header(&self) -> EntryHeader {
+        match self {
+            Entry::IoWrite { entry, .. } => entry.header,
+            Entry::IoReadWrite { entry, .. } => entry.header,
+            Entry::MemWrite { entry, .. } => entry.header,
+            Entry::MemReadWrite { entry, .. } => entry.header,
+            Entry::PciConfigWrite { entry, .. } => entry.header,
+            Entry::PciConfigReadWrite { entry, .. } => entry.header,
+            Entry::PciConfig2Write { entry, .. } => entry.header,
+            Entry::PciConfig2ReadWrite { entry, .. } => entry.header,
+            Entry::SmbusExecute { entry, .. } => entry.header,
+            Entry::Stall(entry) => entry.header,
+            Entry::Dispatch(entry) => entry.header,
+            Entry::Dispatch2(entry) => entry.header,
+            Entry::Information { entry, .. } | Entry::Label { entry, .. } => entry.header,
+            Entry::IoPoll { entry, .. } => entry.header,
+            Entry::MemPoll { entry, .. } => entry.header,
+            Entry::PciConfigPoll { entry, .. } => entry.header,
+            Entry::PciConfig2Poll { entry, .. } => entry.header,
+            Entry::Terminate(entry) => entry.header,
+        }
+    }
+
+    /// Returns the opcode of the entry.
+    pub fn opcode(&self) -> u16 {
+        self.header().opcode
+    }
+
+    /// Decodes the entry at `offset` in `table`, checking it against the bounds of the table.
+    fn decode(table: &'a [u8], offset: usize) -> Result<Self, ParseError> {
+        let truncated = ParseError::Truncated { offset };
+        let header: EntryHeader = read(table.get(offset..).ok_or(truncated)?).ok_or(truncated)?;
+        let length = header.length;
+        let bytes = table.get(offset..offset + length as usize).ok_or(truncated)?;
+        let invalid_length = ParseError::InvalidLength { offset, length };
+        let width = |width: u32| Width::try_from(width).map_err(|_| ParseError::InvalidWidth { offset, width });
+
+        // Returns the opcode structure and the data that follows it.
+        fn split<T: EntryLayout>(bytes: &[u8], error: ParseError) -> Result<(T, &[u8]), ParseError> {
+            let entry = read(bytes).ok_or(error)?;
+            Ok((entry, &bytes[size_of::<T>()..]))
+        }
+        // Returns the data of a write entry, which holds count values of the width.
+        let values = |width: Width, count: u32, data: &'a [u8]| {
+            if data.len() != (count as usize).saturating_mul(width.unit_size()) {
+                Err(invalid_length)?;
+            }
+            Ok(data)
+        };
+        // Returns the data and data mask of a read-write or poll entry.
+        let masked = |width: Width, data: &[u8]| {
+            let size = width.unit_size();
+            if data.len() != 2 * size {
+                Err(invalid_length)?;
+            }
+            Ok((value(&data[..size]), value(&data[size..])))
+        };
+        // Checks that an entry has no data.
+        let fixed = |data: &[u8]| if data.is_empty() { Ok(()) } else { Err(invalid_length) };
+
+        Ok(match header.opcode {
+            IO_WRITE_OPCODE => {
+                let (entry, data) = split::<IoWrite>(bytes, invalid_length)?;
+                Entry::IoWrite { entry, data: values(width(entry.width)?, entry.count, data)? }
+            }
+            IO_READ_WRITE_OPCODE => {
+                let (entry, data) = split::<IoReadWrite>(bytes, invalid_length)?;
+                let (data, data_mask) = masked(width(entry.width)?, data)?;
+                Entry::IoReadWrite { entry, data, data_mask }
+            }
+            MEM_WRITE_OPCODE => {
+                let (entry, data) = split::<MemWrite>(bytes, invalid_length)?;
+                Entry::MemWrite { entry, data: values(width(entry.width)?, entry.count, data)? }
+            }
+            MEM_READ_WRITE_OPCODE => {
+                let (entry, data) = split::<MemReadWrite>(bytes, invalid_length)?;
+                let (data, data_mask) = masked(width(entry.width)?, data)?;
+                Entry::MemReadWrite { entry, data, data_mask }
+            }
+            PCI_CONFIG_WRITE_OPCODE => {
+                let (entry, data) = split::<PciConfigWrite>(bytes, invalid_length)?;
+                Entry::PciConfigWrite { entry, data: values(width(entry.width)?, entry.count, data)? }
+            }
+            PCI_CONFIG_READ_WRITE_OPCODE => {
+                let (entry, data) = split::<PciConfigReadWrite>(bytes, invalid_length)?;
+                let (data, data_mask) = masked(width(entry.width)?, data)?;
+                Entry::PciConfigReadWrite { entry, data, data_mask }
+            }
+            PCI_CONFIG2_WRITE_OPCODE => {
+                let (entry, data) = split::<PciConfig2Write>(bytes, invalid_length)?;
+                Entry::PciConfig2Write { entry, data: values(width(entry.width)?, entry.count, data)? }
+            }
+            PCI_CONFIG2_READ_WRITE_OPCODE => {
+                let (entry, data) = split::<PciConfig2ReadWrite>(bytes, invalid_length)?;
+                let (data, data_mask) = masked(width(entry.width)?, data)?;
+                Entry::PciConfig2ReadWrite { entry, data, data_mask }
+            }
+            SMBUS_EXECUTE_OPCODE => {
+                let (entry, data) = split::<SmbusExecute>(bytes, invalid_length)?;
+                let operation = entry
+                    .smbus_operation()
+                    .map_err(|operation| ParseError::InvalidSmbusOperation { offset, operation })?;
+                if data.len() != entry.data_size as usize || !smbus::data_length(operation).contains(&data.len()) {
+                    Err(invalid_length)?;
+                }
+                Entry::SmbusExecute { entry, data }
+            }
+            STALL_OPCODE => {
+                let (entry, data) = split::<Stall>(bytes, invalid_length)?;
+                fixed(data)?;
+                Entry::Stall(entry)
+            }
+            DISPATCH_OPCODE => {
+                let (entry, data) = split::<Dispatch>(bytes, invalid_length)?;
+                fixed(data)?;
+                Entry::Dispatch(entry)
+            }
+            DISPATCH_2_OPCODE => {
+                let (entry, data) = split::<Dispatch2>(bytes, invalid_length)?;
+                fixed(data)?;
+                Entry::Dispatch2(entry)
+            }
+            INFORMATION_OPCODE | LABEL_OPCODE => {
+                let (entry, information) = split::<Information>(bytes, invalid_length)?;
+                if information.len() != entry.information_length as usize {
+                    Err(invalid_length)?;
+                }
+                if header.opcode == INFORMATION_OPCODE {
+                    Entry::Information { entry, information }
+                } else {
+                    let label = match information.split_last() {
+                        Some((0, label)) if label.is_ascii() && !label.contains(&0) => label,
+                        _ => Err(ParseError::InvalidLabel { offset })?,
+                    };
+                    //Safety: ASCII is valid UTF-8.
+                    Entry::Label { entry, label: unsafe { str::from_utf8_unchecked(label) } }
+                }
+            }
+            IO_POLL_OPCODE => {
+                let (entry, data) = split::<IoPoll>(bytes, invalid_length)?;
+                let (data, data_mask) = masked(width(entry.width)?, data)?;
+                Entry::IoPoll { entry, data, data_mask }
+            }
+            MEM_POLL_OPCODE => {
+                let (entry, data) = split::<MemPoll>(bytes, invalid_length)?;
+                let (data, data_mask) = masked(width(entry.width)?, data)?;
+                Entry::MemPoll { entry, data, data_mask }
+            }
+            PCI_CONFIG_POLL_OPCODE => {
+                let (entry, data) = split::<PciConfigPoll>(bytes, invalid_length)?;
+                let (data, data_mask) = masked(width(entry.width)?, data)?;
+                Entry::PciConfigPoll { entry, data, data_mask }
+            }
+            PCI_CONFIG2_POLL_OPCODE => {
+                let (entry, data) = split::<PciConfig2Poll>(bytes, invalid_length)?;
+                let (data, data_mask) = masked(width(entry.width)?, data)?;
+                Entry::PciConfig2Poll { entry, data, data_mask }
+            }
+            TERMINATE_OPCODE => {
+                let (entry, data) = split::<Terminate>(bytes, invalid_length)?;
+                fixed(data)?;
+                Entry::Terminate(entry)
+            }
+            opcode => Err(ParseError::UnknownOpcode { offset, opcode })?,
+        })
+    }
+}
+
+// Displays the values written by a write entry.
+struct Values<'a>(u32, &'a [u8]);
+
+impl fmt::Display for Values<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The width of a parsed entry is valid.
+        let size = Width::try_from(self.0).map_or(1, Width::unit_size);
+        for (index, chunk) in self.1.chunks(size).enumerate() {
+            let separator = if index == 0 { "" } else { ", " };
+            write!(f, "{separator}{:#X}", value(chunk))?;
+        }
+        Ok(())
+    }
+}
+
+// Displays the width of an entry.
+struct DisplayWidth(u32);
+
+impl fmt::Display for DisplayWidth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match Width::try_from(self.0) {
+            Ok(width) => write!(f, "{width:?}"),
+            Err(_) => write!(f, "width {}", self.0),
+        }
+    }
+}
+
+/// Displays the entry as a line of a script listing, e.g. "poll MMIO 0xFED40044 mask 0x1 == 0x1 timeout 100ms".
+impl fmt::Display for Entry<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Poll entries have a condition and a timeout.
+        if let (Some(until), Some(timeout)) = (self.poll_until(), self.poll_timeout()) {
+            match self {
+                Entry::IoPoll { entry, .. } => write!(f, "poll IO {:#X}", { entry.address })?,
+                Entry::MemPoll { entry, .. } => write!(f, "poll MMIO {:#X}", { entry.address })?,
+                Entry::PciConfigPoll { entry, .. } => write!(f, "poll PCI {:#X}", { entry.address })?,
+                Entry::PciConfig2Poll { entry, .. } => {
+                    write!(f, "poll PCI segment {} {:#X}", { entry.segment }, { entry.address })?
+                }
+                _ => (),
+            }
+            return write!(f, " {until} timeout {}", DisplayDuration(timeout));
+        }
+        match *self {
+            Entry::IoWrite { entry, data } => {
+                write!(
+                    f,
+                    "write IO {:#X} {} [{}]",
+                    { entry.address },
+                    DisplayWidth(entry.width),
+                    Values(entry.width, data)
+                )
+            }
+            Entry::MemWrite { entry, data } => {
+                write!(
+                    f,
+                    "write MMIO {:#X} {} [{}]",
+                    { entry.address },
+                    DisplayWidth(entry.width),
+                    Values(entry.width, data)
+                )
+            }
+            Entry::PciConfigWrite { entry, data } => {
+                write!(
+                    f,
+                    "write PCI {:#X} {} [{}]",
+                    { entry.address },
+                    DisplayWidth(entry.width),
+                    Values(entry.width, data)
+                )
+            }
+            Entry::PciConfig2Write { entry, data } => write!(
+                f,
+                "write PCI segment {} {:#X} {} [{}]",
+                { entry.segment },
+                { entry.address },
+                DisplayWidth(entry.width),
+                Values(entry.width, data)
+            ),
+            Entry::IoReadWrite { entry, data, data_mask } => {
+                write!(
+                    f,
+                    "read-write IO {:#X} {} & {data_mask:#X} | {data:#X}",
+                    { entry.address },
+                    DisplayWidth(entry.width)
+                )
+            }
+            Entry::MemReadWrite { entry, data, data_mask } => write!(
+                f,
+                "read-write MMIO {:#X} {} & {data_mask:#X} | {data:#X}",
+                { entry.address },
+                DisplayWidth(entry.width)
+            ),
+            Entry::PciConfigReadWrite { entry, data, data_mask } => write!(
+                f,
+                "read-write PCI {:#X} {} & {data_mask:#X} | {data:#X}",
+                { entry.address },
+                DisplayWidth(entry.width)
+            ),
+            Entry::PciConfig2ReadWrite { entry, data, data_mask } => write!(
+                f,
+                "read-write PCI segment {} {:#X} {} & {data_mask:#X} | {data:#X}",
+                { entry.segment },
+                { entry.address },
+                DisplayWidth(entry.width)
+            ),
+            Entry::SmbusExecute { entry, data } => {
+                write!(f, "smbus {:#X} ", entry.slave_address().0)?;
+                match entry.smbus_operation() {
+                    Ok(operation) => write!(f, "{operation:?}")?,
+                    Err(operation) => write!(f, "operation {operation}")?,
+                }
+                write!(f, " command {:#X}", entry.command())?;
+                if entry.pec_check() {
+                    write!(f, " pec")?;
+                }
+                if !data.is_empty() {
+                    write!(f, " [{}]", Values(Width::Uint8 as u32, data))?;
+                }
+                Ok(())
+            }
+            Entry::Stall(entry) => {
+                write!(f, "stall {}", DisplayDuration(core::time::Duration::from_micros(entry.duration)))
+            }
+            Entry::Dispatch(entry) => write!(f, "dispatch {:#X}", { entry.entry_point }),
+            Entry::Dispatch2(entry) => {
+                write!(f, "dispatch {:#X} context {:#X}", { entry.entry_point }, { entry.context })
+            }
+            Entry::Information { information, .. } => {
+                write!(f, "information [{}]", Values(Width::Uint8 as u32, information))
+            }
+            Entry::Label { label, .. } => write!(f, "label \"{label}\""),
+            Entry::Terminate(_) => write!(f, "terminate"),
+            Entry::IoPoll { .. }
+            | Entry::MemPoll { .. }
+            | Entry::PciConfigPoll { .. }
+            | Entry::PciConfig2Poll { .. } => Ok(()),
+        }
+    }
+}
+
+// Reads a packed structure from the start of bytes.
+fn read<T: Copy>(bytes: &[u8]) -> Option<T> {
+    if bytes.len() < size_of::<T>() {
+        return None;
+    }
+    //Safety: bytes holds enough bytes for T, which is only instantiated with packed structures of integers, for
+    // which every bit pattern is valid.
+    Some(unsafe { ptr::read_unaligned(bytes.as_ptr() as *const T) })
+}
+
+// Zero-extends a little-endian value of at most 8 bytes.
+fn value(bytes: &[u8]) -> u64 {
+    let mut value = [0u8; 8];
+    value[..bytes.len()].copy_from_slice(bytes);
+    u64::from_le_bytes(value)
+}
+
+/// A boot script table whose header and entries have been validated.
+///
+/// ## Example
+/// ```
+/// use mu_pi::boot_script::{self, Entry, ScriptTable};
+///
+/// let entries = [boot_script::label("Start").unwrap(), boot_script::stall(10), boot_script::terminate()];
+/// let length = 13 + entries.iter().map(|entry| entry.as_bytes().len()).sum::<usize>();
+/// let mut bytes = boot_script::table_header(length as u32).into_bytes();
+/// entries.iter().for_each(|entry| bytes.extend_from_slice(entry.as_bytes()));
+///
+/// let table = ScriptTable::parse(&bytes).unwrap();
+/// assert_eq!(table.find_label("Start"), Some(0));
+/// assert!(matches!(table.entries().nth(1), Some(Entry::Stall(_))));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ScriptTable<'a> {
+    header: TableHeader,
+    // The entries following the table header, up to and including the terminate entry.
+    entries: &'a [u8],
+}
+
+impl<'a> ScriptTable<'a> {
+    /// Parses the boot script table at the start of `bytes`, which may be longer than the table.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        let header: TableHeader = read(bytes).ok_or(ParseError::InvalidHeader)?;
+        if header.header.opcode != TABLE_OPCODE || header.header.length as usize != size_of::<TableHeader>() {
+            Err(ParseError::InvalidHeader)?;
+        }
+        if header.version != TABLE_VERSION {
+            Err(ParseError::UnsupportedVersion(header.version))?;
+        }
+        let table_length = header.table_length;
+        let table = match bytes.get(..table_length as usize) {
+            Some(table) if table.len() >= size_of::<TableHeader>() + size_of::<Terminate>() => table,
+            _ => Err(ParseError::InvalidTableLength(table_length))?,
+        };
+
+        let mut offset = size_of::<TableHeader>();
+        loop {
+            if offset == table.len() {
+                Err(ParseError::MissingTerminate)?;
+            }
+            let entry = Entry::decode(table, offset)?;
+            offset += entry.header().length as usize;
+            if entry.opcode() == TERMINATE_OPCODE {
+                break;
+            }
+        }
+        if offset != table.len() {
+            Err(ParseError::TrailingData { offset })?;
+        }
+        Ok(Self { header, entries: &table[size_of::<TableHeader>()..] })
+    }
+
+    /// Returns the table header.
+    pub fn header(&self) -> TableHeader {
+        self.header
+    }
+
+    /// Returns the length in bytes of the table, including the header and terminate entry.
+    pub fn len(&self) -> usize {
+        self.header.table_length as usize
+    }
+
+    /// Returns true if the table holds no entries other than the terminate entry.
+    pub fn is_empty(&self) -> bool {
+        self.entries.len() == size_of::<Terminate>()
+    }
+
+    /// Returns an iterator over the entries of the table, ending with the terminate entry.
+    pub fn entries(&self) -> Entries<'a> {
+        Entries { entries: self.entries, offset: 0 }
+    }
+
+    /// Returns the index in [`entries`](Self::entries) of the first label entry named `name`, the position the S3
+    /// Save State Protocol Label() function inserts entries before or after.
+    pub fn find_label(&self, name: &str) -> Option<usize> {
+        self.entries().position(|entry| matches!(entry, Entry::Label { label, .. } if label == name))
+    }
+}
+
+/// Iterator over the entries of a [`ScriptTable`].
+#[derive(Debug, Clone)]
+pub struct Entries<'a> {
+    entries: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Iterator for Entries<'a> {
+    type Item = Entry<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The entries were validated by ScriptTable::parse(), so decoding only stops at the end of the table.
+        let entry = Entry::decode(self.entries, self.offset).ok()?;
+        self.offset += entry.header().length as usize;
+        Some(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    use alloc::{
+        string::{String, ToString},
+        vec::Vec,
+    };
+
+    use super::{Entry, ParseError, ScriptTable};
+    use crate::boot_script::*;
+
+    // A table recorded by S3BootScriptLib on a platform: a label, a POST code write, an LPC decode range, an MMIO
+    // read-write, a TPM access poll, a stall, a platform tag and a dispatch to a resume helper.
+    #[rustfmt::skip]
+    const SNAPSHOT: [u8; 176] = [
+        // Table header, 0xb0 bytes
+        0xaa, 0x00, 0x0d, 0x01, 0x00, 0xb0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        // Label "SiInit"
+        0xfe, 0x00, 0x0e, 0x07, 0x00, 0x00, 0x00, 0x53, 0x69, 0x49, 0x6e, 0x69, 0x74, 0x00,
+        // IO_WRITE 8-bit 0x80 = 0x55
+        0x00, 0x00, 0x14, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x55,
+        // PCI_CONFIG_WRITE 32-bit 00:1f.0 0x48 = 0x501
+        0x04, 0x00, 0x17, 0x02, 0x00, 0x00, 0x00, 0x48, 0x00, 0x1f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,
+        0x00, 0x00, 0x00, 0x01, 0x05, 0x00, 0x00,
+        // MEM_READ_WRITE 32-bit 0xfed1f404 |= 0x4
+        0x03, 0x00, 0x17, 0x02, 0x00, 0x00, 0x00, 0x04, 0xf4, 0xd1, 0xfe, 0x00, 0x00, 0x00, 0x00, 0x04,
+        0x00, 0x00, 0x00, 0xfb, 0xff, 0xff, 0xff,
+        // MEM_POLL 32-bit 0xfed40044 & 0x1 == 0x1, 10us x 10000
+        0x0e, 0x00, 0x27, 0x02, 0x00, 0x00, 0x00, 0x44, 0x00, 0xd4, 0xfe, 0x00, 0x00, 0x00, 0x00, 0x0a,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x27, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,
+        0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+        // STALL 100us
+        0x07, 0x00, 0x0b, 0x64, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        // INFORMATION "PCH"
+        0x0a, 0x00, 0x0b, 0x04, 0x00, 0x00, 0x00, 0x50, 0x43, 0x48, 0x00,
+        // DISPATCH_2 0x7f8e1000 (0x7f8e2000)
+        0x09, 0x00, 0x13, 0x00, 0x10, 0x8e, 0x7f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x8e, 0x7f, 0x00,
+        0x00, 0x00, 0x00,
+        // TERMINATE
+        0xff, 0x00, 0x03,
+    ];
+
+    #[test]
+    fn snapshot_should_decode() {
+        // The table may be followed by unused memory.
+        let mut memory = SNAPSHOT.to_vec();
+        memory.extend_from_slice(&[0xaf; 32]);
+        let table = ScriptTable::parse(&memory).unwrap();
+        assert_eq!(table.len(), SNAPSHOT.len());
+        assert!(!table.is_empty());
+
+        let entries: Vec<Entry> = table.entries().collect();
+        assert_eq!(entries.len(), 9);
+        assert!(matches!(entries[0], Entry::Label { label: "SiInit", .. }));
+        match entries[1] {
+            Entry::IoWrite { entry, data } => {
+                assert_eq!((entry.width, entry.count, entry.address), (Width::Uint8 as u32, 1, 0x80));
+                assert_eq!(data, [0x55]);
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+        match entries[2] {
+            Entry::PciConfigWrite { entry, data } => {
+                assert_eq!({ entry.address }, 0x001f0048);
+                assert_eq!(data, 0x501u32.to_le_bytes());
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+        match entries[3] {
+            Entry::MemReadWrite { entry, data, data_mask } => {
+                assert_eq!(({ entry.address }, data, data_mask), (0xfed1f404, 0x4, 0xfffffffb));
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+        match entries[4] {
+            Entry::MemPoll { entry, data, data_mask } => {
+                assert_eq!(({ entry.address }, { entry.duration }, { entry.loop_times }), (0xfed40044, 10, 10000));
+                assert_eq!((data, data_mask), (1, 1));
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+        assert!(matches!(entries[5], Entry::Stall(stall) if { stall.duration } == 100));
+        assert!(matches!(entries[6], Entry::Information { information: b"PCH\0", .. }));
+        assert!(matches!(entries[7], Entry::Dispatch2(dispatch) if { dispatch.context } == 0x7f8e2000));
+        assert_eq!(entries[8].opcode(), TERMINATE_OPCODE);
+
+        assert_eq!(table.find_label("SiInit"), Some(0));
+        assert_eq!(table.find_label("SiInit2"), None);
+    }
+
+    #[test]
+    fn encoded_entries_should_decode() {
+        let encoded = [
+            label("Start").unwrap(),
+            io_read_write(Width::Uint16, 0x1004, 0x1, 0xfffe).unwrap(),
+            mem_write(Width::FifoUint32, 0x1000, 2, &[1, 0, 0, 0, 2, 0, 0, 0]).unwrap(),
+            pci_config2_read_write(Width::Uint8, 1, 0x10, 0x80, 0x7f).unwrap(),
+            pci_config2_write(Width::Uint8, 1, 0x10, 1, &[0x80]).unwrap(),
+            io_poll(Width::Uint8, 0x64, 0, 0x2, 1000).unwrap(),
+            pci_config_poll(Width::Uint16, 0x08, 0x1, 0x1, 10).unwrap(),
+            pci_config2_poll(Width::Uint32, 2, 0x08, 0x1, 0x1, 10).unwrap(),
+            dispatch(0x1234),
+            label("End").unwrap(),
+        ];
+        let table_length = size_of::<TableHeader>() + encoded.iter().map(|e| e.as_bytes().len()).sum::<usize>() + 3;
+        let mut bytes = table_header(table_length as u32).into_bytes();
+        encoded.iter().for_each(|entry| bytes.extend_from_slice(entry.as_bytes()));
+        bytes.extend_from_slice(terminate().as_bytes());
+
+        let table = ScriptTable::parse(&bytes).unwrap();
+        let opcodes: Vec<u16> = table.entries().map(|entry| entry.opcode()).collect();
+        let expected: Vec<u16> = encoded.iter().map(|entry| entry.opcode()).chain([TERMINATE_OPCODE]).collect();
+        assert_eq!(opcodes, expected);
+        assert_eq!(table.find_label("End"), Some(9));
+        assert!(matches!(
+            table.entries().nth(3),
+            Some(Entry::PciConfig2ReadWrite { data: 0x80, data_mask: 0x7f, entry }) if { entry.segment } == 1
+        ));
+    }
+
+    #[test]
+    fn entries_should_display() {
+        let table = ScriptTable::parse(&SNAPSHOT).unwrap();
+        let lines: Vec<String> = table.entries().map(|entry| entry.to_string()).collect();
+        assert_eq!(
+            lines,
+            [
+                "label \"SiInit\"",
+                "write IO 0x80 Uint8 [0x55]",
+                "write PCI 0x1F0048 Uint32 [0x501]",
+                "read-write MMIO 0xFED1F404 Uint32 & 0xFFFFFFFB | 0x4",
+                "poll MMIO 0xFED40044 mask 0x1 == 0x1 timeout 100ms",
+                "stall 100us",
+                "information [0x50, 0x43, 0x48, 0x0]",
+                "dispatch 0x7F8E1000 context 0x7F8E2000",
+                "terminate",
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_tables_should_be_rejected() {
+        let parse = |patch: &dyn Fn(&mut Vec<u8>)| {
+            let mut bytes = SNAPSHOT.to_vec();
+            patch(&mut bytes);
+            ScriptTable::parse(&bytes).map(|_| ())
+        };
+
+        assert_eq!(parse(&|b| b.truncate(10)), Err(ParseError::InvalidHeader));
+        assert_eq!(parse(&|b| b[0] = 0xab), Err(ParseError::InvalidHeader));
+        assert_eq!(parse(&|b| b[3] = 2), Err(ParseError::UnsupportedVersion(2)));
+        assert_eq!(parse(&|b| b.truncate(175)), Err(ParseError::InvalidTableLength(0xb0)));
+        assert_eq!(parse(&|b| b[5] = 0x0f), Err(ParseError::InvalidTableLength(0x0f)));
+        // Label length running past the end of the table.
+        assert_eq!(parse(&|b| b[15] = 0xff), Err(ParseError::Truncated { offset: 13 }));
+        // Label missing its null terminator.
+        assert_eq!(parse(&|b| b[26] = b'!'), Err(ParseError::InvalidLabel { offset: 13 }));
+        // Label information length disagreeing with the entry length.
+        assert_eq!(parse(&|b| b[16] = 6), Err(ParseError::InvalidLength { offset: 13, length: 0x0e }));
+        // IO_WRITE count disagreeing with the entry length.
+        assert_eq!(parse(&|b| b[34] = 2), Err(ParseError::InvalidLength { offset: 27, length: 0x14 }));
+        assert_eq!(parse(&|b| b[30] = 12), Err(ParseError::InvalidWidth { offset: 27, width: 12 }));
+        assert_eq!(parse(&|b| b[27] = 0x42), Err(ParseError::UnknownOpcode { offset: 27, opcode: 0x42 }));
+        // Entry shorter than its opcode structure.
+        assert_eq!(parse(&|b| b[29] = 3), Err(ParseError::InvalidLength { offset: 27, length: 3 }));
+        // Zero length entry.
+        assert_eq!(parse(&|b| b[29] = 0), Err(ParseError::InvalidLength { offset: 27, length: 0 }));
+        // Terminate replaced by an information entry.
+        assert_eq!(parse(&|b| b[173] = 0x0a), Err(ParseError::InvalidLength { offset: 173, length: 3 }));
+        // Terminate before the end of the table.
+        assert_eq!(
+            parse(&|b| {
+                b.splice(13..13, terminate().into_bytes());
+                b[5] += 3;
+            }),
+            Err(ParseError::TrailingData { offset: 16 })
+        );
+        // No terminate.
+        assert_eq!(
+            parse(&|b| {
+                b.truncate(173);
+                b[5] -= 3;
+            }),
+            Err(ParseError::MissingTerminate)
+        );
+        assert_eq!(efi::Status::from(ParseError::MissingTerminate), efi::Status::VOLUME_CORRUPTED);
+    }
+}
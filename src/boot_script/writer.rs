@@ -0,0 +1,289 @@
+//! Boot Script Writer
+//!
+//! Records boot script entries and produces the table image, the way the S3 Save State Protocol Write(), Insert()
+//! and Label() functions maintain the script.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::mem::size_of;
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+use super::{
+    label, table_header, terminate, BootScriptError, ScriptEntry, TableHeader, Terminate, DISPATCH_2_OPCODE,
+    DISPATCH_OPCODE, LABEL_OPCODE, TABLE_OPCODE, TERMINATE_OPCODE,
+};
+
+/// Where entries are inserted relative to a [`Position`] (the BeforeOrAfter parameter of Insert() and Label()).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    /// Before the position, or at the start of the script without one.
+    Before,
+    /// After the position, or at the end of the script without one.
+    After,
+}
+
+/// Identifies an entry of a [`ScriptWriter`]. Positions remain valid as other entries are inserted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Position(u32);
+
+/// Checks applied to the entry points of DISPATCH and DISPATCH_2 entries.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DispatchPolicy {
+    /// The script may execute in 32-bit mode, e.g. from a 32-bit PEI phase on resume, so entry points must be below
+    /// 4GB.
+    #[default]
+    Below4Gb,
+    /// The script always executes in 64-bit mode, so entry points may be anywhere.
+    Any,
+}
+
+/// Builds a boot script table.
+///
+/// ## Example
+/// ```
+/// use mu_pi::boot_script::{self, Placement, ScriptTable, ScriptWriter};
+///
+/// let mut writer = ScriptWriter::new();
+/// writer.append(boot_script::stall(100)).unwrap();
+/// let label = writer.insert_label(Placement::Before, None, "Start").unwrap();
+/// writer.insert(Placement::After, Some(label), boot_script::dispatch(0x1000)).unwrap();
+///
+/// let bytes = writer.to_bytes().unwrap();
+/// let table = ScriptTable::parse(&bytes).unwrap();
+/// assert_eq!(table.entries().count(), 4);
+/// assert_eq!(table.find_label("Start"), Some(0));
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct ScriptWriter {
+    entries: Vec<(Position, ScriptEntry)>,
+    next_position: u32,
+    dispatch_policy: DispatchPolicy,
+}
+
+impl ScriptWriter {
+    /// Creates an empty script, with [`DispatchPolicy::Below4Gb`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the checks applied to dispatch entries added from now on.
+    pub fn with_dispatch_policy(mut self, dispatch_policy: DispatchPolicy) -> Self {
+        self.dispatch_policy = dispatch_policy;
+        self
+    }
+
+    /// Appends `entry` to the end of the script (Write()), returning its position.
+    pub fn append(&mut self, entry: ScriptEntry) -> Result<Position, BootScriptError> {
+        self.insert(Placement::After, None, entry)
+    }
+
+    /// Inserts `entry` before or after the entry at `position`, or at the start or end of the script without a
+    /// position (Insert()), returning the position of the new entry.
+    pub fn insert(
+        &mut self,
+        placement: Placement,
+        position: Option<Position>,
+        entry: ScriptEntry,
+    ) -> Result<Position, BootScriptError> {
+        self.check(&entry)?;
+        let index = match (placement, position) {
+            (Placement::Before, None) => 0,
+            (Placement::After, None) => self.entries.len(),
+            (Placement::Before, Some(position)) => self.index(position)?,
+            (Placement::After, Some(position)) => self.index(position)? + 1,
+        };
+        let position = Position(self.next_position);
+        self.next_position += 1;
+        self.entries.insert(index, (position, entry));
+        Ok(position)
+    }
+
+    /// Returns the position of the label named `name`, inserting the label before or after `position` if the
+    /// script does not have one (Label() with IsLabel set).
+    pub fn insert_label(
+        &mut self,
+        placement: Placement,
+        position: Option<Position>,
+        name: &str,
+    ) -> Result<Position, BootScriptError> {
+        match self.find_label(name) {
+            Some(position) => Ok(position),
+            None => self.insert(placement, position, label(name)?),
+        }
+    }
+
+    /// Returns the position of the label named `name`.
+    pub fn find_label(&self, name: &str) -> Option<Position> {
+        self.entries.iter().find(|(_, entry)| label_name(entry) == Some(name.as_bytes())).map(|(position, _)| *position)
+    }
+
+    /// Returns the entries of the script in order, without the table header and terminate entries.
+    pub fn entries(&self) -> impl Iterator<Item = &ScriptEntry> {
+        self.entries.iter().map(|(_, entry)| entry)
+    }
+
+    /// Returns the length in bytes of the table image, including the table header and terminate entries.
+    pub fn table_length(&self) -> Result<u32, BootScriptError> {
+        let length = self.entries().map(|entry| entry.as_bytes().len()).sum::<usize>()
+            + size_of::<TableHeader>()
+            + size_of::<Terminate>();
+        u32::try_from(length).map_err(|_| BootScriptError::TableTooLong)
+    }
+
+    /// Returns the table image: the table header, the entries and the terminate entry.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, BootScriptError> {
+        let table_length = self.table_length()?;
+        let mut bytes = Vec::with_capacity(table_length as usize);
+        bytes.extend_from_slice(table_header(table_length).as_bytes());
+        self.entries().for_each(|entry| bytes.extend_from_slice(entry.as_bytes()));
+        bytes.extend_from_slice(terminate().as_bytes());
+        Ok(bytes)
+    }
+
+    fn index(&self, position: Position) -> Result<usize, BootScriptError> {
+        self.entries.iter().position(|(p, _)| *p == position).ok_or(BootScriptError::InvalidPosition)
+    }
+
+    fn check(&self, entry: &ScriptEntry) -> Result<(), BootScriptError> {
+        match entry.opcode() {
+            opcode @ (TABLE_OPCODE | TERMINATE_OPCODE) => Err(BootScriptError::ReservedOpcode(opcode)),
+            DISPATCH_OPCODE | DISPATCH_2_OPCODE if self.dispatch_policy == DispatchPolicy::Below4Gb => {
+                // The entry point immediately follows the entry header in both dispatch entries.
+                let entry_point = u64::from_le_bytes(entry.as_bytes()[3..11].try_into().unwrap());
+                if entry_point > u32::MAX as u64 {
+                    Err(BootScriptError::DispatchAbove4Gb(entry_point))?;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// Returns the name of a label entry, without its null terminator.
+fn label_name(entry: &ScriptEntry) -> Option<&[u8]> {
+    let bytes = entry.as_bytes();
+    (entry.opcode() == LABEL_OPCODE).then(|| &bytes[7..bytes.len() - 1])
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    use super::{DispatchPolicy, Placement, Position, ScriptWriter};
+    use crate::boot_script::{self, BootScriptError, Entry, ScriptTable, Width};
+
+    fn labels(bytes: &[u8]) -> Vec<&str> {
+        ScriptTable::parse(bytes)
+            .unwrap()
+            .entries()
+            .filter_map(|entry| match entry {
+                Entry::Label { label, .. } => Some(label),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn written_table_should_round_trip() {
+        let mut writer = ScriptWriter::new();
+        let entries = [
+            boot_script::io_write(Width::Uint8, 0x80, 1, &[0x55]).unwrap(),
+            boot_script::mem_read_write(Width::Uint32, 0xfed1f404, 0x4, 0xfffffffb).unwrap(),
+            boot_script::pci_config_write(Width::Uint16, 0x001f0004, 1, &[0x07, 0x00]).unwrap(),
+            boot_script::mem_poll(Width::Uint32, 0xfed40044, 1, 1, 10, 100).unwrap(),
+            boot_script::information(b"silicon").unwrap(),
+            boot_script::dispatch2(0xfff00000, 0x1000),
+        ];
+        for entry in entries.iter().cloned() {
+            writer.append(entry).unwrap();
+        }
+
+        let bytes = writer.to_bytes().unwrap();
+        assert_eq!(bytes.len(), writer.table_length().unwrap() as usize);
+        let table = ScriptTable::parse(&bytes).unwrap();
+        assert_eq!(table.len(), bytes.len());
+        let decoded: Vec<u16> = table.entries().map(|entry| entry.opcode()).collect();
+        let expected: Vec<u16> =
+            entries.iter().map(|entry| entry.opcode()).chain([boot_script::TERMINATE_OPCODE]).collect();
+        assert_eq!(decoded, expected);
+        assert!(writer.entries().eq(entries.iter()));
+
+        let empty = ScriptWriter::new().to_bytes().unwrap();
+        assert!(ScriptTable::parse(&empty).unwrap().is_empty());
+    }
+
+    #[test]
+    fn entries_should_be_inserted_relative_to_labels() {
+        let mut writer = ScriptWriter::new();
+        let silicon = writer.insert_label(Placement::After, None, "Silicon").unwrap();
+        let platform = writer.insert_label(Placement::After, None, "Platform").unwrap();
+        // An existing label is found, not duplicated.
+        assert_eq!(writer.insert_label(Placement::Before, None, "Silicon"), Ok(silicon));
+
+        // Entries after a label go in the order they are inserted when each is inserted after the previous one.
+        let first = writer.insert(Placement::After, Some(silicon), boot_script::stall(1)).unwrap();
+        writer.insert(Placement::After, Some(first), boot_script::stall(2)).unwrap();
+        // Entries before a label go immediately before it.
+        writer.insert(Placement::Before, Some(platform), boot_script::stall(3)).unwrap();
+        writer.insert(Placement::After, Some(platform), boot_script::stall(4)).unwrap();
+        writer.insert(Placement::Before, None, boot_script::stall(0)).unwrap();
+        writer.insert_label(Placement::After, None, "End").unwrap();
+
+        let bytes = writer.to_bytes().unwrap();
+        assert_eq!(labels(&bytes), ["Silicon", "Platform", "End"]);
+        let order: Vec<u64> = ScriptTable::parse(&bytes)
+            .unwrap()
+            .entries()
+            .map(|entry| match entry {
+                Entry::Stall(stall) => stall.duration,
+                Entry::Label { label: "Silicon", .. } => 100,
+                Entry::Label { label: "Platform", .. } => 200,
+                Entry::Label { .. } => 300,
+                _ => u64::MAX,
+            })
+            .collect();
+        assert_eq!(order, [0, 100, 1, 2, 3, 200, 4, 300, u64::MAX]);
+        assert_eq!(writer.find_label("Platform"), Some(platform));
+        assert_eq!(writer.find_label("Missing"), None);
+    }
+
+    #[test]
+    fn invalid_entries_should_be_rejected() {
+        let mut writer = ScriptWriter::new();
+        assert_eq!(
+            writer.insert(Placement::After, Some(Position(7)), boot_script::stall(1)),
+            Err(BootScriptError::InvalidPosition)
+        );
+        assert_eq!(
+            writer.append(boot_script::terminate()),
+            Err(BootScriptError::ReservedOpcode(boot_script::TERMINATE_OPCODE))
+        );
+        assert_eq!(
+            writer.append(boot_script::table_header(0)),
+            Err(BootScriptError::ReservedOpcode(boot_script::TABLE_OPCODE))
+        );
+        assert_eq!(
+            writer.append(boot_script::dispatch2(0x1_0000_0000, 0)),
+            Err(BootScriptError::DispatchAbove4Gb(0x1_0000_0000))
+        );
+        assert_eq!(
+            writer.append(boot_script::dispatch(0x1_0000_0000)),
+            Err(BootScriptError::DispatchAbove4Gb(0x1_0000_0000))
+        );
+        assert!(writer.append(boot_script::dispatch2(0xffff_ffff, 0x1_0000_0000)).is_ok());
+        assert_eq!(writer.insert_label(Placement::After, None, "bad\0"), Err(BootScriptError::InvalidLabel));
+
+        let mut writer = ScriptWriter::new().with_dispatch_policy(DispatchPolicy::Any);
+        assert!(writer.append(boot_script::dispatch2(0x1_0000_0000, 0)).is_ok());
+        assert_eq!(writer.entries().count(), 1);
+    }
+}
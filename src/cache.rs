@@ -0,0 +1,181 @@
+//! Cache Maintenance
+//!
+//! Flushes and invalidates CPU caches, e.g. before handing a buffer to a device that does not snoop the caches, or
+//! after writing code to memory.
+//!
+//! On x86_64, [`X64CacheMaintenance`] uses CLFLUSH and WBINVD. Other targets use [`NullCacheMaintenance`], whose
+//! operations do nothing. [`DefaultCacheMaintenance`] is whichever applies to the target.
+//!
+//! See <https://github.com/tianocore/edk2/blob/master/MdePkg/Include/Library/CacheMaintenanceLib.h>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::sync::atomic::{fence, Ordering};
+
+/// Cache line size assumed when the CPU does not report one.
+pub const DEFAULT_CACHE_LINE_SIZE: usize = 64;
+
+/// Cache maintenance operations.
+pub trait CacheMaintenance {
+    /// Writes back and invalidates the data cache lines covering `length` bytes at `base`.
+    ///
+    /// # Safety
+    /// The range must be mapped.
+    unsafe fn flush_range(&self, base: u64, length: usize);
+
+    /// Invalidates the data cache lines covering `length` bytes at `base`. Where the CPU cannot invalidate a range
+    /// without writing it back, the lines are written back as well.
+    ///
+    /// # Safety
+    /// The range must be mapped, and any data in the caches that has not been written back may be lost.
+    unsafe fn invalidate_range(&self, base: u64, length: usize);
+
+    /// Writes back and invalidates the entire data cache.
+    ///
+    /// # Safety
+    /// Must be called at a privilege level allowed to do so.
+    unsafe fn write_back_invalidate_all(&self);
+}
+
+/// Cache maintenance that does nothing, for targets whose caches are coherent with all agents of interest or for
+/// which no implementation exists.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NullCacheMaintenance;
+
+impl CacheMaintenance for NullCacheMaintenance {
+    unsafe fn flush_range(&self, _base: u64, _length: usize) {}
+
+    unsafe fn invalidate_range(&self, _base: u64, _length: usize) {}
+
+    unsafe fn write_back_invalidate_all(&self) {}
+}
+
+/// Cache maintenance with CLFLUSH and WBINVD.
+#[cfg(target_arch = "x86_64")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct X64CacheMaintenance;
+
+#[cfg(target_arch = "x86_64")]
+impl X64CacheMaintenance {
+    /// Returns the CLFLUSH line size reported by CPUID, or [`DEFAULT_CACHE_LINE_SIZE`] if it is not a power of two.
+    pub fn cache_line_size() -> usize {
+        //Safety: CPUID leaf 1 is available on every x86_64 CPU.
+        let ebx = unsafe { core::arch::x86_64::__cpuid(1) }.ebx;
+        match ((ebx >> 8) & 0xff) as usize * 8 {
+            size if size.is_power_of_two() => size,
+            _ => DEFAULT_CACHE_LINE_SIZE,
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+impl CacheMaintenance for X64CacheMaintenance {
+    unsafe fn flush_range(&self, base: u64, length: usize) {
+        for line in cache_lines(base, length, Self::cache_line_size()).into_iter().flatten() {
+            core::arch::asm!("clflush [{}]", in(reg) line, options(nostack, preserves_flags));
+        }
+        fence(Ordering::SeqCst);
+    }
+
+    unsafe fn invalidate_range(&self, base: u64, length: usize) {
+        // x86 only invalidates a range by writing it back; INVD discards the entire cache.
+        self.flush_range(base, length)
+    }
+
+    unsafe fn write_back_invalidate_all(&self) {
+        core::arch::asm!("wbinvd", options(nostack, preserves_flags));
+    }
+}
+
+/// The cache maintenance of the target.
+#[cfg(target_arch = "x86_64")]
+pub type DefaultCacheMaintenance = X64CacheMaintenance;
+
+/// The cache maintenance of the target.
+#[cfg(not(target_arch = "x86_64"))]
+pub type DefaultCacheMaintenance = NullCacheMaintenance;
+
+/// Returns the address of each cache line of `line_size` bytes covering `length` bytes at `base`, or `None` if
+/// `line_size` is not a power of two.
+pub fn cache_lines(base: u64, length: usize, line_size: usize) -> Option<impl Iterator<Item = u64>> {
+    if !line_size.is_power_of_two() {
+        return None;
+    }
+    let start = base & !(line_size as u64 - 1);
+    let end = if length == 0 { start } else { base.saturating_add(length as u64) };
+    Some((start..end).step_by(line_size))
+}
+
+/// Writes back and invalidates the data cache lines covering `length` bytes at `base`.
+///
+/// # Safety
+/// See [`CacheMaintenance::flush_range`].
+pub unsafe fn flush_cache_range(base: u64, length: usize) {
+    DefaultCacheMaintenance::default().flush_range(base, length)
+}
+
+/// Makes code written to `length` bytes at `base` visible to instruction fetch.
+///
+/// The x86 instruction cache is coherent with the data cache, so this only orders the writes before the code is
+/// executed. Other targets have no implementation yet and do the same.
+///
+/// # Safety
+/// The range must be mapped.
+pub unsafe fn invalidate_instruction_cache(_base: u64, _length: usize) {
+    fence(Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    use alloc::{vec, vec::Vec};
+
+    use super::{cache_lines, flush_cache_range, invalidate_instruction_cache, CacheMaintenance, NullCacheMaintenance};
+
+    // Flushes a buffer handed to a device, as a driver would.
+    fn prepare_for_device(cache: &impl CacheMaintenance, buffer: &[u8]) {
+        unsafe {
+            cache.flush_range(buffer.as_ptr() as u64, buffer.len());
+            cache.invalidate_range(buffer.as_ptr() as u64, buffer.len());
+        }
+    }
+
+    #[test]
+    fn null_maintenance_should_leave_memory_unchanged() {
+        let buffer = vec![0x5au8; 0x1000];
+        prepare_for_device(&NullCacheMaintenance, &buffer);
+        unsafe { NullCacheMaintenance.write_back_invalidate_all() };
+        assert!(buffer.iter().all(|byte| *byte == 0x5a));
+    }
+
+    #[test]
+    fn cache_lines_should_cover_range() {
+        let lines = |base, length, line_size| cache_lines(base, length, line_size).unwrap().collect::<Vec<_>>();
+        assert_eq!(lines(0x1000, 0x40, 0x40), [0x1000]);
+        assert_eq!(lines(0x103f, 2, 0x40), [0x1000, 0x1040]);
+        assert_eq!(lines(0x1010, 0x80, 0x40), [0x1000, 0x1040, 0x1080]);
+        assert_eq!(lines(0x1010, 0, 0x40), []);
+        assert_eq!(lines(u64::MAX - 0x3f, 0x100, 0x40), [u64::MAX - 0x3f]);
+    }
+
+    #[test]
+    fn cache_lines_should_reject_invalid_line_sizes() {
+        assert!(cache_lines(0x1000, 0x40, 0).is_none());
+        assert!(cache_lines(0x1000, 0x40, 0x18).is_none());
+    }
+
+    #[test]
+    fn flush_should_preserve_contents() {
+        let buffer: Vec<u8> = (0..=255).cycle().take(0x1000).collect();
+        unsafe {
+            flush_cache_range(buffer.as_ptr() as u64 + 3, buffer.len() - 3);
+            invalidate_instruction_cache(buffer.as_ptr() as u64, buffer.len());
+        }
+        assert!(buffer.iter().enumerate().all(|(index, byte)| *byte == index as u8));
+    }
+}
@@ -0,0 +1,568 @@
+//! UEFI Capsules
+//!
+//! The capsule header (EFI_CAPSULE_HEADER) and its flags, and a parser that validates a capsule before its body is
+//! used. Capsules are passed to UpdateCapsule() by the operating system and may be crafted by an attacker, so
+//! [`Capsule::parse`] checks every header field against the buffer rather than trusting it.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/08_Services_Runtime_Services.html#update-capsule>
+//!
+//! ## Example
+//! ```
+//! use mu_pi::capsule::{Capsule, CapsuleFlags};
+//!
+//! # let mut bytes = vec![0u8; 0x30];
+//! # bytes[16] = 28;
+//! # bytes[20..24].copy_from_slice(&0x10000u32.to_le_bytes());
+//! # bytes[24] = 0x30;
+//! let capsule = Capsule::parse(&bytes).unwrap();
+//! assert!(capsule.flags().contains(CapsuleFlags::PERSIST_ACROSS_RESET));
+//! assert_eq!(capsule.body().len(), 0x30 - 28);
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+extern crate alloc;
+
+use alloc::{vec, vec::Vec};
+use core::{
+    fmt,
+    mem::size_of,
+    ops::{BitAnd, BitOr, BitOrAssign, Not},
+    ptr,
+};
+
+use r_efi::efi;
+
+use crate::hob::{self, Hob, HobList};
+
+pub mod block;
+pub mod fmp;
+pub mod result;
+
+/// The header at the start of every capsule (EFI_CAPSULE_HEADER).
+pub type Header = efi::CapsuleHeader;
+
+/// A set of `CAPSULE_FLAGS_*` bits of a capsule header.
+///
+/// Bits 15:0 are defined by the capsule type identified by the capsule GUID, bits 18:16 are defined by the UEFI
+/// specification, and the remaining bits are reserved.
+#[repr(transparent)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct CapsuleFlags(u32);
+
+impl CapsuleFlags {
+    /// The capsule is kept in memory across a system reset, to be processed on the next boot.
+    pub const PERSIST_ACROSS_RESET: Self = Self(efi::CAPSULE_FLAGS_PERSIST_ACROSS_RESET);
+    /// The capsule is added to the EFI System Table after it is processed. Requires [`Self::PERSIST_ACROSS_RESET`].
+    pub const POPULATE_SYSTEM_TABLE: Self = Self(efi::CAPSULE_FLAGS_POPULATE_SYSTEM_TABLE);
+    /// UpdateCapsule() resets the system once the capsule is saved. Requires [`Self::PERSIST_ACROSS_RESET`].
+    pub const INITIATE_RESET: Self = Self(efi::CAPSULE_FLAGS_INITIATE_RESET);
+    /// The bits defined by the capsule type.
+    pub const PLATFORM_MASK: Self = Self(0x0000_FFFF);
+    /// Every bit that is not reserved.
+    pub const ALL: Self = Self(
+        Self::PLATFORM_MASK.0
+            | efi::CAPSULE_FLAGS_PERSIST_ACROSS_RESET
+            | efi::CAPSULE_FLAGS_POPULATE_SYSTEM_TABLE
+            | efi::CAPSULE_FLAGS_INITIATE_RESET,
+    );
+
+    const NAMES: &'static [(Self, &'static str)] = &[
+        (Self::PERSIST_ACROSS_RESET, "PERSIST_ACROSS_RESET"),
+        (Self::POPULATE_SYSTEM_TABLE, "POPULATE_SYSTEM_TABLE"),
+        (Self::INITIATE_RESET, "INITIATE_RESET"),
+    ];
+
+    /// Returns the empty set of flags.
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Converts raw flags, keeping reserved bits.
+    pub const fn from_bits_retain(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    /// Returns the raw flags.
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Returns the bits defined by the capsule type.
+    pub const fn platform_bits(self) -> u16 {
+        (self.0 & Self::PLATFORM_MASK.0) as u16
+    }
+
+    /// Returns true if every flag of `other` is set.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns true if any flag of `other` is set.
+    pub const fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// Checks the flags as UpdateCapsule() does: reserved bits must be clear, and [`Self::POPULATE_SYSTEM_TABLE`]
+    /// and [`Self::INITIATE_RESET`] both require [`Self::PERSIST_ACROSS_RESET`].
+    pub fn validate(self) -> Result<(), CapsuleError> {
+        if !Self::ALL.contains(self) {
+            Err(CapsuleError::ReservedFlags(self))?;
+        }
+        if self.intersects(Self::POPULATE_SYSTEM_TABLE | Self::INITIATE_RESET)
+            && !self.contains(Self::PERSIST_ACROSS_RESET)
+        {
+            Err(CapsuleError::InconsistentFlags(self))?;
+        }
+        Ok(())
+    }
+}
+
+impl From<CapsuleFlags> for u32 {
+    fn from(value: CapsuleFlags) -> Self {
+        value.0
+    }
+}
+
+impl BitOr for CapsuleFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for CapsuleFlags {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAnd for CapsuleFlags {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl Not for CapsuleFlags {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self(!self.0)
+    }
+}
+
+impl fmt::Debug for CapsuleFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CapsuleFlags(")?;
+        let mut separator = "";
+        for (_, name) in Self::NAMES.iter().filter(|(flag, _)| self.contains(*flag)) {
+            write!(f, "{separator}{name}")?;
+            separator = " | ";
+        }
+        let other = self.0 & !Self::NAMES.iter().fold(0, |mask, (flag, _)| mask | flag.0);
+        if other != 0 {
+            write!(f, "{separator}{other:#x}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Errors reported when parsing a capsule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CapsuleError {
+    /// The buffer of the given size is too small for a capsule header.
+    BufferTooSmall(usize),
+    /// The header size is smaller than a capsule header or larger than the capsule image.
+    InvalidHeaderSize(u32),
+    /// The capsule image size is larger than the buffer.
+    InvalidImageSize(u32),
+    /// Reserved flags are set.
+    ReservedFlags(CapsuleFlags),
+    /// POPULATE_SYSTEM_TABLE or INITIATE_RESET is set without PERSIST_ACROSS_RESET.
+    InconsistentFlags(CapsuleFlags),
+    /// The maximum block size of a block descriptor list is zero.
+    InvalidBlockSize(u64),
+    /// A block descriptor list cannot hold a data block and a continuation pointer.
+    InvalidListCapacity(usize),
+    /// The data block at `address` extends past the end of the address space.
+    InvalidBlock { address: u64, length: u64 },
+    /// The continuation pointer to the given address leads back to a block descriptor list already walked.
+    DescriptorCycle(u64),
+    /// The capsules of a block descriptor list add up to more than the given limit.
+    TooLarge(u64),
+    /// A block descriptor list has more than the given number of descriptors.
+    TooManyDescriptors(usize),
+    /// The memory at the given physical address could not be read.
+    ReadFailed(u64),
+    /// The capsule HOB range is not covered by a system memory resource descriptor HOB.
+    NotInSystemMemory { base: u64, length: u64 },
+}
+
+impl fmt::Display for CapsuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CapsuleError::BufferTooSmall(size) => write!(f, "capsule of {size:#x} bytes is smaller than its header"),
+            CapsuleError::InvalidHeaderSize(size) => write!(f, "invalid capsule header size {size:#x}"),
+            CapsuleError::InvalidImageSize(size) => write!(f, "capsule image size {size:#x} exceeds the buffer"),
+            CapsuleError::ReservedFlags(flags) => write!(f, "capsule has reserved flags set: {flags:?}"),
+            CapsuleError::InconsistentFlags(flags) => {
+                write!(f, "capsule flags {flags:?} require PERSIST_ACROSS_RESET")
+            }
+            CapsuleError::InvalidBlockSize(size) => write!(f, "invalid capsule block size {size:#x}"),
+            CapsuleError::InvalidListCapacity(capacity) => {
+                write!(f, "capsule block descriptor list capacity {capacity} is too small")
+            }
+            CapsuleError::InvalidBlock { address, length } => {
+                write!(f, "capsule block at {address:#x} of {length:#x} bytes wraps around")
+            }
+            CapsuleError::DescriptorCycle(address) => {
+                write!(f, "capsule block descriptor list at {address:#x} is part of a cycle")
+            }
+            CapsuleError::TooLarge(limit) => write!(f, "capsules exceed the limit of {limit:#x} bytes"),
+            CapsuleError::TooManyDescriptors(limit) => {
+                write!(f, "capsule block descriptor lists exceed the limit of {limit} descriptors")
+            }
+            CapsuleError::ReadFailed(address) => write!(f, "cannot read capsule memory at {address:#x}"),
+            CapsuleError::NotInSystemMemory { base, length } => {
+                write!(f, "capsule at {base:#x} of {length:#x} bytes is not in system memory")
+            }
+        }
+    }
+}
+
+impl From<CapsuleError> for efi::Status {
+    fn from(_: CapsuleError) -> Self {
+        efi::Status::INVALID_PARAMETER
+    }
+}
+
+/// A validated capsule.
+#[derive(Debug, Clone, Copy)]
+pub struct Capsule<'a> {
+    header: Header,
+    image: &'a [u8],
+}
+
+impl<'a> Capsule<'a> {
+    /// Parses the capsule at the start of `bytes`, which may be followed by other data.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, CapsuleError> {
+        if bytes.len() < size_of::<Header>() {
+            Err(CapsuleError::BufferTooSmall(bytes.len()))?;
+        }
+        //Safety: bytes holds at least a header, which is read without assuming alignment.
+        let header = unsafe { ptr::read_unaligned(bytes.as_ptr() as *const Header) };
+        let image_size = header.capsule_image_size;
+        if image_size as usize > bytes.len() {
+            Err(CapsuleError::InvalidImageSize(image_size))?;
+        }
+        if (header.header_size as usize) < size_of::<Header>() || header.header_size > image_size {
+            Err(CapsuleError::InvalidHeaderSize(header.header_size))?;
+        }
+        CapsuleFlags(header.flags).validate()?;
+        Ok(Self { header, image: &bytes[..image_size as usize] })
+    }
+
+    /// Returns the capsule header.
+    pub fn header(&self) -> Header {
+        self.header
+    }
+
+    /// Returns the GUID identifying the capsule type.
+    pub fn guid(&self) -> efi::Guid {
+        self.header.capsule_guid
+    }
+
+    /// Returns the capsule flags.
+    pub fn flags(&self) -> CapsuleFlags {
+        CapsuleFlags(self.header.flags)
+    }
+
+    /// Returns the capsule image: the header, including any bytes past the EFI_CAPSULE_HEADER structure, and the body.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.image
+    }
+
+    /// Returns the bytes of the capsule image after the header.
+    pub fn body(&self) -> &'a [u8] {
+        &self.image[self.header.header_size as usize..]
+    }
+}
+
+/// A validated capsule that owns its image, such as a capsule read from physical memory.
+#[derive(Debug, Clone)]
+pub struct OwnedCapsule {
+    header: Header,
+    image: Vec<u8>,
+}
+
+impl OwnedCapsule {
+    /// Parses the capsule at the start of `bytes`, dropping any bytes after the capsule image.
+    pub fn parse(mut bytes: Vec<u8>) -> Result<Self, CapsuleError> {
+        let capsule = Capsule::parse(&bytes)?;
+        let (header, image_size) = (capsule.header, capsule.image.len());
+        bytes.truncate(image_size);
+        Ok(Self { header, image: bytes })
+    }
+
+    /// Returns the capsule, to access its header and body.
+    pub fn as_capsule(&self) -> Capsule<'_> {
+        Capsule { header: self.header, image: &self.image }
+    }
+
+    /// Returns the GUID identifying the capsule type.
+    pub fn guid(&self) -> efi::Guid {
+        self.header.capsule_guid
+    }
+
+    /// Returns the capsule image.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.image
+    }
+}
+
+/// Returns the capsules described by the UEFI capsule HOBs of `list`, as PEI publishes them after coalescing the
+/// capsules passed to UpdateCapsule() before the reset.
+///
+/// Each capsule HOB must be covered by a system memory resource descriptor HOB (see [`hob::system_memory_covers`]).
+/// The capsule is read from physical memory with `read`, which fills the buffer with the memory at the address or
+/// fails, typically with [`CapsuleError::ReadFailed`]. The header is read first, so that no more than the capsule
+/// image size, itself checked against the HOB length, is read; the image is then parsed with [`Capsule::parse`].
+/// An error for one HOB does not end the iteration.
+pub fn from_hob<'a>(
+    list: &'a HobList<'a>,
+    read: impl Fn(u64, &mut [u8]) -> Result<(), CapsuleError> + 'a,
+) -> impl Iterator<Item = Result<OwnedCapsule, CapsuleError>> + 'a {
+    list.iter().filter_map(move |hob| match hob {
+        Hob::Capsule(capsule) => {
+            Some(read_capsule(list, hob::physical_address(capsule.base_address), capsule.length, &read))
+        }
+        _ => None,
+    })
+}
+
+fn read_capsule(
+    list: &HobList,
+    base: u64,
+    length: u64,
+    read: &impl Fn(u64, &mut [u8]) -> Result<(), CapsuleError>,
+) -> Result<OwnedCapsule, CapsuleError> {
+    if !hob::system_memory_covers(list, base, length) {
+        Err(CapsuleError::NotInSystemMemory { base, length })?;
+    }
+    if length < size_of::<Header>() as u64 {
+        Err(CapsuleError::BufferTooSmall(length as usize))?;
+    }
+    let mut header = [0; size_of::<Header>()];
+    read(base, &mut header)?;
+    let image_size = u32::from_le_bytes(header[24..28].try_into().unwrap());
+    if image_size as u64 > length {
+        Err(CapsuleError::InvalidImageSize(image_size))?;
+    }
+    let mut image = vec![0; image_size as usize];
+    read(base, &mut image)?;
+    OwnedCapsule::parse(image)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    use alloc::{format, vec::Vec};
+    use core::mem::size_of;
+
+    use r_efi::efi;
+
+    use super::{fmp, from_hob, Capsule, CapsuleError, CapsuleFlags, Header, OwnedCapsule};
+    use crate::hob::{self, Hob, HobList};
+
+    const GUID: efi::Guid =
+        efi::Guid::from_fields(0x6dcbd5ed, 0xe82d, 0x4c44, 0xbd, 0xa1, &[0x71, 0x94, 0x19, 0x9a, 0xd9, 0x2a]);
+
+    fn image(header_size: u32, flags: u32, image_size: u32, length: usize) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(length);
+        bytes.extend_from_slice(GUID.as_bytes());
+        bytes.extend_from_slice(&header_size.to_le_bytes());
+        bytes.extend_from_slice(&flags.to_le_bytes());
+        bytes.extend_from_slice(&image_size.to_le_bytes());
+        bytes.extend((bytes.len()..length).map(|index| index as u8));
+        bytes
+    }
+
+    #[test]
+    fn valid_capsule_should_parse() {
+        assert_eq!(size_of::<Header>(), 28);
+        let flags = CapsuleFlags::PERSIST_ACROSS_RESET | CapsuleFlags::INITIATE_RESET;
+        // A header with extra bytes, followed by data that is not part of the capsule.
+        let bytes = image(32, flags.bits() | 0x1234, 0x40, 0x50);
+        let capsule = Capsule::parse(&bytes).unwrap();
+        assert_eq!(capsule.guid(), GUID);
+        assert_eq!(capsule.flags(), flags | CapsuleFlags::from_bits_retain(0x1234));
+        assert_eq!(capsule.flags().platform_bits(), 0x1234);
+        assert_eq!(capsule.as_bytes(), &bytes[..0x40]);
+        assert_eq!(capsule.body(), &bytes[32..0x40]);
+
+        // A capsule with an empty body.
+        let bytes = image(28, 0, 28, 28);
+        assert!(Capsule::parse(&bytes).unwrap().body().is_empty());
+    }
+
+    #[test]
+    fn malformed_headers_should_be_rejected() {
+        let bytes = image(28, 0, 0x40, 0x40);
+        for length in 0..28 {
+            assert_eq!(Capsule::parse(&bytes[..length]).unwrap_err(), CapsuleError::BufferTooSmall(length));
+        }
+        for length in 28..0x40 {
+            assert_eq!(Capsule::parse(&bytes[..length]).unwrap_err(), CapsuleError::InvalidImageSize(0x40));
+        }
+        for header_size in [0, 27, 0x41, u32::MAX] {
+            let bytes = image(header_size, 0, 0x40, 0x40);
+            assert_eq!(Capsule::parse(&bytes).unwrap_err(), CapsuleError::InvalidHeaderSize(header_size));
+        }
+        // An image size smaller than the header.
+        let bytes = image(28, 0, 20, 0x40);
+        assert_eq!(Capsule::parse(&bytes).unwrap_err(), CapsuleError::InvalidHeaderSize(28));
+        let bytes = image(28, 0, u32::MAX, 0x40);
+        assert_eq!(Capsule::parse(&bytes).unwrap_err(), CapsuleError::InvalidImageSize(u32::MAX));
+    }
+
+    #[test]
+    fn inconsistent_flags_should_be_rejected() {
+        let persist = CapsuleFlags::PERSIST_ACROSS_RESET;
+        for flags in [CapsuleFlags::POPULATE_SYSTEM_TABLE, CapsuleFlags::INITIATE_RESET] {
+            assert_eq!(flags.validate(), Err(CapsuleError::InconsistentFlags(flags)));
+            assert_eq!((flags | persist).validate(), Ok(()));
+            let bytes = image(28, flags.bits(), 28, 28);
+            assert_eq!(Capsule::parse(&bytes).unwrap_err(), CapsuleError::InconsistentFlags(flags));
+        }
+        let reserved = CapsuleFlags::from_bits_retain(0x0008_0000) | persist;
+        assert_eq!(reserved.validate(), Err(CapsuleError::ReservedFlags(reserved)));
+        assert_eq!(CapsuleFlags::from_bits_retain(0xffff).validate(), Ok(()));
+        assert_eq!(format!("{reserved:?}"), "CapsuleFlags(PERSIST_ACROSS_RESET | 0x80000)");
+        assert_eq!(efi::Status::from(CapsuleError::InvalidHeaderSize(0)), efi::Status::INVALID_PARAMETER);
+    }
+
+    #[test]
+    fn random_headers_should_not_panic() {
+        // Headers built from a fixed pseudo-random sequence, biased towards sizes near the buffer length.
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for _ in 0..10_000 {
+            let length = (next() % 0x60) as usize;
+            let random = next();
+            let header_size = if random & 1 == 0 { (random >> 8) as u32 % 0x70 } else { (random >> 8) as u32 };
+            let image_size = if random & 2 == 0 { (random >> 40) as u32 % 0x70 } else { (random >> 32) as u32 };
+            let bytes = image(header_size, next() as u32, image_size, length.max(28));
+            let bytes = &bytes[..length.min(bytes.len())];
+            if let Ok(capsule) = Capsule::parse(bytes) {
+                assert!(capsule.as_bytes().len() <= bytes.len());
+                assert_eq!(capsule.body().len(), capsule.header().capsule_image_size as usize - header_size as usize);
+                assert_eq!(capsule.flags().validate(), Ok(()));
+            }
+        }
+    }
+
+    // An FMP capsule with one version 3 payload item holding a 16-byte image.
+    fn fmp_capsule() -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&[1, 0, 0, 0, 0, 0, 1, 0]);
+        body.extend_from_slice(&16u64.to_le_bytes());
+        body.extend_from_slice(&3u32.to_le_bytes());
+        body.extend_from_slice(GUID.as_bytes());
+        body.extend_from_slice(&[1, 0, 0, 0]);
+        body.extend_from_slice(&16u32.to_le_bytes());
+        body.extend_from_slice(&[0; 4 + 8 + 8]);
+        body.extend(0..16u8);
+        let mut bytes = image(28, efi::CAPSULE_FLAGS_PERSIST_ACROSS_RESET, 28 + body.len() as u32, 28);
+        bytes.extend_from_slice(&body);
+        bytes
+    }
+
+    fn header<T>(r#type: u16) -> hob::header::Hob {
+        hob::header::Hob { r#type, length: size_of::<T>() as u16, reserved: 0 }
+    }
+
+    fn capsule_hob(base_address: hob::EfiPhysicalAddress, length: u64) -> hob::Capsule {
+        hob::Capsule { header: header::<hob::Capsule>(hob::UEFI_CAPSULE), base_address, length }
+    }
+
+    #[test]
+    fn owned_capsule_should_drop_trailing_bytes() {
+        let mut bytes = image(28, 0, 0x30, 0x40);
+        let owned = OwnedCapsule::parse(bytes.clone()).unwrap();
+        assert_eq!(owned.guid(), GUID);
+        assert_eq!(owned.as_capsule().body(), &bytes[28..0x30]);
+        bytes.truncate(0x30);
+        assert_eq!(owned.into_bytes(), bytes);
+        assert_eq!(OwnedCapsule::parse(bytes[..0x20].to_vec()).unwrap_err(), CapsuleError::InvalidImageSize(0x30));
+    }
+
+    #[test]
+    fn capsule_hobs_should_yield_parsed_capsules() {
+        // Physical memory: system memory at 0x8000_0000 holding an FMP capsule and a truncated capsule, and a
+        // capsule in reserved memory.
+        const MEMORY: u64 = 0x8000_0000;
+        let fmp_capsule = fmp_capsule();
+        let mut memory = vec![0u8; 0x3000];
+        memory[..fmp_capsule.len()].copy_from_slice(&fmp_capsule);
+        memory[0x1000..0x1040].copy_from_slice(&image(28, 0, 0x100, 0x40));
+        let read = |address: u64, buffer: &mut [u8]| {
+            let offset = address.checked_sub(MEMORY).ok_or(CapsuleError::ReadFailed(address))? as usize;
+            let bytes = memory.get(offset..offset + buffer.len()).ok_or(CapsuleError::ReadFailed(address))?;
+            buffer.copy_from_slice(bytes);
+            Ok(())
+        };
+
+        let system_memory = hob::ResourceDescriptor {
+            header: header::<hob::ResourceDescriptor>(hob::RESOURCE_DESCRIPTOR),
+            owner: efi::Guid::from_fields(0, 0, 0, 0, 0, &[0; 6]),
+            resource_type: hob::EFI_RESOURCE_SYSTEM_MEMORY,
+            resource_attribute: hob::TESTED_MEMORY_ATTRIBUTES,
+            physical_start: MEMORY as hob::EfiPhysicalAddress,
+            resource_length: 0x2000,
+        };
+        let reserved = hob::ResourceDescriptor {
+            resource_type: hob::EFI_RESOURCE_MEMORY_RESERVED,
+            physical_start: (MEMORY + 0x2000) as hob::EfiPhysicalAddress,
+            ..system_memory
+        };
+        let capsules = [
+            capsule_hob(MEMORY as hob::EfiPhysicalAddress, 0x1000),
+            capsule_hob((MEMORY + 0x1000) as hob::EfiPhysicalAddress, 0x40),
+            capsule_hob((MEMORY + 0x2000) as hob::EfiPhysicalAddress, 0x100),
+            capsule_hob((MEMORY + 0x1800) as hob::EfiPhysicalAddress, 0x10),
+        ];
+        let mut list = HobList::new();
+        list.push(Hob::ResourceDescriptor(&system_memory));
+        list.push(Hob::ResourceDescriptor(&reserved));
+        capsules.iter().for_each(|capsule| list.push(Hob::Capsule(capsule)));
+
+        let results: Vec<_> = from_hob(&list, read).collect();
+        assert_eq!(results.len(), 4);
+        let capsule = results[0].as_ref().unwrap();
+        assert_eq!(capsule.as_capsule().as_bytes(), fmp_capsule);
+        let fmp = fmp::FmpCapsule::from_capsule(&capsule.as_capsule()).unwrap();
+        let payloads: Vec<_> = fmp.payloads().collect();
+        assert_eq!(payloads.len(), 1);
+        assert_eq!(payloads[0].image(), (0..16u8).collect::<Vec<_>>());
+
+        assert_eq!(results[1].as_ref().unwrap_err(), &CapsuleError::InvalidImageSize(0x100));
+        assert_eq!(
+            results[2].as_ref().unwrap_err(),
+            &CapsuleError::NotInSystemMemory { base: MEMORY + 0x2000, length: 0x100 }
+        );
+        assert_eq!(results[3].as_ref().unwrap_err(), &CapsuleError::BufferTooSmall(0x10));
+    }
+}
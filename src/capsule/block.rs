@@ -0,0 +1,462 @@
+//! Capsule Block Descriptors
+//!
+//! UpdateCapsule() receives the capsules it is given twice: as virtual pointers to each capsule header, and as the
+//! physical address of a scatter-gather list of block descriptors (EFI_CAPSULE_BLOCK_DESCRIPTOR) that describes
+//! where the capsules are in physical memory, for use after a reset. Each descriptor is one of:
+//!
+//! | Length   | Address  | Meaning                                                                    |
+//! |----------|----------|----------------------------------------------------------------------------|
+//! | non-zero | any      | A data block: the next `Length` bytes of the capsules are at `Address`.    |
+//! | 0        | non-zero | A continuation pointer: the list continues with the descriptor at `Address`. |
+//! | 0        | 0        | The end of the list.                                                       |
+//!
+//! The capsules are the concatenation of the data blocks in list order. [`BlockListBuilder`] fragments capsules into
+//! descriptor lists, as an update agent does before calling UpdateCapsule(). [`BlockWalker`] reassembles them, as the
+//! PEI capsule coalescing code does after the reset; the lists then come from memory that survived a reset and may be
+//! corrupt, so the walker detects cycles of continuation pointers and limits the size of the capsules and the number
+//! of descriptors.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/08_Services_Runtime_Services.html#update-capsule>
+//!
+//! ## Example
+//! ```
+//! use mu_pi::capsule::block::{Block, BlockListBuilder, BlockWalker};
+//!
+//! // A capsule of 0x1800 bytes at 0x10000, in blocks of at most 0x1000 bytes.
+//! let blocks = BlockListBuilder::new(0x1000).with_range(0x10000, 0x1800).build().unwrap();
+//! assert_eq!(
+//!   blocks,
+//!   [Block::Data { address: 0x10000, length: 0x1000 }, Block::Data { address: 0x11000, length: 0x800 }, Block::End]
+//! );
+//!
+//! // Physical memory holding the descriptor list at 0x2000 and a 4-byte capsule at 0x3000.
+//! let read = |address: u64, buffer: &mut [u8]| {
+//!   match address {
+//!     0x2000 => buffer.copy_from_slice(&Block::Data { address: 0x3000, length: 4 }.to_bytes()),
+//!     0x2010 => buffer.copy_from_slice(&Block::End.to_bytes()),
+//!     0x3000 => buffer.copy_from_slice(b"data"),
+//!     _ => Err(mu_pi::capsule::CapsuleError::ReadFailed(address))?,
+//!   }
+//!   Ok(())
+//! };
+//! assert_eq!(BlockWalker::new(0x2000, 0x1000).walk(read).unwrap(), b"data");
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+extern crate alloc;
+
+use alloc::{collections::BTreeSet, vec::Vec};
+use core::mem::size_of;
+
+use r_efi::efi;
+
+use super::CapsuleError;
+
+/// A block descriptor (EFI_CAPSULE_BLOCK_DESCRIPTOR).
+pub type BlockDescriptor = efi::CapsuleBlockDescriptor;
+
+/// Size in bytes of a block descriptor.
+pub const BLOCK_DESCRIPTOR_SIZE: usize = size_of::<BlockDescriptor>();
+
+/// The number of descriptors a [`BlockWalker`] reads before giving up, unless set otherwise.
+pub const DEFAULT_MAX_DESCRIPTORS: usize = 0x10000;
+
+/// The meaning of a block descriptor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Block {
+    /// `length` bytes of capsule data at `address`. The length must not be zero.
+    Data { address: u64, length: u64 },
+    /// The list continues with the descriptor at the address, which must not be zero.
+    Continuation(u64),
+    /// The end of the list.
+    End,
+}
+
+impl Block {
+    /// Decodes a descriptor as stored in memory.
+    pub fn from_bytes(bytes: &[u8; BLOCK_DESCRIPTOR_SIZE]) -> Self {
+        let length = u64::from_le_bytes(bytes[..8].try_into().unwrap());
+        let address = u64::from_le_bytes(bytes[8..].try_into().unwrap());
+        match (length, address) {
+            (0, 0) => Block::End,
+            (0, address) => Block::Continuation(address),
+            (length, address) => Block::Data { address, length },
+        }
+    }
+
+    /// Encodes the descriptor as stored in memory.
+    pub fn to_bytes(self) -> [u8; BLOCK_DESCRIPTOR_SIZE] {
+        let (length, address) = match self {
+            Block::Data { address, length } => (length, address),
+            Block::Continuation(address) => (0, address),
+            Block::End => (0, 0),
+        };
+        let mut bytes = [0; BLOCK_DESCRIPTOR_SIZE];
+        bytes[..8].copy_from_slice(&length.to_le_bytes());
+        bytes[8..].copy_from_slice(&address.to_le_bytes());
+        bytes
+    }
+}
+
+impl From<BlockDescriptor> for Block {
+    fn from(descriptor: BlockDescriptor) -> Self {
+        // Both members of the union are physical addresses.
+        let address = unsafe { descriptor.data.data_block };
+        let mut bytes = [0; BLOCK_DESCRIPTOR_SIZE];
+        bytes[..8].copy_from_slice(&descriptor.length.to_le_bytes());
+        bytes[8..].copy_from_slice(&address.to_le_bytes());
+        Self::from_bytes(&bytes)
+    }
+}
+
+impl From<Block> for BlockDescriptor {
+    fn from(block: Block) -> Self {
+        let bytes = block.to_bytes();
+        BlockDescriptor {
+            length: u64::from_le_bytes(bytes[..8].try_into().unwrap()),
+            data: efi::CapsuleBlockDescriptorUnion { data_block: u64::from_le_bytes(bytes[8..].try_into().unwrap()) },
+        }
+    }
+}
+
+/// Builds block descriptor lists for capsules in physical memory.
+#[derive(Debug, Clone)]
+pub struct BlockListBuilder {
+    max_block_size: u64,
+    ranges: Vec<(u64, u64)>,
+}
+
+impl BlockListBuilder {
+    /// Starts building a list whose data blocks are at most `max_block_size` bytes.
+    pub fn new(max_block_size: u64) -> Self {
+        Self { max_block_size, ranges: Vec::new() }
+    }
+
+    /// Appends `length` bytes of capsule data that are contiguous in physical memory at `address`, such as a page of
+    /// a capsule buffer. Capsules are appended one after the other; empty ranges are ignored.
+    pub fn with_range(mut self, address: u64, length: u64) -> Self {
+        self.ranges.push((address, length));
+        self
+    }
+
+    /// Builds a single list: the data blocks followed by [`Block::End`].
+    pub fn build(self) -> Result<Vec<Block>, CapsuleError> {
+        let mut blocks = self.data_blocks()?;
+        blocks.push(Block::End);
+        Ok(blocks)
+    }
+
+    /// Builds lists of at most `capacity` descriptors each, such as the descriptors that fit in a page. `place` is
+    /// called with the index of each list, in order, and returns the physical address the list will be written to;
+    /// each list but the last ends with a continuation pointer to the next.
+    ///
+    /// Returns the address and descriptors of each list; the first address is the one passed to UpdateCapsule().
+    pub fn build_lists(
+        self,
+        capacity: usize,
+        mut place: impl FnMut(usize) -> u64,
+    ) -> Result<Vec<(u64, Vec<Block>)>, CapsuleError> {
+        if capacity < 2 {
+            Err(CapsuleError::InvalidListCapacity(capacity))?;
+        }
+        let blocks = self.data_blocks()?;
+        let chunks: Vec<&[Block]> = match blocks.is_empty() {
+            true => alloc::vec![&[]],
+            false => blocks.chunks(capacity - 1).collect(),
+        };
+        let addresses: Vec<u64> = (0..chunks.len()).map(&mut place).collect();
+        Ok(chunks
+            .iter()
+            .enumerate()
+            .map(|(index, chunk)| {
+                let mut list = chunk.to_vec();
+                list.push(addresses.get(index + 1).map_or(Block::End, |next| Block::Continuation(*next)));
+                (addresses[index], list)
+            })
+            .collect())
+    }
+
+    fn data_blocks(&self) -> Result<Vec<Block>, CapsuleError> {
+        if self.max_block_size == 0 {
+            Err(CapsuleError::InvalidBlockSize(0))?;
+        }
+        let mut blocks = Vec::new();
+        for &(address, length) in &self.ranges {
+            if address.checked_add(length).is_none() {
+                Err(CapsuleError::InvalidBlock { address, length })?;
+            }
+            let mut offset = 0;
+            while offset < length {
+                let block_length = (length - offset).min(self.max_block_size);
+                blocks.push(Block::Data { address: address + offset, length: block_length });
+                offset += block_length;
+            }
+        }
+        Ok(blocks)
+    }
+}
+
+/// Reassembles the capsules described by a block descriptor list.
+#[derive(Debug, Clone, Copy)]
+pub struct BlockWalker {
+    list: u64,
+    max_size: u64,
+    max_descriptors: usize,
+}
+
+impl BlockWalker {
+    /// Starts walking the list at physical address `list`, whose capsules may total at most `max_size` bytes.
+    pub fn new(list: u64, max_size: u64) -> Self {
+        Self { list, max_size, max_descriptors: DEFAULT_MAX_DESCRIPTORS }
+    }
+
+    /// Sets the number of descriptors, including continuation pointers and the end, read before giving up.
+    pub fn with_max_descriptors(mut self, max_descriptors: usize) -> Self {
+        self.max_descriptors = max_descriptors;
+        self
+    }
+
+    /// Walks the list, reading physical memory with `read`, and returns the concatenated data blocks.
+    ///
+    /// `read` fills the buffer with the memory at the address, or fails, typically with
+    /// [`CapsuleError::ReadFailed`], if the memory is not readable system memory. Data blocks are only read once the
+    /// size limit has been checked, so a corrupt length cannot cause a large allocation.
+    pub fn walk(
+        self,
+        mut read: impl FnMut(u64, &mut [u8]) -> Result<(), CapsuleError>,
+    ) -> Result<Vec<u8>, CapsuleError> {
+        let mut capsules = Vec::new();
+        // The lists entered so far. Any cycle passes through a continuation pointer, so following one to a list
+        // already entered is the first sign of a cycle.
+        let mut lists = BTreeSet::from([self.list]);
+        let mut address = self.list;
+        for _ in 0..self.max_descriptors {
+            let mut bytes = [0; BLOCK_DESCRIPTOR_SIZE];
+            read(address, &mut bytes)?;
+            match Block::from_bytes(&bytes) {
+                Block::End => return Ok(capsules),
+                Block::Continuation(next) => {
+                    if !lists.insert(next) {
+                        Err(CapsuleError::DescriptorCycle(next))?;
+                    }
+                    address = next;
+                }
+                Block::Data { address: data, length } => {
+                    if data.checked_add(length).is_none() {
+                        Err(CapsuleError::InvalidBlock { address: data, length })?;
+                    }
+                    let start = capsules.len();
+                    let size = (start as u64)
+                        .checked_add(length)
+                        .filter(|size| *size <= self.max_size)
+                        .and_then(|size| usize::try_from(size).ok())
+                        .ok_or(CapsuleError::TooLarge(self.max_size))?;
+                    capsules.resize(size, 0);
+                    read(data, &mut capsules[start..])?;
+                    address = address
+                        .checked_add(BLOCK_DESCRIPTOR_SIZE as u64)
+                        .ok_or(CapsuleError::InvalidBlock { address, length: BLOCK_DESCRIPTOR_SIZE as u64 })?;
+                }
+            }
+        }
+        Err(CapsuleError::TooManyDescriptors(self.max_descriptors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    use super::{Block, BlockDescriptor, BlockListBuilder, BlockWalker, BLOCK_DESCRIPTOR_SIZE};
+    use crate::capsule::{Capsule, CapsuleError};
+
+    // A sparse physical address space made of separately allocated regions.
+    #[derive(Default)]
+    struct FakeMemory {
+        regions: Vec<(u64, Vec<u8>)>,
+    }
+
+    impl FakeMemory {
+        fn write(&mut self, address: u64, bytes: &[u8]) {
+            self.regions.push((address, bytes.to_vec()));
+        }
+
+        fn write_list(&mut self, address: u64, list: &[Block]) {
+            let bytes: Vec<u8> = list.iter().flat_map(|block| block.to_bytes()).collect();
+            self.write(address, &bytes);
+        }
+
+        fn read(&self, address: u64, buffer: &mut [u8]) -> Result<(), CapsuleError> {
+            let (base, bytes) = self
+                .regions
+                .iter()
+                .find(|(base, bytes)| address >= *base && address - base + buffer.len() as u64 <= bytes.len() as u64)
+                .ok_or(CapsuleError::ReadFailed(address))?;
+            let offset = (address - base) as usize;
+            buffer.copy_from_slice(&bytes[offset..offset + buffer.len()]);
+            Ok(())
+        }
+    }
+
+    // A capsule of the given size filled with a counter, with a valid header.
+    fn capsule(size: usize) -> Vec<u8> {
+        let mut capsule: Vec<u8> = (0..size).map(|index| (index % 251) as u8).collect();
+        capsule[16..20].copy_from_slice(&28u32.to_le_bytes());
+        capsule[20..24].copy_from_slice(&0u32.to_le_bytes());
+        capsule[24..28].copy_from_slice(&(size as u32).to_le_bytes());
+        capsule
+    }
+
+    #[test]
+    fn descriptors_should_follow_length_rules() {
+        for (block, length, address) in [
+            (Block::Data { address: 0x1000, length: 0x10 }, 0x10, 0x1000),
+            (Block::Data { address: 0, length: 1 }, 1, 0),
+            (Block::Continuation(0x2000), 0, 0x2000),
+            (Block::End, 0, 0),
+        ] {
+            let bytes = block.to_bytes();
+            assert_eq!(bytes[..8], u64::to_le_bytes(length));
+            assert_eq!(bytes[8..], u64::to_le_bytes(address));
+            assert_eq!(Block::from_bytes(&bytes), block);
+            let descriptor = BlockDescriptor::from(block);
+            assert_eq!(descriptor.length, length);
+            assert_eq!(Block::from(descriptor), block);
+        }
+        assert_eq!(BLOCK_DESCRIPTOR_SIZE, 16);
+    }
+
+    #[test]
+    fn builder_should_fragment_ranges() {
+        let blocks =
+            BlockListBuilder::new(0x1000).with_range(0x8000, 0x2800).with_range(0x1000, 0).with_range(0x20000, 0x10);
+        assert_eq!(
+            blocks.clone().build().unwrap(),
+            [
+                Block::Data { address: 0x8000, length: 0x1000 },
+                Block::Data { address: 0x9000, length: 0x1000 },
+                Block::Data { address: 0xA000, length: 0x800 },
+                Block::Data { address: 0x20000, length: 0x10 },
+                Block::End,
+            ]
+        );
+
+        let lists = blocks.build_lists(3, |index| 0x100000 + 0x1000 * index as u64).unwrap();
+        assert_eq!(
+            lists,
+            [
+                (
+                    0x100000,
+                    vec![
+                        Block::Data { address: 0x8000, length: 0x1000 },
+                        Block::Data { address: 0x9000, length: 0x1000 },
+                        Block::Continuation(0x101000),
+                    ]
+                ),
+                (
+                    0x101000,
+                    vec![
+                        Block::Data { address: 0xA000, length: 0x800 },
+                        Block::Data { address: 0x20000, length: 0x10 },
+                        Block::End,
+                    ]
+                ),
+            ]
+        );
+
+        assert_eq!(BlockListBuilder::new(0x1000).build_lists(2, |_| 0x5000).unwrap(), [(0x5000, vec![Block::End])]);
+        assert_eq!(
+            BlockListBuilder::new(0x1000).build_lists(1, |_| 0).unwrap_err(),
+            CapsuleError::InvalidListCapacity(1)
+        );
+        assert_eq!(BlockListBuilder::new(0).with_range(0, 1).build().unwrap_err(), CapsuleError::InvalidBlockSize(0));
+        assert_eq!(
+            BlockListBuilder::new(0x1000).with_range(u64::MAX, 2).build().unwrap_err(),
+            CapsuleError::InvalidBlock { address: u64::MAX, length: 2 }
+        );
+    }
+
+    #[test]
+    fn walker_should_reassemble_scattered_capsules() {
+        // Two capsules in 0x1000-byte pages scattered in reverse order, described by lists of 4 descriptors.
+        let capsules = [capsule(0x2345), capsule(0x1000)].concat();
+        let mut memory = FakeMemory::default();
+        let mut builder = BlockListBuilder::new(0x800);
+        for (index, page) in capsules.chunks(0x1000).enumerate() {
+            let address = 0x8000_0000 - 0x10000 * index as u64;
+            memory.write(address, page);
+            builder = builder.with_range(address, page.len() as u64);
+        }
+        let lists = builder.build_lists(4, |index| 0x1000_0000 + 0x3000 * index as u64).unwrap();
+        assert_eq!(lists.len(), 3);
+        for (address, list) in &lists {
+            memory.write_list(*address, list);
+        }
+
+        let walked =
+            BlockWalker::new(lists[0].0, 0x10000).walk(|address, buffer| memory.read(address, buffer)).unwrap();
+        assert_eq!(walked, capsules);
+        let first = Capsule::parse(&walked).unwrap();
+        assert_eq!(first.as_bytes().len(), 0x2345);
+        assert_eq!(Capsule::parse(&walked[0x2345..]).unwrap().as_bytes().len(), 0x1000);
+
+        // The limits are inclusive.
+        let walker = BlockWalker::new(lists[0].0, capsules.len() as u64).with_max_descriptors(11);
+        assert_eq!(walker.walk(|address, buffer| memory.read(address, buffer)).unwrap(), capsules);
+    }
+
+    #[test]
+    fn cycles_should_be_detected() {
+        let mut memory = FakeMemory::default();
+        memory.write(0x4000, b"data");
+        let data = Block::Data { address: 0x4000, length: 4 };
+        // A list continuing to itself, two lists continuing to each other, and a continuation into the middle of a
+        // list already walked.
+        memory.write_list(0x1000, &[data, Block::Continuation(0x1000)]);
+        memory.write_list(0x2000, &[data, Block::Continuation(0x3000)]);
+        memory.write_list(0x3000, &[data, Block::Continuation(0x2000)]);
+        memory.write_list(0x5000, &[data, data, Block::Continuation(0x6000)]);
+        memory.write_list(0x6000, &[Block::Continuation(0x5010)]);
+
+        let walk = |list| BlockWalker::new(list, u64::MAX).walk(|address, buffer| memory.read(address, buffer));
+        assert_eq!(walk(0x1000).unwrap_err(), CapsuleError::DescriptorCycle(0x1000));
+        assert_eq!(walk(0x2000).unwrap_err(), CapsuleError::DescriptorCycle(0x2000));
+        assert_eq!(walk(0x3000).unwrap_err(), CapsuleError::DescriptorCycle(0x3000));
+        assert_eq!(walk(0x5000).unwrap_err(), CapsuleError::DescriptorCycle(0x6000));
+    }
+
+    #[test]
+    fn limits_should_be_enforced() {
+        let mut memory = FakeMemory::default();
+        memory.write(0x4000, &[0xA5; 0x100]);
+        // A corrupt length is rejected before anything is allocated or read.
+        memory.write_list(0x1000, &[Block::Data { address: 0x4000, length: u64::MAX / 2 }, Block::End]);
+        memory.write_list(0x2000, &[Block::Data { address: u64::MAX, length: 1 }, Block::End]);
+        // Blocks that only exceed the limit together.
+        memory.write_list(0x3000, &[Block::Data { address: 0x4000, length: 0x100 }; 3]);
+        // A chain of continuations without a cycle.
+        for index in 0..16 {
+            memory.write_list(0x10000 + 0x10 * index, &[Block::Continuation(0x10000 + 0x10 * (index + 1))]);
+        }
+
+        let walk = |list, max_size| {
+            BlockWalker::new(list, max_size)
+                .with_max_descriptors(8)
+                .walk(|address, buffer| memory.read(address, buffer))
+        };
+        assert_eq!(walk(0x1000, 0x10000).unwrap_err(), CapsuleError::TooLarge(0x10000));
+        assert_eq!(walk(0x2000, 0x10000).unwrap_err(), CapsuleError::InvalidBlock { address: u64::MAX, length: 1 });
+        assert_eq!(walk(0x3000, 0x200).unwrap_err(), CapsuleError::TooLarge(0x200));
+        assert_eq!(walk(0x10000, 0x10000).unwrap_err(), CapsuleError::TooManyDescriptors(8));
+        // Unreadable descriptors and data blocks.
+        assert_eq!(walk(0x3000, 0x10000).unwrap_err(), CapsuleError::ReadFailed(0x3030));
+        assert_eq!(walk(0x9000, 0x10000).unwrap_err(), CapsuleError::ReadFailed(0x9000));
+    }
+}
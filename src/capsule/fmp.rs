@@ -0,0 +1,723 @@
+//! Firmware Management Protocol Capsules
+//!
+//! The body of a capsule with the [`CAPSULE_ID_GUID`] GUID, which carries firmware images for the Firmware Management
+//! Protocol (FMP) instances of the platform, and optionally drivers that provide those instances.
+//!
+//! The body starts with an [`FmpCapsuleHeader`] followed by an array of offsets, one for each embedded driver and then
+//! one for each payload item. Each payload item is an [`FmpCapsuleImageHeader`] followed by the image and any vendor
+//! code. The offsets are relative to the start of the body, must be in ascending order, and each item extends to the
+//! next offset, the last to the end of the body. [`FmpCapsule::parse`] checks every offset and item size before any
+//! item is returned, as the EDK II capsule library does.
+//!
+//! The image of a payload is opaque to the capsule, but usually starts with an EFI_FIRMWARE_IMAGE_AUTHENTICATION
+//! and, if the image header says so, a dependency expression (see [`depex`]). [`FmpPayload::authentication`] and
+//! [`FmpPayload::dependencies`] parse those on request; their length fields are checked against the image.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/23_Firmware_Update_and_Reporting.html#delivering-capsules-containing-updates-to-firmware-management-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{fmt, mem::size_of, ptr};
+
+use r_efi::efi;
+
+use super::Capsule;
+
+pub mod depex;
+
+/// GUID of FMP capsules (EFI_FIRMWARE_MANAGEMENT_CAPSULE_ID_GUID).
+pub const CAPSULE_ID_GUID: efi::Guid =
+    efi::Guid::from_fields(0x6dcbd5ed, 0xe82d, 0x4c44, 0xbd, 0xa1, &[0x71, 0x94, 0x19, 0x9a, 0xd9, 0x2a]);
+
+/// The latest [`FmpCapsuleHeader`] version.
+pub const CAPSULE_HEADER_VERSION: u32 = 1;
+
+/// The latest [`FmpCapsuleImageHeader`] version.
+pub const IMAGE_HEADER_VERSION: u32 = 3;
+
+/// Size in bytes of a version 1 image header, which ends before `update_hardware_instance`.
+pub const IMAGE_HEADER_V1_SIZE: usize = 32;
+
+/// Size in bytes of a version 2 image header, which ends before `image_capsule_support`.
+pub const IMAGE_HEADER_V2_SIZE: usize = 40;
+
+/// The header of the body of an FMP capsule (EFI_FIRMWARE_MANAGEMENT_CAPSULE_HEADER). It is followed by the
+/// `embedded_driver_count + payload_item_count` item offsets (ItemOffsetList), each a `u64`.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FmpCapsuleHeader {
+    pub version: u32,
+    pub embedded_driver_count: u16,
+    pub payload_item_count: u16,
+}
+
+/// The header of a payload item (EFI_FIRMWARE_MANAGEMENT_CAPSULE_IMAGE_HEADER). It is followed by
+/// `update_image_size` bytes of image and `update_vendor_code_size` bytes of vendor code.
+///
+/// Version 1 headers end before `update_hardware_instance` and version 2 headers before `image_capsule_support`; the
+/// fields a header does not have are zero when parsed. The EDK II definition is packed, but every field is naturally
+/// aligned, so the layout is the same.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FmpCapsuleImageHeader {
+    pub version: u32,
+    /// The ImageTypeId of the firmware image the payload updates.
+    pub update_image_type_id: efi::Guid,
+    /// The ImageIndex of the firmware image, passed to SetImage().
+    pub update_image_index: u8,
+    pub reserved_bytes: [u8; 3],
+    pub update_image_size: u32,
+    pub update_vendor_code_size: u32,
+    /// The hardware instance to update, or zero for any instance. Version 2 and later.
+    pub update_hardware_instance: u64,
+    /// The `CAPSULE_SUPPORT_*` bits describing the payload, e.g. whether it is authenticated. Version 3 and later.
+    pub image_capsule_support: u64,
+}
+
+/// The payload starts with an EFI_FIRMWARE_IMAGE_AUTHENTICATION structure.
+pub const CAPSULE_SUPPORT_AUTHENTICATION: u64 = 0x0000_0000_0000_0001;
+/// The payload has an EFI_FIRMWARE_IMAGE_DEP dependency expression after its authentication.
+pub const CAPSULE_SUPPORT_DEPENDENCY: u64 = 0x0000_0000_0000_0002;
+
+/// The revision of [`WinCertificate`] (WIN_CERT_CURRENT_VERSION).
+pub const WIN_CERT_REVISION: u16 = 0x0200;
+
+/// The certificate type of a [`WinCertificateUefiGuid`] (WIN_CERT_TYPE_EFI_GUID).
+pub const WIN_CERT_TYPE_EFI_GUID: u16 = 0x0EF1;
+
+/// Certificate type of a PKCS#7 SignedData signature (EFI_CERT_TYPE_PKCS7_GUID).
+pub const CERT_TYPE_PKCS7_GUID: efi::Guid =
+    efi::Guid::from_fields(0x4aafd29d, 0x68df, 0x49ee, 0x8a, 0xa9, &[0x34, 0x7d, 0x37, 0x56, 0x65, 0xa7]);
+
+/// Certificate type of an RSA-2048 public key and SHA-256 signature (EFI_CERT_TYPE_RSA2048_SHA256_GUID).
+pub const CERT_TYPE_RSA2048_SHA256_GUID: efi::Guid =
+    efi::Guid::from_fields(0xa7717414, 0xc616, 0x4977, 0x94, 0x20, &[0x84, 0x47, 0x12, 0xa7, 0x35, 0xbf]);
+
+/// The header of a signature (WIN_CERTIFICATE). `length` covers the header and everything after it.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WinCertificate {
+    pub length: u32,
+    pub revision: u16,
+    pub certificate_type: u16,
+}
+
+/// A signature identified by a GUID (WIN_CERTIFICATE_UEFI_GUID), followed by the certificate data.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WinCertificateUefiGuid {
+    pub hdr: WinCertificate,
+    /// The format of the certificate data, e.g. [`CERT_TYPE_PKCS7_GUID`].
+    pub cert_type: efi::Guid,
+}
+
+/// The authentication at the start of a payload image (EFI_FIRMWARE_IMAGE_AUTHENTICATION). The signature covers the
+/// rest of the image followed by the monotonic count.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FirmwareImageAuthentication {
+    /// Increases with each image, so that an older image cannot be replayed.
+    pub monotonic_count: u64,
+    pub auth_info: WinCertificateUefiGuid,
+}
+
+/// Errors reported when parsing an FMP capsule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FmpError {
+    /// The capsule GUID is not [`CAPSULE_ID_GUID`].
+    NotFmpCapsule,
+    /// The body is too small for the capsule header and item offsets.
+    Truncated,
+    /// The capsule header version is newer than [`CAPSULE_HEADER_VERSION`].
+    UnsupportedVersion(u32),
+    /// The capsule has no embedded drivers or payload items.
+    NoItems,
+    /// The offset of item `index` overlaps the capsule header or item offsets, or is outside the body.
+    OffsetOutOfRange { index: usize, offset: u64 },
+    /// The offset of item `index` is not above the offset of the item before it.
+    OverlappingItems { index: usize },
+    /// The image header of item `index` has an unsupported version.
+    UnsupportedImageHeaderVersion { index: usize, version: u32 },
+    /// The image header, image and vendor code sizes of item `index` do not add up to the item size.
+    InvalidImageSize { index: usize },
+    /// The authentication of a payload is truncated, or its certificate header is invalid.
+    InvalidAuthentication,
+    /// The dependency expression of a payload is invalid.
+    InvalidDependencies(depex::DepexError),
+}
+
+impl fmt::Display for FmpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FmpError::NotFmpCapsule => write!(f, "not an FMP capsule"),
+            FmpError::Truncated => write!(f, "FMP capsule is too small for its header"),
+            FmpError::UnsupportedVersion(version) => write!(f, "unsupported FMP capsule version {version}"),
+            FmpError::NoItems => write!(f, "FMP capsule has no items"),
+            FmpError::OffsetOutOfRange { index, offset } => {
+                write!(f, "FMP capsule item {index} offset {offset:#x} is out of range")
+            }
+            FmpError::OverlappingItems { index } => write!(f, "FMP capsule item {index} overlaps the item before it"),
+            FmpError::UnsupportedImageHeaderVersion { index, version } => {
+                write!(f, "FMP capsule item {index} has unsupported image header version {version}")
+            }
+            FmpError::InvalidImageSize { index } => write!(f, "FMP capsule item {index} has inconsistent sizes"),
+            FmpError::InvalidAuthentication => write!(f, "FMP payload has invalid authentication"),
+            FmpError::InvalidDependencies(error) => write!(f, "FMP payload has invalid dependencies: {error}"),
+        }
+    }
+}
+
+impl From<FmpError> for efi::Status {
+    fn from(_: FmpError) -> Self {
+        efi::Status::INVALID_PARAMETER
+    }
+}
+
+/// The authentication of a payload image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FmpAuthentication<'a> {
+    monotonic_count: u64,
+    cert_type: efi::Guid,
+    cert_data: &'a [u8],
+    size: usize,
+}
+
+impl<'a> FmpAuthentication<'a> {
+    /// Returns the monotonic count of the image.
+    pub fn monotonic_count(&self) -> u64 {
+        self.monotonic_count
+    }
+
+    /// Returns the format of the certificate data, e.g. [`CERT_TYPE_PKCS7_GUID`].
+    pub fn cert_type(&self) -> efi::Guid {
+        self.cert_type
+    }
+
+    /// Returns the certificate data.
+    pub fn cert_data(&self) -> &'a [u8] {
+        self.cert_data
+    }
+
+    /// Returns the size in bytes of the authentication at the start of the image.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+/// A payload item: an image for an FMP instance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FmpPayload<'a> {
+    header: FmpCapsuleImageHeader,
+    image: &'a [u8],
+    vendor_code: &'a [u8],
+}
+
+impl<'a> FmpPayload<'a> {
+    /// Returns the image header, with the fields of later versions than its own zero.
+    pub fn header(&self) -> FmpCapsuleImageHeader {
+        self.header
+    }
+
+    /// Returns the ImageTypeId of the firmware image the payload updates.
+    pub fn update_image_type_id(&self) -> efi::Guid {
+        self.header.update_image_type_id
+    }
+
+    /// Returns the ImageIndex of the firmware image the payload updates.
+    pub fn update_image_index(&self) -> u8 {
+        self.header.update_image_index
+    }
+
+    /// Returns the hardware instance the payload updates, or `None` for version 1 headers, which update any.
+    pub fn update_hardware_instance(&self) -> Option<u64> {
+        (self.header.version >= 2).then_some(self.header.update_hardware_instance)
+    }
+
+    /// Returns the image passed to SetImage().
+    pub fn image(&self) -> &'a [u8] {
+        self.image
+    }
+
+    /// Returns the vendor code passed to SetImage().
+    pub fn vendor_code(&self) -> &'a [u8] {
+        self.vendor_code
+    }
+
+    /// Parses the authentication at the start of the image.
+    ///
+    /// Returns `None` if a version 3 or later image header does not set [`CAPSULE_SUPPORT_AUTHENTICATION`]. Earlier
+    /// versions cannot say, and their images are parsed as authenticated, as FMP instances requiring authentication
+    /// do.
+    pub fn authentication(&self) -> Result<Option<FmpAuthentication<'a>>, FmpError> {
+        if self.header.version >= 3 && self.header.image_capsule_support & CAPSULE_SUPPORT_AUTHENTICATION == 0 {
+            return Ok(None);
+        }
+        let auth: FirmwareImageAuthentication = read(self.image).ok_or(FmpError::InvalidAuthentication)?;
+        let hdr = auth.auth_info.hdr;
+        if hdr.revision != WIN_CERT_REVISION
+            || hdr.certificate_type != WIN_CERT_TYPE_EFI_GUID
+            || (hdr.length as usize) < size_of::<WinCertificateUefiGuid>()
+        {
+            Err(FmpError::InvalidAuthentication)?;
+        }
+        // The certificate length comes from the image, so it may claim more than the image holds.
+        let size = (hdr.length as usize).checked_add(size_of::<u64>()).ok_or(FmpError::InvalidAuthentication)?;
+        let cert_data =
+            self.image.get(size_of::<FirmwareImageAuthentication>()..size).ok_or(FmpError::InvalidAuthentication)?;
+        Ok(Some(FmpAuthentication {
+            monotonic_count: auth.monotonic_count,
+            cert_type: auth.auth_info.cert_type,
+            cert_data,
+            size,
+        }))
+    }
+
+    /// Parses the dependency expression (EFI_FIRMWARE_IMAGE_DEP) that follows the authentication, returning its
+    /// bytes through the END opcode.
+    ///
+    /// Returns `None` unless a version 3 or later image header sets [`CAPSULE_SUPPORT_DEPENDENCY`].
+    pub fn dependencies(&self) -> Result<Option<&'a [u8]>, FmpError> {
+        if self.header.version < 3 || self.header.image_capsule_support & CAPSULE_SUPPORT_DEPENDENCY == 0 {
+            return Ok(None);
+        }
+        let start = self.authentication()?.map_or(0, |auth| auth.size());
+        depex::parse(&self.image[start..]).map(Some).map_err(FmpError::InvalidDependencies)
+    }
+}
+
+/// An item of an FMP capsule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FmpItem<'a> {
+    /// A UEFI driver image, loaded before the payloads are processed.
+    EmbeddedDriver(&'a [u8]),
+    /// A payload item.
+    Payload(FmpPayload<'a>),
+}
+
+/// A validated FMP capsule body.
+#[derive(Debug, Clone, Copy)]
+pub struct FmpCapsule<'a> {
+    header: FmpCapsuleHeader,
+    body: &'a [u8],
+}
+
+impl<'a> FmpCapsule<'a> {
+    /// Parses the body of `capsule`, which must have the [`CAPSULE_ID_GUID`] GUID.
+    pub fn from_capsule(capsule: &Capsule<'a>) -> Result<Self, FmpError> {
+        if capsule.guid() != CAPSULE_ID_GUID {
+            Err(FmpError::NotFmpCapsule)?;
+        }
+        Self::parse(capsule.body())
+    }
+
+    /// Parses an FMP capsule body, checking the offset and size of every item.
+    pub fn parse(body: &'a [u8]) -> Result<Self, FmpError> {
+        let header: FmpCapsuleHeader = read(body).ok_or(FmpError::Truncated)?;
+        if header.version > CAPSULE_HEADER_VERSION {
+            Err(FmpError::UnsupportedVersion(header.version))?;
+        }
+        let capsule = Self { header, body };
+        let count = capsule.item_count();
+        if count == 0 {
+            Err(FmpError::NoItems)?;
+        }
+        let items_start = size_of::<FmpCapsuleHeader>() + count * size_of::<u64>();
+        if body.len() < items_start {
+            Err(FmpError::Truncated)?;
+        }
+
+        let mut previous = None;
+        for index in 0..count {
+            let offset = capsule.offset(index);
+            if offset < items_start as u64 || offset >= body.len() as u64 {
+                Err(FmpError::OffsetOutOfRange { index, offset })?;
+            }
+            if previous.is_some_and(|previous| offset <= previous) {
+                Err(FmpError::OverlappingItems { index })?;
+            }
+            previous = Some(offset);
+        }
+        for index in header.embedded_driver_count as usize..count {
+            capsule.payload(index)?;
+        }
+        Ok(capsule)
+    }
+
+    /// Returns the capsule header.
+    pub fn header(&self) -> FmpCapsuleHeader {
+        self.header
+    }
+
+    /// Returns the embedded drivers and then the payload items.
+    pub fn items(&self) -> impl Iterator<Item = FmpItem<'a>> + '_ {
+        let drivers = self.header.embedded_driver_count as usize;
+        (0..self.item_count()).map(move |index| match index < drivers {
+            true => FmpItem::EmbeddedDriver(self.item(index)),
+            // Every payload was checked by parse().
+            false => FmpItem::Payload(self.payload(index).unwrap()),
+        })
+    }
+
+    /// Returns the embedded driver images.
+    pub fn embedded_drivers(&self) -> impl Iterator<Item = &'a [u8]> + '_ {
+        self.items().filter_map(|item| match item {
+            FmpItem::EmbeddedDriver(driver) => Some(driver),
+            FmpItem::Payload(_) => None,
+        })
+    }
+
+    /// Returns the payload items.
+    pub fn payloads(&self) -> impl Iterator<Item = FmpPayload<'a>> + '_ {
+        self.items().filter_map(|item| match item {
+            FmpItem::EmbeddedDriver(_) => None,
+            FmpItem::Payload(payload) => Some(payload),
+        })
+    }
+
+    fn item_count(&self) -> usize {
+        self.header.embedded_driver_count as usize + self.header.payload_item_count as usize
+    }
+
+    // Returns the offset of item index, which the caller has checked is in the offset list.
+    fn offset(&self, index: usize) -> u64 {
+        let start = size_of::<FmpCapsuleHeader>() + index * size_of::<u64>();
+        u64::from_le_bytes(self.body[start..start + size_of::<u64>()].try_into().unwrap())
+    }
+
+    // Returns the bytes of item index, whose offsets parse() has checked.
+    fn item(&self, index: usize) -> &'a [u8] {
+        let end = if index + 1 < self.item_count() { self.offset(index + 1) as usize } else { self.body.len() };
+        &self.body[self.offset(index) as usize..end]
+    }
+
+    fn payload(&self, index: usize) -> Result<FmpPayload<'a>, FmpError> {
+        let item = self.item(index);
+        let invalid_size = FmpError::InvalidImageSize { index };
+        let version = read::<u32>(item).ok_or(invalid_size)?;
+        let header_size = match version {
+            1 => IMAGE_HEADER_V1_SIZE,
+            2 => IMAGE_HEADER_V2_SIZE,
+            IMAGE_HEADER_VERSION => size_of::<FmpCapsuleImageHeader>(),
+            _ => Err(FmpError::UnsupportedImageHeaderVersion { index, version })?,
+        };
+        // Copy the header into a zeroed version 3 header, so that the fields of later versions are zero.
+        let mut bytes = [0u8; size_of::<FmpCapsuleImageHeader>()];
+        bytes[..header_size].copy_from_slice(item.get(..header_size).ok_or(invalid_size)?);
+        let header: FmpCapsuleImageHeader = read(&bytes).unwrap();
+
+        let image_size = header.update_image_size as usize;
+        let vendor_code_size = header.update_vendor_code_size as usize;
+        if header_size.checked_add(image_size).and_then(|size| size.checked_add(vendor_code_size)) != Some(item.len()) {
+            Err(invalid_size)?;
+        }
+        let (image, vendor_code) = item[header_size..].split_at(image_size);
+        Ok(FmpPayload { header, image, vendor_code })
+    }
+}
+
+// Reads a T from the start of bytes, if it is large enough.
+fn read<T: Copy>(bytes: &[u8]) -> Option<T> {
+    //Safety: the read is bounds checked, and does not assume alignment.
+    (bytes.len() >= size_of::<T>()).then(|| unsafe { ptr::read_unaligned(bytes.as_ptr() as *const T) })
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    use alloc::vec::Vec;
+    use core::mem::size_of;
+
+    use r_efi::efi;
+
+    use super::{
+        depex::{DepexError, EFI_FMP_DEP_END, EFI_FMP_DEP_TRUE},
+        FirmwareImageAuthentication, FmpCapsule, FmpCapsuleHeader, FmpCapsuleImageHeader, FmpError, FmpItem,
+        WinCertificateUefiGuid, CAPSULE_ID_GUID, CAPSULE_SUPPORT_AUTHENTICATION, CAPSULE_SUPPORT_DEPENDENCY,
+        CERT_TYPE_PKCS7_GUID, CERT_TYPE_RSA2048_SHA256_GUID,
+    };
+    use crate::capsule::Capsule;
+
+    const IMAGE_TYPE_ID: efi::Guid =
+        efi::Guid::from_fields(0x938b3a62, 0x3e8e, 0x4c51, 0x9a, 0xa7, &[0xbb, 0x8a, 0x2c, 0x4f, 0x2a, 0x4f]);
+
+    // An FMP capsule laid out as the EDK II GenerateCapsule tool writes one: a capsule header padded to 32 bytes, the
+    // FMP capsule header with one payload item, and a version 3 image header followed by a 16-byte image and no
+    // vendor code. The image would normally start with the payload authentication, which is opaque here.
+    #[rustfmt::skip]
+    const CAPSULE: [u8; 32 + 16 + 48 + 16] = [
+        // EFI_CAPSULE_HEADER: EFI_FIRMWARE_MANAGEMENT_CAPSULE_ID_GUID, HeaderSize 0x20,
+        // CAPSULE_FLAGS_PERSIST_ACROSS_RESET, CapsuleImageSize 0x70, 4 bytes of padding.
+        0xed, 0xd5, 0xcb, 0x6d, 0x2d, 0xe8, 0x44, 0x4c, 0xbd, 0xa1, 0x71, 0x94, 0x19, 0x9a, 0xd9, 0x2a,
+        0x20, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x01, 0x00,
+        0x70, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00,
+        // EFI_FIRMWARE_MANAGEMENT_CAPSULE_HEADER: Version 1, no embedded drivers, one payload.
+        0x01, 0x00, 0x00, 0x00,
+        0x00, 0x00,
+        0x01, 0x00,
+        // ItemOffsetList[0]
+        0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        // EFI_FIRMWARE_MANAGEMENT_CAPSULE_IMAGE_HEADER: Version 3, UpdateImageTypeId, UpdateImageIndex 1,
+        // UpdateImageSize 0x10, no vendor code, UpdateHardwareInstance 0, ImageCapsuleSupport AUTHENTICATION.
+        0x03, 0x00, 0x00, 0x00,
+        0x62, 0x3a, 0x8b, 0x93, 0x8e, 0x3e, 0x51, 0x4c, 0x9a, 0xa7, 0xbb, 0x8a, 0x2c, 0x4f, 0x2a, 0x4f,
+        0x01, 0x00, 0x00, 0x00,
+        0x10, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        // Image
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+    ];
+
+    // Builds a body with the given drivers and payloads, given as (header version, image, vendor code).
+    fn body(drivers: &[&[u8]], payloads: &[(u32, &[u8], &[u8])]) -> Vec<u8> {
+        let count = drivers.len() + payloads.len();
+        let mut items: Vec<Vec<u8>> = drivers.iter().map(|driver| driver.to_vec()).collect();
+        for (version, image, vendor_code) in payloads {
+            let header_size = [32, 40, 48][*version as usize - 1];
+            let mut item = Vec::new();
+            item.extend_from_slice(&version.to_le_bytes());
+            item.extend_from_slice(IMAGE_TYPE_ID.as_bytes());
+            item.extend_from_slice(&[items.len() as u8, 0, 0, 0]);
+            item.extend_from_slice(&(image.len() as u32).to_le_bytes());
+            item.extend_from_slice(&(vendor_code.len() as u32).to_le_bytes());
+            item.extend_from_slice(&7u64.to_le_bytes());
+            item.extend_from_slice(&CAPSULE_SUPPORT_AUTHENTICATION.to_le_bytes());
+            item.truncate(header_size);
+            item.extend_from_slice(image);
+            item.extend_from_slice(vendor_code);
+            items.push(item);
+        }
+        let mut body = Vec::new();
+        body.extend_from_slice(&1u32.to_le_bytes());
+        body.extend_from_slice(&(drivers.len() as u16).to_le_bytes());
+        body.extend_from_slice(&(payloads.len() as u16).to_le_bytes());
+        let mut offset = 8 + 8 * count;
+        for item in &items {
+            body.extend_from_slice(&(offset as u64).to_le_bytes());
+            offset += item.len();
+        }
+        items.iter().for_each(|item| body.extend_from_slice(item));
+        body
+    }
+
+    fn set_offset(body: &mut [u8], index: usize, offset: u64) {
+        body[8 + 8 * index..16 + 8 * index].copy_from_slice(&offset.to_le_bytes());
+    }
+
+    // Builds a payload image starting with an authentication of the given certificate, followed by rest.
+    fn authenticated_image(monotonic_count: u64, cert_type: efi::Guid, cert_data: &[u8], rest: &[u8]) -> Vec<u8> {
+        let mut image = Vec::new();
+        image.extend_from_slice(&monotonic_count.to_le_bytes());
+        image.extend_from_slice(&(24 + cert_data.len() as u32).to_le_bytes());
+        image.extend_from_slice(&0x0200u16.to_le_bytes());
+        image.extend_from_slice(&0x0EF1u16.to_le_bytes());
+        image.extend_from_slice(cert_type.as_bytes());
+        image.extend_from_slice(cert_data);
+        image.extend_from_slice(rest);
+        image
+    }
+
+    // Sets the ImageCapsuleSupport of the only payload of a body with no drivers.
+    fn set_capsule_support(body: &mut [u8], image_capsule_support: u64) {
+        body[16 + 40..16 + 48].copy_from_slice(&image_capsule_support.to_le_bytes());
+    }
+
+    #[test]
+    fn layouts_should_match_edk2() {
+        assert_eq!(size_of::<FmpCapsuleHeader>(), 8);
+        assert_eq!(size_of::<FmpCapsuleImageHeader>(), 48);
+        assert_eq!(size_of::<WinCertificateUefiGuid>(), 24);
+        assert_eq!(size_of::<FirmwareImageAuthentication>(), 32);
+        assert_eq!(
+            CERT_TYPE_PKCS7_GUID.as_bytes(),
+            &[0x9d, 0xd2, 0xaf, 0x4a, 0xdf, 0x68, 0xee, 0x49, 0x8a, 0xa9, 0x34, 0x7d, 0x37, 0x56, 0x65, 0xa7]
+        );
+    }
+
+    #[test]
+    fn generated_capsule_should_parse() {
+        let capsule = Capsule::parse(&CAPSULE).unwrap();
+        assert_eq!(capsule.guid(), CAPSULE_ID_GUID);
+        let fmp = FmpCapsule::from_capsule(&capsule).unwrap();
+        assert_eq!(fmp.embedded_drivers().count(), 0);
+        let payloads: Vec<_> = fmp.payloads().collect();
+        assert_eq!(payloads.len(), 1);
+        assert_eq!(payloads[0].update_image_type_id(), IMAGE_TYPE_ID);
+        assert_eq!(payloads[0].update_image_index(), 1);
+        assert_eq!(payloads[0].update_hardware_instance(), Some(0));
+        assert_eq!({ payloads[0].header().image_capsule_support }, CAPSULE_SUPPORT_AUTHENTICATION);
+        assert_eq!(payloads[0].image(), (0..16).collect::<Vec<u8>>());
+        assert!(payloads[0].vendor_code().is_empty());
+
+        let mut bytes = CAPSULE;
+        bytes[0] ^= 1;
+        assert_eq!(FmpCapsule::from_capsule(&Capsule::parse(&bytes).unwrap()).unwrap_err(), FmpError::NotFmpCapsule);
+    }
+
+    #[test]
+    fn drivers_and_payloads_should_be_returned_in_order() {
+        let body = body(
+            &[b"driver0", b"driver1"],
+            &[(1, b"v1 image", b""), (2, b"v2 image", b"vendor"), (3, b"v3 image", b"code")],
+        );
+        let fmp = FmpCapsule::parse(&body).unwrap();
+        let drivers: Vec<&[u8]> = fmp.embedded_drivers().collect();
+        assert_eq!(drivers, [&b"driver0"[..], &b"driver1"[..]]);
+        let payloads: Vec<_> = fmp.payloads().collect();
+        let images: Vec<&[u8]> = payloads.iter().map(|payload| payload.image()).collect();
+        assert_eq!(images, [&b"v1 image"[..], &b"v2 image"[..], &b"v3 image"[..]]);
+        assert_eq!(payloads[1].vendor_code(), b"vendor");
+        assert_eq!(payloads[2].vendor_code(), b"code");
+        // Fields a header version does not have are zero.
+        assert_eq!(payloads[0].update_hardware_instance(), None);
+        assert_eq!({ payloads[0].header().update_hardware_instance }, 0);
+        assert_eq!(payloads[1].update_hardware_instance(), Some(7));
+        assert_eq!({ payloads[1].header().image_capsule_support }, 0);
+        assert_eq!({ payloads[2].header().image_capsule_support }, CAPSULE_SUPPORT_AUTHENTICATION);
+        assert!(matches!(fmp.items().nth(2), Some(FmpItem::Payload(payload)) if payload.update_image_index() == 2));
+    }
+
+    #[test]
+    fn malformed_offsets_should_be_rejected() {
+        let valid = body(&[b"driver"], &[(3, b"image one", b""), (3, b"image two", b"")]);
+        let offsets = [0x20u64, 0x26, 0x26 + 48 + 9];
+        let end = valid.len() as u64;
+
+        // Out of range: into the header or offset list, at or past the end.
+        for (index, offset) in [(0, 0), (0, 0x1f), (2, end), (2, u64::MAX)] {
+            let mut body = valid.clone();
+            set_offset(&mut body, index, offset);
+            assert_eq!(FmpCapsule::parse(&body).unwrap_err(), FmpError::OffsetOutOfRange { index, offset });
+        }
+        // Overlapping: equal or descending offsets.
+        for (index, offset) in [(1, offsets[0]), (2, offsets[1]), (1, offsets[0] - 1)] {
+            let mut body = valid.clone();
+            set_offset(&mut body, index, offset);
+            let error = FmpCapsule::parse(&body).unwrap_err();
+            assert!(
+                matches!(error, FmpError::OverlappingItems { .. } | FmpError::OffsetOutOfRange { .. }),
+                "{error:?}"
+            );
+        }
+        // Offsets that move an item boundary no longer match the image sizes.
+        let mut body = valid.clone();
+        set_offset(&mut body, 2, offsets[2] + 1);
+        assert_eq!(FmpCapsule::parse(&body).unwrap_err(), FmpError::InvalidImageSize { index: 1 });
+
+        // Counts whose offset list does not fit.
+        let mut body = valid.clone();
+        body[6..8].copy_from_slice(&u16::MAX.to_le_bytes());
+        assert_eq!(FmpCapsule::parse(&body).unwrap_err(), FmpError::Truncated);
+        assert_eq!(FmpCapsule::parse(&valid[..7]).unwrap_err(), FmpError::Truncated);
+        let mut body = valid.clone();
+        body[4..8].copy_from_slice(&[0; 4]);
+        assert_eq!(FmpCapsule::parse(&body).unwrap_err(), FmpError::NoItems);
+    }
+
+    #[test]
+    fn malformed_images_should_be_rejected() {
+        let valid = body(&[], &[(3, b"image", b"code")]);
+        // Image and vendor code sizes larger than the item, including sums that would overflow 32 bits.
+        for (field, value) in [(24, 6u32), (28, 5), (24, u32::MAX), (28, u32::MAX)] {
+            let mut body = valid.clone();
+            body[16 + field..20 + field].copy_from_slice(&value.to_le_bytes());
+            assert_eq!(FmpCapsule::parse(&body).unwrap_err(), FmpError::InvalidImageSize { index: 0 });
+        }
+        for version in [0u32, 4] {
+            let mut body = valid.clone();
+            body[16..20].copy_from_slice(&version.to_le_bytes());
+            assert_eq!(
+                FmpCapsule::parse(&body).unwrap_err(),
+                FmpError::UnsupportedImageHeaderVersion { index: 0, version }
+            );
+        }
+        // An item too small for its image header.
+        let short = body(&[b"driver"], &[(3, b"", b"")]);
+        assert_eq!(FmpCapsule::parse(&short[..short.len() - 1]).unwrap_err(), FmpError::InvalidImageSize { index: 1 });
+        let mut body = valid.clone();
+        body[0] = 2;
+        assert_eq!(FmpCapsule::parse(&body).unwrap_err(), FmpError::UnsupportedVersion(2));
+    }
+
+    #[test]
+    fn authentication_and_dependencies_should_parse() {
+        let dependencies = [EFI_FMP_DEP_TRUE, EFI_FMP_DEP_END];
+        let image =
+            authenticated_image(5, CERT_TYPE_PKCS7_GUID, b"signature", &[&dependencies[..], b"firmware"].concat());
+        let mut body = body(&[], &[(3, &image, b"")]);
+        set_capsule_support(&mut body, CAPSULE_SUPPORT_AUTHENTICATION | CAPSULE_SUPPORT_DEPENDENCY);
+        let payload = FmpCapsule::parse(&body).unwrap().payloads().next().unwrap();
+        let auth = payload.authentication().unwrap().unwrap();
+        assert_eq!(auth.monotonic_count(), 5);
+        assert_eq!(auth.cert_type(), CERT_TYPE_PKCS7_GUID);
+        assert_eq!(auth.cert_data(), b"signature");
+        assert_eq!(auth.size(), 32 + 9);
+        assert_eq!(payload.dependencies(), Ok(Some(&dependencies[..])));
+
+        // Without dependencies, or authentication, the image header says where the parts are.
+        set_capsule_support(&mut body, CAPSULE_SUPPORT_AUTHENTICATION);
+        let payload = FmpCapsule::parse(&body).unwrap().payloads().next().unwrap();
+        assert_eq!(payload.dependencies(), Ok(None));
+        let image = [&dependencies[..], b"firmware"].concat();
+        let mut body = self::body(&[], &[(3, &image, b"")]);
+        set_capsule_support(&mut body, CAPSULE_SUPPORT_DEPENDENCY);
+        let payload = FmpCapsule::parse(&body).unwrap().payloads().next().unwrap();
+        assert_eq!(payload.authentication(), Ok(None));
+        assert_eq!(payload.dependencies(), Ok(Some(&dependencies[..])));
+
+        // Earlier image header versions are always authenticated and never have dependencies.
+        let image = authenticated_image(9, CERT_TYPE_RSA2048_SHA256_GUID, &[0xAA; 4], &dependencies);
+        let body = self::body(&[], &[(2, &image, b"")]);
+        let payload = FmpCapsule::parse(&body).unwrap().payloads().next().unwrap();
+        assert_eq!(payload.authentication().unwrap().unwrap().cert_type(), CERT_TYPE_RSA2048_SHA256_GUID);
+        assert_eq!(payload.dependencies(), Ok(None));
+    }
+
+    #[test]
+    fn malformed_authentication_should_be_rejected() {
+        let valid = authenticated_image(1, CERT_TYPE_PKCS7_GUID, b"signature", b"firmware");
+        let mut images = Vec::new();
+        // Certificate lengths larger than the payload, including ones that overflow when the count is added.
+        for length in [valid.len() as u32, 24 + 9 + 8 + 1, u32::MAX, u32::MAX - 7] {
+            let mut image = valid.clone();
+            image[8..12].copy_from_slice(&length.to_le_bytes());
+            images.push(image);
+        }
+        // Certificate lengths smaller than the certificate header.
+        for length in [0u32, 23] {
+            let mut image = valid.clone();
+            image[8..12].copy_from_slice(&length.to_le_bytes());
+            images.push(image);
+        }
+        // Wrong revision and certificate type, and an image too small for the authentication header.
+        let mut image = valid.clone();
+        image[13] = 1;
+        images.push(image);
+        let mut image = valid.clone();
+        image[14] = 2;
+        images.push(image);
+        images.push(valid[..31].to_vec());
+
+        for image in images {
+            let body = body(&[], &[(3, &image, b"")]);
+            let payload = FmpCapsule::parse(&body).unwrap().payloads().next().unwrap();
+            assert_eq!(payload.authentication(), Err(FmpError::InvalidAuthentication));
+        }
+
+        // A length that exactly covers the image leaves nothing for the dependencies.
+        let mut body = body(&[], &[(3, &valid, b"")]);
+        set_capsule_support(&mut body, CAPSULE_SUPPORT_AUTHENTICATION | CAPSULE_SUPPORT_DEPENDENCY);
+        body[16 + 48 + 8..16 + 48 + 12].copy_from_slice(&(valid.len() as u32 - 8).to_le_bytes());
+        let payload = FmpCapsule::parse(&body).unwrap().payloads().next().unwrap();
+        assert_eq!(payload.authentication().unwrap().unwrap().cert_data().len(), 9 + 8);
+        assert_eq!(payload.dependencies(), Err(FmpError::InvalidDependencies(DepexError::MissingEnd)));
+    }
+}
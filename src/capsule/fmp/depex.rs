@@ -0,0 +1,334 @@
+//! FMP Dependency Expressions
+//!
+//! The dependency expression (EFI_FIRMWARE_IMAGE_DEP) that may follow the authentication of an FMP payload. It is a
+//! postfix expression over the versions of other firmware images, and its opcodes differ from those of the
+//! dependency expressions of PEIMs and DXE drivers. The expression has no length field; it ends with
+//! [`EFI_FMP_DEP_END`], so [`parse`] finds its length by decoding it.
+//!
+//! Each [`EFI_FMP_DEP_PUSH_GUID`] pushes the current version of the firmware image with that ImageTypeId, each
+//! comparison pops two versions and pushes a boolean, and the expression must leave a single boolean for
+//! [`EFI_FMP_DEP_END`]. [`parse`] checks the operand types as the EDK II FMP dependency library does, but does not
+//! evaluate the expression.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/23_Firmware_Update_and_Reporting.html#dependency-expression-instruction-set>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::{fmt, mem::size_of};
+
+use r_efi::efi;
+
+/// Pushes the version of the firmware image with the ImageTypeId that follows the opcode.
+pub const EFI_FMP_DEP_PUSH_GUID: u8 = 0x00;
+/// Pushes the 32-bit version that follows the opcode.
+pub const EFI_FMP_DEP_PUSH_VERSION: u8 = 0x01;
+/// A null-terminated ASCII description of the version requirement, with no effect on the stack.
+pub const EFI_FMP_DEP_VERSION_STR: u8 = 0x02;
+pub const EFI_FMP_DEP_AND: u8 = 0x03;
+pub const EFI_FMP_DEP_OR: u8 = 0x04;
+pub const EFI_FMP_DEP_NOT: u8 = 0x05;
+pub const EFI_FMP_DEP_TRUE: u8 = 0x06;
+pub const EFI_FMP_DEP_FALSE: u8 = 0x07;
+pub const EFI_FMP_DEP_EQ: u8 = 0x08;
+pub const EFI_FMP_DEP_GT: u8 = 0x09;
+pub const EFI_FMP_DEP_GTE: u8 = 0x0A;
+pub const EFI_FMP_DEP_LT: u8 = 0x0B;
+pub const EFI_FMP_DEP_LTE: u8 = 0x0C;
+/// Ends the expression.
+pub const EFI_FMP_DEP_END: u8 = 0x0D;
+/// Declares the length in bytes of the whole expression, in the 32-bit value that follows the opcode. Only allowed as
+/// the first opcode.
+pub const EFI_FMP_DEP_DECLARE_LENGTH: u8 = 0x0E;
+
+/// A decoded opcode of a dependency expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepexOp<'a> {
+    PushGuid(efi::Guid),
+    PushVersion(u32),
+    /// The description, without its null terminator.
+    VersionStr(&'a [u8]),
+    And,
+    Or,
+    Not,
+    True,
+    False,
+    Eq,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    End,
+    DeclareLength(u32),
+}
+
+/// Errors reported when parsing a dependency expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepexError {
+    /// The operand of the opcode at `offset` extends past the end of the buffer.
+    Truncated { offset: usize },
+    /// The opcode at `offset` is not defined.
+    UnknownOpcode { offset: usize, opcode: u8 },
+    /// The stack does not hold the operands the opcode at `offset` needs, or the opcode is out of place.
+    InvalidOperands { offset: usize },
+    /// The declared length does not match the length of the expression.
+    LengthMismatch { declared: u32, length: usize },
+    /// The buffer ends before [`EFI_FMP_DEP_END`].
+    MissingEnd,
+}
+
+impl fmt::Display for DepexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DepexError::Truncated { offset } => write!(f, "dependency opcode at {offset:#x} is truncated"),
+            DepexError::UnknownOpcode { offset, opcode } => {
+                write!(f, "unknown dependency opcode {opcode:#x} at {offset:#x}")
+            }
+            DepexError::InvalidOperands { offset } => {
+                write!(f, "invalid operands for dependency opcode at {offset:#x}")
+            }
+            DepexError::LengthMismatch { declared, length } => {
+                write!(f, "dependency expression of {length:#x} bytes declares length {declared:#x}")
+            }
+            DepexError::MissingEnd => write!(f, "dependency expression has no END opcode"),
+        }
+    }
+}
+
+impl From<DepexError> for efi::Status {
+    fn from(_: DepexError) -> Self {
+        efi::Status::INVALID_PARAMETER
+    }
+}
+
+/// Decodes the opcodes of a dependency expression, returning each with its offset.
+///
+/// Decoding stops after [`EFI_FMP_DEP_END`] or the first error; operand types are not checked.
+pub fn opcodes(expression: &[u8]) -> impl Iterator<Item = Result<(usize, DepexOp<'_>), DepexError>> + '_ {
+    let mut offset = 0;
+    let mut done = false;
+    core::iter::from_fn(move || {
+        if done || offset >= expression.len() {
+            return None;
+        }
+        let result = decode(expression, offset);
+        match result {
+            Ok((op, length)) => {
+                done = op == DepexOp::End;
+                offset += length;
+                Some(Ok((offset - length, op)))
+            }
+            Err(error) => {
+                done = true;
+                Some(Err(error))
+            }
+        }
+    })
+}
+
+/// Validates the dependency expression at the start of `bytes`, returning the expression through its
+/// [`EFI_FMP_DEP_END`] opcode.
+pub fn parse(bytes: &[u8]) -> Result<&[u8], DepexError> {
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    enum Operand {
+        Version,
+        Boolean,
+    }
+
+    let mut stack = Vec::new();
+    let mut declared = None;
+    for opcode in opcodes(bytes) {
+        let (offset, op) = opcode?;
+        let invalid = DepexError::InvalidOperands { offset };
+        let pop = |stack: &mut Vec<Operand>, operand| match stack.pop() {
+            Some(top) if top == operand => Ok(()),
+            _ => Err(invalid),
+        };
+        match op {
+            DepexOp::PushGuid(_) | DepexOp::PushVersion(_) => stack.push(Operand::Version),
+            DepexOp::VersionStr(_) => (),
+            DepexOp::And | DepexOp::Or => {
+                pop(&mut stack, Operand::Boolean)?;
+                pop(&mut stack, Operand::Boolean)?;
+                stack.push(Operand::Boolean);
+            }
+            DepexOp::Not => {
+                pop(&mut stack, Operand::Boolean)?;
+                stack.push(Operand::Boolean);
+            }
+            DepexOp::True | DepexOp::False => stack.push(Operand::Boolean),
+            DepexOp::Eq | DepexOp::Gt | DepexOp::Gte | DepexOp::Lt | DepexOp::Lte => {
+                pop(&mut stack, Operand::Version)?;
+                pop(&mut stack, Operand::Version)?;
+                stack.push(Operand::Boolean);
+            }
+            DepexOp::End => {
+                pop(&mut stack, Operand::Boolean)?;
+                if !stack.is_empty() {
+                    Err(invalid)?;
+                }
+                let length = offset + 1;
+                match declared {
+                    Some(declared) if declared as usize != length => {
+                        Err(DepexError::LengthMismatch { declared, length })?
+                    }
+                    _ => return Ok(&bytes[..length]),
+                }
+            }
+            DepexOp::DeclareLength(length) => {
+                if offset != 0 {
+                    Err(invalid)?;
+                }
+                declared = Some(length);
+            }
+        }
+    }
+    Err(DepexError::MissingEnd)
+}
+
+// Decodes the opcode at offset, returning it with its length including operands.
+fn decode(expression: &[u8], offset: usize) -> Result<(DepexOp<'_>, usize), DepexError> {
+    let opcode = expression[offset];
+    let operand = &expression[offset + 1..];
+    let truncated = DepexError::Truncated { offset };
+    let op = match opcode {
+        EFI_FMP_DEP_PUSH_GUID => {
+            let bytes = operand.get(..size_of::<efi::Guid>()).ok_or(truncated)?;
+            return Ok((DepexOp::PushGuid(efi::Guid::from_bytes(bytes.try_into().unwrap())), 1 + bytes.len()));
+        }
+        EFI_FMP_DEP_PUSH_VERSION | EFI_FMP_DEP_DECLARE_LENGTH => {
+            let bytes = operand.get(..size_of::<u32>()).ok_or(truncated)?;
+            let value = u32::from_le_bytes(bytes.try_into().unwrap());
+            let op = match opcode {
+                EFI_FMP_DEP_PUSH_VERSION => DepexOp::PushVersion(value),
+                _ => DepexOp::DeclareLength(value),
+            };
+            return Ok((op, 1 + size_of::<u32>()));
+        }
+        EFI_FMP_DEP_VERSION_STR => {
+            let length = operand.iter().position(|byte| *byte == 0).ok_or(truncated)?;
+            return Ok((DepexOp::VersionStr(&operand[..length]), 1 + length + 1));
+        }
+        EFI_FMP_DEP_AND => DepexOp::And,
+        EFI_FMP_DEP_OR => DepexOp::Or,
+        EFI_FMP_DEP_NOT => DepexOp::Not,
+        EFI_FMP_DEP_TRUE => DepexOp::True,
+        EFI_FMP_DEP_FALSE => DepexOp::False,
+        EFI_FMP_DEP_EQ => DepexOp::Eq,
+        EFI_FMP_DEP_GT => DepexOp::Gt,
+        EFI_FMP_DEP_GTE => DepexOp::Gte,
+        EFI_FMP_DEP_LT => DepexOp::Lt,
+        EFI_FMP_DEP_LTE => DepexOp::Lte,
+        EFI_FMP_DEP_END => DepexOp::End,
+        _ => Err(DepexError::UnknownOpcode { offset, opcode })?,
+    };
+    Ok((op, 1))
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    use r_efi::efi;
+
+    use super::*;
+
+    const IMAGE_TYPE_ID: efi::Guid =
+        efi::Guid::from_fields(0x938b3a62, 0x3e8e, 0x4c51, 0x9a, 0xa7, &[0xbb, 0x8a, 0x2c, 0x4f, 0x2a, 0x4f]);
+
+    // "The image IMAGE_TYPE_ID is at version 0x10000 or later": PUSH_VERSION 0x10000, PUSH_GUID, GTE, END.
+    fn gte_expression() -> Vec<u8> {
+        let mut expression = vec![EFI_FMP_DEP_PUSH_VERSION, 0x00, 0x00, 0x01, 0x00, EFI_FMP_DEP_PUSH_GUID];
+        expression.extend_from_slice(IMAGE_TYPE_ID.as_bytes());
+        expression.extend_from_slice(&[EFI_FMP_DEP_GTE, EFI_FMP_DEP_END]);
+        expression
+    }
+
+    #[test]
+    fn expression_should_decode_and_end_at_end_opcode() {
+        let mut bytes = gte_expression();
+        let length = bytes.len();
+        // Bytes after END belong to the image.
+        bytes.extend_from_slice(&[0xFF, 0xFF]);
+        assert_eq!(parse(&bytes), Ok(&bytes[..length]));
+
+        let ops: Vec<_> = opcodes(&bytes).map(Result::unwrap).collect();
+        assert_eq!(
+            ops,
+            [
+                (0, DepexOp::PushVersion(0x10000)),
+                (5, DepexOp::PushGuid(IMAGE_TYPE_ID)),
+                (22, DepexOp::Gte),
+                (23, DepexOp::End)
+            ]
+        );
+    }
+
+    #[test]
+    fn every_opcode_should_parse() {
+        let mut bytes = vec![EFI_FMP_DEP_DECLARE_LENGTH, 0, 0, 0, 0];
+        bytes.extend_from_slice(&[EFI_FMP_DEP_VERSION_STR, b'1', b'.', b'0', 0]);
+        for comparison in [EFI_FMP_DEP_EQ, EFI_FMP_DEP_GT, EFI_FMP_DEP_GTE, EFI_FMP_DEP_LT, EFI_FMP_DEP_LTE] {
+            bytes.extend_from_slice(&[EFI_FMP_DEP_PUSH_VERSION, 1, 0, 0, 0, EFI_FMP_DEP_PUSH_VERSION, 2, 0, 0, 0]);
+            bytes.push(comparison);
+        }
+        bytes.extend_from_slice(&[EFI_FMP_DEP_AND, EFI_FMP_DEP_OR, EFI_FMP_DEP_TRUE, EFI_FMP_DEP_FALSE]);
+        bytes.extend_from_slice(&[EFI_FMP_DEP_NOT, EFI_FMP_DEP_AND, EFI_FMP_DEP_OR, EFI_FMP_DEP_AND]);
+        bytes.extend_from_slice(&[EFI_FMP_DEP_OR, EFI_FMP_DEP_END]);
+        let length = bytes.len() as u32;
+        bytes[1..5].copy_from_slice(&length.to_le_bytes());
+        assert_eq!(parse(&bytes), Ok(&bytes[..]));
+        assert!(opcodes(&bytes).any(|op| op == Ok((5, DepexOp::VersionStr(b"1.0")))));
+
+        bytes[1] += 1;
+        assert_eq!(parse(&bytes), Err(DepexError::LengthMismatch { declared: length + 1, length: length as usize }));
+    }
+
+    #[test]
+    fn truncated_expressions_should_be_rejected() {
+        let bytes = gte_expression();
+        assert_eq!(parse(&bytes[..bytes.len() - 1]), Err(DepexError::MissingEnd));
+        assert_eq!(parse(&[]), Err(DepexError::MissingEnd));
+        // Operands cut short.
+        assert_eq!(parse(&bytes[..3]), Err(DepexError::Truncated { offset: 0 }));
+        assert_eq!(parse(&bytes[..20]), Err(DepexError::Truncated { offset: 5 }));
+        assert_eq!(parse(&[EFI_FMP_DEP_VERSION_STR, b'1']), Err(DepexError::Truncated { offset: 0 }));
+    }
+
+    #[test]
+    fn malformed_expressions_should_be_rejected() {
+        assert_eq!(parse(&[0x0F]), Err(DepexError::UnknownOpcode { offset: 0, opcode: 0x0F }));
+        // END with an empty stack, a version, or more than one value.
+        assert_eq!(parse(&[EFI_FMP_DEP_END]), Err(DepexError::InvalidOperands { offset: 0 }));
+        assert_eq!(
+            parse(&[EFI_FMP_DEP_PUSH_VERSION, 1, 0, 0, 0, EFI_FMP_DEP_END]),
+            Err(DepexError::InvalidOperands { offset: 5 })
+        );
+        assert_eq!(
+            parse(&[EFI_FMP_DEP_TRUE, EFI_FMP_DEP_TRUE, EFI_FMP_DEP_END]),
+            Err(DepexError::InvalidOperands { offset: 2 })
+        );
+        // Booleans compared, and versions combined.
+        assert_eq!(
+            parse(&[EFI_FMP_DEP_TRUE, EFI_FMP_DEP_FALSE, EFI_FMP_DEP_EQ, EFI_FMP_DEP_END]),
+            Err(DepexError::InvalidOperands { offset: 2 })
+        );
+        assert_eq!(
+            parse(&[EFI_FMP_DEP_PUSH_VERSION, 1, 0, 0, 0, EFI_FMP_DEP_NOT, EFI_FMP_DEP_END]),
+            Err(DepexError::InvalidOperands { offset: 5 })
+        );
+        // DECLARE_LENGTH after the first opcode.
+        assert_eq!(
+            parse(&[EFI_FMP_DEP_TRUE, EFI_FMP_DEP_DECLARE_LENGTH, 7, 0, 0, 0, EFI_FMP_DEP_END]),
+            Err(DepexError::InvalidOperands { offset: 1 })
+        );
+    }
+}
@@ -0,0 +1,476 @@
+//! Capsule Result Variables
+//!
+//! After processing a capsule, firmware records the outcome in a `Capsule####` variable with the
+//! [`CAPSULE_REPORT_GUID`] vendor GUID, where `####` counts up to the maximum given by the `CapsuleMax` variable and
+//! wraps around. Each variable holds an [`ResultVariableHeader`] followed, for FMP capsules, by an
+//! [`ResultVariableFmp`] and two null-terminated UCS-2 strings: the file name of the capsule and the text of the
+//! device path of the updated device. Either string may be empty.
+//!
+//! [`CapsuleResult`] builds the contents of a variable from typed fields and decodes them back. Result variables are
+//! read back from flash and may have been written by other firmware, so decoding checks every size and requires both
+//! strings to be terminated within the variable; writers that end the variable before either string are accepted,
+//! and the missing strings decode as empty.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/08_Services_Runtime_Services.html#uefi-variable-reporting-on-the-success-or-any-errors-encountered-in-processing-of-capsules-after-restart>
+//!
+//! ## Example
+//! ```
+//! use mu_pi::capsule::result::{result_variable_name, CapsuleResult};
+//! use r_efi::efi;
+//!
+//! let result = CapsuleResult::new(mu_pi::capsule::fmp::CAPSULE_ID_GUID, Default::default(), efi::Status::SUCCESS);
+//! let contents = result.encode().unwrap();
+//! assert_eq!(CapsuleResult::decode(&contents).unwrap().capsule_status, efi::Status::SUCCESS);
+//! assert_eq!(result_variable_name(0x1a), "Capsule001a");
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+extern crate alloc;
+
+use alloc::{format, string::String, vec::Vec};
+use core::{fmt, mem::size_of};
+
+use r_efi::efi;
+
+use super::fmp::CAPSULE_ID_GUID;
+
+/// Vendor GUID of the capsule result variables (EFI_CAPSULE_REPORT_GUID).
+pub const CAPSULE_REPORT_GUID: efi::Guid =
+    efi::Guid::from_fields(0x39b68c46, 0xf7fb, 0x441b, 0xb6, 0xec, &[0x16, 0xb0, 0xf6, 0x98, 0x21, 0xf3]);
+
+/// Name of the variable holding the name of the last result variable the firmware may use, e.g. `Capsule0009`.
+pub const CAPSULE_MAX_VARIABLE_NAME: &str = "CapsuleMax";
+
+/// Name of the variable holding the name of the result variable written last.
+pub const CAPSULE_LAST_VARIABLE_NAME: &str = "CapsuleLast";
+
+/// The latest [`ResultVariableFmp`] version.
+pub const RESULT_VARIABLE_FMP_VERSION: u16 = 1;
+
+/// The start of every capsule result variable (EFI_CAPSULE_RESULT_VARIABLE_HEADER).
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct ResultVariableHeader {
+    /// Size of the variable, including this header.
+    pub variable_total_size: u32,
+    pub reserved: u32,
+    pub capsule_guid: efi::Guid,
+    /// When the capsule was processed.
+    pub capsule_processed: efi::Time,
+    pub capsule_status: efi::Status,
+}
+
+/// The result of an FMP capsule (EFI_CAPSULE_RESULT_VARIABLE_FMP), following the [`ResultVariableHeader`]. It is
+/// followed by the capsule file name (CapsuleFileName) and target (CapsuleTarget) strings.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResultVariableFmp {
+    pub version: u16,
+    /// The index of the payload item in the capsule.
+    pub payload_index: u8,
+    pub update_image_index: u8,
+    pub update_image_type_id: efi::Guid,
+}
+
+/// Errors reported when encoding or decoding a capsule result variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResultVariableError {
+    /// The variable of the given size is too small for the header, or for the FMP result it must contain.
+    Truncated(usize),
+    /// The total size in the header is smaller than the header or larger than the variable.
+    InvalidTotalSize(u32),
+    /// The FMP result version is not [`RESULT_VARIABLE_FMP_VERSION`].
+    UnsupportedVersion(u16),
+    /// A string is not null-terminated within the variable, or has an odd number of bytes.
+    UnterminatedString,
+    /// A string to encode contains a null character.
+    InvalidString,
+    /// The encoded variable would be larger than 4 GiB.
+    TooLarge,
+}
+
+impl fmt::Display for ResultVariableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResultVariableError::Truncated(size) => {
+                write!(f, "capsule result variable of {size:#x} bytes is truncated")
+            }
+            ResultVariableError::InvalidTotalSize(size) => {
+                write!(f, "invalid capsule result variable total size {size:#x}")
+            }
+            ResultVariableError::UnsupportedVersion(version) => {
+                write!(f, "unsupported capsule result FMP version {version}")
+            }
+            ResultVariableError::UnterminatedString => write!(f, "capsule result string is not null-terminated"),
+            ResultVariableError::InvalidString => write!(f, "capsule result string contains a null character"),
+            ResultVariableError::TooLarge => write!(f, "capsule result variable is too large"),
+        }
+    }
+}
+
+impl From<ResultVariableError> for efi::Status {
+    fn from(_: ResultVariableError) -> Self {
+        efi::Status::INVALID_PARAMETER
+    }
+}
+
+/// The FMP-specific part of a capsule result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FmpResult {
+    pub payload_index: u8,
+    pub update_image_index: u8,
+    pub update_image_type_id: efi::Guid,
+    /// The file name of the capsule, if it was loaded from a file.
+    pub capsule_file_name: String,
+    /// The text of the device path of the updated device.
+    pub capsule_target: String,
+}
+
+/// The contents of a capsule result variable.
+#[derive(Debug, Clone)]
+pub struct CapsuleResult {
+    pub capsule_guid: efi::Guid,
+    pub capsule_processed: efi::Time,
+    pub capsule_status: efi::Status,
+    /// The FMP result, if the variable has one. Encoded and decoded only for capsules with the FMP
+    /// [`CAPSULE_ID_GUID`]; the contents that follow the header for other capsules are not decoded.
+    pub fmp: Option<FmpResult>,
+}
+
+impl CapsuleResult {
+    /// Creates a result without an FMP result.
+    pub fn new(capsule_guid: efi::Guid, capsule_processed: efi::Time, capsule_status: efi::Status) -> Self {
+        Self { capsule_guid, capsule_processed, capsule_status, fmp: None }
+    }
+
+    /// Adds an FMP result.
+    pub fn with_fmp(mut self, fmp: FmpResult) -> Self {
+        self.fmp = Some(fmp);
+        self
+    }
+
+    /// Returns the contents of the variable.
+    pub fn encode(&self) -> Result<Vec<u8>, ResultVariableError> {
+        let mut bytes = Vec::with_capacity(size_of::<ResultVariableHeader>() + size_of::<ResultVariableFmp>());
+        // The total size is filled in last.
+        bytes.extend_from_slice(&[0; 8]);
+        bytes.extend_from_slice(self.capsule_guid.as_bytes());
+        encode_time(&mut bytes, &self.capsule_processed);
+        bytes.extend_from_slice(&self.capsule_status.as_usize().to_le_bytes());
+        debug_assert_eq!(bytes.len(), size_of::<ResultVariableHeader>());
+
+        if let Some(fmp) = self.fmp.as_ref().filter(|_| self.capsule_guid == CAPSULE_ID_GUID) {
+            bytes.extend_from_slice(&RESULT_VARIABLE_FMP_VERSION.to_le_bytes());
+            bytes.extend_from_slice(&[fmp.payload_index, fmp.update_image_index]);
+            bytes.extend_from_slice(fmp.update_image_type_id.as_bytes());
+            encode_string(&mut bytes, &fmp.capsule_file_name)?;
+            encode_string(&mut bytes, &fmp.capsule_target)?;
+        }
+
+        let total_size = u32::try_from(bytes.len()).map_err(|_| ResultVariableError::TooLarge)?;
+        bytes[..4].copy_from_slice(&total_size.to_le_bytes());
+        Ok(bytes)
+    }
+
+    /// Decodes the contents of a variable. Bytes past the total size in the header are ignored.
+    pub fn decode(bytes: &[u8]) -> Result<Self, ResultVariableError> {
+        let mut reader = Reader { bytes, offset: 0 };
+        let header_size = size_of::<ResultVariableHeader>();
+        if bytes.len() < header_size {
+            Err(ResultVariableError::Truncated(bytes.len()))?;
+        }
+        let total_size = reader.u32()?;
+        if (total_size as usize) < header_size || total_size as usize > bytes.len() {
+            Err(ResultVariableError::InvalidTotalSize(total_size))?;
+        }
+        let mut reader = Reader { bytes: &bytes[..total_size as usize], offset: 4 };
+        reader.take(4)?;
+        let capsule_guid = reader.guid()?;
+        let capsule_processed = reader.time()?;
+        let capsule_status = efi::Status::from_usize(usize::from_le_bytes(reader.array()?));
+        let mut result = Self::new(capsule_guid, capsule_processed, capsule_status);
+        if capsule_guid != CAPSULE_ID_GUID || total_size as usize == header_size {
+            return Ok(result);
+        }
+
+        let version = u16::from_le_bytes(reader.array()?);
+        if version != RESULT_VARIABLE_FMP_VERSION {
+            Err(ResultVariableError::UnsupportedVersion(version))?;
+        }
+        let [payload_index, update_image_index] = reader.array()?;
+        let update_image_type_id = reader.guid()?;
+        let capsule_file_name = reader.string()?;
+        let capsule_target = reader.string()?;
+        result.fmp = Some(FmpResult {
+            payload_index,
+            update_image_index,
+            update_image_type_id,
+            capsule_file_name,
+            capsule_target,
+        });
+        Ok(result)
+    }
+}
+
+/// Returns the name of the result variable with the given number, e.g. `Capsule000a`.
+pub fn result_variable_name(index: u16) -> String {
+    format!("Capsule{index:04x}")
+}
+
+/// Returns the number of a result variable name of the form `Capsule####`, such as the contents of the `CapsuleMax`
+/// and `CapsuleLast` variables. The hexadecimal digits may be of either case.
+pub fn parse_result_variable_name(name: &str) -> Option<u16> {
+    let digits = name.strip_prefix("Capsule")?;
+    if digits.len() != 4 || !digits.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+        return None;
+    }
+    u16::from_str_radix(digits, 16).ok()
+}
+
+fn encode_time(bytes: &mut Vec<u8>, time: &efi::Time) {
+    bytes.extend_from_slice(&time.year.to_le_bytes());
+    bytes.extend_from_slice(&[time.month, time.day, time.hour, time.minute, time.second, 0]);
+    bytes.extend_from_slice(&time.nanosecond.to_le_bytes());
+    bytes.extend_from_slice(&time.timezone.to_le_bytes());
+    bytes.extend_from_slice(&[time.daylight, 0]);
+}
+
+fn encode_string(bytes: &mut Vec<u8>, string: &str) -> Result<(), ResultVariableError> {
+    if string.contains('\0') {
+        Err(ResultVariableError::InvalidString)?;
+    }
+    bytes.extend(string.encode_utf16().chain([0]).flat_map(u16::to_le_bytes));
+    Ok(())
+}
+
+// Reads the fields of a variable in order; every read is checked against the end of the variable.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], ResultVariableError> {
+        let bytes = self
+            .bytes
+            .get(self.offset..self.offset + length)
+            .ok_or(ResultVariableError::Truncated(self.bytes.len()))?;
+        self.offset += length;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], ResultVariableError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u32(&mut self) -> Result<u32, ResultVariableError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn guid(&mut self) -> Result<efi::Guid, ResultVariableError> {
+        Ok(efi::Guid::from_bytes(&self.array()?))
+    }
+
+    fn time(&mut self) -> Result<efi::Time, ResultVariableError> {
+        let year = u16::from_le_bytes(self.array()?);
+        let [month, day, hour, minute, second, pad1] = self.array()?;
+        let nanosecond = self.u32()?;
+        let timezone = i16::from_le_bytes(self.array()?);
+        let [daylight, pad2] = self.array()?;
+        Ok(efi::Time { year, month, day, hour, minute, second, pad1, nanosecond, timezone, daylight, pad2 })
+    }
+
+    // A null-terminated UCS-2 string, or an empty string at the end of the variable. Invalid characters are
+    // replaced rather than rejected, so that a result can still be audited.
+    fn string(&mut self) -> Result<String, ResultVariableError> {
+        let rest = &self.bytes[self.offset..];
+        if rest.is_empty() {
+            return Ok(String::new());
+        }
+        let mut chars = rest.chunks(2).map(|pair| match pair {
+            [low, high] => Ok(u16::from_le_bytes([*low, *high])),
+            _ => Err(ResultVariableError::UnterminatedString),
+        });
+        let mut units = Vec::new();
+        loop {
+            match chars.next().ok_or(ResultVariableError::UnterminatedString)?? {
+                0 => break,
+                unit => units.push(unit),
+            }
+        }
+        self.offset += (units.len() + 1) * 2;
+        Ok(char::decode_utf16(units).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    use alloc::{string::String, vec::Vec};
+    use core::mem::size_of;
+
+    use r_efi::efi;
+
+    use super::{
+        parse_result_variable_name, result_variable_name, CapsuleResult, FmpResult, ResultVariableError,
+        ResultVariableFmp, ResultVariableHeader,
+    };
+    use crate::capsule::fmp::CAPSULE_ID_GUID;
+
+    const IMAGE_TYPE_ID: efi::Guid =
+        efi::Guid::from_fields(0x3f2b7c1e, 0x8a4d, 0x4e6f, 0x9b, 0x21, &[0x5c, 0x0d, 0x7e, 0x3a, 0x11, 0x42]);
+    const OTHER_GUID: efi::Guid = efi::Guid::from_fields(1, 2, 3, 4, 5, &[6, 7, 8, 9, 10, 11]);
+
+    fn time() -> efi::Time {
+        efi::Time {
+            year: 2024,
+            month: 5,
+            day: 17,
+            hour: 13,
+            minute: 45,
+            second: 30,
+            nanosecond: 123_456_789,
+            timezone: -480,
+            daylight: 1,
+            ..Default::default()
+        }
+    }
+
+    fn fmp_result(file_name: &str, target: &str) -> CapsuleResult {
+        CapsuleResult::new(CAPSULE_ID_GUID, time(), efi::Status::ABORTED).with_fmp(FmpResult {
+            payload_index: 2,
+            update_image_index: 1,
+            update_image_type_id: IMAGE_TYPE_ID,
+            capsule_file_name: String::from(file_name),
+            capsule_target: String::from(target),
+        })
+    }
+
+    fn assert_same(decoded: &CapsuleResult, expected: &CapsuleResult) {
+        assert_eq!(decoded.capsule_guid, expected.capsule_guid);
+        assert_eq!(decoded.capsule_status, expected.capsule_status);
+        let (a, b) = (decoded.capsule_processed, expected.capsule_processed);
+        assert_eq!(
+            (a.year, a.month, a.day, a.hour, a.minute, a.second, a.nanosecond, a.timezone, a.daylight),
+            (b.year, b.month, b.day, b.hour, b.minute, b.second, b.nanosecond, b.timezone, b.daylight)
+        );
+        assert_eq!(decoded.fmp, expected.fmp);
+    }
+
+    #[test]
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    fn structures_should_match_spec() {
+        assert_eq!(size_of::<ResultVariableHeader>(), 48);
+        assert_eq!(size_of::<ResultVariableFmp>(), 20);
+    }
+
+    #[test]
+    fn results_should_round_trip() {
+        for result in [
+            fmp_result("\\EFI\\UpdateCapsule\\Firmware.cap", "PciRoot(0x0)/Pci(0x1F,0x5)"),
+            fmp_result("", ""),
+            fmp_result("", "VenHw(3F2B7C1E-8A4D-4E6F-9B21-5C0D7E3A1142)"),
+            fmp_result("Fïrmwäre ✓.cap", ""),
+            CapsuleResult::new(CAPSULE_ID_GUID, time(), efi::Status::NOT_READY),
+            CapsuleResult::new(OTHER_GUID, time(), efi::Status::SUCCESS),
+        ] {
+            let bytes = result.encode().unwrap();
+            assert_eq!(u32::from_le_bytes(bytes[..4].try_into().unwrap()) as usize, bytes.len());
+            let decoded = CapsuleResult::decode(&bytes).unwrap();
+            assert_same(&decoded, &result);
+            assert_eq!(decoded.encode().unwrap(), bytes);
+        }
+
+        // The layout of the FMP result and its strings.
+        let bytes = fmp_result("a", "bc").encode().unwrap();
+        assert_eq!(bytes.len(), 48 + 20 + 4 + 6);
+        assert_eq!(bytes[4..8], [0; 4]);
+        assert_eq!(&bytes[8..24], CAPSULE_ID_GUID.as_bytes());
+        assert_eq!(bytes[40..48], efi::Status::ABORTED.as_usize().to_le_bytes());
+        assert_eq!(bytes[48..52], [1, 0, 2, 1]);
+        assert_eq!(&bytes[52..68], IMAGE_TYPE_ID.as_bytes());
+        assert_eq!(bytes[68..], [b'a', 0, 0, 0, b'b', 0, b'c', 0, 0, 0]);
+
+        // An FMP result is only encoded for FMP capsules, and trailing bytes of other capsules are ignored.
+        let mut result = fmp_result("a", "b");
+        result.capsule_guid = OTHER_GUID;
+        let mut bytes = result.encode().unwrap();
+        assert_eq!(bytes.len(), 48);
+        bytes[0] = 52;
+        bytes.extend_from_slice(&[0xFF; 8]);
+        assert!(CapsuleResult::decode(&bytes).unwrap().fmp.is_none());
+    }
+
+    #[test]
+    fn missing_strings_should_decode_as_empty() {
+        let bytes = fmp_result("a", "b").encode().unwrap();
+        for (length, file_name, target) in [(68, "", ""), (72, "a", "")] {
+            let mut bytes = bytes[..length].to_vec();
+            bytes[..4].copy_from_slice(&(length as u32).to_le_bytes());
+            let fmp = CapsuleResult::decode(&bytes).unwrap().fmp.unwrap();
+            assert_eq!((fmp.capsule_file_name.as_str(), fmp.capsule_target.as_str()), (file_name, target));
+        }
+        // Bytes past the total size, as a variable buffer larger than the variable has, are ignored.
+        let mut padded = bytes.clone();
+        padded.extend_from_slice(&[0xAA; 16]);
+        assert_eq!(CapsuleResult::decode(&padded).unwrap().fmp.unwrap().capsule_target, "b");
+    }
+
+    #[test]
+    fn malformed_variables_should_be_rejected() {
+        let bytes = fmp_result("name", "target").encode().unwrap();
+        let with_total_size = |length: usize, total_size: u32| {
+            let mut bytes: Vec<u8> = bytes[..length].to_vec();
+            bytes[..4].copy_from_slice(&total_size.to_le_bytes());
+            CapsuleResult::decode(&bytes)
+        };
+
+        for length in 0..48 {
+            assert_eq!(CapsuleResult::decode(&bytes[..length]).unwrap_err(), ResultVariableError::Truncated(length));
+        }
+        assert_eq!(with_total_size(48, 47).unwrap_err(), ResultVariableError::InvalidTotalSize(47));
+        assert_eq!(with_total_size(48, 49).unwrap_err(), ResultVariableError::InvalidTotalSize(49));
+        // An FMP result cut short.
+        for length in 49..68 {
+            assert_eq!(with_total_size(length, length as u32).unwrap_err(), ResultVariableError::Truncated(length));
+        }
+        // Strings cut before their terminator, or with an odd number of bytes.
+        for length in (69..bytes.len()).filter(|length| *length != 78) {
+            assert_eq!(
+                with_total_size(length, length as u32).unwrap_err(),
+                ResultVariableError::UnterminatedString,
+                "length {length}"
+            );
+        }
+
+        let mut bytes = bytes.clone();
+        bytes[48] = 2;
+        assert_eq!(CapsuleResult::decode(&bytes).unwrap_err(), ResultVariableError::UnsupportedVersion(2));
+        assert_eq!(fmp_result("a\0b", "").encode().unwrap_err(), ResultVariableError::InvalidString);
+        assert_eq!(efi::Status::from(ResultVariableError::TooLarge), efi::Status::INVALID_PARAMETER);
+    }
+
+    #[test]
+    fn unpaired_surrogates_should_be_replaced() {
+        let mut bytes = fmp_result("ab", "").encode().unwrap();
+        bytes[68..70].copy_from_slice(&0xD800u16.to_le_bytes());
+        assert_eq!(CapsuleResult::decode(&bytes).unwrap().fmp.unwrap().capsule_file_name, "\u{FFFD}b");
+    }
+
+    #[test]
+    fn variable_names_should_use_four_hex_digits() {
+        assert_eq!(result_variable_name(0), "Capsule0000");
+        assert_eq!(result_variable_name(0xabcd), "Capsuleabcd");
+        assert_eq!(parse_result_variable_name("Capsule00FE"), Some(0xfe));
+        assert_eq!(parse_result_variable_name(&result_variable_name(0x1234)), Some(0x1234));
+        for name in ["CapsuleMax", "CapsuleLast", "Capsule123", "Capsule12345", "Capsule+123", "Boot0001"] {
+            assert_eq!(parse_result_variable_name(name), None);
+        }
+    }
+}
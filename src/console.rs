@@ -0,0 +1,21 @@
+//! Console Support
+//!
+//! Wrappers around the UEFI console protocols for use by firmware components that need to interact with the console
+//! before an operating system is running.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/12_Protocols_Console_Support.html>.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+pub mod splitter;
+pub mod text_input;
+pub mod text_output;
+
+pub use splitter::ConSplitter;
+pub use text_input::{InputKey, TextInput};
+pub use text_output::TextOutput;
@@ -0,0 +1,195 @@
+//! Console Splitter
+//!
+//! Provides a passthrough that duplicates text output to several console output devices.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use r_efi::efi;
+
+use super::TextOutput;
+
+/// Duplicates text output to a set of [`TextOutput`] devices.
+///
+/// A failure to write to one output does not prevent the remaining outputs from being written. The most recent failure
+/// is recorded and can be retrieved with [`ConSplitter::last_error`].
+///
+/// ## Example
+///```no_run
+/// use core::fmt::Write;
+/// use mu_pi::console::{ConSplitter, TextOutput};
+/// use r_efi::efi;
+///
+/// fn example(system_table: &efi::SystemTable) {
+///   let mut splitter = ConSplitter::new();
+///   splitter.add_output(unsafe { TextOutput::new(system_table.con_out as *const _) });
+///   splitter.add_output(unsafe { TextOutput::new(system_table.std_err as *const _) });
+///   writeln!(splitter, "written to all consoles").unwrap();
+/// }
+///```
+#[derive(Debug, Default)]
+pub struct ConSplitter {
+    outputs: Vec<TextOutput>,
+    last_error: Option<(usize, efi::Status)>,
+}
+
+impl ConSplitter {
+    /// Instantiates a new ConSplitter with no outputs.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an output device.
+    pub fn add_output(&mut self, output: TextOutput) {
+        self.outputs.push(output);
+    }
+
+    /// Removes the output device at `index`, returning it if it exists.
+    pub fn remove_output(&mut self, index: usize) -> Option<TextOutput> {
+        (index < self.outputs.len()).then(|| self.outputs.remove(index))
+    }
+
+    /// Returns the current output devices.
+    pub fn outputs(&self) -> &[TextOutput] {
+        &self.outputs
+    }
+
+    /// Returns the index of the output and the error status of the most recent failed write, if any.
+    pub fn last_error(&self) -> Option<(usize, efi::Status)> {
+        self.last_error
+    }
+}
+
+impl fmt::Write for ConSplitter {
+    /// Writes the string to every output device.
+    ///
+    /// Returns an error only if there is at least one output device and every device failed the write.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut written = false;
+        for (index, output) in self.outputs.iter().enumerate() {
+            let status = output.write_str(s);
+            if status.is_error() {
+                self.last_error = Some((index, status));
+            } else {
+                written = true;
+            }
+        }
+        if written || self.outputs.is_empty() {
+            Ok(())
+        } else {
+            Err(fmt::Error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{cell::RefCell, fmt::Write};
+
+    use r_efi::efi;
+
+    use super::ConSplitter;
+    use crate::{
+        console::{text_output, TextOutput},
+        protocols::simple_text_output,
+    };
+
+    // A mock protocol that records its output, recovered from the protocol pointer passed to OutputString().
+    #[repr(C)]
+    struct MockOutput {
+        protocol: simple_text_output::Protocol,
+        status: efi::Status,
+        output: RefCell<Vec<u16>>,
+    }
+
+    extern "efiapi" fn output_string(
+        this: *const simple_text_output::Protocol,
+        string: *const efi::Char16,
+    ) -> efi::Status {
+        let mock = unsafe { &*(this as *const MockOutput) };
+        if mock.status.is_error() {
+            return mock.status;
+        }
+        let mut idx = 0;
+        while unsafe { *string.add(idx) } != 0 {
+            mock.output.borrow_mut().push(unsafe { *string.add(idx) });
+            idx += 1;
+        }
+        efi::Status::SUCCESS
+    }
+
+    fn mock_output(status: efi::Status) -> Box<MockOutput> {
+        let mut protocol = text_output::tests::mock_protocol();
+        protocol.output_string = output_string;
+        Box::new(MockOutput { protocol, status, output: RefCell::new(Vec::new()) })
+    }
+
+    fn text_output(mock: &MockOutput) -> TextOutput {
+        unsafe { TextOutput::new(&mock.protocol) }
+    }
+
+    #[test]
+    fn splitter_should_write_to_all_outputs() {
+        let mocks = [mock_output(efi::Status::SUCCESS), mock_output(efi::Status::SUCCESS)];
+        let mut splitter = ConSplitter::new();
+        mocks.iter().for_each(|mock| splitter.add_output(text_output(mock)));
+
+        writeln!(splitter, "boot option {}", 3).unwrap();
+        for mock in &mocks {
+            assert_eq!(String::from_utf16(&mock.output.borrow()).unwrap(), "boot option 3\r\n");
+        }
+        assert_eq!(splitter.last_error(), None);
+    }
+
+    #[test]
+    fn splitter_should_continue_after_failed_output() {
+        let mocks = [
+            mock_output(efi::Status::SUCCESS),
+            mock_output(efi::Status::DEVICE_ERROR),
+            mock_output(efi::Status::SUCCESS),
+        ];
+        let mut splitter = ConSplitter::new();
+        mocks.iter().for_each(|mock| splitter.add_output(text_output(mock)));
+
+        write!(splitter, "hello").unwrap();
+        assert_eq!(String::from_utf16(&mocks[0].output.borrow()).unwrap(), "hello");
+        assert!(mocks[1].output.borrow().is_empty());
+        assert_eq!(String::from_utf16(&mocks[2].output.borrow()).unwrap(), "hello");
+        assert_eq!(splitter.last_error(), Some((1, efi::Status::DEVICE_ERROR)));
+    }
+
+    #[test]
+    fn splitter_should_fail_when_all_outputs_fail() {
+        let mock = mock_output(efi::Status::DEVICE_ERROR);
+        let mut splitter = ConSplitter::new();
+        assert!(write!(splitter, "nobody listening").is_ok());
+
+        splitter.add_output(text_output(&mock));
+        assert!(write!(splitter, "hello").is_err());
+    }
+
+    #[test]
+    fn splitter_should_add_and_remove_outputs() {
+        let mocks = [mock_output(efi::Status::SUCCESS), mock_output(efi::Status::SUCCESS)];
+        let mut splitter = ConSplitter::new();
+        mocks.iter().for_each(|mock| splitter.add_output(text_output(mock)));
+        assert_eq!(splitter.outputs().len(), 2);
+
+        let removed = splitter.remove_output(0).unwrap();
+        assert_eq!(removed.protocol(), &mocks[0].protocol as *const _);
+        assert!(splitter.remove_output(1).is_none());
+
+        write!(splitter, "x").unwrap();
+        assert!(mocks[0].output.borrow().is_empty());
+        assert_eq!(String::from_utf16(&mocks[1].output.borrow()).unwrap(), "x");
+    }
+}
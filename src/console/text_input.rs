@@ -0,0 +1,278 @@
+//! Console Text Input
+//!
+//! Provides a wrapper over the Simple Text Input Protocol (e.g. the ConIn instance in the system table).
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{char, fmt};
+
+use r_efi::efi;
+
+use crate::protocols::simple_text_input::{
+    InputKey as EfiInputKey, Protocol as SimpleTextInputProtocol, SCAN_ESC, SCAN_NULL,
+};
+
+const CHAR_CARRIAGE_RETURN: u16 = 0x000D;
+const CHAR_LINEFEED: u16 = 0x000A;
+
+/// A keystroke read from a text input device.
+#[derive(Clone, Copy, Default)]
+#[repr(transparent)]
+pub struct InputKey(pub EfiInputKey);
+
+impl InputKey {
+    /// Instantiates a new InputKey from a scan code and a UCS-2 character.
+    pub fn new(scan_code: u16, unicode_char: u16) -> Self {
+        Self(EfiInputKey { scan_code, unicode_char })
+    }
+
+    /// Returns the scan code of the key.
+    pub fn scan_code(&self) -> u16 {
+        self.0.scan_code
+    }
+
+    /// Returns the UCS-2 character of the key.
+    pub fn unicode_char(&self) -> u16 {
+        self.0.unicode_char
+    }
+
+    /// Indicates whether the key is the enter key.
+    pub fn is_enter(&self) -> bool {
+        self.scan_code() == SCAN_NULL && matches!(self.unicode_char(), CHAR_CARRIAGE_RETURN | CHAR_LINEFEED)
+    }
+
+    /// Indicates whether the key is the escape key.
+    pub fn is_escape(&self) -> bool {
+        self.scan_code() == SCAN_ESC
+    }
+
+    /// Returns the character of the key if it is a printable character.
+    pub fn is_printable(&self) -> Option<char> {
+        if self.scan_code() != SCAN_NULL {
+            return None;
+        }
+        char::from_u32(self.unicode_char() as u32).filter(|c| !c.is_control())
+    }
+}
+
+impl PartialEq for InputKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.scan_code() == other.scan_code() && self.unicode_char() == other.unicode_char()
+    }
+}
+
+impl Eq for InputKey {}
+
+impl fmt::Debug for InputKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InputKey")
+            .field("scan_code", &format_args!("{:#x}", self.scan_code()))
+            .field("unicode_char", &format_args!("{:#x}", self.unicode_char()))
+            .finish()
+    }
+}
+
+/// Wrapper over a Simple Text Input Protocol instance.
+///
+/// Iterating a TextInput returns keystrokes until no more are pending.
+///
+/// ## Example
+///```no_run
+/// use mu_pi::console::TextInput;
+/// use r_efi::efi;
+///
+/// fn example(system_table: &efi::SystemTable) {
+///   let con_in = unsafe { TextInput::new(system_table.con_in as *const _) };
+///   for key in con_in {
+///     if let Some(c) = key.is_printable() {
+///       // handle c
+///     }
+///   }
+/// }
+///```
+#[derive(Clone, Copy)]
+pub struct TextInput {
+    protocol: *const SimpleTextInputProtocol,
+    wait_for_event: Option<efi::BootWaitForEvent>,
+}
+
+impl TextInput {
+    /// Instantiates a new TextInput.
+    ///
+    /// ## Safety
+    /// Caller must ensure that `protocol` points to a valid Simple Text Input Protocol instance for as long as the
+    /// TextInput (or any copy of it) is used.
+    pub unsafe fn new(protocol: *const SimpleTextInputProtocol) -> Self {
+        Self { protocol, wait_for_event: None }
+    }
+
+    /// Associates boot services with the TextInput, which is required for [`Self::wait_for_key`].
+    pub fn with_boot_services(mut self, boot_services: &efi::BootServices) -> Self {
+        self.wait_for_event = Some(boot_services.wait_for_event);
+        self
+    }
+
+    /// Returns the wrapped protocol pointer.
+    pub fn protocol(&self) -> *const SimpleTextInputProtocol {
+        self.protocol
+    }
+
+    fn protocol_ref(&self) -> &SimpleTextInputProtocol {
+        //Safety: the constructor contract guarantees the pointer is valid.
+        unsafe { &*self.protocol }
+    }
+
+    /// Reads the next keystroke from the input device.
+    ///
+    /// Returns `Err(efi::Status::NOT_READY)` if no keystroke is pending.
+    pub fn read_key(&self) -> Result<InputKey, efi::Status> {
+        let mut key = InputKey::default();
+        let status = (self.protocol_ref().read_key_stroke)(self.protocol, &mut key.0);
+        if status.is_error() {
+            Err(status)?;
+        }
+        Ok(key)
+    }
+
+    /// Waits until a keystroke is pending or another event is signaled.
+    ///
+    /// `event[0]` is overwritten with the WaitForKey event of the protocol; any remaining entries are additional events
+    /// to wait on (e.g. a timeout timer). Requires boot services to have been associated with
+    /// [`Self::with_boot_services`], and must be called at TPL_APPLICATION.
+    pub fn wait_for_key(&self, event: &mut [efi::Event]) -> efi::Status {
+        let Some(wait_for_event) = self.wait_for_event else {
+            return efi::Status::UNSUPPORTED;
+        };
+        if event.is_empty() {
+            return efi::Status::INVALID_PARAMETER;
+        }
+        event[0] = self.protocol_ref().wait_for_key;
+        let mut index = 0;
+        wait_for_event(event.len(), event.as_mut_ptr(), &mut index)
+    }
+}
+
+impl fmt::Debug for TextInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TextInput").field("protocol", &self.protocol).finish_non_exhaustive()
+    }
+}
+
+impl Iterator for TextInput {
+    type Item = InputKey;
+
+    /// Returns the next pending keystroke, or `None` once ReadKeyStroke() reports `NOT_READY` (or any other error).
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_key().ok()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use core::{cell::RefCell, ptr};
+
+    use r_efi::efi;
+
+    use super::{InputKey, TextInput};
+    use crate::protocols::simple_text_input;
+
+    std::thread_local! {
+        pub(crate) static KEYS: RefCell<Vec<InputKey>> = RefCell::new(Vec::new());
+        static WAITED: RefCell<Vec<efi::Event>> = RefCell::new(Vec::new());
+    }
+
+    extern "efiapi" fn reset(_: *const simple_text_input::Protocol, _: efi::Boolean) -> efi::Status {
+        efi::Status::SUCCESS
+    }
+
+    pub(crate) extern "efiapi" fn read_key_stroke(
+        _: *const simple_text_input::Protocol,
+        key: *mut simple_text_input::InputKey,
+    ) -> efi::Status {
+        KEYS.with(|keys| {
+            let mut keys = keys.borrow_mut();
+            if keys.is_empty() {
+                return efi::Status::NOT_READY;
+            }
+            unsafe { key.write(keys.remove(0).0) };
+            efi::Status::SUCCESS
+        })
+    }
+
+    extern "efiapi" fn wait_for_event(count: usize, events: *mut efi::Event, index: *mut usize) -> efi::Status {
+        let events = unsafe { core::slice::from_raw_parts(events, count) };
+        WAITED.with(|waited| *waited.borrow_mut() = events.to_vec());
+        unsafe { index.write(0) };
+        efi::Status::SUCCESS
+    }
+
+    pub(crate) fn mock_protocol(wait_for_key: efi::Event) -> simple_text_input::Protocol {
+        simple_text_input::Protocol { reset, read_key_stroke, wait_for_key }
+    }
+
+    pub(crate) fn push_keys(keys: &[InputKey]) {
+        KEYS.with(|k| k.borrow_mut().extend_from_slice(keys));
+    }
+
+    #[test]
+    fn read_key_should_return_pending_keys() {
+        let protocol = mock_protocol(ptr::null_mut());
+        let con_in = unsafe { TextInput::new(&protocol) };
+        push_keys(&[InputKey::new(0, 'a' as u16)]);
+        assert_eq!(con_in.read_key(), Ok(InputKey::new(0, 'a' as u16)));
+        assert_eq!(con_in.read_key(), Err(efi::Status::NOT_READY));
+    }
+
+    #[test]
+    fn iterator_should_drain_pending_keys() {
+        let protocol = mock_protocol(ptr::null_mut());
+        let con_in = unsafe { TextInput::new(&protocol) };
+        push_keys(&[InputKey::new(0, 'h' as u16), InputKey::new(0, 'i' as u16), InputKey::new(0, 0x0D)]);
+        let keys: Vec<InputKey> = con_in.collect();
+        assert_eq!(keys.len(), 3);
+        assert_eq!(keys.iter().filter_map(InputKey::is_printable).collect::<String>(), "hi");
+        assert!(keys[2].is_enter());
+        assert_eq!(con_in.count(), 0);
+    }
+
+    #[test]
+    fn input_key_should_classify_keys() {
+        let escape = InputKey::new(0x17, 0);
+        assert!(escape.is_escape());
+        assert!(!escape.is_enter());
+        assert_eq!(escape.is_printable(), None);
+
+        let up_arrow = InputKey::new(0x01, 0);
+        assert!(!up_arrow.is_escape());
+        assert_eq!(up_arrow.is_printable(), None);
+
+        assert!(InputKey::new(0, 0x0A).is_enter());
+        assert_eq!(InputKey::new(0, 0x0D).is_printable(), None);
+        assert_eq!(InputKey::new(0, 0x08).is_printable(), None);
+        assert_eq!(InputKey::new(0, 0xE9).is_printable(), Some('\u{e9}'));
+        assert_eq!(InputKey::new(0, 0xD800).is_printable(), None);
+    }
+
+    #[test]
+    fn wait_for_key_should_wait_on_protocol_event() {
+        let key_event = 0x1000 as efi::Event;
+        let timer_event = 0x2000 as efi::Event;
+        let protocol = mock_protocol(key_event);
+
+        let con_in = unsafe { TextInput::new(&protocol) };
+        assert_eq!(con_in.wait_for_key(&mut [ptr::null_mut()]), efi::Status::UNSUPPORTED);
+
+        let con_in = TextInput { wait_for_event: Some(wait_for_event), ..con_in };
+        assert_eq!(con_in.wait_for_key(&mut []), efi::Status::INVALID_PARAMETER);
+
+        let mut events = [ptr::null_mut(), timer_event];
+        assert_eq!(con_in.wait_for_key(&mut events), efi::Status::SUCCESS);
+        assert_eq!(events[0], key_event);
+        assert_eq!(WAITED.with(|w| w.borrow().clone()), vec![key_event, timer_event]);
+    }
+}
@@ -0,0 +1,294 @@
+//! Console Text Output
+//!
+//! Provides a wrapper over the Simple Text Output Protocol (e.g. the ConOut instance in the system table) that accepts
+//! Rust strings.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::fmt;
+
+use r_efi::efi;
+
+use crate::protocols::simple_text_output::Protocol as SimpleTextOutputProtocol;
+
+// Number of UCS-2 characters (including the null terminator) converted on the stack per OutputString() call.
+const BUFFER_CHARS: usize = 128;
+
+// Replacement for characters that cannot be represented in UCS-2.
+const REPLACEMENT_CHAR: u16 = 0xFFFD;
+
+/// Wrapper over a Simple Text Output Protocol instance.
+///
+/// ## Example
+///```no_run
+/// use core::fmt::Write;
+/// use mu_pi::console::TextOutput;
+/// use r_efi::efi;
+///
+/// fn example(system_table: &efi::SystemTable) {
+///   let mut con_out = unsafe { TextOutput::new(system_table.con_out as *const _) };
+///   con_out.clear_screen();
+///   writeln!(con_out, "Hello from {}!", "DXE").unwrap();
+/// }
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct TextOutput {
+    protocol: *const SimpleTextOutputProtocol,
+}
+
+impl TextOutput {
+    /// Instantiates a new TextOutput.
+    ///
+    /// ## Safety
+    /// Caller must ensure that `protocol` points to a valid Simple Text Output Protocol instance for as long as the
+    /// TextOutput (or any copy of it) is used.
+    pub unsafe fn new(protocol: *const SimpleTextOutputProtocol) -> Self {
+        Self { protocol }
+    }
+
+    /// Returns the wrapped protocol pointer.
+    pub fn protocol(&self) -> *const SimpleTextOutputProtocol {
+        self.protocol
+    }
+
+    fn protocol_ref(&self) -> &SimpleTextOutputProtocol {
+        //Safety: the constructor contract guarantees the pointer is valid.
+        unsafe { &*self.protocol }
+    }
+
+    /// Writes a string to the output device.
+    ///
+    /// The string is converted to UCS-2 in a fixed-size stack buffer and written in chunks, so no allocation is
+    /// required. A `'\n'` is written as `"\r\n"`, and characters outside the Basic Multilingual Plane are replaced with
+    /// U+FFFD.
+    ///
+    /// Returns the first error status reported by the device, if any, otherwise the status of the last write.
+    pub fn write_str(&self, s: &str) -> efi::Status {
+        let mut buffer = [0u16; BUFFER_CHARS];
+        let mut len = 0;
+        let mut status = efi::Status::SUCCESS;
+
+        for c in s.chars() {
+            // reserve room for a CR/LF pair plus the null terminator.
+            if len + 3 > BUFFER_CHARS {
+                status = self.output(&mut buffer, len);
+                if status.is_error() {
+                    return status;
+                }
+                len = 0;
+            }
+            if c == '\n' {
+                buffer[len] = '\r' as u16;
+                len += 1;
+            }
+            buffer[len] = if (c as u32) <= 0xFFFF { c as u16 } else { REPLACEMENT_CHAR };
+            len += 1;
+        }
+
+        if len > 0 {
+            status = self.output(&mut buffer, len);
+        }
+        status
+    }
+
+    /// Writes a single UCS-2 character to the output device.
+    pub fn write_char(&self, c: u16) -> efi::Status {
+        let mut buffer = [c, 0];
+        self.output(&mut buffer, 1)
+    }
+
+    // Null-terminates the first len characters of the buffer and passes them to OutputString().
+    fn output(&self, buffer: &mut [u16], len: usize) -> efi::Status {
+        buffer[len] = 0;
+        (self.protocol_ref().output_string)(self.protocol, buffer.as_ptr())
+    }
+
+    /// Clears the output device display to the currently selected background color.
+    pub fn clear_screen(&self) -> efi::Status {
+        (self.protocol_ref().clear_screen)(self.protocol)
+    }
+
+    /// Sets the foreground (0-15) and background (0-7) colors for subsequent output.
+    pub fn set_attribute(&self, foreground: u8, background: u8) -> efi::Status {
+        let attribute = (foreground & 0x0F) as usize | (((background & 0x07) as usize) << 4);
+        (self.protocol_ref().set_attribute)(self.protocol, attribute)
+    }
+
+    /// Sets the current cursor position.
+    pub fn set_cursor_position(&self, col: usize, row: usize) -> efi::Status {
+        (self.protocol_ref().set_cursor_position)(self.protocol, col, row)
+    }
+}
+
+impl fmt::Write for TextOutput {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if TextOutput::write_str(self, s).is_error() {
+            Err(fmt::Error)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use core::{cell::RefCell, fmt::Write, ptr};
+
+    use r_efi::efi;
+
+    use super::TextOutput;
+    use crate::protocols::simple_text_output;
+
+    std::thread_local! {
+        pub(crate) static OUTPUT: RefCell<Vec<u16>> = RefCell::new(Vec::new());
+        static ATTRIBUTE: RefCell<Option<usize>> = RefCell::new(None);
+        static CURSOR: RefCell<Option<(usize, usize)>> = RefCell::new(None);
+        static CLEARED: RefCell<bool> = RefCell::new(false);
+    }
+
+    extern "efiapi" fn reset(_: *const simple_text_output::Protocol, _: efi::Boolean) -> efi::Status {
+        efi::Status::SUCCESS
+    }
+
+    pub(crate) extern "efiapi" fn output_string(
+        _: *const simple_text_output::Protocol,
+        string: *const efi::Char16,
+    ) -> efi::Status {
+        let mut idx = 0;
+        OUTPUT.with(|output| loop {
+            let c = unsafe { *string.add(idx) };
+            if c == 0 {
+                break;
+            }
+            output.borrow_mut().push(c);
+            idx += 1;
+        });
+        efi::Status::SUCCESS
+    }
+
+    extern "efiapi" fn failing_output_string(
+        _: *const simple_text_output::Protocol,
+        _: *const efi::Char16,
+    ) -> efi::Status {
+        efi::Status::DEVICE_ERROR
+    }
+
+    extern "efiapi" fn test_string(_: *const simple_text_output::Protocol, _: *const efi::Char16) -> efi::Status {
+        efi::Status::SUCCESS
+    }
+
+    extern "efiapi" fn query_mode(
+        _: *const simple_text_output::Protocol,
+        _: usize,
+        _: *mut usize,
+        _: *mut usize,
+    ) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+
+    extern "efiapi" fn set_mode(_: *const simple_text_output::Protocol, _: usize) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+
+    extern "efiapi" fn set_attribute(_: *const simple_text_output::Protocol, attribute: usize) -> efi::Status {
+        ATTRIBUTE.with(|a| *a.borrow_mut() = Some(attribute));
+        efi::Status::SUCCESS
+    }
+
+    extern "efiapi" fn clear_screen(_: *const simple_text_output::Protocol) -> efi::Status {
+        CLEARED.with(|c| *c.borrow_mut() = true);
+        efi::Status::SUCCESS
+    }
+
+    extern "efiapi" fn set_cursor_position(
+        _: *const simple_text_output::Protocol,
+        col: usize,
+        row: usize,
+    ) -> efi::Status {
+        CURSOR.with(|c| *c.borrow_mut() = Some((col, row)));
+        efi::Status::SUCCESS
+    }
+
+    extern "efiapi" fn enable_cursor(_: *const simple_text_output::Protocol, _: efi::Boolean) -> efi::Status {
+        efi::Status::SUCCESS
+    }
+
+    pub(crate) fn mock_protocol() -> simple_text_output::Protocol {
+        simple_text_output::Protocol {
+            reset,
+            output_string,
+            test_string,
+            query_mode,
+            set_mode,
+            set_attribute,
+            clear_screen,
+            set_cursor_position,
+            enable_cursor,
+            mode: ptr::null_mut(),
+        }
+    }
+
+    pub(crate) fn take_output() -> String {
+        OUTPUT.with(|output| String::from_utf16_lossy(&output.borrow_mut().drain(..).collect::<Vec<u16>>()))
+    }
+
+    #[test]
+    fn write_str_should_convert_to_ucs2() {
+        let protocol = mock_protocol();
+        let con_out = unsafe { TextOutput::new(&protocol) };
+        assert_eq!(con_out.write_str("Hello\n"), efi::Status::SUCCESS);
+        assert_eq!(con_out.write_str("caf\u{e9} \u{1F600}"), efi::Status::SUCCESS);
+        assert_eq!(take_output(), "Hello\r\ncaf\u{e9} \u{FFFD}");
+    }
+
+    #[test]
+    fn write_str_should_split_long_strings() {
+        let protocol = mock_protocol();
+        let con_out = unsafe { TextOutput::new(&protocol) };
+        let long_string = "0123456789\n".repeat(40);
+        assert_eq!(con_out.write_str(&long_string), efi::Status::SUCCESS);
+        assert_eq!(take_output(), long_string.replace('\n', "\r\n"));
+    }
+
+    #[test]
+    fn write_char_should_write_single_character() {
+        let protocol = mock_protocol();
+        let con_out = unsafe { TextOutput::new(&protocol) };
+        assert_eq!(con_out.write_char('A' as u16), efi::Status::SUCCESS);
+        assert_eq!(take_output(), "A");
+    }
+
+    #[test]
+    fn fmt_write_should_format_into_output() {
+        let protocol = mock_protocol();
+        let mut con_out = unsafe { TextOutput::new(&protocol) };
+        write!(con_out, "{:#x}-{}", 0x1234, 42).unwrap();
+        assert_eq!(take_output(), "0x1234-42");
+    }
+
+    #[test]
+    fn fmt_write_should_report_device_errors() {
+        let mut protocol = mock_protocol();
+        protocol.output_string = failing_output_string;
+        let mut con_out = unsafe { TextOutput::new(&protocol) };
+        assert_eq!(con_out.write_str("x"), efi::Status::DEVICE_ERROR);
+        assert!(write!(con_out, "x").is_err());
+    }
+
+    #[test]
+    fn screen_control_should_call_protocol() {
+        let protocol = mock_protocol();
+        let con_out = unsafe { TextOutput::new(&protocol) };
+        assert_eq!(con_out.clear_screen(), efi::Status::SUCCESS);
+        assert!(CLEARED.with(|c| *c.borrow()));
+        assert_eq!(con_out.set_attribute(0x0F, 0x01), efi::Status::SUCCESS);
+        assert_eq!(ATTRIBUTE.with(|a| *a.borrow()), Some(0x1F));
+        assert_eq!(con_out.set_cursor_position(10, 20), efi::Status::SUCCESS);
+        assert_eq!(CURSOR.with(|c| *c.borrow()), Some((10, 20)));
+    }
+}
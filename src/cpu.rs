@@ -0,0 +1,19 @@
+//! CPU Support
+//!
+//! Processor structures used by firmware that configures the CPU directly, e.g. when running code in a virtualized
+//! or virtual-8086 mode.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub mod io_port;
+pub mod iopb;
+#[cfg(target_arch = "x86_64")]
+pub mod lapic_timer;
+
+pub use iopb::IoPermissionBitmap;
@@ -0,0 +1,103 @@
+//! I/O Ports
+//!
+//! Typed access to an x86 I/O port with the IN and OUT instructions.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{arch::asm, fmt, marker::PhantomData};
+
+/// A value that can be read from and written to an I/O port: `u8`, `u16` or `u32`.
+pub trait PortValue: Copy {
+    /// Reads the value from `port`.
+    ///
+    /// # Safety
+    /// See [`IoPort::new`].
+    unsafe fn read_port(port: u16) -> Self;
+
+    /// Writes the value to `port`.
+    ///
+    /// # Safety
+    /// See [`IoPort::new`].
+    unsafe fn write_port(port: u16, value: Self);
+}
+
+impl PortValue for u8 {
+    unsafe fn read_port(port: u16) -> Self {
+        let value: u8;
+        asm!("in al, dx", in("dx") port, out("al") value, options(nomem, nostack, preserves_flags));
+        value
+    }
+
+    unsafe fn write_port(port: u16, value: Self) {
+        asm!("out dx, al", in("dx") port, in("al") value, options(nomem, nostack, preserves_flags));
+    }
+}
+
+impl PortValue for u16 {
+    unsafe fn read_port(port: u16) -> Self {
+        let value: u16;
+        asm!("in ax, dx", in("dx") port, out("ax") value, options(nomem, nostack, preserves_flags));
+        value
+    }
+
+    unsafe fn write_port(port: u16, value: Self) {
+        asm!("out dx, ax", in("dx") port, in("ax") value, options(nomem, nostack, preserves_flags));
+    }
+}
+
+impl PortValue for u32 {
+    unsafe fn read_port(port: u16) -> Self {
+        let value: u32;
+        asm!("in eax, dx", in("dx") port, out("eax") value, options(nomem, nostack, preserves_flags));
+        value
+    }
+
+    unsafe fn write_port(port: u16, value: Self) {
+        asm!("out dx, eax", in("dx") port, in("eax") value, options(nomem, nostack, preserves_flags));
+    }
+}
+
+/// An I/O port accessed with values of type `T`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct IoPort<T: PortValue> {
+    port: u16,
+    value: PhantomData<T>,
+}
+
+impl<T: PortValue> IoPort<T> {
+    /// Creates an accessor of `port`.
+    ///
+    /// # Safety
+    /// Accesses of `T` to `port` must have no effect on memory safety, and the code must run with I/O privilege.
+    pub const unsafe fn new(port: u16) -> Self {
+        Self { port, value: PhantomData }
+    }
+
+    /// Returns the port number.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Reads the port.
+    pub fn read(&self) -> T {
+        //Safety: the port was declared safe to access in new().
+        unsafe { T::read_port(self.port) }
+    }
+
+    /// Writes `value` to the port.
+    pub fn write(&self, value: T) {
+        //Safety: the port was declared safe to access in new().
+        unsafe { T::write_port(self.port, value) }
+    }
+}
+
+impl<T: PortValue> fmt::Debug for IoPort<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "IoPort<{}>({:#x})", core::any::type_name::<T>(), self.port)
+    }
+}
@@ -0,0 +1,218 @@
+//! I/O Permission Bitmap
+//!
+//! The I/O permission bitmap of an x86 Task State Segment (TSS), or the I/O bitmaps of a VMX or SVM virtual machine,
+//! which decide the I/O ports code running with reduced privilege may access. Each of the 65536 ports has one bit,
+//! bit `port % 8` of byte `port / 8`. A set bit denies access to the port, and a clear bit allows it.
+//!
+//! See the Intel 64 and IA-32 Architectures Software Developer's Manual, Volume 1, Section 19.5.2, "I/O Permission
+//! Bit Map".
+//!
+//! ## Example
+//! ```
+//! use mu_pi::cpu::IoPermissionBitmap;
+//!
+//! let mut iopb = IoPermissionBitmap::new();
+//! iopb.allow_range(0x3f8, 0x3ff);
+//! iopb.deny_port(0x3f9);
+//! assert!(iopb.is_allowed(0x3f8));
+//! assert!(!iopb.is_allowed(0x3f9));
+//! assert!(!iopb.is_allowed(0x80));
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::fmt;
+
+/// Size in bytes of a bitmap covering every I/O port.
+pub const IOPB_SIZE: usize = 8192;
+
+/// An I/O permission bitmap covering every I/O port.
+#[repr(C)]
+#[derive(Clone, PartialEq, Eq)]
+pub struct IoPermissionBitmap {
+    bitmap: [u8; IOPB_SIZE],
+}
+
+impl IoPermissionBitmap {
+    /// Creates a bitmap denying access to every port.
+    pub const fn new() -> Self {
+        Self { bitmap: [0xff; IOPB_SIZE] }
+    }
+
+    /// Allows access to `port`.
+    pub fn allow_port(&mut self, port: u16) {
+        self.bitmap[port as usize / 8] &= !(1 << (port % 8));
+    }
+
+    /// Denies access to `port`.
+    pub fn deny_port(&mut self, port: u16) {
+        self.bitmap[port as usize / 8] |= 1 << (port % 8);
+    }
+
+    /// Returns whether access to `port` is allowed.
+    pub fn is_allowed(&self, port: u16) -> bool {
+        self.bitmap[port as usize / 8] & (1 << (port % 8)) == 0
+    }
+
+    /// Allows access to the ports from `start` to `end` inclusive. Does nothing if `start` is above `end`.
+    pub fn allow_range(&mut self, start: u16, end: u16) {
+        self.update_range(start, end, false);
+    }
+
+    /// Denies access to the ports from `start` to `end` inclusive. Does nothing if `start` is above `end`.
+    pub fn deny_range(&mut self, start: u16, end: u16) {
+        self.update_range(start, end, true);
+    }
+
+    /// Returns the bitmap, as loaded by the processor.
+    pub fn as_bytes(&self) -> &[u8; IOPB_SIZE] {
+        &self.bitmap
+    }
+
+    // Sets or clears the bits of the ports from start to end inclusive, a byte at a time.
+    fn update_range(&mut self, start: u16, end: u16, deny: bool) {
+        if start > end {
+            return;
+        }
+        let (start, end) = (start as usize, end as usize);
+        let (first, last) = (start / 8, end / 8);
+        for index in first..=last {
+            let low = if index == first { start % 8 } else { 0 };
+            let high = if index == last { end % 8 } else { 7 };
+            // Bits low to high inclusive.
+            let mask = (0xffu8 << low) & (0xffu8 >> (7 - high));
+            if deny {
+                self.bitmap[index] |= mask;
+            } else {
+                self.bitmap[index] &= !mask;
+            }
+        }
+    }
+}
+
+impl Default for IoPermissionBitmap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for IoPermissionBitmap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Lists the allowed ranges rather than 8192 bytes.
+        let mut list = f.debug_list();
+        let mut ports = (0..=u16::MAX).peekable();
+        while let Some(start) = ports.find(|port| self.is_allowed(*port)) {
+            let mut end = start;
+            while let Some(port) = ports.next_if(|port| self.is_allowed(*port)) {
+                end = port;
+            }
+            list.entry(&format_args!("{start:#x}..={end:#x}"));
+        }
+        list.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    use alloc::format;
+
+    use super::{IoPermissionBitmap, IOPB_SIZE};
+
+    fn allowed_ports(iopb: &IoPermissionBitmap) -> usize {
+        (0..=u16::MAX).filter(|port| iopb.is_allowed(*port)).count()
+    }
+
+    #[test]
+    fn new_bitmap_should_deny_every_port() {
+        let iopb = IoPermissionBitmap::default();
+        assert_eq!(iopb.as_bytes().len(), IOPB_SIZE);
+        assert!(iopb.as_bytes().iter().all(|byte| *byte == 0xff));
+        assert_eq!(allowed_ports(&iopb), 0);
+        assert_eq!(iopb, IoPermissionBitmap::new());
+    }
+
+    #[test]
+    fn ports_should_map_to_bits() {
+        let mut iopb = IoPermissionBitmap::new();
+        for (port, index, bit) in [(0u16, 0usize, 0u8), (7, 0, 7), (8, 1, 0), (0x80, 0x10, 0), (0x3fb, 0x7f, 3)] {
+            iopb.allow_port(port);
+            assert_eq!(iopb.as_bytes()[index], !(1 << bit), "port {port:#x}");
+            assert!(iopb.is_allowed(port));
+            iopb.deny_port(port);
+            assert_eq!(iopb.as_bytes()[index], 0xff);
+            assert!(!iopb.is_allowed(port));
+        }
+        iopb.allow_port(u16::MAX);
+        assert_eq!(iopb.as_bytes()[IOPB_SIZE - 1], 0x7f);
+        assert_eq!(allowed_ports(&iopb), 1);
+    }
+
+    #[test]
+    fn single_ports_should_not_affect_neighbours() {
+        let mut iopb = IoPermissionBitmap::new();
+        iopb.allow_range(0x60, 0x6f);
+        iopb.deny_port(0x64);
+        assert!(iopb.is_allowed(0x63));
+        assert!(!iopb.is_allowed(0x64));
+        assert!(iopb.is_allowed(0x65));
+        // Allowing an allowed port, or denying a denied one, changes nothing.
+        iopb.allow_port(0x60);
+        iopb.deny_port(0x70);
+        assert_eq!(allowed_ports(&iopb), 15);
+    }
+
+    #[test]
+    fn ranges_should_cover_partial_and_whole_bytes() {
+        let mut iopb = IoPermissionBitmap::new();
+        // Within one byte.
+        iopb.allow_range(0x3, 0x5);
+        assert_eq!(iopb.as_bytes()[0], 0b1100_0111);
+        // Across a partial first byte, whole bytes and a partial last byte.
+        iopb.allow_range(0x0e, 0x31);
+        assert_eq!(iopb.as_bytes()[1], 0b0011_1111);
+        assert_eq!(&iopb.as_bytes()[2..6], [0; 4]);
+        assert_eq!(iopb.as_bytes()[6], 0b1111_1100);
+        assert_eq!(allowed_ports(&iopb), 3 + (0x31 - 0x0e + 1));
+
+        iopb.deny_range(0x10, 0x17);
+        assert_eq!(iopb.as_bytes()[2], 0xff);
+        iopb.deny_range(0x0f, 0x0f);
+        assert_eq!(iopb.as_bytes()[1], 0b1011_1111);
+
+        // An empty range changes nothing.
+        let before = iopb.clone();
+        iopb.allow_range(0x20, 0x1f);
+        iopb.deny_range(0x21, 0x20);
+        assert!(iopb == before);
+    }
+
+    #[test]
+    fn ranges_should_reach_the_last_port() {
+        let mut iopb = IoPermissionBitmap::new();
+        iopb.allow_range(0, u16::MAX);
+        assert!(iopb.as_bytes().iter().all(|byte| *byte == 0));
+        assert_eq!(allowed_ports(&iopb), 0x10000);
+        iopb.deny_range(0xfff9, u16::MAX);
+        assert_eq!(iopb.as_bytes()[IOPB_SIZE - 1], 0xfe);
+        assert_eq!(allowed_ports(&iopb), 0x10000 - 7);
+        iopb.deny_range(0, 0);
+        assert!(!iopb.is_allowed(0));
+        assert!(iopb.is_allowed(1));
+    }
+
+    #[test]
+    fn debug_should_list_allowed_ranges() {
+        let mut iopb = IoPermissionBitmap::new();
+        assert_eq!(format!("{iopb:?}"), "[]");
+        iopb.allow_range(0x60, 0x64);
+        iopb.allow_port(0xcf8);
+        iopb.allow_port(u16::MAX);
+        assert_eq!(format!("{iopb:?}"), "[0x60..=0x64, 0xcf8..=0xcf8, 0xffff..=0xffff]");
+    }
+}
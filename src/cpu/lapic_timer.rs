@@ -0,0 +1,128 @@
+//! Local APIC Timer
+//!
+//! Calibration and programming of the timer of the local APIC in xAPIC (memory-mapped) mode. The LAPIC timer counts
+//! at a rate derived from a clock whose frequency is not architecturally discoverable, so it is calibrated against the
+//! ACPI PM timer, as early DXE timer drivers do.
+//!
+//! See Intel(R) 64 and IA-32 Architectures Software Developer's Manual, Volume 3, 11.5.4 APIC Timer.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::ptr;
+
+use crate::acpi::pm_timer::{self, PmTimer, PM_TIMER_FREQUENCY};
+
+/// Byte offsets of the timer registers from the LAPIC base.
+pub mod register {
+    pub const LVT_TIMER: usize = 0x320;
+    pub const INITIAL_COUNT: usize = 0x380;
+    pub const CURRENT_COUNT: usize = 0x390;
+    pub const DIVIDE_CONFIGURATION: usize = 0x3E0;
+}
+
+/// The Divide Configuration register value dividing the timer clock by 16.
+pub const DIVIDE_BY_16: u32 = 0b0011;
+/// The mask bit of the LVT Timer register.
+pub const LVT_MASKED: u32 = 1 << 16;
+/// The PM timer ticks waited for during calibration: 10 ms.
+pub const CALIBRATION_PM_TICKS: u32 = (PM_TIMER_FREQUENCY / 100) as u32;
+
+/// Returns the frequency of a LAPIC timer that counted `lapic_ticks` while a PM timer counted `pm_ticks`, or 0 if
+/// `pm_ticks` is 0.
+pub fn lapic_frequency(lapic_ticks: u32, pm_ticks: u32) -> u64 {
+    match pm_ticks {
+        0 => 0,
+        _ => lapic_ticks as u64 * PM_TIMER_FREQUENCY / pm_ticks as u64,
+    }
+}
+
+unsafe fn read(lapic_base: *mut u32, offset: usize) -> u32 {
+    ptr::read_volatile(lapic_base.byte_add(offset))
+}
+
+unsafe fn write(lapic_base: *mut u32, offset: usize, value: u32) {
+    ptr::write_volatile(lapic_base.byte_add(offset), value)
+}
+
+/// Returns the frequency of the LAPIC timer counter in Hz, with the timer clock divided by 16, measured over 10 ms of
+/// `pm_timer`.
+///
+/// The timer is left stopped with the divide-by-16 configuration in place, so the result converts times to the ticks
+/// taken by [`start_lapic_timer`].
+///
+/// # Safety
+/// `lapic_base` must point to the memory-mapped registers of the local APIC of the processor running the code, and the
+/// timer must not be in use.
+pub unsafe fn calibrate_lapic_hz(pm_timer: &PmTimer, lapic_base: *mut u32) -> u64 {
+    write(lapic_base, register::LVT_TIMER, read(lapic_base, register::LVT_TIMER) | LVT_MASKED);
+    write(lapic_base, register::DIVIDE_CONFIGURATION, DIVIDE_BY_16);
+
+    let start = pm_timer.read_counter();
+    write(lapic_base, register::INITIAL_COUNT, u32::MAX);
+    let mut pm_ticks = 0;
+    while pm_ticks < CALIBRATION_PM_TICKS {
+        pm_ticks = pm_timer::elapsed_ticks(start, pm_timer.read_counter(), pm_timer.is_extended());
+    }
+    let lapic_ticks = u32::MAX - read(lapic_base, register::CURRENT_COUNT);
+
+    stop_lapic_timer(lapic_base);
+    lapic_frequency(lapic_ticks, pm_ticks)
+}
+
+/// Starts the timer counting down from `ticks`, in the mode and with the vector already in its LVT Timer register.
+///
+/// # Safety
+/// `lapic_base` must point to the memory-mapped registers of the local APIC of the processor running the code.
+pub unsafe fn start_lapic_timer(lapic_base: *mut u32, ticks: u32) {
+    write(lapic_base, register::LVT_TIMER, read(lapic_base, register::LVT_TIMER) & !LVT_MASKED);
+    write(lapic_base, register::INITIAL_COUNT, ticks);
+}
+
+/// Stops and masks the timer.
+///
+/// # Safety
+/// `lapic_base` must point to the memory-mapped registers of the local APIC of the processor running the code.
+pub unsafe fn stop_lapic_timer(lapic_base: *mut u32) {
+    write(lapic_base, register::LVT_TIMER, read(lapic_base, register::LVT_TIMER) | LVT_MASKED);
+    write(lapic_base, register::INITIAL_COUNT, 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lapic_frequency, register, start_lapic_timer, stop_lapic_timer, CALIBRATION_PM_TICKS, LVT_MASKED};
+
+    #[test]
+    fn calibration_window_should_be_10ms() {
+        assert_eq!(CALIBRATION_PM_TICKS, 35_795);
+    }
+
+    #[test]
+    fn lapic_frequency_should_scale_by_pm_ticks() {
+        // 100 MHz bus divided by 16 over exactly 10 ms.
+        assert_eq!(lapic_frequency(62_500, CALIBRATION_PM_TICKS), 6_250_078);
+        // Waiting past the window does not change the result.
+        assert_eq!(lapic_frequency(62_500 * 2, CALIBRATION_PM_TICKS * 2), 6_250_078);
+        assert_eq!(lapic_frequency(u32::MAX, 1), u32::MAX as u64 * 3_579_545);
+        assert_eq!(lapic_frequency(1000, 0), 0);
+    }
+
+    #[test]
+    fn start_and_stop_should_program_the_registers() {
+        let mut registers = [0u32; 0x400 / 4];
+        registers[register::LVT_TIMER / 4] = LVT_MASKED | 0x20;
+        let base = registers.as_mut_ptr();
+
+        unsafe { start_lapic_timer(base, 1234) };
+        assert_eq!(registers[register::LVT_TIMER / 4], 0x20);
+        assert_eq!(registers[register::INITIAL_COUNT / 4], 1234);
+
+        unsafe { stop_lapic_timer(base) };
+        assert_eq!(registers[register::LVT_TIMER / 4], LVT_MASKED | 0x20);
+        assert_eq!(registers[register::INITIAL_COUNT / 4], 0);
+    }
+}
@@ -0,0 +1,522 @@
+//! Device Path Support
+//!
+//! Provides safe access to device paths received across the FFI boundary. A device path is a packed sequence of
+//! variable-length nodes, each beginning with a four byte header (type, subtype, and a little-endian 16-bit length
+//! that includes the header), and terminated by an End of Hardware Device Path node (type 0x7F, subtype 0xFF).
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/10_Protocols_Device_Path_Protocol.html>.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::{fmt, mem, slice};
+
+pub mod build;
+pub mod compare;
+mod kind;
+pub mod piwg;
+mod text;
+
+pub use kind::{DevicePathNodeKind, PartitionSignature, Ucs2Slice};
+pub use text::to_text;
+pub(crate) use text::{write_guid, GuidFmt};
+
+pub use r_efi::protocols::device_path::Protocol as DevicePathProtocol;
+
+/// Device path node type and subtype values.
+pub mod raw {
+    pub use r_efi::protocols::device_path::{
+        TYPE_ACPI as ACPI, TYPE_BIOS as BIOS, TYPE_END as END, TYPE_HARDWARE as HARDWARE, TYPE_MEDIA as MEDIA,
+        TYPE_MESSAGING as MESSAGING,
+    };
+
+    /// End of Hardware Device Path subtypes.
+    pub mod end {
+        /// Terminates one device path instance of a multi-instance device path.
+        pub const INSTANCE: u8 = 0x01;
+        /// Terminates the entire device path.
+        pub const ENTIRE: u8 = 0xFF;
+    }
+
+    /// Hardware Device Path subtypes.
+    pub mod hardware {
+        /// PCI device and function.
+        pub const PCI: u8 = 0x01;
+        /// Memory-mapped range.
+        pub const MEMORY_MAPPED: u8 = 0x03;
+        /// Vendor-defined hardware node.
+        pub const VENDOR: u8 = 0x04;
+    }
+
+    /// Messaging Device Path subtypes.
+    pub mod messaging {
+        /// Vendor-defined messaging node.
+        pub const VENDOR: u8 = 0x0A;
+        /// USB device class.
+        pub const USB_CLASS: u8 = 0x0F;
+        /// USB World Wide ID.
+        pub const USB_WWID: u8 = 0x10;
+    }
+
+    /// Media Device Path subtypes.
+    pub mod media {
+        /// Hard drive partition.
+        pub const HARD_DRIVE: u8 = 0x01;
+        /// Vendor-defined media node.
+        pub const VENDOR: u8 = 0x03;
+        /// File path.
+        pub const FILE_PATH: u8 = 0x04;
+        /// PI firmware file.
+        pub const PIWG_FIRMWARE_FILE: u8 = 0x06;
+        /// PI firmware volume.
+        pub const PIWG_FIRMWARE_VOLUME: u8 = 0x07;
+        /// Relative offset range.
+        pub const RELATIVE_OFFSET_RANGE: u8 = 0x08;
+    }
+}
+
+/// Size of the header common to all device path nodes.
+pub const NODE_HEADER_SIZE: usize = mem::size_of::<DevicePathProtocol>();
+
+/// Errors reported while walking a device path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DevicePathError {
+    /// The device path pointer was null.
+    NullPointer,
+    /// A node at the given byte offset has a length smaller than the node header.
+    InvalidNodeLength { offset: usize },
+    /// The node at the given byte offset extends past the end of the available buffer, or the buffer ends before an
+    /// End of Hardware Device Path node is found.
+    Truncated { offset: usize },
+    /// The device path has more nodes than the given limit.
+    TooManyNodes { limit: usize },
+}
+
+impl fmt::Display for DevicePathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DevicePathError::NullPointer => write!(f, "device path pointer is null"),
+            DevicePathError::InvalidNodeLength { offset } => {
+                write!(f, "device path node at offset {offset:#x} has an invalid length")
+            }
+            DevicePathError::Truncated { offset } => write!(f, "device path is truncated at offset {offset:#x}"),
+            DevicePathError::TooManyNodes { limit } => write!(f, "device path has more than {limit} nodes"),
+        }
+    }
+}
+
+/// Default limit on the number of nodes (including the End of Hardware Device Path node) accepted by [`validate`].
+pub const MAX_NODES: usize = 256;
+
+// Validates the length of the node whose header is given, returning it.
+fn node_length(header: &[u8], offset: usize, max_len: usize) -> Result<usize, DevicePathError> {
+    let length = u16::from_le_bytes([header[2], header[3]]) as usize;
+    if length < NODE_HEADER_SIZE {
+        Err(DevicePathError::InvalidNodeLength { offset })?;
+    }
+    if length > max_len - offset {
+        Err(DevicePathError::Truncated { offset })?;
+    }
+    Ok(length)
+}
+
+/// Validates the device path at `ptr`, returning its total length in bytes including the End of Hardware Device Path
+/// node.
+///
+/// This is intended to be the first operation performed on a device path received across the FFI boundary, e.g. by a
+/// protocol implementation, before the path is used or copied. The device path is rejected if `ptr` is null, if any node
+/// is shorter than the node header, if the path (including the End of Hardware Device Path node) does not fit within
+/// `max_len` bytes, or if it has more than [`MAX_NODES`] nodes.
+///
+/// ## Safety
+/// Caller must ensure that `ptr` is either null or valid for reads of `max_len` bytes (or up to and including the End of
+/// Hardware Device Path node, if that comes first).
+pub unsafe fn validate(ptr: *const DevicePathProtocol, max_len: usize) -> Result<usize, DevicePathError> {
+    validate_with_limit(ptr, max_len, MAX_NODES)
+}
+
+/// Same as [`validate`], but with a caller-provided limit on the number of nodes (including the End of Hardware Device
+/// Path node).
+///
+/// ## Safety
+/// See [`validate`].
+pub unsafe fn validate_with_limit(
+    ptr: *const DevicePathProtocol,
+    max_len: usize,
+    max_nodes: usize,
+) -> Result<usize, DevicePathError> {
+    if ptr.is_null() {
+        Err(DevicePathError::NullPointer)?;
+    }
+
+    let base = ptr as *const u8;
+    let mut offset = 0;
+    let mut nodes = 0;
+    loop {
+        if nodes == max_nodes {
+            Err(DevicePathError::TooManyNodes { limit: max_nodes })?;
+        }
+        if max_len - offset < NODE_HEADER_SIZE {
+            Err(DevicePathError::Truncated { offset })?;
+        }
+        //Safety: the node header is within the max_len bytes the caller guarantees to be readable.
+        let header = slice::from_raw_parts(base.add(offset), NODE_HEADER_SIZE);
+        offset += node_length(header, offset, max_len)?;
+        nodes += 1;
+        if header[0] == raw::END && header[1] == raw::end::ENTIRE {
+            return Ok(offset);
+        }
+    }
+}
+
+/// Validates the device path at `ptr` with [`validate`] and copies it, including the End of Hardware Device Path node,
+/// into owned memory.
+///
+/// ## Safety
+/// See [`validate`].
+pub unsafe fn to_owned(ptr: *const DevicePathProtocol, max_len: usize) -> Result<Vec<u8>, DevicePathError> {
+    let length = validate(ptr, max_len)?;
+    //Safety: validate guarantees that length bytes are readable.
+    Ok(slice::from_raw_parts(ptr as *const u8, length).to_vec())
+}
+
+/// A single node of a device path.
+#[derive(Clone, Copy)]
+pub struct DevicePathNode<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> DevicePathNode<'a> {
+    /// Returns the node type.
+    pub fn node_type(&self) -> u8 {
+        self.bytes[0]
+    }
+
+    /// Returns the node subtype.
+    pub fn sub_type(&self) -> u8 {
+        self.bytes[1]
+    }
+
+    /// Returns the length of the node in bytes, including the header.
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns true if the node carries no data beyond its header.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.len() == NODE_HEADER_SIZE
+    }
+
+    /// Returns the node data following the header.
+    pub fn data(&self) -> &'a [u8] {
+        &self.bytes[NODE_HEADER_SIZE..]
+    }
+
+    /// Returns the raw bytes of the node, including the header.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Indicates whether this node terminates a device path instance (but not the entire device path).
+    pub fn is_end_instance(&self) -> bool {
+        self.node_type() == raw::END && self.sub_type() == raw::end::INSTANCE
+    }
+
+    /// Indicates whether this node terminates the entire device path.
+    pub fn is_end_entire(&self) -> bool {
+        self.node_type() == raw::END && self.sub_type() == raw::end::ENTIRE
+    }
+
+    /// Decodes the node into one of the commonly matched node types.
+    ///
+    /// Nodes of other types, or nodes too short to hold the fields of their type, are returned as
+    /// [`DevicePathNodeKind::Other`].
+    pub fn kind(&self) -> DevicePathNodeKind<'a> {
+        DevicePathNodeKind::decode(self.node_type(), self.sub_type(), self.data())
+    }
+}
+
+impl<'a> fmt::Debug for DevicePathNode<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DevicePathNode")
+            .field("node_type", &self.node_type())
+            .field("sub_type", &self.sub_type())
+            .field("data", &self.data())
+            .finish()
+    }
+}
+
+/// Bounds-checked view of a device path.
+///
+/// A walker can only be constructed over a device path that has been fully validated: every node is at least as long
+/// as the node header, every node lies within the buffer, and the path is terminated by an End of Hardware Device Path
+/// node. Iterating the walker therefore never reads outside the validated bytes.
+///
+/// ## Example
+///```
+/// use mu_pi::device_path::DevicePathWalker;
+///
+/// // A hardware vendor node without vendor GUID or data, followed by the End of Hardware Device Path node.
+/// let path = [0x01, 0x04, 0x04, 0x00, 0x7F, 0xFF, 0x04, 0x00];
+/// let walker = DevicePathWalker::from_slice(&path).unwrap();
+/// assert_eq!(walker.total_len(), 8);
+/// for node in walker.iter() {
+///   println!("type: {:#x} subtype: {:#x}", node.node_type(), node.sub_type());
+/// }
+///```
+#[derive(Clone, Copy)]
+pub struct DevicePathWalker<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> DevicePathWalker<'a> {
+    /// Instantiates a new DevicePathWalker over the device path at `ptr`.
+    ///
+    /// No more than `max_len` bytes starting at `ptr` are read while the device path is validated.
+    ///
+    /// ## Safety
+    /// Caller must ensure that `ptr` is either null or valid for reads of `max_len` bytes (or up to and including the
+    /// End of Hardware Device Path node, if that comes first) for the lifetime `'a`.
+    pub unsafe fn from_ptr(ptr: *const DevicePathProtocol, max_len: usize) -> Result<Self, DevicePathError> {
+        let length = validate_with_limit(ptr, max_len, usize::MAX)?;
+        //Safety: every byte up to length has been validated to lie within max_len.
+        Ok(Self { bytes: slice::from_raw_parts(ptr as *const u8, length) })
+    }
+
+    /// Instantiates a new DevicePathWalker over the device path at the start of `buffer`.
+    ///
+    /// The buffer may extend past the End of Hardware Device Path node; trailing bytes are ignored.
+    pub fn from_slice(buffer: &'a [u8]) -> Result<Self, DevicePathError> {
+        //Safety: buffer is valid for reads of buffer.len() bytes. A null pointer cannot occur for a slice.
+        unsafe { Self::from_ptr(buffer.as_ptr() as *const DevicePathProtocol, buffer.len()) }
+    }
+
+    /// Returns the total length of the device path in bytes, including the End of Hardware Device Path node.
+    pub fn total_len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns the raw bytes of the device path, including the End of Hardware Device Path node.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Returns an iterator over the nodes in the device path.
+    ///
+    /// End of Instance nodes of multi-instance device paths are returned, but the End of Hardware Device Path node that
+    /// terminates the device path is not.
+    pub fn iter(&self) -> DevicePathNodeIterator<'a> {
+        DevicePathNodeIterator { bytes: self.bytes }
+    }
+}
+
+impl<'a> fmt::Debug for DevicePathWalker<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a> IntoIterator for &DevicePathWalker<'a> {
+    type Item = DevicePathNode<'a>;
+    type IntoIter = DevicePathNodeIterator<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the nodes of a [`DevicePathWalker`].
+#[derive(Debug, Clone)]
+pub struct DevicePathNodeIterator<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Iterator for DevicePathNodeIterator<'a> {
+    type Item = DevicePathNode<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The walker guarantees that each remaining node is complete and that the path ends with an END node.
+        if self.bytes.len() < NODE_HEADER_SIZE {
+            return None;
+        }
+        let length = u16::from_le_bytes([self.bytes[2], self.bytes[3]]) as usize;
+        let node = DevicePathNode { bytes: &self.bytes[..length] };
+        if node.is_end_entire() {
+            self.bytes = &[];
+            return None;
+        }
+        self.bytes = &self.bytes[length..];
+        Some(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::ptr;
+
+    use super::{
+        raw, to_owned, validate, validate_with_limit, DevicePathError, DevicePathProtocol, DevicePathWalker, MAX_NODES,
+    };
+
+    const END: [u8; 4] = [0x7F, 0xFF, 0x04, 0x00];
+
+    // PciRoot(0x0)/Pci(0x1F,0x2)
+    const PCI_PATH: [u8; 22] = [
+        0x02, 0x01, 0x0C, 0x00, 0xD0, 0x41, 0x03, 0x0A, 0x00, 0x00, 0x00, 0x00, // Acpi(PNP0A03,0)
+        0x01, 0x01, 0x06, 0x00, 0x02, 0x1F, // Pci(0x1F,0x2)
+        0x7F, 0xFF, 0x04, 0x00,
+    ];
+
+    #[test]
+    fn walker_should_iterate_nodes() {
+        let walker = DevicePathWalker::from_slice(&PCI_PATH).unwrap();
+        assert_eq!(walker.total_len(), PCI_PATH.len());
+
+        let nodes: Vec<_> = walker.iter().collect();
+        assert_eq!(nodes.len(), 2);
+        assert_eq!((nodes[0].node_type(), nodes[0].sub_type()), (raw::ACPI, 0x01));
+        assert_eq!(nodes[0].data(), &[0xD0, 0x41, 0x03, 0x0A, 0x00, 0x00, 0x00, 0x00]);
+        assert_eq!((nodes[1].node_type(), nodes[1].sub_type()), (raw::HARDWARE, 0x01));
+        assert_eq!(nodes[1].data(), &[0x02, 0x1F]);
+        assert_eq!(nodes[1].len(), 6);
+    }
+
+    #[test]
+    fn walker_should_ignore_trailing_bytes() {
+        let mut buffer = PCI_PATH.to_vec();
+        buffer.extend_from_slice(&[0xa5; 16]);
+        let walker = DevicePathWalker::from_slice(&buffer).unwrap();
+        assert_eq!(walker.total_len(), PCI_PATH.len());
+        assert_eq!(walker.iter().count(), 2);
+    }
+
+    #[test]
+    fn walker_should_accept_end_only_path() {
+        let walker = DevicePathWalker::from_slice(&END).unwrap();
+        assert_eq!(walker.total_len(), 4);
+        assert_eq!(walker.iter().count(), 0);
+    }
+
+    #[test]
+    fn walker_should_yield_instance_end_nodes() {
+        let path = [0x01, 0x01, 0x06, 0x00, 0x00, 0x01, 0x7F, 0x01, 0x04, 0x00, 0x01, 0x01, 0x06, 0x00, 0x00, 0x02]
+            .iter()
+            .chain(END.iter())
+            .copied()
+            .collect::<Vec<u8>>();
+        let walker = DevicePathWalker::from_slice(&path).unwrap();
+        let nodes: Vec<_> = walker.iter().collect();
+        assert_eq!(nodes.len(), 3);
+        assert!(nodes[1].is_end_instance());
+        assert!(!nodes[1].is_end_entire());
+    }
+
+    #[test]
+    fn walker_should_reject_null_pointer() {
+        let result = unsafe { DevicePathWalker::from_ptr(ptr::null(), 64) };
+        assert_eq!(result.unwrap_err(), DevicePathError::NullPointer);
+    }
+
+    #[test]
+    fn walker_should_reject_short_nodes() {
+        // zero length would loop forever if accepted.
+        let path = [0x01, 0x01, 0x00, 0x00, 0x7F, 0xFF, 0x04, 0x00];
+        assert_eq!(DevicePathWalker::from_slice(&path).unwrap_err(), DevicePathError::InvalidNodeLength { offset: 0 });
+
+        let path = [0x01, 0x01, 0x06, 0x00, 0x00, 0x00, 0x01, 0x01, 0x03, 0x00, 0x7F, 0xFF, 0x04, 0x00];
+        assert_eq!(DevicePathWalker::from_slice(&path).unwrap_err(), DevicePathError::InvalidNodeLength { offset: 6 });
+    }
+
+    #[test]
+    fn walker_should_reject_truncated_paths() {
+        // missing END node.
+        let path = &PCI_PATH[..18];
+        assert_eq!(DevicePathWalker::from_slice(path).unwrap_err(), DevicePathError::Truncated { offset: 18 });
+
+        // partial END node.
+        let path = &PCI_PATH[..20];
+        assert_eq!(DevicePathWalker::from_slice(path).unwrap_err(), DevicePathError::Truncated { offset: 18 });
+
+        // node length runs past the buffer.
+        let path = [0x01, 0x01, 0x40, 0x00, 0x00, 0x00, 0x7F, 0xFF, 0x04, 0x00];
+        assert_eq!(DevicePathWalker::from_slice(&path).unwrap_err(), DevicePathError::Truncated { offset: 0 });
+
+        // max_len smaller than the path.
+        let result = unsafe { DevicePathWalker::from_ptr(PCI_PATH.as_ptr() as *const DevicePathProtocol, 21) };
+        assert_eq!(result.unwrap_err(), DevicePathError::Truncated { offset: 18 });
+
+        assert_eq!(DevicePathWalker::from_slice(&[]).unwrap_err(), DevicePathError::Truncated { offset: 0 });
+    }
+
+    #[test]
+    fn walker_from_ptr_should_match_from_slice() {
+        let walker = unsafe { DevicePathWalker::from_ptr(PCI_PATH.as_ptr() as *const DevicePathProtocol, 4096) };
+        assert_eq!(walker.unwrap().as_bytes(), &PCI_PATH);
+    }
+
+    #[test]
+    fn validate_should_return_total_length() {
+        let mut buffer = PCI_PATH.to_vec();
+        buffer.extend_from_slice(&[0xa5; 16]);
+        let ptr = buffer.as_ptr() as *const DevicePathProtocol;
+        assert_eq!(unsafe { validate(ptr, buffer.len()) }, Ok(PCI_PATH.len()));
+        assert_eq!(unsafe { validate(ptr, PCI_PATH.len()) }, Ok(PCI_PATH.len()));
+        assert_eq!(unsafe { validate(ptr, PCI_PATH.len() - 1) }, Err(DevicePathError::Truncated { offset: 18 }));
+    }
+
+    #[test]
+    fn validate_should_reject_malformed_paths() {
+        assert_eq!(unsafe { validate(ptr::null(), 64) }, Err(DevicePathError::NullPointer));
+
+        let path = [0x01u8, 0x01, 0x02, 0x00, 0x7F, 0xFF, 0x04, 0x00];
+        assert_eq!(
+            unsafe { validate(path.as_ptr() as *const DevicePathProtocol, path.len()) },
+            Err(DevicePathError::InvalidNodeLength { offset: 0 })
+        );
+
+        let path = [0x01u8, 0x01, 0x04, 0x00];
+        assert_eq!(
+            unsafe { validate(path.as_ptr() as *const DevicePathProtocol, path.len()) },
+            Err(DevicePathError::Truncated { offset: 4 })
+        );
+    }
+
+    #[test]
+    fn validate_should_enforce_node_limit() {
+        // MAX_NODES - 1 vendor nodes plus the END node is accepted; one more node is not.
+        let mut path = [0x01, 0x04, 0x04, 0x00].repeat(MAX_NODES - 1);
+        path.extend_from_slice(&END);
+        let ptr = path.as_ptr() as *const DevicePathProtocol;
+        assert_eq!(unsafe { validate(ptr, path.len()) }, Ok(path.len()));
+
+        let mut path = [0x01, 0x04, 0x04, 0x00].repeat(MAX_NODES);
+        path.extend_from_slice(&END);
+        let ptr = path.as_ptr() as *const DevicePathProtocol;
+        assert_eq!(unsafe { validate(ptr, path.len()) }, Err(DevicePathError::TooManyNodes { limit: MAX_NODES }));
+
+        let ptr = PCI_PATH.as_ptr() as *const DevicePathProtocol;
+        assert_eq!(unsafe { validate_with_limit(ptr, PCI_PATH.len(), 3) }, Ok(PCI_PATH.len()));
+        assert_eq!(
+            unsafe { validate_with_limit(ptr, PCI_PATH.len(), 2) },
+            Err(DevicePathError::TooManyNodes { limit: 2 })
+        );
+        // the walker does not limit the number of nodes.
+        assert!(unsafe { DevicePathWalker::from_ptr(path.as_ptr() as *const DevicePathProtocol, path.len()) }.is_ok());
+    }
+
+    #[test]
+    fn to_owned_should_copy_validated_bytes() {
+        let mut buffer = PCI_PATH.to_vec();
+        buffer.extend_from_slice(&[0xa5; 16]);
+        let owned = unsafe { to_owned(buffer.as_ptr() as *const DevicePathProtocol, buffer.len()) }.unwrap();
+        assert_eq!(owned, PCI_PATH);
+        assert_eq!(unsafe { to_owned(ptr::null(), 64) }, Err(DevicePathError::NullPointer));
+    }
+}
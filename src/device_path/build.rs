@@ -0,0 +1,536 @@
+//! Device Path Construction
+//!
+//! Builds device paths from existing paths and nodes. Every function writes exactly one complete device path,
+//! terminated by a single End of Hardware Device Path node, to a [`PathOutput`], which may be a `Vec<u8>` or a
+//! caller-provided [`FixedBuffer`]. The size of the result is computed before anything is written, so a failed call
+//! never leaves a partial device path in the output.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::{raw, DevicePathNode, DevicePathNodeKind, DevicePathWalker, PartitionSignature, NODE_HEADER_SIZE};
+
+const END_ENTIRE: [u8; NODE_HEADER_SIZE] = [raw::END, raw::end::ENTIRE, NODE_HEADER_SIZE as u8, 0];
+
+/// Errors reported while building a device path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildError {
+    /// The node at the given index is too large to be described by the 16-bit node length.
+    NodeTooLarge { index: usize },
+    /// The node at the given index is an End of Hardware Device Path node. The terminating node is always added by the
+    /// build functions; End of Instance nodes are permitted.
+    UnexpectedEndNode { index: usize },
+    /// The output does not have room for the device path, which requires the given number of bytes.
+    BufferTooSmall { required: usize },
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::NodeTooLarge { index } => write!(f, "device path node {index} is too large"),
+            BuildError::UnexpectedEndNode { index } => {
+                write!(f, "device path node {index} is an End of Hardware Device Path node")
+            }
+            BuildError::BufferTooSmall { required } => write!(f, "device path requires {required:#x} bytes"),
+        }
+    }
+}
+
+/// A device path node that can be serialized.
+pub trait AsRawNode {
+    /// Returns the node type.
+    fn node_type(&self) -> u8;
+
+    /// Returns the node subtype.
+    fn sub_type(&self) -> u8;
+
+    /// Returns the length of the node data in bytes, excluding the header.
+    fn data_len(&self) -> usize;
+
+    /// Writes the node data to `out`, which is exactly [`Self::data_len`] bytes long.
+    fn write_data(&self, out: &mut [u8]);
+}
+
+impl<T: AsRawNode + ?Sized> AsRawNode for &T {
+    fn node_type(&self) -> u8 {
+        (**self).node_type()
+    }
+
+    fn sub_type(&self) -> u8 {
+        (**self).sub_type()
+    }
+
+    fn data_len(&self) -> usize {
+        (**self).data_len()
+    }
+
+    fn write_data(&self, out: &mut [u8]) {
+        (**self).write_data(out)
+    }
+}
+
+impl<'a> AsRawNode for DevicePathNode<'a> {
+    fn node_type(&self) -> u8 {
+        DevicePathNode::node_type(self)
+    }
+
+    fn sub_type(&self) -> u8 {
+        DevicePathNode::sub_type(self)
+    }
+
+    fn data_len(&self) -> usize {
+        self.data().len()
+    }
+
+    fn write_data(&self, out: &mut [u8]) {
+        out.copy_from_slice(self.data())
+    }
+}
+
+impl<'a> AsRawNode for DevicePathNodeKind<'a> {
+    fn node_type(&self) -> u8 {
+        match self {
+            DevicePathNodeKind::Pci { .. } | DevicePathNodeKind::MemoryMapped { .. } => raw::HARDWARE,
+            DevicePathNodeKind::UsbClass { .. } | DevicePathNodeKind::UsbWwid { .. } => raw::MESSAGING,
+            DevicePathNodeKind::HardDrive { .. }
+            | DevicePathNodeKind::FilePath { .. }
+            | DevicePathNodeKind::FirmwareVolume { .. }
+            | DevicePathNodeKind::FirmwareFile { .. } => raw::MEDIA,
+            DevicePathNodeKind::Vendor { node_type, .. } | DevicePathNodeKind::Other { node_type, .. } => *node_type,
+        }
+    }
+
+    fn sub_type(&self) -> u8 {
+        match self {
+            DevicePathNodeKind::Pci { .. } => raw::hardware::PCI,
+            DevicePathNodeKind::MemoryMapped { .. } => raw::hardware::MEMORY_MAPPED,
+            DevicePathNodeKind::UsbClass { .. } => raw::messaging::USB_CLASS,
+            DevicePathNodeKind::UsbWwid { .. } => raw::messaging::USB_WWID,
+            DevicePathNodeKind::HardDrive { .. } => raw::media::HARD_DRIVE,
+            DevicePathNodeKind::FilePath { .. } => raw::media::FILE_PATH,
+            DevicePathNodeKind::FirmwareVolume { .. } => raw::media::PIWG_FIRMWARE_VOLUME,
+            DevicePathNodeKind::FirmwareFile { .. } => raw::media::PIWG_FIRMWARE_FILE,
+            DevicePathNodeKind::Vendor { node_type, .. } => match *node_type {
+                raw::HARDWARE => raw::hardware::VENDOR,
+                raw::MESSAGING => raw::messaging::VENDOR,
+                _ => raw::media::VENDOR,
+            },
+            DevicePathNodeKind::Other { sub_type, .. } => *sub_type,
+        }
+    }
+
+    fn data_len(&self) -> usize {
+        match self {
+            DevicePathNodeKind::Pci { .. } => 2,
+            DevicePathNodeKind::MemoryMapped { .. } => 20,
+            DevicePathNodeKind::UsbClass { .. } => 7,
+            // the serial number and file path are null-terminated.
+            DevicePathNodeKind::UsbWwid { serial_number, .. } => 6 + serial_number.as_bytes().len() + 2,
+            DevicePathNodeKind::HardDrive { .. } => 38,
+            DevicePathNodeKind::FilePath { path } => path.as_bytes().len() + 2,
+            DevicePathNodeKind::FirmwareVolume { .. } | DevicePathNodeKind::FirmwareFile { .. } => 16,
+            DevicePathNodeKind::Vendor { data, .. } => 16 + data.len(),
+            DevicePathNodeKind::Other { data, .. } => data.len(),
+        }
+    }
+
+    fn write_data(&self, out: &mut [u8]) {
+        out.fill(0);
+        match self {
+            DevicePathNodeKind::Pci { function, device } => out.copy_from_slice(&[*function, *device]),
+            DevicePathNodeKind::MemoryMapped { memory_type, start_address, end_address } => {
+                out[0..4].copy_from_slice(&memory_type.to_le_bytes());
+                out[4..12].copy_from_slice(&start_address.to_le_bytes());
+                out[12..20].copy_from_slice(&end_address.to_le_bytes());
+            }
+            DevicePathNodeKind::UsbClass { vendor_id, product_id, device_class, device_subclass, device_protocol } => {
+                out[0..2].copy_from_slice(&vendor_id.to_le_bytes());
+                out[2..4].copy_from_slice(&product_id.to_le_bytes());
+                out[4..7].copy_from_slice(&[*device_class, *device_subclass, *device_protocol]);
+            }
+            DevicePathNodeKind::UsbWwid { interface_number, vendor_id, product_id, serial_number } => {
+                out[0..2].copy_from_slice(&interface_number.to_le_bytes());
+                out[2..4].copy_from_slice(&vendor_id.to_le_bytes());
+                out[4..6].copy_from_slice(&product_id.to_le_bytes());
+                out[6..6 + serial_number.as_bytes().len()].copy_from_slice(serial_number.as_bytes());
+            }
+            DevicePathNodeKind::HardDrive {
+                partition_number,
+                partition_start,
+                partition_size,
+                partition_format,
+                signature,
+            } => {
+                out[0..4].copy_from_slice(&partition_number.to_le_bytes());
+                out[4..12].copy_from_slice(&partition_start.to_le_bytes());
+                out[12..20].copy_from_slice(&partition_size.to_le_bytes());
+                let signature_type = match signature {
+                    PartitionSignature::None => 0x00,
+                    PartitionSignature::Mbr(mbr) => {
+                        out[20..24].copy_from_slice(&mbr.to_le_bytes());
+                        0x01
+                    }
+                    PartitionSignature::Guid(guid) => {
+                        out[20..36].copy_from_slice(guid.as_bytes());
+                        0x02
+                    }
+                    PartitionSignature::Unknown { signature_type, signature } => {
+                        out[20..36].copy_from_slice(signature);
+                        *signature_type
+                    }
+                };
+                out[36] = *partition_format;
+                out[37] = signature_type;
+            }
+            DevicePathNodeKind::FilePath { path } => out[..path.as_bytes().len()].copy_from_slice(path.as_bytes()),
+            DevicePathNodeKind::FirmwareVolume { name } | DevicePathNodeKind::FirmwareFile { name } => {
+                out.copy_from_slice(name.as_bytes())
+            }
+            DevicePathNodeKind::Vendor { vendor_guid, data, .. } => {
+                out[..16].copy_from_slice(vendor_guid.as_bytes());
+                out[16..].copy_from_slice(data);
+            }
+            DevicePathNodeKind::Other { data, .. } => out.copy_from_slice(data),
+        }
+    }
+}
+
+/// A file path media node built from a Rust string, which is converted to null-terminated UCS-2.
+///
+/// Characters outside the Basic Multilingual Plane are replaced with U+FFFD.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FilePathNode<'a>(pub &'a str);
+
+impl<'a> AsRawNode for FilePathNode<'a> {
+    fn node_type(&self) -> u8 {
+        raw::MEDIA
+    }
+
+    fn sub_type(&self) -> u8 {
+        raw::media::FILE_PATH
+    }
+
+    fn data_len(&self) -> usize {
+        (self.0.chars().count() + 1) * 2
+    }
+
+    fn write_data(&self, out: &mut [u8]) {
+        let chars = self.0.chars().map(|c| if (c as u32) <= 0xFFFF { c as u16 } else { 0xFFFD }).chain([0]);
+        for (c, out) in chars.zip(out.chunks_exact_mut(2)) {
+            out.copy_from_slice(&c.to_le_bytes());
+        }
+    }
+}
+
+/// Destination for a device path under construction.
+pub trait PathOutput {
+    /// Returns the number of bytes that can still be written.
+    fn remaining(&self) -> usize;
+
+    /// Appends `len` bytes, which are initialized by `init`. Callers must first ensure that `len` does not exceed
+    /// [`Self::remaining`].
+    fn write_with<F: FnOnce(&mut [u8])>(&mut self, len: usize, init: F);
+}
+
+impl PathOutput for Vec<u8> {
+    fn remaining(&self) -> usize {
+        isize::MAX as usize - self.len()
+    }
+
+    fn write_with<F: FnOnce(&mut [u8])>(&mut self, len: usize, init: F) {
+        let start = self.len();
+        self.resize(start + len, 0);
+        init(&mut self[start..]);
+    }
+}
+
+/// A fixed-size buffer that receives a device path without allocating.
+#[derive(Debug)]
+pub struct FixedBuffer<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> FixedBuffer<'a> {
+    /// Instantiates a new, empty FixedBuffer over `buffer`.
+    pub fn new(buffer: &'a mut [u8]) -> Self {
+        Self { buffer, len: 0 }
+    }
+
+    /// Returns the number of bytes written.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if nothing has been written.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the bytes written.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buffer[..self.len]
+    }
+}
+
+impl<'a> PathOutput for FixedBuffer<'a> {
+    fn remaining(&self) -> usize {
+        self.buffer.len() - self.len
+    }
+
+    fn write_with<F: FnOnce(&mut [u8])>(&mut self, len: usize, init: F) {
+        init(&mut self.buffer[self.len..self.len + len]);
+        self.len += len;
+    }
+}
+
+// Validates the node at the given index of the input, returning its total length.
+fn node_len(node: &impl AsRawNode, index: usize) -> Result<usize, BuildError> {
+    if node.node_type() == raw::END && node.sub_type() == raw::end::ENTIRE {
+        Err(BuildError::UnexpectedEndNode { index })?;
+    }
+    let len = NODE_HEADER_SIZE + node.data_len();
+    if len > u16::MAX as usize {
+        Err(BuildError::NodeTooLarge { index })?;
+    }
+    Ok(len)
+}
+
+fn write_node(output: &mut impl PathOutput, node: &impl AsRawNode, len: usize) {
+    output.write_with(len, |out| {
+        out[0] = node.node_type();
+        out[1] = node.sub_type();
+        out[2..4].copy_from_slice(&(len as u16).to_le_bytes());
+        node.write_data(&mut out[NODE_HEADER_SIZE..]);
+    });
+}
+
+// Returns the nodes of the path, excluding the End of Hardware Device Path node.
+fn path_nodes<'a>(path: &DevicePathWalker<'a>) -> &'a [u8] {
+    let bytes = path.as_bytes();
+    &bytes[..bytes.len() - NODE_HEADER_SIZE]
+}
+
+fn check_capacity(output: &impl PathOutput, required: usize) -> Result<(), BuildError> {
+    if required > output.remaining() {
+        Err(BuildError::BufferTooSmall { required })?;
+    }
+    Ok(())
+}
+
+fn write_bytes(output: &mut impl PathOutput, bytes: &[u8]) {
+    output.write_with(bytes.len(), |out| out.copy_from_slice(bytes));
+}
+
+/// Writes a device path consisting of the nodes of `base` followed by `node`.
+pub fn append(base: &DevicePathWalker, node: &impl AsRawNode, output: &mut impl PathOutput) -> Result<(), BuildError> {
+    let base = path_nodes(base);
+    let len = node_len(node, 0)?;
+    check_capacity(output, base.len() + len + NODE_HEADER_SIZE)?;
+
+    write_bytes(output, base);
+    write_node(output, node, len);
+    write_bytes(output, &END_ENTIRE);
+    Ok(())
+}
+
+/// Writes a device path consisting of the nodes of `base` followed by the nodes of `other`.
+pub fn append_path(
+    base: &DevicePathWalker,
+    other: &DevicePathWalker,
+    output: &mut impl PathOutput,
+) -> Result<(), BuildError> {
+    let base = path_nodes(base);
+    let other = path_nodes(other);
+    check_capacity(output, base.len() + other.len() + NODE_HEADER_SIZE)?;
+
+    write_bytes(output, base);
+    write_bytes(output, other);
+    write_bytes(output, &END_ENTIRE);
+    Ok(())
+}
+
+/// Writes a device path consisting of `nodes`.
+///
+/// End of Instance nodes may be included to build a multi-instance device path.
+pub fn from_nodes<N: AsRawNode>(nodes: &[N], output: &mut impl PathOutput) -> Result<(), BuildError> {
+    let mut required = NODE_HEADER_SIZE;
+    for (index, node) in nodes.iter().enumerate() {
+        required += node_len(node, index)?;
+    }
+    check_capacity(output, required)?;
+
+    for node in nodes {
+        write_node(output, node, NODE_HEADER_SIZE + node.data_len());
+    }
+    write_bytes(output, &END_ENTIRE);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use r_efi::efi;
+
+    use super::{append, append_path, from_nodes, AsRawNode, BuildError, FilePathNode, FixedBuffer};
+    use crate::device_path::{raw, DevicePathNodeKind, DevicePathWalker, PartitionSignature};
+
+    const END: [u8; 4] = [0x7F, 0xFF, 0x04, 0x00];
+    const END_INSTANCE: [u8; 4] = [0x7F, 0x01, 0x04, 0x00];
+    // PciRoot(0x0)/Pci(0x1F,0x2)
+    const PCI_PATH: [u8; 22] = [
+        0x02, 0x01, 0x0C, 0x00, 0xD0, 0x41, 0x03, 0x0A, 0x00, 0x00, 0x00, 0x00, // Acpi(PNP0A03,0)
+        0x01, 0x01, 0x06, 0x00, 0x02, 0x1F, // Pci(0x1F,0x2)
+        0x7F, 0xFF, 0x04, 0x00,
+    ];
+
+    const FV_NAME: efi::Guid =
+        efi::Guid::from_fields(0x7CB8BDC9, 0xF8EB, 0x4F34, 0xAA, 0xEA, &[0x3E, 0xE4, 0xAF, 0x65, 0x16, 0xA1]);
+    const FILE_NAME: efi::Guid =
+        efi::Guid::from_fields(0x462CAA21, 0x7614, 0x4503, 0x83, 0x6E, &[0x8A, 0xB6, 0xF4, 0x66, 0x23, 0x31]);
+
+    fn walker(bytes: &[u8]) -> DevicePathWalker<'_> {
+        DevicePathWalker::from_slice(bytes).unwrap()
+    }
+
+    #[test]
+    fn append_should_reproduce_fv_dispatch_path() {
+        let mut fv_path = Vec::new();
+        from_nodes(&[DevicePathNodeKind::FirmwareVolume { name: FV_NAME }], &mut fv_path).unwrap();
+        assert_eq!(fv_path.len(), 24);
+
+        let mut file_path = Vec::new();
+        append(&walker(&fv_path), &DevicePathNodeKind::FirmwareFile { name: FILE_NAME }, &mut file_path).unwrap();
+
+        let mut expected = vec![raw::MEDIA, raw::media::PIWG_FIRMWARE_VOLUME, 20, 0];
+        expected.extend_from_slice(FV_NAME.as_bytes());
+        expected.extend_from_slice(&[raw::MEDIA, raw::media::PIWG_FIRMWARE_FILE, 20, 0]);
+        expected.extend_from_slice(FILE_NAME.as_bytes());
+        expected.extend_from_slice(&END);
+        assert_eq!(file_path, expected);
+
+        let kinds: Vec<_> = walker(&file_path).iter().map(|node| node.kind()).collect();
+        assert_eq!(
+            kinds,
+            [
+                DevicePathNodeKind::FirmwareVolume { name: FV_NAME },
+                DevicePathNodeKind::FirmwareFile { name: FILE_NAME }
+            ]
+        );
+    }
+
+    #[test]
+    fn from_nodes_should_round_trip_through_walker() {
+        let mut hd = Vec::new();
+        let hd_node = DevicePathNodeKind::HardDrive {
+            partition_number: 1,
+            partition_start: 0x800,
+            partition_size: 0x32000,
+            partition_format: PartitionSignature::FORMAT_GPT,
+            signature: PartitionSignature::Guid(FV_NAME),
+        };
+        from_nodes(&[hd_node], &mut hd).unwrap();
+
+        let pci = walker(&PCI_PATH);
+        let nodes: [&dyn AsRawNode; 6] = [
+            &pci.iter().next().unwrap(),
+            &DevicePathNodeKind::Pci { function: 0x02, device: 0x1F },
+            &DevicePathNodeKind::MemoryMapped { memory_type: 0xB, start_address: 0x1000, end_address: 0x1FFF },
+            &DevicePathNodeKind::UsbClass {
+                vendor_id: 0xFFFF,
+                product_id: 0xFFFF,
+                device_class: 3,
+                device_subclass: 1,
+                device_protocol: 1,
+            },
+            &walker(&hd).iter().next().unwrap(),
+            &FilePathNode("\\EFI\\BOOT\\BOOTX64.EFI"),
+        ];
+        let mut path = Vec::new();
+        from_nodes(&nodes, &mut path).unwrap();
+
+        let walker = walker(&path);
+        assert_eq!(walker.total_len(), path.len());
+        let kinds: Vec<_> = walker.iter().map(|node| node.kind()).collect();
+        assert_eq!(kinds.len(), 6);
+        assert_eq!(kinds[1], DevicePathNodeKind::Pci { function: 0x02, device: 0x1F });
+        assert_eq!(kinds[4], hd_node);
+        let DevicePathNodeKind::FilePath { path: file_path } = kinds[5] else { panic!("expected a file path node") };
+        assert_eq!(file_path, "\\EFI\\BOOT\\BOOTX64.EFI");
+
+        // re-serializing the decoded nodes yields the same bytes.
+        let mut reencoded = Vec::new();
+        from_nodes(&kinds, &mut reencoded).unwrap();
+        assert_eq!(reencoded, path);
+    }
+
+    #[test]
+    fn append_path_should_emit_single_end_node() {
+        let usb = [0x03, 0x05, 0x06, 0x00, 0x02, 0x00, 0x7F, 0xFF, 0x04, 0x00];
+        let mut path = Vec::new();
+        append_path(&walker(&PCI_PATH), &walker(&usb), &mut path).unwrap();
+        assert_eq!(&path[..18], &PCI_PATH[..18]);
+        assert_eq!(&path[18..], &usb);
+
+        let mut path = Vec::new();
+        append_path(&walker(&END), &walker(&END), &mut path).unwrap();
+        assert_eq!(path, END);
+
+        let mut path = Vec::new();
+        append_path(&walker(&PCI_PATH), &walker(&END), &mut path).unwrap();
+        assert_eq!(path, PCI_PATH);
+    }
+
+    #[test]
+    fn from_nodes_should_build_multi_instance_paths() {
+        let end_instance_path = [END_INSTANCE, END].concat();
+        let end_instance = walker(&end_instance_path).iter().next().unwrap().kind();
+        let pci = DevicePathNodeKind::Pci { function: 0, device: 0x14 };
+        let mut path = Vec::new();
+        from_nodes(&[pci, end_instance, pci], &mut path).unwrap();
+        let nodes: Vec<_> = walker(&path).iter().collect();
+        assert_eq!(nodes.len(), 3);
+        assert!(nodes[1].is_end_instance());
+        assert_eq!(path.len(), 6 + 4 + 6 + 4);
+    }
+
+    #[test]
+    fn build_should_reject_invalid_nodes() {
+        let end_entire = DevicePathNodeKind::Other { node_type: raw::END, sub_type: raw::end::ENTIRE, data: &[] };
+        let pci = DevicePathNodeKind::Pci { function: 0, device: 0 };
+        let mut path = Vec::new();
+        assert_eq!(from_nodes(&[pci, end_entire], &mut path), Err(BuildError::UnexpectedEndNode { index: 1 }));
+        assert_eq!(append(&walker(&PCI_PATH), &end_entire, &mut path), Err(BuildError::UnexpectedEndNode { index: 0 }));
+
+        let large = vec![0u8; u16::MAX as usize];
+        let large_node = DevicePathNodeKind::Other { node_type: raw::HARDWARE, sub_type: 0x7E, data: &large };
+        assert_eq!(from_nodes(&[large_node], &mut path), Err(BuildError::NodeTooLarge { index: 0 }));
+        assert!(path.is_empty());
+    }
+
+    #[test]
+    fn fixed_buffer_should_hold_path_without_allocation() {
+        let mut storage = [0xA5u8; 48];
+        let mut buffer = FixedBuffer::new(&mut storage);
+        append(&walker(&PCI_PATH), &DevicePathNodeKind::Pci { function: 0, device: 3 }, &mut buffer).unwrap();
+        assert_eq!(buffer.len(), 28);
+        let walker = walker(buffer.as_bytes());
+        assert_eq!(walker.iter().count(), 3);
+
+        let mut storage = [0u8; 27];
+        let mut buffer = FixedBuffer::new(&mut storage);
+        assert_eq!(
+            append(&walker, &DevicePathNodeKind::Pci { function: 0, device: 3 }, &mut buffer),
+            Err(BuildError::BufferTooSmall { required: 34 })
+        );
+        assert!(buffer.is_empty());
+    }
+}
@@ -0,0 +1,205 @@
+//! Device Path Comparison
+//!
+//! Node-by-node comparison and prefix matching of device paths, e.g. for security policy that allow-lists images by the
+//! location they were loaded from. All comparisons operate directly on [`DevicePathWalker`] views and do not allocate,
+//! so they can be applied to unaligned device paths received across the FFI boundary.
+//!
+//! Nodes are compared by their raw bytes (type, subtype, length, and data). End of Instance nodes (0x7F/0x01) are
+//! compared like any other node, so a match can never continue from one instance of a multi-instance device path into
+//! the next. An End of Instance node at the end of a device path is equivalent to the End of Hardware Device Path node.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use super::DevicePathWalker;
+
+// Matches `prefix` against the start of `path` node by node, returning the number of bytes of `path` that matched.
+fn match_prefix(path: &DevicePathWalker, prefix: &DevicePathWalker) -> Option<usize> {
+    let mut path_nodes = path.iter();
+    let mut prefix_nodes = prefix.iter();
+    let mut offset = 0;
+    loop {
+        let Some(prefix_node) = prefix_nodes.next() else {
+            return Some(offset);
+        };
+        match path_nodes.next() {
+            Some(path_node) if path_node.as_bytes() == prefix_node.as_bytes() => offset += path_node.len(),
+            Some(_) => return None,
+            // the end of path also ends its last instance, so trailing End of Instance nodes in prefix still match.
+            None => {
+                return (prefix_node.is_end_instance() && prefix_nodes.all(|node| node.is_end_instance()))
+                    .then_some(offset)
+            }
+        }
+    }
+}
+
+// Returns the portion of path starting at offset. Offsets returned from match_prefix are always node boundaries.
+fn remainder<'a>(path: &DevicePathWalker<'a>, offset: usize) -> DevicePathWalker<'a> {
+    DevicePathWalker { bytes: &path.as_bytes()[offset..] }
+}
+
+/// Returns true if the two device paths contain the same nodes.
+///
+/// Trailing End of Instance nodes are ignored, so `A/B` is equal to `A/B` followed by an End of Instance node.
+pub fn eq(a: &DevicePathWalker, b: &DevicePathWalker) -> bool {
+    match_prefix(a, b).is_some_and(|offset| remainder(a, offset).iter().all(|node| node.is_end_instance()))
+}
+
+/// Returns true if `path` begins with the nodes of `prefix`.
+///
+/// Every complete instance of a multi-instance `prefix` must be equal to the corresponding instance of `path`; only
+/// the last instance of `prefix` may end part way through an instance of `path`. An empty `prefix` matches any path.
+pub fn starts_with(path: &DevicePathWalker, prefix: &DevicePathWalker) -> bool {
+    match_prefix(path, prefix).is_some()
+}
+
+/// Returns the nodes of `path` that follow `prefix`, or `None` if `path` does not start with `prefix`.
+///
+/// The returned walker borrows from `path` and is terminated by its End of Hardware Device Path node. If `prefix` ends
+/// at the end of an instance of `path`, the returned walker begins with the End of Instance node that separates it from
+/// the next instance, so that a remainder that continues an instance can be distinguished from one that starts a new
+/// instance.
+pub fn strip_prefix<'a>(path: &DevicePathWalker<'a>, prefix: &DevicePathWalker) -> Option<DevicePathWalker<'a>> {
+    match_prefix(path, prefix).map(|offset| remainder(path, offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{eq, starts_with, strip_prefix};
+    use crate::device_path::{DevicePathProtocol, DevicePathWalker};
+
+    const END: [u8; 4] = [0x7F, 0xFF, 0x04, 0x00];
+    const END_INSTANCE: [u8; 4] = [0x7F, 0x01, 0x04, 0x00];
+    // PciRoot(0x0)
+    const PCI_ROOT: [u8; 12] = [0x02, 0x01, 0x0C, 0x00, 0xD0, 0x41, 0x03, 0x0A, 0x00, 0x00, 0x00, 0x00];
+    // Pci(0x14,0x0)
+    const PCI_XHCI: [u8; 6] = [0x01, 0x01, 0x06, 0x00, 0x00, 0x14];
+    // Pci(0x1F,0x2)
+    const PCI_SATA: [u8; 6] = [0x01, 0x01, 0x06, 0x00, 0x02, 0x1F];
+    // USB(0x2,0x0)
+    const USB_PORT2: [u8; 6] = [0x03, 0x05, 0x06, 0x00, 0x02, 0x00];
+    // USB(0x3,0x0)
+    const USB_PORT3: [u8; 6] = [0x03, 0x05, 0x06, 0x00, 0x03, 0x00];
+
+    fn path(nodes: &[&[u8]]) -> Vec<u8> {
+        let mut path = nodes.concat();
+        path.extend_from_slice(&END);
+        path
+    }
+
+    fn walker(bytes: &[u8]) -> DevicePathWalker<'_> {
+        DevicePathWalker::from_slice(bytes).unwrap()
+    }
+
+    #[test]
+    fn eq_should_compare_nodes() {
+        let a = path(&[&PCI_ROOT, &PCI_XHCI, &USB_PORT2]);
+        let b = path(&[&PCI_ROOT, &PCI_XHCI, &USB_PORT2]);
+        let c = path(&[&PCI_ROOT, &PCI_XHCI, &USB_PORT3]);
+        let d = path(&[&PCI_ROOT, &PCI_XHCI]);
+        assert!(eq(&walker(&a), &walker(&b)));
+        assert!(!eq(&walker(&a), &walker(&c)));
+        assert!(!eq(&walker(&a), &walker(&d)));
+        assert!(!eq(&walker(&d), &walker(&a)));
+        assert!(eq(&walker(&END), &walker(&END)));
+    }
+
+    #[test]
+    fn eq_should_ignore_trailing_end_differences() {
+        let a = path(&[&PCI_ROOT, &PCI_XHCI]);
+        let b = path(&[&PCI_ROOT, &PCI_XHCI, &END_INSTANCE]);
+        assert!(eq(&walker(&a), &walker(&b)));
+        assert!(eq(&walker(&b), &walker(&a)));
+
+        // trailing bytes after the END node are not part of the path.
+        let mut c = a.clone();
+        c.extend_from_slice(&USB_PORT2);
+        assert!(eq(&walker(&a), &walker(&c)));
+    }
+
+    #[test]
+    fn eq_should_compare_each_instance() {
+        let a = path(&[&PCI_ROOT, &PCI_XHCI, &END_INSTANCE, &PCI_ROOT, &PCI_SATA]);
+        let b = path(&[&PCI_ROOT, &PCI_XHCI, &END_INSTANCE, &PCI_ROOT, &PCI_SATA]);
+        let swapped = path(&[&PCI_ROOT, &PCI_SATA, &END_INSTANCE, &PCI_ROOT, &PCI_XHCI]);
+        let merged = path(&[&PCI_ROOT, &PCI_XHCI, &PCI_ROOT, &PCI_SATA]);
+        assert!(eq(&walker(&a), &walker(&b)));
+        assert!(!eq(&walker(&a), &walker(&swapped)));
+        assert!(!eq(&walker(&a), &walker(&merged)));
+    }
+
+    #[test]
+    fn starts_with_should_match_prefixes_ending_mid_instance() {
+        let usb = path(&[&PCI_ROOT, &PCI_XHCI, &USB_PORT2]);
+        assert!(starts_with(&walker(&usb), &walker(&path(&[&PCI_ROOT, &PCI_XHCI]))));
+        assert!(starts_with(&walker(&usb), &walker(&path(&[&PCI_ROOT, &PCI_XHCI, &USB_PORT2]))));
+        assert!(starts_with(&walker(&usb), &walker(&END)));
+        assert!(!starts_with(&walker(&usb), &walker(&path(&[&PCI_ROOT, &PCI_SATA]))));
+        assert!(!starts_with(&walker(&usb), &walker(&path(&[&PCI_ROOT, &PCI_XHCI, &USB_PORT2, &USB_PORT3]))));
+
+        // a prefix terminated by an End of Instance node only matches a complete instance.
+        assert!(!starts_with(&walker(&usb), &walker(&path(&[&PCI_ROOT, &PCI_XHCI, &END_INSTANCE]))));
+        assert!(starts_with(&walker(&usb), &walker(&path(&[&PCI_ROOT, &PCI_XHCI, &USB_PORT2, &END_INSTANCE]))));
+    }
+
+    #[test]
+    fn starts_with_should_not_match_across_instances() {
+        let multi = path(&[&PCI_ROOT, &PCI_XHCI, &END_INSTANCE, &PCI_ROOT, &PCI_SATA, &USB_PORT2]);
+
+        // complete first instance, partial second instance.
+        assert!(starts_with(&walker(&multi), &walker(&path(&[&PCI_ROOT, &PCI_XHCI, &END_INSTANCE, &PCI_ROOT]))));
+
+        // first instance of the prefix is only a prefix of the first instance of the path.
+        assert!(!starts_with(&walker(&multi), &walker(&path(&[&PCI_ROOT, &END_INSTANCE, &PCI_ROOT]))));
+
+        // nodes of the second instance do not continue the first.
+        assert!(!starts_with(&walker(&multi), &walker(&path(&[&PCI_ROOT, &PCI_XHCI, &PCI_ROOT]))));
+    }
+
+    #[test]
+    fn strip_prefix_should_return_remaining_nodes() {
+        let usb = path(&[&PCI_ROOT, &PCI_XHCI, &USB_PORT2, &USB_PORT3]);
+        let rest = strip_prefix(&walker(&usb), &walker(&path(&[&PCI_ROOT, &PCI_XHCI]))).unwrap();
+        assert_eq!(rest.as_bytes(), path(&[&USB_PORT2, &USB_PORT3]));
+
+        let rest = strip_prefix(&walker(&usb), &walker(&usb)).unwrap();
+        assert_eq!(rest.as_bytes(), END);
+
+        let rest = strip_prefix(&walker(&usb), &walker(&END)).unwrap();
+        assert_eq!(rest.as_bytes(), usb);
+
+        assert!(strip_prefix(&walker(&usb), &walker(&path(&[&PCI_ROOT, &PCI_SATA]))).is_none());
+    }
+
+    #[test]
+    fn strip_prefix_should_preserve_instance_boundaries() {
+        let multi = path(&[&PCI_ROOT, &PCI_XHCI, &END_INSTANCE, &PCI_ROOT, &PCI_SATA]);
+
+        let rest = strip_prefix(&walker(&multi), &walker(&path(&[&PCI_ROOT, &PCI_XHCI]))).unwrap();
+        assert_eq!(rest.as_bytes(), path(&[&END_INSTANCE, &PCI_ROOT, &PCI_SATA]));
+
+        let rest = strip_prefix(&walker(&multi), &walker(&path(&[&PCI_ROOT, &PCI_XHCI, &END_INSTANCE]))).unwrap();
+        assert_eq!(rest.as_bytes(), path(&[&PCI_ROOT, &PCI_SATA]));
+    }
+
+    #[test]
+    fn compare_should_work_on_unaligned_paths() {
+        let aligned = path(&[&PCI_ROOT, &PCI_XHCI, &USB_PORT2]);
+        let mut buffer = vec![0u8];
+        buffer.extend_from_slice(&aligned);
+        let unaligned_ptr = buffer[1..].as_ptr() as *const DevicePathProtocol;
+        let unaligned = unsafe { DevicePathWalker::from_ptr(unaligned_ptr, aligned.len()) }.unwrap();
+
+        assert!(eq(&unaligned, &walker(&aligned)));
+        assert!(starts_with(&unaligned, &walker(&path(&[&PCI_ROOT]))));
+        assert_eq!(
+            strip_prefix(&unaligned, &walker(&path(&[&PCI_ROOT, &PCI_XHCI]))).unwrap().as_bytes(),
+            path(&[&USB_PORT2])
+        );
+    }
+}
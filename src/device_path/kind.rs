@@ -0,0 +1,443 @@
+//! Device Path Node Decoding
+//!
+//! Decodes the device path node types that firmware policy most commonly matches on into typed values.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/10_Protocols_Device_Path_Protocol.html>.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{char, fmt};
+
+use r_efi::efi;
+
+use super::raw;
+
+// Device path nodes are byte-packed, so multi-byte fields are copied out of the node rather than referenced.
+fn read_bytes<const N: usize>(data: &[u8], offset: usize) -> [u8; N] {
+    data[offset..offset + N].try_into().unwrap()
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(read_bytes(data, offset))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(read_bytes(data, offset))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(read_bytes(data, offset))
+}
+
+fn read_guid(data: &[u8], offset: usize) -> efi::Guid {
+    efi::Guid::from_bytes(&read_bytes(data, offset))
+}
+
+/// A UCS-2 string embedded in a device path node.
+///
+/// The characters are not guaranteed to be 2-byte aligned within the node, so they are exposed through an iterator
+/// rather than as a `&[u16]`. The string ends at the first null character or at the end of the node, whichever comes
+/// first.
+#[derive(Clone, Copy)]
+pub struct Ucs2Slice<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Ucs2Slice<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        let chars = bytes.len() / 2;
+        let len = (0..chars).find(|&idx| read_u16(bytes, idx * 2) == 0).unwrap_or(chars);
+        Self { bytes: &bytes[..len * 2] }
+    }
+
+    /// Returns the number of characters in the string, excluding any null terminator.
+    pub fn len(&self) -> usize {
+        self.bytes.len() / 2
+    }
+
+    /// Returns true if the string has no characters.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Returns an iterator over the UCS-2 characters of the string.
+    pub fn iter(&self) -> impl Iterator<Item = u16> + 'a {
+        self.bytes.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]]))
+    }
+
+    /// Returns an iterator over the characters of the string, replacing invalid characters with U+FFFD.
+    pub fn chars(&self) -> impl Iterator<Item = char> + 'a {
+        char::decode_utf16(self.iter()).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+    }
+
+    /// Returns the raw little-endian bytes of the string, excluding any null terminator.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+}
+
+impl<'a> fmt::Display for Ucs2Slice<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.chars().try_for_each(|c| fmt::Write::write_char(f, c))
+    }
+}
+
+impl<'a> fmt::Debug for Ucs2Slice<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\"{self}\"")
+    }
+}
+
+impl<'a> PartialEq for Ucs2Slice<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.bytes == other.bytes
+    }
+}
+
+impl<'a> PartialEq<str> for Ucs2Slice<'a> {
+    fn eq(&self, other: &str) -> bool {
+        self.iter().eq(other.encode_utf16())
+    }
+}
+
+impl<'a> PartialEq<&str> for Ucs2Slice<'a> {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+/// Partition signature of a hard drive media device path node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartitionSignature {
+    /// The partition has no signature.
+    None,
+    /// 32-bit MBR disk signature.
+    Mbr(u32),
+    /// GPT unique partition GUID.
+    Guid(efi::Guid),
+    /// Signature of an unrecognized signature type.
+    Unknown { signature_type: u8, signature: [u8; 16] },
+}
+
+impl PartitionSignature {
+    /// MBR partition format.
+    pub const FORMAT_MBR: u8 = 0x01;
+    /// GPT partition format.
+    pub const FORMAT_GPT: u8 = 0x02;
+
+    const TYPE_NONE: u8 = 0x00;
+    const TYPE_MBR: u8 = 0x01;
+    const TYPE_GUID: u8 = 0x02;
+
+    fn decode(signature_type: u8, signature: [u8; 16]) -> Self {
+        match signature_type {
+            Self::TYPE_NONE => PartitionSignature::None,
+            Self::TYPE_MBR => PartitionSignature::Mbr(read_u32(&signature, 0)),
+            Self::TYPE_GUID => PartitionSignature::Guid(efi::Guid::from_bytes(&signature)),
+            _ => PartitionSignature::Unknown { signature_type, signature },
+        }
+    }
+}
+
+/// Decoded form of a device path node.
+///
+/// Obtained from [`DevicePathNode::kind`](super::DevicePathNode::kind).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DevicePathNodeKind<'a> {
+    /// PCI device and function (Pci).
+    Pci { function: u8, device: u8 },
+    /// Memory-mapped range (MemoryMapped).
+    MemoryMapped { memory_type: u32, start_address: u64, end_address: u64 },
+    /// USB device class (UsbClass).
+    UsbClass { vendor_id: u16, product_id: u16, device_class: u8, device_subclass: u8, device_protocol: u8 },
+    /// USB World Wide ID (UsbWwid).
+    UsbWwid { interface_number: u16, vendor_id: u16, product_id: u16, serial_number: Ucs2Slice<'a> },
+    /// Hard drive partition (HD).
+    HardDrive {
+        partition_number: u32,
+        partition_start: u64,
+        partition_size: u64,
+        partition_format: u8,
+        signature: PartitionSignature,
+    },
+    /// File path (a UCS-2 path name).
+    FilePath { path: Ucs2Slice<'a> },
+    /// PI firmware volume (Fv).
+    FirmwareVolume { name: efi::Guid },
+    /// PI firmware file (FvFile).
+    FirmwareFile { name: efi::Guid },
+    /// Vendor-defined hardware, messaging, or media node (VenHw, VenMsg, VenMedia).
+    Vendor { node_type: u8, vendor_guid: efi::Guid, data: &'a [u8] },
+    /// Any other node, or a node too short to hold the fields of its type.
+    Other { node_type: u8, sub_type: u8, data: &'a [u8] },
+}
+
+impl<'a> DevicePathNodeKind<'a> {
+    pub(crate) fn decode(node_type: u8, sub_type: u8, data: &'a [u8]) -> Self {
+        match (node_type, sub_type) {
+            (raw::HARDWARE, raw::hardware::PCI) if data.len() >= 2 => {
+                DevicePathNodeKind::Pci { function: data[0], device: data[1] }
+            }
+            (raw::HARDWARE, raw::hardware::MEMORY_MAPPED) if data.len() >= 20 => DevicePathNodeKind::MemoryMapped {
+                memory_type: read_u32(data, 0),
+                start_address: read_u64(data, 4),
+                end_address: read_u64(data, 12),
+            },
+            (raw::MESSAGING, raw::messaging::USB_CLASS) if data.len() >= 7 => DevicePathNodeKind::UsbClass {
+                vendor_id: read_u16(data, 0),
+                product_id: read_u16(data, 2),
+                device_class: data[4],
+                device_subclass: data[5],
+                device_protocol: data[6],
+            },
+            (raw::MESSAGING, raw::messaging::USB_WWID) if data.len() >= 6 => DevicePathNodeKind::UsbWwid {
+                interface_number: read_u16(data, 0),
+                vendor_id: read_u16(data, 2),
+                product_id: read_u16(data, 4),
+                serial_number: Ucs2Slice::new(&data[6..]),
+            },
+            (raw::MEDIA, raw::media::HARD_DRIVE) if data.len() >= 38 => DevicePathNodeKind::HardDrive {
+                partition_number: read_u32(data, 0),
+                partition_start: read_u64(data, 4),
+                partition_size: read_u64(data, 12),
+                partition_format: data[36],
+                signature: PartitionSignature::decode(data[37], read_bytes(data, 20)),
+            },
+            (raw::MEDIA, raw::media::FILE_PATH) => DevicePathNodeKind::FilePath { path: Ucs2Slice::new(data) },
+            (raw::MEDIA, raw::media::PIWG_FIRMWARE_VOLUME) if data.len() >= 16 => {
+                DevicePathNodeKind::FirmwareVolume { name: read_guid(data, 0) }
+            }
+            (raw::MEDIA, raw::media::PIWG_FIRMWARE_FILE) if data.len() >= 16 => {
+                DevicePathNodeKind::FirmwareFile { name: read_guid(data, 0) }
+            }
+            (raw::HARDWARE, raw::hardware::VENDOR)
+            | (raw::MESSAGING, raw::messaging::VENDOR)
+            | (raw::MEDIA, raw::media::VENDOR)
+                if data.len() >= 16 =>
+            {
+                DevicePathNodeKind::Vendor { node_type, vendor_guid: read_guid(data, 0), data: &data[16..] }
+            }
+            _ => DevicePathNodeKind::Other { node_type, sub_type, data },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use r_efi::efi;
+
+    use super::{DevicePathNodeKind, PartitionSignature};
+    use crate::device_path::{raw, DevicePathWalker};
+
+    fn node(node_type: u8, sub_type: u8, data: &[u8]) -> Vec<u8> {
+        let mut node = vec![node_type, sub_type];
+        node.extend_from_slice(&((data.len() + 4) as u16).to_le_bytes());
+        node.extend_from_slice(data);
+        node
+    }
+
+    fn path(nodes: &[Vec<u8>]) -> Vec<u8> {
+        let mut path = nodes.concat();
+        path.extend_from_slice(&[0x7F, 0xFF, 0x04, 0x00]);
+        path
+    }
+
+    fn ucs2(s: &str) -> Vec<u8> {
+        s.encode_utf16().chain([0]).flat_map(u16::to_le_bytes).collect()
+    }
+
+    fn kinds(path: &[u8]) -> Vec<DevicePathNodeKind<'_>> {
+        DevicePathWalker::from_slice(path).unwrap().iter().map(|node| node.kind()).collect()
+    }
+
+    // 7CB8BDC9-F8EB-4F34-AAEA-3EE4AF6516A1
+    const FV_NAME: efi::Guid =
+        efi::Guid::from_fields(0x7CB8BDC9, 0xF8EB, 0x4F34, 0xAA, 0xEA, &[0x3E, 0xE4, 0xAF, 0x65, 0x16, 0xA1]);
+    // 462CAA21-7614-4503-836E-8AB6F4662331
+    const FILE_NAME: efi::Guid =
+        efi::Guid::from_fields(0x462CAA21, 0x7614, 0x4503, 0x83, 0x6E, &[0x8A, 0xB6, 0xF4, 0x66, 0x23, 0x31]);
+
+    #[test]
+    fn kind_should_decode_fv_file_path() {
+        // Fv(7CB8BDC9-F8EB-4F34-AAEA-3EE4AF6516A1)/FvFile(462CAA21-7614-4503-836E-8AB6F4662331)
+        let path = path(&[
+            node(raw::MEDIA, raw::media::PIWG_FIRMWARE_VOLUME, FV_NAME.as_bytes()),
+            node(raw::MEDIA, raw::media::PIWG_FIRMWARE_FILE, FILE_NAME.as_bytes()),
+        ]);
+        assert_eq!(
+            kinds(&path),
+            [
+                DevicePathNodeKind::FirmwareVolume { name: FV_NAME },
+                DevicePathNodeKind::FirmwareFile { name: FILE_NAME }
+            ]
+        );
+    }
+
+    #[test]
+    fn kind_should_decode_memory_mapped_fv_file_path() {
+        // MemoryMapped(0xB,0xFF800000,0xFFFFFFFF)/FvFile(462CAA21-7614-4503-836E-8AB6F4662331)
+        let mut mmap = 0xBu32.to_le_bytes().to_vec();
+        mmap.extend_from_slice(&0xFF800000u64.to_le_bytes());
+        mmap.extend_from_slice(&0xFFFFFFFFu64.to_le_bytes());
+        let path = path(&[
+            node(raw::HARDWARE, raw::hardware::MEMORY_MAPPED, &mmap),
+            node(raw::MEDIA, raw::media::PIWG_FIRMWARE_FILE, FILE_NAME.as_bytes()),
+        ]);
+        assert_eq!(
+            kinds(&path),
+            [
+                DevicePathNodeKind::MemoryMapped {
+                    memory_type: 0xB,
+                    start_address: 0xFF800000,
+                    end_address: 0xFFFFFFFF
+                },
+                DevicePathNodeKind::FirmwareFile { name: FILE_NAME }
+            ]
+        );
+    }
+
+    #[test]
+    fn kind_should_decode_gpt_boot_option_path() {
+        // PciRoot(0x0)/Pci(0x1F,0x2)/Sata(0x0,0xFFFF,0x0)/HD(1,GPT,<guid>,0x800,0x32000)/\EFI\BOOT\BOOTX64.EFI
+        let partition_guid =
+            efi::Guid::from_fields(0x0E2D3D8C, 0x51A1, 0x4C2B, 0x9B, 0x11, &[0x3C, 0x61, 0x3F, 0x1E, 0x51, 0x2D]);
+        let mut hd = 1u32.to_le_bytes().to_vec();
+        hd.extend_from_slice(&0x800u64.to_le_bytes());
+        hd.extend_from_slice(&0x32000u64.to_le_bytes());
+        hd.extend_from_slice(partition_guid.as_bytes());
+        hd.extend_from_slice(&[PartitionSignature::FORMAT_GPT, 0x02]);
+        let path = path(&[
+            node(raw::ACPI, 0x01, &[0xD0, 0x41, 0x03, 0x0A, 0x00, 0x00, 0x00, 0x00]),
+            node(raw::HARDWARE, raw::hardware::PCI, &[0x02, 0x1F]),
+            node(raw::MESSAGING, 0x12, &[0x00, 0x00, 0xFF, 0xFF, 0x00, 0x00]),
+            node(raw::MEDIA, raw::media::HARD_DRIVE, &hd),
+            node(raw::MEDIA, raw::media::FILE_PATH, &ucs2("\\EFI\\BOOT\\BOOTX64.EFI")),
+        ]);
+
+        let kinds = kinds(&path);
+        assert_eq!(kinds.len(), 5);
+        assert!(
+            matches!(kinds[0], DevicePathNodeKind::Other { node_type: raw::ACPI, sub_type: 0x01, data } if data.len() == 8)
+        );
+        assert_eq!(kinds[1], DevicePathNodeKind::Pci { function: 0x02, device: 0x1F });
+        assert!(matches!(kinds[2], DevicePathNodeKind::Other { node_type: raw::MESSAGING, sub_type: 0x12, .. }));
+        assert_eq!(
+            kinds[3],
+            DevicePathNodeKind::HardDrive {
+                partition_number: 1,
+                partition_start: 0x800,
+                partition_size: 0x32000,
+                partition_format: PartitionSignature::FORMAT_GPT,
+                signature: PartitionSignature::Guid(partition_guid),
+            }
+        );
+        let DevicePathNodeKind::FilePath { path } = kinds[4] else { panic!("expected a file path node") };
+        assert_eq!(path, "\\EFI\\BOOT\\BOOTX64.EFI");
+        assert_eq!(path.len(), 21);
+        assert_eq!(format!("{path}"), "\\EFI\\BOOT\\BOOTX64.EFI");
+    }
+
+    #[test]
+    fn kind_should_decode_mbr_partition_signature() {
+        let mut hd = 2u32.to_le_bytes().to_vec();
+        hd.extend_from_slice(&0x3Fu64.to_le_bytes());
+        hd.extend_from_slice(&0x1000u64.to_le_bytes());
+        hd.extend_from_slice(&[0x78, 0x56, 0x34, 0x12]);
+        hd.extend_from_slice(&[0; 12]);
+        hd.extend_from_slice(&[PartitionSignature::FORMAT_MBR, 0x01]);
+        let path = path(&[node(raw::MEDIA, raw::media::HARD_DRIVE, &hd)]);
+        let DevicePathNodeKind::HardDrive { signature, partition_format, .. } = kinds(&path)[0] else {
+            panic!("expected a hard drive node")
+        };
+        assert_eq!(signature, PartitionSignature::Mbr(0x12345678));
+        assert_eq!(partition_format, PartitionSignature::FORMAT_MBR);
+    }
+
+    #[test]
+    fn kind_should_decode_usb_paths() {
+        // PciRoot(0x0)/Pci(0x14,0x0)/UsbClass(0xFFFF,0xFFFF,0x3,0x1,0x1) - any USB boot keyboard.
+        // UsbWwid(0x0,0x0781,0x5567,"4C53")
+        let mut wwid = vec![0x00, 0x00, 0x81, 0x07, 0x67, 0x55];
+        wwid.extend_from_slice(&ucs2("4C53"));
+        let path = path(&[
+            node(raw::HARDWARE, raw::hardware::PCI, &[0x00, 0x14]),
+            node(raw::MESSAGING, raw::messaging::USB_CLASS, &[0xFF, 0xFF, 0xFF, 0xFF, 0x03, 0x01, 0x01]),
+            node(raw::MESSAGING, raw::messaging::USB_WWID, &wwid),
+        ]);
+        let kinds = kinds(&path);
+        assert_eq!(kinds[0], DevicePathNodeKind::Pci { function: 0x00, device: 0x14 });
+        assert_eq!(
+            kinds[1],
+            DevicePathNodeKind::UsbClass {
+                vendor_id: 0xFFFF,
+                product_id: 0xFFFF,
+                device_class: 0x03,
+                device_subclass: 0x01,
+                device_protocol: 0x01
+            }
+        );
+        let DevicePathNodeKind::UsbWwid { interface_number, vendor_id, product_id, serial_number } = kinds[2] else {
+            panic!("expected a USB WWID node")
+        };
+        assert_eq!((interface_number, vendor_id, product_id), (0, 0x0781, 0x5567));
+        assert_eq!(serial_number, "4C53");
+    }
+
+    #[test]
+    fn kind_should_decode_vendor_nodes() {
+        // VenHw(<guid>,0102) followed by VenMsg(<guid>)
+        let mut vendor_data = FV_NAME.as_bytes().to_vec();
+        vendor_data.extend_from_slice(&[0x01, 0x02]);
+        let path = path(&[
+            node(raw::HARDWARE, raw::hardware::VENDOR, &vendor_data),
+            node(raw::MESSAGING, raw::messaging::VENDOR, FILE_NAME.as_bytes()),
+        ]);
+        assert_eq!(
+            kinds(&path),
+            [
+                DevicePathNodeKind::Vendor { node_type: raw::HARDWARE, vendor_guid: FV_NAME, data: &[0x01, 0x02] },
+                DevicePathNodeKind::Vendor { node_type: raw::MESSAGING, vendor_guid: FILE_NAME, data: &[] }
+            ]
+        );
+    }
+
+    #[test]
+    fn kind_should_fall_back_to_other_for_short_nodes() {
+        // an FvFile node with a truncated GUID and a Pci node with no data.
+        let path = path(&[
+            node(raw::MEDIA, raw::media::PIWG_FIRMWARE_FILE, &[0xAA; 8]),
+            node(raw::HARDWARE, raw::hardware::PCI, &[]),
+        ]);
+        assert_eq!(
+            kinds(&path),
+            [
+                DevicePathNodeKind::Other {
+                    node_type: raw::MEDIA,
+                    sub_type: raw::media::PIWG_FIRMWARE_FILE,
+                    data: &[0xAA; 8]
+                },
+                DevicePathNodeKind::Other { node_type: raw::HARDWARE, sub_type: raw::hardware::PCI, data: &[] }
+            ]
+        );
+    }
+
+    #[test]
+    fn kind_should_tolerate_unaligned_nodes() {
+        // a leading 5-byte vendor node misaligns every following field.
+        let mut path = path(&[
+            node(raw::HARDWARE, 0x7E, &[0x00]),
+            node(raw::MEDIA, raw::media::FILE_PATH, &ucs2("a\\b")),
+            node(raw::MEDIA, raw::media::PIWG_FIRMWARE_FILE, FILE_NAME.as_bytes()),
+        ]);
+        path.insert(0, 0);
+        let kinds = kinds(&path[1..]);
+        let DevicePathNodeKind::FilePath { path } = kinds[1] else { panic!("expected a file path node") };
+        assert_eq!(path.iter().collect::<Vec<u16>>(), "a\\b".encode_utf16().collect::<Vec<u16>>());
+        assert_eq!(kinds[2], DevicePathNodeKind::FirmwareFile { name: FILE_NAME });
+    }
+}
@@ -0,0 +1,146 @@
+//! PI Firmware Device Paths
+//!
+//! Helpers for the device paths the DXE dispatcher assigns to images loaded from firmware volumes. These paths end in a
+//! PIWG firmware file node (FvFile), usually preceded by a PIWG firmware volume node (Fv) or by a memory-mapped node
+//! describing the firmware volume's location.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V2_Code_Definitions.html#firmware-volume-media-device-path>.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use r_efi::efi;
+
+use super::{
+    build::{self, BuildError, PathOutput},
+    DevicePathNodeKind, DevicePathWalker,
+};
+
+/// Returns the firmware volume name and file name of the first firmware file node in `path`.
+///
+/// The firmware volume name is returned if a firmware volume node precedes the file node in the same device path
+/// instance. Unrelated nodes before the file node (such as a memory-mapped node) are skipped. Returns `None` if the
+/// path does not contain a firmware file node.
+///
+/// ## Example
+///```
+/// use mu_pi::device_path::{piwg, DevicePathWalker};
+/// use r_efi::efi;
+///
+/// let fv_name = efi::Guid::from_fields(0x7cb8bdc9, 0xf8eb, 0x4f34, 0xaa, 0xea, &[0x3e, 0xe4, 0xaf, 0x65, 0x16, 0xa1]);
+/// let file_name = efi::Guid::from_fields(0x462caa21, 0x7614, 0x4503, 0x83, 0x6e, &[0x8a, 0xb6, 0xf4, 0x66, 0x23, 0x31]);
+///
+/// let mut path = Vec::new();
+/// piwg::fv_file_path(&fv_name, &file_name, &mut path).unwrap();
+///
+/// let walker = DevicePathWalker::from_slice(&path).unwrap();
+/// assert_eq!(piwg::extract_fv_file(&walker), Some((Some(fv_name), file_name)));
+///```
+pub fn extract_fv_file(path: &DevicePathWalker) -> Option<(Option<efi::Guid>, efi::Guid)> {
+    let mut fv_name = None;
+    for node in path.iter() {
+        match node.kind() {
+            DevicePathNodeKind::FirmwareVolume { name } => fv_name = Some(name),
+            DevicePathNodeKind::FirmwareFile { name } => return Some((fv_name, name)),
+            _ if node.is_end_instance() => fv_name = None,
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Writes the canonical device path of a file in a firmware volume, `Fv(fv_name)/FvFile(file_name)`, e.g. for an FV
+/// and file described by a HOB.
+pub fn fv_file_path(
+    fv_name: &efi::Guid,
+    file_name: &efi::Guid,
+    output: &mut impl PathOutput,
+) -> Result<(), BuildError> {
+    build::from_nodes(
+        &[DevicePathNodeKind::FirmwareVolume { name: *fv_name }, DevicePathNodeKind::FirmwareFile { name: *file_name }],
+        output,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use r_efi::efi;
+
+    use super::{extract_fv_file, fv_file_path};
+    use crate::device_path::{build, raw, DevicePathNodeKind, DevicePathWalker};
+
+    const FV_NAME: efi::Guid =
+        efi::Guid::from_fields(0x7CB8BDC9, 0xF8EB, 0x4F34, 0xAA, 0xEA, &[0x3E, 0xE4, 0xAF, 0x65, 0x16, 0xA1]);
+    const FILE_NAME: efi::Guid =
+        efi::Guid::from_fields(0x462CAA21, 0x7614, 0x4503, 0x83, 0x6E, &[0x8A, 0xB6, 0xF4, 0x66, 0x23, 0x31]);
+
+    const MEMORY_MAPPED: DevicePathNodeKind =
+        DevicePathNodeKind::MemoryMapped { memory_type: 0xB, start_address: 0xFF800000, end_address: 0xFFFFFFFF };
+
+    fn path(nodes: &[DevicePathNodeKind]) -> Vec<u8> {
+        let mut path = Vec::new();
+        build::from_nodes(nodes, &mut path).unwrap();
+        path
+    }
+
+    fn extract(path: &[u8]) -> Option<(Option<efi::Guid>, efi::Guid)> {
+        extract_fv_file(&DevicePathWalker::from_slice(path).unwrap())
+    }
+
+    #[test]
+    fn extract_should_handle_fv_file_path() {
+        let path = path(&[
+            DevicePathNodeKind::FirmwareVolume { name: FV_NAME },
+            DevicePathNodeKind::FirmwareFile { name: FILE_NAME },
+        ]);
+        assert_eq!(extract(&path), Some((Some(FV_NAME), FILE_NAME)));
+    }
+
+    #[test]
+    fn extract_should_handle_memory_mapped_file_path() {
+        let path = path(&[MEMORY_MAPPED, DevicePathNodeKind::FirmwareFile { name: FILE_NAME }]);
+        assert_eq!(extract(&path), Some((None, FILE_NAME)));
+    }
+
+    #[test]
+    fn extract_should_handle_bare_file_path() {
+        let path = path(&[DevicePathNodeKind::FirmwareFile { name: FILE_NAME }]);
+        assert_eq!(extract(&path), Some((None, FILE_NAME)));
+    }
+
+    #[test]
+    fn extract_should_return_none_without_file_node() {
+        assert_eq!(extract(&path(&[DevicePathNodeKind::FirmwareVolume { name: FV_NAME }])), None);
+        assert_eq!(extract(&path(&[MEMORY_MAPPED])), None);
+        assert_eq!(extract(&path(&[])), None);
+    }
+
+    #[test]
+    fn extract_should_not_pair_fv_from_other_instance() {
+        let end_instance = DevicePathNodeKind::Other { node_type: raw::END, sub_type: raw::end::INSTANCE, data: &[] };
+        let path = path(&[
+            DevicePathNodeKind::FirmwareVolume { name: FV_NAME },
+            end_instance,
+            DevicePathNodeKind::FirmwareFile { name: FILE_NAME },
+        ]);
+        assert_eq!(extract(&path), Some((None, FILE_NAME)));
+    }
+
+    #[test]
+    fn fv_file_path_should_build_canonical_path() {
+        let mut path = Vec::new();
+        fv_file_path(&FV_NAME, &FILE_NAME, &mut path).unwrap();
+
+        let mut expected = vec![raw::MEDIA, raw::media::PIWG_FIRMWARE_VOLUME, 20, 0];
+        expected.extend_from_slice(FV_NAME.as_bytes());
+        expected.extend_from_slice(&[raw::MEDIA, raw::media::PIWG_FIRMWARE_FILE, 20, 0]);
+        expected.extend_from_slice(FILE_NAME.as_bytes());
+        expected.extend_from_slice(&[0x7F, 0xFF, 0x04, 0x00]);
+        assert_eq!(path, expected);
+        assert_eq!(extract(&path), Some((Some(FV_NAME), FILE_NAME)));
+    }
+}
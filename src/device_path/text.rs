@@ -0,0 +1,200 @@
+//! Device Path Text Rendering
+//!
+//! Renders device paths in the text format defined by the UEFI specification for the node types relevant to firmware
+//! (e.g. for logging the location of an image from a security handler). Rendering is done directly into a
+//! [`core::fmt::Write`] without allocating, so it can be used with a serial log in PEI or DXE.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/10_Protocols_Device_Path_Protocol.html#text-device-node-reference>.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::fmt;
+
+use r_efi::efi;
+
+use crate::guids;
+
+use super::{raw, DevicePathNode, DevicePathNodeKind, DevicePathWalker};
+
+pub(crate) fn write_guid(w: &mut impl fmt::Write, guid: &efi::Guid) -> fmt::Result {
+    let (time_low, time_mid, time_hi_and_version, clk_seq_hi_res, clk_seq_low, node) = guid.as_fields();
+    write!(w, "{time_low:08X}-{time_mid:04X}-{time_hi_and_version:04X}-{clk_seq_hi_res:02X}{clk_seq_low:02X}-")?;
+    node.iter().try_for_each(|byte| write!(w, "{byte:02X}"))
+}
+
+/// Formats a GUID in its canonical form with [`write_guid`], followed by its name if the crate defines it, for `Debug`
+/// implementations of structures holding one.
+pub(crate) struct GuidFmt<'a>(pub(crate) &'a efi::Guid);
+
+impl fmt::Debug for GuidFmt<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_guid(f, self.0)?;
+        match guids::name_of(self.0) {
+            Some(name) => write!(f, " ({name})"),
+            None => Ok(()),
+        }
+    }
+}
+
+fn write_node(w: &mut impl fmt::Write, node: &DevicePathNode) -> fmt::Result {
+    match node.kind() {
+        DevicePathNodeKind::FirmwareVolume { name } => {
+            w.write_str("Fv(")?;
+            write_guid(w, &name)?;
+            return w.write_str(")");
+        }
+        DevicePathNodeKind::FirmwareFile { name } => {
+            w.write_str("FvFile(")?;
+            write_guid(w, &name)?;
+            return w.write_str(")");
+        }
+        DevicePathNodeKind::MemoryMapped { memory_type, start_address, end_address } => {
+            return write!(w, "MemoryMapped({memory_type:#X},{start_address:#X},{end_address:#X})");
+        }
+        DevicePathNodeKind::Pci { function, device } => return write!(w, "Pci({device:#X},{function:#X})"),
+        _ => (),
+    }
+
+    let data = node.data();
+    if (node.node_type(), node.sub_type()) == (raw::MEDIA, raw::media::RELATIVE_OFFSET_RANGE) && data.len() >= 20 {
+        let start = u64::from_le_bytes(data[4..12].try_into().unwrap());
+        let end = u64::from_le_bytes(data[12..20].try_into().unwrap());
+        return write!(w, "Offset({start:#X},{end:#X})");
+    }
+
+    write!(w, "Path({},{}", node.node_type(), node.sub_type())?;
+    if !data.is_empty() {
+        w.write_str(",")?;
+        data.iter().try_for_each(|byte| write!(w, "{byte:02X}"))?;
+    }
+    w.write_str(")")
+}
+
+/// Writes the text representation of a device path.
+///
+/// Nodes are separated by `/` and instances of a multi-instance device path by `,`. PI firmware volume and file,
+/// memory-mapped, relative offset range, and PCI nodes are rendered by name (e.g. `Fv(GUID)/FvFile(GUID)`); all other
+/// nodes use the generic `Path(type,subtype,data)` form, with the data as hex bytes.
+///
+/// ## Example
+///```
+/// use mu_pi::device_path::{to_text, DevicePathWalker};
+///
+/// let path = [0x01, 0x01, 0x06, 0x00, 0x02, 0x1F, 0x7F, 0xFF, 0x04, 0x00];
+/// let mut text = String::new();
+/// to_text(&DevicePathWalker::from_slice(&path).unwrap(), &mut text).unwrap();
+/// assert_eq!(text, "Pci(0x1F,0x2)");
+///```
+pub fn to_text(walker: &DevicePathWalker, w: &mut impl fmt::Write) -> fmt::Result {
+    let mut separator = None;
+    for node in walker.iter() {
+        if node.is_end_instance() {
+            separator = Some(",");
+            continue;
+        }
+        if let Some(separator) = separator {
+            w.write_str(separator)?;
+        }
+        write_node(w, &node)?;
+        separator = Some("/");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use core::fmt::{self, Write};
+
+    use super::to_text;
+    use crate::device_path::DevicePathWalker;
+
+    fn node(node_type: u8, sub_type: u8, data: &[u8]) -> Vec<u8> {
+        let mut node = vec![node_type, sub_type];
+        node.extend_from_slice(&((data.len() + 4) as u16).to_le_bytes());
+        node.extend_from_slice(data);
+        node
+    }
+
+    fn render(nodes: &[Vec<u8>]) -> String {
+        let mut path = nodes.concat();
+        path.extend_from_slice(&[0x7F, 0xFF, 0x04, 0x00]);
+        let mut text = String::new();
+        to_text(&DevicePathWalker::from_slice(&path).unwrap(), &mut text).unwrap();
+        text
+    }
+
+    // Raw GUID bytes of 7CB8BDC9-F8EB-4F34-AAEA-3EE4AF6516A1 (MdeModulePkg FV name used by OVMF).
+    const FV_NAME: [u8; 16] =
+        [0xC9, 0xBD, 0xB8, 0x7C, 0xEB, 0xF8, 0x34, 0x4F, 0xAA, 0xEA, 0x3E, 0xE4, 0xAF, 0x65, 0x16, 0xA1];
+    // Raw GUID bytes of 462CAA21-7614-4503-836E-8AB6F4662331 (UiApp).
+    const UI_APP: [u8; 16] =
+        [0x21, 0xAA, 0x2C, 0x46, 0x14, 0x76, 0x03, 0x45, 0x83, 0x6E, 0x8A, 0xB6, 0xF4, 0x66, 0x23, 0x31];
+
+    #[test]
+    fn to_text_should_match_edk2_for_fv_file_paths() {
+        assert_eq!(
+            render(&[node(0x04, 0x07, &FV_NAME), node(0x04, 0x06, &UI_APP)]),
+            "Fv(7CB8BDC9-F8EB-4F34-AAEA-3EE4AF6516A1)/FvFile(462CAA21-7614-4503-836E-8AB6F4662331)"
+        );
+    }
+
+    #[test]
+    fn to_text_should_match_edk2_for_memory_mapped_paths() {
+        let mut mmap = 0xBu32.to_le_bytes().to_vec();
+        mmap.extend_from_slice(&0xFF800000u64.to_le_bytes());
+        mmap.extend_from_slice(&0xFFFFFFFFu64.to_le_bytes());
+        let mut offset = 0u32.to_le_bytes().to_vec();
+        offset.extend_from_slice(&0x1000u64.to_le_bytes());
+        offset.extend_from_slice(&0x1FFFu64.to_le_bytes());
+        assert_eq!(
+            render(&[node(0x01, 0x03, &mmap), node(0x04, 0x08, &offset), node(0x04, 0x06, &UI_APP)]),
+            "MemoryMapped(0xB,0xFF800000,0xFFFFFFFF)/Offset(0x1000,0x1FFF)/FvFile(462CAA21-7614-4503-836E-8AB6F4662331)"
+        );
+    }
+
+    #[test]
+    fn to_text_should_render_pci_device_before_function() {
+        assert_eq!(
+            render(&[node(0x01, 0x01, &[0x02, 0x1F]), node(0x01, 0x01, &[0x00, 0x00])]),
+            "Pci(0x1F,0x2)/Pci(0x0,0x0)"
+        );
+    }
+
+    #[test]
+    fn to_text_should_fall_back_to_generic_path() {
+        // node types without a text form of their own are rendered the same way by edk2.
+        assert_eq!(render(&[node(0x06, 0x01, &[0xDE, 0xAD, 0x0B])]), "Path(6,1,DEAD0B)");
+        assert_eq!(render(&[node(0x01, 0x01, &[0x02, 0x1F]), node(0x06, 0x02, &[])]), "Pci(0x1F,0x2)/Path(6,2)");
+        // a node too short for its type is rendered generically rather than misparsed.
+        assert_eq!(render(&[node(0x04, 0x06, &[0xAA; 4])]), "Path(4,6,AAAAAAAA)");
+    }
+
+    #[test]
+    fn to_text_should_separate_instances() {
+        assert_eq!(
+            render(&[node(0x01, 0x01, &[0x00, 0x14]), node(0x7F, 0x01, &[]), node(0x01, 0x01, &[0x02, 0x1F])]),
+            "Pci(0x14,0x0),Pci(0x1F,0x2)"
+        );
+        assert_eq!(render(&[]), "");
+    }
+
+    #[test]
+    fn to_text_should_propagate_writer_errors() {
+        struct Limited(usize);
+        impl Write for Limited {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                self.0 = self.0.checked_sub(s.len()).ok_or(fmt::Error)?;
+                Ok(())
+            }
+        }
+        let path = [0x01, 0x01, 0x06, 0x00, 0x02, 0x1F, 0x7F, 0xFF, 0x04, 0x00];
+        let walker = DevicePathWalker::from_slice(&path).unwrap();
+        assert!(to_text(&walker, &mut Limited(4)).is_err());
+        assert!(to_text(&walker, &mut Limited(64)).is_ok());
+    }
+}
@@ -0,0 +1,17 @@
+//! DXE Phase Support
+//!
+//! Support code for drivers and cores executing in the Driver Execution Environment (DXE) phase.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V2_Overview.html#driver-execution-environment-dxe-phase>.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+pub mod allocator;
+pub mod entry;
+pub mod event_groups;
+pub mod sor;
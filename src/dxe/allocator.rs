@@ -0,0 +1,231 @@
+//! Boot Services Allocator
+//!
+//! Implements [`GlobalAlloc`] over the AllocatePool() and FreePool() boot services, for DXE drivers and applications
+//! written in Rust. Memory is allocated as boot services data.
+//!
+//! The allocator must be uninitialized before ExitBootServices() returns, after which allocations fail and
+//! deallocations leak.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/07_Services_Boot_Services.html#memory-allocation-services>
+//!
+//! ## Example
+//! ```no_run
+//! use mu_pi::dxe::allocator::BootServicesAllocator;
+//! use r_efi::efi;
+//!
+//! #[global_allocator]
+//! static ALLOCATOR: BootServicesAllocator = BootServicesAllocator::new();
+//!
+//! extern "efiapi" fn driver_entry(_image_handle: efi::Handle, system_table: *mut efi::SystemTable) -> efi::Status {
+//!     //Safety: the firmware passes a valid system table, whose boot services stay valid until ExitBootServices().
+//!     unsafe { ALLOCATOR.initialize((*system_table).boot_services) };
+//!     efi::Status::SUCCESS
+//! }
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    ffi::c_void,
+    ptr,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+use r_efi::efi;
+
+/// The alignment of the buffers AllocatePool() returns.
+pub const POOL_ALIGNMENT: usize = 8;
+
+/// Allocates memory with the AllocatePool() and FreePool() boot services.
+///
+/// Allocations aligned to more than [`POOL_ALIGNMENT`] are over-allocated, with the pointer returned by AllocatePool()
+/// stored just before the aligned buffer for FreePool().
+#[derive(Debug)]
+pub struct BootServicesAllocator(AtomicPtr<efi::BootServices>);
+
+impl BootServicesAllocator {
+    /// Creates an uninitialized allocator, whose allocations fail until [`initialize`](Self::initialize) is called.
+    pub const fn new() -> Self {
+        Self(AtomicPtr::new(ptr::null_mut()))
+    }
+
+    /// Sets the boot services the allocator uses.
+    ///
+    /// # Safety
+    /// `bt` must point to valid boot services until [`uninitialize`](Self::uninitialize) is called.
+    pub unsafe fn initialize(&self, bt: *mut efi::BootServices) {
+        self.0.store(bt, Ordering::Release);
+    }
+
+    /// Clears the boot services, for instance when ExitBootServices() is signaled. Allocations then fail and
+    /// deallocations leak.
+    pub fn uninitialize(&self) {
+        self.0.store(ptr::null_mut(), Ordering::Release);
+    }
+
+    /// Returns whether the allocator has boot services.
+    pub fn is_initialized(&self) -> bool {
+        !self.0.load(Ordering::Acquire).is_null()
+    }
+}
+
+impl Default for BootServicesAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl GlobalAlloc for BootServicesAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let bt = self.0.load(Ordering::Acquire);
+        if bt.is_null() {
+            return ptr::null_mut();
+        }
+
+        let align = layout.align();
+        let Some(size) = layout.size().checked_add(if align > POOL_ALIGNMENT { align } else { 0 }) else {
+            return ptr::null_mut();
+        };
+        let mut buffer: *mut c_void = ptr::null_mut();
+        //Safety: initialize() requires bt to be valid. Only the field is read, as callers may provide partial tables.
+        if ((*bt).allocate_pool)(efi::BOOT_SERVICES_DATA, size, &mut buffer) != efi::Status::SUCCESS {
+            return ptr::null_mut();
+        }
+
+        let buffer = buffer as *mut u8;
+        if align <= POOL_ALIGNMENT {
+            return buffer;
+        }
+        // The offset is at least POOL_ALIGNMENT, leaving room for the original pointer.
+        let aligned = buffer.add(align - (buffer as usize & (align - 1)));
+        (aligned as *mut *mut u8).sub(1).write(buffer);
+        aligned
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let bt = self.0.load(Ordering::Acquire);
+        if bt.is_null() {
+            return;
+        }
+
+        let buffer = match layout.align() > POOL_ALIGNMENT {
+            true => (ptr as *mut *mut u8).sub(1).read(),
+            false => ptr,
+        };
+        //Safety: initialize() requires bt to be valid.
+        let _ = ((*bt).free_pool)(buffer as *mut c_void);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        alloc::{GlobalAlloc, Layout},
+        cell::RefCell,
+        ffi::c_void,
+        mem::MaybeUninit,
+        ptr,
+    };
+    use std::alloc::{alloc, dealloc};
+
+    use r_efi::efi;
+
+    use super::BootServicesAllocator;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Call {
+        AllocatePool(efi::MemoryType, usize),
+        FreePool(*mut c_void),
+    }
+
+    thread_local! {
+        static CALLS: RefCell<Vec<Call>> = const { RefCell::new(Vec::new()) };
+        static BUFFERS: RefCell<Vec<(*mut u8, Layout)>> = const { RefCell::new(Vec::new()) };
+    }
+
+    // Returns buffers aligned to exactly 8 bytes, the weakest alignment pool allocations have.
+    extern "efiapi" fn allocate_pool(pool_type: efi::MemoryType, size: usize, buffer: *mut *mut c_void) -> efi::Status {
+        CALLS.with(|calls| calls.borrow_mut().push(Call::AllocatePool(pool_type, size)));
+        let layout = Layout::from_size_align(size + 8, 16).unwrap();
+        let memory = unsafe { alloc(layout) };
+        BUFFERS.with(|buffers| buffers.borrow_mut().push((memory, layout)));
+        unsafe { *buffer = memory.add(8) as *mut c_void };
+        efi::Status::SUCCESS
+    }
+
+    extern "efiapi" fn free_pool(buffer: *mut c_void) -> efi::Status {
+        CALLS.with(|calls| calls.borrow_mut().push(Call::FreePool(buffer)));
+        BUFFERS.with(|buffers| {
+            let mut buffers = buffers.borrow_mut();
+            let index = buffers.iter().position(|(memory, _)| unsafe { memory.add(8) } == buffer as *mut u8).unwrap();
+            let (memory, layout) = buffers.remove(index);
+            unsafe { dealloc(memory, layout) };
+        });
+        efi::Status::SUCCESS
+    }
+
+    // efi::BootServices has variadic members that cannot be defined in Rust, so only the two services the allocator
+    // uses are set.
+    fn boot_services() -> Box<MaybeUninit<efi::BootServices>> {
+        let mut bt = Box::new(MaybeUninit::<efi::BootServices>::zeroed());
+        unsafe {
+            ptr::addr_of_mut!((*bt.as_mut_ptr()).allocate_pool).write(allocate_pool);
+            ptr::addr_of_mut!((*bt.as_mut_ptr()).free_pool).write(free_pool);
+        }
+        bt
+    }
+
+    fn calls() -> Vec<Call> {
+        CALLS.with(|calls| calls.borrow_mut().drain(..).collect())
+    }
+
+    #[test]
+    fn uninitialized_allocator_should_fail() {
+        let allocator = BootServicesAllocator::new();
+        assert!(!allocator.is_initialized());
+        assert!(unsafe { allocator.alloc(Layout::new::<u64>()) }.is_null());
+        unsafe { allocator.dealloc(8 as *mut u8, Layout::new::<u64>()) };
+        assert_eq!(calls(), []);
+    }
+
+    #[test]
+    fn allocator_should_allocate_boot_services_data_pool() {
+        let mut bt = boot_services();
+        let allocator = BootServicesAllocator::new();
+        unsafe { allocator.initialize(bt.as_mut_ptr()) };
+
+        let layout = Layout::from_size_align(100, 8).unwrap();
+        let buffer = unsafe { allocator.alloc(layout) };
+        unsafe { buffer.write_bytes(0xa5, 100) };
+        unsafe { allocator.dealloc(buffer, layout) };
+        assert_eq!(calls(), [Call::AllocatePool(efi::BOOT_SERVICES_DATA, 100), Call::FreePool(buffer as *mut c_void)]);
+
+        allocator.uninitialize();
+        assert!(unsafe { allocator.alloc(layout) }.is_null());
+        assert_eq!(calls(), []);
+    }
+
+    #[test]
+    fn allocator_should_align_beyond_pool_alignment() {
+        let mut bt = boot_services();
+        let allocator = BootServicesAllocator::new();
+        unsafe { allocator.initialize(bt.as_mut_ptr()) };
+
+        let layout = Layout::from_size_align(100, 64).unwrap();
+        let buffer = unsafe { allocator.alloc(layout) };
+        assert_eq!(buffer as usize % 64, 0);
+        unsafe { buffer.write_bytes(0xa5, 100) };
+        unsafe { allocator.dealloc(buffer, layout) };
+
+        let calls = calls();
+        assert_eq!(calls[0], Call::AllocatePool(efi::BOOT_SERVICES_DATA, 164));
+        let Call::FreePool(pool) = calls[1] else { panic!("unexpected call {:?}", calls[1]) };
+        assert!((buffer as usize - 64..buffer as usize).contains(&(pool as usize)));
+    }
+}
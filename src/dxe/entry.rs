@@ -0,0 +1,166 @@
+//! DXE Driver Entry Point
+//!
+//! The entry point type of DXE drivers, handle newtypes keeping image handles apart from the handles protocols are
+//! installed on, and [`dxe_entry!`](crate::dxe_entry) to declare the entry point of a driver as a safe function.
+//!
+//! The entry point generated by the macro stores the image handle and system table before calling the driver, so they
+//! are available from [`image_handle`] and [`system_table`] afterwards.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V2_DXE_Foundation.html#dxe-foundation-entry-point>
+//!
+//! ## Example
+//! ```no_run
+//! use mu_pi::{dxe::entry::ImageHandle, dxe_entry};
+//! use r_efi::efi;
+//!
+//! fn driver_entry(image_handle: ImageHandle, system_table: &efi::SystemTable) -> efi::Status {
+//!     if system_table.boot_services.is_null() {
+//!         return efi::Status::UNSUPPORTED;
+//!     }
+//!     efi::Status::SUCCESS
+//! }
+//!
+//! dxe_entry!(driver_entry);
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{
+    ffi::c_void,
+    ptr,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+use r_efi::efi;
+
+#[doc(hidden)]
+pub use r_efi::efi as _efi;
+
+/// The entry point of a UEFI image (EFI_IMAGE_ENTRY_POINT).
+///
+/// # Documentation
+/// UEFI Specification, Release 2.10, Section 7.4.1
+pub type ImageEntryPoint =
+    extern "efiapi" fn(image_handle: efi::Handle, system_table: *mut efi::SystemTable) -> efi::Status;
+
+/// The handle of a loaded image.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ImageHandle(efi::Handle);
+
+impl ImageHandle {
+    /// Wraps the handle of a loaded image.
+    pub const fn new(handle: efi::Handle) -> Self {
+        Self(handle)
+    }
+
+    /// Returns the wrapped handle.
+    pub const fn as_handle(&self) -> efi::Handle {
+        self.0
+    }
+}
+
+/// The handle of a device or service protocols are installed on.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProtocolHandle(efi::Handle);
+
+impl ProtocolHandle {
+    /// Wraps the handle of a device or service.
+    pub const fn new(handle: efi::Handle) -> Self {
+        Self(handle)
+    }
+
+    /// Returns the wrapped handle.
+    pub const fn as_handle(&self) -> efi::Handle {
+        self.0
+    }
+}
+
+static IMAGE_HANDLE: AtomicPtr<c_void> = AtomicPtr::new(ptr::null_mut());
+static SYSTEM_TABLE: AtomicPtr<efi::SystemTable> = AtomicPtr::new(ptr::null_mut());
+
+/// Returns the image handle passed to the entry point generated by [`dxe_entry!`](crate::dxe_entry), or `None` before
+/// it runs.
+pub fn image_handle() -> Option<ImageHandle> {
+    let handle = IMAGE_HANDLE.load(Ordering::Acquire);
+    (!handle.is_null()).then_some(ImageHandle(handle))
+}
+
+/// Returns the system table passed to the entry point generated by [`dxe_entry!`](crate::dxe_entry), or `None` before
+/// it runs.
+///
+/// The boot services of the table must not be used after ExitBootServices().
+pub fn system_table() -> Option<&'static efi::SystemTable> {
+    //Safety: _dispatch() only stores the system table passed by the firmware, which stays valid.
+    unsafe { SYSTEM_TABLE.load(Ordering::Acquire).as_ref() }
+}
+
+#[doc(hidden)]
+pub unsafe fn _dispatch(
+    image_handle: efi::Handle,
+    system_table: *mut efi::SystemTable,
+    entry: fn(ImageHandle, &efi::SystemTable) -> efi::Status,
+) -> efi::Status {
+    if image_handle.is_null() || system_table.is_null() {
+        return efi::Status::INVALID_PARAMETER;
+    }
+    IMAGE_HANDLE.store(image_handle, Ordering::Release);
+    SYSTEM_TABLE.store(system_table, Ordering::Release);
+    entry(ImageHandle(image_handle), &*system_table)
+}
+
+/// Declares `efi_main`, the entry point of the image, calling a `fn(ImageHandle, &efi::SystemTable) -> efi::Status`
+/// after storing the image handle and system table for [`image_handle`](crate::dxe::entry::image_handle) and
+/// [`system_table`](crate::dxe::entry::system_table).
+///
+/// The entry point returns INVALID_PARAMETER without calling the function if either argument is null.
+#[macro_export]
+macro_rules! dxe_entry {
+    ($entry:path $(,)?) => {
+        #[export_name = "efi_main"]
+        extern "efiapi" fn __dxe_entry(
+            image_handle: $crate::dxe::entry::_efi::Handle,
+            system_table: *mut $crate::dxe::entry::_efi::SystemTable,
+        ) -> $crate::dxe::entry::_efi::Status {
+            //Safety: the firmware passes the handle of the image and a valid system table.
+            unsafe { $crate::dxe::entry::_dispatch(image_handle, system_table, $entry) }
+        }
+
+        const _: $crate::dxe::entry::ImageEntryPoint = __dxe_entry;
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{ffi::c_void, mem::MaybeUninit, ptr};
+
+    use r_efi::efi;
+
+    use super::{_dispatch, image_handle, system_table, ImageHandle};
+
+    fn entry(image: ImageHandle, table: &efi::SystemTable) -> efi::Status {
+        assert_eq!(image_handle(), Some(image));
+        assert!(ptr::eq(system_table().unwrap(), table));
+        efi::Status::SUCCESS
+    }
+
+    #[test]
+    fn dispatch_should_store_the_image_handle_and_system_table() {
+        // The table only holds pointers, so zeroes are valid; it is leaked as the stored table must stay valid.
+        let table = Box::leak(Box::new(MaybeUninit::<efi::SystemTable>::zeroed()));
+        let handle = 0x1000 as *mut c_void;
+
+        assert_eq!(unsafe { _dispatch(handle, ptr::null_mut(), entry) }, efi::Status::INVALID_PARAMETER);
+        assert_eq!(image_handle(), None);
+
+        assert_eq!(unsafe { _dispatch(handle, table.as_mut_ptr(), entry) }, efi::Status::SUCCESS);
+        assert_eq!(image_handle().map(|image| image.as_handle()), Some(handle));
+        assert!(ptr::eq(system_table().unwrap(), table.as_ptr()));
+    }
+}
@@ -0,0 +1,171 @@
+//! DXE Event Groups
+//!
+//! GUIDs of the event groups, and of the notification protocols used as event groups, that the PI Specification
+//! defines for the DXE phase. Drivers register for an event group with CreateEventEx(), and for a notification
+//! protocol with RegisterProtocolNotify().
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V2_DXE_Boot_Services_Protocol.html>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use r_efi::efi;
+
+/// End of DXE event group.
+///
+/// Signaled by the platform, typically from BDS, once every driver shipped with the platform firmware has been
+/// dispatched and before any code that is not part of the platform firmware (option ROMs, boot options) is run. It
+/// precedes [`DXE_MM_READY_TO_LOCK_PROTOCOL_GUID`]; drivers use it to lock resources that third-party code must not
+/// change.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section II-5.1.2.1
+pub const END_OF_DXE_EVENT_GROUP_GUID: efi::Guid =
+    efi::Guid::from_fields(0x02ce967a, 0xdd7e, 0x4ffc, 0x9e, 0xe7, &[0x81, 0x0c, 0xf0, 0x47, 0x08, 0x80]);
+
+/// DXE dispatch event group.
+///
+/// Signaled by the DXE core each time the DXE dispatcher completes a pass over the firmware volumes and has no more
+/// drivers to dispatch, including the passes that follow Dispatch() calls made by BDS after the Security
+/// Architectural Protocol deferred or the platform trusted a driver. Drivers use it to learn when the drivers that
+/// may produce a protocol they wait on have all run.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section II-5.1.2
+pub const DXE_DISPATCH_EVENT_GROUP_GUID: efi::Guid =
+    efi::Guid::from_fields(0x7081e22f, 0xcac6, 0x4053, 0x94, 0x68, &[0x67, 0x57, 0x82, 0xcf, 0x88, 0xe5]);
+
+/// DXE MM Ready To Lock notification protocol (also known as DXE SMM Ready To Lock).
+///
+/// Installed by the platform, typically from BDS, after [`END_OF_DXE_EVENT_GROUP_GUID`] is signaled and before any
+/// third-party code is run. The MM IPL responds by locking MMRAM and installing [`MM_READY_TO_LOCK_PROTOCOL_GUID`] in
+/// the MM protocol database.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 4, EFI_DXE_MM_READY_TO_LOCK_PROTOCOL
+pub const DXE_MM_READY_TO_LOCK_PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x60ff8964, 0xe906, 0x41d0, 0xaf, 0xed, &[0xf2, 0x41, 0xe9, 0x74, 0xe0, 0x8e]);
+
+/// MM Ready To Lock notification protocol (also known as SMM Ready To Lock).
+///
+/// Installed in the MM protocol database by the MM IPL when [`DXE_MM_READY_TO_LOCK_PROTOCOL_GUID`] is installed,
+/// just before MMRAM is locked. MM drivers use it to lock their own resources.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 4, EFI_MM_READY_TO_LOCK_PROTOCOL
+pub const MM_READY_TO_LOCK_PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x47b7fa8c, 0xf4bd, 0x4af6, 0x82, 0x00, &[0x33, 0x30, 0x86, 0xf0, 0xd2, 0xc8]);
+
+/// MM End Of DXE notification protocol (also known as SMM End Of DXE).
+///
+/// Installed in the MM protocol database by the MM IPL when [`END_OF_DXE_EVENT_GROUP_GUID`] is signaled, so that MM
+/// drivers learn of End of DXE.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 4, EFI_MM_END_OF_DXE_PROTOCOL
+pub const MM_END_OF_DXE_PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x24e70042, 0xd5c5, 0x4260, 0x8c, 0x39, &[0x0a, 0xd3, 0xaa, 0x32, 0xe9, 0x3d]);
+
+/// The DXE event groups and notification protocols defined by the PI Specification, in the order they occur during
+/// a boot.
+///
+/// ## Example
+///```
+/// use mu_pi::dxe::event_groups::{EventGroup, END_OF_DXE_EVENT_GROUP_GUID};
+///
+/// assert_eq!(EventGroup::from_guid(&END_OF_DXE_EVENT_GROUP_GUID), Some(EventGroup::EndOfDxe));
+/// assert_eq!(EventGroup::EndOfDxe.guid(), END_OF_DXE_EVENT_GROUP_GUID);
+/// assert!(!EventGroup::EndOfDxe.is_protocol());
+///```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventGroup {
+    /// See [`DXE_DISPATCH_EVENT_GROUP_GUID`].
+    DxeDispatch,
+    /// See [`END_OF_DXE_EVENT_GROUP_GUID`].
+    EndOfDxe,
+    /// See [`MM_END_OF_DXE_PROTOCOL_GUID`].
+    MmEndOfDxe,
+    /// See [`DXE_MM_READY_TO_LOCK_PROTOCOL_GUID`].
+    DxeMmReadyToLock,
+    /// See [`MM_READY_TO_LOCK_PROTOCOL_GUID`].
+    MmReadyToLock,
+}
+
+impl EventGroup {
+    /// Every event group, in the order they occur during a boot.
+    pub const ALL: [Self; 5] =
+        [Self::DxeDispatch, Self::EndOfDxe, Self::MmEndOfDxe, Self::DxeMmReadyToLock, Self::MmReadyToLock];
+
+    /// Returns the event group identified by `guid`, or `None` if the GUID is not one of the groups.
+    pub fn from_guid(guid: &efi::Guid) -> Option<Self> {
+        Self::ALL.into_iter().find(|group| group.guid() == *guid)
+    }
+
+    /// Returns the GUID of the event group.
+    pub const fn guid(&self) -> efi::Guid {
+        match self {
+            Self::DxeDispatch => DXE_DISPATCH_EVENT_GROUP_GUID,
+            Self::EndOfDxe => END_OF_DXE_EVENT_GROUP_GUID,
+            Self::MmEndOfDxe => MM_END_OF_DXE_PROTOCOL_GUID,
+            Self::DxeMmReadyToLock => DXE_MM_READY_TO_LOCK_PROTOCOL_GUID,
+            Self::MmReadyToLock => MM_READY_TO_LOCK_PROTOCOL_GUID,
+        }
+    }
+
+    /// Returns true if the group is announced by installing a protocol, to be waited on with
+    /// RegisterProtocolNotify(), rather than by signaling an event group.
+    pub const fn is_protocol(&self) -> bool {
+        !matches!(self, Self::DxeDispatch | Self::EndOfDxe)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use r_efi::efi;
+
+    use super::EventGroup;
+
+    #[test]
+    fn guids_should_match_spec() {
+        let expected: [(EventGroup, [u8; 16]); 5] = [
+            (
+                EventGroup::DxeDispatch,
+                [0x2f, 0xe2, 0x81, 0x70, 0xc6, 0xca, 0x53, 0x40, 0x94, 0x68, 0x67, 0x57, 0x82, 0xcf, 0x88, 0xe5],
+            ),
+            (
+                EventGroup::EndOfDxe,
+                [0x7a, 0x96, 0xce, 0x02, 0x7e, 0xdd, 0xfc, 0x4f, 0x9e, 0xe7, 0x81, 0x0c, 0xf0, 0x47, 0x08, 0x80],
+            ),
+            (
+                EventGroup::MmEndOfDxe,
+                [0x42, 0x00, 0xe7, 0x24, 0xc5, 0xd5, 0x60, 0x42, 0x8c, 0x39, 0x0a, 0xd3, 0xaa, 0x32, 0xe9, 0x3d],
+            ),
+            (
+                EventGroup::DxeMmReadyToLock,
+                [0x64, 0x89, 0xff, 0x60, 0x06, 0xe9, 0xd0, 0x41, 0xaf, 0xed, 0xf2, 0x41, 0xe9, 0x74, 0xe0, 0x8e],
+            ),
+            (
+                EventGroup::MmReadyToLock,
+                [0x8c, 0xfa, 0xb7, 0x47, 0xbd, 0xf4, 0xf6, 0x4a, 0x82, 0x00, 0x33, 0x30, 0x86, 0xf0, 0xd2, 0xc8],
+            ),
+        ];
+        for (group, bytes) in expected {
+            assert_eq!(group.guid().as_bytes(), &bytes, "{group:?}");
+        }
+        assert_eq!(expected.map(|(group, _)| group), EventGroup::ALL);
+    }
+
+    #[test]
+    fn groups_should_round_trip_through_guid() {
+        for group in EventGroup::ALL {
+            assert_eq!(EventGroup::from_guid(&group.guid()), Some(group));
+        }
+        let other = efi::Guid::from_fields(1, 2, 3, 4, 5, &[6, 7, 8, 9, 10, 11]);
+        assert_eq!(EventGroup::from_guid(&other), None);
+        assert_eq!(EventGroup::ALL.iter().filter(|group| group.is_protocol()).count(), 3);
+    }
+}
@@ -0,0 +1,267 @@
+//! Schedule On Request (SOR) Driver Management
+//!
+//! The DXE Dispatcher does not load drivers whose dependency expression begins with the SOR opcode, and it does not
+//! load drivers that the Security Architectural Protocol placed in the untrusted state. Such drivers stay pending until
+//! a platform component explicitly calls the Schedule() or Trust() DXE Services for them.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V2_Services_DXE_Services.html#dispatcher-services>.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use r_efi::efi;
+
+use crate::{
+    dxe_services::{DxeServices, DxeServicesTable},
+    status::StatusError,
+};
+
+/// Promotes a file in a firmware volume from the untrusted to the trusted state using the Trust() DXE Service.
+///
+/// See [`DxeServices::trust`].
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section II-7.3.3
+pub fn trust_driver(
+    dxe_services: &DxeServicesTable,
+    firmware_volume_handle: efi::Handle,
+    file_name: &efi::Guid,
+) -> Result<(), StatusError> {
+    DxeServices::from_table(dxe_services).trust(firmware_volume_handle, file_name)
+}
+
+/// Clears the Schedule On Request (SOR) flag for a file in a firmware volume using the Schedule() DXE Service.
+///
+/// See [`DxeServices::schedule`].
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section II-7.3.2
+pub fn schedule_driver(
+    dxe_services: &DxeServicesTable,
+    firmware_volume_handle: efi::Handle,
+    file_name: &efi::Guid,
+) -> Result<(), StatusError> {
+    DxeServices::from_table(dxe_services).schedule(firmware_volume_handle, file_name)
+}
+
+/// A driver that is waiting on an explicit Schedule() or Trust() call before it can be dispatched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SorEntry {
+    /// Handle of the firmware volume that contains the driver.
+    pub firmware_volume_handle: efi::Handle,
+    /// File name GUID of the driver within the firmware volume.
+    pub file_name: efi::Guid,
+}
+
+/// Tracks drivers that are pending dispatch because they were left untrusted or on request.
+///
+/// Entries are removed once the corresponding DXE Service call succeeds. Entries for which the call fails remain
+/// pending so that the caller can retry or report them.
+#[derive(Debug)]
+pub struct SorManager<'a> {
+    dxe_services: DxeServices<'a>,
+    pending: Vec<SorEntry>,
+}
+
+impl<'a> SorManager<'a> {
+    /// Instantiates a new SorManager that calls the dispatcher services of `dxe_services`.
+    pub fn new(dxe_services: DxeServices<'a>) -> Self {
+        Self { dxe_services, pending: Vec::new() }
+    }
+
+    /// Adds a driver to the pending list. Duplicate entries are ignored.
+    pub fn add(&mut self, firmware_volume_handle: efi::Handle, file_name: efi::Guid) {
+        let entry = SorEntry { firmware_volume_handle, file_name };
+        if !self.pending.contains(&entry) {
+            self.pending.push(entry);
+        }
+    }
+
+    /// Returns the drivers that are still pending.
+    pub fn pending(&self) -> &[SorEntry] {
+        &self.pending
+    }
+
+    /// Calls Trust() for every pending driver.
+    ///
+    /// Returns `Ok` if every call succeeded, otherwise the error of the first failure.
+    pub fn trust_all(&mut self) -> Result<(), StatusError> {
+        self.trust_if(|_| true)
+    }
+
+    /// Calls Trust() for every pending driver that the given policy approves.
+    ///
+    /// This is where a platform connects the decision logic of its Security Architectural Protocol implementation:
+    /// drivers that were deferred with `EFI_SECURITY_VIOLATION` can be re-evaluated here, and only the ones approved
+    /// by `policy` are promoted to the trusted state. Drivers rejected by the policy stay pending.
+    ///
+    /// Returns `Ok` if every Trust() call succeeded, otherwise the error of the first failure.
+    pub fn trust_if<F>(&mut self, mut policy: F) -> Result<(), StatusError>
+    where
+        F: FnMut(&SorEntry) -> bool,
+    {
+        let dxe_services = self.dxe_services;
+        Self::process(&mut self.pending, |entry| {
+            if policy(entry) {
+                Some(dxe_services.trust(entry.firmware_volume_handle, &entry.file_name))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Calls Schedule() for every pending driver.
+    ///
+    /// Returns `Ok` if every call succeeded, otherwise the error of the first failure.
+    pub fn schedule_all(&mut self) -> Result<(), StatusError> {
+        let dxe_services = self.dxe_services;
+        Self::process(&mut self.pending, |entry| {
+            Some(dxe_services.schedule(entry.firmware_volume_handle, &entry.file_name))
+        })
+    }
+
+    // Applies `action` to each pending entry, dropping entries for which it returns success.
+    fn process<F>(pending: &mut Vec<SorEntry>, mut action: F) -> Result<(), StatusError>
+    where
+        F: FnMut(&SorEntry) -> Option<Result<(), StatusError>>,
+    {
+        let mut result = Ok(());
+        pending.retain(|entry| match action(entry) {
+            Some(Ok(())) => false,
+            Some(Err(error)) => {
+                if result.is_ok() {
+                    result = Err(error);
+                }
+                true
+            }
+            None => true,
+        });
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::RefCell;
+
+    use r_efi::efi;
+
+    use crate::{
+        dxe_services::{tests::fake_table, DxeServices, DxeServicesTable},
+        status::StatusError,
+    };
+
+    use super::{schedule_driver, trust_driver, SorManager};
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Call {
+        Trust(efi::Handle, efi::Guid),
+        Schedule(efi::Handle, efi::Guid),
+    }
+
+    std::thread_local! {
+        static CALLS: RefCell<Vec<Call>> = RefCell::new(Vec::new());
+    }
+
+    const FAILING_FILE: efi::Guid = efi::Guid::from_fields(0xdeadbeef, 0, 0, 0, 0, &[0, 0, 0, 0, 0, 0]);
+
+    extern "efiapi" fn trust(handle: efi::Handle, file: *const efi::Guid) -> efi::Status {
+        let file = unsafe { *file };
+        CALLS.with(|calls| calls.borrow_mut().push(Call::Trust(handle, file)));
+        if file == FAILING_FILE {
+            efi::Status::NOT_FOUND
+        } else {
+            efi::Status::SUCCESS
+        }
+    }
+
+    extern "efiapi" fn schedule(handle: efi::Handle, file: *const efi::Guid) -> efi::Status {
+        let file = unsafe { *file };
+        CALLS.with(|calls| calls.borrow_mut().push(Call::Schedule(handle, file)));
+        efi::Status::SUCCESS
+    }
+
+    fn mock_dxe_services() -> DxeServicesTable {
+        DxeServicesTable { schedule, trust, ..fake_table() }
+    }
+
+    fn take_calls() -> Vec<Call> {
+        CALLS.with(|calls| calls.borrow_mut().drain(..).collect())
+    }
+
+    fn guid(n: u32) -> efi::Guid {
+        efi::Guid::from_fields(n, 0x1234, 0x5678, 0x9a, 0xbc, &[1, 2, 3, 4, 5, 6])
+    }
+
+    #[test]
+    fn trust_and_schedule_driver_pass_arguments_through() {
+        let dxe_services = mock_dxe_services();
+        let fv_handle = 0x1000 as efi::Handle;
+
+        assert_eq!(trust_driver(&dxe_services, fv_handle, &guid(1)), Ok(()));
+        assert_eq!(schedule_driver(&dxe_services, fv_handle, &guid(2)), Ok(()));
+        assert_eq!(trust_driver(&dxe_services, fv_handle, &FAILING_FILE), Err(StatusError(efi::Status::NOT_FOUND)));
+        assert_eq!(
+            take_calls(),
+            vec![
+                Call::Trust(fv_handle, guid(1)),
+                Call::Schedule(fv_handle, guid(2)),
+                Call::Trust(fv_handle, FAILING_FILE)
+            ]
+        );
+    }
+
+    #[test]
+    fn trust_all_clears_pending_entries() {
+        let dxe_services = mock_dxe_services();
+        let fv_handle = 0x1000 as efi::Handle;
+        let mut manager = SorManager::new(DxeServices::from_table(&dxe_services));
+        manager.add(fv_handle, guid(1));
+        manager.add(fv_handle, guid(2));
+        manager.add(fv_handle, guid(1));
+        assert_eq!(manager.pending().len(), 2);
+
+        assert_eq!(manager.trust_all(), Ok(()));
+        assert!(manager.pending().is_empty());
+        assert_eq!(take_calls(), vec![Call::Trust(fv_handle, guid(1)), Call::Trust(fv_handle, guid(2))]);
+    }
+
+    #[test]
+    fn trust_all_keeps_failed_entries() {
+        let dxe_services = mock_dxe_services();
+        let fv_handle = 0x1000 as efi::Handle;
+        let mut manager = SorManager::new(DxeServices::from_table(&dxe_services));
+        manager.add(fv_handle, FAILING_FILE);
+        manager.add(fv_handle, guid(3));
+
+        assert_eq!(manager.trust_all(), Err(StatusError(efi::Status::NOT_FOUND)));
+        assert_eq!(manager.pending().len(), 1);
+        assert_eq!(manager.pending()[0].file_name, FAILING_FILE);
+        take_calls();
+    }
+
+    #[test]
+    fn trust_if_only_trusts_approved_entries() {
+        let dxe_services = mock_dxe_services();
+        let fv_handle = 0x1000 as efi::Handle;
+        let mut manager = SorManager::new(DxeServices::from_table(&dxe_services));
+        manager.add(fv_handle, guid(1));
+        manager.add(fv_handle, guid(2));
+
+        assert_eq!(manager.trust_if(|entry| entry.file_name == guid(2)), Ok(()));
+        assert_eq!(manager.pending().len(), 1);
+        assert_eq!(manager.pending()[0].file_name, guid(1));
+        assert_eq!(take_calls(), vec![Call::Trust(fv_handle, guid(2))]);
+
+        assert_eq!(manager.schedule_all(), Ok(()));
+        assert!(manager.pending().is_empty());
+        assert_eq!(take_calls(), vec![Call::Schedule(fv_handle, guid(1))]);
+    }
+}
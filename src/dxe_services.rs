@@ -14,21 +14,47 @@
 //! SPDX-License-Identifier: BSD-2-Clause-Patent
 //!
 
-use core::{default::Default, ffi::c_void};
+use core::{ffi::c_void, ptr};
 
 use r_efi::{
-    efi::{Guid, Handle, PhysicalAddress, Status},
+    efi::{self, Guid, Handle, PhysicalAddress, Status},
     system::TableHeader,
 };
 
+use crate::{
+    hob::{self, known_tables},
+    mem_attr::EfiMemoryAttributes,
+    status::{StatusError, StatusExt},
+};
+
+pub mod gcd;
+
+pub use gcd::{GcdAllocateType, GcdIoType, GcdMemoryType, IoSpaceDescriptor, MemorySpaceDescriptor};
+
+/// GUID of the EFI configuration table entry that points to the DXE Services Table.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section II-4.5
 pub const DXE_SERVICES_TABLE_GUID: Guid =
     Guid::from_fields(0x5ad34ba, 0x6f02, 0x4214, 0x95, 0x2e, &[0x4d, 0xa0, 0x39, 0x8e, 0x2b, 0xb9]);
 
+/// Signature of the DXE Services Table header ("DXE_SERV").
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section II-4.5
+pub const DXE_SERVICES_SIGNATURE: u64 = 0x565245535f455844;
+
+/// Revision of the DXE Services Table header for this version of the PI Specification.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section II-4.5
+pub const DXE_SERVICES_REVISION: u32 = (1 << 16) | 80;
+
 /// This service adds reserved memory system memory or memory mapped IO resources to the global coherency domain of the processor.
 ///
 /// # Documentation
 /// UEFI Platform Initialization Specification, Release 1.8, Section II-7.2.4.1
-pub type AddMemorySpace = extern "efiapi" fn(GcdMemoryType, PhysicalAddress, u64, u64) -> Status;
+pub type AddMemorySpace = extern "efiapi" fn(GcdMemoryType, PhysicalAddress, u64, EfiMemoryAttributes) -> Status;
 
 /// This service allocates nonexistent memory reserved memory system memory or memory-mapped IO resources
 /// from the global coherency domain of the processor.
@@ -62,13 +88,13 @@ pub type GetMemorySpaceDescriptor = extern "efiapi" fn(PhysicalAddress, *mut Mem
 ///
 /// # Documentation
 /// UEFI Platform Initialization Specification, Release 1.8, Section II-7.2.4.6
-pub type SetMemorySpaceAttributes = extern "efiapi" fn(PhysicalAddress, u64, u64) -> Status;
+pub type SetMemorySpaceAttributes = extern "efiapi" fn(PhysicalAddress, u64, EfiMemoryAttributes) -> Status;
 
 /// This service modifies the capabilities for a memory region in the global coherency domain of the processor.
 ///
 /// # Documentation
 /// UEFI Platform Initialization Specification, Release 1.8, Section II-7.2.4.7
-pub type SetMemorySpaceCapabilities = extern "efiapi" fn(PhysicalAddress, u64, u64) -> Status;
+pub type SetMemorySpaceCapabilities = extern "efiapi" fn(PhysicalAddress, u64, EfiMemoryAttributes) -> Status;
 
 /// Returns a map of the memory resources in the global coherency domain of the processor.
 ///
@@ -129,6 +155,9 @@ pub type Schedule = extern "efiapi" fn(Handle, *const Guid) -> Status;
 /// Only the Security Architectural Protocol can place a file in the untrusted state.
 /// A platform specific component may choose to use this service to promote a previously untrusted file to the trusted state.
 ///
+/// See [`crate::dxe::sor`] for tracking the drivers that the Security Architectural Protocol deferred and promoting
+/// them with this service.
+///
 /// # Documentation
 /// UEFI Platform Initialization Specification, Release 1.8, Section II-7.3.3
 pub type Trust = extern "efiapi" fn(Handle, *const Guid) -> Status;
@@ -139,118 +168,11 @@ pub type Trust = extern "efiapi" fn(Handle, *const Guid) -> Status;
 /// UEFI Platform Initialization Specification, Release 1.8, Section II-7.3. II-59 (This one does not have a section)
 pub type ProcessFirmwareVolume = extern "efiapi" fn(*const c_void, usize, *mut Handle) -> Status;
 
-#[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
-/// `EFI_GCD_MEMORY_TYPE` in specification.
-pub enum GcdMemoryType {
-    /// A memory region that is visible to the boot processor.
-    /// However, there are no system components that are currently decoding this memory region.
-    #[default]
-    NonExistent = 0,
-    /// A memory region that is visible to the boot processor.
-    /// This memory region is being decoded by a system component,
-    /// but the memory region is not considered to be either system memory or memory-mapped I/O.
-    Reserved,
-    /// A memory region that is visible to the boot processor.
-    /// A memory controller is currently decoding this memory region
-    /// and the memory controller is producing a tested system memory region that is available to the memory services.
-    SystemMemory,
-    /// A memory region that is visible to the boot processor. This memory region is currently being decoded by a
-    /// component as memory-mapped I/O that can be used to access I/O devices in the platform.
-    MemoryMappedIo,
-    /// A memory region that is visible to the boot processor. This memory supports byte-addressable non-volatility.
-    Persistent,
-    /// A memory region that provides higher reliability relative to other memory in the system.
-    /// If all memory has the same reliability, then this bit is not used.
-    MoreReliable,
-    /// A memory region that is unaccepted. This region must be accepted before it can be converted to system memory.
-    Unaccepted,
-}
-
-#[repr(C)]
-#[derive(Debug, Clone, Copy, Default)]
-/// `EFI_GCD_ALLOCATE_TYPE` in specification.
-pub enum GcdAllocateType {
-    #[default]
-    AnySearchBottomUp,
-    MaxAddressSearchBottomUp,
-    Address,
-    AnySearchTopDown,
-    MaxAddressSearchTopDown,
-    MaxAllocateType,
-}
-
-#[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-/// `EFI_GCD_MEMORY_SPACE_DESCRIPTOR` in specification.
-pub struct MemorySpaceDescriptor {
-    /// The physical address of the first byte in the memory region.
-    pub base_address: PhysicalAddress,
-    /// The number of bytes in the memory region.
-    pub length: u64,
-    /// The bit mask of attributes that the memory region is capable of supporting.
-    pub capabilities: u64,
-    /// The bit mask of attributes that the memory region is currently using.
-    pub attributes: u64,
-    /// Type of the memory region.
-    pub memory_type: GcdMemoryType,
-    /// The image handle of the agent that allocated the memory resource described by PhysicalStart and NumberOfBytes.
-    ///
-    /// If this field is NULL, then the memory resource is not currently allocated.
-    pub image_handle: Handle,
-    /// The device handle for which the memory resource has been allocated.
-    ///
-    /// If ImageHandle is NULL, then the memory resource is not currently allocated.
-    ///
-    /// If this field is NULL, then the memory resource is not associated with a device that is described by a device handle.
-    pub device_handle: Handle,
-}
-
-#[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
-/// `EFI_GCD_IO_TYPE` in specification.
-pub enum GcdIoType {
-    /// An I/O region that is visible to the boot processor.
-    /// However, there are no system components that are currently decoding this I/O region.
-    #[default]
-    NonExistent = 0,
-    /// An I/O region that is visible to the boot processor.
-    /// This I/O region is currently being decoded by a system component,
-    ///  but the I/O region cannot be used to access I/O devices.
-    Reserved,
-    /// An I/O region that is visible to the boot processor.
-    /// This I/O region is currently being decoded by a system component
-    /// that is producing I/O ports that can be used to access I/O devices.
-    Io,
-    Maximum,
-}
-
-#[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-/// `EFI_GCD_IO_SPACE_DESCRIPTOR` in specification.
-pub struct IoSpaceDescriptor {
-    /// Physical address of the first byte in the I/O region.
-    pub base_address: PhysicalAddress,
-    /// Number of bytes in the I/O region.
-    pub length: u64,
-    /// Type of the I/O region.
-    pub io_type: GcdIoType,
-    /// The image handle of the agent that allocated the I/O resource described by PhysicalStart and NumberOfBytes.
-    ///
-    /// If this field is NULL, then the I/O resource is not currently allocated.
-    pub image_handle: Handle,
-    /// The device handle for which the I/O resource has been allocated.
-    ///
-    /// If ImageHandle is NULL , then the I/O resource is not currently allocated.
-    ///
-    /// If this field is NULL, then the I/O resource is not associated with a device that is described by a device handle.
-    pub device_handle: Handle,
-}
-
 #[repr(C)]
 /// Contains a table header and pointers to all of the DXE-specific services.
 ///
 /// See <https://uefi.org/specs/PI/1.8A/V2_UEFI_System_Table.html#dxe-services-table>.
+#[derive(Debug)]
 pub struct DxeServicesTable {
     pub header: TableHeader,
 
@@ -290,28 +212,408 @@ pub struct DxeServicesTable {
     pub set_memory_space_capabilities: SetMemorySpaceCapabilities,
 }
 
-impl Default for MemorySpaceDescriptor {
-    fn default() -> Self {
-        Self {
-            base_address: Default::default(),
-            length: Default::default(),
-            capabilities: Default::default(),
-            attributes: Default::default(),
-            memory_type: Default::default(),
-            image_handle: 0 as Handle,
-            device_handle: 0 as Handle,
+impl DxeServicesTable {
+    /// Locates the DXE Services Table in the EFI configuration table of `system_table`.
+    ///
+    /// Returns `None` if [`hob::find_configuration_table`] finds no entry for
+    /// [`known_tables::DXE_SERVICES_TABLE_GUID`], or if the entry does not point to a table with the
+    /// [`DXE_SERVICES_SIGNATURE`].
+    ///
+    /// ## Safety
+    /// Caller must ensure that the configuration table of `system_table` is valid, that its DXE Services Table entry
+    /// points to at least a readable table header, and that a table with the expected signature is valid for as long
+    /// as the returned reference is used.
+    pub unsafe fn locate(system_table: &efi::SystemTable) -> Option<&Self> {
+        let table =
+            hob::find_configuration_table(system_table, &known_tables::DXE_SERVICES_TABLE_GUID).ok()? as *const Self;
+        if table.is_null() || ptr::addr_of!((*table).header.signature).read_unaligned() != DXE_SERVICES_SIGNATURE {
+            return None;
         }
+        table.as_ref()
+    }
+}
+
+/// Safe wrapper over the dispatcher services of a DXE Services Table.
+///
+/// ## Example
+///```no_run
+/// use mu_pi::{
+///   dxe_services::{DxeServices, DxeServicesTable},
+///   status::StatusError,
+/// };
+/// use r_efi::efi;
+///
+/// fn example(system_table: &efi::SystemTable, fv_handle: efi::Handle, file: &efi::Guid) -> Result<(), StatusError> {
+///   let table = unsafe { DxeServicesTable::locate(system_table) }.ok_or(StatusError(efi::Status::NOT_FOUND))?;
+///   let dxe_services = DxeServices::from_table(table);
+///   dxe_services.trust(fv_handle, file)?;
+///   dxe_services.dispatch()
+/// }
+///```
+#[derive(Debug, Clone, Copy)]
+pub struct DxeServices<'a> {
+    table: &'a DxeServicesTable,
+}
+
+impl<'a> DxeServices<'a> {
+    /// Instantiates a new DxeServices from a table pointer.
+    ///
+    /// Returns `INVALID_PARAMETER` if `table` is null or does not point to a table with the
+    /// [`DXE_SERVICES_SIGNATURE`].
+    ///
+    /// ## Safety
+    /// Caller must ensure that a non-null `table` points to a readable table header and, if the signature matches, to
+    /// a valid DXE Services Table for `'a`.
+    pub unsafe fn new(table: *const DxeServicesTable) -> Result<Self, StatusError> {
+        if table.is_null() || ptr::addr_of!((*table).header.signature).read_unaligned() != DXE_SERVICES_SIGNATURE {
+            Err(StatusError(Status::INVALID_PARAMETER))?;
+        }
+        Ok(Self { table: &*table })
+    }
+
+    /// Instantiates a new DxeServices over a table, e.g. one returned by [`DxeServicesTable::locate`].
+    pub fn from_table(table: &'a DxeServicesTable) -> Self {
+        Self { table }
+    }
+
+    /// Returns the wrapped table.
+    pub fn table(&self) -> &'a DxeServicesTable {
+        self.table
+    }
+
+    /// Loads and executes the DXE drivers whose dependencies are satisfied, including those made dispatchable by
+    /// [`Self::schedule`] and [`Self::trust`].
+    ///
+    /// Returns `NOT_FOUND` if no driver was dispatched, and `ALREADY_STARTED` if called from within the dispatcher.
+    pub fn dispatch(&self) -> Result<(), StatusError> {
+        (self.table.dispatch)().ok().map_err(StatusError)
+    }
+
+    /// Clears the Schedule On Request (SOR) flag of `file` in the firmware volume `fv_handle`, so that the next
+    /// [`Self::dispatch`] can load it.
+    ///
+    /// Returns `NOT_FOUND` if the file is not in the SOR state.
+    pub fn schedule(&self, fv_handle: Handle, file: &Guid) -> Result<(), StatusError> {
+        (self.table.schedule)(fv_handle, file as *const Guid).ok().map_err(StatusError)
+    }
+
+    /// Promotes `file` in the firmware volume `fv_handle` from the untrusted to the trusted state.
+    ///
+    /// A driver is left in the untrusted state when the Security Architectural Protocol returns `SECURITY_VIOLATION`
+    /// for it during dispatch: the platform may still decide to run it later, e.g. after user consent. Once trusted,
+    /// the driver is loaded by the next [`Self::dispatch`]. Drivers for which the Security Architectural Protocol
+    /// returned `ACCESS_DENIED` are never dispatched and cannot be trusted. See [`crate::dxe::sor`] for tracking the
+    /// deferred drivers.
+    ///
+    /// Returns `NOT_FOUND` if the file is not in the untrusted state.
+    pub fn trust(&self, fv_handle: Handle, file: &Guid) -> Result<(), StatusError> {
+        (self.table.trust)(fv_handle, file as *const Guid).ok().map_err(StatusError)
+    }
+
+    /// Creates a firmware volume handle for the firmware volume in `buffer`, and returns the handle.
+    ///
+    /// The DXE core keeps accessing the firmware volume through the handle, so the buffer must never be freed; a
+    /// buffer allocated at runtime can be leaked with `Box::leak()`. Returns `VOLUME_CORRUPTED` if the buffer does not
+    /// hold a valid firmware volume, and `OUT_OF_RESOURCES` if the handle could not be created.
+    pub fn process_firmware_volume(&self, buffer: &'static [u8]) -> Result<Handle, StatusError> {
+        let mut handle: Handle = ptr::null_mut();
+        (self.table.process_firmware_volume)(buffer.as_ptr() as *const c_void, buffer.len(), &mut handle).ok()?;
+        Ok(handle)
     }
 }
 
-impl Default for IoSpaceDescriptor {
-    fn default() -> Self {
-        Self {
-            base_address: Default::default(),
-            length: Default::default(),
-            io_type: Default::default(),
-            image_handle: 0 as Handle,
-            device_handle: 0 as Handle,
+#[cfg(test)]
+pub(crate) mod tests {
+    use core::{
+        ffi::c_void,
+        mem::{self, size_of},
+        ptr,
+    };
+
+    use r_efi::efi;
+
+    use super::{
+        DxeServicesTable, GcdAllocateType, GcdIoType, GcdMemoryType, IoSpaceDescriptor, MemorySpaceDescriptor,
+        DXE_SERVICES_SIGNATURE, DXE_SERVICES_TABLE_GUID,
+    };
+    use crate::mem_attr::EfiMemoryAttributes;
+
+    extern "efiapi" fn add_memory_space(_: GcdMemoryType, _: u64, _: u64, _: EfiMemoryAttributes) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+    extern "efiapi" fn allocate_memory_space(
+        _: GcdAllocateType,
+        _: GcdMemoryType,
+        _: usize,
+        _: u64,
+        _: *mut u64,
+        _: efi::Handle,
+        _: efi::Handle,
+    ) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+    extern "efiapi" fn free_memory_space(_: u64, _: u64) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+    extern "efiapi" fn get_memory_space_descriptor(_: u64, _: *mut MemorySpaceDescriptor) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+    extern "efiapi" fn set_memory_space_attributes(_: u64, _: u64, _: EfiMemoryAttributes) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+    extern "efiapi" fn get_memory_space_map(_: *mut usize, _: *mut *mut MemorySpaceDescriptor) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+    extern "efiapi" fn add_io_space(_: GcdIoType, _: u64, _: u64) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+    extern "efiapi" fn allocate_io_space(
+        _: GcdAllocateType,
+        _: GcdIoType,
+        _: usize,
+        _: u64,
+        _: *mut u64,
+        _: efi::Handle,
+        _: efi::Handle,
+    ) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+    extern "efiapi" fn get_io_space_descriptor(_: u64, _: *mut IoSpaceDescriptor) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+    extern "efiapi" fn get_io_space_map(_: *mut usize, _: *mut *mut IoSpaceDescriptor) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+
+    extern "efiapi" fn dispatch() -> efi::Status {
+        efi::Status::NOT_FOUND
+    }
+    extern "efiapi" fn schedule(_: efi::Handle, _: *const efi::Guid) -> efi::Status {
+        efi::Status::NOT_FOUND
+    }
+    extern "efiapi" fn process_firmware_volume(_: *const c_void, _: usize, _: *mut efi::Handle) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+
+    /// A DXE Services Table with a valid header, whose services fail. Tests override the services they exercise.
+    pub(crate) fn fake_table() -> DxeServicesTable {
+        let mut header: r_efi::system::TableHeader = unsafe { mem::zeroed() };
+        header.signature = DXE_SERVICES_SIGNATURE;
+        DxeServicesTable {
+            header,
+            add_memory_space,
+            allocate_memory_space,
+            free_memory_space,
+            remove_memory_space: free_memory_space,
+            get_memory_space_descriptor,
+            set_memory_space_attributes,
+            get_memory_space_map,
+            add_io_space,
+            allocate_io_space,
+            free_io_space: free_memory_space,
+            remove_io_space: free_memory_space,
+            get_io_space_descriptor,
+            get_io_space_map,
+            dispatch,
+            schedule,
+            trust: schedule,
+            process_firmware_volume,
+            set_memory_space_capabilities: set_memory_space_attributes,
+        }
+    }
+
+    #[test]
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    fn table_layout_should_match_spec() {
+        assert_eq!(offset_of!(DxeServicesTable, add_memory_space), 24);
+        assert_eq!(offset_of!(DxeServicesTable, allocate_memory_space), 32);
+        assert_eq!(offset_of!(DxeServicesTable, free_memory_space), 40);
+        assert_eq!(offset_of!(DxeServicesTable, remove_memory_space), 48);
+        assert_eq!(offset_of!(DxeServicesTable, get_memory_space_descriptor), 56);
+        assert_eq!(offset_of!(DxeServicesTable, set_memory_space_attributes), 64);
+        assert_eq!(offset_of!(DxeServicesTable, get_memory_space_map), 72);
+        assert_eq!(offset_of!(DxeServicesTable, add_io_space), 80);
+        assert_eq!(offset_of!(DxeServicesTable, allocate_io_space), 88);
+        assert_eq!(offset_of!(DxeServicesTable, free_io_space), 96);
+        assert_eq!(offset_of!(DxeServicesTable, remove_io_space), 104);
+        assert_eq!(offset_of!(DxeServicesTable, get_io_space_descriptor), 112);
+        assert_eq!(offset_of!(DxeServicesTable, get_io_space_map), 120);
+        assert_eq!(offset_of!(DxeServicesTable, dispatch), 128);
+        assert_eq!(offset_of!(DxeServicesTable, schedule), 136);
+        assert_eq!(offset_of!(DxeServicesTable, trust), 144);
+        assert_eq!(offset_of!(DxeServicesTable, process_firmware_volume), 152);
+        assert_eq!(offset_of!(DxeServicesTable, set_memory_space_capabilities), 160);
+        assert_eq!(size_of::<DxeServicesTable>(), 168);
+
+        assert_eq!(offset_of!(MemorySpaceDescriptor, memory_type), 32);
+        assert_eq!(offset_of!(MemorySpaceDescriptor, image_handle), 40);
+        assert_eq!(offset_of!(MemorySpaceDescriptor, device_handle), 48);
+        assert_eq!(size_of::<MemorySpaceDescriptor>(), 56);
+
+        assert_eq!(offset_of!(IoSpaceDescriptor, io_type), 16);
+        assert_eq!(offset_of!(IoSpaceDescriptor, image_handle), 24);
+        assert_eq!(offset_of!(IoSpaceDescriptor, device_handle), 32);
+        assert_eq!(size_of::<IoSpaceDescriptor>(), 40);
+    }
+
+    extern "efiapi" fn unused() -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+
+    #[test]
+    fn locate_should_find_table_by_guid_and_signature() {
+        // Every service pointer is set to a valid function so that the buffer may be viewed as a table; the services
+        // are never called.
+        let mut table = [unused as usize; size_of::<DxeServicesTable>() / size_of::<usize>()];
+        let table_ptr = table.as_mut_ptr() as *mut DxeServicesTable;
+        unsafe { ptr::addr_of_mut!((*table_ptr).header).write(mem::zeroed()) };
+
+        let other = efi::Guid::from_fields(1, 2, 3, 4, 5, &[6, 7, 8, 9, 10, 11]);
+        let mut entries = [
+            efi::ConfigurationTable { vendor_guid: other, vendor_table: ptr::null_mut() },
+            efi::ConfigurationTable { vendor_guid: DXE_SERVICES_TABLE_GUID, vendor_table: table_ptr as *mut c_void },
+        ];
+        let mut system_table: efi::SystemTable = unsafe { mem::zeroed() };
+        assert!(unsafe { DxeServicesTable::locate(&system_table) }.is_none());
+
+        system_table.number_of_table_entries = entries.len();
+        system_table.configuration_table = entries.as_mut_ptr();
+        // The entry is ignored until the table carries the expected signature.
+        assert!(unsafe { DxeServicesTable::locate(&system_table) }.is_none());
+
+        unsafe { (*table_ptr).header.signature = DXE_SERVICES_SIGNATURE };
+        let located = unsafe { DxeServicesTable::locate(&system_table) }.unwrap();
+        assert!(ptr::eq(located, table_ptr));
+
+        system_table.number_of_table_entries = usize::MAX;
+        assert!(unsafe { DxeServicesTable::locate(&system_table) }.is_none());
+        system_table.number_of_table_entries = entries.len();
+
+        entries[1].vendor_guid = other;
+        assert!(unsafe { DxeServicesTable::locate(&system_table) }.is_none());
+    }
+
+    mod wrapper {
+        use core::{cell::RefCell, ffi::c_void, ptr};
+
+        use r_efi::efi;
+
+        use crate::{
+            dxe_services::{DxeServices, DxeServicesTable},
+            status::StatusError,
+        };
+
+        // A minimal dispatcher: files deferred by the security policy stay untrusted until Trust() promotes them, and
+        // Dispatch() loads the trusted files that have not been loaded yet.
+        #[derive(Default)]
+        struct Dispatcher {
+            untrusted: Vec<(usize, efi::Guid)>,
+            trusted: Vec<(usize, efi::Guid)>,
+            dispatched: Vec<efi::Guid>,
+            scheduled: Vec<(usize, efi::Guid)>,
+            processed: Vec<(usize, usize)>,
+        }
+
+        std::thread_local! {
+            static DISPATCHER: RefCell<Dispatcher> = RefCell::new(Dispatcher::default());
+        }
+
+        extern "efiapi" fn dispatch() -> efi::Status {
+            DISPATCHER.with(|d| {
+                let mut d = d.borrow_mut();
+                let pending: Vec<_> = d.trusted.drain(..).map(|(_, file)| file).collect();
+                if pending.is_empty() {
+                    return efi::Status::NOT_FOUND;
+                }
+                d.dispatched.extend(pending);
+                efi::Status::SUCCESS
+            })
+        }
+
+        extern "efiapi" fn schedule(fv_handle: efi::Handle, file: *const efi::Guid) -> efi::Status {
+            let file = unsafe { *file };
+            DISPATCHER.with(|d| d.borrow_mut().scheduled.push((fv_handle as usize, file)));
+            efi::Status::SUCCESS
+        }
+
+        extern "efiapi" fn trust(fv_handle: efi::Handle, file: *const efi::Guid) -> efi::Status {
+            let entry = (fv_handle as usize, unsafe { *file });
+            DISPATCHER.with(|d| {
+                let mut d = d.borrow_mut();
+                match d.untrusted.iter().position(|untrusted| *untrusted == entry) {
+                    Some(index) => {
+                        d.untrusted.remove(index);
+                        d.trusted.push(entry);
+                        efi::Status::SUCCESS
+                    }
+                    None => efi::Status::NOT_FOUND,
+                }
+            })
+        }
+
+        extern "efiapi" fn process_firmware_volume(
+            buffer: *const c_void,
+            size: usize,
+            handle: *mut efi::Handle,
+        ) -> efi::Status {
+            if size < 0x48 {
+                return efi::Status::VOLUME_CORRUPTED;
+            }
+            DISPATCHER.with(|d| d.borrow_mut().processed.push((buffer as usize, size)));
+            unsafe { *handle = 0xF000 as efi::Handle };
+            efi::Status::SUCCESS
+        }
+
+        fn fake_table() -> DxeServicesTable {
+            DxeServicesTable { dispatch, schedule, trust, process_firmware_volume, ..super::fake_table() }
+        }
+
+        fn guid(n: u32) -> efi::Guid {
+            efi::Guid::from_fields(n, 0x1234, 0x5678, 0x9a, 0xbc, &[1, 2, 3, 4, 5, 6])
+        }
+
+        #[test]
+        fn new_should_validate_the_table() {
+            let mut table = fake_table();
+            assert!(unsafe { DxeServices::new(ptr::null()) }.is_err());
+            assert!(unsafe { DxeServices::new(&table) }.is_ok());
+            table.header.signature = 0;
+            assert_eq!(unsafe { DxeServices::new(&table) }.err(), Some(StatusError(efi::Status::INVALID_PARAMETER)));
+        }
+
+        #[test]
+        fn trust_should_release_drivers_deferred_by_security_violation() {
+            let table = fake_table();
+            let dxe_services = DxeServices::from_table(&table);
+            let fv_handle = 0x1000 as efi::Handle;
+            // The Security Architectural Protocol returned SECURITY_VIOLATION for guid(1) during dispatch.
+            DISPATCHER.with(|d| d.borrow_mut().untrusted.push((0x1000, guid(1))));
+
+            assert_eq!(dxe_services.dispatch(), Err(StatusError(efi::Status::NOT_FOUND)));
+            assert_eq!(dxe_services.trust(fv_handle, &guid(2)), Err(StatusError(efi::Status::NOT_FOUND)));
+            assert_eq!(dxe_services.trust(0x2000 as efi::Handle, &guid(1)), Err(StatusError(efi::Status::NOT_FOUND)));
+            assert_eq!(dxe_services.trust(fv_handle, &guid(1)), Ok(()));
+            assert_eq!(dxe_services.trust(fv_handle, &guid(1)), Err(StatusError(efi::Status::NOT_FOUND)));
+            assert_eq!(dxe_services.dispatch(), Ok(()));
+            assert_eq!(DISPATCHER.with(|d| d.borrow().dispatched.clone()), vec![guid(1)]);
+        }
+
+        #[test]
+        fn schedule_and_process_firmware_volume_should_marshal_arguments() {
+            static FV: [u8; 0x48] = [0; 0x48];
+            static TRUNCATED: [u8; 4] = [0; 4];
+            let table = fake_table();
+            let dxe_services = DxeServices::from_table(&table);
+
+            assert_eq!(dxe_services.schedule(0x3000 as efi::Handle, &guid(3)), Ok(()));
+            assert_eq!(DISPATCHER.with(|d| d.borrow().scheduled.clone()), vec![(0x3000, guid(3))]);
+
+            assert_eq!(dxe_services.process_firmware_volume(&FV), Ok(0xF000 as efi::Handle));
+            assert_eq!(
+                dxe_services.process_firmware_volume(&TRUNCATED),
+                Err(StatusError(efi::Status::VOLUME_CORRUPTED))
+            );
+            assert_eq!(DISPATCHER.with(|d| d.borrow().processed.clone()), vec![(FV.as_ptr() as usize, FV.len())]);
         }
     }
 }
@@ -0,0 +1,520 @@
+//! Global Coherency Domain (GCD) Memory and I/O Space
+//!
+//! Descriptor types of the GCD services, and the conversion between the resource attributes of resource descriptor
+//! HOBs and GCD capabilities (`EFI_MEMORY_*` bits) that a DXE core applies when it seeds the GCD memory space map from
+//! the HOB list. I/O space helpers validate ranges against the processor's port space, detect overlapping
+//! descriptors, and read the map returned by GetIoSpaceMap().
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V2_Services_DXE_Services.html#global-coherency-domain-services>.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+extern crate alloc;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use r_efi::efi::{self, Handle, PhysicalAddress};
+
+use crate::{
+    hob::ResourceAttributes,
+    mem_attr::{EfiMemoryAttributes, RESOURCE_ATTRIBUTE_CONVERSION},
+};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// `EFI_GCD_MEMORY_TYPE` in specification.
+pub enum GcdMemoryType {
+    /// A memory region that is visible to the boot processor.
+    /// However, there are no system components that are currently decoding this memory region.
+    #[default]
+    NonExistent = 0,
+    /// A memory region that is visible to the boot processor.
+    /// This memory region is being decoded by a system component,
+    /// but the memory region is not considered to be either system memory or memory-mapped I/O.
+    Reserved,
+    /// A memory region that is visible to the boot processor.
+    /// A memory controller is currently decoding this memory region
+    /// and the memory controller is producing a tested system memory region that is available to the memory services.
+    SystemMemory,
+    /// A memory region that is visible to the boot processor. This memory region is currently being decoded by a
+    /// component as memory-mapped I/O that can be used to access I/O devices in the platform.
+    MemoryMappedIo,
+    /// A memory region that is visible to the boot processor. This memory supports byte-addressable non-volatility.
+    Persistent,
+    /// A memory region that provides higher reliability relative to other memory in the system.
+    /// If all memory has the same reliability, then this bit is not used.
+    MoreReliable,
+    /// A memory region that is unaccepted. This region must be accepted before it can be converted to system memory.
+    Unaccepted,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+/// `EFI_GCD_ALLOCATE_TYPE` in specification.
+pub enum GcdAllocateType {
+    #[default]
+    AnySearchBottomUp,
+    MaxAddressSearchBottomUp,
+    Address,
+    AnySearchTopDown,
+    MaxAddressSearchTopDown,
+    MaxAllocateType,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// `EFI_GCD_MEMORY_SPACE_DESCRIPTOR` in specification.
+pub struct MemorySpaceDescriptor {
+    /// The physical address of the first byte in the memory region.
+    pub base_address: PhysicalAddress,
+    /// The number of bytes in the memory region.
+    pub length: u64,
+    /// The bit mask of attributes that the memory region is capable of supporting.
+    pub capabilities: EfiMemoryAttributes,
+    /// The bit mask of attributes that the memory region is currently using.
+    pub attributes: EfiMemoryAttributes,
+    /// Type of the memory region.
+    pub memory_type: GcdMemoryType,
+    /// The image handle of the agent that allocated the memory resource described by PhysicalStart and NumberOfBytes.
+    ///
+    /// If this field is NULL, then the memory resource is not currently allocated.
+    pub image_handle: Handle,
+    /// The device handle for which the memory resource has been allocated.
+    ///
+    /// If ImageHandle is NULL, then the memory resource is not currently allocated.
+    ///
+    /// If this field is NULL, then the memory resource is not associated with a device that is described by a device handle.
+    pub device_handle: Handle,
+}
+
+impl Default for MemorySpaceDescriptor {
+    fn default() -> Self {
+        Self {
+            base_address: Default::default(),
+            length: Default::default(),
+            capabilities: Default::default(),
+            attributes: Default::default(),
+            memory_type: Default::default(),
+            image_handle: 0 as Handle,
+            device_handle: 0 as Handle,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// `EFI_GCD_IO_TYPE` in specification.
+pub enum GcdIoType {
+    /// An I/O region that is visible to the boot processor.
+    /// However, there are no system components that are currently decoding this I/O region.
+    #[default]
+    NonExistent = 0,
+    /// An I/O region that is visible to the boot processor.
+    /// This I/O region is currently being decoded by a system component,
+    ///  but the I/O region cannot be used to access I/O devices.
+    Reserved,
+    /// An I/O region that is visible to the boot processor.
+    /// This I/O region is currently being decoded by a system component
+    /// that is producing I/O ports that can be used to access I/O devices.
+    Io,
+    Maximum,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// `EFI_GCD_IO_SPACE_DESCRIPTOR` in specification.
+pub struct IoSpaceDescriptor {
+    /// Physical address of the first byte in the I/O region.
+    pub base_address: PhysicalAddress,
+    /// Number of bytes in the I/O region.
+    pub length: u64,
+    /// Type of the I/O region.
+    pub io_type: GcdIoType,
+    /// The image handle of the agent that allocated the I/O resource described by PhysicalStart and NumberOfBytes.
+    ///
+    /// If this field is NULL, then the I/O resource is not currently allocated.
+    pub image_handle: Handle,
+    /// The device handle for which the I/O resource has been allocated.
+    ///
+    /// If ImageHandle is NULL , then the I/O resource is not currently allocated.
+    ///
+    /// If this field is NULL, then the I/O resource is not associated with a device that is described by a device handle.
+    pub device_handle: Handle,
+}
+
+impl Default for IoSpaceDescriptor {
+    fn default() -> Self {
+        Self {
+            base_address: Default::default(),
+            length: Default::default(),
+            io_type: Default::default(),
+            image_handle: 0 as Handle,
+            device_handle: 0 as Handle,
+        }
+    }
+}
+
+/// Converts the attributes of a resource descriptor HOB to GCD capabilities.
+///
+/// Every capability attribute (cacheability, protectability, persistability, reliability) is converted, as are the
+/// PRESENT, INITIALIZED and TESTED state attributes, which become [`EfiMemoryAttributes::PRESENT`],
+/// [`EfiMemoryAttributes::INITIALIZED`] and [`EfiMemoryAttributes::TESTED`]. Attributes with no GCD equivalent, such
+/// as the ECC and I/O width attributes and the protected (as opposed to protectable) attributes, are dropped; use
+/// [`EfiMemoryAttributes::from_resource_attributes`] to have them reported.
+///
+/// See [`gcd_capabilities_for_memory_type`] for the conversion of a region being added with a known memory type.
+pub fn gcd_capabilities_from_hob(attrs: ResourceAttributes) -> EfiMemoryAttributes {
+    convert(attrs, true)
+}
+
+/// Converts the attributes of a resource descriptor HOB to the capabilities of a GCD memory region of type
+/// `memory_type`.
+///
+/// This matches [`gcd_capabilities_from_hob`], except that the PRESENT, INITIALIZED and TESTED state attributes are
+/// not converted for [`GcdMemoryType::SystemMemory`] and [`GcdMemoryType::MoreReliable`] regions: the state of such
+/// memory is implied by its type.
+pub fn gcd_capabilities_for_memory_type(memory_type: GcdMemoryType, attrs: ResourceAttributes) -> EfiMemoryAttributes {
+    convert(attrs, !matches!(memory_type, GcdMemoryType::SystemMemory | GcdMemoryType::MoreReliable))
+}
+
+/// Converts GCD capabilities back to the attributes of a resource descriptor HOB.
+///
+/// This is the reverse of [`gcd_capabilities_from_hob`]: each GCD capability with a resource attribute counterpart
+/// is converted, and other bits (e.g. [`EfiMemoryAttributes::RUNTIME`]) are dropped; use
+/// [`EfiMemoryAttributes::to_resource_attributes`] to have them reported.
+pub fn hob_attributes_from_gcd_capabilities(capabilities: EfiMemoryAttributes) -> ResourceAttributes {
+    capabilities.to_resource_attributes().0
+}
+
+fn convert(attrs: ResourceAttributes, include_state: bool) -> EfiMemoryAttributes {
+    RESOURCE_ATTRIBUTE_CONVERSION
+        .iter()
+        .filter(|(attribute, _, is_capability)| attrs & attribute != 0 && (*is_capability || include_state))
+        .fold(EfiMemoryAttributes::empty(), |capabilities, (_, capability, _)| capabilities | *capability)
+}
+
+/// Highest I/O port address supported by the processor: the 16-bit port space on x86, the full address range on
+/// processors that map I/O space into memory.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub const MAX_IO_ADDRESS: u64 = 0xFFFF;
+
+/// Highest I/O port address supported by the processor: the 16-bit port space on x86, the full address range on
+/// processors that map I/O space into memory.
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+pub const MAX_IO_ADDRESS: u64 = u64::MAX;
+
+impl TryFrom<u32> for GcdIoType {
+    type Error = efi::Status;
+
+    /// Converts a raw `EFI_GCD_IO_TYPE`, rejecting `EfiGcdIoTypeMaximum` and unknown values with `INVALID_PARAMETER`.
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::NonExistent),
+            1 => Ok(Self::Reserved),
+            2 => Ok(Self::Io),
+            _ => Err(efi::Status::INVALID_PARAMETER),
+        }
+    }
+}
+
+impl IoSpaceDescriptor {
+    /// Returns the address of the last byte of the region, or `None` if the region is empty or wraps around the end
+    /// of the address space.
+    pub fn last_address(&self) -> Option<PhysicalAddress> {
+        self.length.checked_sub(1).and_then(|offset| self.base_address.checked_add(offset))
+    }
+
+    /// Returns true if the region shares at least one port with `other`. Empty regions overlap nothing.
+    pub fn overlaps(&self, other: &Self) -> bool {
+        match (self.last_address(), other.last_address()) {
+            (Some(last), Some(other_last)) => self.base_address <= other_last && other.base_address <= last,
+            _ => false,
+        }
+    }
+}
+
+/// Validates an I/O space range as AddIoSpace() and AllocateIoSpace() do.
+///
+/// Returns `INVALID_PARAMETER` if `length` is zero, and `UNSUPPORTED` if the range extends beyond
+/// [`MAX_IO_ADDRESS`].
+pub fn validate_io_range(base_address: PhysicalAddress, length: u64) -> Result<(), efi::Status> {
+    if length == 0 {
+        Err(efi::Status::INVALID_PARAMETER)?;
+    }
+    match base_address.checked_add(length - 1) {
+        Some(last) if last <= MAX_IO_ADDRESS => Ok(()),
+        _ => Err(efi::Status::UNSUPPORTED),
+    }
+}
+
+/// Returns the index pairs `(i, j)`, with `i < j`, of every two descriptors in `descriptors` that overlap.
+///
+/// The GCD never produces overlapping descriptors, so any pair returned points at a corrupted map or at claims that
+/// were tracked outside of the GCD.
+pub fn find_io_overlaps(descriptors: &[IoSpaceDescriptor]) -> Vec<(usize, usize)> {
+    let mut overlaps = Vec::new();
+    for (i, descriptor) in descriptors.iter().enumerate() {
+        for (j, other) in descriptors.iter().enumerate().skip(i + 1) {
+            if descriptor.overlaps(other) {
+                overlaps.push((i, j));
+            }
+        }
+    }
+    overlaps
+}
+
+// Layout of EFI_GCD_IO_SPACE_DESCRIPTOR with the I/O type as a raw value, so that a descriptor from firmware can be
+// read before its type is known to be valid.
+#[repr(C)]
+struct RawIoSpaceDescriptor {
+    base_address: PhysicalAddress,
+    length: u64,
+    io_type: u32,
+    image_handle: Handle,
+    device_handle: Handle,
+}
+
+/// Iterates over the I/O space map returned by GetIoSpaceMap().
+///
+/// Each descriptor is validated as it is read; a descriptor with an invalid I/O type yields `INVALID_PARAMETER`. The
+/// iterator does not take ownership of the buffer, which the caller must still free with FreePool().
+///
+/// ## Example
+///```no_run
+/// use mu_pi::dxe_services::{gcd::{find_io_overlaps, IoSpaceMap}, DxeServicesTable, IoSpaceDescriptor};
+/// use r_efi::efi;
+///
+/// fn audit(dxe_services: &DxeServicesTable) -> Result<(), efi::Status> {
+///   let mut count = 0;
+///   let mut descriptors: *mut IoSpaceDescriptor = core::ptr::null_mut();
+///   let status = (dxe_services.get_io_space_map)(&mut count, &mut descriptors);
+///   if status.is_error() {
+///     return Err(status);
+///   }
+///   let map = unsafe { IoSpaceMap::new(count, descriptors) }.collect::<Result<Vec<_>, _>>()?;
+///   assert!(find_io_overlaps(&map).is_empty());
+///   Ok(())
+/// }
+///```
+#[derive(Debug, Clone)]
+pub struct IoSpaceMap<'a> {
+    descriptors: *const IoSpaceDescriptor,
+    count: usize,
+    index: usize,
+    _buffer: PhantomData<&'a [IoSpaceDescriptor]>,
+}
+
+impl<'a> IoSpaceMap<'a> {
+    /// Instantiates a new IoSpaceMap over `count` descriptors at `descriptors`, as returned by GetIoSpaceMap().
+    ///
+    /// ## Safety
+    /// Caller must ensure that, unless `count` is zero, `descriptors` points to `count` readable and suitably aligned
+    /// descriptors that stay valid for `'a`.
+    pub unsafe fn new(count: usize, descriptors: *const IoSpaceDescriptor) -> Self {
+        Self { descriptors, count, index: 0, _buffer: PhantomData }
+    }
+}
+
+impl<'a> Iterator for IoSpaceMap<'a> {
+    type Item = Result<IoSpaceDescriptor, efi::Status>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.count {
+            return None;
+        }
+        //Safety: the constructor contract guarantees that the descriptor is readable, and the raw layout accepts any
+        //I/O type value.
+        let raw = unsafe { (self.descriptors.add(self.index) as *const RawIoSpaceDescriptor).read() };
+        self.index += 1;
+        Some(GcdIoType::try_from(raw.io_type).map(|io_type| IoSpaceDescriptor {
+            base_address: raw.base_address,
+            length: raw.length,
+            io_type,
+            image_handle: raw.image_handle,
+            device_handle: raw.device_handle,
+        }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.count - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use r_efi::efi;
+
+    use super::{
+        find_io_overlaps, gcd_capabilities_for_memory_type, gcd_capabilities_from_hob,
+        hob_attributes_from_gcd_capabilities, validate_io_range, GcdIoType, GcdMemoryType, IoSpaceDescriptor,
+        IoSpaceMap, MAX_IO_ADDRESS,
+    };
+    use crate::{hob, mem_attr::EfiMemoryAttributes};
+
+    // The conversion table transcribed with literal values, so that a mistake in a named constant is caught too.
+    const EXPECTED: &[(u32, u64)] = &[
+        (0x0000_0001, 0x0100_0000_0000_0000), // PRESENT -> EFI_MEMORY_PRESENT
+        (0x0000_0002, 0x0200_0000_0000_0000), // INITIALIZED -> EFI_MEMORY_INITIALIZED
+        (0x0000_0004, 0x0400_0000_0000_0000), // TESTED -> EFI_MEMORY_TESTED
+        (0x0000_0400, 0x0000_0000_0000_0001), // UNCACHEABLE -> EFI_MEMORY_UC
+        (0x0000_0800, 0x0000_0000_0000_0002), // WRITE_COMBINEABLE -> EFI_MEMORY_WC
+        (0x0000_1000, 0x0000_0000_0000_0004), // WRITE_THROUGH_CACHEABLE -> EFI_MEMORY_WT
+        (0x0000_2000, 0x0000_0000_0000_0008), // WRITE_BACK_CACHEABLE -> EFI_MEMORY_WB
+        (0x0002_0000, 0x0000_0000_0000_0010), // UNCACHED_EXPORTED -> EFI_MEMORY_UCE
+        (0x0008_0000, 0x0000_0000_0002_0000), // READ_ONLY_PROTECTABLE -> EFI_MEMORY_RO
+        (0x0010_0000, 0x0000_0000_0000_2000), // READ_PROTECTABLE -> EFI_MEMORY_RP
+        (0x0020_0000, 0x0000_0000_0000_1000), // WRITE_PROTECTABLE -> EFI_MEMORY_WP
+        (0x0040_0000, 0x0000_0000_0000_4000), // EXECUTION_PROTECTABLE -> EFI_MEMORY_XP
+        (0x0100_0000, 0x0000_0000_0000_8000), // PERSISTABLE -> EFI_MEMORY_NV
+        (0x0200_0000, 0x0000_0000_0001_0000), // MORE_RELIABLE -> EFI_MEMORY_MORE_RELIABLE
+    ];
+
+    const STATE_ATTRIBUTES: u32 = 0x7;
+
+    fn expected_capability(attribute: u32) -> u64 {
+        EXPECTED.iter().find(|(a, _)| *a == attribute).map_or(0, |(_, c)| *c)
+    }
+
+    #[test]
+    fn each_hob_attribute_bit_should_convert_to_its_capability() {
+        for bit in 0..32 {
+            let attribute = 1u32 << bit;
+            assert_eq!(
+                gcd_capabilities_from_hob(attribute).bits(),
+                expected_capability(attribute),
+                "attribute bit {bit}"
+            );
+        }
+    }
+
+    #[test]
+    fn each_capability_bit_should_convert_to_its_hob_attribute() {
+        for bit in 0..64 {
+            let capability = 1u64 << bit;
+            let expected = EXPECTED.iter().find(|(_, c)| *c == capability).map_or(0, |(a, _)| *a);
+            assert_eq!(
+                hob_attributes_from_gcd_capabilities(EfiMemoryAttributes::from_bits_retain(capability)),
+                expected,
+                "capability bit {bit}"
+            );
+        }
+        assert_eq!(hob_attributes_from_gcd_capabilities(EfiMemoryAttributes::RUNTIME), 0);
+    }
+
+    #[test]
+    fn state_attributes_should_be_dropped_for_system_memory() {
+        for bit in 0..32 {
+            let attribute = 1u32 << bit;
+            let state = attribute & STATE_ATTRIBUTES != 0;
+            for memory_type in [GcdMemoryType::SystemMemory, GcdMemoryType::MoreReliable] {
+                let expected = if state { 0 } else { expected_capability(attribute) };
+                assert_eq!(
+                    gcd_capabilities_for_memory_type(memory_type, attribute).bits(),
+                    expected,
+                    "attribute bit {bit}"
+                );
+            }
+            for memory_type in [
+                GcdMemoryType::NonExistent,
+                GcdMemoryType::Reserved,
+                GcdMemoryType::MemoryMappedIo,
+                GcdMemoryType::Persistent,
+                GcdMemoryType::Unaccepted,
+            ] {
+                assert_eq!(
+                    gcd_capabilities_for_memory_type(memory_type, attribute).bits(),
+                    expected_capability(attribute),
+                    "attribute bit {bit}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn typical_resource_descriptors_should_convert() {
+        let tested_wb = hob::TESTED_MEMORY_ATTRIBUTES
+            | hob::EFI_RESOURCE_ATTRIBUTE_UNCACHEABLE
+            | hob::EFI_RESOURCE_ATTRIBUTE_WRITE_COMBINEABLE
+            | hob::EFI_RESOURCE_ATTRIBUTE_WRITE_THROUGH_CACHEABLE
+            | hob::EFI_RESOURCE_ATTRIBUTE_WRITE_BACK_CACHEABLE
+            | hob::EFI_RESOURCE_ATTRIBUTE_EXECUTION_PROTECTABLE
+            | hob::EFI_RESOURCE_ATTRIBUTE_READ_ONLY_PROTECTABLE;
+        let capabilities = EfiMemoryAttributes::UC
+            | EfiMemoryAttributes::WC
+            | EfiMemoryAttributes::WT
+            | EfiMemoryAttributes::WB
+            | EfiMemoryAttributes::XP
+            | EfiMemoryAttributes::RO;
+        let state = EfiMemoryAttributes::PRESENT | EfiMemoryAttributes::INITIALIZED | EfiMemoryAttributes::TESTED;
+        assert_eq!(gcd_capabilities_from_hob(tested_wb), capabilities | state);
+        assert_eq!(gcd_capabilities_for_memory_type(GcdMemoryType::SystemMemory, tested_wb), capabilities);
+        assert_eq!(hob_attributes_from_gcd_capabilities(capabilities | state), tested_wb);
+
+        // Protected attributes and I/O widths have no GCD equivalent.
+        let mmio = hob::EFI_RESOURCE_ATTRIBUTE_PRESENT
+            | hob::EFI_RESOURCE_ATTRIBUTE_UNCACHEABLE
+            | hob::EFI_RESOURCE_ATTRIBUTE_READ_PROTECTED
+            | hob::EFI_RESOURCE_ATTRIBUTE_32_BIT_IO;
+        assert_eq!(
+            gcd_capabilities_for_memory_type(GcdMemoryType::MemoryMappedIo, mmio),
+            EfiMemoryAttributes::PRESENT | EfiMemoryAttributes::UC
+        );
+    }
+
+    fn io(base_address: u64, length: u64) -> IoSpaceDescriptor {
+        IoSpaceDescriptor { base_address, length, io_type: GcdIoType::Io, ..Default::default() }
+    }
+
+    #[test]
+    fn io_ranges_should_be_validated() {
+        assert_eq!(validate_io_range(0x3F8, 0), Err(efi::Status::INVALID_PARAMETER));
+        assert_eq!(validate_io_range(0x3F8, 8), Ok(()));
+        assert_eq!(validate_io_range(0, MAX_IO_ADDRESS), Ok(()));
+        assert_eq!(validate_io_range(u64::MAX, 2), Err(efi::Status::UNSUPPORTED));
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            assert_eq!(validate_io_range(0, 0x10000), Ok(()));
+            assert_eq!(validate_io_range(0xFFFF, 1), Ok(()));
+            assert_eq!(validate_io_range(0xFFFF, 2), Err(efi::Status::UNSUPPORTED));
+            assert_eq!(validate_io_range(0x10000, 1), Err(efi::Status::UNSUPPORTED));
+        }
+    }
+
+    #[test]
+    fn io_overlaps_should_be_detected() {
+        assert!(io(0x60, 1).overlaps(&io(0x60, 1)));
+        assert!(io(0x60, 0x10).overlaps(&io(0x6F, 1)));
+        assert!(!io(0x60, 0x10).overlaps(&io(0x70, 1)));
+        assert!(!io(0x60, 0).overlaps(&io(0x60, 1)));
+        assert!(!io(u64::MAX, 1).overlaps(&io(0, u64::MAX)));
+        assert_eq!(io(u64::MAX, 2).last_address(), None);
+
+        let map = [io(0x0, 0x20), io(0xCF8, 8), io(0x20, 0x10), io(0xCFC, 4), io(0x10, 0x20)];
+        assert_eq!(find_io_overlaps(&map), vec![(0, 4), (1, 3), (2, 4)]);
+        assert!(find_io_overlaps(&map[..3]).is_empty());
+    }
+
+    #[test]
+    fn io_space_map_should_yield_validated_descriptors() {
+        let mut buffer = [io(0, 0x1000), io(0x1000, 0xF000), io(0x2000, 1)];
+        buffer[1].io_type = GcdIoType::NonExistent;
+        // An invalid I/O type, as corrupted firmware might report it.
+        unsafe { core::ptr::addr_of_mut!(buffer[2].io_type).cast::<u32>().write(7) };
+
+        let mut map = unsafe { IoSpaceMap::new(buffer.len(), buffer.as_ptr()) };
+        assert_eq!(map.size_hint(), (3, Some(3)));
+        assert_eq!(map.next(), Some(Ok(io(0, 0x1000))));
+        assert_eq!(map.next().unwrap().unwrap().io_type, GcdIoType::NonExistent);
+        assert_eq!(map.next(), Some(Err(efi::Status::INVALID_PARAMETER)));
+        assert_eq!(map.next(), None);
+
+        assert_eq!(unsafe { IoSpaceMap::new(0, core::ptr::null()) }.count(), 0);
+        assert_eq!(GcdIoType::try_from(3), Err(efi::Status::INVALID_PARAMETER));
+    }
+}
@@ -13,7 +13,7 @@
 
 extern crate alloc;
 
-use core::{fmt, mem, num::Wrapping, slice};
+use core::{fmt, mem, slice};
 
 pub mod ffs;
 pub mod fv;
@@ -39,7 +39,6 @@ pub use fv::{
 pub use fvb::attributes::{raw::fvb2 as Fvb2RawAttributes, EfiFvbAttributes2, Fvb2 as Fvb2Attributes};
 
 use alloc::{boxed::Box, collections::VecDeque, vec::Vec};
-use num_traits::WrappingSub;
 use r_efi::efi;
 
 use crate::address_helper::align_up;
@@ -172,15 +171,7 @@ impl<'a> FirmwareVolume<'a> {
         }
 
         // checksum: fv header must sum to zero (and must be multiple of 2 bytes)
-        if fv_header.header_length & 0x01 != 0 {
-            Err(efi::Status::VOLUME_CORRUPTED)?;
-        }
-
-        let header_slice = &buffer[..fv_header.header_length as usize];
-        let sum: Wrapping<u16> =
-            header_slice.chunks_exact(2).map(|x| Wrapping(u16::from_le_bytes(x.try_into().unwrap()))).sum();
-
-        if sum != Wrapping(0u16) {
+        if !fv::integrity::verify_fv_header_checksum(buffer) {
             Err(efi::Status::VOLUME_CORRUPTED)?;
         }
 
@@ -422,7 +413,7 @@ impl<'a> File<'a> {
                 }
                 let size =
                     u64::from_le_bytes(buffer[header_size..header_size + extended_size_length].try_into().unwrap());
-                (header_size + extended_size_length, size as u64)
+                (header_size + extended_size_length, size)
             }
         };
 
@@ -449,26 +440,14 @@ impl<'a> File<'a> {
             }
         }
 
-        //Verify the header checksum.
-        let header_sum: Wrapping<u8> = buffer[..header_size].iter().map(|&x| Wrapping(x)).sum();
-        // integrity_check_file and state are assumed to be zero for checksum, so subtract them here.
-        let header_sum = header_sum.wrapping_sub(&Wrapping(file_header.integrity_check_file));
-        let header_sum = header_sum.wrapping_sub(&Wrapping(file_header.state));
-        if header_sum != Wrapping(0u8) {
-            Err(efi::Status::VOLUME_CORRUPTED)?;
-        }
-
-        //Verify the file data checksum.
-        if file_header.attributes & ffs::attributes::raw::CHECKSUM != 0 {
-            let data_sum: Wrapping<u8> = buffer[header_size..size as usize].iter().map(|&x| Wrapping(x)).sum();
-            if data_sum != Wrapping(0u8) {
-                Err(efi::Status::VOLUME_CORRUPTED)?;
-            }
+        //Verify the header and file data checksums.
+        let checksums_valid = if (file_header.attributes & LARGE_FILE) == 0 {
+            fv::integrity::verify_ffs_file_checksum(&buffer[..size as usize])
         } else {
-            // Verify that the checksum is initialized to 0xAA per spec requirements when CHECKSUM attribute is cleared.
-            if file_header.integrity_check_file != 0xAA {
-                Err(efi::Status::VOLUME_CORRUPTED)?;
-            }
+            fv::integrity::verify_ffs_file2_checksum(&buffer[..size as usize])
+        };
+        if !checksums_valid {
+            Err(efi::Status::VOLUME_CORRUPTED)?;
         }
 
         Ok(Self {
@@ -1181,10 +1160,23 @@ mod unit_tests {
         let a_ptr = &a as *const A;
 
         unsafe {
-            assert_eq!((&(*a_ptr).block_map).as_ptr(), a_ptr.offset(1) as *const fv::BlockMapEntry);
+            assert_eq!((*a_ptr).block_map.as_ptr(), a_ptr.offset(1) as *const fv::BlockMapEntry);
         }
     }
 
+    #[test]
+    fn fv_header_debug_should_decode_fields() -> Result<(), Box<dyn Error>> {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("test_resources");
+        let fv_bytes = fs::read(root.join("DXEFV.Fv"))?;
+        let fv_header = unsafe { &*(fv_bytes.as_ptr() as *const fv::Header) };
+
+        let text = format!("{:?}", fv_header);
+        assert!(text.contains("file_system_guid: 8C8CE578-8A3D-4F1C-9935-896185C32DD3"), "{text}");
+        assert!(text.contains(&format!("fv_length: {:#x}", fv_bytes.len())), "{text}");
+        assert!(text.contains("signature: 0x4856465f"), "{text}");
+        Ok(())
+    }
+
     struct ExampleSectionExtractor {}
     impl SectionExtractor for ExampleSectionExtractor {
         fn extract(&self, section: &Section) -> Result<Box<[u8]>, efi::Status> {
@@ -10,8 +10,12 @@
 //! SPDX-License-Identifier: BSD-2-Clause-Patent
 //!
 
+use core::fmt;
+
 use r_efi::efi;
 
+use crate::device_path::GuidFmt;
+
 pub mod raw {
     /// File State Bits
     pub mod state {
@@ -92,7 +96,7 @@ pub enum State {
 
 // EFI_FFS_FILE_HEADER
 #[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub(crate) struct Header {
     pub(crate) name: efi::Guid,
     pub(crate) integrity_check_header: u8,
@@ -105,9 +109,24 @@ pub(crate) struct Header {
 
 // EFI_FFS_FILE_HEADER
 #[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[allow(dead_code)]
 pub(crate) struct Header2 {
     pub(crate) header: Header,
     pub(crate) extended_size: u64,
 }
+
+impl fmt::Debug for Header {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let size = u32::from_le_bytes([self.size[0], self.size[1], self.size[2], 0]);
+        f.debug_struct("Header")
+            .field("name", &GuidFmt(&self.name))
+            .field("integrity_check_header", &format_args!("{:#x}", self.integrity_check_header))
+            .field("integrity_check_file", &format_args!("{:#x}", self.integrity_check_file))
+            .field("file_type", &format_args!("{:#x}", self.file_type))
+            .field("attributes", &format_args!("{:#x}", self.attributes))
+            .field("size", &format_args!("{:#x}", size))
+            .field("state", &format_args!("{:#x}", self.state))
+            .finish()
+    }
+}
@@ -69,17 +69,21 @@ pub enum Type {
 
 /// EFI_COMMON_SECTION_HEADER per PI spec 1.8A 3.2.4.1
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Header {
     pub size: [u8; 3],
     pub section_type: u8,
 }
 
 pub mod header {
+    use core::fmt;
+
     use r_efi::base::Guid;
 
+    use crate::device_path::GuidFmt;
+
     #[repr(C)]
-    #[derive(Debug)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     pub struct CommonSectionHeaderStandard {
         pub size: [u8; 3],
         pub section_type: u8,
@@ -87,7 +91,7 @@ pub mod header {
 
     /// EFI_COMMON_SECTION_HEADER2 per PI spec 1.8A 3.2.4.1
     #[repr(C)]
-    #[derive(Debug)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     pub struct CommonSectionHeaderExtended {
         pub size: [u8; 3],
         pub section_type: u8,
@@ -96,7 +100,7 @@ pub mod header {
 
     /// EFI_COMPRESSION_SECTION per PI spec 1.8A 3.2.5.2
     #[repr(C, packed)]
-    #[derive(Debug, Clone, Copy)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     pub struct Compression {
         pub uncompressed_length: u32,
         pub compression_type: u8,
@@ -104,7 +108,7 @@ pub mod header {
 
     /// EFI_GUID_DEFINED_SECTION per PI spec 1.8A 3.2.5.7
     #[repr(C)]
-    #[derive(Debug, Clone, Copy)]
+    #[derive(Clone, Copy, PartialEq, Eq, Hash)]
     pub struct GuidDefined {
         pub section_definition_guid: Guid,
         pub data_offset: u16,
@@ -112,17 +116,81 @@ pub mod header {
         // Guid-specific header fields.
     }
 
+    impl fmt::Debug for GuidDefined {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("GuidDefined")
+                .field("section_definition_guid", &GuidFmt(&self.section_definition_guid))
+                .field("data_offset", &format_args!("{:#x}", self.data_offset))
+                .field("attributes", &format_args!("{:#x}", self.attributes))
+                .finish()
+        }
+    }
+
     /// EFI_VERSION_SECTION per PI spec 1.8A 3.2.5.15
     #[repr(C)]
-    #[derive(Debug, Clone, Copy)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     pub struct Version {
         pub build_number: u16,
     }
 
     /// EFI_FREEFORM_SUBTYPE_GUID_SECTION per PI spec 1.8A 3.2.5.6
     #[repr(C)]
-    #[derive(Debug, Clone, Copy)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     pub struct FreeformSubtypeGuid {
         pub sub_type_guid: Guid,
     }
 }
+
+/// Authentication status bits (EFI_AUTH_STATUS_*) reported for the contents of GUID-defined sections, per PI spec 1.8A
+/// 3.2.5.7. The status of a file is the combination of the status of the sections enclosing it.
+pub mod auth_status {
+    /// The platform overrode the authentication of the section.
+    pub const PLATFORM_OVERRIDE: u32 = 0x01;
+    /// The section is signed.
+    pub const IMAGE_SIGNED: u32 = 0x02;
+    /// The signature of the section was not checked.
+    pub const NOT_TESTED: u32 = 0x04;
+    /// The signature of the section was checked and is invalid.
+    pub const TEST_FAILED: u32 = 0x08;
+    /// All the authentication status bits.
+    pub const ALL: u32 = 0x0F;
+}
+
+/// The authentication status of a file or section, as passed to the Security Architectural Protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct AuthStatus(u32);
+
+impl AuthStatus {
+    /// Creates the status from its raw value, keeping any reserved bits.
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    /// Returns the raw value of the status.
+    pub const fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns whether all the bits of `bits` are set.
+    pub const fn contains(&self, bits: u32) -> bool {
+        self.0 & bits == bits
+    }
+}
+
+impl From<AuthStatus> for u32 {
+    fn from(status: AuthStatus) -> Self {
+        status.0
+    }
+}
+
+/// Fails with the value if it has bits other than [`auth_status::ALL`].
+impl TryFrom<u32> for AuthStatus {
+    type Error = u32;
+
+    fn try_from(bits: u32) -> Result<Self, Self::Error> {
+        match bits & !auth_status::ALL {
+            0 => Ok(Self(bits)),
+            _ => Err(bits),
+        }
+    }
+}
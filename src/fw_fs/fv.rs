@@ -11,9 +11,15 @@
 //!
 
 pub mod attributes;
+pub mod decompress_section;
 pub mod file;
+pub mod integrity;
+use core::fmt;
+
 use r_efi::efi;
 
+use crate::device_path::GuidFmt;
+
 pub type EfiFvFileType = u8;
 
 /// Firmware Volume Write Policy bit definitions
@@ -34,7 +40,7 @@ pub enum WritePolicy {
 
 /// EFI_FIRMWARE_VOLUME_HEADER
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Header {
     pub(crate) zero_vector: [u8; 16],
     pub(crate) file_system_guid: efi::Guid,
@@ -50,7 +56,7 @@ pub struct Header {
 }
 
 #[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct BlockMapEntry {
     pub num_blocks: u32,
     pub length: u32,
@@ -58,8 +64,32 @@ pub struct BlockMapEntry {
 
 /// EFI_FIRMWARE_VOLUME_EXT_HEADER
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub(crate) struct ExtHeader {
     pub(crate) fv_name: efi::Guid,
     pub(crate) ext_header_size: u32,
 }
+
+impl fmt::Debug for Header {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Header")
+            .field("file_system_guid", &GuidFmt(&self.file_system_guid))
+            .field("fv_length", &format_args!("{:#x}", self.fv_length))
+            .field("signature", &format_args!("{:#x}", self.signature))
+            .field("attributes", &format_args!("{:#x}", self.attributes))
+            .field("header_length", &format_args!("{:#x}", self.header_length))
+            .field("checksum", &format_args!("{:#x}", self.checksum))
+            .field("ext_header_offset", &format_args!("{:#x}", self.ext_header_offset))
+            .field("revision", &self.revision)
+            .finish()
+    }
+}
+
+impl fmt::Debug for ExtHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtHeader")
+            .field("fv_name", &GuidFmt(&self.fv_name))
+            .field("ext_header_size", &format_args!("{:#x}", self.ext_header_size))
+            .finish()
+    }
+}
@@ -0,0 +1,275 @@
+//! Compression Section Decompression
+//!
+//! Extracts the contents of an EFI_SECTION_COMPRESSION encapsulation section, decompressing them with the
+//! EFI_DECOMPRESS_PROTOCOL when they use the standard compression algorithm.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V3_Code_Definitions.html#efi-section-compression>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+extern crate alloc;
+
+use alloc::{vec, vec::Vec};
+use core::{ffi::c_void, mem, ptr};
+
+use r_efi::efi;
+
+use crate::fw_fs::ffs::section::{self, raw_type::encapsulated::COMPRESSION};
+
+/// The EFI_DECOMPRESS_PROTOCOL, which decompresses the standard compression algorithm.
+pub type DecompressProtocol = r_efi::protocols::decompress::Protocol;
+
+/// Compression types of a compression section.
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CompressionType {
+    /// The section data is not compressed (EFI_NOT_COMPRESSED).
+    NotCompressed = 0x00,
+    /// The section data is compressed with the standard UEFI compression algorithm (EFI_STANDARD_COMPRESSION).
+    StandardCompression = 0x01,
+    /// The section data is compressed with a platform specific algorithm (EFI_CUSTOMIZED_COMPRESSION).
+    CustomCompression = 0x02,
+}
+
+/// Fails with the value if it is not a known compression type.
+impl TryFrom<u8> for CompressionType {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(CompressionType::NotCompressed),
+            0x01 => Ok(CompressionType::StandardCompression),
+            0x02 => Ok(CompressionType::CustomCompression),
+            _ => Err(value),
+        }
+    }
+}
+
+/// Errors extracting a section.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FvError {
+    /// The section is not a compression section.
+    NotCompressionSection,
+    /// The compression type is unknown, or has no decompressor.
+    UnsupportedCompression,
+    /// The decompressor failed, or did not produce the uncompressed length of the section.
+    DecompressFailed,
+    /// The buffer is smaller than the section headers or the section size, or the section is smaller than its headers.
+    TruncatedInput,
+}
+
+/// Returns the decompressed contents of the compression section at the start of `section_data`.
+///
+/// Sections using the standard compression algorithm are decompressed with `decompress`. Custom compression is not
+/// supported, as it requires a platform specific decompressor.
+pub fn decompress_section(section_data: &[u8], decompress: &DecompressProtocol) -> Result<Vec<u8>, FvError> {
+    let header_size = mem::size_of::<section::Header>();
+    if section_data.len() < header_size {
+        Err(FvError::TruncatedInput)?;
+    }
+
+    // An all-ones 24-bit size is followed by the 32-bit size of the section (EFI_COMMON_SECTION_HEADER2).
+    let (size, content_offset) = match [section_data[0], section_data[1], section_data[2]] {
+        [0xff, 0xff, 0xff] => {
+            let Some(size) = section_data.get(header_size..header_size + mem::size_of::<u32>()) else {
+                return Err(FvError::TruncatedInput);
+            };
+            (u32::from_le_bytes(size.try_into().unwrap()) as usize, header_size + mem::size_of::<u32>())
+        }
+        [a, b, c] => (u32::from_le_bytes([a, b, c, 0]) as usize, header_size),
+    };
+    if section_data[3] != COMPRESSION {
+        Err(FvError::NotCompressionSection)?;
+    }
+
+    let data_offset = content_offset + mem::size_of::<section::header::Compression>();
+    if size < data_offset || size > section_data.len() {
+        Err(FvError::TruncatedInput)?;
+    }
+    //Safety: the section holds the compression header, which is packed.
+    let compression_header =
+        unsafe { ptr::read_unaligned(section_data[content_offset..].as_ptr() as *const section::header::Compression) };
+    let uncompressed_length = compression_header.uncompressed_length as usize;
+    let data = &section_data[data_offset..size];
+
+    match CompressionType::try_from(compression_header.compression_type) {
+        Ok(CompressionType::NotCompressed) => {
+            data.get(..uncompressed_length).map(<[u8]>::to_vec).ok_or(FvError::TruncatedInput)
+        }
+        Ok(CompressionType::StandardCompression) => decompress_standard(data, uncompressed_length, decompress),
+        Ok(CompressionType::CustomCompression) | Err(_) => Err(FvError::UnsupportedCompression),
+    }
+}
+
+fn decompress_standard(
+    source: &[u8],
+    uncompressed_length: usize,
+    decompress: &DecompressProtocol,
+) -> Result<Vec<u8>, FvError> {
+    // The protocol does not modify itself or the source buffer.
+    let this = decompress as *const DecompressProtocol as *mut DecompressProtocol;
+    let source_ptr = source.as_ptr() as *mut c_void;
+    let source_size = u32::try_from(source.len()).map_err(|_| FvError::DecompressFailed)?;
+
+    let mut destination_size = 0u32;
+    let mut scratch_size = 0u32;
+    if (decompress.get_info)(this, source_ptr, source_size, &mut destination_size, &mut scratch_size)
+        != efi::Status::SUCCESS
+        || destination_size as usize != uncompressed_length
+    {
+        Err(FvError::DecompressFailed)?;
+    }
+
+    let mut destination = vec![0u8; destination_size as usize];
+    let mut scratch = vec![0u8; scratch_size as usize];
+    let status = (decompress.decompress)(
+        this,
+        source_ptr,
+        source_size,
+        destination.as_mut_ptr() as *mut c_void,
+        destination_size,
+        scratch.as_mut_ptr() as *mut c_void,
+        scratch_size,
+    );
+    match status {
+        efi::Status::SUCCESS => Ok(destination),
+        _ => Err(FvError::DecompressFailed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{ffi::c_void, slice};
+
+    use r_efi::efi;
+
+    use super::{decompress_section, CompressionType, DecompressProtocol, FvError};
+
+    // The fake compressed format is the 32-bit length of the data followed by the data XORed with 0x5A.
+    extern "efiapi" fn get_info(
+        _: *mut DecompressProtocol,
+        source: *mut c_void,
+        source_size: u32,
+        destination_size: *mut u32,
+        scratch_size: *mut u32,
+    ) -> efi::Status {
+        if source_size < 4 {
+            return efi::Status::INVALID_PARAMETER;
+        }
+        let source = unsafe { slice::from_raw_parts(source as *const u8, source_size as usize) };
+        unsafe {
+            *destination_size = u32::from_le_bytes(source[..4].try_into().unwrap());
+            *scratch_size = 0x10;
+        }
+        efi::Status::SUCCESS
+    }
+
+    extern "efiapi" fn decompress(
+        _: *mut DecompressProtocol,
+        source: *mut c_void,
+        source_size: u32,
+        destination: *mut c_void,
+        destination_size: u32,
+        _scratch: *mut c_void,
+        scratch_size: u32,
+    ) -> efi::Status {
+        let source = unsafe { slice::from_raw_parts(source as *const u8, source_size as usize) };
+        let destination = unsafe { slice::from_raw_parts_mut(destination as *mut u8, destination_size as usize) };
+        if scratch_size != 0x10 || source.len() - 4 != destination.len() {
+            return efi::Status::VOLUME_CORRUPTED;
+        }
+        for (destination, source) in destination.iter_mut().zip(&source[4..]) {
+            *destination = source ^ 0x5A;
+        }
+        efi::Status::SUCCESS
+    }
+
+    const PROTOCOL: DecompressProtocol = DecompressProtocol { get_info, decompress };
+
+    fn compress(data: &[u8]) -> Vec<u8> {
+        let length = (data.len() as u32).to_le_bytes();
+        length.iter().copied().chain(data.iter().map(|byte| byte ^ 0x5A)).collect()
+    }
+
+    fn compression_section(compression_type: u8, uncompressed_length: u32, data: &[u8]) -> Vec<u8> {
+        let size = (4 + 5 + data.len()) as u32;
+        let mut section = size.to_le_bytes()[..3].to_vec();
+        section.push(0x01);
+        section.extend(uncompressed_length.to_le_bytes());
+        section.push(compression_type);
+        section.extend(data);
+        section
+    }
+
+    #[test]
+    fn compression_type_should_convert_from_raw_values() {
+        assert_eq!(CompressionType::try_from(0x00), Ok(CompressionType::NotCompressed));
+        assert_eq!(CompressionType::try_from(0x01), Ok(CompressionType::StandardCompression));
+        assert_eq!(CompressionType::try_from(0x02), Ok(CompressionType::CustomCompression));
+        assert_eq!(CompressionType::try_from(0x03), Err(0x03));
+    }
+
+    #[test]
+    fn decompress_section_should_dispatch_on_compression_type() {
+        let data = b"compressed section contents";
+        let section = compression_section(0x01, data.len() as u32, &compress(data));
+        assert_eq!(decompress_section(&section, &PROTOCOL), Ok(data.to_vec()));
+
+        let section = compression_section(0x00, data.len() as u32, data);
+        assert_eq!(decompress_section(&section, &PROTOCOL), Ok(data.to_vec()));
+
+        let section = compression_section(0x02, data.len() as u32, data);
+        assert_eq!(decompress_section(&section, &PROTOCOL), Err(FvError::UnsupportedCompression));
+        let section = compression_section(0x7F, data.len() as u32, data);
+        assert_eq!(decompress_section(&section, &PROTOCOL), Err(FvError::UnsupportedCompression));
+    }
+
+    #[test]
+    fn decompress_section_should_support_extended_headers() {
+        let data = [0xA5u8; 0x40];
+        let compressed = compress(&data);
+        let mut section = vec![0xff, 0xff, 0xff, 0x01];
+        section.extend(((8 + 5 + compressed.len()) as u32).to_le_bytes());
+        section.extend(0x40u32.to_le_bytes());
+        section.push(0x01);
+        section.extend(&compressed);
+        assert_eq!(decompress_section(&section, &PROTOCOL), Ok(data.to_vec()));
+    }
+
+    #[test]
+    fn decompress_section_should_reject_bad_sections() {
+        let data = b"contents";
+        let section = compression_section(0x01, data.len() as u32, &compress(data));
+        assert_eq!(decompress_section(&section[..3], &PROTOCOL), Err(FvError::TruncatedInput));
+        assert_eq!(decompress_section(&section[..section.len() - 1], &PROTOCOL), Err(FvError::TruncatedInput));
+        assert_eq!(decompress_section(&[0xff, 0xff, 0xff, 0x01, 0x10], &PROTOCOL), Err(FvError::TruncatedInput));
+        assert_eq!(decompress_section(&[0x08, 0x00, 0x00, 0x01, 0, 0, 0, 0], &PROTOCOL), Err(FvError::TruncatedInput));
+
+        let mut raw = section.clone();
+        raw[3] = 0x19;
+        assert_eq!(decompress_section(&raw, &PROTOCOL), Err(FvError::NotCompressionSection));
+
+        let section = compression_section(0x00, data.len() as u32 + 1, data);
+        assert_eq!(decompress_section(&section, &PROTOCOL), Err(FvError::TruncatedInput));
+    }
+
+    #[test]
+    fn decompress_section_should_report_decompressor_failures() {
+        let data = b"contents";
+        let section = compression_section(0x01, data.len() as u32 + 1, &compress(data));
+        assert_eq!(decompress_section(&section, &PROTOCOL), Err(FvError::DecompressFailed));
+
+        let section = compression_section(0x01, 0, &[0x00, 0x00]);
+        assert_eq!(decompress_section(&section, &PROTOCOL), Err(FvError::DecompressFailed));
+
+        let mut compressed = compress(data);
+        compressed[0] -= 1;
+        let section = compression_section(0x01, data.len() as u32 - 1, &compressed);
+        assert_eq!(decompress_section(&section, &PROTOCOL), Err(FvError::DecompressFailed));
+    }
+}
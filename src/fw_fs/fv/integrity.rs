@@ -0,0 +1,262 @@
+//! Firmware Volume Integrity Checks
+//!
+//! Verifies the checksum of a firmware volume header, and the header and data checksums of the FFS files it holds,
+//! before their contents are trusted.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V3_Code_Definitions.html#efi-firmware-volume-header> and
+//! <https://uefi.org/specs/PI/1.8A/V3_Code_Definitions.html#efi-ffs-file-header>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{mem, ptr};
+
+use super::Header;
+use crate::fw_fs::ffs::{attributes::raw::CHECKSUM, file};
+
+/// The value of IntegrityCheck.Checksum.File when the file data is not checksummed.
+pub const FFS_FIXED_CHECKSUM: u8 = 0xAA;
+
+fn sum8(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte))
+}
+
+/// Returns whether the firmware volume header at the start of `buffer` sums to zero as little-endian 16-bit words
+/// over its HeaderLength, which includes the block map following the fixed part of the header.
+///
+/// Returns false if `buffer` does not hold the header or HeaderLength bytes, or if HeaderLength is odd.
+pub fn verify_fv_header_checksum(buffer: &[u8]) -> bool {
+    if buffer.len() < mem::size_of::<Header>() {
+        return false;
+    }
+    //Safety: buffer is large enough to contain the header, which may not be aligned within it.
+    let header = unsafe { ptr::read_unaligned(buffer.as_ptr() as *const Header) };
+
+    let header_length = header.header_length as usize;
+    if header_length & 0x01 != 0 || header_length > buffer.len() {
+        return false;
+    }
+    let sum = buffer[..header_length]
+        .chunks_exact(2)
+        .fold(0u16, |sum, word| sum.wrapping_add(u16::from_le_bytes([word[0], word[1]])));
+    sum == 0
+}
+
+// The header checksum is computed with IntegrityCheck.Checksum.File and State taken as zero.
+fn verify_header(header: &file::Header, bytes: &[u8]) -> bool {
+    sum8(bytes).wrapping_sub(header.integrity_check_file).wrapping_sub(header.state) == 0
+}
+
+// With the CHECKSUM attribute set, the data and IntegrityCheck.Checksum.File sum to zero; otherwise the checksum must be
+// FFS_FIXED_CHECKSUM.
+fn verify_data(header: &file::Header, data: &[u8]) -> bool {
+    match header.attributes & CHECKSUM != 0 {
+        true => sum8(data).wrapping_add(header.integrity_check_file) == 0,
+        false => header.integrity_check_file == FFS_FIXED_CHECKSUM,
+    }
+}
+
+// The header covers the first `header_size` bytes of the file, the data the rest of its `size` bytes.
+fn verify_file(header: &file::Header, file: &[u8], header_size: usize, size: usize) -> bool {
+    (header_size..=file.len()).contains(&size)
+        && verify_header(header, &file[..header_size])
+        && verify_data(header, &file[header_size..size])
+}
+
+/// Returns whether both the header checksum and the data checksum of the FFS file at the start of `file` are valid.
+///
+/// `file` holds the EFI_FFS_FILE_HEADER followed by the file data, Size bytes in all; any bytes past Size are ignored.
+/// Returns false if `file` is shorter than the header or than Size.
+///
+/// ## Example
+/// ```
+/// use mu_pi::fw_fs::fv::integrity::{verify_ffs_file_checksum, FFS_FIXED_CHECKSUM};
+///
+/// // A raw file of 4 data bytes without the CHECKSUM attribute, so its data checksum is fixed.
+/// let mut file = [0u8; 28];
+/// file[..16].copy_from_slice(&[0x5a; 16]); // Name
+/// file[17] = FFS_FIXED_CHECKSUM; // IntegrityCheck.Checksum.File
+/// file[18] = 0x01; // Type: EFI_FV_FILETYPE_RAW
+/// file[20] = 28; // Size
+/// file[23] = 0xF8; // State: EFI_FILE_DATA_VALID with an erase polarity of 1
+/// file[24..].copy_from_slice(b"data");
+///
+/// // The header sums to zero, not counting IntegrityCheck.Checksum.File and State.
+/// let sum = file[..24].iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+/// file[16] = sum.wrapping_sub(file[17]).wrapping_sub(file[23]).wrapping_neg();
+/// assert!(verify_ffs_file_checksum(&file));
+///
+/// file[18] = 0x02;
+/// assert!(!verify_ffs_file_checksum(&file));
+/// ```
+pub fn verify_ffs_file_checksum(file: &[u8]) -> bool {
+    let header_size = mem::size_of::<file::Header>();
+    if file.len() < header_size {
+        return false;
+    }
+    //Safety: file is large enough to contain the header, which may not be aligned within it.
+    let header = unsafe { ptr::read_unaligned(file.as_ptr() as *const file::Header) };
+    let size = u32::from_le_bytes([header.size[0], header.size[1], header.size[2], 0]) as usize;
+    verify_file(&header, file, header_size, size)
+}
+
+/// Returns whether both the header checksum, which covers the extended size, and the data checksum of the large FFS
+/// file at the start of `file` are valid.
+///
+/// `file` holds the EFI_FFS_FILE_HEADER2 followed by the file data, ExtendedSize bytes in all; any bytes past
+/// ExtendedSize are ignored. Returns false if `file` is shorter than the header or than ExtendedSize.
+pub fn verify_ffs_file2_checksum(file: &[u8]) -> bool {
+    let header_size = mem::size_of::<file::Header2>();
+    if file.len() < header_size {
+        return false;
+    }
+    //Safety: file is large enough to contain the header, which may not be aligned within it.
+    let header = unsafe { ptr::read_unaligned(file.as_ptr() as *const file::Header2) };
+    let Ok(size) = usize::try_from(header.extended_size) else {
+        return false;
+    };
+    verify_file(&header.header, file, header_size, size)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{mem, slice};
+
+    use r_efi::efi;
+
+    use super::{
+        sum8, verify_ffs_file2_checksum, verify_ffs_file_checksum, verify_fv_header_checksum, FFS_FIXED_CHECKSUM,
+    };
+    use crate::fw_fs::ffs::{
+        attributes::raw::{CHECKSUM, LARGE_FILE},
+        file,
+    };
+
+    fn fv_header() -> Vec<u8> {
+        // The fixed header, a block map entry of 4 blocks of 0x1000 bytes and the terminating entry.
+        let mut header = vec![0u8; 0x48];
+        header[32..40].copy_from_slice(&0x4000u64.to_le_bytes());
+        header[40..44].copy_from_slice(b"_FVH");
+        header[48..50].copy_from_slice(&0x48u16.to_le_bytes());
+        header[55] = 2;
+        header[56..60].copy_from_slice(&4u32.to_le_bytes());
+        header[60..64].copy_from_slice(&0x1000u32.to_le_bytes());
+        let sum =
+            header.chunks_exact(2).fold(0u16, |sum, word| sum.wrapping_add(u16::from_le_bytes([word[0], word[1]])));
+        header[50..52].copy_from_slice(&sum.wrapping_neg().to_le_bytes());
+        header
+    }
+
+    // Only used on the FFS headers, which have no padding bytes.
+    fn bytes_of<T>(value: &T) -> &[u8] {
+        unsafe { slice::from_raw_parts(value as *const T as *const u8, mem::size_of::<T>()) }
+    }
+
+    fn file_bytes<T>(header: &T, data: &[u8]) -> Vec<u8> {
+        [bytes_of(header), data].concat()
+    }
+
+    fn file_header(attributes: u8, data: &[u8]) -> file::Header {
+        let mut header = file::Header {
+            name: efi::Guid::from_fields(0x1b45cc0a, 0x156a, 0x428a, 0xaf, 0x62, &[0x49, 0x86, 0x4d, 0xa0, 0xe6, 0xe6]),
+            integrity_check_header: 0,
+            integrity_check_file: 0,
+            file_type: 0x07,
+            attributes,
+            size: ((mem::size_of::<file::Header>() + data.len()) as u32).to_le_bytes()[..3].try_into().unwrap(),
+            state: 0xF8,
+        };
+        header.integrity_check_file = match attributes & CHECKSUM != 0 {
+            true => sum8(data).wrapping_neg(),
+            false => FFS_FIXED_CHECKSUM,
+        };
+        let sum = sum8(bytes_of(&header)).wrapping_sub(header.integrity_check_file).wrapping_sub(header.state);
+        header.integrity_check_header = sum.wrapping_neg();
+        header
+    }
+
+    #[test]
+    fn fv_header_checksum_should_cover_the_block_map() {
+        let mut header = fv_header();
+        assert!(verify_fv_header_checksum(&header));
+
+        header[60] ^= 0x01;
+        assert!(!verify_fv_header_checksum(&header));
+    }
+
+    #[test]
+    fn fv_header_checksum_should_reject_bad_lengths() {
+        let header = fv_header();
+        assert!(!verify_fv_header_checksum(&header[..0x40]));
+        assert!(!verify_fv_header_checksum(&header[..0x20]));
+
+        let mut odd = header.clone();
+        odd[48] = 0x47;
+        assert!(!verify_fv_header_checksum(&odd));
+    }
+
+    #[test]
+    fn ffs_file_checksums_should_be_verified_independently() {
+        let data = [0x12, 0x34, 0x56, 0x78];
+        let header = file_header(CHECKSUM, &data);
+        assert!(verify_ffs_file_checksum(&file_bytes(&header, &data)));
+        assert!(!verify_ffs_file_checksum(&file_bytes(&header, &[0x12, 0x34, 0x56, 0x79])));
+
+        let mut corrupt = header;
+        corrupt.file_type ^= 0x01;
+        assert!(!verify_ffs_file_checksum(&file_bytes(&corrupt, &data)));
+
+        // State and the file checksum are not covered by the header checksum.
+        let mut updated = header;
+        updated.state = 0xF0;
+        assert!(verify_ffs_file_checksum(&file_bytes(&updated, &data)));
+    }
+
+    #[test]
+    fn ffs_file_checksum_should_cover_the_file_size() {
+        let data = [0x12, 0x34, 0x56, 0x78];
+        let file = file_bytes(&file_header(CHECKSUM, &data), &data);
+        // Bytes past the size of the file, such as the next file of the volume, are not part of it.
+        assert!(verify_ffs_file_checksum(&[&file[..], &[0xFF; 8]].concat()));
+        assert!(!verify_ffs_file_checksum(&file[..file.len() - 1]));
+        assert!(!verify_ffs_file_checksum(&file[..20]));
+
+        let unaligned = [&[0u8][..], &file].concat();
+        assert!(verify_ffs_file_checksum(&unaligned[1..]));
+    }
+
+    #[test]
+    fn ffs_file_without_checksum_attribute_should_have_fixed_checksum() {
+        let data = [0x12, 0x34, 0x56, 0x78];
+        let header = file_header(0, &data);
+        assert!(verify_ffs_file_checksum(&file_bytes(&header, &data)));
+        assert!(verify_ffs_file_checksum(&file_bytes(&header, &[0xFF; 4])));
+
+        let mut corrupt = header;
+        corrupt.integrity_check_file = 0x55;
+        assert!(!verify_ffs_file_checksum(&file_bytes(&corrupt, &data)));
+    }
+
+    #[test]
+    fn ffs_file2_header_checksum_should_cover_the_extended_size() {
+        let data = [0xA5; 16];
+        let mut header = file::Header2 { header: file_header(CHECKSUM | LARGE_FILE, &data), extended_size: 0x30 };
+        header.header.size = [0; 3];
+        header.header.integrity_check_header = 0;
+        let sum =
+            sum8(bytes_of(&header)).wrapping_sub(header.header.integrity_check_file).wrapping_sub(header.header.state);
+        header.header.integrity_check_header = sum.wrapping_neg();
+        let file = file_bytes(&header, &data);
+        assert!(verify_ffs_file2_checksum(&file));
+        assert!(!verify_ffs_file2_checksum(&file[..file.len() - 1]));
+        assert!(!verify_ffs_file2_checksum(&file[..24]));
+
+        // A zero byte more of data leaves the data checksum unchanged, so only the header checksum catches the change.
+        header.extended_size = 0x31;
+        assert!(!verify_ffs_file2_checksum(&file_bytes(&header, &[&data[..], &[0]].concat())));
+    }
+}
@@ -0,0 +1,18 @@
+//! Graphics Support
+//!
+//! Helpers for firmware components that draw to the screen through the Graphics Output Protocol.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/12_Protocols_Console_Support.html#efi-graphics-output-protocol>.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+pub mod blt_buffer;
+pub mod bmp;
+pub mod pixel_convert;
+
+pub use blt_buffer::BltBuffer;
@@ -0,0 +1,265 @@
+//! BLT Buffer
+//!
+//! An off-screen rectangle of pixels that can be drawn into and then copied to the screen with a single Blt() call.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+extern crate alloc;
+use alloc::{vec, vec::Vec};
+use core::mem::size_of;
+
+use r_efi::efi;
+
+use crate::protocols::graphics_output::{BltOperation, BltPixel, Protocol as GraphicsOutputProtocol};
+
+/// A width x height rectangle of [`BltPixel`]s stored in row-major order, as expected by the BltBufferToVideo
+/// operation of the Graphics Output Protocol.
+///
+/// ## Example
+///```
+/// use mu_pi::{graphics::BltBuffer, protocols::graphics_output::BltPixel};
+///
+/// let white = BltPixel { blue: 0xFF, green: 0xFF, red: 0xFF, reserved: 0 };
+/// let mut buffer = BltBuffer::new(8, 8);
+/// buffer.fill_rect(2, 2, 4, 4, white).unwrap();
+/// assert_eq!(buffer.pixel(2, 2), Some(white));
+/// assert_eq!(buffer.pixel(1, 1), Some(BltPixel::default()));
+/// assert!(buffer.draw_pixel(8, 0, white).is_err());
+///```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BltBuffer {
+    pixels: Vec<BltPixel>,
+    width: usize,
+    height: usize,
+}
+
+impl BltBuffer {
+    /// Creates a buffer of the given size with every pixel black.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { pixels: vec![BltPixel::default(); width * height], width, height }
+    }
+
+    /// Creates a buffer from row-major `pixels`. Returns `None` if the number of pixels does not match the size.
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<BltPixel>) -> Option<Self> {
+        if width.checked_mul(height)? != pixels.len() {
+            return None;
+        }
+        Some(Self { pixels, width, height })
+    }
+
+    /// Returns the width of the buffer in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height of the buffer in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the pixels of the buffer in row-major order.
+    pub fn pixels(&self) -> &[BltPixel] {
+        &self.pixels
+    }
+
+    /// Returns the pixel at (x, y), or `None` if it is outside the buffer.
+    pub fn pixel(&self, x: usize, y: usize) -> Option<BltPixel> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        Some(self.pixels[y * self.width + x])
+    }
+
+    /// Sets the pixel at (x, y).
+    ///
+    /// Returns `Err(efi::Status::INVALID_PARAMETER)` if the pixel is outside the buffer.
+    pub fn draw_pixel(&mut self, x: usize, y: usize, color: BltPixel) -> Result<(), efi::Status> {
+        if x >= self.width || y >= self.height {
+            Err(efi::Status::INVALID_PARAMETER)?;
+        }
+        self.pixels[y * self.width + x] = color;
+        Ok(())
+    }
+
+    /// Sets every pixel of the `w` x `h` rectangle whose top-left corner is (x, y).
+    ///
+    /// Returns `Err(efi::Status::INVALID_PARAMETER)` without modifying the buffer if any part of the rectangle is
+    /// outside the buffer.
+    pub fn fill_rect(&mut self, x: usize, y: usize, w: usize, h: usize, color: BltPixel) -> Result<(), efi::Status> {
+        match (x.checked_add(w), y.checked_add(h)) {
+            (Some(right), Some(bottom)) if right <= self.width && bottom <= self.height => (),
+            _ => Err(efi::Status::INVALID_PARAMETER)?,
+        }
+        if w == 0 {
+            return Ok(());
+        }
+        for row in self.pixels.chunks_exact_mut(self.width).skip(y).take(h) {
+            row[x..x + w].fill(color);
+        }
+        Ok(())
+    }
+
+    /// Copies the whole buffer to the screen with its top-left corner at (dest_x, dest_y).
+    ///
+    /// Returns the status of the Blt() call. Clipping against the current mode is left to the protocol, which returns
+    /// `INVALID_PARAMETER` if the destination rectangle does not fit on the screen.
+    pub fn blit_to_screen(&self, gop: &GraphicsOutputProtocol, dest_x: usize, dest_y: usize) -> efi::Status {
+        if self.pixels.is_empty() {
+            return efi::Status::SUCCESS;
+        }
+        (gop.blt)(
+            gop,
+            // BltBufferToVideo only reads from the buffer.
+            self.pixels.as_ptr() as *mut BltPixel,
+            BltOperation::BltBufferToVideo,
+            0,
+            0,
+            dest_x,
+            dest_y,
+            self.width,
+            self.height,
+            self.width * size_of::<BltPixel>(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{cell::RefCell, mem::size_of, ptr};
+
+    use r_efi::efi;
+
+    use super::BltBuffer;
+    use crate::protocols::graphics_output::{BltOperation, BltPixel, GraphicsOutputModeInformation, Protocol};
+
+    const RED: BltPixel = BltPixel { blue: 0, green: 0, red: 0xFF, reserved: 0 };
+    const BLUE: BltPixel = BltPixel { blue: 0xFF, green: 0, red: 0, reserved: 0 };
+    const BLACK: BltPixel = BltPixel { blue: 0, green: 0, red: 0, reserved: 0 };
+
+    struct BltCall {
+        operation: BltOperation,
+        pixels: Vec<BltPixel>,
+        args: [usize; 7],
+    }
+
+    std::thread_local! {
+        static BLT_CALL: RefCell<Option<BltCall>> = RefCell::new(None);
+    }
+
+    extern "efiapi" fn query_mode(
+        _: *const Protocol,
+        _: u32,
+        _: *mut usize,
+        _: *mut *mut GraphicsOutputModeInformation,
+    ) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+
+    extern "efiapi" fn set_mode(_: *const Protocol, _: u32) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+
+    extern "efiapi" fn blt(
+        _: *const Protocol,
+        buffer: *mut BltPixel,
+        operation: BltOperation,
+        source_x: usize,
+        source_y: usize,
+        destination_x: usize,
+        destination_y: usize,
+        width: usize,
+        height: usize,
+        delta: usize,
+    ) -> efi::Status {
+        let pixels = unsafe { core::slice::from_raw_parts(buffer, delta / size_of::<BltPixel>() * height) }.to_vec();
+        let args = [source_x, source_y, destination_x, destination_y, width, height, delta];
+        BLT_CALL.with(|call| *call.borrow_mut() = Some(BltCall { operation, pixels, args }));
+        efi::Status::SUCCESS
+    }
+
+    fn mock_protocol() -> Protocol {
+        Protocol { query_mode, set_mode, blt, mode: ptr::null_mut() }
+    }
+
+    fn rows(buffer: &BltBuffer) -> Vec<Vec<BltPixel>> {
+        buffer.pixels().chunks(buffer.width()).map(|row| row.to_vec()).collect()
+    }
+
+    #[test]
+    fn new_should_create_black_buffer() {
+        let buffer = BltBuffer::new(3, 2);
+        assert_eq!(buffer.width(), 3);
+        assert_eq!(buffer.height(), 2);
+        assert_eq!(buffer.pixels(), &[BLACK; 6]);
+    }
+
+    #[test]
+    fn from_pixels_should_check_size() {
+        assert!(BltBuffer::from_pixels(2, 2, vec![RED; 4]).is_some());
+        assert!(BltBuffer::from_pixels(2, 2, vec![RED; 3]).is_none());
+        assert!(BltBuffer::from_pixels(usize::MAX, 2, vec![]).is_none());
+    }
+
+    #[test]
+    fn fill_rect_should_fill_only_rectangle() {
+        let mut buffer = BltBuffer::new(4, 3);
+        buffer.fill_rect(1, 1, 2, 2, RED).unwrap();
+        assert_eq!(
+            rows(&buffer),
+            vec![vec![BLACK, BLACK, BLACK, BLACK], vec![BLACK, RED, RED, BLACK], vec![BLACK, RED, RED, BLACK]]
+        );
+
+        buffer.fill_rect(0, 0, 4, 3, BLUE).unwrap();
+        assert_eq!(buffer.pixels(), &[BLUE; 12]);
+
+        // empty rectangles are allowed, including at the far edge.
+        buffer.fill_rect(4, 3, 0, 0, RED).unwrap();
+        assert_eq!(buffer.pixels(), &[BLUE; 12]);
+        BltBuffer::new(0, 0).fill_rect(0, 0, 0, 0, RED).unwrap();
+    }
+
+    #[test]
+    fn fill_rect_should_reject_out_of_bounds() {
+        let mut buffer = BltBuffer::new(4, 3);
+        assert_eq!(buffer.fill_rect(3, 0, 2, 1, RED), Err(efi::Status::INVALID_PARAMETER));
+        assert_eq!(buffer.fill_rect(0, 2, 1, 2, RED), Err(efi::Status::INVALID_PARAMETER));
+        assert_eq!(buffer.fill_rect(usize::MAX, 0, 2, 1, RED), Err(efi::Status::INVALID_PARAMETER));
+        assert_eq!(buffer.pixels(), &[BLACK; 12]);
+    }
+
+    #[test]
+    fn draw_pixel_should_check_bounds() {
+        let mut buffer = BltBuffer::new(2, 2);
+        buffer.draw_pixel(1, 0, RED).unwrap();
+        assert_eq!(buffer.pixel(1, 0), Some(RED));
+        assert_eq!(buffer.pixel(0, 1), Some(BLACK));
+        assert_eq!(buffer.draw_pixel(2, 0, RED), Err(efi::Status::INVALID_PARAMETER));
+        assert_eq!(buffer.draw_pixel(0, 2, RED), Err(efi::Status::INVALID_PARAMETER));
+        assert_eq!(buffer.pixel(2, 0), None);
+    }
+
+    #[test]
+    fn blit_to_screen_should_copy_whole_buffer() {
+        let gop = mock_protocol();
+        let mut buffer = BltBuffer::new(3, 2);
+        buffer.draw_pixel(2, 1, RED).unwrap();
+
+        assert_eq!(buffer.blit_to_screen(&gop, 10, 20), efi::Status::SUCCESS);
+        let call = BLT_CALL.with(|call| call.borrow_mut().take()).unwrap();
+        assert_eq!(call.operation, BltOperation::BltBufferToVideo);
+        assert_eq!(call.args, [0, 0, 10, 20, 3, 2, 3 * size_of::<BltPixel>()]);
+        assert_eq!(call.pixels, buffer.pixels());
+    }
+
+    #[test]
+    fn blit_to_screen_should_skip_empty_buffer() {
+        let gop = mock_protocol();
+        assert_eq!(BltBuffer::new(0, 5).blit_to_screen(&gop, 0, 0), efi::Status::SUCCESS);
+        assert!(BLT_CALL.with(|call| call.borrow().is_none()));
+    }
+}
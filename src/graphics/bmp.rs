@@ -0,0 +1,305 @@
+//! BMP Decoder
+//!
+//! Decodes the uncompressed 24 and 32 bits per pixel BMP images typically used for boot logos.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+extern crate alloc;
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::BltBuffer;
+use crate::protocols::graphics_output::BltPixel;
+
+// BITMAPFILEHEADER is 14 bytes, followed by at least a 40 byte BITMAPINFOHEADER.
+const FILE_HEADER_SIZE: usize = 14;
+const INFO_HEADER_SIZE: usize = 40;
+
+// BI_RGB
+const COMPRESSION_NONE: u32 = 0;
+
+/// Upper bound on the number of pixels of a buffer created by [`bmp_to_blt_buffer`] (256 MiB of pixels).
+pub const MAX_BLT_PIXELS: usize = 0x0400_0000;
+
+/// Errors reported by [`decode_bmp`] and [`bmp_to_blt_buffer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BmpError {
+    /// The data does not start with the "BM" signature.
+    InvalidSignature,
+    /// The image is not an uncompressed 24 or 32 bits per pixel image. The bit depth of the image is given.
+    UnsupportedBitDepth(u16),
+    /// The image is compressed. The compression method of the image is given.
+    UnsupportedCompression(u32),
+    /// The image has a zero width or height, or is too large to be described in memory.
+    InvalidDimensions,
+    /// The data ends before the end of the headers or the pixel data.
+    TruncatedData,
+}
+
+impl fmt::Display for BmpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BmpError::InvalidSignature => write!(f, "invalid BMP signature"),
+            BmpError::UnsupportedBitDepth(bits) => write!(f, "unsupported BMP bit depth {bits}"),
+            BmpError::UnsupportedCompression(method) => write!(f, "unsupported BMP compression {method}"),
+            BmpError::InvalidDimensions => write!(f, "invalid BMP dimensions"),
+            BmpError::TruncatedData => write!(f, "truncated BMP data"),
+        }
+    }
+}
+
+/// A decoded image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BmpImage {
+    pub width: u32,
+    pub height: u32,
+    /// Pixels in row-major order starting with the top row.
+    pub pixels: Vec<BltPixel>,
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, BmpError> {
+    let bytes = data.get(offset..offset + 2).ok_or(BmpError::TruncatedData)?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, BmpError> {
+    let bytes = data.get(offset..offset + 4).ok_or(BmpError::TruncatedData)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Decodes an uncompressed 24 or 32 bits per pixel BMP image.
+///
+/// Both bottom-up (positive height) and top-down (negative height) images are supported; the returned pixels always
+/// start with the top row. The reserved byte of each pixel is zero.
+///
+/// ## Example
+///```
+/// use mu_pi::graphics::bmp::{decode_bmp, BmpError};
+///
+/// assert_eq!(decode_bmp(b"GIF89a"), Err(BmpError::InvalidSignature));
+///```
+pub fn decode_bmp(data: &[u8]) -> Result<BmpImage, BmpError> {
+    if data.len() < 2 {
+        Err(BmpError::TruncatedData)?;
+    }
+    if &data[..2] != b"BM" {
+        Err(BmpError::InvalidSignature)?;
+    }
+    if data.len() < FILE_HEADER_SIZE + INFO_HEADER_SIZE {
+        Err(BmpError::TruncatedData)?;
+    }
+
+    let pixel_offset = read_u32(data, 10)? as usize;
+    let info = FILE_HEADER_SIZE;
+    let width = read_u32(data, info + 4)? as i32;
+    let height = read_u32(data, info + 8)? as i32;
+    let bit_count = read_u16(data, info + 14)?;
+    let compression = read_u32(data, info + 16)?;
+
+    let bytes_per_pixel = match bit_count {
+        24 => 3,
+        32 => 4,
+        _ => Err(BmpError::UnsupportedBitDepth(bit_count))?,
+    };
+    if compression != COMPRESSION_NONE {
+        Err(BmpError::UnsupportedCompression(compression))?;
+    }
+    if width <= 0 || height == 0 || height == i32::MIN {
+        Err(BmpError::InvalidDimensions)?;
+    }
+
+    let top_down = height < 0;
+    let width = width as u32;
+    let height = height.unsigned_abs();
+
+    // each row is padded to a multiple of four bytes.
+    let row_size = (width as usize)
+        .checked_mul(bytes_per_pixel)
+        .and_then(|size| size.checked_add(3))
+        .ok_or(BmpError::InvalidDimensions)?
+        & !3;
+    let pixel_count = (width as usize).checked_mul(height as usize).ok_or(BmpError::InvalidDimensions)?;
+    let end = row_size
+        .checked_mul(height as usize)
+        .and_then(|size| size.checked_add(pixel_offset))
+        .ok_or(BmpError::InvalidDimensions)?;
+    let pixel_data = data.get(pixel_offset..end).ok_or(BmpError::TruncatedData)?;
+
+    let mut pixels = Vec::with_capacity(pixel_count);
+    for row in 0..height as usize {
+        let source_row = if top_down { row } else { height as usize - 1 - row };
+        let row_data = &pixel_data[source_row * row_size..][..width as usize * bytes_per_pixel];
+        pixels.extend(row_data.chunks_exact(bytes_per_pixel).map(|pixel| BltPixel {
+            blue: pixel[0],
+            green: pixel[1],
+            red: pixel[2],
+            reserved: 0,
+        }));
+    }
+
+    Ok(BmpImage { width, height, pixels })
+}
+
+/// Converts a decoded image to a buffer that can be copied to the screen.
+///
+/// If the number of pixels does not match the dimensions of the image, missing pixels are black and extra pixels are
+/// ignored. Returns `InvalidDimensions` if the image has more than [`MAX_BLT_PIXELS`] pixels.
+pub fn bmp_to_blt_buffer(bmp: &BmpImage) -> Result<BltBuffer, BmpError> {
+    let (width, height) = (bmp.width as usize, bmp.height as usize);
+    let pixel_count =
+        width.checked_mul(height).filter(|&count| count <= MAX_BLT_PIXELS).ok_or(BmpError::InvalidDimensions)?;
+    let mut pixels = bmp.pixels.clone();
+    pixels.resize(pixel_count, BltPixel::default());
+    BltBuffer::from_pixels(width, height, pixels).ok_or(BmpError::InvalidDimensions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bmp_to_blt_buffer, decode_bmp, BmpError, BmpImage, MAX_BLT_PIXELS};
+    use crate::protocols::graphics_output::BltPixel;
+
+    const fn bgr(blue: u8, green: u8, red: u8) -> BltPixel {
+        BltPixel { blue, green, red, reserved: 0 }
+    }
+
+    // Builds a BMP file from top-down rows of (blue, green, red) pixels.
+    fn bmp(bit_count: u16, rows: &[[BltPixel; 4]], top_down: bool) -> Vec<u8> {
+        let bytes_per_pixel = bit_count as usize / 8;
+        let row_size = (4 * bytes_per_pixel + 3) & !3;
+        let image_size = row_size * rows.len();
+
+        let mut data = Vec::new();
+        data.extend_from_slice(b"BM");
+        data.extend_from_slice(&(54 + image_size as u32).to_le_bytes());
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&54u32.to_le_bytes());
+        data.extend_from_slice(&40u32.to_le_bytes());
+        data.extend_from_slice(&4i32.to_le_bytes());
+        let height = rows.len() as i32;
+        data.extend_from_slice(&(if top_down { -height } else { height }).to_le_bytes());
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&bit_count.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&(image_size as u32).to_le_bytes());
+        data.extend_from_slice(&[0; 16]);
+
+        let mut ordered: Vec<_> = rows.iter().collect();
+        if !top_down {
+            ordered.reverse();
+        }
+        for row in ordered {
+            let start = data.len();
+            for pixel in row {
+                data.extend_from_slice(&[pixel.blue, pixel.green, pixel.red]);
+                if bytes_per_pixel == 4 {
+                    data.push(0xFF);
+                }
+            }
+            data.resize(start + row_size, 0);
+        }
+        data
+    }
+
+    fn test_rows() -> [[BltPixel; 4]; 4] {
+        let mut rows = [[BltPixel::default(); 4]; 4];
+        for (y, row) in rows.iter_mut().enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = bgr(x as u8 * 0x10, y as u8 * 0x10, 0x80 | (y * 4 + x) as u8);
+            }
+        }
+        rows
+    }
+
+    #[test]
+    fn decode_should_handle_24bpp_bottom_up() {
+        let rows = test_rows();
+        let data = bmp(24, &rows, false);
+        // 4 pixels * 3 bytes is already a multiple of four, so there is no row padding.
+        assert_eq!(data.len(), 54 + 48);
+
+        let image = decode_bmp(&data).unwrap();
+        assert_eq!((image.width, image.height), (4, 4));
+        assert_eq!(image.pixels, rows.concat());
+    }
+
+    #[test]
+    fn decode_should_handle_32bpp_top_down() {
+        let rows = test_rows();
+        let image = decode_bmp(&bmp(32, &rows, true)).unwrap();
+        assert_eq!((image.width, image.height), (4, 4));
+        assert_eq!(image.pixels, rows.concat());
+    }
+
+    #[test]
+    fn decode_should_skip_row_padding() {
+        let mut data = bmp(24, &test_rows(), false);
+        // shrink the image to 3x2: rows are now 9 bytes of pixels plus 3 bytes of padding.
+        data[18..22].copy_from_slice(&3i32.to_le_bytes());
+        data[22..26].copy_from_slice(&2i32.to_le_bytes());
+        data.truncate(54 + 24);
+        data[54..78].copy_from_slice(&[
+            1, 2, 3, 4, 5, 6, 7, 8, 9, 0xEE, 0xEE, 0xEE, //bottom row
+            11, 12, 13, 14, 15, 16, 17, 18, 19, 0xEE, 0xEE, 0xEE, //top row
+        ]);
+
+        let image = decode_bmp(&data).unwrap();
+        assert_eq!(
+            image.pixels,
+            vec![bgr(11, 12, 13), bgr(14, 15, 16), bgr(17, 18, 19), bgr(1, 2, 3), bgr(4, 5, 6), bgr(7, 8, 9)]
+        );
+    }
+
+    #[test]
+    fn decode_should_reject_invalid_images() {
+        let data = bmp(24, &test_rows(), false);
+
+        assert_eq!(decode_bmp(b"B"), Err(BmpError::TruncatedData));
+        assert_eq!(decode_bmp(b"PNG"), Err(BmpError::InvalidSignature));
+        assert_eq!(decode_bmp(&data[..40]), Err(BmpError::TruncatedData));
+        assert_eq!(decode_bmp(&data[..data.len() - 1]), Err(BmpError::TruncatedData));
+
+        let mut bad = data.clone();
+        bad[28..30].copy_from_slice(&8u16.to_le_bytes());
+        assert_eq!(decode_bmp(&bad), Err(BmpError::UnsupportedBitDepth(8)));
+
+        let mut bad = data.clone();
+        bad[30..34].copy_from_slice(&1u32.to_le_bytes());
+        assert_eq!(decode_bmp(&bad), Err(BmpError::UnsupportedCompression(1)));
+
+        let mut bad = data.clone();
+        bad[22..26].copy_from_slice(&0i32.to_le_bytes());
+        assert_eq!(decode_bmp(&bad), Err(BmpError::InvalidDimensions));
+
+        let mut bad = data;
+        bad[10..14].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(decode_bmp(&bad), Err(BmpError::TruncatedData));
+    }
+
+    #[test]
+    fn bmp_to_blt_buffer_should_preserve_pixels() {
+        let rows = test_rows();
+        let image = decode_bmp(&bmp(24, &rows, false)).unwrap();
+        let buffer = bmp_to_blt_buffer(&image).unwrap();
+        assert_eq!((buffer.width(), buffer.height()), (4, 4));
+        assert_eq!(buffer.pixel(3, 0), Some(rows[0][3]));
+        assert_eq!(buffer.pixel(0, 3), Some(rows[3][0]));
+
+        let short = BmpImage { width: 2, height: 2, pixels: vec![bgr(1, 2, 3)] };
+        let buffer = bmp_to_blt_buffer(&short).unwrap();
+        assert_eq!(buffer.pixels(), &[bgr(1, 2, 3), BltPixel::default(), BltPixel::default(), BltPixel::default()]);
+    }
+
+    #[test]
+    fn bmp_to_blt_buffer_should_reject_oversized_images() {
+        let huge = BmpImage { width: u32::MAX, height: u32::MAX, pixels: vec![] };
+        assert_eq!(bmp_to_blt_buffer(&huge), Err(BmpError::InvalidDimensions));
+
+        let huge = BmpImage { width: MAX_BLT_PIXELS as u32 + 1, height: 1, pixels: vec![] };
+        assert_eq!(bmp_to_blt_buffer(&huge), Err(BmpError::InvalidDimensions));
+    }
+}
@@ -0,0 +1,292 @@
+//! Pixel Format Conversion
+//!
+//! Converts pixels between the frame buffer formats of the Graphics Output Protocol and the [`BltPixel`] format used
+//! by Blt(), e.g. to read back a frame buffer that is not in the Blt() format.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/12_Protocols_Console_Support.html#efi-graphics-output-protocol-queryMode>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::mem::size_of;
+
+use crate::protocols::graphics_output::{BltPixel, PixelBitmask, PixelFormat};
+
+// Scales a color component of `mask` to 8 bits. An empty mask has no component, which reads as 0.
+fn decode_component(pixel: u32, mask: u32) -> u8 {
+    if mask == 0 {
+        return 0;
+    }
+    let max = mask >> mask.trailing_zeros();
+    let value = (pixel & mask) >> mask.trailing_zeros();
+    ((u64::from(value) * 0xFF + u64::from(max) / 2) / u64::from(max)) as u8
+}
+
+// Scales an 8-bit color component to `mask`.
+fn encode_component(component: u8, mask: u32) -> u32 {
+    if mask == 0 {
+        return 0;
+    }
+    let max = mask >> mask.trailing_zeros();
+    let value = (u64::from(component) * u64::from(max) + 0x7F) / 0xFF;
+    (value as u32) << mask.trailing_zeros()
+}
+
+/// Returns the color of a frame buffer `pixel` in format `from`.
+///
+/// `bitmask` describes the pixel if `from` is [`PixelFormat::PixelBitMask`], and is ignored otherwise. Pixels of
+/// [`PixelFormat::PixelBltOnly`] devices are only ever seen through Blt(), so they are in the [`BltPixel`] format.
+pub fn decode_pixel(pixel: u32, from: PixelFormat, bitmask: &PixelBitmask) -> BltPixel {
+    let [b0, b1, b2, b3] = pixel.to_le_bytes();
+    match from {
+        PixelFormat::PixelRedGreenBlueReserved8BitPerColor => BltPixel { blue: b2, green: b1, red: b0, reserved: b3 },
+        PixelFormat::PixelBitMask => BltPixel {
+            blue: decode_component(pixel, bitmask.blue_mask),
+            green: decode_component(pixel, bitmask.green_mask),
+            red: decode_component(pixel, bitmask.red_mask),
+            reserved: decode_component(pixel, bitmask.reserved_mask),
+        },
+        PixelFormat::PixelBlueGreenRedReserved8BitPerColor
+        | PixelFormat::PixelBltOnly
+        | PixelFormat::PixelFormatMax => BltPixel { blue: b0, green: b1, red: b2, reserved: b3 },
+    }
+}
+
+/// Returns the frame buffer pixel of `color` in format `to`. This is the reverse of [`decode_pixel`].
+pub fn encode_pixel(color: BltPixel, to: PixelFormat, bitmask: &PixelBitmask) -> u32 {
+    match to {
+        PixelFormat::PixelRedGreenBlueReserved8BitPerColor => {
+            u32::from_le_bytes([color.red, color.green, color.blue, color.reserved])
+        }
+        PixelFormat::PixelBitMask => {
+            encode_component(color.red, bitmask.red_mask)
+                | encode_component(color.green, bitmask.green_mask)
+                | encode_component(color.blue, bitmask.blue_mask)
+                | encode_component(color.reserved, bitmask.reserved_mask)
+        }
+        PixelFormat::PixelBlueGreenRedReserved8BitPerColor
+        | PixelFormat::PixelBltOnly
+        | PixelFormat::PixelFormatMax => u32::from_le_bytes([color.blue, color.green, color.red, color.reserved]),
+    }
+}
+
+/// Converts a frame buffer `pixel` from format `from` to format `to`, and returns the four bytes of the converted
+/// pixel in memory order.
+///
+/// `bitmask` describes the pixel layout for whichever of `from` and `to` is [`PixelFormat::PixelBitMask`]. When `to` is
+/// [`PixelFormat::PixelBlueGreenRedReserved8BitPerColor`] or [`PixelFormat::PixelBltOnly`], the result is the color of
+/// the pixel.
+///
+/// ## Example
+///```
+/// use mu_pi::{
+///   graphics::pixel_convert::convert_pixel,
+///   protocols::graphics_output::{BltPixel, PixelBitmask, PixelFormat},
+/// };
+///
+/// // A red pixel of an RGBX frame buffer.
+/// let pixel = u32::from_le_bytes([0xFF, 0x00, 0x00, 0x00]);
+/// let color = convert_pixel(
+///   pixel,
+///   PixelFormat::PixelRedGreenBlueReserved8BitPerColor,
+///   &PixelBitmask::default(),
+///   PixelFormat::PixelBltOnly,
+/// );
+/// assert_eq!(color, BltPixel { blue: 0x00, green: 0x00, red: 0xFF, reserved: 0x00 });
+///```
+pub fn convert_pixel(pixel: u32, from: PixelFormat, bitmask: &PixelBitmask, to: PixelFormat) -> BltPixel {
+    let [blue, green, red, reserved] = encode_pixel(decode_pixel(pixel, from, bitmask), to, bitmask).to_le_bytes();
+    BltPixel { blue, green, red, reserved }
+}
+
+/// Converts the first `width` pixels of a frame buffer scan line in format `from_fmt` to `dst`.
+///
+/// `src` holds the raw scan line, four bytes per pixel. Conversion from
+/// [`PixelFormat::PixelRedGreenBlueReserved8BitPerColor`], the most common format that differs from [`BltPixel`], uses
+/// SSE2 when the target supports it.
+///
+/// # Panics
+/// Panics if `src` is shorter than `width` pixels, or `dst` holds fewer than `width` pixels.
+pub fn convert_scanline(src: &[u8], dst: &mut [BltPixel], width: usize, from_fmt: PixelFormat, bitmask: &PixelBitmask) {
+    let src = &src[..width * size_of::<u32>()];
+    let dst = &mut dst[..width];
+    match from_fmt {
+        PixelFormat::PixelRedGreenBlueReserved8BitPerColor => swap_red_blue(src, dst),
+        _ => {
+            for (pixel, color) in src.chunks_exact(size_of::<u32>()).zip(dst.iter_mut()) {
+                let pixel = u32::from_le_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
+                *color = decode_pixel(pixel, from_fmt, bitmask);
+            }
+        }
+    }
+}
+
+fn swap_red_blue_scalar(src: &[u8], dst: &mut [BltPixel]) {
+    for (pixel, color) in src.chunks_exact(size_of::<u32>()).zip(dst.iter_mut()) {
+        *color = BltPixel { blue: pixel[2], green: pixel[1], red: pixel[0], reserved: pixel[3] };
+    }
+}
+
+#[cfg(not(all(any(target_arch = "x86", target_arch = "x86_64"), target_feature = "sse2")))]
+fn swap_red_blue(src: &[u8], dst: &mut [BltPixel]) {
+    swap_red_blue_scalar(src, dst)
+}
+
+// Swaps bytes 0 and 2 of each pixel, four pixels at a time. SSE2 has no byte shuffle, so bytes 0 and 2 are isolated
+// and rotated by 16 bits within each 32-bit pixel, then merged with bytes 1 and 3.
+#[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), target_feature = "sse2"))]
+fn swap_red_blue(src: &[u8], dst: &mut [BltPixel]) {
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::{
+        __m128i, _mm_and_si128, _mm_loadu_si128, _mm_or_si128, _mm_set1_epi32, _mm_slli_epi32, _mm_srli_epi32,
+        _mm_storeu_si128,
+    };
+    #[cfg(target_arch = "x86_64")]
+    use core::arch::x86_64::{
+        __m128i, _mm_and_si128, _mm_loadu_si128, _mm_or_si128, _mm_set1_epi32, _mm_slli_epi32, _mm_srli_epi32,
+        _mm_storeu_si128,
+    };
+
+    const LANE: usize = size_of::<__m128i>();
+    let pixels_per_lane = LANE / size_of::<BltPixel>();
+    let lanes = dst.len() / pixels_per_lane;
+    //Safety: SSE2 is enabled for the target. Every load reads LANE bytes within src and every store writes LANE
+    //bytes within dst, as both hold dst.len() pixels; the unaligned load and store have no alignment requirement.
+    unsafe {
+        let red_blue = _mm_set1_epi32(0x00FF_00FF);
+        let green_reserved = _mm_set1_epi32(0xFF00_FF00_u32 as i32);
+        for lane in 0..lanes {
+            let pixels = _mm_loadu_si128(src.as_ptr().add(lane * LANE) as *const __m128i);
+            let swapped = _mm_and_si128(pixels, red_blue);
+            let swapped = _mm_or_si128(_mm_slli_epi32(swapped, 16), _mm_srli_epi32(swapped, 16));
+            let pixels = _mm_or_si128(swapped, _mm_and_si128(pixels, green_reserved));
+            _mm_storeu_si128(dst.as_mut_ptr().add(lane * pixels_per_lane) as *mut __m128i, pixels);
+        }
+    }
+    let done = lanes * pixels_per_lane;
+    swap_red_blue_scalar(&src[done * size_of::<BltPixel>()..], &mut dst[done..]);
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    use alloc::{vec, vec::Vec};
+
+    use super::{convert_pixel, convert_scanline, decode_pixel, encode_pixel, swap_red_blue_scalar};
+    use crate::protocols::graphics_output::{BltPixel, PixelBitmask, PixelFormat};
+
+    // A 16-bit RGB565 layout, so that the components must be scaled.
+    const RGB565: PixelBitmask =
+        PixelBitmask { red_mask: 0xF800, green_mask: 0x07E0, blue_mask: 0x001F, reserved_mask: 0 };
+    // A 32-bit layout that matches PixelRedGreenBlueReserved8BitPerColor.
+    const RGBX: PixelBitmask = PixelBitmask {
+        red_mask: 0x0000_00FF,
+        green_mask: 0x0000_FF00,
+        blue_mask: 0x00FF_0000,
+        reserved_mask: 0xFF00_0000,
+    };
+
+    const FORMATS: [PixelFormat; 4] = [
+        PixelFormat::PixelRedGreenBlueReserved8BitPerColor,
+        PixelFormat::PixelBlueGreenRedReserved8BitPerColor,
+        PixelFormat::PixelBitMask,
+        PixelFormat::PixelBltOnly,
+    ];
+
+    const COLOR: BltPixel = BltPixel { blue: 0x12, green: 0x34, red: 0x56, reserved: 0x78 };
+
+    // The frame buffer pixel of COLOR in each format, with the RGBX bitmask.
+    fn raw(format: PixelFormat) -> u32 {
+        match format {
+            PixelFormat::PixelRedGreenBlueReserved8BitPerColor | PixelFormat::PixelBitMask => 0x7812_3456,
+            _ => 0x7856_3412,
+        }
+    }
+
+    #[test]
+    fn every_format_combination_should_convert() {
+        for from in FORMATS {
+            for to in FORMATS {
+                let [blue, green, red, reserved] = raw(to).to_le_bytes();
+                assert_eq!(
+                    convert_pixel(raw(from), from, &RGBX, to),
+                    BltPixel { blue, green, red, reserved },
+                    "{from:?} -> {to:?}"
+                );
+            }
+            assert_eq!(decode_pixel(raw(from), from, &RGBX), COLOR, "{from:?}");
+            assert_eq!(encode_pixel(COLOR, from, &RGBX), raw(from), "{from:?}");
+        }
+    }
+
+    #[test]
+    fn bitmask_components_should_be_scaled() {
+        let white = BltPixel { blue: 0xFF, green: 0xFF, red: 0xFF, reserved: 0 };
+        assert_eq!(decode_pixel(0xFFFF, PixelFormat::PixelBitMask, &RGB565), white);
+        assert_eq!(encode_pixel(white, PixelFormat::PixelBitMask, &RGB565), 0xFFFF);
+        assert_eq!(
+            decode_pixel(0x8410, PixelFormat::PixelBitMask, &RGB565),
+            BltPixel { blue: 0x84, green: 0x82, red: 0x84, reserved: 0 }
+        );
+        assert_eq!(
+            convert_pixel(0xF800, PixelFormat::PixelBitMask, &RGB565, PixelFormat::PixelBltOnly),
+            BltPixel { blue: 0, green: 0, red: 0xFF, reserved: 0 }
+        );
+        assert_eq!(
+            convert_pixel(
+                0x0000_00FF,
+                PixelFormat::PixelBlueGreenRedReserved8BitPerColor,
+                &RGB565,
+                PixelFormat::PixelBitMask
+            ),
+            BltPixel { blue: 0x1F, green: 0, red: 0, reserved: 0 }
+        );
+        assert_eq!(decode_pixel(0xFFFF_FFFF, PixelFormat::PixelBitMask, &PixelBitmask::default()), BltPixel::default());
+    }
+
+    fn scanline(width: usize) -> (Vec<u8>, Vec<BltPixel>) {
+        let colors: Vec<BltPixel> = (0..width)
+            .map(|i| BltPixel { blue: i as u8, green: (i * 3) as u8, red: (i * 7) as u8, reserved: (i * 11) as u8 })
+            .collect();
+        (colors.iter().flat_map(|c| [c.red, c.green, c.blue, c.reserved]).collect(), colors)
+    }
+
+    #[test]
+    fn scanlines_should_convert_in_every_format() {
+        for width in [0, 1, 3, 4, 5, 8, 17, 64] {
+            let (rgbx, colors) = scanline(width);
+            for format in FORMATS {
+                let src: Vec<u8> =
+                    colors.iter().flat_map(|color| encode_pixel(*color, format, &RGBX).to_le_bytes()).collect();
+                if format == PixelFormat::PixelRedGreenBlueReserved8BitPerColor {
+                    assert_eq!(src, rgbx);
+                }
+                let mut dst = vec![BltPixel::default(); width + 1];
+                convert_scanline(&src, &mut dst, width, format, &RGBX);
+                assert_eq!(&dst[..width], &colors[..], "{format:?} width {width}");
+                assert_eq!(dst[width], BltPixel::default(), "{format:?} width {width}");
+            }
+        }
+    }
+
+    #[test]
+    fn vector_path_should_match_scalar_path() {
+        let (rgbx, _) = scanline(259);
+        let mut vector = vec![BltPixel::default(); 259];
+        let mut scalar = vec![BltPixel::default(); 259];
+        convert_scanline(&rgbx, &mut vector, 259, PixelFormat::PixelRedGreenBlueReserved8BitPerColor, &RGBX);
+        swap_red_blue_scalar(&rgbx, &mut scalar);
+        assert_eq!(vector, scalar);
+    }
+
+    #[test]
+    #[should_panic]
+    fn short_scanline_should_panic() {
+        let mut dst = [BltPixel::default(); 4];
+        convert_scanline(&[0; 12], &mut dst, 4, PixelFormat::PixelBltOnly, &RGBX);
+    }
+}
@@ -0,0 +1,238 @@
+//! GUID Registry
+//!
+//! Names of the GUIDs defined by this crate, for logs and dumps that print GUIDs. Each GUID is named by its usual
+//! EDK II global variable name, e.g. `gEfiBdsArchProtocolGuid`.
+//!
+//! The registry is assembled at compile time from the GUID constants of every module, and sorted so lookups are a
+//! binary search. A unit test checks that every GUID constant defined by the crate is registered and that no two
+//! constants share a GUID.
+//!
+//! ## Example
+//! ```
+//! use mu_pi::{guids, protocols::bds};
+//!
+//! assert_eq!(guids::name_of(&bds::PROTOCOL_GUID), Some("gEfiBdsArchProtocolGuid"));
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::cmp::Ordering;
+
+use r_efi::efi;
+
+use crate::{capsule, dxe, dxe_services, fw_fs, hob, nvdimm, protocols, secure_boot, status_code};
+
+// Orders GUIDs by their bytes, usable in const evaluation.
+const fn compare(a: &efi::Guid, b: &efi::Guid) -> Ordering {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return if a[i] < b[i] { Ordering::Less } else { Ordering::Greater };
+        }
+        i += 1;
+    }
+    Ordering::Equal
+}
+
+const fn sort<const N: usize>(entries: &[(efi::Guid, &'static str)]) -> [(efi::Guid, &'static str); N] {
+    let mut sorted = [(efi::Guid::from_bytes(&[0; 16]), ""); N];
+    let mut i = 0;
+    while i < N {
+        let entry = entries[i];
+        let mut j = i;
+        while j > 0 && matches!(compare(&sorted[j - 1].0, &entry.0), Ordering::Greater) {
+            sorted[j] = sorted[j - 1];
+            j -= 1;
+        }
+        sorted[j] = entry;
+        i += 1;
+    }
+    sorted
+}
+
+// Builds the registry from `path => name` pairs. The paths are kept for the completeness test.
+macro_rules! registry {
+    ($($path:path => $name:literal,)*) => {
+        const ENTRIES: &[(efi::Guid, &str)] = &[$(($path, $name),)*];
+        #[cfg(test)]
+        const PATHS: &[&str] = &[$(stringify!($path),)*];
+    };
+}
+
+registry! {
+    capsule::fmp::CAPSULE_ID_GUID => "gEfiFmpCapsuleGuid",
+    capsule::fmp::CERT_TYPE_PKCS7_GUID => "gEfiCertPkcs7Guid",
+    capsule::fmp::CERT_TYPE_RSA2048_SHA256_GUID => "gEfiCertTypeRsa2048Sha256Guid",
+    capsule::result::CAPSULE_REPORT_GUID => "gEfiCapsuleReportGuid",
+    dxe::event_groups::END_OF_DXE_EVENT_GROUP_GUID => "gEfiEndOfDxeEventGroupGuid",
+    dxe::event_groups::DXE_DISPATCH_EVENT_GROUP_GUID => "gEfiEventDxeDispatchGuid",
+    dxe::event_groups::DXE_MM_READY_TO_LOCK_PROTOCOL_GUID => "gEfiDxeMmReadyToLockProtocolGuid",
+    dxe::event_groups::MM_READY_TO_LOCK_PROTOCOL_GUID => "gEfiMmReadyToLockProtocolGuid",
+    dxe::event_groups::MM_END_OF_DXE_PROTOCOL_GUID => "gEfiMmEndOfDxeProtocolGuid",
+    dxe_services::DXE_SERVICES_TABLE_GUID => "gEfiDxeServicesTableGuid",
+    fw_fs::ffs::guid::EFI_FIRMWARE_FILE_SYSTEM2_GUID => "gEfiFirmwareFileSystem2Guid",
+    fw_fs::ffs::guid::EFI_FIRMWARE_FILE_SYSTEM3_GUID => "gEfiFirmwareFileSystem3Guid",
+    fw_fs::ffs::guid::EFI_FFS_VOLUME_TOP_FILE_GUID => "gEfiFirmwareVolumeTopFileGuid",
+    hob::MEMORY_TYPE_INFO_HOB_GUID => "gEfiMemoryTypeInformationGuid",
+    hob::known_tables::HOB_LIST_GUID => "gEfiHobListGuid",
+    hob::handoff::MEMORY_ALLOC_MODULE_GUID => "gEfiHobMemoryAllocModuleGuid",
+    nvdimm::label::NVDIMM_NAMESPACE_GUID => "EFI_ACPI_6_1_NFIT_GUID_BYTE_ADDRESSABLE_PERSISTENT_MEMORY_REGION",
+    protocols::bds::PROTOCOL_GUID => "gEfiBdsArchProtocolGuid",
+    protocols::cpu_arch::PROTOCOL_GUID => "gEfiCpuArchProtocolGuid",
+    protocols::debug_support::PROTOCOL_GUID => "gEfiDebugSupportProtocolGuid",
+    protocols::debugport::PROTOCOL_GUID => "gEfiDebugPortProtocolGuid",
+    protocols::dhcp4::PROTOCOL_GUID => "gEfiDhcp4ProtocolGuid",
+    protocols::dhcp4::SERVICE_BINDING_PROTOCOL_GUID => "gEfiDhcp4ServiceBindingProtocolGuid",
+    protocols::dns4::PROTOCOL_GUID => "gEfiDns4ProtocolGuid",
+    protocols::dns4::SERVICE_BINDING_PROTOCOL_GUID => "gEfiDns4ServiceBindingProtocolGuid",
+    protocols::edid_override::PROTOCOL_GUID => "gEfiEdidOverrideProtocolGuid",
+    protocols::firmware_volume::PROTOCOL_GUID => "gEfiFirmwareVolume2ProtocolGuid",
+    protocols::firmware_volume_block::PROTOCOL_GUID => "gEfiFirmwareVolumeBlockProtocolGuid",
+    protocols::graphics_output::PROTOCOL_GUID => "gEfiGraphicsOutputProtocolGuid",
+    protocols::http::PROTOCOL_GUID => "gEfiHttpProtocolGuid",
+    protocols::http::SERVICE_BINDING_PROTOCOL_GUID => "gEfiHttpServiceBindingProtocolGuid",
+    protocols::ipmi_transport::PROTOCOL_GUID => "gIpmiTransportProtocolGuid",
+    protocols::isa_io::PROTOCOL_GUID => "gEfiIsaIoProtocolGuid",
+    protocols::isa_io::ISA_ACPI_PROTOCOL_GUID => "gEfiIsaAcpiProtocolGuid",
+    protocols::legacy_bios::PROTOCOL_GUID => "gEfiLegacyBiosProtocolGuid",
+    protocols::lock_box::PROTOCOL_GUID => "gLockBoxProtocolGuid",
+    protocols::metronome::PROTOCOL_GUID => "gEfiMetronomeArchProtocolGuid",
+    protocols::monotonic_counter::PROTOCOL_GUID => "gEfiMonotonicCounterArchProtocolGuid",
+    protocols::mtftp4::PROTOCOL_GUID => "gEfiMtftp4ProtocolGuid",
+    protocols::mtftp4::SERVICE_BINDING_PROTOCOL_GUID => "gEfiMtftp4ServiceBindingProtocolGuid",
+    protocols::platform_driver_override::PROTOCOL_GUID => "gEfiPlatformDriverOverrideProtocolGuid",
+    protocols::read_only_variable2::PROTOCOL_GUID => "gEfiPeiReadOnlyVariable2PpiGuid",
+    protocols::real_time_clock::PROTOCOL_GUID => "gEfiRealTimeClockArchProtocolGuid",
+    protocols::reset::PROTOCOL_GUID => "gEfiResetArchProtocolGuid",
+    protocols::runtime::PROTOCOL_GUID => "gEfiRuntimeArchProtocolGuid",
+    protocols::s3_resume::PROTOCOL_GUID => "gEfiPeiS3Resume2PpiGuid",
+    protocols::s3_resume::READY_TO_BOOT_PROTOCOL_GUID => "gEdkiiSmmReadyToBootProtocolGuid",
+    protocols::s3_save_state::PROTOCOL_GUID => "gEfiS3SaveStateProtocolGuid",
+    protocols::security::PROTOCOL_GUID => "gEfiSecurityArchProtocolGuid",
+    protocols::security2::PROTOCOL_GUID => "gEfiSecurity2ArchProtocolGuid",
+    protocols::simple_text_input::PROTOCOL_GUID => "gEfiSimpleTextInProtocolGuid",
+    protocols::simple_text_output::PROTOCOL_GUID => "gEfiSimpleTextOutProtocolGuid",
+    protocols::smbios::PROTOCOL_GUID => "gEfiSmbiosProtocolGuid",
+    protocols::smbus_hc::PROTOCOL_GUID => "gEfiSmbusHcProtocolGuid",
+    protocols::smm::access2::PROTOCOL_GUID => "gEfiSmmAccess2ProtocolGuid",
+    protocols::smm::cpu_io2::PROTOCOL_GUID => "gEfiSmmCpuIo2ProtocolGuid",
+    protocols::smm::sw_dispatch2::PROTOCOL_GUID => "gEfiSmmSwDispatch2ProtocolGuid",
+    protocols::status_code::PROTOCOL_GUID => "gEfiStatusCodeRuntimeProtocolGuid",
+    protocols::timer::PROTOCOL_GUID => "gEfiTimerArchProtocolGuid",
+    protocols::variable::PROTOCOL_GUID => "gEfiVariableArchProtocolGuid",
+    protocols::variable_write::PROTOCOL_GUID => "gEfiVariableWriteArchProtocolGuid",
+    protocols::watchdog::PROTOCOL_GUID => "gEfiWatchdogTimerArchProtocolGuid",
+    secure_boot::sig_db::CERT_SHA1_GUID => "gEfiCertSha1Guid",
+    secure_boot::sig_db::CERT_SHA256_GUID => "gEfiCertSha256Guid",
+    secure_boot::sig_db::CERT_SHA384_GUID => "gEfiCertSha384Guid",
+    secure_boot::sig_db::CERT_SHA512_GUID => "gEfiCertSha512Guid",
+    secure_boot::sig_db::CERT_X509_GUID => "gEfiCertX509Guid",
+    status_code::data::STRING_DATA_TYPE_GUID => "gEfiStatusCodeDataTypeStringGuid",
+    status_code::data::SPECIFIC_DATA_GUID => "gEfiStatusCodeSpecificDataGuid",
+    status_code::data::DEBUG_DATA_TYPE_GUID => "gEfiStatusCodeDataTypeDebugGuid",
+}
+
+static REGISTRY: [(efi::Guid, &str); ENTRIES.len()] = sort(ENTRIES);
+
+/// Returns the GUIDs defined by the crate with their names, sorted by the bytes of the GUIDs.
+pub fn registry() -> &'static [(efi::Guid, &'static str)] {
+    &REGISTRY
+}
+
+/// Returns the name of `guid` if it is defined by the crate.
+pub fn name_of(guid: &efi::Guid) -> Option<&'static str> {
+    let index = REGISTRY.binary_search_by(|(entry, _)| compare(entry, guid)).ok()?;
+    Some(REGISTRY[index].1)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeSet, fs, path::Path};
+
+    use r_efi::efi;
+
+    use super::{name_of, registry, PATHS};
+    use crate::protocols::{bds, debugport};
+
+    #[test]
+    fn registry_should_be_sorted_without_duplicates() {
+        for pair in registry().windows(2) {
+            assert!(
+                pair[0].0.as_bytes() < pair[1].0.as_bytes(),
+                "{} and {} are out of order or equal",
+                pair[0].1,
+                pair[1].1
+            );
+        }
+        let names: BTreeSet<_> = registry().iter().map(|(_, name)| name).collect();
+        assert_eq!(names.len(), registry().len());
+    }
+
+    #[test]
+    fn name_of_should_find_registered_guids() {
+        assert_eq!(name_of(&bds::PROTOCOL_GUID), Some("gEfiBdsArchProtocolGuid"));
+        // The debug port variable is named by the protocol GUID.
+        assert_eq!(name_of(&debugport::DEBUGPORT_VARIABLE_GUID), Some("gEfiDebugPortProtocolGuid"));
+        assert_eq!(name_of(&efi::Guid::from_bytes(&[0xa5; 16])), None);
+        for (guid, name) in registry() {
+            assert_eq!(name_of(guid), Some(*name));
+        }
+    }
+
+    // Returns the `pub` GUID constants defined outside test code in `file`, except those defined as another constant.
+    fn guid_constants(file: &Path) -> Vec<String> {
+        let text = fs::read_to_string(file).unwrap();
+        let lines: Vec<&str> = text.lines().take_while(|line| !line.starts_with("#[cfg(test)]")).collect();
+        let mut constants = Vec::new();
+        for (index, line) in lines.iter().enumerate() {
+            let line = line.trim();
+            let Some(rest) = line.strip_prefix("pub const ") else { continue };
+            let Some((name, initializer)) = rest.split_once(':') else { continue };
+            if !initializer.contains("Guid =") {
+                continue;
+            }
+            let next = lines.get(index + 1).copied().unwrap_or_default();
+            if initializer.contains("from_") || next.contains("from_") {
+                constants.push(name.to_string());
+            }
+        }
+        constants
+    }
+
+    fn visit(directory: &Path, module: &str, missing: &mut Vec<String>) {
+        for entry in fs::read_dir(directory).unwrap() {
+            let path = entry.unwrap().path();
+            let name = path.file_stem().unwrap().to_str().unwrap();
+            let module = match module {
+                "" => name.to_string(),
+                _ => format!("{module}::{name}"),
+            };
+            if path.is_dir() {
+                visit(&path, &module, missing);
+                continue;
+            }
+            for constant in guid_constants(&path) {
+                let registered = PATHS
+                    .iter()
+                    .map(|path| path.replace(' ', ""))
+                    .any(|path| path.starts_with(&format!("{module}::")) && path.ends_with(&format!("::{constant}")));
+                if !registered {
+                    missing.push(format!("{module}::{constant}"));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn registry_should_cover_every_guid_constant() {
+        let mut missing = Vec::new();
+        visit(&Path::new(env!("CARGO_MANIFEST_DIR")).join("src"), "", &mut missing);
+        assert!(missing.is_empty(), "GUID constants missing from the registry: {missing:?}");
+    }
+}
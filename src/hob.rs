@@ -67,7 +67,11 @@
 //! SPDX-License-Identifier: BSD-2-Clause-Patent
 //!
 
-use crate::address_helper::{align_down, align_up};
+use crate::{
+    address_helper::{align_down, align_up},
+    boot_mode::BootMode,
+    device_path::GuidFmt,
+};
 use core::{
     ffi::c_void,
     fmt,
@@ -77,6 +81,10 @@ use core::{
 };
 use indoc::indoc;
 
+pub mod handoff;
+
+pub use handoff::{handoff_check, system_memory_covers, HandoffError, HandoffSummary};
+
 // Expectation is someone will provide alloc
 extern crate alloc;
 use alloc::vec::Vec;
@@ -97,6 +105,12 @@ pub type EfiPhysicalAddress = u32;
 #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
 compile_error!("This crate only (currently) supports x86, x86_64, and aarch64 architectures");
 
+// EfiPhysicalAddress is u32 on x86, while HOB lengths are always u64.
+#[allow(clippy::unnecessary_cast)]
+pub(crate) fn physical_address(address: EfiPhysicalAddress) -> u64 {
+    address as u64
+}
+
 // All targets assume (currently) that EfiBootMode is u32
 pub type EfiBootMode = u32;
 
@@ -123,7 +137,7 @@ pub mod header {
     /// All HOBs must contain this generic HOB header (EFI_HOB_GENERIC_HEADER).
     ///
     #[repr(C)]
-    #[derive(Copy, Clone, Debug)]
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
     pub struct Hob {
         // EFI_HOB_GENERIC_HEADER
         /// Identifies the HOB data structure type.
@@ -144,7 +158,7 @@ pub mod header {
     /// subsequent inclusion in the UEFI memory map.
     ///
     #[repr(C)]
-    #[derive(Copy, Clone, Debug)]
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
     pub struct MemoryAllocation {
         // EFI_HOB_MEMORY_ALLOCATION_HEADER
         /// A GUID that defines the memory allocation region's type and purpose, as well as
@@ -187,7 +201,7 @@ pub type MemoryPool = header::Hob;
 /// This HOB must be the first one in the HOB list.
 ///
 #[repr(C)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct PhaseHandoffInformationTable {
     /// The HOB generic header. Header.HobType = EFI_HOB_TYPE_HANDOFF.
     ///
@@ -226,12 +240,19 @@ pub struct PhaseHandoffInformationTable {
     pub end_of_hob_list: EfiPhysicalAddress,
 }
 
+impl PhaseHandoffInformationTable {
+    /// Returns the system boot mode as determined during the HOB producer phase.
+    pub fn boot_mode(&self) -> BootMode {
+        BootMode::from(self.boot_mode)
+    }
+}
+
 /// Describes all memory ranges used during the HOB producer
 /// phase that exist outside the HOB list. This HOB type
 /// describes how memory is used, not the physical attributes of memory.
 ///
 #[repr(C)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct MemoryAllocation {
     // EFI_HOB_MEMORY_ALLOCATION
     /// The HOB generic header. Header.HobType = EFI_HOB_TYPE_MEMORY_ALLOCATION.
@@ -265,7 +286,7 @@ pub type MemoryAllocationBspStore = MemoryAllocation;
 /// Defines the location and entry point of the HOB consumer phase.
 ///
 #[repr(C)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct MemoryAllocationModule {
     /// The HOB generic header. Header.HobType = EFI_HOB_TYPE_MEMORY_ALLOCATION.
     ///
@@ -309,6 +330,10 @@ pub const EFI_RESOURCE_IO_RESERVED: u32 = 0x00000006;
 // #define BZ3937_EFI_RESOURCE_MEMORY_UNACCEPTED      0x00000007
 pub const EFI_RESOURCE_MAX_MEMORY_TYPE: u32 = 0x00000007;
 
+/// Resource attribute bits of a resource descriptor HOB (EFI_RESOURCE_ATTRIBUTE_TYPE), a combination of the
+/// `EFI_RESOURCE_ATTRIBUTE_*` values.
+pub type ResourceAttributes = u32;
+
 //
 // These types can be ORed together as needed.
 //
@@ -409,7 +434,7 @@ pub const EFI_MEMORY_MORE_RELIABLE: u64 = 0x0000_0000_0001_0000;
 /// host bus during the HOB producer phase.
 ///
 #[repr(C)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct ResourceDescriptor {
     // EFI_HOB_RESOURCE_DESCRIPTOR
     /// The HOB generic header. Header.HobType = EFI_HOB_TYPE_RESOURCE_DESCRIPTOR.
@@ -457,7 +482,7 @@ impl ResourceDescriptor {
 /// maintain and manage HOBs with specific GUID.
 ///
 #[repr(C)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct GuidHob {
     // EFI_HOB_GUID_TYPE
     /// The HOB generic header. Header.HobType = EFI_HOB_TYPE_GUID_EXTENSION.
@@ -474,7 +499,7 @@ pub struct GuidHob {
 /// Details the location of firmware volumes that contain firmware files.
 ///
 #[repr(C)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct FirmwareVolume {
     // EFI_HOB_FIRMWARE_VOLUME
     /// The HOB generic header. Header.HobType = EFI_HOB_TYPE_FV.
@@ -494,7 +519,7 @@ pub struct FirmwareVolume {
 /// from a file within another firmware volume.
 ///
 #[repr(C)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct FirmwareVolume2 {
     // EFI_HOB_FIRMWARE_VOLUME2
     /// The HOB generic header. Header.HobType = EFI_HOB_TYPE_FV2.
@@ -522,7 +547,7 @@ pub struct FirmwareVolume2 {
 /// from a file within another firmware volume.
 ///
 #[repr(C)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct FirmwareVolume3 {
     // EFI_HOB_FIRMWARE_VOLUME3
     /// The HOB generic header. Header.HobType = EFI_HOB_TYPE_FV3.
@@ -560,7 +585,7 @@ pub struct FirmwareVolume3 {
 /// Describes processor information, such as address space and I/O space capabilities.
 ///
 #[repr(C)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Cpu {
     // EFI_HOB_CPU
     /// The HOB generic header. Header.HobType = EFI_HOB_TYPE_CPU.
@@ -587,7 +612,7 @@ pub struct Cpu {
 /// CAPSULE_FLAGS_POPULATE_SYSTEM_TABLE flag set in the EFI_CAPSULE_HEADER.
 ///
 #[repr(C)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Capsule {
     // EFI_HOB_CAPSULE
     /// The HOB generic header where Header.HobType = EFI_HOB_TYPE_UEFI_CAPSULE.
@@ -596,10 +621,12 @@ pub struct Capsule {
 
     /// The physical memory-mapped base address of an UEFI capsule. This value is set to
     /// point to the base of the contiguous memory of the UEFI capsule.
+    ///
+    pub base_address: EfiPhysicalAddress,
+
     /// The length of the contiguous memory in bytes.
     ///
-    pub base_address: u8,
-    pub length: u8,
+    pub length: u64,
 }
 
 /// Represents a HOB list.
@@ -982,8 +1009,10 @@ impl fmt::Debug for HobList<'_> {
                         f,
                         indoc! {"
                         GUID HOB
-                          HOB Length: 0x{:x}\n"},
-                        hob.header.length
+                          HOB Length: 0x{:x}
+                          Name: {:?}\n"},
+                        hob.header.length,
+                        GuidFmt(&hob.name)
                     )?;
                 }
                 Hob::FirmwareVolume2(hob) => {
@@ -992,8 +1021,13 @@ impl fmt::Debug for HobList<'_> {
                         indoc! {"
                         FIRMWARE VOLUME 2 (FV2) HOB
                           Base Address: 0x{:x}
-                          Length: 0x{:x}\n"},
-                        hob.base_address, hob.length
+                          Length: 0x{:x}
+                          FV Name: {:?}
+                          File Name: {:?}\n"},
+                        hob.base_address,
+                        hob.length,
+                        GuidFmt(&hob.fv_name),
+                        GuidFmt(&hob.file_name)
                     )?;
                 }
                 Hob::FirmwareVolume3(hob) => {
@@ -1002,8 +1036,13 @@ impl fmt::Debug for HobList<'_> {
                         indoc! {"
                         FIRMWARE VOLUME 3 (FV3) HOB
                           Base Address: 0x{:x}
-                          Length: 0x{:x}\n"},
-                        hob.base_address, hob.length
+                          Length: 0x{:x}
+                          FV Name: {:?}
+                          File Name: {:?}\n"},
+                        hob.base_address,
+                        hob.length,
+                        GuidFmt(&hob.fv_name),
+                        GuidFmt(&hob.file_name)
                     )?;
                 }
                 Hob::Cpu(hob) => {
@@ -1055,6 +1094,7 @@ impl Hob<'_> {
 
 /// A HOB iterator.
 ///
+#[derive(Debug)]
 pub struct HobIter<'a> {
     hob_ptr: *const header::Hob,
     _a: PhantomData<&'a ()>,
@@ -1126,16 +1166,105 @@ pub struct EFiMemoryTypeInformation {
     pub number_of_pages: u32,
 }
 
+/// GUIDs of the EFI configuration table entries that DXE phase code locates at start of day.
+pub mod known_tables {
+    use r_efi::efi;
+
+    /// GUID of the configuration table entry that points to the HOB list handed off by PEI.
+    ///
+    /// # Documentation
+    /// UEFI Platform Initialization Specification, Release 1.8, Section II-4.5
+    pub const HOB_LIST_GUID: efi::Guid =
+        efi::Guid::from_fields(0x7739f24c, 0x93d7, 0x11d4, 0x9a, 0x3a, &[0x00, 0x90, 0x27, 0x3f, 0xc1, 0x4d]);
+
+    /// GUID of the configuration table entry that holds the memory type information array.
+    ///
+    /// This is the same GUID that names the memory type information GUID extension HOB.
+    pub const MEMORY_TYPE_INFORMATION_GUID: efi::Guid = super::MEMORY_TYPE_INFO_HOB_GUID;
+
+    /// GUID of the configuration table entry that points to the DXE Services Table.
+    pub const DXE_SERVICES_TABLE_GUID: efi::Guid = crate::dxe_services::DXE_SERVICES_TABLE_GUID;
+}
+
+/// Upper bound on the number of configuration table entries walked by [`find_configuration_table`], so that a
+/// corrupt `number_of_table_entries` does not turn into an unbounded slice.
+pub const MAX_CONFIGURATION_TABLE_ENTRIES: usize = 0x1000;
+
+/// Returns the vendor table of the entry for `guid` in the EFI configuration table of `system_table`.
+///
+/// Returns `INVALID_PARAMETER` if the configuration table is null, or if it claims more than
+/// [`MAX_CONFIGURATION_TABLE_ENTRIES`] entries; and `NOT_FOUND` if there is no entry for `guid`.
+///
+/// ## Safety
+/// Caller must ensure that the configuration table of `system_table` is valid.
+pub unsafe fn find_configuration_table(
+    system_table: &r_efi::efi::SystemTable,
+    guid: &r_efi::efi::Guid,
+) -> Result<*mut c_void, r_efi::efi::Status> {
+    use r_efi::efi;
+
+    if system_table.configuration_table.is_null()
+        || system_table.number_of_table_entries > MAX_CONFIGURATION_TABLE_ENTRIES
+    {
+        Err(efi::Status::INVALID_PARAMETER)?;
+    }
+    let entries = slice::from_raw_parts(system_table.configuration_table, system_table.number_of_table_entries);
+    let entry = entries.iter().find(|entry| entry.vendor_guid == *guid).ok_or(efi::Status::NOT_FOUND)?;
+    Ok(entry.vendor_table)
+}
+
+/// Locates the HOB list in the EFI configuration table of `system_table` and discovers its HOBs.
+///
+/// Returns `INVALID_PARAMETER` if `system_table` is null; the errors of [`find_configuration_table`] for
+/// [`known_tables::HOB_LIST_GUID`]; and `VOLUME_CORRUPTED` if the entry does not point to a HOB list that starts
+/// with a PHIT HOB.
+///
+/// ## Safety
+/// Caller must ensure that `system_table` and its configuration table are valid, and that the HOB list they point to
+/// is valid for `'a`.
+///
+/// # Example(s)
+///
+/// ```no_run
+/// use mu_pi::hob;
+/// use r_efi::efi;
+///
+/// fn example(system_table: *const efi::SystemTable) -> Result<(), efi::Status> {
+///     let hob_list = unsafe { hob::locate_hob_list(system_table) }?;
+///     println!("{:?}", hob_list);
+///     Ok(())
+/// }
+/// ```
+pub unsafe fn locate_hob_list<'a>(
+    system_table: *const r_efi::efi::SystemTable,
+) -> Result<HobList<'a>, r_efi::efi::Status> {
+    use r_efi::efi;
+
+    let system_table = system_table.as_ref().ok_or(efi::Status::INVALID_PARAMETER)?;
+    let table = find_configuration_table(system_table, &known_tables::HOB_LIST_GUID)?;
+
+    let phit = (table as *const header::Hob).as_ref().ok_or(efi::Status::VOLUME_CORRUPTED)?;
+    if phit.r#type != HANDOFF || phit.length as usize != size_of::<PhaseHandoffInformationTable>() {
+        Err(efi::Status::VOLUME_CORRUPTED)?;
+    }
+
+    let mut hob_list = HobList::new();
+    hob_list.discover_hobs(table);
+    Ok(hob_list)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
+        boot_mode::BootMode,
         hob,
         hob::{Hob, HobList, HobTrait},
     };
 
     use core::{
         ffi::c_void,
-        mem::{drop, forget, size_of},
+        mem::{self, drop, forget, size_of},
+        ptr,
         slice::from_raw_parts,
     };
 
@@ -1607,4 +1736,77 @@ mod tests {
 
         manually_free_c_array(c_array_hoblist, length);
     }
+
+    #[test]
+    fn test_phit_boot_mode() {
+        let mut handoff = gen_phase_handoff_information_table();
+        assert_eq!(handoff.boot_mode(), BootMode::FullConfiguration);
+
+        handoff.boot_mode = 0x11;
+        assert_eq!(handoff.boot_mode(), BootMode::S3Resume);
+        assert!(handoff.boot_mode().is_s3_resume());
+
+        handoff.boot_mode = 0xF000;
+        assert_eq!(handoff.boot_mode(), BootMode::Unknown(0xF000));
+    }
+
+    #[test]
+    fn test_locate_hob_list() {
+        let handoff = gen_phase_handoff_information_table();
+        let resource = gen_resource_descriptor();
+        let cpu = gen_cpu();
+        let end_of_hob_list = gen_end_of_hoblist();
+
+        let mut hoblist = HobList::new();
+        hoblist.push(Hob::Handoff(&handoff));
+        hoblist.push(Hob::ResourceDescriptor(&resource));
+        hoblist.push(Hob::Cpu(&cpu));
+        hoblist.push(Hob::Handoff(&end_of_hob_list));
+        let (c_array_hoblist, length) = to_c_array(&hoblist);
+
+        let mut dxe_services = [0u8; 8];
+        let mut memory_type_information = [0u8; 8];
+        let other = r_efi::efi::Guid::from_fields(1, 2, 3, 4, 5, &[6, 7, 8, 9, 10, 11]);
+        let entry = |vendor_guid, vendor_table: *mut u8| r_efi::efi::ConfigurationTable {
+            vendor_guid,
+            vendor_table: vendor_table as *mut c_void,
+        };
+        let hob_entry = entry(hob::known_tables::HOB_LIST_GUID, c_array_hoblist as *mut u8);
+        let dxe_entry = entry(hob::known_tables::DXE_SERVICES_TABLE_GUID, dxe_services.as_mut_ptr());
+        let memory_type_entry =
+            entry(hob::known_tables::MEMORY_TYPE_INFORMATION_GUID, memory_type_information.as_mut_ptr());
+        let other_entry = entry(other, ptr::null_mut());
+
+        let orders = [
+            [hob_entry, dxe_entry, memory_type_entry, other_entry],
+            [dxe_entry, memory_type_entry, hob_entry, other_entry],
+            [other_entry, memory_type_entry, dxe_entry, hob_entry],
+        ];
+        let mut system_table: r_efi::efi::SystemTable = unsafe { mem::zeroed() };
+        for mut entries in orders {
+            system_table.number_of_table_entries = entries.len();
+            system_table.configuration_table = entries.as_mut_ptr();
+            let located = unsafe { hob::locate_hob_list(&system_table) }.unwrap();
+            assert_eq!(located.len(), 3);
+            assert!(matches!(located.iter().next(), Some(Hob::Handoff(phit)) if phit.header.r#type == hob::HANDOFF));
+            assert!(matches!(located.iter().nth(2), Some(Hob::Cpu(_))));
+        }
+
+        // Without the HOB list entry, or with a corrupt entry count.
+        let mut entries = [dxe_entry, memory_type_entry, other_entry];
+        system_table.number_of_table_entries = entries.len();
+        system_table.configuration_table = entries.as_mut_ptr();
+        assert_eq!(unsafe { hob::locate_hob_list(&system_table) }.unwrap_err(), r_efi::efi::Status::NOT_FOUND);
+        system_table.number_of_table_entries = usize::MAX;
+        assert_eq!(unsafe { hob::locate_hob_list(&system_table) }.unwrap_err(), r_efi::efi::Status::INVALID_PARAMETER);
+        assert_eq!(unsafe { hob::locate_hob_list(ptr::null()) }.unwrap_err(), r_efi::efi::Status::INVALID_PARAMETER);
+
+        // An entry that does not point to a PHIT HOB.
+        let mut entries = [entry(hob::known_tables::HOB_LIST_GUID, dxe_services.as_mut_ptr())];
+        system_table.number_of_table_entries = entries.len();
+        system_table.configuration_table = entries.as_mut_ptr();
+        assert_eq!(unsafe { hob::locate_hob_list(&system_table) }.unwrap_err(), r_efi::efi::Status::VOLUME_CORRUPTED);
+
+        manually_free_c_array(c_array_hoblist, length);
+    }
 }
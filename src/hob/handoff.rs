@@ -0,0 +1,357 @@
+//! PEI to DXE Handoff Checks
+//!
+//! Verifies that the HOB list handed off by PEI is consistent before the DXE core relies on it, and summarizes the
+//! values the DXE core needs from it.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V3_HOB_Design_Discussion.html#hob-list>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::fmt;
+
+use r_efi::efi;
+
+use crate::{
+    boot_mode::BootMode,
+    hob::{physical_address, Hob, HobList, PhaseHandoffInformationTable, EFI_RESOURCE_SYSTEM_MEMORY},
+};
+
+/// Name of the memory allocation module HOB that describes the DXE core (gEfiHobMemoryAllocModuleGuid).
+pub const MEMORY_ALLOC_MODULE_GUID: efi::Guid =
+    efi::Guid::from_fields(0xf8e21975, 0x0899, 0x4f58, 0xa4, 0xbe, &[0x55, 0x25, 0xa9, 0xc6, 0xd7, 0x7a]);
+
+/// The values the DXE core needs from a handoff that passed [`handoff_check`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HandoffSummary {
+    /// The boot mode of the PHIT HOB.
+    pub boot_mode: BootMode,
+    /// The total length of the system memory resource descriptor HOBs.
+    pub memory_size: u64,
+    /// The physical address width of the CPU HOB.
+    pub address_width: u8,
+    /// The base address of the boot firmware volume, the first firmware volume HOB of the list.
+    pub bfv_base: u64,
+    /// The length of the boot firmware volume.
+    pub bfv_length: u64,
+    /// The entry point of the DXE core, if the list has a memory allocation module HOB for it.
+    pub dxe_core_entry: Option<u64>,
+}
+
+/// Errors reported by [`handoff_check`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandoffError {
+    /// The list does not start with a PHIT HOB.
+    MissingPhit,
+    /// The memory ranges of the PHIT HOB are not nested within each other.
+    InvalidPhit,
+    /// The boot mode of the PHIT HOB is not defined by the specification. The boot mode is given.
+    UnknownBootMode(u32),
+    /// The list has no CPU HOB.
+    MissingCpu,
+    /// The list has no system memory resource descriptor HOB.
+    MissingSystemMemory,
+    /// The DXE core allocation is not covered by a system memory resource descriptor HOB. The allocation base is
+    /// given.
+    DxeCoreOutsideSystemMemory(u64),
+    /// The list has no firmware volume HOB for the boot firmware volume.
+    MissingBfv,
+}
+
+impl fmt::Display for HandoffError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HandoffError::MissingPhit => write!(f, "HOB list does not start with a PHIT HOB"),
+            HandoffError::InvalidPhit => write!(f, "PHIT HOB memory ranges are inconsistent"),
+            HandoffError::UnknownBootMode(mode) => write!(f, "unknown boot mode {mode:#x}"),
+            HandoffError::MissingCpu => write!(f, "HOB list has no CPU HOB"),
+            HandoffError::MissingSystemMemory => write!(f, "HOB list has no system memory resource descriptor"),
+            HandoffError::DxeCoreOutsideSystemMemory(base) => {
+                write!(f, "DXE core allocation at {base:#x} is not in system memory")
+            }
+            HandoffError::MissingBfv => write!(f, "HOB list has no boot firmware volume HOB"),
+        }
+    }
+}
+
+fn phit_is_valid(phit: &PhaseHandoffInformationTable) -> bool {
+    phit.memory_bottom <= phit.free_memory_bottom
+        && phit.free_memory_bottom <= phit.free_memory_top
+        && phit.free_memory_top <= phit.memory_top
+}
+
+// Returns the base and length of each system memory resource descriptor HOB.
+fn system_memory<'a>(list: &'a HobList) -> impl Iterator<Item = (u64, u64)> + 'a {
+    list.iter().filter_map(|hob| match hob {
+        Hob::ResourceDescriptor(resource) if resource.resource_type == EFI_RESOURCE_SYSTEM_MEMORY => {
+            Some((physical_address(resource.physical_start), resource.resource_length))
+        }
+        _ => None,
+    })
+}
+
+/// Returns true if a single system memory resource descriptor HOB of `list` covers the `length` bytes at `base`,
+/// as memory PEI describes in other HOBs must be.
+pub fn system_memory_covers(list: &HobList, base: u64, length: u64) -> bool {
+    base.checked_add(length).is_some_and(|end| {
+        system_memory(list)
+            .any(|(start, size)| start <= base && start.checked_add(size).is_some_and(|limit| end <= limit))
+    })
+}
+
+/// Checks that the handoff in `list` is consistent, and summarizes it.
+///
+/// The list must start with a valid PHIT HOB with a boot mode defined by the specification, and must contain a CPU
+/// HOB, a firmware volume HOB for the boot firmware volume, and at least one system memory resource descriptor HOB.
+/// If the list has a memory allocation module HOB for the DXE core, a system memory resource descriptor HOB must
+/// cover the whole allocation.
+///
+/// # Example(s)
+///
+/// ```no_run
+/// use core::ffi::c_void;
+/// use mu_pi::hob::{self, HobList};
+///
+/// fn example(hob_list: *const c_void) {
+///     let mut the_hob_list = HobList::default();
+///     the_hob_list.discover_hobs(hob_list);
+///     match hob::handoff_check(&the_hob_list) {
+///         Ok(summary) => println!("booting in {} with {:#x} bytes of memory", summary.boot_mode, summary.memory_size),
+///         Err(error) => panic!("invalid handoff: {error}"),
+///     }
+/// }
+/// ```
+pub fn handoff_check(list: &HobList) -> Result<HandoffSummary, HandoffError> {
+    let phit = match list.iter().next() {
+        Some(Hob::Handoff(phit)) => phit,
+        _ => Err(HandoffError::MissingPhit)?,
+    };
+    if !phit_is_valid(phit) {
+        Err(HandoffError::InvalidPhit)?;
+    }
+    let boot_mode = phit.boot_mode();
+    if let BootMode::Unknown(mode) = boot_mode {
+        Err(HandoffError::UnknownBootMode(mode))?;
+    }
+
+    let address_width = list
+        .iter()
+        .find_map(|hob| match hob {
+            Hob::Cpu(cpu) => Some(cpu.size_of_memory_space),
+            _ => None,
+        })
+        .ok_or(HandoffError::MissingCpu)?;
+
+    if system_memory(list).next().is_none() {
+        Err(HandoffError::MissingSystemMemory)?;
+    }
+    let memory_size = system_memory(list).fold(0u64, |size, (_, length)| size.saturating_add(length));
+
+    let dxe_core = list.iter().find_map(|hob| match hob {
+        Hob::MemoryAllocationModule(module) if module.alloc_descriptor.name == MEMORY_ALLOC_MODULE_GUID => Some(module),
+        _ => None,
+    });
+    if let Some(module) = dxe_core {
+        let base = physical_address(module.alloc_descriptor.memory_base_address);
+        if !system_memory_covers(list, base, module.alloc_descriptor.memory_length) {
+            Err(HandoffError::DxeCoreOutsideSystemMemory(base))?;
+        }
+    }
+
+    let (bfv_base, bfv_length) = list
+        .iter()
+        .find_map(|hob| match hob {
+            Hob::FirmwareVolume(fv) => Some((physical_address(fv.base_address), fv.length)),
+            _ => None,
+        })
+        .ok_or(HandoffError::MissingBfv)?;
+
+    Ok(HandoffSummary {
+        boot_mode,
+        memory_size,
+        address_width,
+        bfv_base,
+        bfv_length,
+        dxe_core_entry: dxe_core.map(|module| module.entry_point),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::size_of;
+
+    use crate::{
+        boot_mode::BootMode,
+        hob::{self, Hob, HobList},
+    };
+
+    use super::{handoff_check, HandoffError, HandoffSummary, MEMORY_ALLOC_MODULE_GUID};
+
+    fn header<T>(r#type: u16) -> hob::header::Hob {
+        hob::header::Hob { r#type, length: size_of::<T>() as u16, reserved: 0 }
+    }
+
+    fn phit(boot_mode: u32) -> hob::PhaseHandoffInformationTable {
+        hob::PhaseHandoffInformationTable {
+            header: header::<hob::PhaseHandoffInformationTable>(hob::HANDOFF),
+            version: 0x0009,
+            boot_mode,
+            memory_top: 0x8000_0000,
+            memory_bottom: 0x7000_0000,
+            free_memory_top: 0x7800_0000,
+            free_memory_bottom: 0x7100_0000,
+            end_of_hob_list: 0x7000_1000,
+        }
+    }
+
+    fn cpu() -> hob::Cpu {
+        hob::Cpu {
+            header: header::<hob::Cpu>(hob::CPU),
+            size_of_memory_space: 39,
+            size_of_io_space: 16,
+            reserved: [0; 6],
+        }
+    }
+
+    fn system_memory(physical_start: hob::EfiPhysicalAddress, resource_length: u64) -> hob::ResourceDescriptor {
+        hob::ResourceDescriptor {
+            header: header::<hob::ResourceDescriptor>(hob::RESOURCE_DESCRIPTOR),
+            owner: r_efi::efi::Guid::from_fields(0, 0, 0, 0, 0, &[0; 6]),
+            resource_type: hob::EFI_RESOURCE_SYSTEM_MEMORY,
+            resource_attribute: hob::TESTED_MEMORY_ATTRIBUTES,
+            physical_start,
+            resource_length,
+        }
+    }
+
+    fn bfv() -> hob::FirmwareVolume {
+        hob::FirmwareVolume {
+            header: header::<hob::FirmwareVolume>(hob::FV),
+            base_address: 0xFF00_0000,
+            length: 0x100_0000,
+        }
+    }
+
+    fn dxe_core(memory_base_address: hob::EfiPhysicalAddress) -> hob::MemoryAllocationModule {
+        hob::MemoryAllocationModule {
+            header: header::<hob::MemoryAllocationModule>(hob::MEMORY_ALLOCATION),
+            alloc_descriptor: hob::header::MemoryAllocation {
+                name: MEMORY_ALLOC_MODULE_GUID,
+                memory_base_address,
+                memory_length: 0x4_0000,
+                memory_type: r_efi::efi::BOOT_SERVICES_CODE,
+                reserved: [0; 4],
+            },
+            module_name: r_efi::efi::Guid::from_fields(1, 2, 3, 4, 5, &[6, 7, 8, 9, 10, 11]),
+            entry_point: hob::physical_address(memory_base_address) + 0x1000,
+        }
+    }
+
+    #[test]
+    fn handoff_check_should_summarize_a_valid_handoff() {
+        let phit = phit(0);
+        let cpu = cpu();
+        let low_memory = system_memory(0, 0xA_0000);
+        let high_memory = system_memory(0x10_0000, 0x7FF0_0000);
+        let bfv = bfv();
+        let dxe_core = dxe_core(0x7F00_0000);
+
+        let mut list = HobList::new();
+        list.push(Hob::Handoff(&phit));
+        list.push(Hob::ResourceDescriptor(&low_memory));
+        list.push(Hob::ResourceDescriptor(&high_memory));
+        list.push(Hob::FirmwareVolume(&bfv));
+        list.push(Hob::MemoryAllocationModule(&dxe_core));
+        list.push(Hob::Cpu(&cpu));
+
+        assert_eq!(
+            handoff_check(&list),
+            Ok(HandoffSummary {
+                boot_mode: BootMode::FullConfiguration,
+                memory_size: 0x7FFA_0000,
+                address_width: 39,
+                bfv_base: 0xFF00_0000,
+                bfv_length: 0x100_0000,
+                dxe_core_entry: Some(0x7F00_1000),
+            })
+        );
+    }
+
+    #[test]
+    fn handoff_check_should_accept_s3_resume() {
+        let phit = phit(0x11);
+        let cpu = cpu();
+        let memory = system_memory(0, 0x8000_0000);
+        let bfv = bfv();
+
+        let mut list = HobList::new();
+        list.push(Hob::Handoff(&phit));
+        list.push(Hob::Cpu(&cpu));
+        list.push(Hob::ResourceDescriptor(&memory));
+        list.push(Hob::FirmwareVolume(&bfv));
+
+        let summary = handoff_check(&list).unwrap();
+        assert_eq!(summary.boot_mode, BootMode::S3Resume);
+        assert_eq!(summary.dxe_core_entry, None);
+    }
+
+    #[test]
+    fn handoff_check_should_report_the_inconsistency() {
+        let mut phit = phit(0);
+        let cpu = cpu();
+        let memory = system_memory(0, 0x8000_0000);
+        let bfv = bfv();
+        let covered_dxe_core = dxe_core(0x7F00_0000);
+        let straddling_dxe_core = dxe_core(0x7FFF_0000);
+
+        let check = |hobs: &[Hob]| {
+            let mut list = HobList::new();
+            hobs.iter().for_each(|hob| list.push(hob.clone()));
+            handoff_check(&list)
+        };
+
+        assert_eq!(check(&[]), Err(HandoffError::MissingPhit));
+        assert_eq!(check(&[Hob::Cpu(&cpu), Hob::Handoff(&phit)]), Err(HandoffError::MissingPhit));
+        // The handoff without a CPU HOB.
+        assert_eq!(
+            check(&[Hob::Handoff(&phit), Hob::ResourceDescriptor(&memory), Hob::FirmwareVolume(&bfv)]),
+            Err(HandoffError::MissingCpu)
+        );
+        assert_eq!(
+            check(&[Hob::Handoff(&phit), Hob::Cpu(&cpu), Hob::FirmwareVolume(&bfv)]),
+            Err(HandoffError::MissingSystemMemory)
+        );
+        assert_eq!(
+            check(&[Hob::Handoff(&phit), Hob::Cpu(&cpu), Hob::ResourceDescriptor(&memory)]),
+            Err(HandoffError::MissingBfv)
+        );
+        assert!(check(&[
+            Hob::Handoff(&phit),
+            Hob::Cpu(&cpu),
+            Hob::ResourceDescriptor(&memory),
+            Hob::FirmwareVolume(&bfv),
+            Hob::MemoryAllocationModule(&covered_dxe_core)
+        ])
+        .is_ok());
+        assert_eq!(
+            check(&[
+                Hob::Handoff(&phit),
+                Hob::Cpu(&cpu),
+                Hob::ResourceDescriptor(&memory),
+                Hob::FirmwareVolume(&bfv),
+                Hob::MemoryAllocationModule(&straddling_dxe_core)
+            ]),
+            Err(HandoffError::DxeCoreOutsideSystemMemory(0x7FFF_0000))
+        );
+
+        phit.boot_mode = 0x30;
+        assert_eq!(check(&[Hob::Handoff(&phit)]), Err(HandoffError::UnknownBootMode(0x30)));
+        phit.boot_mode = 0;
+        phit.free_memory_top = phit.memory_top + 1;
+        assert_eq!(check(&[Hob::Handoff(&phit)]), Err(HandoffError::InvalidPhit));
+    }
+}
@@ -30,9 +30,46 @@
 #![cfg_attr(not(test), no_std)]
 #![cfg_attr(feature = "nightly", feature(coverage_attribute))]
 
+// Offset of a field within a structure, for the layout tests of the spec-defined structures.
+#[cfg(test)]
+macro_rules! offset_of {
+    ($type:ty, $field:ident) => {{
+        let value = core::mem::MaybeUninit::<$type>::uninit();
+        let base = value.as_ptr();
+        unsafe { core::ptr::addr_of!((*base).$field) as usize - base as usize }
+    }};
+}
+
+pub mod acpi;
 mod address_helper;
+pub mod boot_mode;
+pub mod boot_script;
+pub mod cache;
+pub mod capsule;
+pub mod console;
+pub mod cpu;
+pub mod device_path;
+pub mod dxe;
 pub mod dxe_services;
 pub mod fw_fs;
+pub mod graphics;
+pub mod guids;
 pub mod hob;
 pub mod list_entry;
+pub mod macros;
+pub mod mem_attr;
+pub mod nvdimm;
+pub mod panic;
+pub mod pei;
+pub mod power;
 pub mod protocols;
+pub mod reset;
+pub mod s3;
+pub mod secure_boot;
+pub mod smm;
+pub mod status;
+pub mod status_code;
+pub mod switch_stack;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod variables;
@@ -0,0 +1,273 @@
+//! Console Print and Status Macros
+//!
+//! [`print!`](crate::print), [`println!`](crate::println) and [`DEBUG!`](crate::DEBUG) macros that format their
+//! arguments with `core::fmt` and write them to a global ConOut (a Simple Text Output Protocol instance) set with
+//! [`set_con_out`], for debugging drivers before an operating system is running.
+//!
+//! [`assert_efi_status!`](crate::assert_efi_status), [`unwrap_efi_status!`](crate::unwrap_efi_status) and
+//! [`efi_try!`](crate::efi_try) macros that propagate an `efi::Status` other than SUCCESS returned by an EFI API call,
+//! either as the status itself, as a panic, or as the error of a `Result`.
+//!
+//! ## Example
+//!```no_run
+//! use mu_pi::{macros::set_con_out, println, DEBUG};
+//! use r_efi::efi;
+//!
+//! fn entry(system_table: &efi::SystemTable) {
+//!   //Safety: the ConOut of the system table stays valid until ExitBootServices().
+//!   unsafe { set_con_out(system_table.con_out as *const _) };
+//!   println!("Hello from {}!", "DXE");
+//!   DEBUG!("only printed in debug builds: {:#x}\n", 0x1234);
+//! }
+//!```
+//!
+//!```
+//! use mu_pi::{assert_efi_status, efi_try};
+//! use r_efi::efi;
+//!
+//! fn reset_device() -> efi::Status {
+//!   efi::Status::DEVICE_ERROR
+//! }
+//!
+//! fn start() -> efi::Status {
+//!   assert_efi_status!(reset_device());
+//!   efi::Status::SUCCESS
+//! }
+//!
+//! fn start_result() -> Result<u32, efi::Status> {
+//!   efi_try!(reset_device());
+//!   Ok(0)
+//! }
+//!
+//! assert_eq!(start(), efi::Status::DEVICE_ERROR);
+//! assert_eq!(start_result(), Err(efi::Status::DEVICE_ERROR));
+//!```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{
+    fmt::{self, Write},
+    ptr,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+use r_efi::efi;
+
+use crate::{console::TextOutput, protocols::simple_text_output::Protocol as SimpleTextOutputProtocol};
+
+static CON_OUT: AtomicPtr<SimpleTextOutputProtocol> = AtomicPtr::new(ptr::null_mut());
+
+/// Sets the ConOut used by the print macros. A null `proto` disables printing, which is the initial state.
+///
+/// # Safety
+/// `proto` must be null or point to a valid Simple Text Output Protocol instance until it is replaced, e.g. the ConOut
+/// of the system table until ExitBootServices().
+pub unsafe fn set_con_out(proto: *const SimpleTextOutputProtocol) {
+    CON_OUT.store(proto as *mut _, Ordering::Release);
+}
+
+#[doc(hidden)]
+pub fn _print(args: fmt::Arguments) {
+    let proto = CON_OUT.load(Ordering::Acquire);
+    if proto.is_null() {
+        return;
+    }
+    //Safety: set_con_out() callers guarantee the pointer stays valid until it is replaced. Output is converted to UCS-2 in a
+    //stack buffer by TextOutput; device errors are ignored, as there is nowhere to report them.
+    let _ = unsafe { TextOutput::new(proto) }.write_fmt(args);
+}
+
+/// Prints to the ConOut set with [`set_con_out`](crate::macros::set_con_out).
+#[macro_export]
+macro_rules! print {
+    ($($arg:tt)*) => {
+        $crate::macros::_print(format_args!($($arg)*))
+    };
+}
+
+/// Prints to the ConOut set with [`set_con_out`](crate::macros::set_con_out), with a newline.
+#[macro_export]
+macro_rules! println {
+    () => {
+        $crate::print!("\n")
+    };
+    ($($arg:tt)*) => {
+        $crate::macros::_print(format_args!("{}\n", format_args!($($arg)*)))
+    };
+}
+
+/// Prints to the ConOut set with [`set_con_out`](crate::macros::set_con_out) in builds with debug assertions, and
+/// does nothing otherwise. The arguments are type checked in every build.
+#[macro_export]
+macro_rules! DEBUG {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            $crate::print!($($arg)*)
+        }
+    };
+}
+
+#[doc(hidden)]
+pub fn _status_result(status: efi::Status) -> Result<(), efi::Status> {
+    if status == efi::Status::SUCCESS {
+        Ok(())
+    } else {
+        Err(status)
+    }
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn _status_failed(status: efi::Status, expression: &str) -> ! {
+    panic!("`{expression}` failed with status {:#x}", status.as_usize())
+}
+
+/// Returns the `efi::Status` produced by an expression from the enclosing function unless it is SUCCESS.
+///
+/// Warnings are returned as well. The enclosing function must return `efi::Status`.
+#[macro_export]
+macro_rules! assert_efi_status {
+    ($expr:expr $(,)?) => {
+        if let Err(status) = $crate::macros::_status_result($expr) {
+            return status;
+        }
+    };
+}
+
+/// Panics unless the `efi::Status` produced by an expression is SUCCESS.
+///
+/// The panic goes to the panic handler of the firmware, with the expression and status as the message.
+#[macro_export]
+macro_rules! unwrap_efi_status {
+    ($expr:expr $(,)?) => {
+        if let Err(status) = $crate::macros::_status_result($expr) {
+            $crate::macros::_status_failed(status, stringify!($expr))
+        }
+    };
+}
+
+/// Converts the `efi::Status` produced by an expression to `Result<(), efi::Status>` and applies `?` to it, returning
+/// any status other than SUCCESS as the error of the enclosing function.
+///
+/// The error type of the enclosing function must implement `From<efi::Status>`.
+#[macro_export]
+macro_rules! efi_try {
+    ($expr:expr $(,)?) => {
+        $crate::macros::_status_result($expr)?
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    use alloc::boxed::Box;
+    use core::ptr;
+    use std::sync::{Mutex, MutexGuard};
+
+    use r_efi::efi;
+
+    use super::set_con_out;
+    use crate::console::text_output::tests::{mock_protocol, take_output};
+
+    // ConOut is global, so tests that replace it must not run concurrently.
+    static CON_OUT_LOCK: Mutex<()> = Mutex::new(());
+
+    fn install_mock() -> MutexGuard<'static, ()> {
+        let guard = CON_OUT_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        unsafe { set_con_out(Box::leak(Box::new(mock_protocol()))) };
+        guard
+    }
+
+    #[test]
+    fn print_macros_should_write_to_con_out() {
+        let _guard = install_mock();
+        crate::print!("{}-{:#x}", "DXE", 0x1234);
+        crate::println!();
+        crate::println!("{:>4}|", 42);
+        assert_eq!(take_output(), "DXE-0x1234\r\n  42|\r\n");
+
+        let long = "x".repeat(300);
+        crate::print!("{long}");
+        assert_eq!(take_output(), long);
+    }
+
+    #[test]
+    fn debug_macro_should_only_print_with_debug_assertions() {
+        let _guard = install_mock();
+        crate::DEBUG!("value {}\n", 7);
+        if cfg!(debug_assertions) {
+            assert_eq!(take_output(), "value 7\r\n");
+        } else {
+            assert_eq!(take_output(), "");
+        }
+    }
+
+    #[test]
+    fn print_should_be_dropped_without_con_out() {
+        let _guard = install_mock();
+        unsafe { set_con_out(ptr::null()) };
+        crate::print!("dropped");
+        assert_eq!(take_output(), "");
+    }
+
+    fn checked(status: efi::Status, reached: &mut bool) -> efi::Status {
+        crate::assert_efi_status!(status);
+        *reached = true;
+        efi::Status::SUCCESS
+    }
+
+    #[test]
+    fn assert_efi_status_should_return_failures() {
+        let mut reached = false;
+        assert_eq!(checked(efi::Status::SUCCESS, &mut reached), efi::Status::SUCCESS);
+        assert!(reached);
+
+        for status in [efi::Status::NOT_FOUND, efi::Status::WARN_BUFFER_TOO_SMALL] {
+            let mut reached = false;
+            assert_eq!(checked(status, &mut reached), status);
+            assert!(!reached);
+        }
+    }
+
+    #[test]
+    fn unwrap_efi_status_should_panic_on_failure() {
+        crate::unwrap_efi_status!(efi::Status::SUCCESS);
+
+        let result = std::panic::catch_unwind(|| crate::unwrap_efi_status!(efi::Status::DEVICE_ERROR));
+        let message = *result.unwrap_err().downcast::<String>().unwrap();
+        assert_eq!(message, "`efi::Status::DEVICE_ERROR` failed with status 0x8000000000000007");
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum DriverError {
+        Efi(efi::Status),
+    }
+
+    impl From<efi::Status> for DriverError {
+        fn from(status: efi::Status) -> Self {
+            DriverError::Efi(status)
+        }
+    }
+
+    #[test]
+    fn efi_try_should_propagate_failures() {
+        fn start(status: efi::Status) -> Result<u32, efi::Status> {
+            crate::efi_try!(status);
+            Ok(7)
+        }
+        fn start_driver(status: efi::Status) -> Result<(), DriverError> {
+            crate::efi_try!(status);
+            Ok(())
+        }
+
+        assert_eq!(start(efi::Status::SUCCESS), Ok(7));
+        assert_eq!(start(efi::Status::ABORTED), Err(efi::Status::ABORTED));
+        assert_eq!(start_driver(efi::Status::SUCCESS), Ok(()));
+        assert_eq!(start_driver(efi::Status::UNSUPPORTED), Err(DriverError::Efi(efi::Status::UNSUPPORTED)));
+    }
+}
@@ -0,0 +1,470 @@
+//! EFI Memory Attributes
+//!
+//! The `EFI_MEMORY_*` attribute bits shared by the UEFI memory map, the GCD memory space services, and the CPU
+//! Architectural Protocol, and their conversion to and from the resource attributes of resource descriptor HOBs.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/07_Services_Boot_Services.html#efi-boot-services-getmemorymap>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{
+    fmt,
+    ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not},
+};
+
+use r_efi::efi;
+
+use crate::hob::{
+    ResourceAttributes, EFI_MEMORY_INITIALIZED, EFI_MEMORY_MORE_RELIABLE, EFI_MEMORY_NV, EFI_MEMORY_PRESENT,
+    EFI_MEMORY_TESTED, EFI_RESOURCE_ATTRIBUTE_EXECUTION_PROTECTABLE, EFI_RESOURCE_ATTRIBUTE_INITIALIZED,
+    EFI_RESOURCE_ATTRIBUTE_MORE_RELIABLE, EFI_RESOURCE_ATTRIBUTE_PERSISTABLE, EFI_RESOURCE_ATTRIBUTE_PRESENT,
+    EFI_RESOURCE_ATTRIBUTE_READ_ONLY_PROTECTABLE, EFI_RESOURCE_ATTRIBUTE_READ_PROTECTABLE,
+    EFI_RESOURCE_ATTRIBUTE_TESTED, EFI_RESOURCE_ATTRIBUTE_UNCACHEABLE, EFI_RESOURCE_ATTRIBUTE_UNCACHED_EXPORTED,
+    EFI_RESOURCE_ATTRIBUTE_WRITE_BACK_CACHEABLE, EFI_RESOURCE_ATTRIBUTE_WRITE_COMBINEABLE,
+    EFI_RESOURCE_ATTRIBUTE_WRITE_PROTECTABLE, EFI_RESOURCE_ATTRIBUTE_WRITE_THROUGH_CACHEABLE,
+};
+
+/// A set of `EFI_MEMORY_*` attribute bits.
+///
+/// The same bits describe both the attributes a memory region is using and the capabilities it supports, e.g. in
+/// [`MemorySpaceDescriptor`](crate::dxe_services::MemorySpaceDescriptor). The type is layout compatible with the
+/// `UINT64` of the specification.
+#[repr(transparent)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct EfiMemoryAttributes(u64);
+
+impl EfiMemoryAttributes {
+    /// Uncacheable.
+    pub const UC: Self = Self(efi::MEMORY_UC);
+    /// Write combining.
+    pub const WC: Self = Self(efi::MEMORY_WC);
+    /// Write through.
+    pub const WT: Self = Self(efi::MEMORY_WT);
+    /// Write back.
+    pub const WB: Self = Self(efi::MEMORY_WB);
+    /// Uncacheable, exported, supporting the "fetch and add" semaphore mechanism.
+    pub const UCE: Self = Self(efi::MEMORY_UCE);
+    /// Write protected (a cacheability attribute on some processors).
+    pub const WP: Self = Self(efi::MEMORY_WP);
+    /// Read protected.
+    pub const RP: Self = Self(efi::MEMORY_RP);
+    /// Execution protected.
+    pub const XP: Self = Self(efi::MEMORY_XP);
+    /// Persistent (byte-addressable non-volatile) memory.
+    pub const NV: Self = Self(EFI_MEMORY_NV);
+    /// Memory with higher reliability relative to other memory in the system.
+    pub const MORE_RELIABLE: Self = Self(EFI_MEMORY_MORE_RELIABLE);
+    /// Read only.
+    pub const RO: Self = Self(efi::MEMORY_RO);
+    /// Specific-purpose memory.
+    pub const SP: Self = Self(0x0000_0000_0004_0000);
+    /// Memory that can be protected with the CPU's memory cryptographic capabilities.
+    pub const CPU_CRYPTO: Self = Self(0x0000_0000_0008_0000);
+    /// Memory that must be mapped by the OS for runtime services.
+    pub const RUNTIME: Self = Self(efi::MEMORY_RUNTIME);
+    /// GCD only: the memory is present. Reserved memory in this state may be promoted to system memory.
+    pub const PRESENT: Self = Self(EFI_MEMORY_PRESENT);
+    /// GCD only: the memory is initialized.
+    pub const INITIALIZED: Self = Self(EFI_MEMORY_INITIALIZED);
+    /// GCD only: the memory is tested.
+    pub const TESTED: Self = Self(EFI_MEMORY_TESTED);
+
+    /// The cacheability attributes, of which a region may use at most one at a time.
+    pub const CACHE_MASK: Self =
+        Self(efi::MEMORY_UC | efi::MEMORY_WC | efi::MEMORY_WT | efi::MEMORY_WB | efi::MEMORY_UCE);
+    /// The memory protection attributes.
+    pub const ACCESS_MASK: Self = Self(efi::MEMORY_WP | efi::MEMORY_RP | efi::MEMORY_XP | efi::MEMORY_RO);
+    /// Every attribute defined by the specifications.
+    pub const ALL: Self = Self(
+        Self::CACHE_MASK.0
+            | Self::ACCESS_MASK.0
+            | EFI_MEMORY_NV
+            | EFI_MEMORY_MORE_RELIABLE
+            | Self::SP.0
+            | Self::CPU_CRYPTO.0
+            | efi::MEMORY_RUNTIME
+            | EFI_MEMORY_PRESENT
+            | EFI_MEMORY_INITIALIZED
+            | EFI_MEMORY_TESTED,
+    );
+
+    const NAMES: &'static [(Self, &'static str)] = &[
+        (Self::UC, "UC"),
+        (Self::WC, "WC"),
+        (Self::WT, "WT"),
+        (Self::WB, "WB"),
+        (Self::UCE, "UCE"),
+        (Self::WP, "WP"),
+        (Self::RP, "RP"),
+        (Self::XP, "XP"),
+        (Self::NV, "NV"),
+        (Self::MORE_RELIABLE, "MORE_RELIABLE"),
+        (Self::RO, "RO"),
+        (Self::SP, "SP"),
+        (Self::CPU_CRYPTO, "CPU_CRYPTO"),
+        (Self::PRESENT, "PRESENT"),
+        (Self::INITIALIZED, "INITIALIZED"),
+        (Self::TESTED, "TESTED"),
+        (Self::RUNTIME, "RUNTIME"),
+    ];
+
+    /// Returns the empty set of attributes.
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Converts a raw attribute mask, such as a GCD capability mask.
+    ///
+    /// Returns [`MemAttrError::UnknownBits`] with the bits that are not defined by the specifications, if any.
+    pub const fn from_bits(bits: u64) -> Result<Self, MemAttrError> {
+        if bits & !Self::ALL.0 != 0 {
+            return Err(MemAttrError::UnknownBits(bits & !Self::ALL.0));
+        }
+        Ok(Self(bits))
+    }
+
+    /// Converts a raw attribute mask, keeping the bits that are not defined by the specifications.
+    pub const fn from_bits_retain(bits: u64) -> Self {
+        Self(bits)
+    }
+
+    /// Returns the raw attribute mask.
+    pub const fn bits(self) -> u64 {
+        self.0
+    }
+
+    /// Returns true if no attribute is set.
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns true if every attribute of `other` is set.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns true if any attribute of `other` is set.
+    pub const fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// Returns the cacheability attributes.
+    pub const fn cacheability(self) -> Self {
+        Self(self.0 & Self::CACHE_MASK.0)
+    }
+
+    /// Validates attributes that are applied to a region, as with SetMemorySpaceAttributes() or the CPU
+    /// Architectural Protocol's SetMemoryAttributes().
+    ///
+    /// A region can only use one cacheability at a time, so more than one cacheability attribute is reported as
+    /// [`MemAttrError::ConflictingCacheability`]. Capabilities are not subject to this check: a region may support
+    /// several cacheabilities.
+    pub fn validate_attributes(self) -> Result<(), MemAttrError> {
+        let cacheability = self.cacheability();
+        if cacheability.0.count_ones() > 1 {
+            Err(MemAttrError::ConflictingCacheability(cacheability))?;
+        }
+        Ok(())
+    }
+
+    /// Converts the attributes of a resource descriptor HOB.
+    ///
+    /// Returns the converted attributes and the resource attributes that have no counterpart, such as the ECC and
+    /// I/O width attributes and the protected (as opposed to protectable) attributes. The PRESENT, INITIALIZED and
+    /// TESTED state attributes convert to [`Self::PRESENT`], [`Self::INITIALIZED`] and [`Self::TESTED`].
+    pub fn from_resource_attributes(attrs: ResourceAttributes) -> (Self, ResourceAttributes) {
+        RESOURCE_ATTRIBUTE_CONVERSION.iter().filter(|(attribute, _, _)| attrs & attribute != 0).fold(
+            (Self::empty(), attrs),
+            |(converted, unmapped), (attribute, memory_attribute, _)| {
+                (converted | *memory_attribute, unmapped & !attribute)
+            },
+        )
+    }
+
+    /// Converts to the attributes of a resource descriptor HOB.
+    ///
+    /// Returns the converted attributes and the attributes that have no resource attribute counterpart, such as
+    /// [`Self::SP`], [`Self::CPU_CRYPTO`] and [`Self::RUNTIME`].
+    pub fn to_resource_attributes(self) -> (ResourceAttributes, Self) {
+        RESOURCE_ATTRIBUTE_CONVERSION.iter().filter(|(_, memory_attribute, _)| self.contains(*memory_attribute)).fold(
+            (0, self),
+            |(converted, unmapped), (attribute, memory_attribute, _)| {
+                (converted | attribute, unmapped & !*memory_attribute)
+            },
+        )
+    }
+}
+
+/// Maps each resource attribute to its memory attribute. The last column is false for the attributes that describe
+/// the state of the memory rather than a capability; the DXE core does not carry those over to system memory.
+pub(crate) const RESOURCE_ATTRIBUTE_CONVERSION: &[(ResourceAttributes, EfiMemoryAttributes, bool)] = &[
+    (EFI_RESOURCE_ATTRIBUTE_UNCACHEABLE, EfiMemoryAttributes::UC, true),
+    (EFI_RESOURCE_ATTRIBUTE_UNCACHED_EXPORTED, EfiMemoryAttributes::UCE, true),
+    (EFI_RESOURCE_ATTRIBUTE_WRITE_COMBINEABLE, EfiMemoryAttributes::WC, true),
+    (EFI_RESOURCE_ATTRIBUTE_WRITE_THROUGH_CACHEABLE, EfiMemoryAttributes::WT, true),
+    (EFI_RESOURCE_ATTRIBUTE_WRITE_BACK_CACHEABLE, EfiMemoryAttributes::WB, true),
+    (EFI_RESOURCE_ATTRIBUTE_READ_PROTECTABLE, EfiMemoryAttributes::RP, true),
+    (EFI_RESOURCE_ATTRIBUTE_WRITE_PROTECTABLE, EfiMemoryAttributes::WP, true),
+    (EFI_RESOURCE_ATTRIBUTE_EXECUTION_PROTECTABLE, EfiMemoryAttributes::XP, true),
+    (EFI_RESOURCE_ATTRIBUTE_READ_ONLY_PROTECTABLE, EfiMemoryAttributes::RO, true),
+    (EFI_RESOURCE_ATTRIBUTE_PRESENT, EfiMemoryAttributes::PRESENT, false),
+    (EFI_RESOURCE_ATTRIBUTE_INITIALIZED, EfiMemoryAttributes::INITIALIZED, false),
+    (EFI_RESOURCE_ATTRIBUTE_TESTED, EfiMemoryAttributes::TESTED, false),
+    (EFI_RESOURCE_ATTRIBUTE_PERSISTABLE, EfiMemoryAttributes::NV, true),
+    (EFI_RESOURCE_ATTRIBUTE_MORE_RELIABLE, EfiMemoryAttributes::MORE_RELIABLE, true),
+];
+
+/// Errors reported when converting or validating [`EfiMemoryAttributes`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemAttrError {
+    /// The mask has bits that are not defined by the specifications. The unknown bits are given.
+    UnknownBits(u64),
+    /// More than one cacheability attribute is set. The cacheability attributes are given.
+    ConflictingCacheability(EfiMemoryAttributes),
+}
+
+impl fmt::Display for MemAttrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MemAttrError::UnknownBits(bits) => write!(f, "unknown memory attribute bits {bits:#x}"),
+            MemAttrError::ConflictingCacheability(attributes) => {
+                write!(f, "conflicting cacheability attributes {attributes:?}")
+            }
+        }
+    }
+}
+
+impl From<MemAttrError> for efi::Status {
+    fn from(_: MemAttrError) -> Self {
+        efi::Status::INVALID_PARAMETER
+    }
+}
+
+impl From<EfiMemoryAttributes> for u64 {
+    fn from(value: EfiMemoryAttributes) -> Self {
+        value.0
+    }
+}
+
+impl TryFrom<u64> for EfiMemoryAttributes {
+    type Error = MemAttrError;
+
+    fn try_from(value: u64) -> Result<Self, Self::Error> {
+        Self::from_bits(value)
+    }
+}
+
+impl BitOr for EfiMemoryAttributes {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for EfiMemoryAttributes {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAnd for EfiMemoryAttributes {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl BitAndAssign for EfiMemoryAttributes {
+    fn bitand_assign(&mut self, rhs: Self) {
+        self.0 &= rhs.0;
+    }
+}
+
+impl Not for EfiMemoryAttributes {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self(!self.0)
+    }
+}
+
+impl fmt::Debug for EfiMemoryAttributes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EfiMemoryAttributes(")?;
+        let mut separator = "";
+        for (_, name) in Self::NAMES.iter().filter(|(attribute, _)| self.contains(*attribute)) {
+            write!(f, "{separator}{name}")?;
+            separator = " | ";
+        }
+        let unknown = self.0 & !Self::ALL.0;
+        if unknown != 0 {
+            write!(f, "{separator}{unknown:#x}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EfiMemoryAttributes, MemAttrError};
+    use crate::hob;
+
+    // Every attribute transcribed with literal values, so that a mistake in a named constant is caught too.
+    const ATTRIBUTES: &[(u64, EfiMemoryAttributes)] = &[
+        (0x0000_0000_0000_0001, EfiMemoryAttributes::UC),
+        (0x0000_0000_0000_0002, EfiMemoryAttributes::WC),
+        (0x0000_0000_0000_0004, EfiMemoryAttributes::WT),
+        (0x0000_0000_0000_0008, EfiMemoryAttributes::WB),
+        (0x0000_0000_0000_0010, EfiMemoryAttributes::UCE),
+        (0x0000_0000_0000_1000, EfiMemoryAttributes::WP),
+        (0x0000_0000_0000_2000, EfiMemoryAttributes::RP),
+        (0x0000_0000_0000_4000, EfiMemoryAttributes::XP),
+        (0x0000_0000_0000_8000, EfiMemoryAttributes::NV),
+        (0x0000_0000_0001_0000, EfiMemoryAttributes::MORE_RELIABLE),
+        (0x0000_0000_0002_0000, EfiMemoryAttributes::RO),
+        (0x0000_0000_0004_0000, EfiMemoryAttributes::SP),
+        (0x0000_0000_0008_0000, EfiMemoryAttributes::CPU_CRYPTO),
+        (0x0100_0000_0000_0000, EfiMemoryAttributes::PRESENT),
+        (0x0200_0000_0000_0000, EfiMemoryAttributes::INITIALIZED),
+        (0x0400_0000_0000_0000, EfiMemoryAttributes::TESTED),
+        (0x8000_0000_0000_0000, EfiMemoryAttributes::RUNTIME),
+    ];
+
+    // Resource attribute to memory attribute, as transcribed from the DXE core's conversion table.
+    const RESOURCE_ATTRIBUTES: &[(u32, u64)] = &[
+        (0x0000_0001, 0x0100_0000_0000_0000), // PRESENT -> PRESENT
+        (0x0000_0002, 0x0200_0000_0000_0000), // INITIALIZED -> INITIALIZED
+        (0x0000_0004, 0x0400_0000_0000_0000), // TESTED -> TESTED
+        (0x0000_0400, 0x0000_0000_0000_0001), // UNCACHEABLE -> UC
+        (0x0000_0800, 0x0000_0000_0000_0002), // WRITE_COMBINEABLE -> WC
+        (0x0000_1000, 0x0000_0000_0000_0004), // WRITE_THROUGH_CACHEABLE -> WT
+        (0x0000_2000, 0x0000_0000_0000_0008), // WRITE_BACK_CACHEABLE -> WB
+        (0x0002_0000, 0x0000_0000_0000_0010), // UNCACHED_EXPORTED -> UCE
+        (0x0008_0000, 0x0000_0000_0002_0000), // READ_ONLY_PROTECTABLE -> RO
+        (0x0010_0000, 0x0000_0000_0000_2000), // READ_PROTECTABLE -> RP
+        (0x0020_0000, 0x0000_0000_0000_1000), // WRITE_PROTECTABLE -> WP
+        (0x0040_0000, 0x0000_0000_0000_4000), // EXECUTION_PROTECTABLE -> XP
+        (0x0100_0000, 0x0000_0000_0000_8000), // PERSISTABLE -> NV
+        (0x0200_0000, 0x0000_0000_0001_0000), // MORE_RELIABLE -> MORE_RELIABLE
+    ];
+
+    #[test]
+    fn each_bit_should_be_known_or_reported() {
+        for bit in 0..64 {
+            let bits = 1u64 << bit;
+            match ATTRIBUTES.iter().find(|(value, _)| *value == bits) {
+                Some((_, attribute)) => {
+                    assert_eq!(attribute.bits(), bits, "bit {bit}");
+                    assert_eq!(EfiMemoryAttributes::from_bits(bits), Ok(*attribute), "bit {bit}");
+                }
+                None => {
+                    assert_eq!(EfiMemoryAttributes::from_bits(bits), Err(MemAttrError::UnknownBits(bits)), "bit {bit}");
+                    assert_eq!(EfiMemoryAttributes::from_bits_retain(bits).bits(), bits);
+                }
+            }
+        }
+        let all = ATTRIBUTES.iter().fold(0, |all, (bits, _)| all | bits);
+        assert_eq!(EfiMemoryAttributes::ALL.bits(), all);
+        assert_eq!(EfiMemoryAttributes::try_from(all | 0x100), Err(MemAttrError::UnknownBits(0x100)));
+    }
+
+    #[test]
+    fn each_resource_attribute_bit_should_convert_or_be_reported() {
+        for bit in 0..32 {
+            let attribute = 1u32 << bit;
+            let (converted, unmapped) = EfiMemoryAttributes::from_resource_attributes(attribute);
+            match RESOURCE_ATTRIBUTES.iter().find(|(a, _)| *a == attribute) {
+                Some((_, expected)) => {
+                    assert_eq!(converted.bits(), *expected, "attribute bit {bit}");
+                    assert_eq!(unmapped, 0, "attribute bit {bit}");
+                }
+                None => {
+                    assert!(converted.is_empty(), "attribute bit {bit}");
+                    assert_eq!(unmapped, attribute, "attribute bit {bit}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn each_memory_attribute_bit_should_convert_or_be_reported() {
+        for bit in 0..64 {
+            let attribute = EfiMemoryAttributes::from_bits_retain(1u64 << bit);
+            let (converted, unmapped) = attribute.to_resource_attributes();
+            match RESOURCE_ATTRIBUTES.iter().find(|(_, m)| *m == attribute.bits()) {
+                Some((expected, _)) => {
+                    assert_eq!(converted, *expected, "attribute bit {bit}");
+                    assert!(unmapped.is_empty(), "attribute bit {bit}");
+                }
+                None => {
+                    assert_eq!(converted, 0, "attribute bit {bit}");
+                    assert_eq!(unmapped, attribute, "attribute bit {bit}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn conversions_should_round_trip() {
+        let resource = hob::TESTED_MEMORY_ATTRIBUTES
+            | hob::EFI_RESOURCE_ATTRIBUTE_UNCACHEABLE
+            | hob::EFI_RESOURCE_ATTRIBUTE_WRITE_BACK_CACHEABLE
+            | hob::EFI_RESOURCE_ATTRIBUTE_EXECUTION_PROTECTABLE
+            | hob::EFI_RESOURCE_ATTRIBUTE_READ_PROTECTED
+            | hob::EFI_RESOURCE_ATTRIBUTE_64_BIT_IO;
+        let (converted, unmapped) = EfiMemoryAttributes::from_resource_attributes(resource);
+        assert_eq!(
+            converted,
+            EfiMemoryAttributes::PRESENT
+                | EfiMemoryAttributes::INITIALIZED
+                | EfiMemoryAttributes::TESTED
+                | EfiMemoryAttributes::UC
+                | EfiMemoryAttributes::WB
+                | EfiMemoryAttributes::XP
+        );
+        let unmapped_resource = hob::EFI_RESOURCE_ATTRIBUTE_READ_PROTECTED | hob::EFI_RESOURCE_ATTRIBUTE_64_BIT_IO;
+        assert_eq!(unmapped, unmapped_resource);
+
+        let (resource_again, unmapped) = (converted | EfiMemoryAttributes::RUNTIME).to_resource_attributes();
+        assert_eq!(resource_again, resource & !unmapped_resource);
+        assert_eq!(unmapped, EfiMemoryAttributes::RUNTIME);
+    }
+
+    #[test]
+    fn cacheability_should_be_exclusive_in_attributes() {
+        assert_eq!(EfiMemoryAttributes::empty().validate_attributes(), Ok(()));
+        assert_eq!((EfiMemoryAttributes::WB | EfiMemoryAttributes::XP).validate_attributes(), Ok(()));
+        for (_, first) in ATTRIBUTES.iter().filter(|(_, a)| EfiMemoryAttributes::CACHE_MASK.contains(*a)) {
+            for (_, second) in ATTRIBUTES.iter().filter(|(_, a)| EfiMemoryAttributes::CACHE_MASK.contains(*a)) {
+                let attributes = *first | *second | EfiMemoryAttributes::RUNTIME;
+                if first == second {
+                    assert_eq!(attributes.validate_attributes(), Ok(()));
+                } else {
+                    assert_eq!(
+                        attributes.validate_attributes(),
+                        Err(MemAttrError::ConflictingCacheability(*first | *second))
+                    );
+                }
+            }
+        }
+        assert_eq!(
+            r_efi::efi::Status::from(MemAttrError::ConflictingCacheability(EfiMemoryAttributes::CACHE_MASK)),
+            r_efi::efi::Status::INVALID_PARAMETER
+        );
+    }
+
+    #[test]
+    fn debug_should_name_the_attributes() {
+        let attributes = EfiMemoryAttributes::WB | EfiMemoryAttributes::XP | EfiMemoryAttributes::RUNTIME;
+        assert_eq!(format!("{attributes:?}"), "EfiMemoryAttributes(WB | XP | RUNTIME)");
+        assert_eq!(format!("{:?}", EfiMemoryAttributes::from_bits_retain(0x101)), "EfiMemoryAttributes(UC | 0x100)");
+        assert_eq!(format!("{:?}", EfiMemoryAttributes::empty()), "EfiMemoryAttributes()");
+    }
+}
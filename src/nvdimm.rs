@@ -0,0 +1,16 @@
+//! NVDIMM Namespaces
+//!
+//! Definitions for the namespace labels stored in the label storage area of NVDIMMs, and generation of the
+//! deterministic namespace UUIDs they carry.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/13_Protocols_Media_Access.html#nvdimm-label-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+pub mod label;
+pub mod uuid;
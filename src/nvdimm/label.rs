@@ -0,0 +1,158 @@
+//! NVDIMM Namespace Labels
+//!
+//! The namespace label (EFI_NVDIMM_LABEL) describing a namespace, or the part of a namespace on one NVDIMM, in the
+//! label storage area, and validation of its checksum.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/13_Protocols_Media_Access.html#label-definitions>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::mem::size_of;
+
+use r_efi::efi;
+
+/// The type GUID of namespace labels describing byte-addressable persistent memory, the NFIT type of persistent memory
+/// regions.
+///
+/// # Documentation
+/// ACPI Specification, Version 6.5, Section 5.2.26.2
+pub const NVDIMM_NAMESPACE_GUID: efi::Guid =
+    efi::Guid::from_fields(0x66f0d379, 0xb4f3, 0x4074, 0xac, 0x43, &[0x0d, 0x33, 0x18, 0xb7, 0x8c, 0xdb]);
+
+/// The length of the name of a namespace label.
+pub const NAME_LEN: usize = 64;
+
+/// The label cannot be updated.
+pub const FLAGS_ROLABEL: u32 = 0x0000_0001;
+/// The namespace is local to the NVDIMM rather than interleaved across NVDIMMs.
+pub const FLAGS_LOCAL: u32 = 0x0000_0002;
+/// The label is being updated.
+pub const FLAGS_UPDATING: u32 = 0x0000_0008;
+
+/// A namespace label (EFI_NVDIMM_LABEL).
+///
+/// # Documentation
+/// UEFI Specification, Release 2.10, Section 13.19
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NamespaceLabel {
+    /// The UUID of the namespace.
+    pub uuid: efi::Guid,
+    /// The NULL-terminated UTF-8 name of the namespace.
+    pub name: [u8; NAME_LEN],
+    /// The FLAGS_* of the label.
+    pub flags: u32,
+    /// The number of NVDIMMs the namespace is interleaved across.
+    pub n_label: u16,
+    /// The position of the NVDIMM in the interleave set.
+    pub position: u16,
+    /// The cookie of the interleave set, checked against the set when the label is used.
+    pub set_cookie: u64,
+    /// The logical block size of the namespace, or 0 for byte-addressable namespaces.
+    pub lba_size: u64,
+    /// The device physical address of the part of the namespace on the NVDIMM.
+    pub dpa: u64,
+    /// The size of the part of the namespace on the NVDIMM.
+    pub raw_size: u64,
+    /// The slot of the label in the label storage area.
+    pub slot: u32,
+    /// The alignment of the namespace, as a power of two.
+    pub alignment: u8,
+    pub reserved: [u8; 3],
+    /// The type of the namespace, e.g. [`NVDIMM_NAMESPACE_GUID`].
+    pub type_guid: efi::Guid,
+    /// The GUID of the address abstraction of the namespace, or zeroes for none.
+    pub address_abstraction_guid: efi::Guid,
+    pub reserved1: [u8; 88],
+    /// The Fletcher64 checksum of the label, computed with this field zero.
+    pub checksum: u64,
+}
+
+impl NamespaceLabel {
+    /// Returns the checksum of the label, computed with the checksum field zero.
+    pub fn compute_checksum(&self) -> u64 {
+        let label = NamespaceLabel { checksum: 0, ..*self };
+        //Safety: the label is plain data without padding.
+        let bytes = unsafe { core::slice::from_raw_parts(&label as *const Self as *const u8, size_of::<Self>()) };
+        fletcher64(bytes)
+    }
+}
+
+// Fletcher64 over little-endian 32-bit words, as label storage areas are checksummed.
+fn fletcher64(bytes: &[u8]) -> u64 {
+    let (mut lo, mut hi) = (0u32, 0u32);
+    for word in bytes.chunks_exact(4) {
+        lo = lo.wrapping_add(u32::from_le_bytes([word[0], word[1], word[2], word[3]]));
+        hi = hi.wrapping_add(lo);
+    }
+    (hi as u64) << 32 | lo as u64
+}
+
+/// Returns whether the checksum field of `label` matches its contents.
+pub fn validate_namespace_label(label: &NamespaceLabel) -> bool {
+    label.checksum == label.compute_checksum()
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::size_of;
+
+    use r_efi::efi;
+
+    use super::{fletcher64, validate_namespace_label, NamespaceLabel, FLAGS_LOCAL, NAME_LEN, NVDIMM_NAMESPACE_GUID};
+
+    fn label() -> NamespaceLabel {
+        let mut name = [0; NAME_LEN];
+        name[..12].copy_from_slice(b"namespace0.0");
+        NamespaceLabel {
+            uuid: efi::Guid::from_bytes(&[
+                0x48, 0x64, 0xc6, 0x58, 0x35, 0x50, 0x56, 0xe5, 0x91, 0x38, 0x7b, 0x47, 0xe2, 0xfa, 0x3d, 0x99,
+            ]),
+            name,
+            flags: FLAGS_LOCAL,
+            n_label: 1,
+            position: 0,
+            set_cookie: 0x1234_5678_9abc_def0,
+            lba_size: 0,
+            dpa: 0x1_0000_0000,
+            raw_size: 0x4000_0000,
+            slot: 2,
+            alignment: 0,
+            reserved: [0; 3],
+            type_guid: NVDIMM_NAMESPACE_GUID,
+            address_abstraction_guid: efi::Guid::from_bytes(&[0; 16]),
+            reserved1: [0; 88],
+            checksum: 0,
+        }
+    }
+
+    #[test]
+    fn label_should_match_spec_layout() {
+        assert_eq!(size_of::<NamespaceLabel>(), 256);
+    }
+
+    #[test]
+    fn fletcher64_should_sum_words() {
+        assert_eq!(fletcher64(&[]), 0);
+        // Words 1, 2, 3: running sums 1, 3, 6 add to 10.
+        assert_eq!(fletcher64(&[1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0]), 10 << 32 | 6);
+        assert_eq!(fletcher64(&[0xff; 8]), 0xffff_fffd_ffff_fffe);
+    }
+
+    #[test]
+    fn label_checksum_should_be_validated() {
+        let mut label = label();
+        assert!(!validate_namespace_label(&label));
+
+        label.checksum = label.compute_checksum();
+        assert!(validate_namespace_label(&label));
+
+        label.slot = 3;
+        assert!(!validate_namespace_label(&label));
+    }
+}
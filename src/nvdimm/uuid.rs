@@ -0,0 +1,163 @@
+//! NVDIMM Namespace UUIDs
+//!
+//! Generation of namespace UUIDs from deterministic inputs, so the same namespace is given the same UUID each time its
+//! labels are created. UUIDs are name-based version 5 UUIDs: the SHA-1 hash of a root UUID followed by a name.
+//!
+//! See <https://www.rfc-editor.org/rfc/rfc4122#section-4.3>
+//!
+//! ## Example
+//! ```
+//! use mu_pi::nvdimm::uuid::generate_namespace_uuid;
+//!
+//! // The DNS namespace of RFC 4122.
+//! let dns = [0x6b, 0xa7, 0xb8, 0x10, 0x9d, 0xad, 0x11, 0xd1, 0x80, 0xb4, 0x00, 0xc0, 0x4f, 0xd4, 0x30, 0xc8];
+//! let uuid = generate_namespace_uuid(&dns, "www.example.com");
+//! assert_eq!(uuid[..4], [0x2e, 0xd6, 0x65, 0x7d]);
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+/// Returns the version 5 UUID of `label` in the namespace `root_uuid`.
+///
+/// Both UUIDs are in the byte order of RFC 4122, most significant byte first. Namespace labels store the bytes as they
+/// are in their UUID fields.
+pub fn generate_namespace_uuid(root_uuid: &[u8; 16], label: &str) -> [u8; 16] {
+    let mut sha1 = Sha1::new();
+    sha1.update(root_uuid);
+    sha1.update(label.as_bytes());
+    let digest = sha1.finish();
+
+    let mut uuid = [0; 16];
+    uuid.copy_from_slice(&digest[..16]);
+    uuid[6] = (uuid[6] & 0x0f) | 0x50;
+    uuid[8] = (uuid[8] & 0x3f) | 0x80;
+    uuid
+}
+
+// SHA-1 (FIPS 180-4), only used to derive UUIDs, where its weaknesses do not matter.
+struct Sha1 {
+    state: [u32; 5],
+    block: [u8; 64],
+    block_len: usize,
+    length: u64,
+}
+
+impl Sha1 {
+    fn new() -> Self {
+        Self {
+            state: [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0],
+            block: [0; 64],
+            block_len: 0,
+            length: 0,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        self.length += data.len() as u64;
+        while !data.is_empty() {
+            let count = (64 - self.block_len).min(data.len());
+            self.block[self.block_len..self.block_len + count].copy_from_slice(&data[..count]);
+            self.block_len += count;
+            data = &data[count..];
+            if self.block_len == 64 {
+                self.compress();
+            }
+        }
+    }
+
+    fn finish(mut self) -> [u8; 20] {
+        let bits = self.length * 8;
+        self.update(&[0x80]);
+        while self.block_len != 56 {
+            self.update(&[0]);
+        }
+        self.update(&bits.to_be_bytes());
+
+        let mut digest = [0; 20];
+        for (bytes, word) in digest.chunks_exact_mut(4).zip(self.state) {
+            bytes.copy_from_slice(&word.to_be_bytes());
+        }
+        digest
+    }
+
+    fn compress(&mut self) {
+        let mut w = [0u32; 80];
+        for (word, bytes) in w.iter_mut().zip(self.block.chunks_exact(4)) {
+            *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = self.state;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5a827999),
+                20..=39 => (b ^ c ^ d, 0x6ed9eba1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+                _ => (b ^ c ^ d, 0xca62c1d6),
+            };
+            let temp = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (state, value) in self.state.iter_mut().zip([a, b, c, d, e]) {
+            *state = state.wrapping_add(value);
+        }
+        self.block_len = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate_namespace_uuid, Sha1};
+
+    fn sha1(data: &[u8]) -> [u8; 20] {
+        let mut sha1 = Sha1::new();
+        sha1.update(data);
+        sha1.finish()
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().fold(String::new(), |hex, byte| hex + &format!("{byte:02x}"))
+    }
+
+    #[test]
+    fn sha1_should_match_fips_180_vectors() {
+        assert_eq!(hex(&sha1(b"")), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(hex(&sha1(b"abc")), "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert_eq!(
+            hex(&sha1(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq")),
+            "84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+        );
+
+        // A million 'a's, hashed in pieces that straddle blocks.
+        let mut hasher = Sha1::new();
+        for _ in 0..1000 {
+            hasher.update(&[b'a'; 999]);
+        }
+        hasher.update(&[b'a'; 1000]);
+        assert_eq!(hex(&hasher.finish()), "34aa973cd4c4daa4f61eeb2bdbad27316534016f");
+    }
+
+    #[test]
+    fn namespace_uuid_should_be_version_5() {
+        let dns = [0x6b, 0xa7, 0xb8, 0x10, 0x9d, 0xad, 0x11, 0xd1, 0x80, 0xb4, 0x00, 0xc0, 0x4f, 0xd4, 0x30, 0xc8];
+        assert_eq!(hex(&generate_namespace_uuid(&dns, "www.example.com")), "2ed6657de927568b95e12665a8aea6a2");
+
+        let pmem = [0x66, 0xf0, 0xd3, 0x79, 0xb4, 0xf3, 0x40, 0x74, 0xac, 0x43, 0x0d, 0x33, 0x18, 0xb7, 0x8c, 0xdb];
+        let uuid = generate_namespace_uuid(&pmem, "namespace0.0");
+        assert_eq!(hex(&uuid), "4864c658355056e591387b47e2fa3d99");
+        assert_eq!(uuid, generate_namespace_uuid(&pmem, "namespace0.0"));
+        assert_ne!(uuid, generate_namespace_uuid(&pmem, "namespace0.1"));
+    }
+}
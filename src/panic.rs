@@ -0,0 +1,154 @@
+//! Firmware Panic Handling
+//!
+//! A panic handler for firmware, which cannot unwind. [`handle_panic`] calls the [`FirmwarePanicHandler`]
+//! registered with [`register_panic_handler`], or by default prints the panic message to the ConOut set with
+//! [`set_panic_output`] and resets the system with the runtime services set with [`set_runtime_services`]. With the
+//! `panic-halt` feature the default handler halts in an infinite loop instead.
+//!
+//! ## Example
+//!```no_run
+//! use mu_pi::panic;
+//! use r_efi::efi;
+//!
+//! // In the firmware binary:
+//! // #[panic_handler]
+//! // fn panic(info: &core::panic::PanicInfo) -> ! {
+//! //   mu_pi::panic::handle_panic(info)
+//! // }
+//!
+//! fn entry(system_table: &efi::SystemTable) {
+//!   //Safety: the ConOut stays valid until ExitBootServices(), the runtime services for the life of the system.
+//!   unsafe {
+//!     panic::set_panic_output(system_table.con_out as *const _);
+//!     panic::set_runtime_services(system_table.runtime_services);
+//!   }
+//! }
+//!```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{
+    fmt::{self, Write},
+    mem,
+    panic::PanicInfo,
+    ptr,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+use r_efi::efi;
+
+use crate::{console::TextOutput, protocols::simple_text_output::Protocol as SimpleTextOutputProtocol};
+
+/// A panic handler. It must not return, since firmware cannot unwind.
+pub type FirmwarePanicHandler = fn(&PanicInfo) -> !;
+
+static PANIC_HANDLER: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+static PANIC_OUTPUT: AtomicPtr<SimpleTextOutputProtocol> = AtomicPtr::new(ptr::null_mut());
+static RUNTIME_SERVICES: AtomicPtr<efi::RuntimeServices> = AtomicPtr::new(ptr::null_mut());
+
+/// Registers the handler [`handle_panic`] calls, replacing the default handler.
+pub fn register_panic_handler(handler: FirmwarePanicHandler) {
+    PANIC_HANDLER.store(handler as *mut (), Ordering::Release);
+}
+
+/// Sets the Simple Text Output Protocol the default handler prints the panic message to. A null `proto`, the initial
+/// state, disables printing.
+///
+/// # Safety
+/// `proto` must be null or point to a valid protocol instance until it is replaced.
+pub unsafe fn set_panic_output(proto: *const SimpleTextOutputProtocol) {
+    PANIC_OUTPUT.store(proto as *mut _, Ordering::Release);
+}
+
+/// Sets the runtime services the default handler resets the system with. Without them, the default handler halts.
+///
+/// # Safety
+/// `runtime_services` must be null or point to a valid runtime services table until it is replaced.
+pub unsafe fn set_runtime_services(runtime_services: *const efi::RuntimeServices) {
+    RUNTIME_SERVICES.store(runtime_services as *mut _, Ordering::Release);
+}
+
+/// Handles a panic with the registered handler, or the default handler if none is registered. Intended to be
+/// called from the `#[panic_handler]` of a firmware binary.
+pub fn handle_panic(info: &PanicInfo) -> ! {
+    match registered_handler() {
+        Some(handler) => handler(info),
+        None => default_panic_handler(info),
+    }
+}
+
+fn registered_handler() -> Option<FirmwarePanicHandler> {
+    let handler = PANIC_HANDLER.load(Ordering::Acquire);
+    //Safety: PANIC_HANDLER is only set from a FirmwarePanicHandler in register_panic_handler().
+    (!handler.is_null()).then(|| unsafe { mem::transmute::<*mut (), FirmwarePanicHandler>(handler) })
+}
+
+/// The default handler: prints the panic message to the panic output and issues a cold reset.
+#[cfg(not(feature = "panic-halt"))]
+pub fn default_panic_handler(info: &PanicInfo) -> ! {
+    print_panic_message(info);
+    //Safety: set_runtime_services() callers guarantee the pointer stays valid until it is replaced.
+    if let Some(runtime_services) = unsafe { RUNTIME_SERVICES.load(Ordering::Acquire).as_ref() } {
+        crate::reset::invoke(runtime_services.reset_system, crate::reset::ResetRequest::Cold, efi::Status::ABORTED)
+    }
+    halt()
+}
+
+/// The default handler: halts in an infinite loop.
+#[cfg(feature = "panic-halt")]
+pub fn default_panic_handler(_info: &PanicInfo) -> ! {
+    halt()
+}
+
+#[cfg_attr(feature = "panic-halt", allow(dead_code))]
+fn print_panic_message(message: &dyn fmt::Display) {
+    let proto = PANIC_OUTPUT.load(Ordering::Acquire);
+    if proto.is_null() {
+        return;
+    }
+    //Safety: set_panic_output() callers guarantee the pointer stays valid until it is replaced.
+    let _ = write!(unsafe { TextOutput::new(proto) }, "\n!!! {message} !!!\n");
+}
+
+fn halt() -> ! {
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    use alloc::boxed::Box;
+    use core::panic::PanicInfo;
+
+    use super::{print_panic_message, register_panic_handler, registered_handler, set_panic_output};
+    use crate::console::text_output::tests::{mock_protocol, take_output};
+
+    fn custom_handler(info: &PanicInfo) -> ! {
+        panic!("{info}")
+    }
+
+    // A panic cannot be routed through handle_panic() in a test without leaving the panicking thread inside the panic
+    // hook, so only the dispatch to the registered handler is checked.
+    #[test]
+    fn registered_handler_should_be_called() {
+        assert_eq!(registered_handler(), None);
+        register_panic_handler(custom_handler);
+        assert_eq!(registered_handler().map(|handler| handler as usize), Some(custom_handler as usize));
+    }
+
+    #[test]
+    fn panic_message_should_be_printed_to_panic_output() {
+        unsafe { set_panic_output(Box::leak(Box::new(mock_protocol()))) };
+        print_panic_message(&"panicked at src/lib.rs:1:1:\nfirmware failure");
+        unsafe { set_panic_output(core::ptr::null()) };
+        print_panic_message(&"dropped");
+        assert_eq!(take_output(), "\r\n!!! panicked at src/lib.rs:1:1:\r\nfirmware failure !!!\r\n");
+    }
+}
@@ -0,0 +1,372 @@
+//! PEI Phase Support
+//!
+//! Support code for modules executing in the Pre-EFI Initialization (PEI) phase.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V1_Overview.html>.
+//!
+//! [`PeiServices`] wraps the PEI Services Table passed to PEIMs with safe methods for the common services.
+//!
+//! ## Example
+//! ```no_run
+//! use mu_pi::pei::{services::{FileHandle, PeiServicesPointer}, PeiServices};
+//! use r_efi::efi;
+//!
+//! extern "efiapi" fn peim_entry(_file_handle: FileHandle, pei_services: PeiServicesPointer) -> efi::Status {
+//!     //Safety: the PEI Foundation passes a valid services table pointer that lives for the whole PEI phase.
+//!     let services = unsafe { PeiServices::new(pei_services) };
+//!     match services.get_boot_mode() {
+//!         Ok(boot_mode) if boot_mode.is_s3_resume() => efi::Status::SUCCESS,
+//!         Ok(_) => efi::Status::SUCCESS,
+//!         Err(status) => status,
+//!     }
+//! }
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+pub mod allocator;
+pub mod mem_tracker;
+pub mod ppi;
+pub mod services;
+
+use core::{ffi::c_void, fmt, ptr};
+
+use r_efi::{efi, system::MemoryType};
+
+use crate::{
+    boot_mode::BootMode,
+    hob::{EfiBootMode, HobList},
+    protocols::status_code::EfiStatusCodeData,
+    reset::{self, ResetRequest},
+    status::StatusExt,
+    status_code::{StatusCodeType, StatusCodeValue},
+};
+use ppi::{NotifyList, PpiList};
+use services::{FileHandle, FvHandle, PeiServicesPointer, PpiDescriptor};
+
+/// Marks a Rust type as the interface of the PPI identified by [`GUID`](Self::GUID), for
+/// [`PeiServices::locate_ppi`].
+///
+/// # Safety
+/// The type must have the layout of the interface the PPI specification defines for the GUID.
+pub unsafe trait Ppi: Sync {
+    /// The GUID of the PPI.
+    const GUID: efi::Guid;
+}
+
+/// Safe access to the [`PeiServicesTable`](services::PeiServicesTable), hiding the pointer to pointer convention of
+/// the services.
+#[derive(Clone, Copy)]
+pub struct PeiServices<'a> {
+    services: PeiServicesPointer,
+    table: &'a services::PeiServicesTable,
+}
+
+impl<'a> PeiServices<'a> {
+    /// Wraps the services passed to a PEIM.
+    ///
+    /// # Safety
+    /// `services` must point to a pointer to a valid PEI Services Table, both valid for `'a`.
+    pub unsafe fn new(services: PeiServicesPointer) -> Self {
+        Self { services, table: &**services }
+    }
+
+    /// Returns the pointer passed to the services.
+    pub fn as_ptr(&self) -> PeiServicesPointer {
+        self.services
+    }
+
+    /// Returns the PEI Services Table.
+    pub fn table(&self) -> &'a services::PeiServicesTable {
+        self.table
+    }
+
+    /// Installs the PPIs of `ppi_list`.
+    pub fn install_ppi<const N: usize>(&self, ppi_list: &'static PpiList<N>) -> Result<(), efi::Status> {
+        (self.table.install_ppi)(self.services, ppi_list.as_ptr()).ok()
+    }
+
+    /// Returns the first installed instance of the PPI `T`, or `None` if none is installed.
+    pub fn locate_ppi<T: Ppi>(&self) -> Option<&'a T> {
+        let mut descriptor: *mut PpiDescriptor = ptr::null_mut();
+        let mut ppi: *mut c_void = ptr::null_mut();
+        match (self.table.locate_ppi)(self.services, &T::GUID, 0, &mut descriptor, &mut ppi) {
+            //Safety: the Ppi implementation of T guarantees its layout matches the PPI installed for the GUID.
+            efi::Status::SUCCESS => unsafe { (ppi as *const T).as_ref() },
+            _ => None,
+        }
+    }
+
+    /// Registers the notifications of `notify_list`.
+    pub fn notify<const N: usize>(&self, notify_list: &'static NotifyList<N>) -> Result<(), efi::Status> {
+        (self.table.notify_ppi)(self.services, notify_list.as_ptr()).ok()
+    }
+
+    /// Returns the boot mode.
+    pub fn get_boot_mode(&self) -> Result<BootMode, efi::Status> {
+        let mut boot_mode: EfiBootMode = 0;
+        (self.table.get_boot_mode)(self.services, &mut boot_mode).ok()?;
+        Ok(BootMode::from(boot_mode))
+    }
+
+    /// Sets the boot mode.
+    pub fn set_boot_mode(&self, boot_mode: BootMode) -> Result<(), efi::Status> {
+        (self.table.set_boot_mode)(self.services, boot_mode.into()).ok()
+    }
+
+    /// Returns the HOBs of the HOB list.
+    pub fn get_hob_list(&self) -> Result<HobList<'a>, efi::Status> {
+        let mut hob_list: *mut c_void = ptr::null_mut();
+        (self.table.get_hob_list)(self.services, &mut hob_list).ok()?;
+        let mut hobs = HobList::new();
+        hobs.discover_hobs(hob_list);
+        Ok(hobs)
+    }
+
+    /// Creates a HOB of `hob_type` and `length` bytes, header included, returning its address. The PEI Foundation
+    /// fills in the header.
+    pub fn create_hob(&self, hob_type: u16, length: u16) -> Result<*mut c_void, efi::Status> {
+        let mut hob: *mut c_void = ptr::null_mut();
+        (self.table.create_hob)(self.services, hob_type, length, &mut hob).ok()?;
+        Ok(hob)
+    }
+
+    /// Allocates `pages` pages of `memory_type`, returning their address.
+    pub fn allocate_pages(&self, memory_type: MemoryType, pages: usize) -> Result<efi::PhysicalAddress, efi::Status> {
+        let mut memory: efi::PhysicalAddress = 0;
+        (self.table.allocate_pages)(self.services, memory_type, pages, &mut memory).ok()?;
+        Ok(memory)
+    }
+
+    /// Allocates `size` bytes of pool memory, which cannot be freed.
+    pub fn allocate_pool(&self, size: usize) -> Result<*mut c_void, efi::Status> {
+        let mut buffer: *mut c_void = ptr::null_mut();
+        (self.table.allocate_pool)(self.services, size, &mut buffer).ok()?;
+        Ok(buffer)
+    }
+
+    /// Returns the firmware volume of index `instance`; fails with NOT_FOUND past the last one.
+    pub fn ffs_find_next_volume(&self, instance: usize) -> Result<FvHandle, efi::Status> {
+        let mut volume: FvHandle = ptr::null_mut();
+        (self.table.ffs_find_next_volume)(self.services, instance, &mut volume).ok()?;
+        Ok(volume)
+    }
+
+    /// Returns the file of `search_type` following `previous` in `volume`, or the first one if `previous` is `None`;
+    /// fails with NOT_FOUND past the last one.
+    pub fn ffs_find_next_file(
+        &self,
+        search_type: u8,
+        volume: FvHandle,
+        previous: Option<FileHandle>,
+    ) -> Result<FileHandle, efi::Status> {
+        let mut file = previous.unwrap_or(ptr::null_mut());
+        (self.table.ffs_find_next_file)(self.services, search_type, volume, &mut file).ok()?;
+        Ok(file)
+    }
+
+    /// Returns the data of the first section of `section_type` in `file`.
+    pub fn ffs_find_section_data(&self, section_type: u8, file: FileHandle) -> Result<*mut c_void, efi::Status> {
+        let mut data: *mut c_void = ptr::null_mut();
+        (self.table.ffs_find_section_data)(self.services, section_type, file, &mut data).ok()?;
+        Ok(data)
+    }
+
+    /// Reports a status code.
+    pub fn report_status_code(
+        &self,
+        code_type: StatusCodeType,
+        value: StatusCodeValue,
+        instance: u32,
+        caller_id: Option<&efi::Guid>,
+        data: Option<&EfiStatusCodeData>,
+    ) -> Result<(), efi::Status> {
+        let caller_id = caller_id.map_or(ptr::null(), |guid| guid as *const efi::Guid);
+        let data = data.map_or(ptr::null(), |data| data as *const EfiStatusCodeData);
+        (self.table.report_status_code)(self.services, code_type, value, instance, caller_id, data).ok()
+    }
+
+    /// Resets the platform with the ResetSystem2() service.
+    pub fn reset_system2(&self, request: ResetRequest<'_>, status: efi::Status) -> ! {
+        reset::invoke(self.table.reset_system2, request, status)
+    }
+}
+
+impl fmt::Debug for PeiServices<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PeiServices").field("services", &self.services).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{cell::Cell, ffi::c_void, mem::size_of, ptr};
+
+    use r_efi::efi;
+
+    use super::{
+        ppi::PpiList,
+        services::{self, mock, PeiServicesPointer, PpiDescriptor, PPI_DESCRIPTOR_PPI, PPI_DESCRIPTOR_TERMINATE_LIST},
+        PeiServices, Ppi,
+    };
+    use crate::{
+        boot_mode::BootMode,
+        hob::{self, EfiBootMode, Hob},
+    };
+
+    const TEST_PPI_GUID: efi::Guid =
+        efi::Guid::from_fields(0x7c1ae4a9, 0x5c5e, 0x4d09, 0x8b, 0x36, &[0x1e, 0x50, 0x92, 0xd4, 0x6e, 0x1f]);
+
+    #[repr(C)]
+    #[derive(Debug, PartialEq, Eq)]
+    struct TestPpi {
+        revision: u32,
+    }
+
+    unsafe impl Ppi for TestPpi {
+        const GUID: efi::Guid = TEST_PPI_GUID;
+    }
+
+    static TEST_PPI: TestPpi = TestPpi { revision: 7 };
+    static PPI_LIST: PpiList<1> = crate::ppi_list![(PPI_DESCRIPTOR_PPI, &TEST_PPI_GUID, &TEST_PPI)];
+
+    thread_local! {
+        static INSTALLED: Cell<*const PpiDescriptor> = const { Cell::new(ptr::null()) };
+        static BOOT_MODE: Cell<EfiBootMode> = const { Cell::new(0) };
+    }
+
+    extern "efiapi" fn install_ppi(_: PeiServicesPointer, ppi_list: *const PpiDescriptor) -> efi::Status {
+        INSTALLED.with(|installed| installed.set(ppi_list));
+        efi::Status::SUCCESS
+    }
+
+    // Searches the list installed last, as the PEI Foundation searches its PPI database.
+    extern "efiapi" fn locate_ppi(
+        _: PeiServicesPointer,
+        guid: *const efi::Guid,
+        instance: usize,
+        ppi_descriptor: *mut *mut PpiDescriptor,
+        ppi: *mut *mut c_void,
+    ) -> efi::Status {
+        let mut descriptor = INSTALLED.with(Cell::get);
+        if descriptor.is_null() {
+            return efi::Status::NOT_FOUND;
+        }
+        let mut found = 0;
+        loop {
+            let current = unsafe { &*descriptor };
+            if unsafe { *current.guid == *guid } {
+                if found == instance {
+                    unsafe {
+                        *ppi_descriptor = descriptor as *mut PpiDescriptor;
+                        *ppi = current.ppi;
+                    }
+                    return efi::Status::SUCCESS;
+                }
+                found += 1;
+            }
+            if current.flags & PPI_DESCRIPTOR_TERMINATE_LIST != 0 {
+                return efi::Status::NOT_FOUND;
+            }
+            descriptor = unsafe { descriptor.add(1) };
+        }
+    }
+
+    extern "efiapi" fn get_boot_mode(_: PeiServicesPointer, boot_mode: *mut EfiBootMode) -> efi::Status {
+        unsafe { *boot_mode = BOOT_MODE.with(Cell::get) };
+        efi::Status::SUCCESS
+    }
+
+    extern "efiapi" fn set_boot_mode(_: PeiServicesPointer, boot_mode: EfiBootMode) -> efi::Status {
+        BOOT_MODE.with(|mode| mode.set(boot_mode));
+        efi::Status::SUCCESS
+    }
+
+    #[repr(C)]
+    struct Hobs {
+        cpu: hob::Cpu,
+        end: hob::header::Hob,
+    }
+
+    static HOBS: Hobs = Hobs {
+        cpu: hob::Cpu {
+            header: hob::header::Hob { r#type: hob::CPU, length: size_of::<hob::Cpu>() as u16, reserved: 0 },
+            size_of_memory_space: 39,
+            size_of_io_space: 16,
+            reserved: [0; 6],
+        },
+        end: hob::header::Hob {
+            r#type: hob::END_OF_HOB_LIST,
+            length: size_of::<hob::header::Hob>() as u16,
+            reserved: 0,
+        },
+    };
+
+    extern "efiapi" fn get_hob_list(_: PeiServicesPointer, hob_list: *mut *mut c_void) -> efi::Status {
+        unsafe { *hob_list = &HOBS as *const Hobs as *mut c_void };
+        efi::Status::SUCCESS
+    }
+
+    fn table() -> services::PeiServicesTable {
+        services::PeiServicesTable {
+            install_ppi,
+            locate_ppi,
+            get_boot_mode,
+            set_boot_mode,
+            get_hob_list,
+            ..mock::services()
+        }
+    }
+
+    #[test]
+    fn installed_ppi_should_be_located() {
+        let table = table();
+        let table_ptr: *const services::PeiServicesTable = &table;
+        let services = unsafe { PeiServices::new(&table_ptr) };
+
+        assert_eq!(services.locate_ppi::<TestPpi>(), None);
+        assert_eq!(services.install_ppi(&PPI_LIST), Ok(()));
+        assert_eq!(services.locate_ppi::<TestPpi>(), Some(&TEST_PPI));
+    }
+
+    #[test]
+    fn boot_mode_should_round_trip() {
+        let table = table();
+        let table_ptr: *const services::PeiServicesTable = &table;
+        let services = unsafe { PeiServices::new(&table_ptr) };
+
+        assert_eq!(services.set_boot_mode(BootMode::S3Resume), Ok(()));
+        assert_eq!(services.get_boot_mode(), Ok(BootMode::S3Resume));
+        assert_eq!(services.set_boot_mode(BootMode::Unknown(0xF001)), Ok(()));
+        assert_eq!(services.get_boot_mode(), Ok(BootMode::Unknown(0xF001)));
+    }
+
+    #[test]
+    fn hob_list_should_be_discovered() {
+        let table = table();
+        let table_ptr: *const services::PeiServicesTable = &table;
+        let services = unsafe { PeiServices::new(&table_ptr) };
+
+        let hobs = services.get_hob_list().unwrap();
+        assert_eq!(hobs.len(), 1);
+        let Some(Hob::Cpu(cpu)) = hobs.iter().next() else { panic!("expected a CPU HOB") };
+        assert_eq!((cpu.size_of_memory_space, cpu.size_of_io_space), (39, 16));
+    }
+
+    #[test]
+    fn failed_services_should_return_their_status() {
+        let table = mock::services();
+        let table_ptr: *const services::PeiServicesTable = &table;
+        let services = unsafe { PeiServices::new(&table_ptr) };
+
+        assert_eq!(services.get_hob_list().err(), Some(efi::Status::UNSUPPORTED));
+        assert_eq!(services.allocate_pool(8), Err(efi::Status::UNSUPPORTED));
+        assert_eq!(services.ffs_find_next_volume(0), Err(efi::Status::UNSUPPORTED));
+        assert_eq!(services.install_ppi(&PPI_LIST), Err(efi::Status::UNSUPPORTED));
+    }
+}
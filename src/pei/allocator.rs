@@ -0,0 +1,286 @@
+//! PEI Memory Services Allocator
+//!
+//! Allocates memory with the memory services of the PEI Services Table, available once permanent memory is installed,
+//! and implements [`GlobalAlloc`] over them for PEIMs written in Rust.
+//!
+//! Memory allocated in PEI is never returned: the PEI Foundation cannot free pool memory, and pages are left for the
+//! DXE phase to reclaim as boot services data.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V1_Services_PEI.html#memory-services>
+//!
+//! ## Example
+//! ```no_run
+//! use mu_pi::pei::{allocator::PeiAllocator, services::PeiServicesPointer};
+//!
+//! static ALLOCATOR: PeiAllocator = PeiAllocator::new();
+//!
+//! extern "efiapi" fn peim_entry(_file_handle: *mut core::ffi::c_void, pei_services: PeiServicesPointer) {
+//!     //Safety: the PEI Foundation passes a valid services table pointer that lives for the whole PEI phase.
+//!     unsafe { ALLOCATOR.set_services(pei_services) };
+//!     let buffer = ALLOCATOR.allocate_pool(0x100).unwrap();
+//! }
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    ffi::c_void,
+    fmt, ptr,
+    sync::atomic::{AtomicBool, AtomicPtr, Ordering},
+};
+
+use r_efi::{efi, system::MemoryType};
+
+use super::services::{PeiServicesPointer, PeiServicesTable};
+use crate::address_helper::align_up;
+
+/// The largest allocation AllocatePool() can satisfy: a memory pool HOB is at most 0xFFFF bytes, header included.
+pub const MAX_POOL_SIZE: usize = 0xFFF0;
+
+/// The alignment of the buffers AllocatePool() returns, that of the HOBs holding them.
+pub const POOL_ALIGNMENT: usize = 8;
+
+/// The size of a page allocated by AllocatePages().
+pub const PAGE_SIZE: usize = 0x1000;
+
+// Releases the lock of a PeiAllocator when dropped.
+struct LockGuard<'a>(&'a AtomicBool);
+
+impl Drop for LockGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+/// Allocates memory with the PEI memory services.
+///
+/// Calls into the services are serialized with a spin lock, as a PEIM may hand work to application processors.
+pub struct PeiAllocator {
+    services: AtomicPtr<*const PeiServicesTable>,
+    lock: AtomicBool,
+}
+
+impl PeiAllocator {
+    /// Creates an allocator without services. Allocations fail with NOT_READY until
+    /// [`set_services`](Self::set_services) is called.
+    pub const fn new() -> Self {
+        Self { services: AtomicPtr::new(ptr::null_mut()), lock: AtomicBool::new(false) }
+    }
+
+    /// Creates an allocator using `services`.
+    ///
+    /// # Safety
+    /// `services` must point to a pointer to a valid PEI Services Table, both remaining valid while the allocator is
+    /// in use.
+    pub const unsafe fn from_services(services: PeiServicesPointer) -> Self {
+        Self { services: AtomicPtr::new(services as *mut _), lock: AtomicBool::new(false) }
+    }
+
+    /// Sets the services the allocator uses.
+    ///
+    /// # Safety
+    /// See [`from_services`](Self::from_services).
+    pub unsafe fn set_services(&self, services: PeiServicesPointer) {
+        self.services.store(services as *mut _, Ordering::Release);
+    }
+
+    fn lock(&self) -> LockGuard<'_> {
+        while self.lock.compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed).is_err() {
+            core::hint::spin_loop();
+        }
+        LockGuard(&self.lock)
+    }
+
+    fn services(&self) -> Result<(PeiServicesPointer, &PeiServicesTable), efi::Status> {
+        let services = self.services.load(Ordering::Acquire) as PeiServicesPointer;
+        //Safety: the pointers were declared valid in from_services() or set_services().
+        match unsafe { services.as_ref().and_then(|table| table.as_ref()) } {
+            Some(table) => Ok((services, table)),
+            None => Err(efi::Status::NOT_READY),
+        }
+    }
+
+    /// Allocates `size` bytes of pool memory.
+    ///
+    /// Fails with NOT_READY if the allocator has no services, or with the status returned by AllocatePool().
+    pub fn allocate_pool(&self, size: usize) -> Result<*mut u8, efi::Status> {
+        let (services, table) = self.services()?;
+        let _guard = self.lock();
+        let mut buffer: *mut c_void = ptr::null_mut();
+        match (table.allocate_pool)(services, size, &mut buffer) {
+            efi::Status::SUCCESS => Ok(buffer as *mut u8),
+            status => Err(status),
+        }
+    }
+
+    /// Frees pool memory allocated with [`allocate_pool`](Self::allocate_pool).
+    ///
+    /// The PEI Foundation cannot free pool memory, so the memory stays allocated; returns SUCCESS, or
+    /// INVALID_PARAMETER if `ptr` is null.
+    pub fn free_pool(&self, ptr: *mut u8) -> efi::Status {
+        match ptr.is_null() {
+            true => efi::Status::INVALID_PARAMETER,
+            false => efi::Status::SUCCESS,
+        }
+    }
+
+    /// Allocates `pages` pages of type `mem_type`, returning their address.
+    ///
+    /// AllocatePages() cannot allocate at a given address: a `phys_addr` of `Some` fails with UNSUPPORTED. Otherwise
+    /// fails with NOT_READY if the allocator has no services, or with the status returned by AllocatePages().
+    pub fn allocate_pages(
+        &self,
+        pages: usize,
+        mem_type: MemoryType,
+        phys_addr: Option<u64>,
+    ) -> Result<u64, efi::Status> {
+        if phys_addr.is_some() {
+            Err(efi::Status::UNSUPPORTED)?;
+        }
+        let (services, table) = self.services()?;
+        let _guard = self.lock();
+        let mut memory: efi::PhysicalAddress = 0;
+        match (table.allocate_pages)(services, mem_type, pages, &mut memory) {
+            efi::Status::SUCCESS => Ok(memory),
+            status => Err(status),
+        }
+    }
+}
+
+impl Default for PeiAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for PeiAllocator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PeiAllocator").field("services", &self.services.load(Ordering::Relaxed)).finish()
+    }
+}
+
+/// Allocations of at most [`MAX_POOL_SIZE`] bytes aligned to at most [`POOL_ALIGNMENT`] come from pool memory, others
+/// from boot services data pages. Deallocation does nothing.
+unsafe impl GlobalAlloc for PeiAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if layout.size() <= MAX_POOL_SIZE && layout.align() <= POOL_ALIGNMENT {
+            return self.allocate_pool(layout.size()).unwrap_or(ptr::null_mut());
+        }
+
+        // Pages are page aligned; larger alignments take up to an extra alignment's worth of pages.
+        let Some(size) = layout.size().checked_add(layout.align().saturating_sub(PAGE_SIZE)) else {
+            return ptr::null_mut();
+        };
+        match self.allocate_pages(size.div_ceil(PAGE_SIZE), efi::BOOT_SERVICES_DATA, None) {
+            Ok(memory) => align_up(memory, layout.align() as u64) as *mut u8,
+            Err(_) => ptr::null_mut(),
+        }
+    }
+
+    unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        alloc::{GlobalAlloc, Layout},
+        cell::RefCell,
+        ffi::c_void,
+    };
+
+    use r_efi::{efi, system::MemoryType};
+
+    use super::{PeiAllocator, MAX_POOL_SIZE};
+    use crate::pei::services::{mock, PeiServicesPointer, PeiServicesTable};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Call {
+        Pool(usize),
+        Pages(MemoryType, usize),
+    }
+
+    thread_local! {
+        static CALLS: RefCell<Vec<Call>> = const { RefCell::new(Vec::new()) };
+        static POOL: RefCell<Vec<Box<[u64]>>> = const { RefCell::new(Vec::new()) };
+    }
+
+    extern "efiapi" fn allocate_pool(_: PeiServicesPointer, size: usize, buffer: *mut *mut c_void) -> efi::Status {
+        CALLS.with(|calls| calls.borrow_mut().push(Call::Pool(size)));
+        if size > MAX_POOL_SIZE {
+            return efi::Status::OUT_OF_RESOURCES;
+        }
+        let mut memory = vec![0u64; size.div_ceil(8)].into_boxed_slice();
+        unsafe { *buffer = memory.as_mut_ptr() as *mut c_void };
+        POOL.with(|pool| pool.borrow_mut().push(memory));
+        efi::Status::SUCCESS
+    }
+
+    // Returns addresses that are only page aligned, without memory behind them.
+    extern "efiapi" fn allocate_pages(
+        _: PeiServicesPointer,
+        memory_type: MemoryType,
+        pages: usize,
+        memory: *mut efi::PhysicalAddress,
+    ) -> efi::Status {
+        CALLS.with(|calls| calls.borrow_mut().push(Call::Pages(memory_type, pages)));
+        unsafe { *memory = 0x8010_1000 };
+        efi::Status::SUCCESS
+    }
+
+    fn services() -> PeiServicesTable {
+        PeiServicesTable { allocate_pool, allocate_pages, ..mock::services() }
+    }
+
+    fn calls() -> Vec<Call> {
+        CALLS.with(|calls| calls.borrow_mut().drain(..).collect())
+    }
+
+    #[test]
+    fn allocator_without_services_should_not_be_ready() {
+        let allocator = PeiAllocator::new();
+        assert_eq!(allocator.allocate_pool(8), Err(efi::Status::NOT_READY));
+        assert_eq!(allocator.allocate_pages(1, efi::BOOT_SERVICES_DATA, None), Err(efi::Status::NOT_READY));
+        assert!(unsafe { allocator.alloc(Layout::new::<u64>()) }.is_null());
+    }
+
+    #[test]
+    fn allocator_should_call_memory_services() {
+        let table = services();
+        let table_ptr: *const PeiServicesTable = &table;
+        let allocator = unsafe { PeiAllocator::from_services(&table_ptr) };
+
+        let buffer = allocator.allocate_pool(24).unwrap();
+        assert!(!buffer.is_null());
+        assert_eq!(allocator.free_pool(buffer), efi::Status::SUCCESS);
+        assert_eq!(allocator.free_pool(core::ptr::null_mut()), efi::Status::INVALID_PARAMETER);
+        assert_eq!(allocator.allocate_pool(MAX_POOL_SIZE + 1), Err(efi::Status::OUT_OF_RESOURCES));
+        assert_eq!(allocator.allocate_pages(2, efi::LOADER_DATA, None), Ok(0x8010_1000));
+        assert_eq!(allocator.allocate_pages(2, efi::LOADER_DATA, Some(0x1000)), Err(efi::Status::UNSUPPORTED));
+        assert_eq!(calls(), [Call::Pool(24), Call::Pool(MAX_POOL_SIZE + 1), Call::Pages(efi::LOADER_DATA, 2)]);
+    }
+
+    #[test]
+    fn global_alloc_should_pick_pool_or_pages() {
+        let table = services();
+        let table_ptr: *const PeiServicesTable = &table;
+        let allocator = PeiAllocator::new();
+        unsafe { allocator.set_services(&table_ptr) };
+
+        let boxed = unsafe { allocator.alloc(Layout::from_size_align(100, 8).unwrap()) };
+        unsafe { boxed.write_bytes(0xa5, 100) };
+        let pages = unsafe { allocator.alloc(Layout::from_size_align(0x1_0000, 8).unwrap()) };
+        assert_eq!(pages as u64, 0x8010_1000);
+        let aligned = unsafe { allocator.alloc(Layout::from_size_align(0x100, 0x10_0000).unwrap()) };
+        assert_eq!(aligned as u64, 0x8020_0000);
+        assert_eq!(
+            calls(),
+            [Call::Pool(100), Call::Pages(efi::BOOT_SERVICES_DATA, 0x10), Call::Pages(efi::BOOT_SERVICES_DATA, 0x100),]
+        );
+    }
+}
@@ -0,0 +1,338 @@
+//! HOB-Based Memory Allocation Tracking
+//!
+//! Tracks the memory allocations made by PEI before a memory allocator is available. The memory available is the
+//! system memory described by the resource descriptor HOBs of the HOB list, less the memory the list already
+//! accounts for: memory allocation HOBs, reserved memory resource descriptors, firmware volumes, capsules and the HOB
+//! list itself. Each allocation is recorded as a memory allocation HOB, to be added to the HOB list so that later
+//! phases do not reuse the memory.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V3_HOB_Code_Definitions.html#memory-allocation-hob>
+//!
+//! ## Example
+//! ```
+//! use core::mem::size_of;
+//! use mu_pi::{hob, hob::{Hob, HobList}, pei::mem_tracker::MemTracker};
+//! use r_efi::efi;
+//!
+//! let memory = hob::ResourceDescriptor {
+//!     header: hob::header::Hob {
+//!         r#type: hob::RESOURCE_DESCRIPTOR,
+//!         length: size_of::<hob::ResourceDescriptor>() as u16,
+//!         reserved: 0,
+//!     },
+//!     owner: efi::Guid::from_bytes(&[0; 16]),
+//!     resource_type: hob::EFI_RESOURCE_SYSTEM_MEMORY,
+//!     resource_attribute: hob::TESTED_MEMORY_ATTRIBUTES,
+//!     physical_start: 0x100000,
+//!     resource_length: 0x100000,
+//! };
+//! let mut list = HobList::new();
+//! list.push(Hob::ResourceDescriptor(&memory));
+//!
+//! let mut tracker = MemTracker::new(&list);
+//! let base = tracker.allocate_aligned(0x2000, 0x1000, efi::BOOT_SERVICES_DATA).unwrap();
+//! assert_eq!(base, 0x1FE000);
+//! assert_eq!(tracker.allocations()[0].alloc_descriptor.memory_length, 0x2000);
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::{mem::size_of, ops::Range};
+
+use r_efi::{efi, system::MemoryType};
+
+use crate::{
+    address_helper::align_down,
+    hob::{
+        self, EfiPhysicalAddress, Hob, HobList, EFI_RESOURCE_MEMORY_RESERVED, EFI_RESOURCE_SYSTEM_MEMORY,
+        MEMORY_ALLOCATION,
+    },
+};
+
+fn range(base: EfiPhysicalAddress, length: u64) -> Range<u64> {
+    let base = hob::physical_address(base);
+    base..base.saturating_add(length)
+}
+
+// Removes `removed` from the sorted, disjoint `ranges`.
+fn subtract(ranges: &mut Vec<Range<u64>>, removed: Range<u64>) {
+    if removed.is_empty() {
+        return;
+    }
+    let mut remaining = Vec::with_capacity(ranges.len() + 1);
+    for r in ranges.drain(..) {
+        if r.end <= removed.start || removed.end <= r.start {
+            remaining.push(r);
+            continue;
+        }
+        if r.start < removed.start {
+            remaining.push(r.start..removed.start);
+        }
+        if removed.end < r.end {
+            remaining.push(removed.end..r.end);
+        }
+    }
+    *ranges = remaining;
+}
+
+/// Tracks the allocations made from the memory described by a HOB list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemTracker {
+    free: Vec<Range<u64>>,
+    allocations: Vec<hob::MemoryAllocation>,
+}
+
+impl MemTracker {
+    /// Creates a tracker of the memory of `hob_list` not yet in use.
+    pub fn new(hob_list: &HobList) -> Self {
+        let mut free = Vec::new();
+        let mut used = Vec::new();
+        for hob in hob_list.iter() {
+            match hob {
+                Hob::ResourceDescriptor(resource) => {
+                    let resource_range = range(resource.physical_start, resource.resource_length);
+                    match resource.resource_type {
+                        EFI_RESOURCE_SYSTEM_MEMORY => free.push(resource_range),
+                        EFI_RESOURCE_MEMORY_RESERVED => used.push(resource_range),
+                        _ => (),
+                    }
+                }
+                Hob::MemoryAllocation(allocation) => used.push(range(
+                    allocation.alloc_descriptor.memory_base_address,
+                    allocation.alloc_descriptor.memory_length,
+                )),
+                Hob::MemoryAllocationModule(module) => {
+                    used.push(range(module.alloc_descriptor.memory_base_address, module.alloc_descriptor.memory_length))
+                }
+                // The HOB list itself lies between the bottom of the PHIT memory and the bottom of its free memory.
+                Hob::Handoff(phit) => {
+                    used.push(hob::physical_address(phit.memory_bottom)..hob::physical_address(phit.free_memory_bottom))
+                }
+                Hob::FirmwareVolume(fv) => used.push(range(fv.base_address, fv.length)),
+                Hob::FirmwareVolume2(fv) => used.push(range(fv.base_address, fv.length)),
+                Hob::FirmwareVolume3(fv) => used.push(range(fv.base_address, fv.length)),
+                Hob::Capsule(capsule) => used.push(range(capsule.base_address, capsule.length)),
+                _ => (),
+            }
+        }
+
+        free.sort_by_key(|r| r.start);
+        let mut merged: Vec<Range<u64>> = Vec::with_capacity(free.len());
+        for r in free.into_iter().filter(|r| !r.is_empty()) {
+            match merged.last_mut() {
+                Some(last) if r.start <= last.end => last.end = last.end.max(r.end),
+                _ => merged.push(r),
+            }
+        }
+        for r in used {
+            subtract(&mut merged, r);
+        }
+        Self { free: merged, allocations: Vec::new() }
+    }
+
+    /// Allocates `size` bytes aligned to `align` of type `mem_type`, from the highest free memory they fit in.
+    ///
+    /// Returns the base of the allocation, or `None` if `size` is 0, `align` is not a power of two, or no free range
+    /// is large enough.
+    pub fn allocate_aligned(&mut self, size: usize, align: usize, mem_type: MemoryType) -> Option<u64> {
+        if size == 0 || !align.is_power_of_two() {
+            return None;
+        }
+        let (size, align) = (size as u64, align as u64);
+
+        let (index, base) = self.free.iter().enumerate().rev().find_map(|(index, r)| {
+            let base = align_down(r.end.checked_sub(size)?, align);
+            (base >= r.start).then_some((index, base))
+        })?;
+
+        let r = self.free.remove(index);
+        let split = [r.start..base, base + size..r.end];
+        for (offset, part) in split.into_iter().filter(|part| !part.is_empty()).enumerate() {
+            self.free.insert(index + offset, part);
+        }
+
+        self.allocations.push(hob::MemoryAllocation {
+            header: hob::header::Hob {
+                r#type: MEMORY_ALLOCATION,
+                length: size_of::<hob::MemoryAllocation>() as u16,
+                reserved: 0,
+            },
+            alloc_descriptor: hob::header::MemoryAllocation {
+                name: efi::Guid::from_bytes(&[0; 16]),
+                memory_base_address: base as EfiPhysicalAddress,
+                memory_length: size,
+                memory_type: mem_type,
+                reserved: [0; 4],
+            },
+        });
+        Some(base)
+    }
+
+    /// Returns the memory allocation HOBs recording the allocations made, in order.
+    pub fn allocations(&self) -> &[hob::MemoryAllocation] {
+        &self.allocations
+    }
+
+    /// Returns the free memory ranges, in ascending order.
+    pub fn free_ranges(&self) -> &[Range<u64>] {
+        &self.free
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{mem::size_of, slice};
+
+    use r_efi::efi;
+
+    use super::MemTracker;
+    use crate::hob::{self, Hob, HobList};
+
+    fn resource(resource_type: u32, physical_start: u64, resource_length: u64) -> hob::ResourceDescriptor {
+        hob::ResourceDescriptor {
+            header: hob::header::Hob {
+                r#type: hob::RESOURCE_DESCRIPTOR,
+                length: size_of::<hob::ResourceDescriptor>() as u16,
+                reserved: 0,
+            },
+            owner: efi::Guid::from_bytes(&[0; 16]),
+            resource_type,
+            resource_attribute: hob::TESTED_MEMORY_ATTRIBUTES,
+            physical_start,
+            resource_length,
+        }
+    }
+
+    fn phit(memory_bottom: u64, free_memory_bottom: u64) -> hob::PhaseHandoffInformationTable {
+        hob::PhaseHandoffInformationTable {
+            header: hob::header::Hob {
+                r#type: hob::HANDOFF,
+                length: size_of::<hob::PhaseHandoffInformationTable>() as u16,
+                reserved: 0,
+            },
+            version: 0x0009,
+            boot_mode: 0,
+            memory_top: 0x1100_0000,
+            memory_bottom,
+            free_memory_top: 0x1100_0000,
+            free_memory_bottom,
+            end_of_hob_list: free_memory_bottom,
+        }
+    }
+
+    fn fv(base_address: u64, length: u64) -> hob::FirmwareVolume {
+        hob::FirmwareVolume {
+            header: hob::header::Hob { r#type: hob::FV, length: size_of::<hob::FirmwareVolume>() as u16, reserved: 0 },
+            base_address,
+            length,
+        }
+    }
+
+    // 16 MB of system memory at 0x1000_0000, holding a firmware volume, the HOB list, a previous allocation and a
+    // reserved range. Another system memory range at 0x2000_0000 is fully reserved.
+    struct Platform {
+        phit: hob::PhaseHandoffInformationTable,
+        memory: [hob::ResourceDescriptor; 2],
+        reserved: [hob::ResourceDescriptor; 2],
+        fv: hob::FirmwareVolume,
+        allocation: hob::MemoryAllocation,
+    }
+
+    impl Platform {
+        fn new() -> Self {
+            // An allocation made by a previous tracker, as the HOB list would hold it.
+            let region = resource(hob::EFI_RESOURCE_SYSTEM_MEMORY, 0x10E0_0000, 0x10_0000);
+            let mut list = HobList::new();
+            list.push(Hob::ResourceDescriptor(&region));
+            let mut previous = MemTracker::new(&list);
+            previous.allocate_aligned(0x10_0000, 0x1000, efi::RUNTIME_SERVICES_DATA).unwrap();
+            Self {
+                phit: phit(0x1010_0000, 0x1011_0000),
+                memory: [
+                    resource(hob::EFI_RESOURCE_SYSTEM_MEMORY, 0x1000_0000, 0x100_0000),
+                    resource(hob::EFI_RESOURCE_SYSTEM_MEMORY, 0x2000_0000, 0x1000),
+                ],
+                reserved: [
+                    resource(hob::EFI_RESOURCE_MEMORY_RESERVED, 0x10F0_0000, 0x10_0000),
+                    resource(hob::EFI_RESOURCE_MEMORY_RESERVED, 0x2000_0000, 0x1000),
+                ],
+                fv: fv(0x1000_0000, 0x10_0000),
+                allocation: previous.allocations()[0],
+            }
+        }
+
+        fn hob_list(&self) -> HobList {
+            let mut list = HobList::new();
+            list.push(Hob::Handoff(&self.phit));
+            list.push(Hob::ResourceDescriptor(&self.memory[0]));
+            list.push(Hob::ResourceDescriptor(&self.memory[1]));
+            list.push(Hob::ResourceDescriptor(&self.reserved[0]));
+            list.push(Hob::ResourceDescriptor(&self.reserved[1]));
+            list.push(Hob::FirmwareVolume(&self.fv));
+            list.push(Hob::MemoryAllocation(&self.allocation));
+            list
+        }
+    }
+
+    #[test]
+    fn new_should_exclude_used_memory() {
+        let platform = Platform::new();
+        let tracker = MemTracker::new(&platform.hob_list());
+        assert_eq!(tracker.free_ranges(), slice::from_ref(&(0x1011_0000..0x10E0_0000)));
+        assert!(tracker.allocations().is_empty());
+    }
+
+    #[test]
+    fn allocate_aligned_should_allocate_from_the_top() {
+        let platform = Platform::new();
+        let mut tracker = MemTracker::new(&platform.hob_list());
+
+        assert_eq!(tracker.allocate_aligned(0x3000, 0x1000, efi::BOOT_SERVICES_DATA), Some(0x10DF_D000));
+        assert_eq!(tracker.allocate_aligned(0x1000, 0x10_0000, efi::BOOT_SERVICES_CODE), Some(0x10D0_0000));
+        assert_eq!(tracker.free_ranges(), [0x1011_0000..0x10D0_0000, 0x10D0_1000..0x10DF_D000]);
+
+        let allocations = tracker.allocations();
+        assert_eq!(allocations.len(), 2);
+        assert_eq!(allocations[0].header.r#type, hob::MEMORY_ALLOCATION);
+        assert_eq!(allocations[0].header.length as usize, size_of::<hob::MemoryAllocation>());
+        assert_eq!(allocations[0].alloc_descriptor.memory_base_address, 0x10DF_D000);
+        assert_eq!(allocations[0].alloc_descriptor.memory_length, 0x3000);
+        assert_eq!(allocations[0].alloc_descriptor.memory_type, efi::BOOT_SERVICES_DATA);
+        assert_eq!(allocations[1].alloc_descriptor.memory_type, efi::BOOT_SERVICES_CODE);
+    }
+
+    #[test]
+    fn allocate_aligned_should_reject_what_it_cannot_satisfy() {
+        let platform = Platform::new();
+        let mut tracker = MemTracker::new(&platform.hob_list());
+
+        assert_eq!(tracker.allocate_aligned(0, 0x1000, efi::BOOT_SERVICES_DATA), None);
+        assert_eq!(tracker.allocate_aligned(0x1000, 0x1800, efi::BOOT_SERVICES_DATA), None);
+        assert_eq!(tracker.allocate_aligned(0xD0_0000, 0x1000, efi::BOOT_SERVICES_DATA), None);
+        assert!(tracker.allocations().is_empty());
+
+        assert_eq!(tracker.allocate_aligned(0xCF_0000, 0x1000, efi::BOOT_SERVICES_DATA), Some(0x1011_0000));
+        assert!(tracker.free_ranges().is_empty());
+        assert_eq!(tracker.allocate_aligned(1, 1, efi::BOOT_SERVICES_DATA), None);
+    }
+
+    #[test]
+    fn recorded_allocations_should_not_be_reused() {
+        let platform = Platform::new();
+        let mut tracker = MemTracker::new(&platform.hob_list());
+        tracker.allocate_aligned(0x6D_0000, 0x1000, efi::BOOT_SERVICES_DATA).unwrap();
+
+        let mut list = platform.hob_list();
+        let allocation = tracker.allocations()[0];
+        list.push(Hob::MemoryAllocation(&allocation));
+        assert_eq!(MemTracker::new(&list).free_ranges(), slice::from_ref(&(0x1011_0000..0x1073_0000)));
+    }
+}
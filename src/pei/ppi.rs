@@ -0,0 +1,418 @@
+//! PPI Descriptor Lists
+//!
+//! Static, terminated lists of PPI and notify descriptors, built with [`ppi_list!`](crate::ppi_list) and
+//! [`notify_list!`](crate::notify_list), and [`register_notify`] to call a Rust function when a PPI is installed.
+//!
+//! The lists are meant to initialize statics: their flags are checked when the static is evaluated at compile time,
+//! and every GUID and PPI they point to must be `'static`.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V1_PEI_Services.html#ppi-services>
+//!
+//! ## Example
+//! ```
+//! use mu_pi::{
+//!     pei::{ppi::PpiList, services::PPI_DESCRIPTOR_PPI},
+//!     ppi_list,
+//! };
+//! use r_efi::efi;
+//!
+//! pub const MY_PPI_GUID: efi::Guid =
+//!     efi::Guid::from_fields(0x3a6d5ea1, 0x3d1f, 0x4f02, 0x85, 0x1b, &[0x6b, 0x0f, 0x2e, 0x4c, 0x91, 0x7a]);
+//!
+//! #[repr(C)]
+//! pub struct MyPpi {
+//!     pub revision: u32,
+//! }
+//!
+//! static MY_PPI: MyPpi = MyPpi { revision: 1 };
+//! static PPI_LIST: PpiList<1> = ppi_list![(PPI_DESCRIPTOR_PPI, &MY_PPI_GUID, &MY_PPI)];
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{
+    cell::UnsafeCell,
+    ffi::c_void,
+    fmt, ptr,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use r_efi::efi;
+
+use super::{
+    services::{
+        NotifyDescriptor, NotifyEntryPoint, PeiServicesPointer, PpiDescriptor, PPI_DESCRIPTOR_NOTIFY_TYPES,
+        PPI_DESCRIPTOR_PPI, PPI_DESCRIPTOR_TERMINATE_LIST,
+    },
+    PeiServices,
+};
+
+/// A PPI descriptor pointing to a `'static` GUID and PPI.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy)]
+pub struct PpiEntry(PpiDescriptor);
+
+impl PpiEntry {
+    /// Creates the descriptor of `ppi`.
+    ///
+    /// Panics if `flags` does not include PPI_DESCRIPTOR_PPI or includes PPI_DESCRIPTOR_TERMINATE_LIST, which
+    /// [`PpiList::new`] sets.
+    pub const fn new<T: Sync>(flags: usize, guid: &'static efi::Guid, ppi: &'static T) -> Self {
+        assert!(flags & PPI_DESCRIPTOR_PPI != 0, "PPI descriptors must have the PPI_DESCRIPTOR_PPI flag");
+        assert!(flags & PPI_DESCRIPTOR_TERMINATE_LIST == 0, "the terminate flag is set by the list");
+        Self(PpiDescriptor { flags, guid, ppi: ppi as *const T as *mut c_void })
+    }
+
+    /// Returns the descriptor.
+    pub const fn descriptor(&self) -> &PpiDescriptor {
+        &self.0
+    }
+}
+
+/// A notify descriptor pointing to a `'static` GUID.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy)]
+pub struct NotifyEntry(NotifyDescriptor);
+
+impl NotifyEntry {
+    /// Creates the descriptor of a notification calling `notify`.
+    ///
+    /// Panics if `flags` includes no notification type or includes PPI_DESCRIPTOR_TERMINATE_LIST, which
+    /// [`NotifyList::new`] sets.
+    pub const fn new(flags: usize, guid: &'static efi::Guid, notify: NotifyEntryPoint) -> Self {
+        assert!(flags & PPI_DESCRIPTOR_NOTIFY_TYPES != 0, "notify descriptors must have a notification type flag");
+        assert!(flags & PPI_DESCRIPTOR_TERMINATE_LIST == 0, "the terminate flag is set by the list");
+        Self(NotifyDescriptor { flags, guid, notify })
+    }
+
+    /// Returns the descriptor.
+    pub const fn descriptor(&self) -> &NotifyDescriptor {
+        &self.0
+    }
+}
+
+/// A list of PPI descriptors whose last descriptor has the terminate flag, laid out as `[EFI_PEI_PPI_DESCRIPTOR; N]`.
+#[repr(transparent)]
+#[derive(Debug)]
+pub struct PpiList<const N: usize>([PpiEntry; N]);
+
+//Safety: the entries only point to 'static GUIDs and Sync PPIs.
+unsafe impl<const N: usize> Sync for PpiList<N> {}
+
+impl<const N: usize> PpiList<N> {
+    /// Creates a list of `entries`, setting the terminate flag of the last one.
+    ///
+    /// Panics if `entries` is empty.
+    pub const fn new(mut entries: [PpiEntry; N]) -> Self {
+        assert!(N > 0, "a PPI list needs at least one descriptor");
+        entries[N - 1].0.flags |= PPI_DESCRIPTOR_TERMINATE_LIST;
+        Self(entries)
+    }
+
+    /// Returns the descriptors of the list.
+    pub fn descriptors(&self) -> &[PpiDescriptor; N] {
+        //Safety: PpiEntry is a transparent PpiDescriptor.
+        unsafe { &*(self as *const Self as *const [PpiDescriptor; N]) }
+    }
+
+    /// Returns a pointer to the first descriptor, for InstallPpi() and ReInstallPpi().
+    pub const fn as_ptr(&self) -> *const PpiDescriptor {
+        self.0.as_ptr() as *const PpiDescriptor
+    }
+}
+
+/// A list of notify descriptors whose last descriptor has the terminate flag, laid out as
+/// `[EFI_PEI_NOTIFY_DESCRIPTOR; N]`.
+#[repr(transparent)]
+#[derive(Debug)]
+pub struct NotifyList<const N: usize>([NotifyEntry; N]);
+
+//Safety: the entries only point to 'static GUIDs.
+unsafe impl<const N: usize> Sync for NotifyList<N> {}
+
+impl<const N: usize> NotifyList<N> {
+    /// Creates a list of `entries`, setting the terminate flag of the last one.
+    ///
+    /// Panics if `entries` is empty.
+    pub const fn new(mut entries: [NotifyEntry; N]) -> Self {
+        assert!(N > 0, "a notify list needs at least one descriptor");
+        entries[N - 1].0.flags |= PPI_DESCRIPTOR_TERMINATE_LIST;
+        Self(entries)
+    }
+
+    /// Returns the descriptors of the list.
+    pub fn descriptors(&self) -> &[NotifyDescriptor; N] {
+        //Safety: NotifyEntry is a transparent NotifyDescriptor.
+        unsafe { &*(self as *const Self as *const [NotifyDescriptor; N]) }
+    }
+
+    /// Returns a pointer to the first descriptor, for NotifyPpi().
+    pub const fn as_ptr(&self) -> *const NotifyDescriptor {
+        self.0.as_ptr() as *const NotifyDescriptor
+    }
+}
+
+/// Builds a [`PpiList`] from `(flags, &GUID, &PPI)` tuples, setting the terminate flag of the last descriptor.
+///
+/// Used to initialize a static, it fails to compile if a descriptor lacks PPI_DESCRIPTOR_PPI or sets
+/// PPI_DESCRIPTOR_TERMINATE_LIST, or if a GUID or PPI reference is not `'static`.
+///
+/// ```compile_fail
+/// use mu_pi::{
+///     pei::{ppi::PpiList, services::{PPI_DESCRIPTOR_PPI, PPI_DESCRIPTOR_TERMINATE_LIST}},
+///     ppi_list,
+/// };
+/// use r_efi::efi;
+///
+/// const GUID: efi::Guid = efi::Guid::from_fields(0, 0, 0, 0, 0, &[0, 0, 0, 0, 0, 1]);
+/// static PPI: u32 = 0;
+/// static LIST: PpiList<1> = ppi_list![(PPI_DESCRIPTOR_PPI | PPI_DESCRIPTOR_TERMINATE_LIST, &GUID, &PPI)];
+/// ```
+#[macro_export]
+macro_rules! ppi_list {
+    ($(($flags:expr, $guid:expr, $ppi:expr $(,)?)),+ $(,)?) => {
+        $crate::pei::ppi::PpiList::new([$($crate::pei::ppi::PpiEntry::new($flags, $guid, $ppi)),+])
+    };
+}
+
+/// Builds a [`NotifyList`] from `(flags, &GUID, notify)` tuples, setting the terminate flag of the last descriptor.
+///
+/// Used to initialize a static, it fails to compile if a descriptor has no notification type or sets
+/// PPI_DESCRIPTOR_TERMINATE_LIST, or if a GUID reference is not `'static`.
+#[macro_export]
+macro_rules! notify_list {
+    ($(($flags:expr, $guid:expr, $notify:expr $(,)?)),+ $(,)?) => {
+        $crate::pei::ppi::NotifyList::new([$($crate::pei::ppi::NotifyEntry::new($flags, $guid, $notify)),+])
+    };
+}
+
+/// A Rust function called with the services and the PPI when a PPI registered with [`register_notify`] is installed.
+pub type NotifyCallback = fn(services: &PeiServices<'_>, ppi: *mut c_void) -> efi::Status;
+
+// The descriptor registered with NotifyPpi(), followed by the callback the trampoline finds through it.
+#[repr(C)]
+struct Registration {
+    descriptor: NotifyDescriptor,
+    callback: Option<NotifyCallback>,
+}
+
+/// The static storage of a notification registered with [`register_notify`].
+pub struct NotifyCell {
+    registered: AtomicBool,
+    registration: UnsafeCell<Registration>,
+}
+
+//Safety: the registration is only written by the register_notify() call that claimed the cell, before the
+// notification can be called.
+unsafe impl Sync for NotifyCell {}
+
+impl NotifyCell {
+    /// Creates an empty cell.
+    pub const fn new() -> Self {
+        Self {
+            registered: AtomicBool::new(false),
+            registration: UnsafeCell::new(Registration {
+                descriptor: NotifyDescriptor { flags: 0, guid: ptr::null(), notify: trampoline },
+                callback: None,
+            }),
+        }
+    }
+
+    /// Returns whether a notification was registered with the cell.
+    pub fn is_registered(&self) -> bool {
+        self.registered.load(Ordering::Acquire)
+    }
+}
+
+impl Default for NotifyCell {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for NotifyCell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NotifyCell").field("registered", &self.is_registered()).finish()
+    }
+}
+
+extern "efiapi" fn trampoline(
+    services: PeiServicesPointer,
+    notify_descriptor: *const NotifyDescriptor,
+    ppi: *mut c_void,
+) -> efi::Status {
+    //Safety: the PEI Foundation passes back the descriptor registered by register_notify(), the first field of the
+    //Registration in a NotifyCell.
+    let registration = unsafe { &*(notify_descriptor as *const Registration) };
+    match registration.callback {
+        //Safety: the PEI Foundation passes its valid services table.
+        Some(callback) => callback(&unsafe { PeiServices::new(services) }, ppi),
+        None => efi::Status::NOT_READY,
+    }
+}
+
+/// Registers `callback` to be called when the PPI `guid` is installed, storing the notification in `cell`.
+///
+/// `flags` selects the notification type and must not include PPI_DESCRIPTOR_TERMINATE_LIST. Fails with
+/// INVALID_PARAMETER for other flags, with ALREADY_STARTED if `cell` already holds a notification, or with the status
+/// returned by NotifyPpi().
+pub fn register_notify(
+    services: &PeiServices<'_>,
+    cell: &'static NotifyCell,
+    flags: usize,
+    guid: &'static efi::Guid,
+    callback: NotifyCallback,
+) -> Result<(), efi::Status> {
+    if flags & PPI_DESCRIPTOR_NOTIFY_TYPES == 0 || flags & PPI_DESCRIPTOR_TERMINATE_LIST != 0 {
+        Err(efi::Status::INVALID_PARAMETER)?;
+    }
+    if cell.registered.swap(true, Ordering::AcqRel) {
+        Err(efi::Status::ALREADY_STARTED)?;
+    }
+
+    //Safety: the cell was just claimed, and its notification is not registered yet.
+    let registration = unsafe { &mut *cell.registration.get() };
+    *registration = Registration {
+        descriptor: NotifyDescriptor { flags: flags | PPI_DESCRIPTOR_TERMINATE_LIST, guid, notify: trampoline },
+        callback: Some(callback),
+    };
+    match (services.table().notify_ppi)(services.as_ptr(), &registration.descriptor) {
+        efi::Status::SUCCESS => Ok(()),
+        status => {
+            cell.registered.store(false, Ordering::Release);
+            Err(status)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        cell::Cell,
+        ffi::c_void,
+        mem::size_of,
+        sync::atomic::{AtomicU32, Ordering},
+    };
+
+    use r_efi::efi;
+
+    use super::{register_notify, NotifyCell, NotifyList, PpiEntry, PpiList};
+    use crate::pei::{
+        services::{
+            self, mock, NotifyDescriptor, PeiServicesPointer, PPI_DESCRIPTOR_NOTIFY_CALLBACK,
+            PPI_DESCRIPTOR_NOTIFY_DISPATCH, PPI_DESCRIPTOR_PPI, PPI_DESCRIPTOR_TERMINATE_LIST,
+        },
+        PeiServices,
+    };
+
+    const GUID_A: efi::Guid = efi::Guid::from_fields(0x1, 0x2, 0x3, 0x4, 0x5, &[0x6, 0x7, 0x8, 0x9, 0xa, 0xb]);
+    const GUID_B: efi::Guid = efi::Guid::from_fields(0xb, 0xa, 0x9, 0x8, 0x7, &[0x6, 0x5, 0x4, 0x3, 0x2, 0x1]);
+
+    static PPI_A: u32 = 0xa;
+    static PPI_B: u64 = 0xb;
+
+    static PPI_LIST: PpiList<2> =
+        ppi_list![(PPI_DESCRIPTOR_PPI, &GUID_A, &PPI_A), (PPI_DESCRIPTOR_PPI, &GUID_B, &PPI_B)];
+
+    extern "efiapi" fn notify(_: PeiServicesPointer, _: *const NotifyDescriptor, _: *mut c_void) -> efi::Status {
+        efi::Status::SUCCESS
+    }
+
+    static NOTIFY_LIST: NotifyList<2> = notify_list![
+        (PPI_DESCRIPTOR_NOTIFY_CALLBACK, &GUID_A, notify),
+        (PPI_DESCRIPTOR_NOTIFY_DISPATCH, &GUID_B, notify),
+    ];
+
+    #[test]
+    fn ppi_list_should_terminate_the_last_descriptor() {
+        assert_eq!(size_of::<PpiList<2>>(), 6 * size_of::<usize>());
+        let descriptors = PPI_LIST.descriptors();
+        assert_eq!(PPI_LIST.as_ptr(), descriptors.as_ptr());
+        assert_eq!(descriptors[0].flags, PPI_DESCRIPTOR_PPI);
+        assert_eq!(descriptors[0].guid, &GUID_A as *const efi::Guid);
+        assert_eq!(descriptors[0].ppi as *const u32, &PPI_A as *const u32);
+        assert_eq!(descriptors[1].flags, PPI_DESCRIPTOR_PPI | PPI_DESCRIPTOR_TERMINATE_LIST);
+        assert_eq!(descriptors[1].ppi as *const u64, &PPI_B as *const u64);
+    }
+
+    #[test]
+    fn notify_list_should_terminate_the_last_descriptor() {
+        assert_eq!(size_of::<NotifyList<2>>(), 6 * size_of::<usize>());
+        let descriptors = NOTIFY_LIST.descriptors();
+        assert_eq!(NOTIFY_LIST.as_ptr(), descriptors.as_ptr());
+        assert_eq!(descriptors[0].flags, PPI_DESCRIPTOR_NOTIFY_CALLBACK);
+        assert_eq!(descriptors[1].flags, PPI_DESCRIPTOR_NOTIFY_DISPATCH | PPI_DESCRIPTOR_TERMINATE_LIST);
+        assert_eq!(descriptors[1].guid, &GUID_B as *const efi::Guid);
+    }
+
+    #[test]
+    #[should_panic(expected = "the terminate flag is set by the list")]
+    fn ppi_entry_should_reject_the_terminate_flag() {
+        PpiEntry::new(PPI_DESCRIPTOR_PPI | PPI_DESCRIPTOR_TERMINATE_LIST, &GUID_A, &PPI_A);
+    }
+
+    thread_local! {
+        static NOTIFY_PPI_CALLS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    // Calls the notification right away, as the PEI Foundation does when the PPI is already installed.
+    extern "efiapi" fn notify_ppi(services: PeiServicesPointer, notify_list: *const NotifyDescriptor) -> efi::Status {
+        NOTIFY_PPI_CALLS.with(|calls| calls.set(calls.get() + 1));
+        let descriptor = unsafe { &*notify_list };
+        assert_eq!(descriptor.flags, PPI_DESCRIPTOR_NOTIFY_CALLBACK | PPI_DESCRIPTOR_TERMINATE_LIST);
+        assert_eq!(descriptor.guid, &GUID_A as *const efi::Guid);
+        (descriptor.notify)(services, notify_list, &PPI_A as *const u32 as *mut c_void)
+    }
+
+    static CALLBACK_PPI: AtomicU32 = AtomicU32::new(0);
+
+    fn callback(services: &PeiServices<'_>, ppi: *mut c_void) -> efi::Status {
+        assert!(!services.as_ptr().is_null());
+        CALLBACK_PPI.store(unsafe { *(ppi as *const u32) }, Ordering::SeqCst);
+        efi::Status::SUCCESS
+    }
+
+    #[test]
+    fn register_notify_should_dispatch_to_the_callback() {
+        static CELL: NotifyCell = NotifyCell::new();
+
+        let table = services::PeiServicesTable { notify_ppi, ..mock::services() };
+        let table_ptr: *const services::PeiServicesTable = &table;
+        let services = unsafe { PeiServices::new(&table_ptr) };
+
+        assert_eq!(
+            register_notify(&services, &CELL, PPI_DESCRIPTOR_PPI, &GUID_A, callback),
+            Err(efi::Status::INVALID_PARAMETER)
+        );
+        assert!(!CELL.is_registered());
+
+        assert_eq!(register_notify(&services, &CELL, PPI_DESCRIPTOR_NOTIFY_CALLBACK, &GUID_A, callback), Ok(()));
+        assert!(CELL.is_registered());
+        assert_eq!(CALLBACK_PPI.load(Ordering::SeqCst), PPI_A);
+
+        assert_eq!(
+            register_notify(&services, &CELL, PPI_DESCRIPTOR_NOTIFY_CALLBACK, &GUID_A, callback),
+            Err(efi::Status::ALREADY_STARTED)
+        );
+        assert_eq!(NOTIFY_PPI_CALLS.with(Cell::get), 1);
+    }
+
+    #[test]
+    fn failed_registration_should_release_the_cell() {
+        static CELL: NotifyCell = NotifyCell::new();
+
+        let table = mock::services();
+        let table_ptr: *const services::PeiServicesTable = &table;
+        let services = unsafe { PeiServices::new(&table_ptr) };
+        assert_eq!(
+            register_notify(&services, &CELL, PPI_DESCRIPTOR_NOTIFY_DISPATCH, &GUID_B, callback),
+            Err(efi::Status::UNSUPPORTED)
+        );
+        assert!(!CELL.is_registered());
+    }
+}
@@ -0,0 +1,524 @@
+//! PEI Services Table
+//!
+//! The table of services the PEI Foundation provides to PEIMs (EFI_PEI_SERVICES). PEIMs receive a pointer to a pointer
+//! to the table in their entry point, and pass it back as the first argument of most services.
+//! [`PeiServices`](crate::pei::PeiServices) wraps the [`PeiServicesTable`] with safe methods.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V1_PEI_Services_Table.html>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::ffi::c_void;
+
+use r_efi::{
+    efi::{Guid, PhysicalAddress, Status},
+    system::{MemoryType, ResetType, TableHeader},
+};
+
+use crate::{
+    hob::EfiBootMode,
+    protocols::status_code::EfiStatusCodeData,
+    status_code::{StatusCodeType, StatusCodeValue},
+};
+
+/// Signature of the PEI Services Table header ("PEI SERV").
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-4.1
+pub const PEI_SERVICES_SIGNATURE: u64 = 0x5652455320494550;
+
+/// Revision of the PEI Services Table header for this version of the PI Specification.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-4.1
+pub const PEI_SERVICES_REVISION: u32 = (1 << 16) | 80;
+
+/// The pointer to the pointer to the PEI Services Table passed to PEIMs and to most services.
+pub type PeiServicesPointer = *const *const PeiServicesTable;
+
+/// A handle of a firmware volume (EFI_PEI_FV_HANDLE).
+pub type FvHandle = *mut c_void;
+
+/// A handle of a file in a firmware volume (EFI_PEI_FILE_HANDLE).
+pub type FileHandle = *mut c_void;
+
+/// The descriptor describes a PPI.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.1.1
+pub const PPI_DESCRIPTOR_PPI: usize = 0x10;
+/// The descriptor describes a notification called when its PPI is installed.
+pub const PPI_DESCRIPTOR_NOTIFY_CALLBACK: usize = 0x20;
+/// The descriptor describes a notification called when the PEI Dispatcher next runs after its PPI is installed.
+pub const PPI_DESCRIPTOR_NOTIFY_DISPATCH: usize = 0x40;
+/// The flags of the notification types.
+pub const PPI_DESCRIPTOR_NOTIFY_TYPES: usize = PPI_DESCRIPTOR_NOTIFY_CALLBACK | PPI_DESCRIPTOR_NOTIFY_DISPATCH;
+/// The descriptor is the last of its list.
+pub const PPI_DESCRIPTOR_TERMINATE_LIST: usize = 0x8000_0000;
+
+/// Describes a PPI and its GUID (EFI_PEI_PPI_DESCRIPTOR).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.1.1
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PpiDescriptor {
+    /// The PPI_DESCRIPTOR_* flags of the descriptor.
+    pub flags: usize,
+    pub guid: *const Guid,
+    pub ppi: *mut c_void,
+}
+
+/// Called when a PPI a notification was registered for is installed.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.1.4
+pub type NotifyEntryPoint =
+    extern "efiapi" fn(PeiServicesPointer, notify_descriptor: *const NotifyDescriptor, ppi: *mut c_void) -> Status;
+
+/// Describes a notification for the installation of a PPI (EFI_PEI_NOTIFY_DESCRIPTOR).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.1.4
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct NotifyDescriptor {
+    /// The PPI_DESCRIPTOR_* flags of the descriptor.
+    pub flags: usize,
+    pub guid: *const Guid,
+    pub notify: NotifyEntryPoint,
+}
+
+/// Installs the PPIs of a descriptor list.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.1.1
+pub type InstallPpi = extern "efiapi" fn(PeiServicesPointer, ppi_list: *const PpiDescriptor) -> Status;
+
+/// Replaces an installed PPI.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.1.2
+pub type ReInstallPpi =
+    extern "efiapi" fn(PeiServicesPointer, old_ppi: *const PpiDescriptor, new_ppi: *const PpiDescriptor) -> Status;
+
+/// Locates an instance of a PPI.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.1.3
+pub type LocatePpi = extern "efiapi" fn(
+    PeiServicesPointer,
+    guid: *const Guid,
+    instance: usize,
+    ppi_descriptor: *mut *mut PpiDescriptor,
+    ppi: *mut *mut c_void,
+) -> Status;
+
+/// Registers notifications for the installation of PPIs.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.1.4
+pub type NotifyPpi = extern "efiapi" fn(PeiServicesPointer, notify_list: *const NotifyDescriptor) -> Status;
+
+/// Returns the boot mode of the platform.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.2.1
+pub type GetBootMode = extern "efiapi" fn(PeiServicesPointer, boot_mode: *mut EfiBootMode) -> Status;
+
+/// Sets the boot mode of the platform.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.2.2
+pub type SetBootMode = extern "efiapi" fn(PeiServicesPointer, boot_mode: EfiBootMode) -> Status;
+
+/// Returns the HOB list.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.3.1
+pub type GetHobList = extern "efiapi" fn(PeiServicesPointer, hob_list: *mut *mut c_void) -> Status;
+
+/// Adds a HOB of a type and length to the HOB list.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.3.2
+pub type CreateHob = extern "efiapi" fn(PeiServicesPointer, r#type: u16, length: u16, hob: *mut *mut c_void) -> Status;
+
+/// Returns a firmware volume known to the PEI Foundation.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.4.1
+pub type FfsFindNextVolume2 =
+    extern "efiapi" fn(PeiServicesPointer, instance: usize, volume_handle: *mut FvHandle) -> Status;
+
+/// Returns the next file of a type in a firmware volume.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.4.2
+pub type FfsFindNextFile2 = extern "efiapi" fn(
+    PeiServicesPointer,
+    search_type: u8,
+    volume_handle: FvHandle,
+    file_handle: *mut FileHandle,
+) -> Status;
+
+/// Returns the data of the first section of a type in a file.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.4.3
+pub type FfsFindSectionData2 = extern "efiapi" fn(
+    PeiServicesPointer,
+    section_type: u8,
+    file_handle: FileHandle,
+    section_data: *mut *mut c_void,
+) -> Status;
+
+/// Registers the permanent memory.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.5.1
+pub type InstallPeiMemory =
+    extern "efiapi" fn(PeiServicesPointer, memory_begin: PhysicalAddress, memory_length: u64) -> Status;
+
+/// Allocates pages of memory, recorded in a memory allocation HOB.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.5.2
+pub type AllocatePages = extern "efiapi" fn(
+    PeiServicesPointer,
+    memory_type: MemoryType,
+    pages: usize,
+    memory: *mut PhysicalAddress,
+) -> Status;
+
+/// Allocates memory from a memory pool HOB. Pool memory cannot be freed.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.5.4
+pub type AllocatePool = extern "efiapi" fn(PeiServicesPointer, size: usize, buffer: *mut *mut c_void) -> Status;
+
+/// Copies bytes between buffers.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.5.5
+pub type CopyMem = extern "efiapi" fn(destination: *mut c_void, source: *mut c_void, length: usize);
+
+/// Fills a buffer with a byte.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.5.6
+pub type SetMem = extern "efiapi" fn(buffer: *mut c_void, size: usize, value: u8);
+
+/// Reports a status code.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.6.1
+pub type ReportStatusCode = extern "efiapi" fn(
+    PeiServicesPointer,
+    r#type: StatusCodeType,
+    value: StatusCodeValue,
+    instance: u32,
+    caller_id: *const Guid,
+    data: *const EfiStatusCodeData,
+) -> Status;
+
+/// Resets the platform.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.7.1
+pub type ResetSystem = extern "efiapi" fn(PeiServicesPointer) -> Status;
+
+/// Returns the file of a name in a firmware volume.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.4.5
+pub type FfsFindFileByName =
+    extern "efiapi" fn(file_name: *const Guid, volume_handle: FvHandle, file_handle: *mut FileHandle) -> Status;
+
+/// Returns information about a file (EFI_FV_FILE_INFO).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.4.6
+pub type FfsGetFileInfo = extern "efiapi" fn(file_handle: FileHandle, file_info: *mut c_void) -> Status;
+
+/// Returns information about a firmware volume (EFI_FV_INFO).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.4.8
+pub type FfsGetVolumeInfo = extern "efiapi" fn(volume_handle: FvHandle, volume_info: *mut c_void) -> Status;
+
+/// Registers the PEIM of a file to be called again once memory is installed.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.4.9
+pub type RegisterForShadow = extern "efiapi" fn(file_handle: FileHandle) -> Status;
+
+/// Returns the data and authentication status of an instance of a section of a type in a file.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.4.4
+pub type FfsFindSectionData3 = extern "efiapi" fn(
+    PeiServicesPointer,
+    section_type: u8,
+    section_instance: usize,
+    file_handle: FileHandle,
+    section_data: *mut *mut c_void,
+    authentication_status: *mut u32,
+) -> Status;
+
+/// Returns information about a file, with its authentication status (EFI_FV_FILE_INFO2).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.4.7
+pub type FfsGetFileInfo2 = extern "efiapi" fn(file_handle: FileHandle, file_info: *mut c_void) -> Status;
+
+/// Resets the platform with a reset type.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.7.2
+pub type ResetSystem2 =
+    extern "efiapi" fn(reset_type: ResetType, reset_status: Status, data_size: usize, reset_data: *mut c_void);
+
+/// Frees pages allocated with AllocatePages().
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-5.5.3
+pub type FreePages = extern "efiapi" fn(PeiServicesPointer, memory: PhysicalAddress, pages: usize) -> Status;
+
+/// The PEI Services Table (EFI_PEI_SERVICES).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-4.1
+#[repr(C)]
+#[derive(Debug)]
+pub struct PeiServicesTable {
+    pub hdr: TableHeader,
+
+    // PPI Functions
+    pub install_ppi: InstallPpi,
+    pub re_install_ppi: ReInstallPpi,
+    pub locate_ppi: LocatePpi,
+    pub notify_ppi: NotifyPpi,
+
+    // Boot Mode Functions
+    pub get_boot_mode: GetBootMode,
+    pub set_boot_mode: SetBootMode,
+
+    // HOB Functions
+    pub get_hob_list: GetHobList,
+    pub create_hob: CreateHob,
+
+    // Firmware Volume Functions
+    pub ffs_find_next_volume: FfsFindNextVolume2,
+    pub ffs_find_next_file: FfsFindNextFile2,
+    pub ffs_find_section_data: FfsFindSectionData2,
+
+    // PEI Memory Functions
+    pub install_pei_memory: InstallPeiMemory,
+    pub allocate_pages: AllocatePages,
+    pub allocate_pool: AllocatePool,
+    pub copy_mem: CopyMem,
+    pub set_mem: SetMem,
+
+    // Status Code
+    pub report_status_code: ReportStatusCode,
+
+    // Reset
+    pub reset_system: ResetSystem,
+
+    // Installed by PEIMs: EFI_PEI_CPU_IO_PPI and EFI_PEI_PCI_CFG2_PPI.
+    pub cpu_io: *const c_void,
+    pub pci_cfg: *const c_void,
+
+    // Future Installed Services
+    pub ffs_find_file_by_name: FfsFindFileByName,
+    pub ffs_get_file_info: FfsGetFileInfo,
+    pub ffs_get_volume_info: FfsGetVolumeInfo,
+    pub register_for_shadow: RegisterForShadow,
+    pub find_section_data3: FfsFindSectionData3,
+    pub ffs_get_file_info2: FfsGetFileInfo2,
+    pub reset_system2: ResetSystem2,
+    pub free_pages: FreePages,
+}
+
+#[cfg(test)]
+pub(crate) mod mock {
+    //! A PEI Services Table whose services all return UNSUPPORTED, for tests to override.
+
+    use core::{ffi::c_void, ptr};
+
+    use r_efi::{
+        efi::{Guid, PhysicalAddress, Status},
+        system::{MemoryType, ResetType, TableHeader},
+    };
+
+    use super::{
+        FileHandle, FvHandle, NotifyDescriptor, PeiServicesPointer, PeiServicesTable, PpiDescriptor,
+        PEI_SERVICES_REVISION, PEI_SERVICES_SIGNATURE,
+    };
+    use crate::{
+        hob::EfiBootMode,
+        protocols::status_code::EfiStatusCodeData,
+        status_code::{StatusCodeType, StatusCodeValue},
+    };
+
+    extern "efiapi" fn install_ppi(_: PeiServicesPointer, _: *const PpiDescriptor) -> Status {
+        Status::UNSUPPORTED
+    }
+    extern "efiapi" fn re_install_ppi(
+        _: PeiServicesPointer,
+        _: *const PpiDescriptor,
+        _: *const PpiDescriptor,
+    ) -> Status {
+        Status::UNSUPPORTED
+    }
+    extern "efiapi" fn locate_ppi(
+        _: PeiServicesPointer,
+        _: *const Guid,
+        _: usize,
+        _: *mut *mut PpiDescriptor,
+        _: *mut *mut c_void,
+    ) -> Status {
+        Status::UNSUPPORTED
+    }
+    extern "efiapi" fn notify_ppi(_: PeiServicesPointer, _: *const NotifyDescriptor) -> Status {
+        Status::UNSUPPORTED
+    }
+    extern "efiapi" fn get_boot_mode(_: PeiServicesPointer, _: *mut EfiBootMode) -> Status {
+        Status::UNSUPPORTED
+    }
+    extern "efiapi" fn set_boot_mode(_: PeiServicesPointer, _: EfiBootMode) -> Status {
+        Status::UNSUPPORTED
+    }
+    extern "efiapi" fn get_hob_list(_: PeiServicesPointer, _: *mut *mut c_void) -> Status {
+        Status::UNSUPPORTED
+    }
+    extern "efiapi" fn create_hob(_: PeiServicesPointer, _: u16, _: u16, _: *mut *mut c_void) -> Status {
+        Status::UNSUPPORTED
+    }
+    extern "efiapi" fn ffs_find_next_volume(_: PeiServicesPointer, _: usize, _: *mut FvHandle) -> Status {
+        Status::UNSUPPORTED
+    }
+    extern "efiapi" fn ffs_find_next_file(_: PeiServicesPointer, _: u8, _: FvHandle, _: *mut FileHandle) -> Status {
+        Status::UNSUPPORTED
+    }
+    extern "efiapi" fn ffs_find_section_data(
+        _: PeiServicesPointer,
+        _: u8,
+        _: FileHandle,
+        _: *mut *mut c_void,
+    ) -> Status {
+        Status::UNSUPPORTED
+    }
+    extern "efiapi" fn install_pei_memory(_: PeiServicesPointer, _: PhysicalAddress, _: u64) -> Status {
+        Status::UNSUPPORTED
+    }
+    extern "efiapi" fn allocate_pages(
+        _: PeiServicesPointer,
+        _: MemoryType,
+        _: usize,
+        _: *mut PhysicalAddress,
+    ) -> Status {
+        Status::UNSUPPORTED
+    }
+    extern "efiapi" fn allocate_pool(_: PeiServicesPointer, _: usize, _: *mut *mut c_void) -> Status {
+        Status::UNSUPPORTED
+    }
+    extern "efiapi" fn copy_mem(_: *mut c_void, _: *mut c_void, _: usize) {}
+    extern "efiapi" fn set_mem(_: *mut c_void, _: usize, _: u8) {}
+    extern "efiapi" fn report_status_code(
+        _: PeiServicesPointer,
+        _: StatusCodeType,
+        _: StatusCodeValue,
+        _: u32,
+        _: *const Guid,
+        _: *const EfiStatusCodeData,
+    ) -> Status {
+        Status::UNSUPPORTED
+    }
+    extern "efiapi" fn reset_system(_: PeiServicesPointer) -> Status {
+        Status::UNSUPPORTED
+    }
+    extern "efiapi" fn ffs_find_file_by_name(_: *const Guid, _: FvHandle, _: *mut FileHandle) -> Status {
+        Status::UNSUPPORTED
+    }
+    extern "efiapi" fn ffs_get_info(_: *mut c_void, _: *mut c_void) -> Status {
+        Status::UNSUPPORTED
+    }
+    extern "efiapi" fn register_for_shadow(_: FileHandle) -> Status {
+        Status::UNSUPPORTED
+    }
+    extern "efiapi" fn find_section_data3(
+        _: PeiServicesPointer,
+        _: u8,
+        _: usize,
+        _: FileHandle,
+        _: *mut *mut c_void,
+        _: *mut u32,
+    ) -> Status {
+        Status::UNSUPPORTED
+    }
+    extern "efiapi" fn reset_system2(_: ResetType, _: Status, _: usize, _: *mut c_void) {}
+    extern "efiapi" fn free_pages(_: PeiServicesPointer, _: PhysicalAddress, _: usize) -> Status {
+        Status::UNSUPPORTED
+    }
+
+    /// Returns a table whose services all return UNSUPPORTED.
+    pub(crate) fn services() -> PeiServicesTable {
+        PeiServicesTable {
+            hdr: TableHeader {
+                signature: PEI_SERVICES_SIGNATURE,
+                revision: PEI_SERVICES_REVISION,
+                header_size: core::mem::size_of::<PeiServicesTable>() as u32,
+                crc32: 0,
+                reserved: 0,
+            },
+            install_ppi,
+            re_install_ppi,
+            locate_ppi,
+            notify_ppi,
+            get_boot_mode,
+            set_boot_mode,
+            get_hob_list,
+            create_hob,
+            ffs_find_next_volume,
+            ffs_find_next_file,
+            ffs_find_section_data,
+            install_pei_memory,
+            allocate_pages,
+            allocate_pool,
+            copy_mem,
+            set_mem,
+            report_status_code,
+            reset_system,
+            cpu_io: ptr::null(),
+            pci_cfg: ptr::null(),
+            ffs_find_file_by_name,
+            ffs_get_file_info: ffs_get_info,
+            ffs_get_volume_info: ffs_get_info,
+            register_for_shadow,
+            find_section_data3,
+            ffs_get_file_info2: ffs_get_info,
+            reset_system2,
+            free_pages,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::size_of;
+
+    use super::PeiServicesTable;
+
+    #[test]
+    fn table_should_match_spec_layout() {
+        // The header, 18 services, 2 PPI pointers and 8 future services.
+        assert_eq!(size_of::<PeiServicesTable>(), 24 + 28 * size_of::<usize>());
+    }
+}
@@ -0,0 +1,14 @@
+//! Power State Support
+//!
+//! Definitions shared by code handling platform sleep state transitions.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V4_SMM_Child_Dispatch_Protocols.html#efi-mm-sx-dispatch-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+pub mod sx_type;
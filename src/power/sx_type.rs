@@ -0,0 +1,117 @@
+//! Sleep State Types
+//!
+//! The ACPI sleep states (EFI_SLEEP_TYPE) and the phase of a transition (EFI_SLEEP_PHASE), as used to register Sx
+//! handlers.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V4_SMM_Child_Dispatch_Protocols.html#efi-mm-sx-dispatch-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::fmt;
+
+/// An ACPI sleep state.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SleepType {
+    /// Working.
+    S0 = 0,
+    /// Power on suspend.
+    S1 = 1,
+    /// Processor powered off.
+    S2 = 2,
+    /// Suspend to RAM.
+    S3 = 3,
+    /// Suspend to disk.
+    S4 = 4,
+    /// Soft off.
+    S5 = 5,
+}
+
+impl TryFrom<u8> for SleepType {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => Self::S0,
+            1 => Self::S1,
+            2 => Self::S2,
+            3 => Self::S3,
+            4 => Self::S4,
+            5 => Self::S5,
+            _ => Err(value)?,
+        })
+    }
+}
+
+/// Displays the state name, e.g. `S3`.
+impl fmt::Display for SleepType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "S{}", *self as u8)
+    }
+}
+
+/// The phase of a sleep state transition.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SleepPhase {
+    /// The platform is entering the state.
+    Entry = 0,
+    /// The platform is leaving the state.
+    Exit = 1,
+}
+
+impl TryFrom<u8> for SleepPhase {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => Self::Entry,
+            1 => Self::Exit,
+            _ => Err(value)?,
+        })
+    }
+}
+
+/// Returns whether the processors lose their context in `s`: S3, S4 and S5.
+pub fn is_deep_sleep(s: SleepType) -> bool {
+    matches!(s, SleepType::S3 | SleepType::S4 | SleepType::S5)
+}
+
+/// Returns whether resuming from `s` replays the S3 boot script: S3 only.
+pub fn requires_s3_script(s: SleepType) -> bool {
+    s == SleepType::S3
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_deep_sleep, requires_s3_script, SleepPhase, SleepType};
+
+    const ALL: [SleepType; 6] =
+        [SleepType::S0, SleepType::S1, SleepType::S2, SleepType::S3, SleepType::S4, SleepType::S5];
+
+    #[test]
+    fn values_should_match_spec() {
+        for (value, s) in ALL.iter().enumerate() {
+            assert_eq!(*s as u8, value as u8);
+            assert_eq!(SleepType::try_from(value as u8), Ok(*s));
+            assert_eq!(s.to_string(), format!("S{value}"));
+        }
+        assert_eq!(SleepType::try_from(6), Err(6));
+        assert_eq!((SleepPhase::Entry as u8, SleepPhase::Exit as u8), (0, 1));
+        assert_eq!(SleepPhase::try_from(1), Ok(SleepPhase::Exit));
+        assert_eq!(SleepPhase::try_from(2), Err(2));
+    }
+
+    #[test]
+    fn helpers_should_classify_every_state() {
+        let deep: Vec<bool> = ALL.iter().map(|s| is_deep_sleep(*s)).collect();
+        assert_eq!(deep, [false, false, false, true, true, true]);
+        let script: Vec<bool> = ALL.iter().map(|s| requires_s3_script(*s)).collect();
+        assert_eq!(script, [false, false, false, true, false, false]);
+    }
+}
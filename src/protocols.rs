@@ -2,6 +2,9 @@
 //!
 //! Each protocol in the PI Specification is maintained as a separate module.
 //!
+//! [`DXE_ARCH_PROTOCOLS`] lists the DXE Architectural Protocols, which the DXE Foundation waits for before it starts
+//! the BDS phase.
+//!
 //! ## License
 //!
 //! Copyright (C) Microsoft Corporation. All rights reserved.
@@ -11,10 +14,104 @@
 
 pub mod bds;
 pub mod cpu_arch;
+pub mod debug_support;
+pub mod debugport;
+pub mod dhcp4;
+pub mod dns4;
+pub mod edid_override;
 pub mod firmware_volume;
 pub mod firmware_volume_block;
+pub mod graphics_output;
+pub mod http;
+pub mod ipmi_transport;
+pub mod isa_io;
+pub mod key_state;
+pub mod legacy_bios;
+pub mod lock_box;
 pub mod metronome;
+pub mod monotonic_counter;
+pub mod mtftp4;
+pub mod platform_driver_override;
+pub mod read_only_variable2;
+pub mod real_time_clock;
+pub mod reset;
 pub mod runtime;
+pub mod s3_resume;
+pub mod s3_save_state;
+pub mod security;
+pub mod security2;
+pub mod simple_text_input;
+pub mod simple_text_output;
+pub mod smbios;
+pub mod smbus_hc;
+pub mod smm;
 pub mod status_code;
 pub mod timer;
+pub mod variable;
+pub mod variable_write;
 pub mod watchdog;
+
+use r_efi::efi;
+
+/// The names and GUIDs of the DXE Architectural Protocols.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section II-12.1
+pub const DXE_ARCH_PROTOCOLS: &[(&str, efi::Guid)] = &[
+    ("Security", security::PROTOCOL_GUID),
+    ("Security2", security2::PROTOCOL_GUID),
+    ("CPU", cpu_arch::PROTOCOL_GUID),
+    ("Timer", timer::PROTOCOL_GUID),
+    ("BDS", bds::PROTOCOL_GUID),
+    ("Metronome", metronome::PROTOCOL_GUID),
+    ("MonotonicCounter", monotonic_counter::PROTOCOL_GUID),
+    ("RealTimeClock", real_time_clock::PROTOCOL_GUID),
+    ("Reset", reset::PROTOCOL_GUID),
+    ("Runtime", runtime::PROTOCOL_GUID),
+    ("StatusCode", status_code::PROTOCOL_GUID),
+    ("Variable", variable::PROTOCOL_GUID),
+    ("VariableWrite", variable_write::PROTOCOL_GUID),
+    ("WatchdogTimer", watchdog::PROTOCOL_GUID),
+];
+
+/// Returns whether `guid` is the GUID of a DXE Architectural Protocol.
+pub fn is_architectural_protocol(guid: &efi::Guid) -> bool {
+    DXE_ARCH_PROTOCOLS.iter().any(|(_, arch_guid)| arch_guid == guid)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{
+        bds, cpu_arch, is_architectural_protocol, metronome, monotonic_counter, real_time_clock, reset, runtime,
+        security, security2, status_code, timer, variable, variable_write, watchdog, DXE_ARCH_PROTOCOLS,
+    };
+
+    #[test]
+    fn dxe_arch_protocols_should_list_all_fourteen() {
+        let guids = [
+            security::PROTOCOL_GUID,
+            security2::PROTOCOL_GUID,
+            cpu_arch::PROTOCOL_GUID,
+            timer::PROTOCOL_GUID,
+            bds::PROTOCOL_GUID,
+            metronome::PROTOCOL_GUID,
+            monotonic_counter::PROTOCOL_GUID,
+            real_time_clock::PROTOCOL_GUID,
+            reset::PROTOCOL_GUID,
+            runtime::PROTOCOL_GUID,
+            status_code::PROTOCOL_GUID,
+            variable::PROTOCOL_GUID,
+            variable_write::PROTOCOL_GUID,
+            watchdog::PROTOCOL_GUID,
+        ];
+        assert_eq!(DXE_ARCH_PROTOCOLS.len(), 14);
+        assert_eq!(guids.iter().map(|guid| *guid.as_bytes()).collect::<BTreeSet<_>>().len(), 14);
+        assert_eq!(DXE_ARCH_PROTOCOLS.iter().map(|(name, _)| *name).collect::<BTreeSet<_>>().len(), 14);
+        for guid in &guids {
+            assert!(is_architectural_protocol(guid));
+        }
+        assert!(!is_architectural_protocol(&super::smbios::PROTOCOL_GUID));
+    }
+}
@@ -31,6 +31,7 @@ pub type BdsEntry = extern "efiapi" fn(*mut Protocol);
 /// # Documentation
 /// UEFI Platform Initialization Specification, Release 1.8, Section II-12.2.1
 #[repr(C)]
+#[derive(Debug)]
 pub struct Protocol {
     pub entry: BdsEntry,
 }
@@ -15,6 +15,8 @@ use core::ffi::c_void;
 
 use r_efi::efi;
 
+use crate::mem_attr::EfiMemoryAttributes;
+
 /// CPU Architectrural Protocol GUID
 ///
 /// # Documentation
@@ -99,13 +101,15 @@ pub type GetTimerValue = extern "efiapi" fn(*const Protocol, u32, *mut u64, *mut
 ///
 /// # Documentation
 /// UEFI Platform Initialization Specification, Release 1.8, Section II-12.3.9
-pub type SetMemoryAttributes = extern "efiapi" fn(*const Protocol, efi::PhysicalAddress, u64, u64) -> efi::Status;
+pub type SetMemoryAttributes =
+    extern "efiapi" fn(*const Protocol, efi::PhysicalAddress, u64, EfiMemoryAttributes) -> efi::Status;
 
 /// Abstracts the processor services that are required to implement some of the DXE services.
 ///
 /// # Documentation
 /// UEFI Platform Initialization Specification, Release 1.8, Section II-12.3.1
 #[repr(C)]
+#[derive(Debug)]
 pub struct Protocol {
     pub flush_data_cache: FlushDataCache,
     pub enable_interrupt: EnableInterrupt,
@@ -0,0 +1,178 @@
+//! Debug Support Protocol
+//!
+//! Provides the services to allow the debug agent to register callback functions that are called either periodically
+//! or when specific processor exceptions occur.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/18_Protocols_Debugger_Support.html#efi-debug-support-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::ffi::c_void;
+
+use r_efi::efi;
+
+/// Debug Support Protocol GUID
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 18.2.1
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x2755590c, 0x6f3c, 0x42fa, 0x9e, 0xa4, &[0xa3, 0xba, 0x54, 0x3c, 0xda, 0x25]);
+
+/// The processor architecture supported by a Debug Support Protocol instance. The values are the PE32+ machine types
+/// of the architectures.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 18.2.1
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstructionSetArchitecture {
+    X86 = 0x014C,
+    X64 = 0x8664,
+    Ipf = 0x0200,
+    Ebc = 0x0EBC,
+    Arm = 0x01C2,
+    Aarch64 = 0xAA64,
+    Riscv32 = 0x5032,
+    Riscv64 = 0x5064,
+    Riscv128 = 0x5128,
+}
+
+impl TryFrom<u32> for InstructionSetArchitecture {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0x014C => Self::X86,
+            0x8664 => Self::X64,
+            0x0200 => Self::Ipf,
+            0x0EBC => Self::Ebc,
+            0x01C2 => Self::Arm,
+            0xAA64 => Self::Aarch64,
+            0x5032 => Self::Riscv32,
+            0x5064 => Self::Riscv64,
+            0x5128 => Self::Riscv128,
+            _ => Err(value)?,
+        })
+    }
+}
+
+/// Pointer to the processor context (EFI_SYSTEM_CONTEXT) of the interrupted processor. The layout of the context
+/// depends on the instruction set architecture.
+pub type EfiContext = *mut c_void;
+
+/// Processor exception to register a callback for. The values depend on the instruction set architecture.
+pub type ExceptionType = isize;
+
+/// Called by the timer interrupt handler on each timer tick.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 18.2.4
+pub type PeriodicCallback = extern "efiapi" fn(system_context: EfiContext);
+
+/// Called when the registered processor exception occurs.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 18.2.5
+pub type ExceptionCallback = extern "efiapi" fn(exception_type: ExceptionType, system_context: EfiContext);
+
+/// Returns the maximum value that may be used for the processor_index parameter of the other functions.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 18.2.3
+pub type GetMaximumProcessorIndex = extern "efiapi" fn(*const Protocol, max_processor_index: *mut usize) -> efi::Status;
+
+/// Registers a function to be called back periodically in interrupt context. A `None` callback unregisters the
+/// current callback.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 18.2.4
+pub type RegisterPeriodicCallback = extern "efiapi" fn(
+    *const Protocol,
+    processor_index: usize,
+    periodic_callback: Option<PeriodicCallback>,
+) -> efi::Status;
+
+/// Registers a function to be called when a given processor exception occurs. A `None` callback unregisters the
+/// current callback.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 18.2.5
+pub type RegisterExceptionCallback = extern "efiapi" fn(
+    *const Protocol,
+    processor_index: usize,
+    exception_callback: Option<ExceptionCallback>,
+    exception_type: ExceptionType,
+) -> efi::Status;
+
+/// Invalidates the processor instruction cache for a memory range.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 18.2.6
+pub type InvalidateInstructionCache =
+    extern "efiapi" fn(*const Protocol, processor_index: usize, start: *mut c_void, length: u64) -> efi::Status;
+
+/// Provides the services to allow the debug agent to register callback functions that are called either periodically
+/// or when specific processor exceptions occur.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 18.2.1
+#[repr(C)]
+#[derive(Debug)]
+pub struct Protocol {
+    /// The processor architecture of this instance; see [`InstructionSetArchitecture`].
+    pub isa: u32,
+    pub get_maximum_processor_index: GetMaximumProcessorIndex,
+    pub register_periodic_callback: RegisterPeriodicCallback,
+    pub register_exception_callback: RegisterExceptionCallback,
+    pub invalidate_instruction_cache: InvalidateInstructionCache,
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::size_of;
+
+    use r_efi::protocols::debug_support;
+
+    use super::{InstructionSetArchitecture, Protocol, PROTOCOL_GUID};
+
+    #[test]
+    fn guid_should_match_spec() {
+        assert_eq!(PROTOCOL_GUID, debug_support::PROTOCOL_GUID);
+    }
+
+    #[test]
+    fn isa_discriminants_should_match_spec() {
+        let isas = [
+            (InstructionSetArchitecture::X86, debug_support::ISA_IA32),
+            (InstructionSetArchitecture::X64, debug_support::ISA_X64),
+            (InstructionSetArchitecture::Ipf, debug_support::ISA_IPF),
+            (InstructionSetArchitecture::Ebc, debug_support::ISA_EBC),
+            (InstructionSetArchitecture::Arm, debug_support::ISA_ARM),
+            (InstructionSetArchitecture::Aarch64, debug_support::ISA_AARCH64),
+            (InstructionSetArchitecture::Riscv32, debug_support::ISA_RISCV32),
+            (InstructionSetArchitecture::Riscv64, debug_support::ISA_RISCV64),
+            (InstructionSetArchitecture::Riscv128, debug_support::ISA_RISCV128),
+        ];
+        for (isa, value) in isas {
+            assert_eq!(isa as u32, value);
+            assert_eq!(InstructionSetArchitecture::try_from(value), Ok(isa));
+        }
+        assert_eq!(InstructionSetArchitecture::try_from(1), Err(1));
+        assert_eq!(size_of::<InstructionSetArchitecture>(), 4);
+    }
+
+    #[test]
+    fn protocol_layout_should_match_spec() {
+        let ptr = size_of::<usize>();
+        assert_eq!(offset_of!(Protocol, isa), 0);
+        assert_eq!(offset_of!(Protocol, get_maximum_processor_index), ptr);
+        assert_eq!(offset_of!(Protocol, invalidate_instruction_cache), 4 * ptr);
+        assert_eq!(size_of::<Protocol>(), 5 * ptr);
+        assert_eq!(size_of::<Protocol>(), size_of::<debug_support::Protocol>());
+    }
+}
@@ -0,0 +1,238 @@
+//! Debug Port Protocol
+//!
+//! Provides the communication link between the debug agent and the remote host.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/18_Protocols_Debugger_Support.html#efi-debugport-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::ffi::c_void;
+
+use r_efi::efi;
+
+/// Debug Port Protocol GUID
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 18.3.1
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0xeba4e8d2, 0x3858, 0x41ec, 0xa2, 0x81, &[0x26, 0x47, 0xba, 0x96, 0x60, 0xd0]);
+
+/// Name of the variable that selects the device used as the debug port ("DEBUGPORT" as a null-terminated UCS-2
+/// string).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 18.3.2
+pub const DEBUGPORT_VARIABLE_NAME: &[efi::Char16] = &[
+    b'D' as u16,
+    b'E' as u16,
+    b'B' as u16,
+    b'U' as u16,
+    b'G' as u16,
+    b'P' as u16,
+    b'O' as u16,
+    b'R' as u16,
+    b'T' as u16,
+    0,
+];
+
+/// Vendor GUID of the DEBUGPORT variable. The specification defines it to be the protocol GUID.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 18.3.2
+pub const DEBUGPORT_VARIABLE_GUID: efi::Guid = PROTOCOL_GUID;
+
+/// Resets the debug port.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 18.3.1
+pub type Reset = extern "efiapi" fn(*const Protocol) -> efi::Status;
+
+/// Writes data to the debug port. On return, buffer_size holds the number of bytes actually written.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 18.3.1
+pub type Write =
+    extern "efiapi" fn(*const Protocol, timeout: u32, buffer_size: *mut usize, buffer: *const c_void) -> efi::Status;
+
+/// Reads data from the debug port. On return, buffer_size holds the number of bytes actually read.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 18.3.1
+pub type Read =
+    extern "efiapi" fn(*const Protocol, timeout: u32, buffer_size: *mut usize, buffer: *mut c_void) -> efi::Status;
+
+/// Checks to see if any characters are available to be read from the debug port.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 18.3.1
+pub type Poll = extern "efiapi" fn(*const Protocol) -> efi::Status;
+
+/// Provides the communication link between the debug agent and the remote host.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 18.3.1
+#[repr(C)]
+#[derive(Debug)]
+pub struct Protocol {
+    pub reset: Reset,
+    pub write: Write,
+    pub read: Read,
+    pub poll: Poll,
+}
+
+/// Wrapper over a Debug Port Protocol instance.
+#[derive(Debug, Clone, Copy)]
+pub struct DebugPort {
+    protocol: *const Protocol,
+    timeout: u32,
+}
+
+impl DebugPort {
+    /// Instantiates a new DebugPort that uses `timeout` (in microseconds) for each write.
+    ///
+    /// ## Safety
+    /// Caller must ensure that `protocol` points to a valid Debug Port Protocol instance for as long as the DebugPort
+    /// (or any copy of it) is used.
+    pub unsafe fn new(protocol: *const Protocol, timeout: u32) -> Self {
+        Self { protocol, timeout }
+    }
+
+    fn protocol_ref(&self) -> &Protocol {
+        //Safety: the constructor contract guarantees the pointer is valid.
+        unsafe { &*self.protocol }
+    }
+
+    /// Writes all of `data` to the debug port.
+    ///
+    /// Writes that time out after transferring part of the data are retried with the remaining data. Returns
+    /// `efi::Status::TIMEOUT` if a write times out without transferring any data, `efi::Status::DEVICE_ERROR` if a write
+    /// succeeds without transferring any data, or the first other error reported by the device.
+    pub fn send(&self, data: &[u8]) -> efi::Status {
+        let protocol = self.protocol_ref();
+        let mut remaining = data;
+        while !remaining.is_empty() {
+            let mut size = remaining.len();
+            let status = (protocol.write)(protocol, self.timeout, &mut size, remaining.as_ptr() as *const c_void);
+            if status.is_error() && (status != efi::Status::TIMEOUT || size == 0) {
+                return status;
+            }
+            // a device that keeps accepting nothing would otherwise be retried forever.
+            if size == 0 {
+                return efi::Status::DEVICE_ERROR;
+            }
+            remaining = &remaining[size.min(remaining.len())..];
+        }
+        efi::Status::SUCCESS
+    }
+
+    /// Returns `efi::Status::SUCCESS` if data is available to be read, or `efi::Status::NOT_READY` if not.
+    pub fn poll(&self) -> efi::Status {
+        let protocol = self.protocol_ref();
+        (protocol.poll)(protocol)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{cell::RefCell, ffi::c_void, mem::size_of};
+
+    use r_efi::{efi, protocols::debugport};
+
+    use super::{DebugPort, Protocol, DEBUGPORT_VARIABLE_GUID, DEBUGPORT_VARIABLE_NAME, PROTOCOL_GUID};
+
+    std::thread_local! {
+        static WRITTEN: RefCell<Vec<u8>> = RefCell::new(Vec::new());
+        // (maximum bytes accepted per write, status returned for a partial write)
+        static LIMIT: RefCell<(usize, efi::Status)> = RefCell::new((usize::MAX, efi::Status::SUCCESS));
+    }
+
+    extern "efiapi" fn reset(_: *const Protocol) -> efi::Status {
+        efi::Status::SUCCESS
+    }
+
+    extern "efiapi" fn write(_: *const Protocol, _: u32, size: *mut usize, buffer: *const c_void) -> efi::Status {
+        let (limit, partial_status) = LIMIT.with(|limit| *limit.borrow());
+        let requested = unsafe { *size };
+        let written = requested.min(limit);
+        let data = unsafe { core::slice::from_raw_parts(buffer as *const u8, written) };
+        WRITTEN.with(|w| w.borrow_mut().extend_from_slice(data));
+        unsafe { *size = written };
+        if written < requested {
+            partial_status
+        } else {
+            efi::Status::SUCCESS
+        }
+    }
+
+    extern "efiapi" fn read(_: *const Protocol, _: u32, size: *mut usize, _: *mut c_void) -> efi::Status {
+        unsafe { *size = 0 };
+        efi::Status::TIMEOUT
+    }
+
+    extern "efiapi" fn poll(_: *const Protocol) -> efi::Status {
+        efi::Status::NOT_READY
+    }
+
+    fn mock_protocol() -> Protocol {
+        Protocol { reset, write, read, poll }
+    }
+
+    fn take_written() -> Vec<u8> {
+        WRITTEN.with(|w| core::mem::take(&mut *w.borrow_mut()))
+    }
+
+    #[test]
+    fn guids_should_match_spec() {
+        assert_eq!(PROTOCOL_GUID, debugport::PROTOCOL_GUID);
+        assert_eq!(DEBUGPORT_VARIABLE_GUID, PROTOCOL_GUID);
+        let name: Vec<u16> = "DEBUGPORT\0".encode_utf16().collect();
+        assert_eq!(DEBUGPORT_VARIABLE_NAME, &name[..]);
+    }
+
+    #[test]
+    fn protocol_layout_should_match_spec() {
+        assert_eq!(size_of::<Protocol>(), 4 * size_of::<usize>());
+        assert_eq!(size_of::<Protocol>(), size_of::<debugport::Protocol>());
+    }
+
+    #[test]
+    fn send_should_write_all_data() {
+        let protocol = mock_protocol();
+        let port = unsafe { DebugPort::new(&protocol, 1000) };
+
+        assert_eq!(port.send(b"hello"), efi::Status::SUCCESS);
+        assert_eq!(take_written(), b"hello");
+
+        // partial writes that time out are retried.
+        LIMIT.with(|limit| *limit.borrow_mut() = (2, efi::Status::TIMEOUT));
+        assert_eq!(port.send(b"hello world"), efi::Status::SUCCESS);
+        assert_eq!(take_written(), b"hello world");
+
+        assert_eq!(port.send(b""), efi::Status::SUCCESS);
+        assert_eq!(port.poll(), efi::Status::NOT_READY);
+    }
+
+    #[test]
+    fn send_should_stop_on_error() {
+        let protocol = mock_protocol();
+        let port = unsafe { DebugPort::new(&protocol, 1000) };
+
+        LIMIT.with(|limit| *limit.borrow_mut() = (0, efi::Status::TIMEOUT));
+        assert_eq!(port.send(b"hello"), efi::Status::TIMEOUT);
+        assert_eq!(take_written(), b"");
+
+        LIMIT.with(|limit| *limit.borrow_mut() = (3, efi::Status::DEVICE_ERROR));
+        assert_eq!(port.send(b"hello"), efi::Status::DEVICE_ERROR);
+        assert_eq!(take_written(), b"hel");
+
+        // a successful write that transfers nothing is not retried.
+        LIMIT.with(|limit| *limit.borrow_mut() = (0, efi::Status::SUCCESS));
+        assert_eq!(port.send(b"hello"), efi::Status::DEVICE_ERROR);
+        assert_eq!(take_written(), b"");
+    }
+}
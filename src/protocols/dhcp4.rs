@@ -0,0 +1,386 @@
+//! DHCPv4 Protocol
+//!
+//! Acquires and manages an IPv4 address lease, and exchanges custom DHCP packets such as the PXE boot server
+//! discovery.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/29_Network_Protocols_ARP_and_DHCPv4.html#efi-dhcp4-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::ffi::c_void;
+
+use r_efi::efi;
+
+/// DHCPv4 Protocol GUID
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.2.2
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x8a219718, 0x4ef5, 0x4761, 0x91, 0xc8, &[0xc0, 0xf0, 0x4b, 0xda, 0x9e, 0x56]);
+
+/// DHCPv4 Service Binding Protocol GUID
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.2.1
+pub const SERVICE_BINDING_PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x9d9a39d8, 0xbd42, 0x4a73, 0xa4, 0xd5, &[0x8e, 0xe9, 0x4b, 0xe1, 0x13, 0x80]);
+
+/// State of the DHCP client state machine (EFI_DHCP4_STATE).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.2.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dhcp4State {
+    Stopped = 0x0,
+    Init = 0x1,
+    Selecting = 0x2,
+    Requesting = 0x3,
+    Bound = 0x4,
+    Renewing = 0x5,
+    Rebinding = 0x6,
+    InitReboot = 0x7,
+    Rebooting = 0x8,
+}
+
+/// Events reported to the [`Dhcp4Callback`] (EFI_DHCP4_EVENT).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.2.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dhcp4Event {
+    SendDiscover = 0x01,
+    RcvdOffer = 0x02,
+    SelectOffer = 0x03,
+    SendRequest = 0x04,
+    RcvdAck = 0x05,
+    RcvdNak = 0x06,
+    SendDecline = 0x07,
+    BoundCompleted = 0x08,
+    EnterRenewing = 0x09,
+    EnterRebinding = 0x0a,
+    AddressLost = 0x0b,
+    Fail = 0x0c,
+}
+
+/// A DHCP option (EFI_DHCP4_PACKET_OPTION); `data` is the first of `length` data bytes.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.2.2
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dhcp4PacketOption {
+    pub op_code: u8,
+    pub length: u8,
+    pub data: [u8; 1],
+}
+
+/// The fixed BOOTP header of a DHCP packet (EFI_DHCP4_HEADER).
+///
+/// Multi-byte fields are in network byte order.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.2.2
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dhcp4Header {
+    pub op_code: u8,
+    pub hw_type: u8,
+    pub hw_addr_len: u8,
+    pub hops: u8,
+    pub xid: u32,
+    pub seconds: u16,
+    pub reserved: u16,
+    pub client_addr: efi::Ipv4Address,
+    pub your_addr: efi::Ipv4Address,
+    pub server_addr: efi::Ipv4Address,
+    pub gateway_addr: efi::Ipv4Address,
+    pub client_hw_addr: [u8; 16],
+    /// Null-terminated ASCII server host name.
+    pub server_name: [u8; 64],
+    /// Null-terminated ASCII boot file name.
+    pub boot_file_name: [u8; 128],
+}
+
+/// A DHCP packet (EFI_DHCP4_PACKET).
+///
+/// The specification wraps `header`, `magic_cookie` and `option` in an anonymous `Dhcp4` structure; since the packet
+/// is packed, flattening them here does not change the layout. `option` is the first byte of the options, which
+/// extend to the end of the packet.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.2.2
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dhcp4Packet {
+    /// Size of the buffer holding the packet, including this field.
+    pub size: u32,
+    /// Length of the DHCP packet, starting at `header`.
+    pub length: u32,
+    pub header: Dhcp4Header,
+    /// DHCP magic cookie, 99.130.83.99 in network byte order.
+    pub magic_cookie: u32,
+    pub option: [u8; 1],
+}
+
+/// Called by the driver at each step of the DHCP process. The callback may modify or replace the packet about to be
+/// sent, and an error status aborts the process.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.2.2
+pub type Dhcp4Callback = extern "efiapi" fn(
+    *const Protocol,
+    context: *mut c_void,
+    current_state: Dhcp4State,
+    dhcp4_event: Dhcp4Event,
+    packet: *mut Dhcp4Packet,
+    new_packet: *mut *mut Dhcp4Packet,
+) -> efi::Status;
+
+/// Configuration of a DHCPv4 instance (EFI_DHCP4_CONFIG_DATA).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.2.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dhcp4ConfigData {
+    pub discover_try_count: u32,
+    /// Array of discover_try_count timeouts, in seconds; null for the defaults.
+    pub discover_timeout: *mut u32,
+    pub request_try_count: u32,
+    /// Array of request_try_count timeouts, in seconds; null for the defaults.
+    pub request_timeout: *mut u32,
+    /// Previously allocated address to request in the INIT-REBOOT state; zero to start in the INIT state.
+    pub client_address: efi::Ipv4Address,
+    pub dhcp4_callback: Option<Dhcp4Callback>,
+    pub callback_context: *mut c_void,
+    pub option_count: u32,
+    /// Array of option_count options to append to each DHCP packet sent.
+    pub option_list: *mut *mut Dhcp4PacketOption,
+}
+
+/// Current state of a DHCPv4 instance (EFI_DHCP4_MODE_DATA).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.2.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dhcp4ModeData {
+    pub state: Dhcp4State,
+    pub config_data: Dhcp4ConfigData,
+    pub client_address: efi::Ipv4Address,
+    pub client_mac_address: efi::MacAddress,
+    pub server_address: efi::Ipv4Address,
+    pub router_address: efi::Ipv4Address,
+    pub subnet_mask: efi::Ipv4Address,
+    /// Lease time in seconds; 0xFFFFFFFF for an infinite lease.
+    pub lease_time: u32,
+    /// The last DHCPACK packet received, or null.
+    pub reply_packet: *mut Dhcp4Packet,
+}
+
+/// An address and port on which to listen for responses (EFI_DHCP4_LISTEN_POINT).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.2.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dhcp4ListenPoint {
+    pub listen_address: efi::Ipv4Address,
+    pub subnet_mask: efi::Ipv4Address,
+    pub listen_port: u16,
+}
+
+/// Describes a TransmitReceive() operation (EFI_DHCP4_TRANSMIT_RECEIVE_TOKEN).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.2.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dhcp4TransmitReceiveToken {
+    /// Completion status of the operation.
+    pub status: efi::Status,
+    /// Event signaled when the operation completes; if null, the operation is synchronous.
+    pub completion_event: efi::Event,
+    pub remote_address: efi::Ipv4Address,
+    pub remote_port: u16,
+    pub gateway_address: efi::Ipv4Address,
+    pub listen_point_count: u32,
+    pub listen_points: *mut Dhcp4ListenPoint,
+    /// Time to wait for responses, in seconds.
+    pub timeout_value: u32,
+    /// The packet to send.
+    pub packet: *mut Dhcp4Packet,
+    pub response_count: u32,
+    /// The packets received, allocated by the driver and freed by the caller.
+    pub response_list: *mut Dhcp4Packet,
+}
+
+/// Returns the current state of the DHCPv4 instance.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.2.2
+pub type GetModeData = extern "efiapi" fn(*const Protocol, dhcp4_mode_data: *mut Dhcp4ModeData) -> efi::Status;
+
+/// Configures the DHCPv4 instance, or resets it to the unconfigured state if dhcp4_cfg_data is null.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.2.2
+pub type Configure = extern "efiapi" fn(*const Protocol, dhcp4_cfg_data: *mut Dhcp4ConfigData) -> efi::Status;
+
+/// Starts the DHCP process to acquire an address lease.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.2.2
+pub type Start = extern "efiapi" fn(*const Protocol, completion_event: efi::Event) -> efi::Status;
+
+/// Extends the lease time by sending a request packet.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.2.2
+pub type RenewRebind =
+    extern "efiapi" fn(*const Protocol, rebind_request: efi::Boolean, completion_event: efi::Event) -> efi::Status;
+
+/// Releases the current address lease.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.2.2
+pub type Release = extern "efiapi" fn(*const Protocol) -> efi::Status;
+
+/// Stops the DHCP process without releasing the lease.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.2.2
+pub type Stop = extern "efiapi" fn(*const Protocol) -> efi::Status;
+
+/// Builds a DHCP packet from a seed packet, deleting and appending options.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.2.2
+pub type Build = extern "efiapi" fn(
+    *const Protocol,
+    seed_packet: *mut Dhcp4Packet,
+    delete_count: u32,
+    delete_list: *mut u8,
+    append_count: u32,
+    append_list: *mut *mut Dhcp4PacketOption,
+    new_packet: *mut *mut Dhcp4Packet,
+) -> efi::Status;
+
+/// Transmits a DHCP packet and collects the responses.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.2.2
+pub type TransmitReceive = extern "efiapi" fn(*const Protocol, token: *mut Dhcp4TransmitReceiveToken) -> efi::Status;
+
+/// Parses the options of a DHCP packet.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.2.2
+pub type Parse = extern "efiapi" fn(
+    *const Protocol,
+    packet: *mut Dhcp4Packet,
+    option_count: *mut u32,
+    packet_option_list: *mut *mut Dhcp4PacketOption,
+) -> efi::Status;
+
+/// Provides a DHCPv4 client.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.2.2
+#[repr(C)]
+#[derive(Debug)]
+pub struct Protocol {
+    pub get_mode_data: GetModeData,
+    pub configure: Configure,
+    pub start: Start,
+    pub renew_rebind: RenewRebind,
+    pub release: Release,
+    pub stop: Stop,
+    pub build: Build,
+    pub transmit_receive: TransmitReceive,
+    pub parse: Parse,
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::size_of;
+
+    use super::{
+        Dhcp4ConfigData, Dhcp4Header, Dhcp4ModeData, Dhcp4Packet, Dhcp4TransmitReceiveToken, Protocol, PROTOCOL_GUID,
+        SERVICE_BINDING_PROTOCOL_GUID,
+    };
+
+    #[test]
+    fn guids_should_match_spec() {
+        assert_eq!(
+            PROTOCOL_GUID.as_bytes(),
+            &[0x18, 0x97, 0x21, 0x8a, 0xf5, 0x4e, 0x61, 0x47, 0x91, 0xc8, 0xc0, 0xf0, 0x4b, 0xda, 0x9e, 0x56]
+        );
+        assert_eq!(
+            SERVICE_BINDING_PROTOCOL_GUID.as_bytes(),
+            &[0xd8, 0x39, 0x9a, 0x9d, 0x42, 0xbd, 0x73, 0x4a, 0xa4, 0xd5, 0x8e, 0xe9, 0x4b, 0xe1, 0x13, 0x80]
+        );
+    }
+
+    #[test]
+    fn packet_layout_should_match_spec() {
+        assert_eq!(offset_of!(Dhcp4Header, xid), 4);
+        assert_eq!(offset_of!(Dhcp4Header, seconds), 8);
+        assert_eq!(offset_of!(Dhcp4Header, client_addr), 12);
+        assert_eq!(offset_of!(Dhcp4Header, your_addr), 16);
+        assert_eq!(offset_of!(Dhcp4Header, server_addr), 20);
+        assert_eq!(offset_of!(Dhcp4Header, gateway_addr), 24);
+        assert_eq!(offset_of!(Dhcp4Header, client_hw_addr), 28);
+        assert_eq!(offset_of!(Dhcp4Header, server_name), 44);
+        assert_eq!(offset_of!(Dhcp4Header, boot_file_name), 108);
+        assert_eq!(size_of::<Dhcp4Header>(), 236);
+
+        assert_eq!(offset_of!(Dhcp4Packet, length), 4);
+        assert_eq!(offset_of!(Dhcp4Packet, header), 8);
+        assert_eq!(offset_of!(Dhcp4Packet, magic_cookie), 244);
+        assert_eq!(offset_of!(Dhcp4Packet, option), 248);
+        assert_eq!(size_of::<Dhcp4Packet>(), 249);
+
+        let mut bytes = [0u8; 249];
+        bytes[8] = 2; // BOOTREPLY
+        bytes[12..16].copy_from_slice(&0x12345678u32.to_be_bytes());
+        bytes[24..28].copy_from_slice(&[192, 168, 1, 10]);
+        bytes[244..248].copy_from_slice(&[99, 130, 83, 99]);
+        let packet = unsafe { (bytes.as_ptr() as *const Dhcp4Packet).read_unaligned() };
+        let header = packet.header;
+        assert_eq!(header.op_code, 2);
+        assert_eq!(u32::from_be(header.xid), 0x12345678);
+        assert_eq!(header.your_addr.addr, [192, 168, 1, 10]);
+        assert_eq!(packet.magic_cookie.to_ne_bytes(), [99, 130, 83, 99]);
+    }
+
+    #[test]
+    fn struct_layouts_should_match_spec() {
+        assert_eq!(offset_of!(Dhcp4ConfigData, discover_timeout), 8);
+        assert_eq!(offset_of!(Dhcp4ConfigData, client_address), 32);
+        assert_eq!(offset_of!(Dhcp4ConfigData, dhcp4_callback), 40);
+        assert_eq!(offset_of!(Dhcp4ConfigData, option_list), 64);
+        assert_eq!(size_of::<Dhcp4ConfigData>(), 72);
+
+        assert_eq!(offset_of!(Dhcp4ModeData, config_data), 8);
+        assert_eq!(offset_of!(Dhcp4ModeData, client_address), 80);
+        assert_eq!(offset_of!(Dhcp4ModeData, client_mac_address), 84);
+        assert_eq!(offset_of!(Dhcp4ModeData, lease_time), 128);
+        assert_eq!(offset_of!(Dhcp4ModeData, reply_packet), 136);
+
+        assert_eq!(offset_of!(Dhcp4TransmitReceiveToken, remote_address), 16);
+        assert_eq!(offset_of!(Dhcp4TransmitReceiveToken, gateway_address), 22);
+        assert_eq!(offset_of!(Dhcp4TransmitReceiveToken, listen_points), 32);
+        assert_eq!(offset_of!(Dhcp4TransmitReceiveToken, packet), 48);
+        assert_eq!(offset_of!(Dhcp4TransmitReceiveToken, response_list), 64);
+
+        assert_eq!(size_of::<Protocol>(), size_of::<[usize; 9]>());
+    }
+}
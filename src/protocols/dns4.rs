@@ -0,0 +1,312 @@
+//! DNSv4 Protocol
+//!
+//! Resolves host names to IPv4 addresses and IPv4 addresses to host names using the Domain Name System.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/29_Network_Protocols_ARP_and_DHCPv4.html#efi-dns4-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::fmt;
+
+use r_efi::efi;
+
+/// DNSv4 Protocol GUID
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.4.2
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0xae3d28cc, 0xe05b, 0x4fa1, 0xa0, 0x11, &[0x7e, 0xb5, 0x5a, 0x3f, 0x14, 0x01]);
+
+/// DNSv4 Service Binding Protocol GUID
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.4.1
+pub const SERVICE_BINDING_PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0xb625b186, 0xe063, 0x44f7, 0x89, 0x05, &[0x6a, 0x74, 0xdc, 0x6f, 0x52, 0xb4]);
+
+/// Configuration of a DNSv4 instance (EFI_DNS4_CONFIG_DATA).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.4.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dns4ConfigData {
+    /// Array of dns_server_list_count DNS server addresses; may be null to use the servers obtained from DHCP.
+    pub dns_server_list: *mut efi::Ipv4Address,
+    pub dns_server_list_count: usize,
+    /// If true, the default address information from the EFI IPv4 Configuration II Protocol is used.
+    pub use_default_setting: efi::Boolean,
+    pub enable_dns_cache: efi::Boolean,
+    /// Transport protocol, either UDP (17) or TCP (6).
+    pub protocol: u8,
+    pub station_ip: efi::Ipv4Address,
+    pub subnet_mask: efi::Ipv4Address,
+    /// Local port; zero to select an ephemeral port.
+    pub local_port: u16,
+    pub retry_count: u32,
+    /// Time to wait between retries, in seconds.
+    pub retry_interval: u32,
+}
+
+/// An entry in the DNS cache (EFI_DNS4_CACHE_ENTRY).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.4.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dns4CacheEntry {
+    /// Null-terminated UCS-2 host name.
+    pub host_name: *mut efi::Char16,
+    pub ip_address: *mut efi::Ipv4Address,
+    /// Time in seconds that the entry remains valid.
+    pub timeout: u32,
+}
+
+/// Current state of a DNSv4 instance (EFI_DNS4_MODE_DATA).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.4.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dns4ModeData {
+    pub dns_config_data: Dns4ConfigData,
+    pub dns_server_count: u32,
+    /// Array of dns_server_count configured DNS server addresses, allocated by the driver.
+    pub dns_server_list: *mut efi::Ipv4Address,
+    pub dns_cache_count: u32,
+    /// Array of dns_cache_count cache entries, allocated by the driver.
+    pub dns_cache_list: *mut Dns4CacheEntry,
+}
+
+/// Response to HostNameToIp() (DNS_HOST_TO_ADDR_DATA).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.4.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DnsHostToAddrData {
+    pub ip_count: u32,
+    /// Array of ip_count addresses.
+    pub ip_list: *mut efi::Ipv4Address,
+}
+
+/// Response to IpToHostName() (DNS_ADDR_TO_HOST_DATA).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.4.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DnsAddrToHostData {
+    /// Null-terminated UCS-2 host name.
+    pub host_name: *mut efi::Char16,
+}
+
+/// A resource record of a general lookup response (DNS_RESOURCE_RECORD).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.4.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DnsResourceRecord {
+    /// Null-terminated ASCII owner name.
+    pub q_name: *mut u8,
+    pub q_type: u16,
+    pub q_class: u16,
+    pub ttl: u32,
+    pub data_length: u16,
+    pub r_data: *mut u8,
+}
+
+/// Response to GeneralLookUp() (DNS_GENERAL_LOOKUP_DATA).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.4.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DnsGeneralLookupData {
+    pub rr_count: u32,
+    /// Array of rr_count resource records.
+    pub rr_list: *mut DnsResourceRecord,
+}
+
+/// Response of a completed lookup, selected by the function the token was passed to.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union Dns4ResponseData {
+    /// A records returned by HostNameToIp().
+    pub h2a_data: *mut DnsHostToAddrData,
+    /// Host name returned by IpToHostName().
+    pub a2h_data: *mut DnsAddrToHostData,
+    /// Resource records returned by GeneralLookUp().
+    pub g_lookup_data: *mut DnsGeneralLookupData,
+}
+
+/// Prints the pointer held by the union.
+impl fmt::Debug for Dns4ResponseData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        //Safety: every field of the union is a pointer.
+        f.debug_tuple("Dns4ResponseData").field(&unsafe { self.h2a_data }).finish()
+    }
+}
+
+/// Completion token for an asynchronous lookup (EFI_DNS4_COMPLETION_TOKEN).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.4.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Dns4CompletionToken {
+    /// Event signaled when the lookup completes.
+    pub event: efi::Event,
+    /// Completion status of the lookup.
+    pub status: efi::Status,
+    pub retry_count: u32,
+    /// Time to wait between retries, in seconds.
+    pub retry_interval: u32,
+    pub rsp_data: Dns4ResponseData,
+}
+
+/// Returns the current state of the DNSv4 instance.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.4.2
+pub type GetModeData = extern "efiapi" fn(*const Protocol, dns_mode_data: *mut Dns4ModeData) -> efi::Status;
+
+/// Configures the DNSv4 instance, or resets it to the unconfigured state if dns_config_data is null.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.4.2
+pub type Configure = extern "efiapi" fn(*const Protocol, dns_config_data: *mut Dns4ConfigData) -> efi::Status;
+
+/// Starts resolving a host name to IPv4 addresses.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.4.2
+pub type HostNameToIp =
+    extern "efiapi" fn(*const Protocol, host_name: *mut efi::Char16, token: *mut Dns4CompletionToken) -> efi::Status;
+
+/// Starts resolving an IPv4 address to a host name.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.4.2
+pub type IpToHostName =
+    extern "efiapi" fn(*const Protocol, ip_address: efi::Ipv4Address, token: *mut Dns4CompletionToken) -> efi::Status;
+
+/// Starts a lookup of arbitrary resource records.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.4.2
+pub type GeneralLookup = extern "efiapi" fn(
+    *const Protocol,
+    q_name: *mut u8,
+    q_type: u16,
+    q_class: u16,
+    token: *mut Dns4CompletionToken,
+) -> efi::Status;
+
+/// Adds an entry to, or deletes an entry from, the DNS cache.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.4.2
+pub type UpdateDnsCache = extern "efiapi" fn(
+    *const Protocol,
+    delete_flag: efi::Boolean,
+    override_flag: efi::Boolean,
+    dns_cache_entry: Dns4CacheEntry,
+) -> efi::Status;
+
+/// Polls for incoming data packets and processes outgoing data packets.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.4.2
+pub type Poll = extern "efiapi" fn(*const Protocol) -> efi::Status;
+
+/// Aborts a pending lookup, or all of them if token is null.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.4.2
+pub type Cancel = extern "efiapi" fn(*const Protocol, token: *mut Dns4CompletionToken) -> efi::Status;
+
+/// Resolves host names and IPv4 addresses using the Domain Name System.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.4.2
+#[repr(C)]
+#[derive(Debug)]
+pub struct Protocol {
+    pub get_mode_data: GetModeData,
+    pub configure: Configure,
+    pub host_name_to_ip: HostNameToIp,
+    pub ip_to_host_name: IpToHostName,
+    pub general_lookup: GeneralLookup,
+    pub update_dns_cache: UpdateDnsCache,
+    pub poll: Poll,
+    pub cancel: Cancel,
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{mem::size_of, ptr};
+
+    use r_efi::efi;
+
+    use super::{
+        Dns4CacheEntry, Dns4CompletionToken, Dns4ConfigData, Dns4ModeData, Dns4ResponseData, DnsHostToAddrData,
+        Protocol, PROTOCOL_GUID, SERVICE_BINDING_PROTOCOL_GUID,
+    };
+
+    #[test]
+    fn guids_should_match_spec() {
+        assert_eq!(
+            PROTOCOL_GUID.as_bytes(),
+            &[0xcc, 0x28, 0x3d, 0xae, 0x5b, 0xe0, 0xa1, 0x4f, 0xa0, 0x11, 0x7e, 0xb5, 0x5a, 0x3f, 0x14, 0x01]
+        );
+        assert_eq!(
+            SERVICE_BINDING_PROTOCOL_GUID.as_bytes(),
+            &[0x86, 0xb1, 0x25, 0xb6, 0x63, 0xe0, 0xf7, 0x44, 0x89, 0x05, 0x6a, 0x74, 0xdc, 0x6f, 0x52, 0xb4]
+        );
+    }
+
+    #[test]
+    fn completion_token_should_match_spec() {
+        let ptr = size_of::<usize>();
+        assert_eq!(size_of::<Dns4ResponseData>(), ptr);
+        assert_eq!(offset_of!(Dns4CompletionToken, status), ptr);
+        assert_eq!(offset_of!(Dns4CompletionToken, retry_count), 2 * ptr);
+        assert_eq!(offset_of!(Dns4CompletionToken, retry_interval), 2 * ptr + 4);
+        assert_eq!(offset_of!(Dns4CompletionToken, rsp_data), 3 * ptr);
+        assert_eq!(size_of::<Dns4CompletionToken>(), 4 * ptr);
+
+        // All response pointers share the same storage.
+        let mut addresses = [efi::Ipv4Address { addr: [10, 0, 0, 1] }];
+        let mut h2a = DnsHostToAddrData { ip_count: 1, ip_list: addresses.as_mut_ptr() };
+        let token = Dns4CompletionToken {
+            event: ptr::null_mut(),
+            status: efi::Status::SUCCESS,
+            retry_count: 0,
+            retry_interval: 0,
+            rsp_data: Dns4ResponseData { h2a_data: &mut h2a },
+        };
+        let response = unsafe { &*token.rsp_data.h2a_data };
+        assert_eq!(response.ip_count, 1);
+        assert_eq!(unsafe { (*response.ip_list).addr }, [10, 0, 0, 1]);
+        assert_eq!(unsafe { token.rsp_data.a2h_data } as usize, unsafe { token.rsp_data.h2a_data } as usize);
+    }
+
+    #[test]
+    fn struct_layouts_should_match_spec() {
+        let ptr = size_of::<usize>();
+        assert_eq!(offset_of!(Dns4ConfigData, use_default_setting), 2 * ptr);
+        assert_eq!(offset_of!(Dns4ConfigData, station_ip), 2 * ptr + 3);
+        assert_eq!(offset_of!(Dns4ConfigData, local_port), 2 * ptr + 12);
+        assert_eq!(offset_of!(Dns4ConfigData, retry_count), 2 * ptr + 16);
+        assert_eq!(size_of::<Dns4CacheEntry>(), 3 * ptr);
+        assert_eq!(offset_of!(Dns4ModeData, dns_server_count), size_of::<Dns4ConfigData>());
+        assert_eq!(size_of::<Protocol>(), 8 * ptr);
+    }
+}
@@ -0,0 +1,117 @@
+//! EDID Override Protocol
+//!
+//! Produced by the platform to allow the platform to provide EDID information to the producer of the Graphics Output
+//! Protocol.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/12_Protocols_Console_Support.html#efi-edid-override-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::ops::{BitAnd, BitOr};
+
+use r_efi::efi;
+
+/// EDID Override Protocol GUID
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.10.3
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x48ecb431, 0xfb72, 0x45c0, 0xa9, 0x22, &[0xf4, 0x58, 0xfe, 0x04, 0x0b, 0xd5]);
+
+/// Attributes returned by GetEdid().
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.10.3.1
+#[repr(transparent)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EdidOverrideAttributes(pub u32);
+
+impl EdidOverrideAttributes {
+    /// EFI_EDID_OVERRIDE_DONT_OVERRIDE: the EDID returned by GetEdid() must not be used; the display's own EDID is
+    /// used instead.
+    pub const DONT_OVERRIDE: Self = Self(0x01);
+    /// EFI_EDID_OVERRIDE_ENABLE_HOT_PLUG: the display supports hot plug and the returned EDID only applies while no
+    /// display EDID can be read.
+    pub const ENABLE_HOT_PLUG: Self = Self(0x02);
+
+    /// Returns true if all bits of `other` are set.
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for EdidOverrideAttributes {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitAnd for EdidOverrideAttributes {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+/// Returns policy information and potentially a replacement EDID for the specified video output device.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.10.3.1
+pub type GetEdid = extern "efiapi" fn(
+    *const Protocol,
+    child_handle: efi::Handle,
+    attributes: *mut u32,
+    edid_size: *mut usize,
+    edid: *mut *mut u8,
+) -> efi::Status;
+
+/// Produced by the platform to allow the platform to provide EDID information to the producer of the Graphics Output
+/// Protocol.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.10.3
+#[repr(C)]
+#[derive(Debug)]
+pub struct Protocol {
+    pub get_edid: GetEdid,
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::size_of;
+
+    use super::{EdidOverrideAttributes, Protocol, PROTOCOL_GUID};
+
+    #[test]
+    fn guid_should_match_spec() {
+        assert_eq!(
+            PROTOCOL_GUID.as_bytes(),
+            &[0x31, 0xb4, 0xec, 0x48, 0x72, 0xfb, 0xc0, 0x45, 0xa9, 0x22, 0xf4, 0x58, 0xfe, 0x04, 0x0b, 0xd5]
+        );
+    }
+
+    #[test]
+    fn attributes_should_match_spec() {
+        assert_eq!(EdidOverrideAttributes::DONT_OVERRIDE.0, 0x01);
+        assert_eq!(EdidOverrideAttributes::ENABLE_HOT_PLUG.0, 0x02);
+
+        let attributes = EdidOverrideAttributes::DONT_OVERRIDE | EdidOverrideAttributes::ENABLE_HOT_PLUG;
+        assert_eq!(attributes.0, 0x03);
+        assert!(attributes.contains(EdidOverrideAttributes::ENABLE_HOT_PLUG));
+        assert!(!EdidOverrideAttributes::default().contains(EdidOverrideAttributes::DONT_OVERRIDE));
+        assert_eq!(size_of::<EdidOverrideAttributes>(), size_of::<u32>());
+    }
+
+    #[test]
+    fn protocol_layout_should_match_spec() {
+        assert_eq!(size_of::<Protocol>(), size_of::<usize>());
+    }
+}
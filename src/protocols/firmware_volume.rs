@@ -30,6 +30,7 @@ pub const PROTOCOL_GUID: Guid =
 pub type EfiFvWritePolicy = u32;
 
 #[repr(C)]
+#[derive(Debug)]
 pub struct EfiFvWriteFileData {
     name_guid: *mut Guid,
     file_type: EfiFvFileType,
@@ -85,6 +86,7 @@ pub type SetInfo = extern "efiapi" fn(*const Protocol, *const Guid, usize, *cons
 /// # Documentation
 /// UEFI Platform Initialization Specification, Release 1.8, Section III-3.4.1.1
 #[repr(C)]
+#[derive(Debug)]
 pub struct Protocol {
     pub get_volume_attributes: GetVolumeAttributes,
     pub set_volume_attributes: SetVolumeAttributes,
@@ -45,6 +45,7 @@ pub type EraseBlocks = extern "efiapi" fn(
 /// # Documentation
 /// UEFI Platform Initialization Specification, Release 1.8, Section III-3.4.2.1
 #[repr(C)]
+#[derive(Debug)]
 pub struct Protocol {
     pub get_attributes: GetAttributes,
     pub set_attributes: SetAttributes,
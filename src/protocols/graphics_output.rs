@@ -0,0 +1,246 @@
+//! Graphics Output Protocol
+//!
+//! Provides a basic abstraction to set video modes and copy pixels to and from the graphics controller's frame buffer.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/12_Protocols_Console_Support.html#efi-graphics-output-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use r_efi::efi;
+
+/// Graphics Output Protocol GUID
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.9.2
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x9042a9de, 0x23dc, 0x4a38, 0x96, 0xfb, &[0x7a, 0xde, 0xd0, 0x80, 0x51, 0x6a]);
+
+/// Physical layout of a pixel in the frame buffer.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.9.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// Byte 0 is red, byte 1 is green, byte 2 is blue, and byte 3 is reserved.
+    PixelRedGreenBlueReserved8BitPerColor,
+    /// Byte 0 is blue, byte 1 is green, byte 2 is red, and byte 3 is reserved.
+    PixelBlueGreenRedReserved8BitPerColor,
+    /// The pixel layout is described by the pixel_information field of the mode information.
+    PixelBitMask,
+    /// The graphics device does not support a physical frame buffer; only Blt() may be used.
+    PixelBltOnly,
+    /// Valid PixelFormat values are less than this value.
+    PixelFormatMax,
+}
+
+/// Bits of a pixel used for each color when the pixel format is [`PixelFormat::PixelBitMask`].
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.9.2
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PixelBitmask {
+    pub red_mask: u32,
+    pub green_mask: u32,
+    pub blue_mask: u32,
+    pub reserved_mask: u32,
+}
+
+/// Describes a graphics mode.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.9.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GraphicsOutputModeInformation {
+    /// Version of this data structure. Zero for this version of the specification.
+    pub version: u32,
+    /// The size of the video screen in pixels in the X dimension.
+    pub horizontal_resolution: u32,
+    /// The size of the video screen in pixels in the Y dimension.
+    pub vertical_resolution: u32,
+    /// The physical format of the pixel.
+    pub pixel_format: PixelFormat,
+    /// The bit mask of the pixel. Only valid if pixel_format is [`PixelFormat::PixelBitMask`].
+    pub pixel_information: PixelBitmask,
+    /// The number of pixel elements per video memory line, which may exceed horizontal_resolution for padding.
+    pub pixels_per_scan_line: u32,
+}
+
+/// The current mode of a graphics device.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.9.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GraphicsOutputMode {
+    /// The number of modes supported by QueryMode() and SetMode().
+    pub max_mode: u32,
+    /// The current mode of the graphics device.
+    pub mode: u32,
+    /// Pointer to the information of the current mode.
+    pub info: *mut GraphicsOutputModeInformation,
+    /// The size of the structure pointed to by info.
+    pub size_of_info: usize,
+    /// Base address of the graphics linear frame buffer.
+    pub frame_buffer_base: efi::PhysicalAddress,
+    /// Size of the frame buffer in bytes.
+    pub frame_buffer_size: usize,
+}
+
+/// A pixel as used by Blt().
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.9.2.3
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BltPixel {
+    pub blue: u8,
+    pub green: u8,
+    pub red: u8,
+    pub reserved: u8,
+}
+
+/// The operation to be performed by Blt().
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.9.2.3
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BltOperation {
+    /// Writes the pixel at (0, 0) of the Blt buffer to every pixel of the destination rectangle.
+    BltVideoFill,
+    /// Reads the source rectangle from video memory into the Blt buffer.
+    BltVideoToBltBuffer,
+    /// Writes the source rectangle of the Blt buffer to video memory.
+    BltBufferToVideo,
+    /// Copies the source rectangle of video memory to the destination rectangle of video memory.
+    BltVideoToVideo,
+    /// Valid BltOperation values are less than this value.
+    BltOperationMax,
+}
+
+/// Returns information for an available graphics mode that the graphics device and the set of active video output
+/// devices supports.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.9.2.1
+pub type QueryMode = extern "efiapi" fn(
+    *const Protocol,
+    mode_number: u32,
+    size_of_info: *mut usize,
+    info: *mut *mut GraphicsOutputModeInformation,
+) -> efi::Status;
+
+/// Sets the video device into the specified mode and clears the visible portions of the output display to black.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.9.2.2
+pub type SetMode = extern "efiapi" fn(*const Protocol, mode_number: u32) -> efi::Status;
+
+/// Blt a rectangle of pixels on the graphics screen.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.9.2.3
+pub type Blt = extern "efiapi" fn(
+    *const Protocol,
+    blt_buffer: *mut BltPixel,
+    blt_operation: BltOperation,
+    source_x: usize,
+    source_y: usize,
+    destination_x: usize,
+    destination_y: usize,
+    width: usize,
+    height: usize,
+    delta: usize,
+) -> efi::Status;
+
+/// Provides a basic abstraction to set video modes and copy pixels to and from the graphics controller's frame buffer.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.9.2
+#[repr(C)]
+#[derive(Debug)]
+pub struct Protocol {
+    pub query_mode: QueryMode,
+    pub set_mode: SetMode,
+    pub blt: Blt,
+    /// Pointer to the current mode of the graphics device.
+    pub mode: *mut GraphicsOutputMode,
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::{align_of, size_of};
+
+    use r_efi::{efi, protocols::graphics_output};
+
+    use super::{
+        BltOperation, BltPixel, GraphicsOutputMode, GraphicsOutputModeInformation, PixelBitmask, PixelFormat, Protocol,
+        PROTOCOL_GUID,
+    };
+
+    #[test]
+    fn guid_should_match_spec() {
+        assert_eq!(PROTOCOL_GUID, graphics_output::PROTOCOL_GUID);
+        assert_eq!(
+            PROTOCOL_GUID.as_bytes(),
+            &[0xde, 0xa9, 0x42, 0x90, 0xdc, 0x23, 0x38, 0x4a, 0x96, 0xfb, 0x7a, 0xde, 0xd0, 0x80, 0x51, 0x6a]
+        );
+    }
+
+    #[test]
+    fn enum_values_should_match_spec() {
+        assert_eq!(size_of::<PixelFormat>(), 4);
+        assert_eq!(PixelFormat::PixelRedGreenBlueReserved8BitPerColor as u32, 0);
+        assert_eq!(PixelFormat::PixelBlueGreenRedReserved8BitPerColor as u32, 1);
+        assert_eq!(PixelFormat::PixelBitMask as u32, 2);
+        assert_eq!(PixelFormat::PixelBltOnly as u32, 3);
+        assert_eq!(PixelFormat::PixelFormatMax as u32, 4);
+
+        assert_eq!(size_of::<BltOperation>(), 4);
+        assert_eq!(BltOperation::BltVideoFill as u32, 0);
+        assert_eq!(BltOperation::BltVideoToBltBuffer as u32, 1);
+        assert_eq!(BltOperation::BltBufferToVideo as u32, 2);
+        assert_eq!(BltOperation::BltVideoToVideo as u32, 3);
+        assert_eq!(BltOperation::BltOperationMax as u32, 4);
+    }
+
+    #[test]
+    fn struct_layouts_should_match_spec() {
+        assert_eq!(size_of::<PixelBitmask>(), 16);
+        assert_eq!(size_of::<BltPixel>(), 4);
+        assert_eq!(offset_of!(BltPixel, blue), 0);
+        assert_eq!(offset_of!(BltPixel, green), 1);
+        assert_eq!(offset_of!(BltPixel, red), 2);
+        assert_eq!(offset_of!(BltPixel, reserved), 3);
+
+        assert_eq!(size_of::<GraphicsOutputModeInformation>(), 36);
+        assert_eq!(align_of::<GraphicsOutputModeInformation>(), 4);
+        assert_eq!(offset_of!(GraphicsOutputModeInformation, pixel_format), 12);
+        assert_eq!(offset_of!(GraphicsOutputModeInformation, pixel_information), 16);
+        assert_eq!(offset_of!(GraphicsOutputModeInformation, pixels_per_scan_line), 32);
+        assert_eq!(size_of::<GraphicsOutputModeInformation>(), size_of::<graphics_output::ModeInformation>());
+
+        let ptr = size_of::<usize>();
+        assert_eq!(offset_of!(GraphicsOutputMode, max_mode), 0);
+        assert_eq!(offset_of!(GraphicsOutputMode, mode), 4);
+        assert_eq!(offset_of!(GraphicsOutputMode, info), 8);
+        assert_eq!(offset_of!(GraphicsOutputMode, size_of_info), 8 + ptr);
+        assert_eq!(offset_of!(GraphicsOutputMode, frame_buffer_base), 8 + 2 * ptr);
+        assert_eq!(offset_of!(GraphicsOutputMode, frame_buffer_size), 8 + 2 * ptr + size_of::<efi::PhysicalAddress>());
+        assert_eq!(size_of::<GraphicsOutputMode>(), size_of::<graphics_output::Mode>());
+
+        assert_eq!(size_of::<Protocol>(), 4 * ptr);
+        assert_eq!(offset_of!(Protocol, query_mode), 0);
+        assert_eq!(offset_of!(Protocol, set_mode), ptr);
+        assert_eq!(offset_of!(Protocol, blt), 2 * ptr);
+        assert_eq!(offset_of!(Protocol, mode), 3 * ptr);
+    }
+}
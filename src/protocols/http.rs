@@ -0,0 +1,366 @@
+//! HTTP Protocol
+//!
+//! Abstracts HTTP access to a network resource, as used by HTTP boot.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/29_Network_Protocols_ARP_and_DHCPv4.html#efi-http-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{ffi::c_void, fmt};
+
+use r_efi::efi;
+
+/// HTTP Protocol GUID
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.6.2
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x7a59b29b, 0x910b, 0x4171, 0x82, 0x42, &[0xa8, 0x5a, 0x0d, 0xf2, 0x5b, 0x5b]);
+
+/// HTTP Service Binding Protocol GUID
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.6.1
+pub const SERVICE_BINDING_PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0xbdc8e6af, 0xd9bc, 0x4379, 0xa7, 0x2a, &[0xe0, 0xc4, 0xe7, 0x5d, 0xae, 0x1c]);
+
+/// HTTP protocol version.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.6.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HttpVersion {
+    HttpVersion10,
+    HttpVersion11,
+    HttpVersionUnsupported,
+}
+
+/// Local address and port of an IPv4 HTTP instance (EFI_HTTPv4_ACCESS_POINT).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.6.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Httpv4AccessPoint {
+    /// If true, the default address information from the EFI IPv4 Configuration II Protocol is used.
+    pub use_default_address: efi::Boolean,
+    pub local_address: efi::Ipv4Address,
+    pub local_subnet: efi::Ipv4Address,
+    /// Local port; zero to select an ephemeral port.
+    pub local_port: u16,
+}
+
+/// Local address and port of an IPv6 HTTP instance (EFI_HTTPv6_ACCESS_POINT).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.6.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Httpv6AccessPoint {
+    pub local_address: efi::Ipv6Address,
+    /// Local port; zero to select an ephemeral port.
+    pub local_port: u16,
+}
+
+/// Access point of an HTTP instance, selected by [`HttpConfigData::local_address_is_ipv6`].
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union HttpAccessPoint {
+    pub ipv4_node: *mut Httpv4AccessPoint,
+    pub ipv6_node: *mut Httpv6AccessPoint,
+}
+
+/// Prints the pointer held by the union.
+impl fmt::Debug for HttpAccessPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        //Safety: every field of the union is a pointer.
+        f.debug_tuple("HttpAccessPoint").field(&unsafe { self.ipv4_node }).finish()
+    }
+}
+
+/// Configuration of an HTTP instance (EFI_HTTP_CONFIG_DATA).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.6.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct HttpConfigData {
+    pub http_version: HttpVersion,
+    /// Timeout for receiving a response, in milliseconds.
+    pub time_out_millisec: u32,
+    pub local_address_is_ipv6: efi::Boolean,
+    pub access_point: HttpAccessPoint,
+}
+
+/// HTTP request method.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.6.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HttpMethod {
+    HttpMethodGet,
+    HttpMethodPost,
+    HttpMethodPatch,
+    HttpMethodOptions,
+    HttpMethodConnect,
+    HttpMethodHead,
+    HttpMethodPut,
+    HttpMethodDelete,
+    HttpMethodTrace,
+    /// Valid HttpMethod values are less than this value.
+    HttpMethodMax,
+}
+
+/// Request line of an HTTP request message (EFI_HTTP_REQUEST_DATA).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.6.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HttpRequestData {
+    pub method: HttpMethod,
+    /// Null-terminated UCS-2 URL of the resource.
+    pub url: *mut efi::Char16,
+}
+
+/// HTTP response status code.
+///
+/// Note that the discriminants are not the numeric status codes; [`HttpStatusCode::Http308PermanentRedirect`] was
+/// added after the other codes and is numbered last.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.6.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HttpStatusCode {
+    HttpUnsupportedStatus,
+    Http100Continue,
+    Http101SwitchingProtocols,
+    Http200Ok,
+    Http201Created,
+    Http202Accepted,
+    Http203NonAuthoritativeInformation,
+    Http204NoContent,
+    Http205ResetContent,
+    Http206PartialContent,
+    Http300MultipleChoices,
+    Http301MovedPermanently,
+    Http302Found,
+    Http303SeeOther,
+    Http304NotModified,
+    Http305UseProxy,
+    Http307TemporaryRedirect,
+    Http400BadRequest,
+    Http401Unauthorized,
+    Http402PaymentRequired,
+    Http403Forbidden,
+    Http404NotFound,
+    Http405MethodNotAllowed,
+    Http406NotAcceptable,
+    Http407ProxyAuthenticationRequired,
+    Http408RequestTimeOut,
+    Http409Conflict,
+    Http410Gone,
+    Http411LengthRequired,
+    Http412PreconditionFailed,
+    Http413RequestEntityTooLarge,
+    Http414RequestUriTooLarge,
+    Http415UnsupportedMediaType,
+    Http416RequestedRangeNotSatisfied,
+    Http417ExpectationFailed,
+    Http500InternalServerError,
+    Http501NotImplemented,
+    Http502BadGateway,
+    Http503ServiceUnavailable,
+    Http504GatewayTimeOut,
+    Http505HttpVersionNotSupported,
+    Http308PermanentRedirect,
+}
+
+/// Status line of an HTTP response message (EFI_HTTP_RESPONSE_DATA).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.6.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HttpResponseData {
+    pub status_code: HttpStatusCode,
+}
+
+/// An HTTP header field (EFI_HTTP_HEADER).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.6.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HttpHeader {
+    /// Null-terminated ASCII field name.
+    pub field_name: *mut u8,
+    /// Null-terminated ASCII field value.
+    pub field_value: *mut u8,
+}
+
+/// Request or response line of an [`HttpMessage`], depending on whether it is passed to Request() or Response().
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union HttpMessageData {
+    pub request: *mut HttpRequestData,
+    pub response: *mut HttpResponseData,
+}
+
+/// Prints the pointer held by the union.
+impl fmt::Debug for HttpMessageData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        //Safety: every field of the union is a pointer.
+        f.debug_tuple("HttpMessageData").field(&unsafe { self.request }).finish()
+    }
+}
+
+/// An HTTP request or response message (EFI_HTTP_MESSAGE).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.6.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct HttpMessage {
+    pub data: HttpMessageData,
+    pub header_count: usize,
+    /// Array of header_count header fields.
+    pub headers: *mut HttpHeader,
+    pub body_length: usize,
+    pub body: *mut c_void,
+}
+
+/// Completion token for an asynchronous request or response (EFI_HTTP_TOKEN).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.6.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HttpToken {
+    /// Event signaled when the operation completes.
+    pub event: efi::Event,
+    /// Completion status of the operation.
+    pub status: efi::Status,
+    pub message: *mut HttpMessage,
+}
+
+/// Returns the current configuration of the HTTP instance.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.6.2
+pub type GetModeData = extern "efiapi" fn(*const Protocol, http_config_data: *mut HttpConfigData) -> efi::Status;
+
+/// Configures the HTTP instance, or resets it to the unconfigured state if http_config_data is null.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.6.2
+pub type Configure = extern "efiapi" fn(*const Protocol, http_config_data: *mut HttpConfigData) -> efi::Status;
+
+/// Queues an HTTP request.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.6.2
+pub type Request = extern "efiapi" fn(*const Protocol, token: *mut HttpToken) -> efi::Status;
+
+/// Aborts a pending request or response, or all of them if token is null.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.6.2
+pub type Cancel = extern "efiapi" fn(*const Protocol, token: *mut HttpToken) -> efi::Status;
+
+/// Queues a request for (more of) an HTTP response.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.6.2
+pub type Response = extern "efiapi" fn(*const Protocol, token: *mut HttpToken) -> efi::Status;
+
+/// Polls for incoming data packets and processes outgoing data packets.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.6.2
+pub type Poll = extern "efiapi" fn(*const Protocol) -> efi::Status;
+
+/// Abstracts HTTP access to a network resource.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 29.6.2
+#[repr(C)]
+#[derive(Debug)]
+pub struct Protocol {
+    pub get_mode_data: GetModeData,
+    pub configure: Configure,
+    pub request: Request,
+    pub cancel: Cancel,
+    pub response: Response,
+    pub poll: Poll,
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::size_of;
+
+    use super::{
+        HttpConfigData, HttpMessage, HttpMethod, HttpStatusCode, HttpToken, HttpVersion, Httpv4AccessPoint, Protocol,
+        PROTOCOL_GUID, SERVICE_BINDING_PROTOCOL_GUID,
+    };
+
+    #[test]
+    fn guids_should_match_spec() {
+        assert_eq!(
+            PROTOCOL_GUID.as_bytes(),
+            &[0x9b, 0xb2, 0x59, 0x7a, 0x0b, 0x91, 0x71, 0x41, 0x82, 0x42, 0xa8, 0x5a, 0x0d, 0xf2, 0x5b, 0x5b]
+        );
+        assert_eq!(
+            SERVICE_BINDING_PROTOCOL_GUID.as_bytes(),
+            &[0xaf, 0xe6, 0xc8, 0xbd, 0xbc, 0xd9, 0x79, 0x43, 0xa7, 0x2a, 0xe0, 0xc4, 0xe7, 0x5d, 0xae, 0x1c]
+        );
+    }
+
+    #[test]
+    fn enum_discriminants_should_match_spec() {
+        assert_eq!(HttpVersion::HttpVersion10 as u32, 0);
+        assert_eq!(HttpVersion::HttpVersion11 as u32, 1);
+        assert_eq!(HttpVersion::HttpVersionUnsupported as u32, 2);
+
+        assert_eq!(HttpMethod::HttpMethodGet as u32, 0);
+        assert_eq!(HttpMethod::HttpMethodPost as u32, 1);
+        assert_eq!(HttpMethod::HttpMethodPatch as u32, 2);
+        assert_eq!(HttpMethod::HttpMethodOptions as u32, 3);
+        assert_eq!(HttpMethod::HttpMethodConnect as u32, 4);
+        assert_eq!(HttpMethod::HttpMethodHead as u32, 5);
+        assert_eq!(HttpMethod::HttpMethodPut as u32, 6);
+        assert_eq!(HttpMethod::HttpMethodDelete as u32, 7);
+        assert_eq!(HttpMethod::HttpMethodTrace as u32, 8);
+        assert_eq!(HttpMethod::HttpMethodMax as u32, 9);
+
+        assert_eq!(HttpStatusCode::HttpUnsupportedStatus as u32, 0);
+        assert_eq!(HttpStatusCode::Http100Continue as u32, 1);
+        assert_eq!(HttpStatusCode::Http200Ok as u32, 3);
+        assert_eq!(HttpStatusCode::Http300MultipleChoices as u32, 10);
+        assert_eq!(HttpStatusCode::Http307TemporaryRedirect as u32, 16);
+        assert_eq!(HttpStatusCode::Http400BadRequest as u32, 17);
+        assert_eq!(HttpStatusCode::Http404NotFound as u32, 21);
+        assert_eq!(HttpStatusCode::Http417ExpectationFailed as u32, 34);
+        assert_eq!(HttpStatusCode::Http500InternalServerError as u32, 35);
+        assert_eq!(HttpStatusCode::Http505HttpVersionNotSupported as u32, 40);
+        assert_eq!(HttpStatusCode::Http308PermanentRedirect as u32, 41);
+    }
+
+    #[test]
+    fn struct_layouts_should_match_spec() {
+        let ptr = size_of::<usize>();
+        assert_eq!(size_of::<Httpv4AccessPoint>(), 12);
+        assert_eq!(offset_of!(HttpConfigData, access_point), if ptr == 8 { 16 } else { 12 });
+        assert_eq!(size_of::<HttpMessage>(), 5 * ptr);
+        assert_eq!(offset_of!(HttpToken, message), 2 * ptr);
+        assert_eq!(size_of::<Protocol>(), 6 * ptr);
+    }
+}
@@ -0,0 +1,323 @@
+//! IPMI Transport Protocol
+//!
+//! Used to submit IPMI commands to the Baseboard Management Controller (BMC) for out-of-band management.
+//!
+//! This protocol is defined by the edk2-platforms IPMI feature package rather than the UEFI or PI specifications. See
+//! <https://github.com/tianocore/edk2-platforms/tree/master/Features/Intel/OutOfBandManagement/IpmiFeaturePkg>.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+extern crate alloc;
+use alloc::{vec, vec::Vec};
+
+use r_efi::efi;
+
+/// IPMI Transport Protocol GUID
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x6bb945e8, 0x3743, 0x433e, 0xb9, 0x0e, &[0x29, 0xb3, 0x0d, 0x5d, 0xc6, 0x30]);
+
+/// Size of the response buffer first offered to the BMC by [`send_raw_ipmi`].
+pub const MAX_RESPONSE_SIZE: usize = 0x100;
+
+/// IPMI network function codes for requests. The corresponding response network function is the request code plus
+/// one.
+///
+/// # Documentation
+/// Intelligent Platform Management Interface Specification, Second Generation, v2.0, Section 5.1
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IpmiNetworkFunction {
+    Chassis = 0x00,
+    Bridge = 0x02,
+    Sensor = 0x04,
+    App = 0x06,
+    Firmware = 0x08,
+    Storage = 0x0A,
+    Transport = 0x0C,
+    GroupExtension = 0x2C,
+}
+
+impl TryFrom<u8> for IpmiNetworkFunction {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(Self::Chassis),
+            0x02 => Ok(Self::Bridge),
+            0x04 => Ok(Self::Sensor),
+            0x06 => Ok(Self::App),
+            0x08 => Ok(Self::Firmware),
+            0x0A => Ok(Self::Storage),
+            0x0C => Ok(Self::Transport),
+            0x2C => Ok(Self::GroupExtension),
+            _ => Err(value),
+        }
+    }
+}
+
+/// Header of an IPMI request message.
+///
+/// # Documentation
+/// Intelligent Platform Management Interface Specification, Second Generation, v2.0, Section 5.2
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct IpmiRequestHeader {
+    /// Network function in bits 7:2 and logical unit number in bits 1:0.
+    pub net_fn_lun: u8,
+    pub command: u8,
+}
+
+impl IpmiRequestHeader {
+    /// Creates a request header. Only the low two bits of `lun` are used.
+    pub const fn new(net_fn: IpmiNetworkFunction, lun: u8, command: u8) -> Self {
+        Self { net_fn_lun: pack_net_fn_lun(net_fn as u8, lun), command }
+    }
+
+    /// Returns the network function code.
+    pub const fn net_fn(&self) -> u8 {
+        self.net_fn_lun >> 2
+    }
+
+    /// Returns the logical unit number.
+    pub const fn lun(&self) -> u8 {
+        self.net_fn_lun & 0x3
+    }
+}
+
+/// Header of an IPMI response message.
+///
+/// # Documentation
+/// Intelligent Platform Management Interface Specification, Second Generation, v2.0, Section 5.2
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct IpmiResponseHeader {
+    /// Network function in bits 7:2 and logical unit number in bits 1:0.
+    pub net_fn_lun: u8,
+    pub command: u8,
+    /// Completion code; zero if the command completed normally.
+    pub completion_code: u8,
+}
+
+impl IpmiResponseHeader {
+    /// Returns the network function code.
+    pub const fn net_fn(&self) -> u8 {
+        self.net_fn_lun >> 2
+    }
+
+    /// Returns the logical unit number.
+    pub const fn lun(&self) -> u8 {
+        self.net_fn_lun & 0x3
+    }
+}
+
+const fn pack_net_fn_lun(net_fn: u8, lun: u8) -> u8 {
+    (net_fn << 2) | (lun & 0x3)
+}
+
+/// Sends an IPMI command to the BMC and waits for the response.
+///
+/// On input, response_data_size is the size of the response buffer; on return it holds the size of the response,
+/// whose first byte is the completion code. Returns `BUFFER_TOO_SMALL` if the response does not fit.
+pub type SendIpmiCommand = extern "efiapi" fn(
+    *const Protocol,
+    net_function: u8,
+    lun: u8,
+    command: u8,
+    command_data: *const u8,
+    command_data_size: u32,
+    response_data: *mut u8,
+    response_data_size: *mut u32,
+) -> efi::Status;
+
+/// Same as [`SendIpmiCommand`], but sends the command over the given system interface type.
+pub type SendIpmiCommandEx = extern "efiapi" fn(
+    *const Protocol,
+    net_function: u8,
+    lun: u8,
+    command: u8,
+    command_data: *const u8,
+    command_data_size: u32,
+    response_data: *mut u8,
+    response_data_size: *mut u32,
+    interface_type: u32,
+) -> efi::Status;
+
+/// Used to submit IPMI commands to the BMC.
+#[repr(C)]
+#[derive(Debug)]
+pub struct Protocol {
+    pub revision: u64,
+    pub send_ipmi_command: SendIpmiCommand,
+    pub send_ipmi_command_ex: SendIpmiCommandEx,
+}
+
+/// Sends `cmd` with `data` to LUN 0 of the BMC and returns the response, starting with the completion code.
+///
+/// If the response does not fit in [`MAX_RESPONSE_SIZE`] bytes the command is sent again with a buffer of the size
+/// reported by the BMC.
+pub fn send_raw_ipmi(
+    protocol: &Protocol,
+    net_fn: IpmiNetworkFunction,
+    cmd: u8,
+    data: &[u8],
+) -> Result<Vec<u8>, efi::Status> {
+    let data_size = u32::try_from(data.len()).map_err(|_| efi::Status::INVALID_PARAMETER)?;
+    let mut response = vec![0u8; MAX_RESPONSE_SIZE];
+    loop {
+        let mut size = response.len() as u32;
+        let status = (protocol.send_ipmi_command)(
+            protocol,
+            net_fn as u8,
+            0,
+            cmd,
+            data.as_ptr(),
+            data_size,
+            response.as_mut_ptr(),
+            &mut size,
+        );
+        match status {
+            efi::Status::SUCCESS => {
+                response.truncate(size as usize);
+                return Ok(response);
+            }
+            efi::Status::BUFFER_TOO_SMALL if size as usize > response.len() => response.resize(size as usize, 0),
+            status => Err(status)?,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{cell::RefCell, mem::size_of};
+
+    use r_efi::efi;
+
+    use super::{
+        send_raw_ipmi, IpmiNetworkFunction, IpmiRequestHeader, IpmiResponseHeader, Protocol, MAX_RESPONSE_SIZE,
+        PROTOCOL_GUID,
+    };
+
+    // (net_fn, lun, command, data) of a command sent.
+    type Sent = (u8, u8, u8, Vec<u8>);
+
+    std::thread_local! {
+        static SENT: RefCell<Vec<Sent>> = RefCell::new(Vec::new());
+        static RESPONSE: RefCell<Result<Vec<u8>, efi::Status>> = RefCell::new(Ok(Vec::new()));
+    }
+
+    extern "efiapi" fn send_ipmi_command(
+        _: *const Protocol,
+        net_fn: u8,
+        lun: u8,
+        command: u8,
+        data: *const u8,
+        data_size: u32,
+        response_data: *mut u8,
+        response_size: *mut u32,
+    ) -> efi::Status {
+        let data = unsafe { core::slice::from_raw_parts(data, data_size as usize) }.to_vec();
+        SENT.with(|sent| sent.borrow_mut().push((net_fn, lun, command, data)));
+        let response = match RESPONSE.with(|response| response.borrow().clone()) {
+            Ok(response) => response,
+            Err(status) => return status,
+        };
+        let available = unsafe { *response_size } as usize;
+        unsafe { *response_size = response.len() as u32 };
+        if response.len() > available {
+            return efi::Status::BUFFER_TOO_SMALL;
+        }
+        unsafe { core::ptr::copy_nonoverlapping(response.as_ptr(), response_data, response.len()) };
+        efi::Status::SUCCESS
+    }
+
+    extern "efiapi" fn send_ipmi_command_ex(
+        _: *const Protocol,
+        _: u8,
+        _: u8,
+        _: u8,
+        _: *const u8,
+        _: u32,
+        _: *mut u8,
+        _: *mut u32,
+        _: u32,
+    ) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+
+    fn mock_protocol() -> Protocol {
+        Protocol { revision: 1, send_ipmi_command, send_ipmi_command_ex }
+    }
+
+    fn take_sent() -> Vec<Sent> {
+        SENT.with(|sent| core::mem::take(&mut *sent.borrow_mut()))
+    }
+
+    #[test]
+    fn guid_should_match_edk2() {
+        assert_eq!(
+            PROTOCOL_GUID.as_bytes(),
+            &[0xe8, 0x45, 0xb9, 0x6b, 0x43, 0x37, 0x3e, 0x43, 0xb9, 0x0e, 0x29, 0xb3, 0x0d, 0x5d, 0xc6, 0x30]
+        );
+        assert_eq!(size_of::<Protocol>(), 8 + 2 * size_of::<usize>());
+    }
+
+    #[test]
+    fn network_functions_should_match_ipmi_spec() {
+        assert_eq!(IpmiNetworkFunction::Chassis as u8, 0x00);
+        assert_eq!(IpmiNetworkFunction::Bridge as u8, 0x02);
+        assert_eq!(IpmiNetworkFunction::Sensor as u8, 0x04);
+        assert_eq!(IpmiNetworkFunction::App as u8, 0x06);
+        assert_eq!(IpmiNetworkFunction::Firmware as u8, 0x08);
+        assert_eq!(IpmiNetworkFunction::Storage as u8, 0x0A);
+        assert_eq!(IpmiNetworkFunction::Transport as u8, 0x0C);
+        assert_eq!(IpmiNetworkFunction::GroupExtension as u8, 0x2C);
+        assert_eq!(IpmiNetworkFunction::try_from(0x0A), Ok(IpmiNetworkFunction::Storage));
+        assert_eq!(IpmiNetworkFunction::try_from(0x07), Err(0x07));
+    }
+
+    #[test]
+    fn headers_should_pack_net_fn_and_lun() {
+        let header = IpmiRequestHeader::new(IpmiNetworkFunction::App, 0x2, 0x01);
+        assert_eq!(header.net_fn_lun, 0x1A);
+        assert_eq!(header.net_fn(), 0x06);
+        assert_eq!(header.lun(), 0x2);
+        assert_eq!(IpmiRequestHeader::new(IpmiNetworkFunction::App, 0xFF, 0x01).lun(), 0x3);
+
+        let response = IpmiResponseHeader { net_fn_lun: 0x1C, command: 0x01, completion_code: 0 };
+        assert_eq!(response.net_fn(), 0x07);
+        assert_eq!(response.lun(), 0);
+        assert_eq!(size_of::<IpmiRequestHeader>(), 2);
+        assert_eq!(size_of::<IpmiResponseHeader>(), 3);
+    }
+
+    #[test]
+    fn send_raw_ipmi_should_return_response() {
+        let protocol = mock_protocol();
+        RESPONSE.with(|response| *response.borrow_mut() = Ok(vec![0x00, 0x20, 0x01]));
+        let response = send_raw_ipmi(&protocol, IpmiNetworkFunction::App, 0x01, &[0xAA]);
+        assert_eq!(response, Ok(vec![0x00, 0x20, 0x01]));
+        assert_eq!(take_sent(), vec![(0x06, 0, 0x01, vec![0xAA])]);
+    }
+
+    #[test]
+    fn send_raw_ipmi_should_retry_large_responses() {
+        let protocol = mock_protocol();
+        let large = vec![0x5A; MAX_RESPONSE_SIZE + 10];
+        RESPONSE.with(|response| *response.borrow_mut() = Ok(large.clone()));
+        assert_eq!(send_raw_ipmi(&protocol, IpmiNetworkFunction::Storage, 0x40, &[]), Ok(large));
+        assert_eq!(take_sent().len(), 2);
+    }
+
+    #[test]
+    fn send_raw_ipmi_should_return_errors() {
+        let protocol = mock_protocol();
+        RESPONSE.with(|response| *response.borrow_mut() = Err(efi::Status::TIMEOUT));
+        assert_eq!(send_raw_ipmi(&protocol, IpmiNetworkFunction::Chassis, 0x02, &[]), Err(efi::Status::TIMEOUT));
+        take_sent();
+    }
+}
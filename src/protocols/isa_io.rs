@@ -0,0 +1,274 @@
+//! ISA I/O Protocol
+//!
+//! Provides the basic memory, I/O, and DMA interfaces used to abstract accesses to legacy ISA devices, along with the
+//! ISA ACPI Protocol that describes the resources of those devices.
+//!
+//! These protocols are defined by the Intel Platform Innovation Framework for EFI rather than the UEFI or PI
+//! specifications. See <https://github.com/tianocore/edk2/blob/master/OvmfPkg/Include/Protocol/IsaIo.h> and
+//! <https://github.com/tianocore/edk2/blob/master/OvmfPkg/Include/Protocol/IsaAcpi.h>.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::ffi::c_void;
+
+use r_efi::efi;
+
+/// ISA I/O Protocol GUID
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x7ee2bd44, 0x3da0, 0x11d4, 0x9a, 0x38, &[0x00, 0x90, 0x27, 0x3f, 0xc1, 0x4d]);
+
+/// ISA ACPI Protocol GUID
+pub const ISA_ACPI_PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x64a892dc, 0x5561, 0x4536, 0x92, 0xc7, &[0x79, 0x9b, 0xfc, 0x18, 0x33, 0x55]);
+
+/// Width of the accesses performed by the ISA I/O functions.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IsaIoWidthType {
+    Uint8,
+    Uint16,
+    Uint32,
+    Reserved,
+    FifoUint8,
+    FifoUint16,
+    FifoUint32,
+    FifoReserved,
+    FillUint8,
+    FillUint16,
+    FillUint32,
+    FillReserved,
+    Maximum,
+}
+
+/// DMA operation requested of Map().
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IsaIoOperation {
+    BusMasterRead,
+    BusMasterWrite,
+    BusMasterCommonBuffer,
+    SlaveRead,
+    SlaveWrite,
+    Maximum,
+}
+
+/// Type of an ISA device resource.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IsaAcpiResourceType {
+    /// Terminates a resource list.
+    EndOfList,
+    Io,
+    Memory,
+    Dma,
+    Interrupt,
+}
+
+/// A resource of an ISA device.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IsaAcpiResource {
+    pub resource_type: IsaAcpiResourceType,
+    pub attribute: u32,
+    pub start_range: u32,
+    pub end_range: u32,
+}
+
+/// ACPI identifier of an ISA device.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct IsaAcpiDeviceId {
+    pub hid: u32,
+    pub uid: u32,
+}
+
+/// The resources of an ISA device.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct IsaAcpiResourceList {
+    pub device: IsaAcpiDeviceId,
+    /// Array of resources terminated by an [`IsaAcpiResourceType::EndOfList`] entry.
+    pub resource_item: *mut IsaAcpiResource,
+}
+
+/// Reads or writes ISA memory or I/O space.
+pub type IoMem = extern "efiapi" fn(
+    *const Protocol,
+    width: IsaIoWidthType,
+    offset: u32,
+    count: usize,
+    buffer: *mut c_void,
+) -> efi::Status;
+
+/// Read and write functions for one ISA address space.
+#[repr(C)]
+#[derive(Debug)]
+pub struct IsaIoAccess {
+    pub read: IoMem,
+    pub write: IoMem,
+}
+
+/// Copies one region of ISA memory space to another.
+pub type CopyMem = extern "efiapi" fn(
+    *const Protocol,
+    width: IsaIoWidthType,
+    dest_offset: u32,
+    src_offset: u32,
+    count: usize,
+) -> efi::Status;
+
+/// Maps a memory region for DMA.
+pub type Map = extern "efiapi" fn(
+    *const Protocol,
+    operation: IsaIoOperation,
+    channel_number: u8,
+    channel_attributes: u32,
+    host_address: *mut c_void,
+    number_of_bytes: *mut usize,
+    device_address: *mut efi::PhysicalAddress,
+    mapping: *mut *mut c_void,
+) -> efi::Status;
+
+/// Completes a Map() operation and releases any corresponding resources.
+pub type Unmap = extern "efiapi" fn(*const Protocol, mapping: *mut c_void) -> efi::Status;
+
+/// Allocates pages that are suitable for a BusMasterCommonBuffer mapping.
+pub type AllocateBuffer = extern "efiapi" fn(
+    *const Protocol,
+    allocate_type: efi::AllocateType,
+    memory_type: efi::MemoryType,
+    pages: usize,
+    host_address: *mut *mut c_void,
+    attributes: u64,
+) -> efi::Status;
+
+/// Frees memory allocated with AllocateBuffer().
+pub type FreeBuffer = extern "efiapi" fn(*const Protocol, pages: usize, host_address: *mut c_void) -> efi::Status;
+
+/// Flushes all posted write transactions to system memory.
+pub type Flush = extern "efiapi" fn(*const Protocol) -> efi::Status;
+
+/// Provides the basic memory, I/O, and DMA interfaces used to abstract accesses to an ISA device.
+#[repr(C)]
+#[derive(Debug)]
+pub struct Protocol {
+    pub mem: IsaIoAccess,
+    pub io: IsaIoAccess,
+    pub copy_mem: CopyMem,
+    pub map: Map,
+    pub unmap: Unmap,
+    pub allocate_buffer: AllocateBuffer,
+    pub free_buffer: FreeBuffer,
+    pub flush: Flush,
+    /// The resources of the ISA device.
+    pub resource_list: *mut IsaAcpiResourceList,
+    /// The size of the option ROM of the ISA device, in bytes.
+    pub rom_size: u32,
+    /// Pointer to the option ROM image of the ISA device.
+    pub rom_image: *mut c_void,
+}
+
+/// Enumerates the ISA devices on the ISA bus. Returns `NOT_FOUND` after the last device.
+pub type DeviceEnumerate = extern "efiapi" fn(*const IsaAcpiProtocol, device: *mut *mut IsaAcpiDeviceId) -> efi::Status;
+
+/// Sets the power state of an ISA device.
+pub type SetPower =
+    extern "efiapi" fn(*const IsaAcpiProtocol, device: *mut IsaAcpiDeviceId, on_off: efi::Boolean) -> efi::Status;
+
+/// Returns the current or possible resources of an ISA device.
+pub type GetResource = extern "efiapi" fn(
+    *const IsaAcpiProtocol,
+    device: *mut IsaAcpiDeviceId,
+    resource_list: *mut *mut IsaAcpiResourceList,
+) -> efi::Status;
+
+/// Sets the resources of an ISA device.
+pub type SetResource = extern "efiapi" fn(
+    *const IsaAcpiProtocol,
+    device: *mut IsaAcpiDeviceId,
+    resource_list: *mut IsaAcpiResourceList,
+) -> efi::Status;
+
+/// Enables or disables an ISA device.
+pub type EnableDevice =
+    extern "efiapi" fn(*const IsaAcpiProtocol, device: *mut IsaAcpiDeviceId, enable: efi::Boolean) -> efi::Status;
+
+/// Initializes an ISA device.
+pub type InitDevice = extern "efiapi" fn(*const IsaAcpiProtocol, device: *mut IsaAcpiDeviceId) -> efi::Status;
+
+/// Initializes the ISA ACPI interface.
+pub type InterfaceInit = extern "efiapi" fn(*const IsaAcpiProtocol) -> efi::Status;
+
+/// Enumerates the devices on an ISA bus and manages their resources.
+#[repr(C)]
+#[derive(Debug)]
+pub struct IsaAcpiProtocol {
+    pub device_enumerate: DeviceEnumerate,
+    pub set_power: SetPower,
+    pub get_cur_resource: GetResource,
+    pub get_pos_resource: GetResource,
+    pub set_resource: SetResource,
+    pub enable_device: EnableDevice,
+    pub init_device: InitDevice,
+    pub interface_init: InterfaceInit,
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::size_of;
+
+    use super::{
+        IsaAcpiProtocol, IsaAcpiResource, IsaAcpiResourceList, IsaAcpiResourceType, IsaIoAccess, IsaIoOperation,
+        IsaIoWidthType, Protocol, ISA_ACPI_PROTOCOL_GUID, PROTOCOL_GUID,
+    };
+
+    #[test]
+    fn guids_should_match_edk2() {
+        assert_eq!(
+            PROTOCOL_GUID.as_bytes(),
+            &[0x44, 0xbd, 0xe2, 0x7e, 0xa0, 0x3d, 0xd4, 0x11, 0x9a, 0x38, 0x00, 0x90, 0x27, 0x3f, 0xc1, 0x4d]
+        );
+        assert_eq!(
+            ISA_ACPI_PROTOCOL_GUID.as_bytes(),
+            &[0xdc, 0x92, 0xa8, 0x64, 0x61, 0x55, 0x36, 0x45, 0x92, 0xc7, 0x79, 0x9b, 0xfc, 0x18, 0x33, 0x55]
+        );
+    }
+
+    #[test]
+    fn enum_values_should_match_edk2() {
+        assert_eq!(IsaIoWidthType::Uint8 as u32, 0);
+        assert_eq!(IsaIoWidthType::FifoUint8 as u32, 4);
+        assert_eq!(IsaIoWidthType::FillUint8 as u32, 8);
+        assert_eq!(IsaIoWidthType::Maximum as u32, 12);
+        assert_eq!(IsaIoOperation::SlaveWrite as u32, 4);
+        assert_eq!(IsaAcpiResourceType::EndOfList as u32, 0);
+        assert_eq!(IsaAcpiResourceType::Io as u32, 1);
+        assert_eq!(IsaAcpiResourceType::Memory as u32, 2);
+        assert_eq!(IsaAcpiResourceType::Dma as u32, 3);
+        assert_eq!(IsaAcpiResourceType::Interrupt as u32, 4);
+    }
+
+    #[test]
+    fn struct_layouts_should_match_edk2() {
+        let ptr = size_of::<usize>();
+        assert_eq!(size_of::<IsaAcpiResource>(), 16);
+        assert_eq!(size_of::<IsaAcpiResourceList>(), 8 + ptr);
+        assert_eq!(size_of::<IsaIoAccess>(), 2 * ptr);
+
+        assert_eq!(offset_of!(Protocol, mem), 0);
+        assert_eq!(offset_of!(Protocol, io), 2 * ptr);
+        assert_eq!(offset_of!(Protocol, copy_mem), 4 * ptr);
+        assert_eq!(offset_of!(Protocol, flush), 9 * ptr);
+        assert_eq!(offset_of!(Protocol, resource_list), 10 * ptr);
+        assert_eq!(offset_of!(Protocol, rom_size), 11 * ptr);
+        assert_eq!(offset_of!(Protocol, rom_image), 12 * ptr);
+        assert_eq!(size_of::<Protocol>(), 13 * ptr);
+
+        assert_eq!(size_of::<IsaAcpiProtocol>(), 8 * ptr);
+    }
+}
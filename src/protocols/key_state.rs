@@ -0,0 +1,208 @@
+//! Key State
+//!
+//! Shift and toggle key state reported by the Simple Text Input Ex Protocol.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/12_Protocols_Console_Support.html#efi-simple-text-input-ex-protocol-readkeystrokeex>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::ops::{BitAnd, BitOr};
+
+/// State of the toggle keys (EFI_KEY_TOGGLE_STATE).
+///
+/// The lock bits are those of EFI_SCROLL_LOCK_ACTIVE (0x01), EFI_NUM_LOCK_ACTIVE (0x02) and EFI_CAPS_LOCK_ACTIVE
+/// (0x04) as defined by the specification and reported by Simple Text Input Ex producers, not shifted up by one or
+/// two bits.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.2.3
+#[repr(transparent)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct KeyToggleState(pub u8);
+
+impl KeyToggleState {
+    pub const SCROLL_LOCK_ACTIVE: Self = Self(0x01);
+    pub const NUM_LOCK_ACTIVE: Self = Self(0x02);
+    pub const CAPS_LOCK_ACTIVE: Self = Self(0x04);
+    /// The device supports reporting partial keystrokes.
+    pub const KEY_STATE_EXPOSED: Self = Self(0x40);
+    /// The toggle state is valid.
+    pub const TOGGLE_STATE_VALID: Self = Self(0x80);
+
+    /// Returns true if all bits of `other` are set.
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns true if the toggle state is valid.
+    pub fn is_valid(&self) -> bool {
+        self.contains(Self::TOGGLE_STATE_VALID)
+    }
+}
+
+impl BitOr for KeyToggleState {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitAnd for KeyToggleState {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+/// State of the shift keys (the KeyShiftState field of EFI_KEY_STATE).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.2.3
+#[repr(transparent)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct KeyShiftState(pub u32);
+
+impl KeyShiftState {
+    pub const RIGHT_SHIFT_PRESSED: Self = Self(0x00000001);
+    pub const LEFT_SHIFT_PRESSED: Self = Self(0x00000002);
+    pub const RIGHT_CONTROL_PRESSED: Self = Self(0x00000004);
+    pub const LEFT_CONTROL_PRESSED: Self = Self(0x00000008);
+    pub const RIGHT_ALT_PRESSED: Self = Self(0x00000010);
+    pub const LEFT_ALT_PRESSED: Self = Self(0x00000020);
+    pub const RIGHT_LOGO_PRESSED: Self = Self(0x00000040);
+    pub const LEFT_LOGO_PRESSED: Self = Self(0x00000080);
+    pub const MENU_KEY_PRESSED: Self = Self(0x00000100);
+    pub const SYS_REQ_PRESSED: Self = Self(0x00000200);
+    /// The shift state is valid.
+    pub const SHIFT_STATE_VALID: Self = Self(0x80000000);
+
+    /// All shift key bits.
+    pub const ANY_PRESSED: Self = Self(0x000003FF);
+
+    /// Returns true if all bits of `other` are set.
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns true if the shift state is valid.
+    pub fn is_valid(&self) -> bool {
+        self.contains(Self::SHIFT_STATE_VALID)
+    }
+}
+
+impl BitOr for KeyShiftState {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitAnd for KeyShiftState {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+/// Shift and toggle key state of a keystroke (EFI_KEY_STATE).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.2.3
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct KeyState {
+    /// Reflects the currently pressed shift modifiers. Only valid if SHIFT_STATE_VALID is set.
+    pub key_shift_state: KeyShiftState,
+    /// Reflects the current internal state of the toggle keys. Only valid if TOGGLE_STATE_VALID is set.
+    pub key_toggle_state: KeyToggleState,
+}
+
+/// Returns true if the shift state is valid and any shift, control, alt, logo, menu, or SysReq key is pressed.
+pub fn any_modifier_pressed(state: &KeyState) -> bool {
+    state.key_shift_state.is_valid() && (state.key_shift_state & KeyShiftState::ANY_PRESSED).0 != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::{align_of, size_of};
+
+    use r_efi::protocols::simple_text_input_ex;
+
+    use super::{any_modifier_pressed, KeyShiftState, KeyState, KeyToggleState};
+
+    #[test]
+    fn toggle_state_bits_should_match_spec() {
+        assert_eq!(KeyToggleState::SCROLL_LOCK_ACTIVE.0, 0x01);
+        assert_eq!(KeyToggleState::NUM_LOCK_ACTIVE.0, 0x02);
+        assert_eq!(KeyToggleState::CAPS_LOCK_ACTIVE.0, 0x04);
+        assert_eq!(KeyToggleState::KEY_STATE_EXPOSED.0, 0x40);
+        assert_eq!(KeyToggleState::TOGGLE_STATE_VALID.0, 0x80);
+        assert_eq!(KeyToggleState::SCROLL_LOCK_ACTIVE.0, simple_text_input_ex::SCROLL_LOCK_ACTIVE);
+        assert_eq!(KeyToggleState::NUM_LOCK_ACTIVE.0, simple_text_input_ex::NUM_LOCK_ACTIVE);
+        assert_eq!(KeyToggleState::CAPS_LOCK_ACTIVE.0, simple_text_input_ex::CAPS_LOCK_ACTIVE);
+        assert_eq!(KeyToggleState::TOGGLE_STATE_VALID.0, simple_text_input_ex::TOGGLE_STATE_VALID);
+
+        let state = KeyToggleState::TOGGLE_STATE_VALID | KeyToggleState::CAPS_LOCK_ACTIVE;
+        assert_eq!(state.0, 0x84);
+        assert!(state.is_valid());
+        assert!(state.contains(KeyToggleState::CAPS_LOCK_ACTIVE));
+        assert!(!state.contains(KeyToggleState::NUM_LOCK_ACTIVE));
+        assert!(!KeyToggleState(0x04).is_valid());
+    }
+
+    #[test]
+    fn shift_state_bits_should_match_spec() {
+        let bits = [
+            KeyShiftState::RIGHT_SHIFT_PRESSED,
+            KeyShiftState::LEFT_SHIFT_PRESSED,
+            KeyShiftState::RIGHT_CONTROL_PRESSED,
+            KeyShiftState::LEFT_CONTROL_PRESSED,
+            KeyShiftState::RIGHT_ALT_PRESSED,
+            KeyShiftState::LEFT_ALT_PRESSED,
+            KeyShiftState::RIGHT_LOGO_PRESSED,
+            KeyShiftState::LEFT_LOGO_PRESSED,
+            KeyShiftState::MENU_KEY_PRESSED,
+            KeyShiftState::SYS_REQ_PRESSED,
+        ];
+        for (idx, bit) in bits.iter().enumerate() {
+            assert_eq!(bit.0, 1 << idx);
+        }
+        let all = bits.iter().fold(KeyShiftState::default(), |acc, bit| acc | *bit);
+        assert_eq!(all, KeyShiftState::ANY_PRESSED);
+        assert_eq!(KeyShiftState::SHIFT_STATE_VALID.0, 0x80000000);
+    }
+
+    #[test]
+    fn key_state_layout_should_match_spec() {
+        assert_eq!(size_of::<KeyState>(), 8);
+        assert_eq!(align_of::<KeyState>(), 4);
+    }
+
+    #[test]
+    fn any_modifier_pressed_should_require_valid_state() {
+        let mut state = KeyState::default();
+        assert!(!any_modifier_pressed(&state));
+
+        state.key_shift_state = KeyShiftState::LEFT_CONTROL_PRESSED;
+        assert!(!any_modifier_pressed(&state));
+
+        state.key_shift_state = KeyShiftState::SHIFT_STATE_VALID;
+        assert!(!any_modifier_pressed(&state));
+
+        state.key_shift_state = KeyShiftState::SHIFT_STATE_VALID | KeyShiftState::LEFT_CONTROL_PRESSED;
+        assert!(any_modifier_pressed(&state));
+
+        state.key_shift_state = KeyShiftState::SHIFT_STATE_VALID | KeyShiftState::SYS_REQ_PRESSED;
+        state.key_toggle_state = KeyToggleState::TOGGLE_STATE_VALID | KeyToggleState::NUM_LOCK_ACTIVE;
+        assert!(any_modifier_pressed(&state));
+    }
+}
@@ -0,0 +1,383 @@
+//! Legacy BIOS Protocol
+//!
+//! Abstracts the legacy BIOS provided by a Compatibility Support Module (CSM): issuing 16-bit BIOS calls, shadowing
+//! option ROMs, and booting legacy devices through the BIOS Boot Specification (BBS) table.
+//!
+//! Defined by the Intel Platform Innovation Framework for EFI Compatibility Support Module Specification, which
+//! predates the PI Specification.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::ffi::c_void;
+
+use r_efi::{efi, protocols::device_path};
+
+/// Legacy BIOS Protocol GUID
+///
+/// # Documentation
+/// Intel Platform Innovation Framework for EFI Compatibility Support Module Specification, Revision 0.97
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0xdb9a1e3d, 0x45cb, 0x4abb, 0x85, 0x3b, &[0xe5, 0x38, 0x7f, 0xdb, 0x2e, 0x2d]);
+
+/// Functions of the Compatibility16 code, passed in AX when calling the Compatibility16 entry point
+/// (EFI_COMPATIBILITY_FUNCTIONS).
+///
+/// # Documentation
+/// Intel Platform Innovation Framework for EFI Compatibility Support Module Specification, Revision 0.97
+pub mod compatibility16 {
+    pub const INITIALIZE_YOURSELF: u16 = 0x0000;
+    pub const UPDATE_BBS: u16 = 0x0001;
+    pub const PREPARE_TO_BOOT: u16 = 0x0002;
+    pub const BOOT: u16 = 0x0003;
+    pub const RETRIEVE_LAST_BOOT_DEVICE: u16 = 0x0004;
+    pub const DISPATCH_OPROM: u16 = 0x0005;
+    pub const GET_TABLE_ADDRESS: u16 = 0x0006;
+    pub const SET_KEYBOARD_LEDS: u16 = 0x0007;
+    pub const INSTALL_PCI_HANDLER: u16 = 0x0008;
+}
+
+/// BBS device types.
+pub mod bbs_device_type {
+    pub const FLOPPY: u16 = 0x01;
+    pub const HARDDISK: u16 = 0x02;
+    pub const CDROM: u16 = 0x03;
+    pub const PCMCIA: u16 = 0x04;
+    pub const USB: u16 = 0x05;
+    pub const EMBED_NETWORK: u16 = 0x06;
+    pub const BEV_DEVICE: u16 = 0x80;
+    pub const UNKNOWN: u16 = 0xFF;
+}
+
+/// Special values of [`BbsTable::boot_priority`].
+pub mod bbs_priority {
+    pub const DO_NOT_BOOT_FROM: u16 = 0xFFFC;
+    pub const LOWEST_PRIORITY: u16 = 0xFFFD;
+    pub const UNPRIORITIZED_ENTRY: u16 = 0xFFFE;
+    pub const IGNORE_ENTRY: u16 = 0xFFFF;
+}
+
+/// 32-bit view of the register set (EFI_DWORD_REGS).
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DwordRegs {
+    pub eax: u32,
+    pub ebx: u32,
+    pub ecx: u32,
+    pub edx: u32,
+    pub esi: u32,
+    pub edi: u32,
+    pub eflags: u32,
+    pub es: u16,
+    pub cs: u16,
+    pub ss: u16,
+    pub ds: u16,
+    pub fs: u16,
+    pub gs: u16,
+    pub ebp: u32,
+    pub esp: u32,
+}
+
+/// 16-bit view of the register set (EFI_WORD_REGS).
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WordRegs {
+    pub ax: u16,
+    pub reserved_ax: u16,
+    pub bx: u16,
+    pub reserved_bx: u16,
+    pub cx: u16,
+    pub reserved_cx: u16,
+    pub dx: u16,
+    pub reserved_dx: u16,
+    pub si: u16,
+    pub reserved_si: u16,
+    pub di: u16,
+    pub reserved_di: u16,
+    pub flags: u16,
+    pub reserved_flags: u16,
+    pub es: u16,
+    pub cs: u16,
+    pub ss: u16,
+    pub ds: u16,
+    pub fs: u16,
+    pub gs: u16,
+    pub bp: u16,
+    pub reserved_bp: u16,
+    pub sp: u16,
+    pub reserved_sp: u16,
+}
+
+/// 8-bit view of the general purpose registers (EFI_BYTE_REGS).
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRegs {
+    pub al: u8,
+    pub ah: u8,
+    pub reserved_ax: u16,
+    pub bl: u8,
+    pub bh: u8,
+    pub reserved_bx: u16,
+    pub cl: u8,
+    pub ch: u8,
+    pub reserved_cx: u16,
+    pub dl: u8,
+    pub dh: u8,
+    pub reserved_dx: u16,
+}
+
+/// Register state passed to and returned from a 16-bit call (EFI_IA32_REGISTER_SET).
+///
+/// # Documentation
+/// Intel Platform Innovation Framework for EFI Compatibility Support Module Specification, Revision 0.97
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union Ia32RegisterSet {
+    pub e: DwordRegs,
+    pub x: WordRegs,
+    pub h: ByteRegs,
+}
+
+/// Data passed to the Compatibility16 InstallPciHandler() function to install the legacy handler of a PCI mass
+/// storage controller (EFI_LEGACY_INSTALL_PCI_HANDLER).
+///
+/// # Documentation
+/// Intel Platform Innovation Framework for EFI Compatibility Support Module Specification, Revision 0.97
+#[repr(C, packed)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LegacyInstallPciHandler {
+    pub pci_bus: u8,
+    pub pci_device_fun: u8,
+    pub pci_segment: u8,
+    pub pci_class: u8,
+    pub pci_subclass: u8,
+    pub pci_interface: u8,
+    pub primary_irq: u8,
+    pub primary_reserved: u8,
+    pub primary_control: u16,
+    pub primary_base: u16,
+    pub primary_bus_master: u16,
+    pub secondary_irq: u8,
+    pub secondary_reserved: u8,
+    pub secondary_control: u16,
+    pub secondary_base: u16,
+    pub secondary_bus_master: u16,
+}
+
+/// An entry of the BIOS Boot Specification table (BBS_TABLE).
+///
+/// # Documentation
+/// Intel Platform Innovation Framework for EFI Compatibility Support Module Specification, Revision 0.97
+#[repr(C, packed)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BbsTable {
+    /// Boot priority, 0 being the highest, or one of the [`bbs_priority`] values.
+    pub boot_priority: u16,
+    pub bus: u32,
+    pub device: u32,
+    pub function: u32,
+    pub class: u8,
+    pub sub_class: u8,
+    pub mfg_string_offset: u16,
+    pub mfg_string_segment: u16,
+    /// One of the [`bbs_device_type`] values.
+    pub device_type: u16,
+    /// BBS_STATUS_FLAGS bit field.
+    pub status_flags: u16,
+    pub boot_handler_offset: u16,
+    pub boot_handler_segment: u16,
+    pub desc_string_offset: u16,
+    pub desc_string_segment: u16,
+    pub init_per_reserved: u32,
+    pub additional_irq13_handler: u32,
+    pub additional_irq18_handler: u32,
+    pub additional_irq19_handler: u32,
+    pub additional_irq40_handler: u32,
+    pub assigned_drive_number: u8,
+    pub additional_irq41_handler: u32,
+    pub additional_irq46_handler: u32,
+    pub ibv1: u32,
+    pub ibv2: u32,
+}
+
+/// An IDE controller and the IDENTIFY data of its drives (HDD_INFO).
+///
+/// # Documentation
+/// Intel Platform Innovation Framework for EFI Compatibility Support Module Specification, Revision 0.97
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HddInfo {
+    pub status: u16,
+    pub bus: u32,
+    pub device: u32,
+    pub function: u32,
+    pub command_base_address: u16,
+    pub control_base_address: u16,
+    pub bus_master_address: u16,
+    pub hdd_irq: u8,
+    /// ATAPI_IDENTIFY data of the master and slave drives.
+    pub identify_drive: [[u16; 256]; 2],
+}
+
+/// BBS device path node (BBS_BBS_DEVICE_PATH), followed by a null-terminated ASCII description.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 10.3.7
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy)]
+pub struct BbsDevicePath {
+    pub header: device_path::Protocol,
+    pub device_type: u16,
+    pub status_flag: u16,
+    pub string: [u8; 1],
+}
+
+/// Issues a software interrupt to the legacy BIOS. Returns true if the carry flag is set on return.
+pub type Int86 = extern "efiapi" fn(*const Protocol, bios_int: u8, regs: *mut Ia32RegisterSet) -> efi::Boolean;
+
+/// Calls a 16-bit function at `segment:offset`, copying `stack_size` bytes of `stack` to the 16-bit stack. Returns
+/// true if the carry flag is set on return.
+pub type FarCall86 = extern "efiapi" fn(
+    *const Protocol,
+    segment: u16,
+    offset: u16,
+    regs: *mut Ia32RegisterSet,
+    stack: *mut c_void,
+    stack_size: usize,
+) -> efi::Boolean;
+
+/// Tests whether a PCI device has a legacy option ROM.
+pub type CheckPciRom = extern "efiapi" fn(
+    *const Protocol,
+    pci_handle: efi::Handle,
+    rom_image: *mut *mut c_void,
+    rom_size: *mut usize,
+    flags: *mut usize,
+) -> efi::Status;
+
+/// Shadows and runs the legacy option ROM of a PCI device.
+pub type InstallPciRom = extern "efiapi" fn(
+    *const Protocol,
+    pci_handle: efi::Handle,
+    rom_image: *mut *mut c_void,
+    flags: *mut usize,
+    disk_start: *mut u8,
+    disk_end: *mut u8,
+    rom_shadow_address: *mut *mut c_void,
+    shadowed_rom_size: *mut u32,
+) -> efi::Status;
+
+/// Boots a legacy operating system from the device described by `boot_option`.
+pub type LegacyBoot = extern "efiapi" fn(
+    *const Protocol,
+    boot_option: *mut BbsDevicePath,
+    load_options_size: u32,
+    load_options: *mut c_void,
+) -> efi::Status;
+
+/// Updates the keyboard LED state in the BIOS Data Area.
+pub type UpdateKeyboardLedStatus = extern "efiapi" fn(*const Protocol, leds: u8) -> efi::Status;
+
+/// Returns the IDE controller information and the BBS table.
+pub type GetBbsInfo = extern "efiapi" fn(
+    *const Protocol,
+    hdd_count: *mut u16,
+    hdd_info: *mut *mut HddInfo,
+    bbs_count: *mut u16,
+    bbs_table: *mut *mut BbsTable,
+) -> efi::Status;
+
+/// Shadows all legacy option ROMs without booting, so that their BBS entries become available.
+pub type ShadowAllLegacyOproms = extern "efiapi" fn(*const Protocol) -> efi::Status;
+
+/// Prepares the legacy BIOS for booting an EFI operating system and returns the BBS table.
+pub type PrepareToBootEfi =
+    extern "efiapi" fn(*const Protocol, bbs_count: *mut u16, bbs_table: *mut *mut BbsTable) -> efi::Status;
+
+/// Allocates memory in the legacy region (0xE0000-0xFFFFF). `region` selects the F0000 segment (bit 0), the E0000
+/// segment (bit 1), or either (0).
+pub type GetLegacyRegion = extern "efiapi" fn(
+    *const Protocol,
+    legacy_memory_size: usize,
+    region: usize,
+    alignment: usize,
+    legacy_memory_address: *mut *mut c_void,
+) -> efi::Status;
+
+/// Copies data into memory allocated by GetLegacyRegion().
+pub type CopyLegacyRegion = extern "efiapi" fn(
+    *const Protocol,
+    legacy_memory_size: usize,
+    legacy_memory_address: *mut c_void,
+    legacy_memory_source_address: *mut c_void,
+) -> efi::Status;
+
+/// Boots a device that does not follow the BBS conventions, such as a PARTIES service area or a BEER device.
+pub type BootUnconventionalDevice = extern "efiapi" fn(
+    *const Protocol,
+    attributes: u8,
+    bbs_entry: usize,
+    beer_data: *mut c_void,
+    service_area_data: *mut c_void,
+) -> efi::Status;
+
+/// Abstracts the legacy BIOS.
+///
+/// # Documentation
+/// Intel Platform Innovation Framework for EFI Compatibility Support Module Specification, Revision 0.97
+#[repr(C)]
+#[derive(Debug)]
+pub struct Protocol {
+    pub int86: Int86,
+    pub far_call86: FarCall86,
+    pub check_pci_rom: CheckPciRom,
+    pub install_pci_rom: InstallPciRom,
+    pub legacy_boot: LegacyBoot,
+    pub update_keyboard_led_status: UpdateKeyboardLedStatus,
+    pub get_bbs_info: GetBbsInfo,
+    pub shadow_all_legacy_oproms: ShadowAllLegacyOproms,
+    pub prepare_to_boot_efi: PrepareToBootEfi,
+    pub get_legacy_region: GetLegacyRegion,
+    pub copy_legacy_region: CopyLegacyRegion,
+    pub boot_unconventional_device: BootUnconventionalDevice,
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::size_of;
+
+    use super::{BbsDevicePath, BbsTable, Ia32RegisterSet, LegacyInstallPciHandler, Protocol, WordRegs, PROTOCOL_GUID};
+
+    #[test]
+    fn guid_should_match_spec() {
+        assert_eq!(
+            PROTOCOL_GUID.as_bytes(),
+            &[0x3d, 0x1e, 0x9a, 0xdb, 0xcb, 0x45, 0xbb, 0x4a, 0x85, 0x3b, 0xe5, 0x38, 0x7f, 0xdb, 0x2e, 0x2d]
+        );
+    }
+
+    #[test]
+    fn struct_layouts_should_match_spec() {
+        assert_eq!(size_of::<BbsTable>(), 0x45);
+        assert_eq!(offset_of!(BbsTable, device_type), 0x14);
+        assert_eq!(offset_of!(BbsTable, assigned_drive_number), 0x34);
+        assert_eq!(offset_of!(BbsTable, ibv2), 0x41);
+
+        assert_eq!(size_of::<LegacyInstallPciHandler>(), 22);
+        assert_eq!(size_of::<BbsDevicePath>(), 9);
+        assert_eq!(size_of::<Ia32RegisterSet>(), 48);
+        assert_eq!(size_of::<Protocol>(), size_of::<[usize; 12]>());
+
+        // The byte registers alias the low bytes of the word registers.
+        let mut regs = Ia32RegisterSet { x: WordRegs { ax: 0x4F02, bx: 0x4118, ..Default::default() } };
+        unsafe {
+            assert_eq!((regs.h.ah, regs.h.al, regs.h.bh), (0x4F, 0x02, 0x41));
+            regs.h.ah = 0;
+            assert_eq!(regs.e.eax, 0x0002);
+        }
+    }
+}
@@ -0,0 +1,251 @@
+//! Lock Box Protocol
+//!
+//! Stores data in SMRAM so that it can be restored on resume from S3 without being exposed to, or modified by, code
+//! running outside SMM. Each lock box is named by a GUID; it is created with SaveLockBox(), can be modified with
+//! UpdateLockBox() until SMM is locked, and its content is read back with RestoreLockBox().
+//!
+//! The PI specification does not define a lock box interface. The functions follow the EDK II LockBoxLib, and the
+//! protocol GUID is defined by this crate.
+//!
+//! See <https://github.com/tianocore/edk2/blob/master/MdeModulePkg/Include/Library/LockBoxLib.h>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::ffi::c_void;
+
+use r_efi::efi;
+
+use crate::status::StatusExt;
+
+/// Lock Box Protocol GUID
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0xded8eb43, 0x3b15, 0x466a, 0xbf, 0xc9, &[0x54, 0x52, 0x3e, 0x92, 0x64, 0xbe]);
+
+/// The lock box is restored to the address it was saved from by RestoreAllLockBoxInPlace() on S3 resume.
+pub const ATTRIBUTE_RESTORE_IN_PLACE: u64 = 0x0000_0001;
+/// The lock box may only be restored during S3 resume.
+pub const ATTRIBUTE_RESTORE_IN_S3_ONLY: u64 = 0x0000_0002;
+
+/// Creates the lock box `guid` with a copy of `length` bytes at `buffer`.
+///
+/// * this - pointer to the protocol
+/// * guid - the name of the lock box
+/// * buffer - the data to save
+/// * length - the size of the data in bytes
+///
+/// * @retval - SUCCESS: the lock box was created
+/// * @retval - INVALID_PARAMETER: guid or buffer is null, or length is 0
+/// * @retval - ALREADY_STARTED: the lock box already exists
+/// * @retval - OUT_OF_RESOURCES: there is not enough SMRAM for the lock box
+/// * @retval - ACCESS_DENIED: SMM is locked
+pub type SaveLockBox = extern "efiapi" fn(
+    this: *const Protocol,
+    guid: *const efi::Guid,
+    buffer: *const c_void,
+    length: usize,
+) -> efi::Status;
+
+/// Sets the `ATTRIBUTE_*` bits of the lock box `guid`.
+///
+/// * @retval - SUCCESS: the attributes were set
+/// * @retval - INVALID_PARAMETER: guid is null, or the attributes are invalid
+/// * @retval - NOT_FOUND: the lock box does not exist
+/// * @retval - ACCESS_DENIED: SMM is locked
+pub type SetLockBoxAttributes =
+    extern "efiapi" fn(this: *const Protocol, guid: *const efi::Guid, attributes: u64) -> efi::Status;
+
+/// Replaces `length` bytes of the lock box `guid` at `offset` with the data at `buffer`.
+///
+/// * this - pointer to the protocol
+/// * guid - the name of the lock box
+/// * offset - the offset in the lock box of the data to replace
+/// * buffer - the new data
+/// * length - the size of the data in bytes
+///
+/// * @retval - SUCCESS: the lock box was updated
+/// * @retval - INVALID_PARAMETER: guid or buffer is null, or length is 0
+/// * @retval - NOT_FOUND: the lock box does not exist
+/// * @retval - BUFFER_TOO_SMALL: the data ends past the end of the lock box
+/// * @retval - ACCESS_DENIED: SMM is locked
+pub type UpdateLockBox = extern "efiapi" fn(
+    this: *const Protocol,
+    guid: *const efi::Guid,
+    offset: usize,
+    buffer: *const c_void,
+    length: usize,
+) -> efi::Status;
+
+/// Copies the content of the lock box `guid` to `buffer`, or to the address it was saved from if `buffer` is null.
+///
+/// * this - pointer to the protocol
+/// * guid - the name of the lock box
+/// * buffer - the buffer to copy the content to, or null
+/// * length - the size of buffer in bytes; receives the size of the lock box
+///
+/// * @retval - SUCCESS: the content was copied
+/// * @retval - INVALID_PARAMETER: guid is null, or only one of buffer and length is null
+/// * @retval - WRITE_PROTECTED: buffer is null and the lock box does not have [`ATTRIBUTE_RESTORE_IN_PLACE`]
+/// * @retval - BUFFER_TOO_SMALL: buffer is too small; length receives the size of the lock box
+/// * @retval - NOT_FOUND: the lock box does not exist
+/// * @retval - ACCESS_DENIED: the lock box has [`ATTRIBUTE_RESTORE_IN_S3_ONLY`] and the platform is not resuming
+pub type RestoreLockBox = extern "efiapi" fn(
+    this: *const Protocol,
+    guid: *const efi::Guid,
+    buffer: *mut c_void,
+    length: *mut usize,
+) -> efi::Status;
+
+/// Saves and restores data across S3 resume.
+#[repr(C)]
+#[derive(Debug)]
+pub struct Protocol {
+    pub save_lock_box: SaveLockBox,
+    pub set_lock_box_attributes: SetLockBoxAttributes,
+    pub update_lock_box: UpdateLockBox,
+    pub restore_lock_box: RestoreLockBox,
+}
+
+/// Creates the lock box `guid` with a copy of `data`.
+pub fn save_lock_box(lock_box: &Protocol, guid: &efi::Guid, data: &[u8]) -> Result<(), efi::Status> {
+    (lock_box.save_lock_box)(lock_box, guid, data.as_ptr() as *const c_void, data.len()).ok()
+}
+
+/// Sets the `ATTRIBUTE_*` bits of the lock box `guid`.
+pub fn set_lock_box_attributes(lock_box: &Protocol, guid: &efi::Guid, attributes: u64) -> Result<(), efi::Status> {
+    (lock_box.set_lock_box_attributes)(lock_box, guid, attributes).ok()
+}
+
+/// Replaces the data of the lock box `guid` at `offset` with `data`.
+pub fn update_lock_box(lock_box: &Protocol, guid: &efi::Guid, offset: usize, data: &[u8]) -> Result<(), efi::Status> {
+    (lock_box.update_lock_box)(lock_box, guid, offset, data.as_ptr() as *const c_void, data.len()).ok()
+}
+
+/// Copies the content of the lock box `guid` to the start of `buffer`, returning its size.
+///
+/// Fails with BUFFER_TOO_SMALL if `buffer` is smaller than the lock box.
+pub fn restore_lock_box(lock_box: &Protocol, guid: &efi::Guid, buffer: &mut [u8]) -> Result<usize, efi::Status> {
+    let mut length = buffer.len();
+    (lock_box.restore_lock_box)(lock_box, guid, buffer.as_mut_ptr() as *mut c_void, &mut length).ok()?;
+    Ok(length)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{cell::RefCell, ffi::c_void, mem::size_of, slice};
+
+    use r_efi::efi;
+
+    use super::{
+        restore_lock_box, save_lock_box, set_lock_box_attributes, update_lock_box, Protocol,
+        ATTRIBUTE_RESTORE_IN_PLACE, ATTRIBUTE_RESTORE_IN_S3_ONLY, PROTOCOL_GUID,
+    };
+
+    // (name, attributes, content)
+    type LockBox = (efi::Guid, u64, Vec<u8>);
+
+    std::thread_local! {
+        static LOCK_BOXES: RefCell<Vec<LockBox>> = RefCell::new(Vec::new());
+    }
+
+    fn with_lock_box<T>(guid: *const efi::Guid, f: impl FnOnce(&mut LockBox) -> T) -> Option<T> {
+        let guid = unsafe { &*guid };
+        LOCK_BOXES.with(|boxes| boxes.borrow_mut().iter_mut().find(|(name, ..)| name == guid).map(f))
+    }
+
+    extern "efiapi" fn mock_save(
+        _: *const Protocol,
+        guid: *const efi::Guid,
+        buffer: *const c_void,
+        length: usize,
+    ) -> efi::Status {
+        if with_lock_box(guid, |_| ()).is_some() {
+            return efi::Status::ALREADY_STARTED;
+        }
+        let data = unsafe { slice::from_raw_parts(buffer as *const u8, length) }.to_vec();
+        LOCK_BOXES.with(|boxes| boxes.borrow_mut().push((unsafe { *guid }, 0, data)));
+        efi::Status::SUCCESS
+    }
+
+    extern "efiapi" fn mock_set_attributes(_: *const Protocol, guid: *const efi::Guid, attributes: u64) -> efi::Status {
+        with_lock_box(guid, |lock_box| lock_box.1 = attributes).map_or(efi::Status::NOT_FOUND, |_| efi::Status::SUCCESS)
+    }
+
+    extern "efiapi" fn mock_update(
+        _: *const Protocol,
+        guid: *const efi::Guid,
+        offset: usize,
+        buffer: *const c_void,
+        length: usize,
+    ) -> efi::Status {
+        let data = unsafe { slice::from_raw_parts(buffer as *const u8, length) };
+        with_lock_box(guid, |(_, _, content)| match content.get_mut(offset..offset + length) {
+            Some(target) => {
+                target.copy_from_slice(data);
+                efi::Status::SUCCESS
+            }
+            None => efi::Status::BUFFER_TOO_SMALL,
+        })
+        .unwrap_or(efi::Status::NOT_FOUND)
+    }
+
+    extern "efiapi" fn mock_restore(
+        _: *const Protocol,
+        guid: *const efi::Guid,
+        buffer: *mut c_void,
+        length: *mut usize,
+    ) -> efi::Status {
+        with_lock_box(guid, |(_, _, content)| unsafe {
+            let available = *length;
+            *length = content.len();
+            if available < content.len() {
+                return efi::Status::BUFFER_TOO_SMALL;
+            }
+            slice::from_raw_parts_mut(buffer as *mut u8, content.len()).copy_from_slice(content);
+            efi::Status::SUCCESS
+        })
+        .unwrap_or(efi::Status::NOT_FOUND)
+    }
+
+    const PROTOCOL: Protocol = Protocol {
+        save_lock_box: mock_save,
+        set_lock_box_attributes: mock_set_attributes,
+        update_lock_box: mock_update,
+        restore_lock_box: mock_restore,
+    };
+
+    const NAME: efi::Guid =
+        efi::Guid::from_fields(0x01234567, 0x89ab, 0xcdef, 0x01, 0x23, &[0x45, 0x67, 0x89, 0xab, 0xcd, 0xef]);
+
+    #[test]
+    fn guid_and_layout_should_match() {
+        assert_eq!(
+            PROTOCOL_GUID.as_bytes(),
+            &[0x43, 0xeb, 0xd8, 0xde, 0x15, 0x3b, 0x6a, 0x46, 0xbf, 0xc9, 0x54, 0x52, 0x3e, 0x92, 0x64, 0xbe]
+        );
+        assert_eq!(size_of::<Protocol>(), 4 * size_of::<usize>());
+        assert_eq!((ATTRIBUTE_RESTORE_IN_PLACE, ATTRIBUTE_RESTORE_IN_S3_ONLY), (1, 2));
+    }
+
+    #[test]
+    fn lock_boxes_should_round_trip() {
+        save_lock_box(&PROTOCOL, &NAME, b"secret data").unwrap();
+        assert_eq!(save_lock_box(&PROTOCOL, &NAME, b"other"), Err(efi::Status::ALREADY_STARTED));
+        set_lock_box_attributes(&PROTOCOL, &NAME, ATTRIBUTE_RESTORE_IN_S3_ONLY).unwrap();
+        update_lock_box(&PROTOCOL, &NAME, 7, b"DATA").unwrap();
+        assert_eq!(update_lock_box(&PROTOCOL, &NAME, 8, b"DATA"), Err(efi::Status::BUFFER_TOO_SMALL));
+
+        let mut buffer = [0u8; 16];
+        let length = restore_lock_box(&PROTOCOL, &NAME, &mut buffer).unwrap();
+        assert_eq!(&buffer[..length], b"secret DATA");
+        assert_eq!(restore_lock_box(&PROTOCOL, &NAME, &mut buffer[..4]), Err(efi::Status::BUFFER_TOO_SMALL));
+        assert_eq!(LOCK_BOXES.with(|boxes| boxes.borrow()[0].1), ATTRIBUTE_RESTORE_IN_S3_ONLY);
+
+        assert_eq!(restore_lock_box(&PROTOCOL, &PROTOCOL_GUID, &mut buffer), Err(efi::Status::NOT_FOUND));
+        assert_eq!(set_lock_box_attributes(&PROTOCOL, &PROTOCOL_GUID, 0), Err(efi::Status::NOT_FOUND));
+    }
+}
@@ -36,6 +36,7 @@ pub type WaitForTick = extern "efiapi" fn(*const Protocol, tick_number: u32) ->
 /// # Documentation
 /// UEFI Platform Initialization Specification, Release 1.8, Section II-12.4.1
 #[repr(C)]
+#[derive(Debug)]
 pub struct Protocol {
     pub wait_for_tick: WaitForTick,
     /// The period of platform’s known time source in 100 ns units. This value on any platform must not exceed 200
@@ -0,0 +1,23 @@
+//! Monotonic Counter Architectural Protocol
+//!
+//! Provides the services required to access the monotonic counter of the platform. The protocol has no functions: it is
+//! installed once the GetNextMonotonicCount() boot service and the GetNextHighMonotonicCount() runtime service are
+//! available.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V2_DXE_Architectural_Protocols.html#monotonic-counter-architectural-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use r_efi::efi;
+
+/// Monotonic Counter Architectural Protocol GUID
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section II-12.5.1
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x1da97072, 0xbddc, 0x4b30, 0x99, 0xf1, &[0x72, 0xa0, 0xb5, 0x6f, 0xff, 0x2a]);
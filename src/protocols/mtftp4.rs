@@ -0,0 +1,517 @@
+//! MTFTPv4 Protocol
+//!
+//! Provides the multicast TFTP (and plain TFTP) client used to download boot images during PXE boot.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/30_Network_Protocols_UDP_and_MTFTP.html#efi-mtftp4-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+extern crate alloc;
+use alloc::vec::Vec;
+use core::{ffi::c_void, ptr, slice};
+
+use r_efi::efi;
+
+/// MTFTPv4 Protocol GUID
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 30.3.2
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x78247c57, 0x63db, 0x4708, 0x99, 0xc2, &[0xa8, 0xb4, 0xa9, 0xa6, 0x1f, 0x6b]);
+
+/// MTFTPv4 Service Binding Protocol GUID
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 30.3.1
+pub const SERVICE_BINDING_PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x2fe800be, 0x8f01, 0x4aa6, 0x94, 0x6b, &[0xd7, 0x13, 0x88, 0xe1, 0x83, 0x3f]);
+
+/// MTFTP packet opcodes.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 30.3.2
+pub mod opcode {
+    pub const RRQ: u16 = 1;
+    pub const WRQ: u16 = 2;
+    pub const DATA: u16 = 3;
+    pub const ACK: u16 = 4;
+    pub const ERROR: u16 = 5;
+    pub const OACK: u16 = 6;
+    pub const DIR: u16 = 7;
+    pub const DATA8: u16 = 8;
+    pub const ACK8: u16 = 9;
+}
+
+/// MTFTP error packet error codes.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 30.3.2
+pub mod error_code {
+    pub const NOT_DEFINED: u16 = 0;
+    pub const FILE_NOT_FOUND: u16 = 1;
+    pub const ACCESS_VIOLATION: u16 = 2;
+    pub const DISK_FULL: u16 = 3;
+    pub const ILLEGAL_OPERATION: u16 = 4;
+    pub const UNKNOWN_TRANSFER_ID: u16 = 5;
+    pub const FILE_ALREADY_EXISTS: u16 = 6;
+    pub const NO_SUCH_USER: u16 = 7;
+    pub const REQUEST_DENIED: u16 = 8;
+}
+
+/// Configuration of an MTFTPv4 instance (EFI_MTFTP4_CONFIG_DATA).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 30.3.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mtftp4Config {
+    /// If true, the default address information from the EFI IPv4 Configuration II Protocol is used.
+    pub use_default_setting: efi::Boolean,
+    pub station_ip: efi::Ipv4Address,
+    pub subnet_mask: efi::Ipv4Address,
+    /// Local port; zero to select an ephemeral port.
+    pub local_port: u16,
+    pub gateway_ip: efi::Ipv4Address,
+    pub server_ip: efi::Ipv4Address,
+    /// Server port for the initial request; zero for the default port (69).
+    pub initial_server_port: u16,
+    pub try_count: u16,
+    /// Time to wait for a response, in seconds.
+    pub timeout_value: u16,
+}
+
+/// Current state of an MTFTPv4 instance (EFI_MTFTP4_MODE_DATA).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 30.3.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mtftp4ModeData {
+    pub config_data: Mtftp4Config,
+    pub supported_option_count: u8,
+    /// Array of supported_option_count null-terminated ASCII option names.
+    pub supported_options: *mut *mut u8,
+    pub unsupported_option_count: u8,
+    /// Array of unsupported_option_count null-terminated ASCII option names.
+    pub unsupported_options: *mut *mut u8,
+}
+
+/// Per-operation overrides of the instance configuration (EFI_MTFTP4_OVERRIDE_DATA).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 30.3.2
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Mtftp4OverrideData {
+    pub gateway_ip: efi::Ipv4Address,
+    pub server_ip: efi::Ipv4Address,
+    /// Server port; zero for the default port (69).
+    pub server_port: u16,
+    /// Zero for the configured try count.
+    pub try_count: u16,
+    /// Zero for the configured timeout.
+    pub timeout_value: u16,
+}
+
+/// A TFTP option (EFI_MTFTP4_OPTION).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 30.3.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mtftp4Option {
+    /// Null-terminated ASCII option name.
+    pub option_str: *mut u8,
+    /// Null-terminated ASCII option value.
+    pub value_str: *mut u8,
+}
+
+/// Fixed part of a DATA or ACK packet (EFI_MTFTP4_DATA_HEADER and EFI_MTFTP4_ACK_HEADER). In a DATA packet the block
+/// is followed by the data.
+///
+/// Like every packet field, the opcode and block number are in network byte order.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 30.3.2
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mtftp4PacketHeader {
+    pub op_code: u16,
+    pub block: u16,
+}
+
+/// Fixed part of an ERROR packet (EFI_MTFTP4_ERROR_HEADER), followed by a null-terminated ASCII error message.
+///
+/// Like every packet field, the opcode and error code are in network byte order.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 30.3.2
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mtftp4ErrorHeader {
+    pub op_code: u16,
+    pub error_code: u16,
+}
+
+/// An MTFTP packet (EFI_MTFTP4_PACKET), interpreted according to its opcode.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 30.3.2
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+pub union Mtftp4Packet {
+    pub op_code: u16,
+    pub data: Mtftp4PacketHeader,
+    pub ack: Mtftp4PacketHeader,
+    pub error: Mtftp4ErrorHeader,
+}
+
+/// Called for each packet received during an operation. Returning an error aborts the operation.
+pub type CheckPacket = extern "efiapi" fn(
+    *const Protocol,
+    token: *mut Mtftp4Token,
+    packet_len: u16,
+    packet: *mut Mtftp4Packet,
+) -> efi::Status;
+
+/// Called when an operation times out. Returning an error aborts the operation.
+pub type TimeoutCallback = extern "efiapi" fn(*const Protocol, token: *mut Mtftp4Token) -> efi::Status;
+
+/// Called by WriteFile() to obtain the next block of data to upload.
+pub type PacketNeeded = extern "efiapi" fn(
+    *const Protocol,
+    token: *mut Mtftp4Token,
+    length: *mut u16,
+    buffer: *mut *mut c_void,
+) -> efi::Status;
+
+/// Describes a file transfer operation (EFI_MTFTP4_TOKEN).
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 30.3.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mtftp4Token {
+    /// Completion status of the operation.
+    pub status: efi::Status,
+    /// Event signaled when the operation completes; if null, the operation is synchronous.
+    pub event: efi::Event,
+    pub override_data: *mut Mtftp4OverrideData,
+    /// Null-terminated ASCII file name.
+    pub filename: *mut u8,
+    /// Null-terminated ASCII transfer mode; null for "octet".
+    pub mode_str: *mut u8,
+    pub option_count: u32,
+    pub option_list: *mut Mtftp4Option,
+    /// Size of buffer in bytes; updated with the size of the file.
+    pub buffer_size: u64,
+    /// Buffer for the file data; if null, downloaded data is only passed to check_packet.
+    pub buffer: *mut c_void,
+    /// Caller context, for use by the callbacks.
+    pub context: *mut c_void,
+    pub check_packet: Option<CheckPacket>,
+    pub timeout_callback: Option<TimeoutCallback>,
+    pub packet_needed: Option<PacketNeeded>,
+}
+
+/// Returns the current state of the MTFTPv4 instance.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 30.3.2
+pub type GetModeData = extern "efiapi" fn(*const Protocol, mode_data: *mut Mtftp4ModeData) -> efi::Status;
+
+/// Configures the MTFTPv4 instance, or resets it to the unconfigured state if mtftp_config_data is null.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 30.3.2
+pub type Configure = extern "efiapi" fn(*const Protocol, mtftp_config_data: *mut Mtftp4Config) -> efi::Status;
+
+/// Requests a file and returns the server's first response (typically an OACK packet) without transferring it.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 30.3.2
+pub type GetInfo = extern "efiapi" fn(
+    *const Protocol,
+    override_data: *mut Mtftp4OverrideData,
+    filename: *mut u8,
+    mode_str: *mut u8,
+    option_count: u8,
+    option_list: *mut Mtftp4Option,
+    packet_length: *mut u32,
+    packet: *mut *mut Mtftp4Packet,
+) -> efi::Status;
+
+/// Parses the options of an OACK packet.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 30.3.2
+pub type ParseOptions = extern "efiapi" fn(
+    *const Protocol,
+    packet_len: u32,
+    packet: *mut Mtftp4Packet,
+    option_count: *mut u32,
+    option_list: *mut *mut Mtftp4Option,
+) -> efi::Status;
+
+/// Downloads a file.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 30.3.2
+pub type ReadFile = extern "efiapi" fn(*const Protocol, token: *mut Mtftp4Token) -> efi::Status;
+
+/// Uploads a file.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 30.3.2
+pub type WriteFile = extern "efiapi" fn(*const Protocol, token: *mut Mtftp4Token) -> efi::Status;
+
+/// Downloads a directory listing.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 30.3.2
+pub type ReadDirectory = extern "efiapi" fn(*const Protocol, token: *mut Mtftp4Token) -> efi::Status;
+
+/// Polls for incoming data packets and processes outgoing data packets.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 30.3.2
+pub type Poll = extern "efiapi" fn(*const Protocol) -> efi::Status;
+
+/// Provides a multicast TFTP client.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 30.3.2
+#[repr(C)]
+#[derive(Debug)]
+pub struct Protocol {
+    pub get_mode_data: GetModeData,
+    pub configure: Configure,
+    pub get_info: GetInfo,
+    pub parse_options: ParseOptions,
+    pub read_file: ReadFile,
+    pub write_file: WriteFile,
+    pub read_directory: ReadDirectory,
+    pub poll: Poll,
+}
+
+// State of a download_file() transfer, passed to collect_data() through the token context.
+struct Download {
+    data: Vec<u8>,
+    next_block: u16,
+}
+
+extern "efiapi" fn collect_data(
+    _: *const Protocol,
+    token: *mut Mtftp4Token,
+    packet_len: u16,
+    packet: *mut Mtftp4Packet,
+) -> efi::Status {
+    let header_len = core::mem::size_of::<Mtftp4PacketHeader>();
+    //Safety: the driver passes the token given to ReadFile() and a packet of packet_len bytes.
+    let (download, packet) = unsafe {
+        (&mut *((*token).context as *mut Download), slice::from_raw_parts(packet as *const u8, packet_len as usize))
+    };
+    let Some(header) = packet.get(..header_len) else {
+        return efi::Status::SUCCESS;
+    };
+    let op_code = u16::from_be_bytes([header[0], header[1]]);
+    let block = u16::from_be_bytes([header[2], header[3]]);
+    if op_code == opcode::DATA && block == download.next_block {
+        download.data.extend_from_slice(&packet[header_len..]);
+        download.next_block = block.wrapping_add(1);
+    }
+    efi::Status::SUCCESS
+}
+
+/// Downloads `filename` from the TFTP server at `server_ip` using a configured MTFTPv4 instance.
+///
+/// The transfer is synchronous and uses the default port, mode, retry count, and timeout. Returns
+/// `INVALID_PARAMETER` if `filename` contains a null character, or the status of the failed transfer.
+pub fn download_file(protocol: &Protocol, server_ip: efi::Ipv4Address, filename: &str) -> Result<Vec<u8>, efi::Status> {
+    if filename.contains('\0') {
+        Err(efi::Status::INVALID_PARAMETER)?;
+    }
+    let mut filename: Vec<u8> = filename.bytes().chain([0]).collect();
+    let mut override_data = Mtftp4OverrideData { server_ip, ..Default::default() };
+    let mut download = Download { data: Vec::new(), next_block: 1 };
+    let mut token = Mtftp4Token {
+        status: efi::Status::SUCCESS,
+        event: ptr::null_mut(),
+        override_data: &mut override_data,
+        filename: filename.as_mut_ptr(),
+        mode_str: ptr::null_mut(),
+        option_count: 0,
+        option_list: ptr::null_mut(),
+        buffer_size: 0,
+        buffer: ptr::null_mut(),
+        context: &mut download as *mut Download as *mut c_void,
+        check_packet: Some(collect_data),
+        timeout_callback: None,
+        packet_needed: None,
+    };
+    let status = (protocol.read_file)(protocol, &mut token);
+    if status.is_error() {
+        Err(status)?;
+    }
+    if token.status.is_error() {
+        Err(token.status)?;
+    }
+    Ok(download.data)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{cell::RefCell, ffi::CStr, mem::size_of};
+
+    use r_efi::efi;
+
+    use super::{
+        download_file, opcode, Mtftp4Config, Mtftp4ErrorHeader, Mtftp4ModeData, Mtftp4Packet, Mtftp4PacketHeader,
+        Mtftp4Token, Protocol, PROTOCOL_GUID, SERVICE_BINDING_PROTOCOL_GUID,
+    };
+
+    std::thread_local! {
+        // Packets delivered by the mock ReadFile(), and the status it completes with.
+        static PACKETS: RefCell<(Vec<Vec<u8>>, efi::Status)> = RefCell::new((Vec::new(), efi::Status::SUCCESS));
+        static REQUEST: RefCell<Option<(String, [u8; 4])>> = RefCell::new(None);
+    }
+
+    extern "efiapi" fn get_mode_data(_: *const Protocol, _: *mut Mtftp4ModeData) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+
+    extern "efiapi" fn configure(_: *const Protocol, _: *mut Mtftp4Config) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+
+    extern "efiapi" fn get_info(
+        _: *const Protocol,
+        _: *mut super::Mtftp4OverrideData,
+        _: *mut u8,
+        _: *mut u8,
+        _: u8,
+        _: *mut super::Mtftp4Option,
+        _: *mut u32,
+        _: *mut *mut Mtftp4Packet,
+    ) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+
+    extern "efiapi" fn parse_options(
+        _: *const Protocol,
+        _: u32,
+        _: *mut Mtftp4Packet,
+        _: *mut u32,
+        _: *mut *mut super::Mtftp4Option,
+    ) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+
+    extern "efiapi" fn read_file(this: *const Protocol, token: *mut Mtftp4Token) -> efi::Status {
+        let token_ref = unsafe { &mut *token };
+        let filename = unsafe { CStr::from_ptr(token_ref.filename as *const _) }.to_str().unwrap().to_string();
+        let server_ip = unsafe { (*token_ref.override_data).server_ip.addr };
+        REQUEST.with(|request| *request.borrow_mut() = Some((filename, server_ip)));
+
+        let (packets, status) = PACKETS.with(|packets| packets.borrow().clone());
+        for mut packet in packets {
+            let check_packet = token_ref.check_packet.unwrap();
+            check_packet(this, token, packet.len() as u16, packet.as_mut_ptr() as *mut Mtftp4Packet);
+        }
+        token_ref.status = status;
+        status
+    }
+
+    extern "efiapi" fn unsupported(_: *const Protocol, _: *mut Mtftp4Token) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+
+    extern "efiapi" fn poll(_: *const Protocol) -> efi::Status {
+        efi::Status::SUCCESS
+    }
+
+    fn mock_protocol() -> Protocol {
+        Protocol {
+            get_mode_data,
+            configure,
+            get_info,
+            parse_options,
+            read_file,
+            write_file: unsupported,
+            read_directory: unsupported,
+            poll,
+        }
+    }
+
+    fn data_packet(block: u16, data: &[u8]) -> Vec<u8> {
+        let mut packet = opcode::DATA.to_be_bytes().to_vec();
+        packet.extend_from_slice(&block.to_be_bytes());
+        packet.extend_from_slice(data);
+        packet
+    }
+
+    #[test]
+    fn guids_should_match_spec() {
+        assert_eq!(
+            PROTOCOL_GUID.as_bytes(),
+            &[0x57, 0x7c, 0x24, 0x78, 0xdb, 0x63, 0x08, 0x47, 0x99, 0xc2, 0xa8, 0xb4, 0xa9, 0xa6, 0x1f, 0x6b]
+        );
+        assert_eq!(
+            SERVICE_BINDING_PROTOCOL_GUID.as_bytes(),
+            &[0xbe, 0x00, 0xe8, 0x2f, 0x01, 0x8f, 0xa6, 0x4a, 0x94, 0x6b, 0xd7, 0x13, 0x88, 0xe1, 0x83, 0x3f]
+        );
+    }
+
+    #[test]
+    fn config_layout_should_match_spec() {
+        assert_eq!(offset_of!(Mtftp4Config, station_ip), 1);
+        assert_eq!(offset_of!(Mtftp4Config, subnet_mask), 5);
+        assert_eq!(offset_of!(Mtftp4Config, local_port), 10);
+        assert_eq!(offset_of!(Mtftp4Config, gateway_ip), 12);
+        assert_eq!(offset_of!(Mtftp4Config, server_ip), 16);
+        assert_eq!(offset_of!(Mtftp4Config, initial_server_port), 20);
+        assert_eq!(offset_of!(Mtftp4Config, try_count), 22);
+        assert_eq!(offset_of!(Mtftp4Config, timeout_value), 24);
+        assert_eq!(size_of::<Mtftp4Config>(), 26);
+
+        assert_eq!(size_of::<Mtftp4PacketHeader>(), 4);
+        assert_eq!(size_of::<Mtftp4ErrorHeader>(), 4);
+        assert_eq!(size_of::<Mtftp4Packet>(), 4);
+        assert_eq!(size_of::<Mtftp4Token>(), size_of::<[usize; 13]>());
+        assert_eq!(size_of::<Protocol>(), size_of::<[usize; 8]>());
+    }
+
+    #[test]
+    fn download_file_should_collect_data_blocks() {
+        let protocol = mock_protocol();
+        let packets = vec![
+            data_packet(1, &[1; 512]),
+            // Duplicates and unexpected packets are ignored.
+            data_packet(1, &[9; 512]),
+            opcode::OACK.to_be_bytes().to_vec(),
+            data_packet(2, &[2, 3]),
+        ];
+        PACKETS.with(|p| *p.borrow_mut() = (packets, efi::Status::SUCCESS));
+
+        let data = download_file(&protocol, efi::Ipv4Address { addr: [192, 168, 0, 1] }, "boot/grubx64.efi").unwrap();
+        assert_eq!(data.len(), 514);
+        assert!(data[..512].iter().all(|&b| b == 1));
+        assert_eq!(&data[512..], &[2, 3]);
+        let request = REQUEST.with(|request| request.borrow_mut().take());
+        assert_eq!(request, Some(("boot/grubx64.efi".to_string(), [192, 168, 0, 1])));
+    }
+
+    #[test]
+    fn download_file_should_return_errors() {
+        let protocol = mock_protocol();
+        PACKETS.with(|p| *p.borrow_mut() = (Vec::new(), efi::Status::TFTP_ERROR));
+        let server = efi::Ipv4Address { addr: [10, 0, 0, 1] };
+        assert_eq!(download_file(&protocol, server, "missing"), Err(efi::Status::TFTP_ERROR));
+        assert_eq!(download_file(&protocol, server, "bad\0name"), Err(efi::Status::INVALID_PARAMETER));
+    }
+}
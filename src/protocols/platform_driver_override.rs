@@ -0,0 +1,262 @@
+//! Platform Driver Override Protocol
+//!
+//! Allows the platform to override the drivers that the `ConnectController()` Boot Service binds to a controller,
+//! and provides a builder that produces an instance of the protocol from a fixed controller to driver mapping.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/11_Protocols_UEFI_Driver_Model.html#efi-platform-driver-override-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+extern crate alloc;
+use alloc::{boxed::Box, collections::BTreeMap, vec::Vec};
+use core::ptr;
+
+use r_efi::{efi, protocols::device_path};
+
+/// Platform Driver Override Protocol GUID
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 11.4
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x6b30c738, 0xa391, 0x11d4, 0x9a, 0x3b, &[0x00, 0x90, 0x27, 0x3f, 0xc1, 0x4d]);
+
+/// Retrieves the image handle of the next platform override driver for a controller.
+///
+/// On input, `driver_image_handle` is null to retrieve the first driver, or the handle returned by the previous call
+/// to retrieve the next one. Returns `NOT_FOUND` once there are no more drivers, and `INVALID_PARAMETER` if
+/// `driver_image_handle` was not returned by a previous call.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 11.4.1
+pub type GetDriver = extern "efiapi" fn(
+    *const Protocol,
+    controller_handle: efi::Handle,
+    driver_image_handle: *mut efi::Handle,
+) -> efi::Status;
+
+/// Retrieves the device path of the next platform override driver for a controller, for drivers that are not yet
+/// loaded.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 11.4.2
+pub type GetDriverPath = extern "efiapi" fn(
+    *const Protocol,
+    controller_handle: efi::Handle,
+    driver_image_path: *mut *mut device_path::Protocol,
+) -> efi::Status;
+
+/// Reports to the platform the image handle of a driver that was loaded from a path returned by GetDriverPath().
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 11.4.3
+pub type DriverLoaded = extern "efiapi" fn(
+    *const Protocol,
+    controller_handle: efi::Handle,
+    driver_image_path: *mut device_path::Protocol,
+    driver_image_handle: efi::Handle,
+) -> efi::Status;
+
+/// Provides a platform specific override for the drivers that are bound to a controller.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 11.4
+#[repr(C)]
+#[derive(Debug)]
+pub struct Protocol {
+    pub get_driver: GetDriver,
+    pub get_driver_path: GetDriverPath,
+    pub driver_loaded: DriverLoaded,
+}
+
+/// Builds a [`PlatformDriverOverride`] from a fixed mapping of controllers to the drivers that override them.
+///
+/// ## Example
+///```
+/// use mu_pi::protocols::platform_driver_override::{PlatformDriverOverride, PlatformDriverOverrideBuilder};
+/// use r_efi::efi;
+///
+/// // The returned instance must be kept alive for as long as its protocol() is installed on a handle.
+/// fn example(controller: efi::Handle, driver: efi::Handle) -> Box<PlatformDriverOverride> {
+///   PlatformDriverOverrideBuilder::new().with_override(controller, driver).build()
+/// }
+///```
+#[derive(Debug, Default)]
+pub struct PlatformDriverOverrideBuilder {
+    overrides: BTreeMap<efi::Handle, Vec<efi::Handle>>,
+}
+
+impl PlatformDriverOverrideBuilder {
+    /// Instantiates a new builder without overrides.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Instantiates a new builder from a controller to driver list mapping. The drivers of each controller are
+    /// returned in the order of the list.
+    pub fn from_map(overrides: BTreeMap<efi::Handle, Vec<efi::Handle>>) -> Self {
+        Self { overrides }
+    }
+
+    /// Appends `driver` to the override drivers of `controller`.
+    pub fn with_override(mut self, controller: efi::Handle, driver: efi::Handle) -> Self {
+        self.overrides.entry(controller).or_default().push(driver);
+        self
+    }
+
+    /// Produces the protocol instance.
+    pub fn build(self) -> Box<PlatformDriverOverride> {
+        Box::new(PlatformDriverOverride {
+            protocol: Protocol {
+                get_driver: PlatformDriverOverride::get_driver,
+                get_driver_path: PlatformDriverOverride::get_driver_path,
+                driver_loaded: PlatformDriverOverride::driver_loaded,
+            },
+            overrides: self.overrides,
+        })
+    }
+}
+
+/// An instance of the Platform Driver Override Protocol backed by a fixed controller to driver mapping.
+///
+/// The overrides are all loaded drivers, so GetDriverPath() and DriverLoaded() return `UNSUPPORTED`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct PlatformDriverOverride {
+    // Must stay the first field: the services recover the instance from the protocol pointer.
+    protocol: Protocol,
+    overrides: BTreeMap<efi::Handle, Vec<efi::Handle>>,
+}
+
+impl PlatformDriverOverride {
+    /// Returns the protocol interface to install.
+    pub fn protocol(&self) -> *const Protocol {
+        &self.protocol
+    }
+
+    /// Returns the override drivers of `controller`, in the order GetDriver() returns them.
+    pub fn drivers(&self, controller: efi::Handle) -> &[efi::Handle] {
+        self.overrides.get(&controller).map_or(&[], Vec::as_slice)
+    }
+
+    extern "efiapi" fn get_driver(
+        this: *const Protocol,
+        controller_handle: efi::Handle,
+        driver_image_handle: *mut efi::Handle,
+    ) -> efi::Status {
+        //Safety: the protocol is the first field of a PlatformDriverOverride, which outlives the installed interface.
+        let Some(this) = (unsafe { (this as *const Self).as_ref() }) else {
+            return efi::Status::INVALID_PARAMETER;
+        };
+        if controller_handle.is_null() || driver_image_handle.is_null() {
+            return efi::Status::INVALID_PARAMETER;
+        }
+        let drivers = this.drivers(controller_handle);
+        let previous = unsafe { driver_image_handle.read() };
+        let next = if previous.is_null() {
+            0
+        } else {
+            match drivers.iter().position(|driver| *driver == previous) {
+                Some(index) => index + 1,
+                None => return efi::Status::INVALID_PARAMETER,
+            }
+        };
+        match drivers.get(next) {
+            Some(driver) => {
+                unsafe { driver_image_handle.write(*driver) };
+                efi::Status::SUCCESS
+            }
+            None => efi::Status::NOT_FOUND,
+        }
+    }
+
+    extern "efiapi" fn get_driver_path(
+        _this: *const Protocol,
+        _controller_handle: efi::Handle,
+        driver_image_path: *mut *mut device_path::Protocol,
+    ) -> efi::Status {
+        if !driver_image_path.is_null() {
+            unsafe { driver_image_path.write(ptr::null_mut()) };
+        }
+        efi::Status::UNSUPPORTED
+    }
+
+    extern "efiapi" fn driver_loaded(
+        _this: *const Protocol,
+        _controller_handle: efi::Handle,
+        _driver_image_path: *mut device_path::Protocol,
+        _driver_image_handle: efi::Handle,
+    ) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    use alloc::{collections::BTreeMap, vec};
+    use core::{mem::size_of, ptr};
+
+    use r_efi::efi;
+
+    use super::{PlatformDriverOverrideBuilder, Protocol, PROTOCOL_GUID};
+
+    fn handle(value: usize) -> efi::Handle {
+        value as efi::Handle
+    }
+
+    #[test]
+    fn guid_should_match_spec() {
+        assert_eq!(
+            PROTOCOL_GUID.as_bytes(),
+            &[0x38, 0xc7, 0x30, 0x6b, 0x91, 0xa3, 0xd4, 0x11, 0x9a, 0x3b, 0x00, 0x90, 0x27, 0x3f, 0xc1, 0x4d]
+        );
+        assert_eq!(size_of::<Protocol>(), size_of::<[usize; 3]>());
+    }
+
+    #[test]
+    fn get_driver_should_enumerate_overrides_in_order() {
+        let mut overrides = BTreeMap::new();
+        overrides.insert(handle(0x1000), vec![handle(0x10), handle(0x20)]);
+        let instance =
+            PlatformDriverOverrideBuilder::from_map(overrides).with_override(handle(0x2000), handle(0x30)).build();
+        let protocol = unsafe { &*instance.protocol() };
+
+        let mut driver: efi::Handle = ptr::null_mut();
+        assert_eq!((protocol.get_driver)(protocol, handle(0x1000), &mut driver), efi::Status::SUCCESS);
+        assert_eq!(driver, handle(0x10));
+        assert_eq!((protocol.get_driver)(protocol, handle(0x1000), &mut driver), efi::Status::SUCCESS);
+        assert_eq!(driver, handle(0x20));
+        assert_eq!((protocol.get_driver)(protocol, handle(0x1000), &mut driver), efi::Status::NOT_FOUND);
+
+        driver = ptr::null_mut();
+        assert_eq!((protocol.get_driver)(protocol, handle(0x2000), &mut driver), efi::Status::SUCCESS);
+        assert_eq!(driver, handle(0x30));
+
+        // A driver that was not returned for the controller, and a controller without overrides.
+        assert_eq!((protocol.get_driver)(protocol, handle(0x1000), &mut driver), efi::Status::INVALID_PARAMETER);
+        driver = ptr::null_mut();
+        assert_eq!((protocol.get_driver)(protocol, handle(0x3000), &mut driver), efi::Status::NOT_FOUND);
+        assert_eq!((protocol.get_driver)(protocol, ptr::null_mut(), &mut driver), efi::Status::INVALID_PARAMETER);
+        assert_eq!((protocol.get_driver)(protocol, handle(0x1000), ptr::null_mut()), efi::Status::INVALID_PARAMETER);
+    }
+
+    #[test]
+    fn path_services_should_be_unsupported() {
+        let instance = PlatformDriverOverrideBuilder::new().build();
+        let protocol = unsafe { &*instance.protocol() };
+
+        let mut path = 0x1234 as *mut _;
+        assert_eq!((protocol.get_driver_path)(protocol, handle(0x1000), &mut path), efi::Status::UNSUPPORTED);
+        assert!(path.is_null());
+        assert_eq!(
+            (protocol.driver_loaded)(protocol, handle(0x1000), ptr::null_mut(), handle(0x10)),
+            efi::Status::UNSUPPORTED
+        );
+        assert!(instance.drivers(handle(0x1000)).is_empty());
+    }
+}
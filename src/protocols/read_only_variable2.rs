@@ -0,0 +1,86 @@
+//! Read Only Variable2 PPI
+//!
+//! Permits read-only access to the UEFI variables in the PEI phase (EFI_PEI_READ_ONLY_VARIABLE2_PPI).
+//! [`VarReader`](crate::variables::VarReader) wraps it with safe accessors.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V1_PEI_Services.html#efi-pei-read-only-variable2-ppi>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::ffi::c_void;
+
+use r_efi::efi;
+
+/// Read Only Variable2 PPI GUID
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-6.3
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x2ab86ef5, 0xecb5, 0x4134, 0xb5, 0x56, &[0x38, 0x54, 0xca, 0x1f, 0xe1, 0xb4]);
+
+/// Reads the value and attributes of a variable.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-6.3
+///
+/// * this - pointer to the PPI
+/// * variable_name - the null-terminated UCS-2 name of the variable
+/// * variable_guid - the vendor GUID of the variable
+/// * attributes - receives the attributes of the variable, if not null
+/// * data_size - the size of the data buffer on input, the size of the data on output
+/// * data - receives the data of the variable, may be null if data_size is zero
+///
+/// * @retval - NOT_FOUND: the variable was not found
+/// * @retval - BUFFER_TOO_SMALL: the buffer is too small for the data; data_size is set to the size needed
+/// * @retval - INVALID_PARAMETER: a pointer is null, or data is null while data_size is not zero
+/// * @retval - DEVICE_ERROR: the variable could not be read due to a hardware error
+pub type GetVariable2 = extern "efiapi" fn(
+    this: *const Protocol,
+    variable_name: *const u16,
+    variable_guid: *const efi::Guid,
+    attributes: *mut u32,
+    data_size: *mut usize,
+    data: *mut c_void,
+) -> efi::Status;
+
+/// Returns the name and vendor GUID of the variable following the given one, the first variable for an empty name.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-6.3
+///
+/// * this - pointer to the PPI
+/// * variable_name_size - the size of the name buffer on input, the size of the name on output
+/// * variable_name - the null-terminated UCS-2 name of the previous variable on input, of the next one on output
+/// * variable_guid - the vendor GUID of the previous variable on input, of the next one on output
+///
+/// * @retval - NOT_FOUND: there is no next variable
+/// * @retval - BUFFER_TOO_SMALL: the name buffer is too small; variable_name_size is set to the size needed
+/// * @retval - INVALID_PARAMETER: a pointer is null
+/// * @retval - DEVICE_ERROR: the variable name could not be read due to a hardware error
+pub type GetNextVariableName2 = extern "efiapi" fn(
+    this: *const Protocol,
+    variable_name_size: *mut usize,
+    variable_name: *mut u16,
+    variable_guid: *mut efi::Guid,
+) -> efi::Status;
+
+/// Read-only access to the UEFI variables in PEI.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section I-6.3
+#[repr(C)]
+#[derive(Debug)]
+pub struct Protocol {
+    pub get_variable: GetVariable2,
+    pub next_variable_name: GetNextVariableName2,
+}
+
+//Safety: Protocol has the layout of EFI_PEI_READ_ONLY_VARIABLE2_PPI.
+unsafe impl crate::pei::Ppi for Protocol {
+    const GUID: efi::Guid = PROTOCOL_GUID;
+}
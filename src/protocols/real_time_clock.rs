@@ -0,0 +1,22 @@
+//! Real Time Clock Architectural Protocol
+//!
+//! Provides the services required to access the real-time clock of the platform. The protocol has no functions: it is
+//! installed once the GetTime(), SetTime(), GetWakeupTime() and SetWakeupTime() runtime services are available.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V2_DXE_Architectural_Protocols.html#real-time-clock-architectural-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use r_efi::efi;
+
+/// Real Time Clock Architectural Protocol GUID
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section II-12.6.1
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x27cfac87, 0x46cc, 0x11d4, 0x9a, 0x38, &[0x00, 0x90, 0x27, 0x3f, 0xc1, 0x4d]);
@@ -0,0 +1,22 @@
+//! Reset Architectural Protocol
+//!
+//! Provides the service required to reset the platform. The protocol has no functions: it is installed once the
+//! ResetSystem() runtime service is available.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V2_DXE_Architectural_Protocols.html#reset-architectural-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use r_efi::efi;
+
+/// Reset Architectural Protocol GUID
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section II-12.7.1
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x27cfac88, 0x46cc, 0x11d4, 0x9a, 0x38, &[0x00, 0x90, 0x27, 0x3f, 0xc1, 0x4d]);
@@ -0,0 +1,88 @@
+//! S3 Resume
+//!
+//! On resume from S3, the PEI phase restores the configuration recorded with the
+//! [S3 Save State Protocol](crate::protocols::s3_save_state) by calling S3RestoreConfig2() of the S3 Resume2 PPI
+//! (EFI_PEI_S3_RESUME2_PPI), which replays the boot script and then transfers control to the OS waking vector. The
+//! PPI is installed by the S3 resume PEIM and called by the PEI Foundation; it does not return on success.
+//!
+//! The boot script replayed is only complete once the platform has recorded all its entries, and must not change once
+//! third-party code may run. The ready-to-boot marker protocol ([`READY_TO_BOOT_PROTOCOL_GUID`]) is installed in the
+//! SMM protocol database at ReadyToBoot; boot script entries must be recorded before it is installed, and boot script
+//! implementations stop accepting entries from outside SMM when it is.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V5_S3_Resume.html>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use r_efi::efi;
+
+/// S3 Resume2 PPI GUID
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section V-8.6
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x6d582dbc, 0xdb85, 0x4514, 0x8f, 0xcc, &[0x5a, 0xdf, 0x62, 0x27, 0xb1, 0x47]);
+
+/// Ready-to-boot marker protocol GUID (EDKII_SMM_READY_TO_BOOT_PROTOCOL_GUID).
+///
+/// The PI specification does not define a ready-to-boot protocol; this is the GUID used by EDK II.
+pub const READY_TO_BOOT_PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x6e057ecf, 0xfa99, 0x4f39, 0x95, 0xbc, &[0x59, 0xf9, 0x92, 0x1d, 0x17, 0xe4]);
+
+/// Restores the platform to its preboot configuration by replaying the boot script, then transfers control to the OS
+/// waking vector.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section V-8.6
+///
+/// * this - pointer to the PPI
+///
+/// * @retval - ABORTED: execution of the boot script was aborted
+/// * @retval - NOT_FOUND: the boot script or the OS waking vector was not found
+/// * @retval - OUT_OF_RESOURCES: there is not enough memory to replay the boot script
+pub type S3RestoreConfig2 = extern "efiapi" fn(this: *const Protocol) -> efi::Status;
+
+/// Restores the platform configuration on resume from S3.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section V-8.6
+#[repr(C)]
+#[derive(Debug)]
+pub struct Protocol {
+    pub s3_restore_config2: S3RestoreConfig2,
+}
+
+/// The ready-to-boot marker protocol. It has no interface; its installation is the signal.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ReadyToBootProtocol;
+
+#[cfg(test)]
+mod tests {
+    use core::mem::size_of;
+
+    use super::{Protocol, ReadyToBootProtocol, PROTOCOL_GUID, READY_TO_BOOT_PROTOCOL_GUID};
+
+    #[test]
+    fn guids_should_match_spec() {
+        assert_eq!(
+            PROTOCOL_GUID.as_bytes(),
+            &[0xbc, 0x2d, 0x58, 0x6d, 0x85, 0xdb, 0x14, 0x45, 0x8f, 0xcc, 0x5a, 0xdf, 0x62, 0x27, 0xb1, 0x47]
+        );
+        assert_eq!(
+            READY_TO_BOOT_PROTOCOL_GUID.as_bytes(),
+            &[0xcf, 0x7e, 0x05, 0x6e, 0x99, 0xfa, 0x39, 0x4f, 0x95, 0xbc, 0x59, 0xf9, 0x92, 0x1d, 0x17, 0xe4]
+        );
+    }
+
+    #[test]
+    fn layouts_should_match_spec() {
+        assert_eq!(size_of::<Protocol>(), size_of::<usize>());
+        assert_eq!(size_of::<ReadyToBootProtocol>(), 0);
+    }
+}
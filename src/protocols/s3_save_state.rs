@@ -0,0 +1,425 @@
+//! S3 Save State Protocol
+//!
+//! Records the boot script replayed on resume from S3. Entries are added with Write(), or inserted relative to an
+//! existing entry with Insert(); Label() finds or creates a named position in the script, and Compare() orders two
+//! positions. The encoding of the entries in the script table is described in [`crate::boot_script`].
+//!
+//! Write() and Insert() are variadic: the arguments that follow the opcode depend on the opcode. Rust cannot declare
+//! variadic `efiapi` functions, so [`S3SaveStateWrite`] and [`S3SaveStateInsert`] only declare the first of these
+//! arguments. The `write_*` functions call Write() with the exact arguments of each opcode, relying on variadic and
+//! fixed integer and pointer arguments being passed the same way by the UEFI calling conventions.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V5_S3_Resume.html#s3-save-state-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{
+    ffi::{c_char, c_void, CStr},
+    mem, ptr,
+};
+
+use r_efi::efi;
+
+use crate::{boot_script, status::StatusExt};
+
+/// S3 Save State Protocol GUID
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section V-8.2
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0xe857caf6, 0xc046, 0x45dc, 0xbe, 0x3f, &[0xee, 0x07, 0x65, 0xfb, 0xa8, 0x87]);
+
+/// Width of each access made by an entry (EFI_BOOT_SCRIPT_WIDTH).
+pub type BootScriptWidth = boot_script::Width;
+
+/// A position in the boot script (EFI_S3_BOOT_SCRIPT_POSITION), returned by Insert() and Label().
+pub type BootScriptPosition = *mut c_void;
+
+/// The opcodes accepted by Write() and Insert() (EFI_BOOT_SCRIPT_*_OPCODE).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section V-8.2
+#[repr(u16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BootScriptOpCode {
+    IoWrite = boot_script::IO_WRITE_OPCODE,
+    IoReadWrite = boot_script::IO_READ_WRITE_OPCODE,
+    MemWrite = boot_script::MEM_WRITE_OPCODE,
+    MemReadWrite = boot_script::MEM_READ_WRITE_OPCODE,
+    PciConfigWrite = boot_script::PCI_CONFIG_WRITE_OPCODE,
+    PciConfigReadWrite = boot_script::PCI_CONFIG_READ_WRITE_OPCODE,
+    SmbusExecute = boot_script::SMBUS_EXECUTE_OPCODE,
+    Stall = boot_script::STALL_OPCODE,
+    Dispatch = boot_script::DISPATCH_OPCODE,
+    Dispatch2 = boot_script::DISPATCH_2_OPCODE,
+    Information = boot_script::INFORMATION_OPCODE,
+    PciConfig2Write = boot_script::PCI_CONFIG2_WRITE_OPCODE,
+    PciConfig2ReadWrite = boot_script::PCI_CONFIG2_READ_WRITE_OPCODE,
+    IoPoll = boot_script::IO_POLL_OPCODE,
+    MemPoll = boot_script::MEM_POLL_OPCODE,
+    PciConfigPoll = boot_script::PCI_CONFIG_POLL_OPCODE,
+    PciConfig2Poll = boot_script::PCI_CONFIG2_POLL_OPCODE,
+}
+
+impl TryFrom<u16> for BootScriptOpCode {
+    type Error = u16;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        Ok(match value {
+            boot_script::IO_WRITE_OPCODE => Self::IoWrite,
+            boot_script::IO_READ_WRITE_OPCODE => Self::IoReadWrite,
+            boot_script::MEM_WRITE_OPCODE => Self::MemWrite,
+            boot_script::MEM_READ_WRITE_OPCODE => Self::MemReadWrite,
+            boot_script::PCI_CONFIG_WRITE_OPCODE => Self::PciConfigWrite,
+            boot_script::PCI_CONFIG_READ_WRITE_OPCODE => Self::PciConfigReadWrite,
+            boot_script::SMBUS_EXECUTE_OPCODE => Self::SmbusExecute,
+            boot_script::STALL_OPCODE => Self::Stall,
+            boot_script::DISPATCH_OPCODE => Self::Dispatch,
+            boot_script::DISPATCH_2_OPCODE => Self::Dispatch2,
+            boot_script::INFORMATION_OPCODE => Self::Information,
+            boot_script::PCI_CONFIG2_WRITE_OPCODE => Self::PciConfig2Write,
+            boot_script::PCI_CONFIG2_READ_WRITE_OPCODE => Self::PciConfig2ReadWrite,
+            boot_script::IO_POLL_OPCODE => Self::IoPoll,
+            boot_script::MEM_POLL_OPCODE => Self::MemPoll,
+            boot_script::PCI_CONFIG_POLL_OPCODE => Self::PciConfigPoll,
+            boot_script::PCI_CONFIG2_POLL_OPCODE => Self::PciConfig2Poll,
+            _ => Err(value)?,
+        })
+    }
+}
+
+/// Appends an entry to the boot script.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section V-8.2.1
+///
+/// * this - pointer to the protocol
+/// * op_code - the opcode of the entry
+/// * payload - the first of the opcode-specific arguments; see the module documentation
+///
+/// * @retval - SUCCESS: the entry was added
+/// * @retval - INVALID_PARAMETER: the opcode is unknown, or its arguments are invalid
+/// * @retval - OUT_OF_RESOURCES: there is not enough memory to store the entry
+pub type S3SaveStateWrite =
+    extern "efiapi" fn(this: *const Protocol, op_code: u16, payload: *const c_void) -> efi::Status;
+
+/// Inserts an entry before or after a position in the boot script.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section V-8.2.2
+///
+/// * this - pointer to the protocol
+/// * before_or_after - insert before the position if TRUE, after it if FALSE
+/// * position - the position, or null for the start or end of the script; receives the position of the new entry
+/// * op_code - the opcode of the entry
+/// * payload - the first of the opcode-specific arguments
+///
+/// * @retval - SUCCESS: the entry was inserted
+/// * @retval - INVALID_PARAMETER: the opcode or position is invalid
+/// * @retval - OUT_OF_RESOURCES: there is not enough memory to store the entry
+pub type S3SaveStateInsert = extern "efiapi" fn(
+    this: *const Protocol,
+    before_or_after: efi::Boolean,
+    position: *mut BootScriptPosition,
+    op_code: u16,
+    payload: *const c_void,
+) -> efi::Status;
+
+/// Finds a label in the boot script, or inserts it before or after a position.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section V-8.2.3
+///
+/// * this - pointer to the protocol
+/// * before_or_after - insert before the position if TRUE, after it if FALSE
+/// * is_label - insert the label if the script does not have it
+/// * position - the position, or null for the start or end of the script; receives the position of the label
+/// * label - the null-terminated ASCII name of the label
+///
+/// * @retval - SUCCESS: the label was found or inserted
+/// * @retval - INVALID_PARAMETER: the label is null or empty, or the position is invalid
+/// * @retval - NOT_FOUND: is_label is FALSE and the script does not have the label
+pub type S3SaveStateLabel = extern "efiapi" fn(
+    this: *const Protocol,
+    before_or_after: efi::Boolean,
+    is_label: efi::Boolean,
+    position: *mut BootScriptPosition,
+    label: *const c_char,
+) -> efi::Status;
+
+/// Compares the order of two positions in the boot script.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section V-8.2.4
+///
+/// * @retval - SUCCESS: relative_position is -1, 0 or 1 as position1 is before, at or after position2
+/// * @retval - INVALID_PARAMETER: a position is invalid
+pub type S3SaveStateCompare = extern "efiapi" fn(
+    this: *const Protocol,
+    position1: BootScriptPosition,
+    position2: BootScriptPosition,
+    relative_position: *mut usize,
+) -> efi::Status;
+
+/// Records the boot script.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section V-8.2
+#[repr(C)]
+#[derive(Debug)]
+pub struct Protocol {
+    pub write: S3SaveStateWrite,
+    pub insert: S3SaveStateInsert,
+    pub label: S3SaveStateLabel,
+    pub compare: S3SaveStateCompare,
+}
+
+// The argument lists of Write() for each group of opcodes.
+type WriteBuffer = extern "efiapi" fn(*const Protocol, u16, BootScriptWidth, u64, usize, *const c_void) -> efi::Status;
+type WriteMasked =
+    extern "efiapi" fn(*const Protocol, u16, BootScriptWidth, u64, *const c_void, *const c_void) -> efi::Status;
+type WriteValue = extern "efiapi" fn(*const Protocol, u16, usize) -> efi::Status;
+type WriteAddress = extern "efiapi" fn(*const Protocol, u16, u64) -> efi::Status;
+type WriteAddresses = extern "efiapi" fn(*const Protocol, u16, u64, u64) -> efi::Status;
+
+fn write_buffer<T>(
+    save_state: &Protocol,
+    op_code: BootScriptOpCode,
+    width: BootScriptWidth,
+    address: u64,
+    data: &T,
+) -> Result<(), efi::Status> {
+    //Safety: Write() takes the width, address, count and buffer after these opcodes.
+    let write: WriteBuffer = unsafe { mem::transmute(save_state.write) };
+    write(save_state, op_code as u16, width, address, 1, data as *const T as *const c_void).ok()
+}
+
+fn write_masked<T>(
+    save_state: &Protocol,
+    op_code: BootScriptOpCode,
+    width: BootScriptWidth,
+    address: u64,
+    data: &T,
+    data_mask: &T,
+) -> Result<(), efi::Status> {
+    //Safety: Write() takes the width, address, data and data mask after these opcodes.
+    let write: WriteMasked = unsafe { mem::transmute(save_state.write) };
+    let (data, data_mask) = (data as *const T as *const c_void, data_mask as *const T as *const c_void);
+    write(save_state, op_code as u16, width, address, data, data_mask).ok()
+}
+
+/// Records a write of `data` to the I/O port `port`.
+pub fn write_io_write(save_state: &Protocol, port: u16, data: u8) -> Result<(), efi::Status> {
+    write_buffer(save_state, BootScriptOpCode::IoWrite, BootScriptWidth::Uint8, port.into(), &data)
+}
+
+/// Records a read of the I/O port `port` that writes `(read & data_mask) | data` back.
+pub fn write_io_read_write(save_state: &Protocol, port: u16, data: u8, data_mask: u8) -> Result<(), efi::Status> {
+    write_masked(save_state, BootScriptOpCode::IoReadWrite, BootScriptWidth::Uint8, port.into(), &data, &data_mask)
+}
+
+/// Records a write of `data` to memory at `address`.
+pub fn write_mem_write(save_state: &Protocol, address: u64, data: u32) -> Result<(), efi::Status> {
+    write_buffer(save_state, BootScriptOpCode::MemWrite, BootScriptWidth::Uint32, address, &data)
+}
+
+/// Records a read of memory at `address` that writes `(read & data_mask) | data` back.
+pub fn write_mem_read_write(save_state: &Protocol, address: u64, data: u32, data_mask: u32) -> Result<(), efi::Status> {
+    write_masked(save_state, BootScriptOpCode::MemReadWrite, BootScriptWidth::Uint32, address, &data, &data_mask)
+}
+
+/// Records a write of `data` to the PCI configuration space `address` of segment 0, encoded as described in
+/// [`crate::s3::boot_script::PciAddress`].
+pub fn write_pci_config_write(save_state: &Protocol, address: u64, data: u16) -> Result<(), efi::Status> {
+    write_buffer(save_state, BootScriptOpCode::PciConfigWrite, BootScriptWidth::Uint16, address, &data)
+}
+
+/// Records a stall of `duration` microseconds.
+pub fn write_stall(save_state: &Protocol, duration: usize) -> Result<(), efi::Status> {
+    //Safety: Write() takes the duration after the STALL opcode.
+    let write: WriteValue = unsafe { mem::transmute(save_state.write) };
+    write(save_state, BootScriptOpCode::Stall as u16, duration).ok()
+}
+
+/// Records a call to the code at `entry_point`.
+pub fn write_dispatch(save_state: &Protocol, entry_point: u64) -> Result<(), efi::Status> {
+    //Safety: Write() takes the entry point as an EFI_PHYSICAL_ADDRESS after the DISPATCH opcode.
+    let write: WriteAddress = unsafe { mem::transmute(save_state.write) };
+    write(save_state, BootScriptOpCode::Dispatch as u16, entry_point).ok()
+}
+
+/// Records a call to the code at `entry_point` with `context`.
+pub fn write_dispatch2(save_state: &Protocol, entry_point: u64, context: u64) -> Result<(), efi::Status> {
+    //Safety: Write() takes the entry point and context as EFI_PHYSICAL_ADDRESS values after the DISPATCH_2 opcode.
+    let write: WriteAddresses = unsafe { mem::transmute(save_state.write) };
+    write(save_state, BootScriptOpCode::Dispatch2 as u16, entry_point, context).ok()
+}
+
+/// Returns the position of the label `label`, inserting it at the end of the script if the script does not have it.
+pub fn insert_label(save_state: &Protocol, label: &CStr) -> Result<BootScriptPosition, efi::Status> {
+    let mut position = ptr::null_mut();
+    (save_state.label)(save_state, efi::Boolean::FALSE, efi::Boolean::TRUE, &mut position, label.as_ptr()).ok()?;
+    Ok(position)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        cell::RefCell,
+        ffi::{c_char, CStr},
+        mem::{self, size_of},
+    };
+
+    use r_efi::efi;
+
+    use super::{
+        insert_label, write_dispatch, write_dispatch2, write_io_read_write, write_io_write, write_mem_read_write,
+        write_mem_write, write_pci_config_write, write_stall, BootScriptOpCode, BootScriptPosition, BootScriptWidth,
+        Protocol, PROTOCOL_GUID,
+    };
+    use crate::boot_script;
+
+    // (opcode, the four arguments after it)
+    type Call = (u16, [usize; 4]);
+
+    std::thread_local! {
+        static CALLS: RefCell<Vec<Call>> = RefCell::new(Vec::new());
+        static LABELS: RefCell<Vec<(bool, bool, String)>> = RefCell::new(Vec::new());
+    }
+
+    // Mock Write() receiving four arguments after the opcode. Arguments the caller did not pass are not inspected.
+    extern "efiapi" fn mock_write(
+        _this: *const Protocol,
+        op_code: u16,
+        a: usize,
+        b: usize,
+        c: usize,
+        d: usize,
+    ) -> efi::Status {
+        // Record the values behind buffer pointers, which do not outlive the call.
+        let read = |pointer: usize, size: usize| unsafe {
+            let mut value = 0usize;
+            core::ptr::copy_nonoverlapping(pointer as *const u8, &mut value as *mut usize as *mut u8, size);
+            value
+        };
+        let arguments = match BootScriptOpCode::try_from(op_code) {
+            Ok(BootScriptOpCode::IoWrite | BootScriptOpCode::MemWrite | BootScriptOpCode::PciConfigWrite) => {
+                let width = [1, 2, 4, 8][a & 3];
+                [a, b, c, read(d, width)]
+            }
+            Ok(BootScriptOpCode::IoReadWrite | BootScriptOpCode::MemReadWrite) => {
+                let width = [1, 2, 4, 8][a & 3];
+                [a, b, read(c, width), read(d, width)]
+            }
+            Ok(BootScriptOpCode::Stall | BootScriptOpCode::Dispatch) => [a, 0, 0, 0],
+            Ok(BootScriptOpCode::Dispatch2) => [a, b, 0, 0],
+            _ => return efi::Status::INVALID_PARAMETER,
+        };
+        CALLS.with(|calls| calls.borrow_mut().push((op_code, arguments)));
+        efi::Status::SUCCESS
+    }
+
+    extern "efiapi" fn mock_label(
+        _this: *const Protocol,
+        before_or_after: efi::Boolean,
+        is_label: efi::Boolean,
+        position: *mut BootScriptPosition,
+        label: *const c_char,
+    ) -> efi::Status {
+        let label = unsafe { CStr::from_ptr(label) }.to_str().unwrap().to_string();
+        if label.is_empty() {
+            return efi::Status::INVALID_PARAMETER;
+        }
+        LABELS.with(|labels| labels.borrow_mut().push((before_or_after.into(), is_label.into(), label)));
+        unsafe { *position = 0x5000 as BootScriptPosition };
+        efi::Status::SUCCESS
+    }
+
+    extern "efiapi" fn unused() -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+
+    fn mock_protocol() -> Protocol {
+        unsafe {
+            Protocol {
+                write: mem::transmute(mock_write as usize),
+                insert: mem::transmute(unused as usize),
+                label: mock_label,
+                compare: mem::transmute(unused as usize),
+            }
+        }
+    }
+
+    fn take_calls() -> Vec<Call> {
+        CALLS.with(|calls| calls.borrow_mut().drain(..).collect())
+    }
+
+    #[test]
+    fn guid_and_layout_should_match_spec() {
+        assert_eq!(
+            PROTOCOL_GUID.as_bytes(),
+            &[0xf6, 0xca, 0x57, 0xe8, 0x46, 0xc0, 0xdc, 0x45, 0xbe, 0x3f, 0xee, 0x07, 0x65, 0xfb, 0xa8, 0x87]
+        );
+        assert_eq!(size_of::<Protocol>(), 4 * size_of::<usize>());
+        assert_eq!(size_of::<BootScriptWidth>(), size_of::<u32>());
+    }
+
+    #[test]
+    fn opcodes_should_match_spec() {
+        assert_eq!(BootScriptOpCode::IoWrite as u16, 0x00);
+        assert_eq!(BootScriptOpCode::Stall as u16, 0x07);
+        assert_eq!(BootScriptOpCode::Information as u16, 0x0A);
+        assert_eq!(BootScriptOpCode::PciConfig2Poll as u16, 0x10);
+        for value in 0..=0x10 {
+            assert_eq!(BootScriptOpCode::try_from(value).map(|op_code| op_code as u16), Ok(value));
+        }
+        for value in [0x11, boot_script::TABLE_OPCODE, boot_script::LABEL_OPCODE, boot_script::TERMINATE_OPCODE] {
+            assert_eq!(BootScriptOpCode::try_from(value), Err(value));
+        }
+    }
+
+    #[test]
+    fn wrappers_should_pass_opcode_arguments() {
+        let protocol = mock_protocol();
+        write_io_write(&protocol, 0x80, 0x55).unwrap();
+        write_io_read_write(&protocol, 0x61, 0x0C, 0xF0).unwrap();
+        write_mem_write(&protocol, 0xFED4_0044, 0xDEAD_BEEF).unwrap();
+        write_mem_read_write(&protocol, 0xFED1_F404, 0x4, 0xFFFF_FFFB).unwrap();
+        write_pci_config_write(&protocol, 0x001F_0004, 0x0007).unwrap();
+        write_stall(&protocol, 100).unwrap();
+        write_dispatch(&protocol, 0xFFF0_0000).unwrap();
+        write_dispatch2(&protocol, 0xFFF0_0000, 0x1000).unwrap();
+
+        let (uint8, uint16, uint32) =
+            (BootScriptWidth::Uint8 as usize, BootScriptWidth::Uint16 as usize, BootScriptWidth::Uint32 as usize);
+        assert_eq!(
+            take_calls(),
+            [
+                (0x00, [uint8, 0x80, 1, 0x55]),
+                (0x01, [uint8, 0x61, 0x0C, 0xF0]),
+                (0x02, [uint32, 0xFED4_0044, 1, 0xDEAD_BEEF]),
+                (0x03, [uint32, 0xFED1_F404, 0x4, 0xFFFF_FFFB]),
+                (0x04, [uint16, 0x001F_0004, 1, 0x0007]),
+                (0x07, [100, 0, 0, 0]),
+                (0x08, [0xFFF0_0000, 0, 0, 0]),
+                (0x09, [0xFFF0_0000, 0x1000, 0, 0]),
+            ]
+        );
+    }
+
+    #[test]
+    fn labels_should_be_inserted_at_the_end() {
+        let protocol = mock_protocol();
+        let position = insert_label(&protocol, CStr::from_bytes_with_nul(b"Silicon\0").unwrap()).unwrap();
+        assert_eq!(position as usize, 0x5000);
+        let empty = CStr::from_bytes_with_nul(b"\0").unwrap();
+        assert_eq!(insert_label(&protocol, empty), Err(efi::Status::INVALID_PARAMETER));
+        let labels = LABELS.with(|labels| labels.borrow().clone());
+        assert_eq!(labels, [(false, true, String::from("Silicon"))]);
+    }
+}
@@ -0,0 +1,348 @@
+//! Security Architectural Protocol
+//!
+//! Abstracts the security-specific functions from the DXE Foundation for the purposes of handling GUIDed section
+//! encapsulations. The DXE Foundation calls FileAuthenticationState() with the authentication status of each file it
+//! dispatches, and the platform policy decides whether the file may be used.
+//!
+//! [`StaticSecurityProtocol`] implements the protocol with a [`SecurityPolicy`], so that the policy does not handle
+//! raw pointers itself.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V2_DXE_Architectural_Protocols.html#security-architectural-protocols>
+//!
+//! ## Example
+//! ```
+//! use mu_pi::{
+//!   device_path::DevicePathWalker,
+//!   fw_fs::ffs::section::AuthStatus,
+//!   protocols::security::{AuthStatusSummary, SecurityPolicy, SecurityVerdict, StaticSecurityProtocol},
+//! };
+//!
+//! // Allows signed files, defers files without a checked signature, and denies files whose signature is invalid.
+//! struct SignedOnly;
+//!
+//! impl SecurityPolicy for SignedOnly {
+//!   fn evaluate(&self, auth_status: AuthStatus, _file: Option<&DevicePathWalker>) -> SecurityVerdict {
+//!     match AuthStatusSummary::from(auth_status) {
+//!       AuthStatusSummary { failed: true, .. } => SecurityVerdict::Deny,
+//!       summary if summary.is_verified() => SecurityVerdict::Allow,
+//!       _ => SecurityVerdict::Defer,
+//!     }
+//!   }
+//! }
+//!
+//! static SECURITY: StaticSecurityProtocol<SignedOnly> = StaticSecurityProtocol::new(SignedOnly);
+//!
+//! // The protocol to install on a handle with the PROTOCOL_GUID.
+//! let protocol = SECURITY.protocol();
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::mem;
+
+use r_efi::efi;
+
+use crate::{
+    device_path::{DevicePathProtocol, DevicePathWalker},
+    fw_fs::ffs::section::{auth_status, AuthStatus},
+};
+
+/// Security Architectural Protocol GUID
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section II-12.9.1
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0xa46423e3, 0x4617, 0x49f1, 0xb9, 0xff, &[0xd1, 0xbf, 0xa9, 0x11, 0x58, 0x39]);
+
+/// Largest device path [`StaticSecurityProtocol`] reads from the DXE Foundation.
+pub const MAX_DEVICE_PATH_SIZE: usize = 0x1_0000;
+
+/// Determines whether a file may be used, given its authentication status.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section II-12.9.2
+///
+/// * this - pointer to the protocol
+/// * authentication_status - the authentication status of the file, a combination of the
+///   [`auth_status`](crate::fw_fs::ffs::section::auth_status) bits
+/// * file - the device path of the file
+///
+/// * @retval - SUCCESS: the file may be used
+/// * @retval - INVALID_PARAMETER: file is null
+/// * @retval - SECURITY_VIOLATION: the file did not authenticate; the platform policy places it in the untrusted state
+/// * @retval - ACCESS_DENIED: the file did not authenticate; the platform policy forbids its use
+pub type FileAuthenticationState = extern "efiapi" fn(
+    this: *const Protocol,
+    authentication_status: u32,
+    file: *const DevicePathProtocol,
+) -> efi::Status;
+
+/// The meaning of an authentication status, as returned by [`interpret_auth_status`].
+///
+/// A file is only known to be authentic if it is signed, its signature was checked, and the check passed; see
+/// [`Self::is_verified`]. A signed file whose signature was not checked (NOT_TESTED) is not verified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct AuthStatusSummary {
+    /// The file is in a signed section (IMAGE_SIGNED).
+    pub signed: bool,
+    /// The signature of the file was checked: it is signed and NOT_TESTED is clear.
+    pub tested: bool,
+    /// The signature of the file was checked and is invalid (TEST_FAILED).
+    pub failed: bool,
+    /// The platform overrode the authentication of the file (PLATFORM_OVERRIDE).
+    pub platform_override: bool,
+}
+
+impl AuthStatusSummary {
+    /// Returns whether the file is signed and its signature was checked and is valid.
+    pub fn is_verified(&self) -> bool {
+        self.signed && self.tested && !self.failed
+    }
+}
+
+impl From<AuthStatus> for AuthStatusSummary {
+    fn from(status: AuthStatus) -> Self {
+        let signed = status.contains(auth_status::IMAGE_SIGNED);
+        Self {
+            signed,
+            tested: signed && !status.contains(auth_status::NOT_TESTED),
+            failed: status.contains(auth_status::TEST_FAILED),
+            platform_override: status.contains(auth_status::PLATFORM_OVERRIDE),
+        }
+    }
+}
+
+/// Interprets the `authentication_status` passed to FileAuthenticationState(). Reserved bits are ignored.
+pub fn interpret_auth_status(authentication_status: u32) -> AuthStatusSummary {
+    AuthStatus::from_bits(authentication_status).into()
+}
+
+/// Abstracts the security-specific functions from the DXE Foundation.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section II-12.9.1
+#[repr(C)]
+#[derive(Debug)]
+pub struct Protocol {
+    pub file_authentication_state: FileAuthenticationState,
+}
+
+/// The decision of a [`SecurityPolicy`] about a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SecurityVerdict {
+    /// The file may be used (SUCCESS).
+    Allow,
+    /// The file is placed in the untrusted state; the DXE Foundation may defer it (SECURITY_VIOLATION).
+    Defer,
+    /// The file must not be used (ACCESS_DENIED).
+    Deny,
+}
+
+impl From<SecurityVerdict> for efi::Status {
+    fn from(verdict: SecurityVerdict) -> Self {
+        match verdict {
+            SecurityVerdict::Allow => efi::Status::SUCCESS,
+            SecurityVerdict::Defer => efi::Status::SECURITY_VIOLATION,
+            SecurityVerdict::Deny => efi::Status::ACCESS_DENIED,
+        }
+    }
+}
+
+/// A platform policy implementing FileAuthenticationState().
+pub trait SecurityPolicy {
+    /// Decides whether the file at `file` with `auth_status` may be used. `file` is `None` if the device path passed
+    /// by the DXE Foundation is malformed.
+    fn evaluate(&self, auth_status: AuthStatus, file: Option<&DevicePathWalker>) -> SecurityVerdict;
+}
+
+/// A Security Architectural Protocol instance implemented by a [`SecurityPolicy`].
+///
+/// The instance starts with the [`Protocol`], whose FileAuthenticationState() finds the policy from its `this`
+/// pointer, so the protocol must be installed from [`Self::protocol`] and the instance must not move while it is
+/// installed; a `static` is the usual place for it.
+///
+/// FileAuthenticationState() returns INVALID_PARAMETER for a null file without calling the policy. A panic in the
+/// policy aborts rather than unwinding into the DXE Foundation.
+#[repr(C)]
+#[derive(Debug)]
+pub struct StaticSecurityProtocol<T: SecurityPolicy> {
+    protocol: Protocol,
+    policy: T,
+}
+
+impl<T: SecurityPolicy> StaticSecurityProtocol<T> {
+    /// Creates the instance implemented by `policy`.
+    pub const fn new(policy: T) -> Self {
+        Self { protocol: Protocol { file_authentication_state: file_authentication_state::<T> }, policy }
+    }
+
+    /// Returns the protocol to install.
+    pub fn protocol(&self) -> &Protocol {
+        &self.protocol
+    }
+
+    /// Returns the policy.
+    pub fn policy(&self) -> &T {
+        &self.policy
+    }
+}
+
+// Turns a panic unwinding through a protocol function into an abort, by panicking again while it is dropped. Must be
+// forgotten when the function returns.
+pub(crate) struct AbortOnUnwind(pub(crate) &'static str);
+
+impl Drop for AbortOnUnwind {
+    fn drop(&mut self) {
+        panic!("{} panicked", self.0);
+    }
+}
+
+extern "efiapi" fn file_authentication_state<T: SecurityPolicy>(
+    this: *const Protocol,
+    authentication_status: u32,
+    file: *const DevicePathProtocol,
+) -> efi::Status {
+    if this.is_null() || file.is_null() {
+        return efi::Status::INVALID_PARAMETER;
+    }
+    let guard = AbortOnUnwind("security policy");
+    //Safety: the function is only installed in the protocol of a StaticSecurityProtocol<T>, which starts with it.
+    let instance = unsafe { &*(this as *const StaticSecurityProtocol<T>) };
+    //Safety: file is non-null, and the DXE Foundation passes a device path ending with an End node.
+    let file = unsafe { DevicePathWalker::from_ptr(file, MAX_DEVICE_PATH_SIZE) }.ok();
+    let verdict = instance.policy.evaluate(AuthStatus::from_bits(authentication_status), file.as_ref());
+    mem::forget(guard);
+    verdict.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{cell::RefCell, ptr};
+
+    use r_efi::efi;
+
+    use super::{
+        interpret_auth_status, AuthStatusSummary, Protocol, SecurityPolicy, SecurityVerdict, StaticSecurityProtocol,
+        PROTOCOL_GUID,
+    };
+    use crate::{
+        device_path::{DevicePathProtocol, DevicePathWalker},
+        fw_fs::ffs::section::{auth_status, AuthStatus},
+    };
+
+    // Allows signed files, defers files without a signature, and denies files whose signature is invalid. Records
+    // the calls it receives.
+    #[derive(Default)]
+    struct SignedOnly {
+        calls: RefCell<Vec<(u32, Option<Vec<u8>>)>>,
+    }
+
+    impl SecurityPolicy for SignedOnly {
+        fn evaluate(&self, auth_status: AuthStatus, file: Option<&DevicePathWalker>) -> SecurityVerdict {
+            let file = file.map(|file| file.as_bytes().to_vec());
+            self.calls.borrow_mut().push((auth_status.bits(), file));
+            if auth_status.contains(auth_status::TEST_FAILED) {
+                SecurityVerdict::Deny
+            } else if auth_status.contains(auth_status::IMAGE_SIGNED) && !auth_status.contains(auth_status::NOT_TESTED)
+            {
+                SecurityVerdict::Allow
+            } else {
+                SecurityVerdict::Defer
+            }
+        }
+    }
+
+    // A file path node for "A" followed by the End node.
+    const PATH: [u8; 12] = [0x04, 0x04, 0x08, 0x00, b'A', 0x00, 0x00, 0x00, 0x7F, 0xFF, 0x04, 0x00];
+
+    fn call(protocol: &Protocol, authentication_status: u32, file: *const DevicePathProtocol) -> efi::Status {
+        (protocol.file_authentication_state)(protocol, authentication_status, file)
+    }
+
+    #[test]
+    fn guid_should_match_spec() {
+        assert_eq!(
+            PROTOCOL_GUID.as_bytes(),
+            &[0xe3, 0x23, 0x64, 0xa4, 0x17, 0x46, 0xf1, 0x49, 0xb9, 0xff, 0xd1, 0xbf, 0xa9, 0x11, 0x58, 0x39]
+        );
+    }
+
+    #[test]
+    fn verdicts_should_map_to_documented_statuses() {
+        let instance = StaticSecurityProtocol::new(SignedOnly::default());
+        let file = PATH.as_ptr() as *const DevicePathProtocol;
+        let protocol = instance.protocol();
+
+        assert_eq!(call(protocol, auth_status::IMAGE_SIGNED, file), efi::Status::SUCCESS);
+        assert_eq!(
+            call(protocol, auth_status::IMAGE_SIGNED | auth_status::NOT_TESTED, file),
+            efi::Status::SECURITY_VIOLATION
+        );
+        assert_eq!(call(protocol, 0, file), efi::Status::SECURITY_VIOLATION);
+        assert_eq!(
+            call(protocol, auth_status::IMAGE_SIGNED | auth_status::TEST_FAILED, file),
+            efi::Status::ACCESS_DENIED
+        );
+
+        let calls = instance.policy().calls.borrow();
+        assert_eq!(calls.len(), 4);
+        assert_eq!(calls[0], (auth_status::IMAGE_SIGNED, Some(PATH.to_vec())));
+    }
+
+    #[test]
+    fn null_file_should_be_rejected_without_calling_the_policy() {
+        let instance = StaticSecurityProtocol::new(SignedOnly::default());
+        assert_eq!(call(instance.protocol(), auth_status::IMAGE_SIGNED, ptr::null()), efi::Status::INVALID_PARAMETER);
+        assert!(instance.policy().calls.borrow().is_empty());
+    }
+
+    #[test]
+    fn malformed_file_should_reach_the_policy_as_none() {
+        let instance = StaticSecurityProtocol::new(SignedOnly::default());
+        // A node shorter than the node header.
+        let path = [0x04, 0x04, 0x02, 0x00, 0x7F, 0xFF, 0x04, 0x00];
+        let status = call(instance.protocol(), auth_status::IMAGE_SIGNED, path.as_ptr() as *const DevicePathProtocol);
+        assert_eq!(status, efi::Status::SUCCESS);
+        assert_eq!(instance.policy().calls.borrow()[0], (auth_status::IMAGE_SIGNED, None));
+    }
+
+    #[test]
+    fn not_tested_should_mean_signature_checking_was_skipped() {
+        let verified = interpret_auth_status(auth_status::IMAGE_SIGNED);
+        assert_eq!(verified, AuthStatusSummary { signed: true, tested: true, ..Default::default() });
+        assert!(verified.is_verified());
+
+        // Signed, but the signature was not checked: not verified.
+        let skipped = interpret_auth_status(auth_status::IMAGE_SIGNED | auth_status::NOT_TESTED);
+        assert_eq!(skipped, AuthStatusSummary { signed: true, tested: false, ..Default::default() });
+        assert!(!skipped.is_verified());
+
+        let failed = interpret_auth_status(auth_status::IMAGE_SIGNED | auth_status::TEST_FAILED);
+        assert_eq!(failed, AuthStatusSummary { signed: true, tested: true, failed: true, platform_override: false });
+        assert!(!failed.is_verified());
+
+        // No signed section: nothing was tested, whatever NOT_TESTED says.
+        for status in [0, auth_status::NOT_TESTED] {
+            assert_eq!(interpret_auth_status(status), AuthStatusSummary::default());
+            assert!(!interpret_auth_status(status).is_verified());
+        }
+
+        let overridden = interpret_auth_status(auth_status::PLATFORM_OVERRIDE | auth_status::IMAGE_SIGNED | 0x100);
+        assert!(overridden.platform_override && overridden.is_verified());
+        assert!(!AuthStatusSummary::from(AuthStatus::from_bits(auth_status::ALL)).is_verified());
+    }
+
+    #[test]
+    fn auth_status_should_convert_to_and_from_u32() {
+        let status = AuthStatus::try_from(auth_status::IMAGE_SIGNED | auth_status::NOT_TESTED).unwrap();
+        assert_eq!(u32::from(status), 0x06);
+        assert_eq!(AuthStatus::try_from(auth_status::ALL).map(u32::from), Ok(0x0F));
+        assert_eq!(AuthStatus::try_from(0x12), Err(0x12));
+        assert_eq!(u32::from(AuthStatus::from_bits(0x12)), 0x12);
+    }
+}
@@ -0,0 +1,480 @@
+//! Security2 Architectural Protocol
+//!
+//! Abstracts the security-specific functions from the DXE Foundation of UEFI Image Verification, Trusted Computing
+//! Group (TCG) measured boot, and User Identity policy for image loading and consoles. The DXE Foundation calls
+//! FileAuthentication() with each image it loads, and with a null image before it connects drivers to a device.
+//!
+//! [`StaticSecurity2Protocol`] implements the protocol with an [`ImagePolicy`], validating the arguments received from
+//! the DXE Foundation before the policy sees them. [`Caller`] calls an installed instance from the DXE Foundation.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V2_DXE_Architectural_Protocols.html#security2-architectural-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{ffi::c_void, fmt, mem, ptr, slice};
+
+use r_efi::efi;
+
+use crate::{
+    device_path::{DevicePathError, DevicePathProtocol, DevicePathWalker},
+    protocols::security::{AbortOnUnwind, MAX_DEVICE_PATH_SIZE},
+};
+
+/// Security2 Architectural Protocol GUID
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section II-12.10.1
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x94ab2f58, 0x1438, 0x4ef1, 0x91, 0x52, &[0x18, 0x94, 0x1a, 0x3a, 0x0e, 0x68]);
+
+/// Largest image [`StaticSecurity2Protocol::new`] passes to its policy.
+pub const DEFAULT_MAX_IMAGE_SIZE: usize = 0x1000_0000;
+
+/// Determines whether an image may be used, or whether drivers may be connected to a device.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section II-12.10.2
+///
+/// * this - pointer to the protocol
+/// * device_path - the device path of the image or device; may be null if file_buffer is not
+/// * file_buffer - the image, or null to authorize connecting drivers to the device at device_path
+/// * file_size - the size of the image in bytes
+/// * boot_policy - TRUE if the image is loaded as a boot selection
+///
+/// * @retval - SUCCESS: the image authenticated and may be used, or file_buffer is null and the user may connect
+///   drivers to the device
+/// * @retval - SECURITY_VIOLATION: the image did not authenticate and is placed in the untrusted state, or the user
+///   may not connect drivers to the device or load drivers from it
+/// * @retval - ACCESS_DENIED: the image did not authenticate and must not be used
+pub type FileAuthentication = extern "efiapi" fn(
+    this: *const Protocol,
+    device_path: *const DevicePathProtocol,
+    file_buffer: *mut c_void,
+    file_size: usize,
+    boot_policy: efi::Boolean,
+) -> efi::Status;
+
+/// Abstracts the image verification, measured boot and user identity policy from the DXE Foundation.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section II-12.10.1
+#[repr(C)]
+#[derive(Debug)]
+pub struct Protocol {
+    pub file_authentication: FileAuthentication,
+}
+
+/// The decision of an [`ImagePolicy`].
+///
+/// With [`Allow`](Self::Allow) and [`DeferConnect`](Self::DeferConnect), these cover the documented results of
+/// FileAuthentication(): an image may be used, placed in the untrusted state, or rejected, and a device may or may not
+/// have drivers connected by the current user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageVerdict {
+    /// The image may be used, or drivers may be connected to the device (SUCCESS).
+    Allow,
+    /// The image is placed in the untrusted state; the DXE Foundation may defer it (SECURITY_VIOLATION).
+    Defer,
+    /// The image must not be used (ACCESS_DENIED).
+    Deny,
+    /// The current user may not connect drivers to the device, or load drivers from it (SECURITY_VIOLATION).
+    DeferConnect,
+}
+
+impl From<ImageVerdict> for efi::Status {
+    fn from(verdict: ImageVerdict) -> Self {
+        match verdict {
+            ImageVerdict::Allow => efi::Status::SUCCESS,
+            ImageVerdict::Defer | ImageVerdict::DeferConnect => efi::Status::SECURITY_VIOLATION,
+            ImageVerdict::Deny => efi::Status::ACCESS_DENIED,
+        }
+    }
+}
+
+/// A platform policy implementing FileAuthentication().
+pub trait ImagePolicy {
+    /// Decides whether `image`, loaded from `device_path`, may be used. `image` is `None` when the DXE Foundation
+    /// asks whether drivers may be connected to the device at `device_path`; `device_path` is `None` for images
+    /// loaded from a buffer only.
+    fn authenticate(
+        &self,
+        device_path: Option<&DevicePathWalker>,
+        image: Option<&[u8]>,
+        boot_policy: bool,
+    ) -> ImageVerdict;
+}
+
+/// A Security2 Architectural Protocol instance implemented by an [`ImagePolicy`].
+///
+/// The instance starts with the [`Protocol`], whose FileAuthentication() finds the policy from its `this` pointer, so
+/// the protocol must be installed from [`Self::protocol`] and the instance must not move while it is installed.
+///
+/// FileAuthentication() returns INVALID_PARAMETER without calling the policy if the device path and the image are
+/// both null, if the image is null with a non-zero size, or if the device path is malformed, and ACCESS_DENIED if the
+/// image is larger than the maximum size of the instance. A panic in the policy aborts rather than unwinding into the
+/// DXE Foundation.
+#[repr(C)]
+#[derive(Debug)]
+pub struct StaticSecurity2Protocol<T: ImagePolicy> {
+    protocol: Protocol,
+    max_image_size: usize,
+    policy: T,
+}
+
+impl<T: ImagePolicy> StaticSecurity2Protocol<T> {
+    /// Creates the instance implemented by `policy`, accepting images of up to [`DEFAULT_MAX_IMAGE_SIZE`] bytes.
+    pub const fn new(policy: T) -> Self {
+        Self {
+            protocol: Protocol { file_authentication: file_authentication::<T> },
+            max_image_size: DEFAULT_MAX_IMAGE_SIZE,
+            policy,
+        }
+    }
+
+    /// Sets the largest image passed to the policy.
+    pub const fn with_max_image_size(mut self, max_image_size: usize) -> Self {
+        self.max_image_size = max_image_size;
+        self
+    }
+
+    /// Returns the protocol to install.
+    pub fn protocol(&self) -> &Protocol {
+        &self.protocol
+    }
+
+    /// Returns the policy.
+    pub fn policy(&self) -> &T {
+        &self.policy
+    }
+}
+
+extern "efiapi" fn file_authentication<T: ImagePolicy>(
+    this: *const Protocol,
+    device_path: *const DevicePathProtocol,
+    file_buffer: *mut c_void,
+    file_size: usize,
+    boot_policy: efi::Boolean,
+) -> efi::Status {
+    if this.is_null() || (device_path.is_null() && file_buffer.is_null()) || (file_buffer.is_null() && file_size != 0) {
+        return efi::Status::INVALID_PARAMETER;
+    }
+    //Safety: the function is only installed in the protocol of a StaticSecurity2Protocol<T>, which starts with it.
+    let instance = unsafe { &*(this as *const StaticSecurity2Protocol<T>) };
+    if file_size > instance.max_image_size {
+        return efi::Status::ACCESS_DENIED;
+    }
+    let device_path = match device_path.is_null() {
+        true => None,
+        //Safety: device_path is non-null, and the DXE Foundation passes a device path ending with an End node.
+        false => match unsafe { DevicePathWalker::from_ptr(device_path, MAX_DEVICE_PATH_SIZE) } {
+            Ok(device_path) => Some(device_path),
+            Err(_) => return efi::Status::INVALID_PARAMETER,
+        },
+    };
+    let image = match file_buffer.is_null() {
+        true => None,
+        //Safety: file_buffer is non-null and the DXE Foundation passes file_size bytes of image.
+        false => Some(unsafe { slice::from_raw_parts(file_buffer as *const u8, file_size) }),
+    };
+    let guard = AbortOnUnwind("security policy");
+    let verdict = instance.policy.authenticate(device_path.as_ref(), image, boot_policy.into());
+    mem::forget(guard);
+    verdict.into()
+}
+
+/// Errors returned by the FileAuthentication() of an installed instance, as reported by [`Caller`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecurityError {
+    /// The image must not be used (ACCESS_DENIED).
+    Denied,
+    /// The image is placed in the untrusted state, or the user may not connect drivers to the device
+    /// (SECURITY_VIOLATION).
+    Deferred,
+    /// The instance does not support the request (UNSUPPORTED).
+    Unsupported,
+    /// The device path passed to [`Caller`] is malformed; the instance was not called.
+    InvalidDevicePath(DevicePathError),
+    /// Any other status returned by the instance.
+    Other(efi::Status),
+}
+
+impl fmt::Display for SecurityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecurityError::Denied => write!(f, "access denied by security policy"),
+            SecurityError::Deferred => write!(f, "deferred by security policy"),
+            SecurityError::Unsupported => write!(f, "security policy does not support the request"),
+            SecurityError::InvalidDevicePath(error) => write!(f, "invalid device path: {error}"),
+            SecurityError::Other(status) => write!(f, "security policy failed with status {:#x}", status.as_usize()),
+        }
+    }
+}
+
+impl From<efi::Status> for SecurityError {
+    fn from(status: efi::Status) -> Self {
+        match status {
+            efi::Status::ACCESS_DENIED => SecurityError::Denied,
+            efi::Status::SECURITY_VIOLATION => SecurityError::Deferred,
+            efi::Status::UNSUPPORTED => SecurityError::Unsupported,
+            status => SecurityError::Other(status),
+        }
+    }
+}
+
+impl From<SecurityError> for efi::Status {
+    fn from(error: SecurityError) -> Self {
+        match error {
+            SecurityError::Denied => efi::Status::ACCESS_DENIED,
+            SecurityError::Deferred => efi::Status::SECURITY_VIOLATION,
+            SecurityError::Unsupported => efi::Status::UNSUPPORTED,
+            SecurityError::InvalidDevicePath(_) => efi::Status::INVALID_PARAMETER,
+            SecurityError::Other(status) => status,
+        }
+    }
+}
+
+/// Calls the FileAuthentication() of an installed Security2 Architectural Protocol instance.
+///
+/// Device paths are validated before the call, so that the instance only receives well-formed paths.
+#[derive(Debug, Clone, Copy)]
+pub struct Caller<'a> {
+    protocol: &'a Protocol,
+}
+
+impl<'a> Caller<'a> {
+    /// Creates a caller of `protocol`.
+    pub fn new(protocol: &'a Protocol) -> Self {
+        Self { protocol }
+    }
+
+    /// Authenticates `image`, loaded from `device_path` if it was loaded from a device.
+    pub fn authenticate_image(
+        &self,
+        device_path: Option<&[u8]>,
+        image: &[u8],
+        boot_policy: bool,
+    ) -> Result<(), SecurityError> {
+        let device_path = match device_path {
+            Some(device_path) => device_path_ptr(device_path)?,
+            None => ptr::null(),
+        };
+        self.call(device_path, image.as_ptr() as *mut c_void, image.len(), boot_policy)
+    }
+
+    /// Asks whether the current user may connect drivers to the device at `device_path`.
+    pub fn authorize_connect(&self, device_path: &[u8]) -> Result<(), SecurityError> {
+        self.call(device_path_ptr(device_path)?, ptr::null_mut(), 0, false)
+    }
+
+    fn call(
+        &self,
+        device_path: *const DevicePathProtocol,
+        file_buffer: *mut c_void,
+        file_size: usize,
+        boot_policy: bool,
+    ) -> Result<(), SecurityError> {
+        match (self.protocol.file_authentication)(
+            self.protocol,
+            device_path,
+            file_buffer,
+            file_size,
+            boot_policy.into(),
+        ) {
+            efi::Status::SUCCESS => Ok(()),
+            status => Err(status.into()),
+        }
+    }
+}
+
+fn device_path_ptr(device_path: &[u8]) -> Result<*const DevicePathProtocol, SecurityError> {
+    let device_path = DevicePathWalker::from_slice(device_path).map_err(SecurityError::InvalidDevicePath)?;
+    Ok(device_path.as_bytes().as_ptr() as *const DevicePathProtocol)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{cell::RefCell, ffi::c_void, ptr};
+
+    use r_efi::efi;
+
+    use super::{Caller, ImagePolicy, ImageVerdict, Protocol, SecurityError, StaticSecurity2Protocol, PROTOCOL_GUID};
+    use crate::device_path::{DevicePathError, DevicePathProtocol, DevicePathWalker};
+
+    // A file path node for "A" followed by the End node.
+    const PATH: [u8; 12] = [0x04, 0x04, 0x08, 0x00, b'A', 0x00, 0x00, 0x00, 0x7F, 0xFF, 0x04, 0x00];
+    // A file path node for "B" followed by the End node.
+    const LOCKED_PATH: [u8; 12] = [0x04, 0x04, 0x08, 0x00, b'B', 0x00, 0x00, 0x00, 0x7F, 0xFF, 0x04, 0x00];
+
+    // (device path, image, boot policy)
+    type Call = (Option<Vec<u8>>, Option<Vec<u8>>, bool);
+
+    // Allows images starting with "MZ" and connecting to devices other than LOCKED_PATH, defers images starting with
+    // "??", and denies other images. Records the calls it receives.
+    #[derive(Default)]
+    struct Policy {
+        calls: RefCell<Vec<Call>>,
+    }
+
+    impl ImagePolicy for Policy {
+        fn authenticate(
+            &self,
+            device_path: Option<&DevicePathWalker>,
+            image: Option<&[u8]>,
+            boot_policy: bool,
+        ) -> ImageVerdict {
+            let device_path = device_path.map(|device_path| device_path.as_bytes());
+            self.calls.borrow_mut().push((device_path.map(<[u8]>::to_vec), image.map(<[u8]>::to_vec), boot_policy));
+            match image {
+                None if device_path == Some(&LOCKED_PATH) => ImageVerdict::DeferConnect,
+                None => ImageVerdict::Allow,
+                Some([b'M', b'Z', ..]) => ImageVerdict::Allow,
+                Some([b'?', b'?', ..]) => ImageVerdict::Defer,
+                Some(_) => ImageVerdict::Deny,
+            }
+        }
+    }
+
+    fn call(protocol: &Protocol, device_path: &[u8], image: &[u8], boot_policy: bool) -> efi::Status {
+        let device_path = match device_path.is_empty() {
+            true => ptr::null(),
+            false => device_path.as_ptr() as *const DevicePathProtocol,
+        };
+        let file_buffer = match image.is_empty() {
+            true => ptr::null_mut(),
+            false => image.as_ptr() as *mut c_void,
+        };
+        (protocol.file_authentication)(protocol, device_path, file_buffer, image.len(), boot_policy.into())
+    }
+
+    #[test]
+    fn guid_should_match_spec() {
+        assert_eq!(
+            PROTOCOL_GUID.as_bytes(),
+            &[0x58, 0x2f, 0xab, 0x94, 0x38, 0x14, 0xf1, 0x4e, 0x91, 0x52, 0x18, 0x94, 0x1a, 0x3a, 0x0e, 0x68]
+        );
+    }
+
+    #[test]
+    fn verdicts_should_cover_documented_scenarios() {
+        let instance = StaticSecurity2Protocol::new(Policy::default());
+        let protocol = instance.protocol();
+
+        // The image authenticated, with and without a device path.
+        assert_eq!(call(protocol, &PATH, b"MZ image", false), efi::Status::SUCCESS);
+        assert_eq!(call(protocol, &[], b"MZ image", true), efi::Status::SUCCESS);
+        // The user may connect drivers to the device.
+        assert_eq!(call(protocol, &PATH, &[], false), efi::Status::SUCCESS);
+        // The image is untrusted.
+        assert_eq!(call(protocol, &PATH, b"?? image", false), efi::Status::SECURITY_VIOLATION);
+        // The image is rejected.
+        assert_eq!(call(protocol, &PATH, b"bad image", false), efi::Status::ACCESS_DENIED);
+        // The user may not connect drivers to the device.
+        assert_eq!(call(protocol, &LOCKED_PATH, &[], false), efi::Status::SECURITY_VIOLATION);
+
+        let calls = instance.policy().calls.borrow();
+        assert_eq!(calls.len(), 6);
+        assert_eq!(calls[0], (Some(PATH.to_vec()), Some(b"MZ image".to_vec()), false));
+        assert_eq!(calls[1], (None, Some(b"MZ image".to_vec()), true));
+        assert_eq!(calls[2], (Some(PATH.to_vec()), None, false));
+    }
+
+    #[test]
+    fn invalid_arguments_should_not_reach_the_policy() {
+        let instance = StaticSecurity2Protocol::new(Policy::default()).with_max_image_size(8);
+        let protocol = instance.protocol();
+        let file_authentication = protocol.file_authentication;
+        let path = PATH.as_ptr() as *const DevicePathProtocol;
+
+        // Neither a device path nor an image.
+        assert_eq!(call(protocol, &[], &[], false), efi::Status::INVALID_PARAMETER);
+        // A null image with a size.
+        let status = file_authentication(protocol, path, ptr::null_mut(), 4, efi::Boolean::FALSE);
+        assert_eq!(status, efi::Status::INVALID_PARAMETER);
+        // A malformed device path.
+        assert_eq!(call(protocol, &[0x04, 0x04, 0x02, 0x00], b"MZ", false), efi::Status::INVALID_PARAMETER);
+        // An image larger than the maximum, checked before the buffer is read.
+        let status = file_authentication(protocol, path, 0x1000 as *mut c_void, 9, efi::Boolean::FALSE);
+        assert_eq!(status, efi::Status::ACCESS_DENIED);
+        assert_eq!(call(protocol, &PATH, b"MZ image", false), efi::Status::SUCCESS);
+
+        assert_eq!(instance.policy().calls.borrow().len(), 1);
+    }
+
+    std::thread_local! {
+        static RECEIVED: RefCell<Vec<Call>> = RefCell::new(Vec::new());
+        static RESULTS: RefCell<Vec<efi::Status>> = RefCell::new(Vec::new());
+    }
+
+    // Records the arguments it receives, and returns the next status of RESULTS.
+    extern "efiapi" fn fake_file_authentication(
+        _: *const Protocol,
+        device_path: *const DevicePathProtocol,
+        file_buffer: *mut c_void,
+        file_size: usize,
+        boot_policy: efi::Boolean,
+    ) -> efi::Status {
+        let device_path = match device_path.is_null() {
+            true => None,
+            false => Some(unsafe { DevicePathWalker::from_ptr(device_path, usize::MAX) }.unwrap().as_bytes().to_vec()),
+        };
+        let image = match file_buffer.is_null() {
+            true => None,
+            false => Some(unsafe { core::slice::from_raw_parts(file_buffer as *const u8, file_size) }.to_vec()),
+        };
+        RECEIVED.with(|received| received.borrow_mut().push((device_path, image, boot_policy.into())));
+        RESULTS.with(|results| results.borrow_mut().remove(0))
+    }
+
+    #[test]
+    fn caller_should_pass_arguments_and_convert_statuses() {
+        let protocol = Protocol { file_authentication: fake_file_authentication };
+        let caller = Caller::new(&protocol);
+        RESULTS.with(|results| {
+            results.borrow_mut().extend([
+                efi::Status::SUCCESS,
+                efi::Status::SUCCESS,
+                efi::Status::SECURITY_VIOLATION,
+                efi::Status::ACCESS_DENIED,
+                efi::Status::UNSUPPORTED,
+                efi::Status::OUT_OF_RESOURCES,
+            ])
+        });
+
+        // A device path followed by unrelated bytes is passed up to its End node.
+        let mut trailing = PATH.to_vec();
+        trailing.extend_from_slice(&[0xAA; 4]);
+        assert_eq!(caller.authenticate_image(Some(&trailing), b"MZ image", true), Ok(()));
+        assert_eq!(caller.authenticate_image(None, b"MZ image", false), Ok(()));
+        assert_eq!(caller.authorize_connect(&LOCKED_PATH), Err(SecurityError::Deferred));
+        assert_eq!(caller.authenticate_image(Some(&PATH), b"bad", false), Err(SecurityError::Denied));
+        assert_eq!(caller.authorize_connect(&PATH), Err(SecurityError::Unsupported));
+        assert_eq!(
+            caller.authenticate_image(None, b"MZ", false),
+            Err(SecurityError::Other(efi::Status::OUT_OF_RESOURCES))
+        );
+
+        let received = RECEIVED.with(|received| received.take());
+        assert_eq!(received.len(), 6);
+        assert_eq!(received[0], (Some(PATH.to_vec()), Some(b"MZ image".to_vec()), true));
+        assert_eq!(received[1], (None, Some(b"MZ image".to_vec()), false));
+        assert_eq!(received[2], (Some(LOCKED_PATH.to_vec()), None, false));
+    }
+
+    #[test]
+    fn caller_should_not_pass_malformed_device_paths() {
+        let protocol = Protocol { file_authentication: fake_file_authentication };
+        let caller = Caller::new(&protocol);
+
+        let error = caller.authorize_connect(&PATH[..8]).unwrap_err();
+        assert_eq!(error, SecurityError::InvalidDevicePath(DevicePathError::Truncated { offset: 8 }));
+        assert_eq!(efi::Status::from(error), efi::Status::INVALID_PARAMETER);
+        let error = caller.authenticate_image(Some(&[0x04, 0x04, 0x02, 0x00]), b"MZ", false).unwrap_err();
+        assert!(matches!(error, SecurityError::InvalidDevicePath(_)));
+        assert!(RECEIVED.with(|received| received.borrow().is_empty()));
+    }
+}
@@ -0,0 +1,116 @@
+//! Simple Text Input Protocol
+//!
+//! Used to obtain input from the ConIn device. This protocol reports only basic keystrokes; it does not report
+//! modifier keys or toggle state.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/12_Protocols_Console_Support.html#simple-text-input-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use r_efi::efi;
+
+/// Simple Text Input Protocol GUID
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.3.1
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x387477c1, 0x69c7, 0x11d2, 0x8e, 0x39, &[0x00, 0xa0, 0xc9, 0x69, 0x72, 0x3b]);
+
+// EFI scan codes for keys that do not have a Unicode character.
+//
+// UEFI Specification version 2.10, Section 12.3.3, Table 12.5
+pub const SCAN_NULL: u16 = 0x0000;
+pub const SCAN_UP: u16 = 0x0001;
+pub const SCAN_DOWN: u16 = 0x0002;
+pub const SCAN_RIGHT: u16 = 0x0003;
+pub const SCAN_LEFT: u16 = 0x0004;
+pub const SCAN_HOME: u16 = 0x0005;
+pub const SCAN_END: u16 = 0x0006;
+pub const SCAN_INSERT: u16 = 0x0007;
+pub const SCAN_DELETE: u16 = 0x0008;
+pub const SCAN_PAGE_UP: u16 = 0x0009;
+pub const SCAN_PAGE_DOWN: u16 = 0x000a;
+pub const SCAN_F1: u16 = 0x000b;
+pub const SCAN_F2: u16 = 0x000c;
+pub const SCAN_F3: u16 = 0x000d;
+pub const SCAN_F4: u16 = 0x000e;
+pub const SCAN_F5: u16 = 0x000f;
+pub const SCAN_F6: u16 = 0x0010;
+pub const SCAN_F7: u16 = 0x0011;
+pub const SCAN_F8: u16 = 0x0012;
+pub const SCAN_F9: u16 = 0x0013;
+pub const SCAN_F10: u16 = 0x0014;
+pub const SCAN_F11: u16 = 0x0015;
+pub const SCAN_F12: u16 = 0x0016;
+pub const SCAN_ESC: u16 = 0x0017;
+
+/// A keystroke.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.3.3
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct InputKey {
+    /// One of the SCAN_* codes, or SCAN_NULL if the key is described by unicode_char.
+    pub scan_code: u16,
+    /// The UCS-2 character of the key, or 0 if the key is described by scan_code.
+    pub unicode_char: efi::Char16,
+}
+
+/// Resets the input device hardware.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.3.2
+pub type Reset = extern "efiapi" fn(*const Protocol, extended_verification: efi::Boolean) -> efi::Status;
+
+/// Reads the next keystroke from the input device.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.3.3
+pub type ReadKeyStroke = extern "efiapi" fn(*const Protocol, key: *mut InputKey) -> efi::Status;
+
+/// Used to obtain input from the ConIn device.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.3.1
+#[repr(C)]
+#[derive(Debug)]
+pub struct Protocol {
+    pub reset: Reset,
+    pub read_key_stroke: ReadKeyStroke,
+    /// Event to use with WaitForEvent() to wait for a key to be available.
+    pub wait_for_key: efi::Event,
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::{align_of, size_of};
+
+    use r_efi::protocols::simple_text_input;
+
+    use super::{InputKey, Protocol};
+
+    #[test]
+    fn input_key_layout_should_match_spec() {
+        assert_eq!(size_of::<InputKey>(), 4);
+        assert_eq!(align_of::<InputKey>(), 2);
+        assert_eq!(offset_of!(InputKey, scan_code), 0);
+        assert_eq!(offset_of!(InputKey, unicode_char), 2);
+        assert_eq!(size_of::<InputKey>(), size_of::<simple_text_input::InputKey>());
+    }
+
+    #[test]
+    fn protocol_layout_should_match_spec() {
+        let ptr = size_of::<usize>();
+        assert_eq!(size_of::<Protocol>(), 3 * ptr);
+        assert_eq!(offset_of!(Protocol, reset), 0);
+        assert_eq!(offset_of!(Protocol, read_key_stroke), ptr);
+        assert_eq!(offset_of!(Protocol, wait_for_key), 2 * ptr);
+        assert_eq!(size_of::<Protocol>(), size_of::<simple_text_input::Protocol>());
+    }
+}
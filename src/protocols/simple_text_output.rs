@@ -0,0 +1,244 @@
+//! Simple Text Output Protocol
+//!
+//! Controls text-based output devices. This is the protocol used by the ConOut and StdErr consoles in the system
+//! table.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/12_Protocols_Console_Support.html#simple-text-output-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use r_efi::efi;
+
+/// Simple Text Output Protocol GUID
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.4.1
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x387477c2, 0x69c7, 0x11d2, 0x8e, 0x39, &[0x00, 0xa0, 0xc9, 0x69, 0x72, 0x3b]);
+
+// Unicode box drawing characters, required to be supported by all output devices.
+pub const BOXDRAW_HORIZONTAL: u16 = 0x2500;
+pub const BOXDRAW_VERTICAL: u16 = 0x2502;
+pub const BOXDRAW_DOWN_RIGHT: u16 = 0x250c;
+pub const BOXDRAW_DOWN_LEFT: u16 = 0x2510;
+pub const BOXDRAW_UP_RIGHT: u16 = 0x2514;
+pub const BOXDRAW_UP_LEFT: u16 = 0x2518;
+pub const BOXDRAW_VERTICAL_RIGHT: u16 = 0x251c;
+pub const BOXDRAW_VERTICAL_LEFT: u16 = 0x2524;
+pub const BOXDRAW_DOWN_HORIZONTAL: u16 = 0x252c;
+pub const BOXDRAW_UP_HORIZONTAL: u16 = 0x2534;
+pub const BOXDRAW_VERTICAL_HORIZONTAL: u16 = 0x253c;
+pub const BOXDRAW_DOUBLE_HORIZONTAL: u16 = 0x2550;
+pub const BOXDRAW_DOUBLE_VERTICAL: u16 = 0x2551;
+pub const BOXDRAW_DOWN_RIGHT_DOUBLE: u16 = 0x2552;
+pub const BOXDRAW_DOWN_DOUBLE_RIGHT: u16 = 0x2553;
+pub const BOXDRAW_DOUBLE_DOWN_RIGHT: u16 = 0x2554;
+pub const BOXDRAW_DOWN_LEFT_DOUBLE: u16 = 0x2555;
+pub const BOXDRAW_DOWN_DOUBLE_LEFT: u16 = 0x2556;
+pub const BOXDRAW_DOUBLE_DOWN_LEFT: u16 = 0x2557;
+pub const BOXDRAW_UP_RIGHT_DOUBLE: u16 = 0x2558;
+pub const BOXDRAW_UP_DOUBLE_RIGHT: u16 = 0x2559;
+pub const BOXDRAW_DOUBLE_UP_RIGHT: u16 = 0x255a;
+pub const BOXDRAW_UP_LEFT_DOUBLE: u16 = 0x255b;
+pub const BOXDRAW_UP_DOUBLE_LEFT: u16 = 0x255c;
+pub const BOXDRAW_DOUBLE_UP_LEFT: u16 = 0x255d;
+pub const BOXDRAW_VERTICAL_RIGHT_DOUBLE: u16 = 0x255e;
+pub const BOXDRAW_VERTICAL_DOUBLE_RIGHT: u16 = 0x255f;
+pub const BOXDRAW_DOUBLE_VERTICAL_RIGHT: u16 = 0x2560;
+pub const BOXDRAW_VERTICAL_LEFT_DOUBLE: u16 = 0x2561;
+pub const BOXDRAW_VERTICAL_DOUBLE_LEFT: u16 = 0x2562;
+pub const BOXDRAW_DOUBLE_VERTICAL_LEFT: u16 = 0x2563;
+pub const BOXDRAW_DOWN_HORIZONTAL_DOUBLE: u16 = 0x2564;
+pub const BOXDRAW_DOWN_DOUBLE_HORIZONTAL: u16 = 0x2565;
+pub const BOXDRAW_DOUBLE_DOWN_HORIZONTAL: u16 = 0x2566;
+pub const BOXDRAW_UP_HORIZONTAL_DOUBLE: u16 = 0x2567;
+pub const BOXDRAW_UP_DOUBLE_HORIZONTAL: u16 = 0x2568;
+pub const BOXDRAW_DOUBLE_UP_HORIZONTAL: u16 = 0x2569;
+pub const BOXDRAW_VERTICAL_HORIZONTAL_DOUBLE: u16 = 0x256a;
+pub const BOXDRAW_VERTICAL_DOUBLE_HORIZONTAL: u16 = 0x256b;
+pub const BOXDRAW_DOUBLE_VERTICAL_HORIZONTAL: u16 = 0x256c;
+
+// Unicode block elements.
+pub const BLOCKELEMENT_FULL_BLOCK: u16 = 0x2588;
+pub const BLOCKELEMENT_LIGHT_SHADE: u16 = 0x2591;
+
+// Unicode geometric shapes.
+pub const GEOMETRICSHAPE_UP_TRIANGLE: u16 = 0x25b2;
+pub const GEOMETRICSHAPE_RIGHT_TRIANGLE: u16 = 0x25ba;
+pub const GEOMETRICSHAPE_DOWN_TRIANGLE: u16 = 0x25bc;
+pub const GEOMETRICSHAPE_LEFT_TRIANGLE: u16 = 0x25c4;
+
+// Unicode arrows.
+pub const ARROW_LEFT: u16 = 0x2190;
+pub const ARROW_UP: u16 = 0x2191;
+pub const ARROW_RIGHT: u16 = 0x2192;
+pub const ARROW_DOWN: u16 = 0x2193;
+
+// Foreground colors for SetAttribute().
+pub const BLACK: usize = 0x00;
+pub const BLUE: usize = 0x01;
+pub const GREEN: usize = 0x02;
+pub const CYAN: usize = 0x03;
+pub const RED: usize = 0x04;
+pub const MAGENTA: usize = 0x05;
+pub const BROWN: usize = 0x06;
+pub const LIGHTGRAY: usize = 0x07;
+pub const BRIGHT: usize = 0x08;
+pub const DARKGRAY: usize = 0x08;
+pub const LIGHTBLUE: usize = 0x09;
+pub const LIGHTGREEN: usize = 0x0a;
+pub const LIGHTCYAN: usize = 0x0b;
+pub const LIGHTRED: usize = 0x0c;
+pub const LIGHTMAGENTA: usize = 0x0d;
+pub const YELLOW: usize = 0x0e;
+pub const WHITE: usize = 0x0f;
+
+// Background colors for SetAttribute().
+pub const BACKGROUND_BLACK: usize = 0x00;
+pub const BACKGROUND_BLUE: usize = 0x10;
+pub const BACKGROUND_GREEN: usize = 0x20;
+pub const BACKGROUND_CYAN: usize = 0x30;
+pub const BACKGROUND_RED: usize = 0x40;
+pub const BACKGROUND_MAGENTA: usize = 0x50;
+pub const BACKGROUND_BROWN: usize = 0x60;
+pub const BACKGROUND_LIGHTGRAY: usize = 0x70;
+
+/// Mode information of an output device.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.4.1
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimpleTextOutputMode {
+    /// The number of modes supported by QueryMode() and SetMode().
+    pub max_mode: i32,
+    /// The text mode of the output device.
+    pub mode: i32,
+    /// The current character output attribute.
+    pub attribute: i32,
+    /// The cursor's column.
+    pub cursor_column: i32,
+    /// The cursor's row.
+    pub cursor_row: i32,
+    /// The cursor is currently visible or not.
+    pub cursor_visible: efi::Boolean,
+}
+
+/// Resets the text output device hardware.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.4.2
+pub type Reset = extern "efiapi" fn(*const Protocol, extended_verification: efi::Boolean) -> efi::Status;
+
+/// Writes a null-terminated string to the output device.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.4.3
+pub type OutputString = extern "efiapi" fn(*const Protocol, string: *const efi::Char16) -> efi::Status;
+
+/// Verifies that all characters in a null-terminated string can be output to the target device.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.4.4
+pub type TestString = extern "efiapi" fn(*const Protocol, string: *const efi::Char16) -> efi::Status;
+
+/// Returns information for an available text mode that the output device(s) supports.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.4.5
+pub type QueryMode =
+    extern "efiapi" fn(*const Protocol, mode_number: usize, columns: *mut usize, rows: *mut usize) -> efi::Status;
+
+/// Sets the output device(s) to a specified mode.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.4.6
+pub type SetMode = extern "efiapi" fn(*const Protocol, mode_number: usize) -> efi::Status;
+
+/// Sets the background and foreground colors for the OutputString() and ClearScreen() functions.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.4.7
+pub type SetAttribute = extern "efiapi" fn(*const Protocol, attribute: usize) -> efi::Status;
+
+/// Clears the output device(s) display to the currently selected background color.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.4.8
+pub type ClearScreen = extern "efiapi" fn(*const Protocol) -> efi::Status;
+
+/// Sets the current coordinates of the cursor position.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.4.9
+pub type SetCursorPosition = extern "efiapi" fn(*const Protocol, column: usize, row: usize) -> efi::Status;
+
+/// Makes the cursor visible or invisible.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.4.10
+pub type EnableCursor = extern "efiapi" fn(*const Protocol, visible: efi::Boolean) -> efi::Status;
+
+/// Controls text-based output devices.
+///
+/// # Documentation
+/// UEFI Specification version 2.10, Section 12.4.1
+#[repr(C)]
+#[derive(Debug)]
+pub struct Protocol {
+    pub reset: Reset,
+    pub output_string: OutputString,
+    pub test_string: TestString,
+    pub query_mode: QueryMode,
+    pub set_mode: SetMode,
+    pub set_attribute: SetAttribute,
+    pub clear_screen: ClearScreen,
+    pub set_cursor_position: SetCursorPosition,
+    pub enable_cursor: EnableCursor,
+    /// Pointer to the current mode information of the device.
+    pub mode: *mut SimpleTextOutputMode,
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::{align_of, size_of};
+
+    use r_efi::protocols::simple_text_output;
+
+    use super::{Protocol, SimpleTextOutputMode};
+
+    #[test]
+    fn mode_layout_should_match_spec() {
+        assert_eq!(size_of::<SimpleTextOutputMode>(), 24);
+        assert_eq!(align_of::<SimpleTextOutputMode>(), 4);
+        assert_eq!(offset_of!(SimpleTextOutputMode, max_mode), 0);
+        assert_eq!(offset_of!(SimpleTextOutputMode, mode), 4);
+        assert_eq!(offset_of!(SimpleTextOutputMode, attribute), 8);
+        assert_eq!(offset_of!(SimpleTextOutputMode, cursor_column), 12);
+        assert_eq!(offset_of!(SimpleTextOutputMode, cursor_row), 16);
+        assert_eq!(offset_of!(SimpleTextOutputMode, cursor_visible), 20);
+        assert_eq!(size_of::<SimpleTextOutputMode>(), size_of::<simple_text_output::Mode>());
+    }
+
+    #[test]
+    fn protocol_layout_should_match_spec() {
+        let ptr = size_of::<usize>();
+        assert_eq!(size_of::<Protocol>(), 10 * ptr);
+        assert_eq!(offset_of!(Protocol, reset), 0);
+        assert_eq!(offset_of!(Protocol, output_string), ptr);
+        assert_eq!(offset_of!(Protocol, test_string), 2 * ptr);
+        assert_eq!(offset_of!(Protocol, query_mode), 3 * ptr);
+        assert_eq!(offset_of!(Protocol, set_mode), 4 * ptr);
+        assert_eq!(offset_of!(Protocol, set_attribute), 5 * ptr);
+        assert_eq!(offset_of!(Protocol, clear_screen), 6 * ptr);
+        assert_eq!(offset_of!(Protocol, set_cursor_position), 7 * ptr);
+        assert_eq!(offset_of!(Protocol, enable_cursor), 8 * ptr);
+        assert_eq!(offset_of!(Protocol, mode), 9 * ptr);
+        assert_eq!(size_of::<Protocol>(), size_of::<simple_text_output::Protocol>());
+    }
+}
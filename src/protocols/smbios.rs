@@ -0,0 +1,310 @@
+//! SMBIOS Protocol
+//!
+//! Allows consumers to add, update, remove, and enumerate the records of the SMBIOS table, and provides a builder
+//! that serializes a record in the SMBIOS binary format.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V5_SMBIOS_Protocol.html#efi-smbios-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+extern crate alloc;
+use alloc::vec::Vec;
+use core::{mem::size_of, slice};
+
+use r_efi::efi;
+
+/// SMBIOS Protocol GUID
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section V-6.2
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x03583ff6, 0xcb36, 0x4940, 0x94, 0x7e, &[0xb9, 0xb3, 0x9f, 0x4a, 0xfa, 0xf7]);
+
+/// Handle of an SMBIOS record (EFI_SMBIOS_HANDLE).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section V-6.2
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SmbiosHandle(pub u16);
+
+impl SmbiosHandle {
+    /// Passed to Add() to have a unique handle assigned, and to GetNext() to start the enumeration.
+    pub const PI_RESERVED: Self = Self(0xFFFE);
+    /// Handles from this value upwards are reserved by the SMBIOS specification.
+    pub const RESERVED_BEGIN: Self = Self(0xFF00);
+}
+
+/// The header of every SMBIOS record (EFI_SMBIOS_TABLE_HEADER).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section V-6.2
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmbiosRecordHeader {
+    pub record_type: u8,
+    /// Length of the formatted area of the record, including this header but not the string table.
+    pub length: u8,
+    pub handle: SmbiosHandle,
+}
+
+/// Adds an SMBIOS record.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section V-6.2.1
+pub type Add = extern "efiapi" fn(
+    *const Protocol,
+    producer_handle: efi::Handle,
+    smbios_handle: *mut SmbiosHandle,
+    record: *mut SmbiosRecordHeader,
+) -> efi::Status;
+
+/// Updates a string of an SMBIOS record.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section V-6.2.2
+pub type UpdateString = extern "efiapi" fn(
+    *const Protocol,
+    smbios_handle: *mut SmbiosHandle,
+    string_number: *mut usize,
+    string: *mut u8,
+) -> efi::Status;
+
+/// Removes an SMBIOS record.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section V-6.2.3
+pub type Remove = extern "efiapi" fn(*const Protocol, smbios_handle: SmbiosHandle) -> efi::Status;
+
+/// Returns the next SMBIOS record, optionally of a given type.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section V-6.2.4
+pub type GetNext = extern "efiapi" fn(
+    *const Protocol,
+    smbios_handle: *mut SmbiosHandle,
+    record_type: *mut u8,
+    record: *mut *mut SmbiosRecordHeader,
+    producer_handle: *mut efi::Handle,
+) -> efi::Status;
+
+/// Allows consumers to log SMBIOS data records and enables the producer to create the SMBIOS tables for a platform.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section V-6.2
+#[repr(C)]
+#[derive(Debug)]
+pub struct Protocol {
+    pub add: Add,
+    pub update_string: UpdateString,
+    pub remove: Remove,
+    pub get_next: GetNext,
+    /// Major version of the SMBIOS specification supported.
+    pub major_version: u8,
+    /// Minor version of the SMBIOS specification supported.
+    pub minor_version: u8,
+}
+
+/// Marker for the structures [`SmbiosBuilder`] copies into a record byte for byte as its formatted area.
+///
+/// # Safety
+/// The type must have no padding or other uninitialized bytes, e.g. be a `#[repr(C, packed)]` structure of integers
+/// and byte arrays.
+pub unsafe trait FormattedArea: Copy {}
+
+//Safety: none of these types have padding.
+unsafe impl FormattedArea for () {}
+unsafe impl FormattedArea for u8 {}
+unsafe impl<const N: usize> FormattedArea for [u8; N] {}
+
+/// Serializes an SMBIOS record: the header, the formatted area `T` that follows it, and the string table.
+///
+/// `T` is laid out as the formatted area of the record type after the 4-byte header. String fields of `T` hold the
+/// numbers returned by [`Self::add_string`].
+///
+/// ## Example
+///```
+/// use mu_pi::protocols::smbios::{FormattedArea, SmbiosBuilder, SmbiosHandle};
+///
+/// #[repr(C, packed)]
+/// #[derive(Clone, Copy)]
+/// struct OemStrings {
+///   count: u8,
+/// }
+///
+/// //Safety: OemStrings is packed, so has no padding.
+/// unsafe impl FormattedArea for OemStrings {}
+///
+/// let mut builder = SmbiosBuilder::new(11, OemStrings { count: 1 });
+/// assert_eq!(builder.add_string("Hello").unwrap(), 1);
+/// let record = builder.build().unwrap();
+/// assert_eq!(record, [11, 5, 0xFE, 0xFF, 1, b'H', b'e', b'l', b'l', b'o', 0, 0]);
+///```
+#[derive(Debug, Clone)]
+pub struct SmbiosBuilder<T: Sized> {
+    record_type: u8,
+    handle: SmbiosHandle,
+    formatted: T,
+    strings: Vec<u8>,
+    string_count: u8,
+}
+
+impl<T: FormattedArea> SmbiosBuilder<T> {
+    /// Instantiates a new SmbiosBuilder for a record of `record_type` with the given formatted area. The handle is
+    /// [`SmbiosHandle::PI_RESERVED`], which has Add() assign one.
+    pub fn new(record_type: u8, formatted: T) -> Self {
+        Self { record_type, handle: SmbiosHandle::PI_RESERVED, formatted, strings: Vec::new(), string_count: 0 }
+    }
+
+    /// Sets the handle written to the record header.
+    pub fn with_handle(mut self, handle: SmbiosHandle) -> Self {
+        self.handle = handle;
+        self
+    }
+
+    /// Returns the formatted area, to fill in the string numbers after the strings are added.
+    pub fn formatted_mut(&mut self) -> &mut T {
+        &mut self.formatted
+    }
+
+    /// Appends `string` to the string table and returns its string number.
+    ///
+    /// Returns `INVALID_PARAMETER` if the string is empty or contains a null character (string number 0 denotes an
+    /// absent string, so empty strings cannot be stored), and `OUT_OF_RESOURCES` if the table already has 255 strings.
+    pub fn add_string(&mut self, string: &str) -> Result<u8, efi::Status> {
+        if string.is_empty() || string.contains('\0') {
+            Err(efi::Status::INVALID_PARAMETER)?;
+        }
+        if self.string_count == u8::MAX {
+            Err(efi::Status::OUT_OF_RESOURCES)?;
+        }
+        self.strings.extend_from_slice(string.as_bytes());
+        self.strings.push(0);
+        self.string_count += 1;
+        Ok(self.string_count)
+    }
+
+    /// Serializes the record. The string table is terminated by an additional null, so a record without strings
+    /// ends with two nulls.
+    ///
+    /// Returns `INVALID_PARAMETER` if the header and formatted area are longer than 255 bytes.
+    pub fn build(&self) -> Result<Vec<u8>, efi::Status> {
+        let length = u8::try_from(size_of::<SmbiosRecordHeader>() + size_of::<T>())
+            .map_err(|_| efi::Status::INVALID_PARAMETER)?;
+        let header = SmbiosRecordHeader { record_type: self.record_type, length, handle: self.handle };
+        //Safety: both structures are plain data without padding, which FormattedArea requires of T.
+        let (header, formatted) = unsafe {
+            (
+                slice::from_raw_parts(&header as *const _ as *const u8, size_of::<SmbiosRecordHeader>()),
+                slice::from_raw_parts(&self.formatted as *const T as *const u8, size_of::<T>()),
+            )
+        };
+        let mut record = Vec::with_capacity(length as usize + self.strings.len() + 2);
+        record.extend_from_slice(header);
+        record.extend_from_slice(formatted);
+        record.extend_from_slice(&self.strings);
+        if self.strings.is_empty() {
+            record.push(0);
+        }
+        record.push(0);
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::size_of;
+
+    use r_efi::efi;
+
+    use super::{FormattedArea, Protocol, SmbiosBuilder, SmbiosHandle, SmbiosRecordHeader, PROTOCOL_GUID};
+
+    // Formatted area of a Type 0 (BIOS Information) record, SMBIOS 2.4 layout.
+    #[repr(C, packed)]
+    #[derive(Clone, Copy)]
+    struct BiosInformation {
+        vendor: u8,
+        bios_version: u8,
+        bios_segment: u16,
+        bios_release_date: u8,
+        bios_size: u8,
+        bios_characteristics: u64,
+        bios_characteristics_extension: [u8; 2],
+        system_bios_major_release: u8,
+        system_bios_minor_release: u8,
+        ec_firmware_major_release: u8,
+        ec_firmware_minor_release: u8,
+    }
+
+    unsafe impl FormattedArea for BiosInformation {}
+
+    #[test]
+    fn guid_should_match_spec() {
+        assert_eq!(
+            PROTOCOL_GUID.as_bytes(),
+            &[0xf6, 0x3f, 0x58, 0x03, 0x36, 0xcb, 0x40, 0x49, 0x94, 0x7e, 0xb9, 0xb3, 0x9f, 0x4a, 0xfa, 0xf7]
+        );
+        assert_eq!(size_of::<SmbiosRecordHeader>(), 4);
+        assert_eq!(size_of::<Protocol>(), size_of::<[usize; 5]>());
+    }
+
+    #[test]
+    fn builder_should_serialize_bios_information() {
+        let mut builder = SmbiosBuilder::new(
+            0,
+            BiosInformation {
+                vendor: 0,
+                bios_version: 0,
+                bios_segment: 0xE800,
+                bios_release_date: 0,
+                bios_size: 0xFF,
+                bios_characteristics: 0x08,
+                bios_characteristics_extension: [0x03, 0x0C],
+                system_bios_major_release: 1,
+                system_bios_minor_release: 2,
+                ec_firmware_major_release: 0xFF,
+                ec_firmware_minor_release: 0xFF,
+            },
+        )
+        .with_handle(SmbiosHandle(0x0001));
+        let vendor = builder.add_string("Project Mu").unwrap();
+        let version = builder.add_string("1.2").unwrap();
+        let date = builder.add_string("10/15/2026").unwrap();
+        let formatted = builder.formatted_mut();
+        formatted.vendor = vendor;
+        formatted.bios_version = version;
+        formatted.bios_release_date = date;
+
+        let record = builder.build().unwrap();
+        let mut expected = vec![0x00, 0x18, 0x01, 0x00, 1, 2, 0x00, 0xE8, 3, 0xFF];
+        expected.extend_from_slice(&[0x08, 0, 0, 0, 0, 0, 0, 0, 0x03, 0x0C, 1, 2, 0xFF, 0xFF]);
+        expected.extend_from_slice(b"Project Mu\x001.2\x0010/15/2026\x00\x00");
+        assert_eq!(record, expected);
+        assert_eq!(record[1] as usize, 4 + size_of::<BiosInformation>());
+    }
+
+    #[test]
+    fn builder_should_terminate_records_without_strings() {
+        let record = SmbiosBuilder::new(127, ()).build().unwrap();
+        assert_eq!(record, [127, 4, 0xFE, 0xFF, 0, 0]);
+    }
+
+    #[test]
+    fn builder_should_reject_invalid_input() {
+        let mut builder = SmbiosBuilder::new(1, 0u8);
+        assert_eq!(builder.add_string(""), Err(efi::Status::INVALID_PARAMETER));
+        assert_eq!(builder.add_string("a\0b"), Err(efi::Status::INVALID_PARAMETER));
+        for number in 1..=255 {
+            assert_eq!(builder.add_string("x"), Ok(number));
+        }
+        assert_eq!(builder.add_string("x"), Err(efi::Status::OUT_OF_RESOURCES));
+
+        assert_eq!(SmbiosBuilder::new(1, [0u8; 252]).build(), Err(efi::Status::INVALID_PARAMETER));
+        assert_eq!(SmbiosBuilder::new(1, [0u8; 251]).build().unwrap()[1], 255);
+    }
+}
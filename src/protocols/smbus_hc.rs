@@ -0,0 +1,178 @@
+//! SMBus Host Controller Protocol
+//!
+//! Used to execute SMBus commands on the devices of an SMBus host controller, and to assign addresses to them with
+//! the SMBus Address Resolution Protocol (ARP).
+//!
+//! See <https://github.com/tianocore/edk2/blob/master/MdePkg/Include/Protocol/SmbusHc.h>
+//! and <https://github.com/tianocore/edk2/blob/master/MdePkg/Include/IndustryStandard/SmBus.h>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::ffi::c_void;
+
+use r_efi::efi;
+
+/// SMBus Host Controller Protocol GUID
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0xe49d33ed, 0x513d, 0x4634, 0xb6, 0x98, &[0x6f, 0x55, 0xaa, 0x75, 0x1c, 0x1b]);
+
+/// Largest number of data bytes of an SMBus block transfer.
+pub const MAX_BLOCK_LENGTH: usize = 32;
+
+/// The 7-bit address of a device on the SMBus (EFI_SMBUS_DEVICE_ADDRESS). Only bits 6:0 are used.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, SMBus Host Controller Code Definitions
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SmbusDeviceAddress(pub usize);
+
+/// The command sent to a device (EFI_SMBUS_DEVICE_COMMAND). Only bits 7:0 are used.
+pub type SmbusDeviceCommand = usize;
+
+/// The SMBus operations a host controller can execute (EFI_SMBUS_OPERATION).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, SMBus Host Controller Code Definitions
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SmbusOperation {
+    QuickRead,
+    QuickWrite,
+    ReceiveByte,
+    SendByte,
+    ReadByte,
+    WriteByte,
+    ReadWord,
+    WriteWord,
+    ReadBlock,
+    WriteBlock,
+    ProcessCall,
+    BWBRProcessCall,
+}
+
+impl TryFrom<u32> for SmbusOperation {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => Self::QuickRead,
+            1 => Self::QuickWrite,
+            2 => Self::ReceiveByte,
+            3 => Self::SendByte,
+            4 => Self::ReadByte,
+            5 => Self::WriteByte,
+            6 => Self::ReadWord,
+            7 => Self::WriteWord,
+            8 => Self::ReadBlock,
+            9 => Self::WriteBlock,
+            10 => Self::ProcessCall,
+            11 => Self::BWBRProcessCall,
+            _ => Err(value)?,
+        })
+    }
+}
+
+/// The Unique Device Identifier of an SMBus device (EFI_SMBUS_UDID).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmbusUdid {
+    pub vendor_specific_id: u32,
+    pub device_id: u16,
+    pub vendor_id: u16,
+}
+
+/// An address assigned to a device by ARP (EFI_SMBUS_DEVICE_MAP).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmbusDeviceMap {
+    pub smbus_device_address: SmbusDeviceAddress,
+    pub smbus_device_udid: SmbusUdid,
+}
+
+/// Executes an SMBus operation on the device at `slave_address`.
+/// * this - The EFI_SMBUS_HC_PROTOCOL instance.
+/// * slave_address - The address of the device.
+/// * command - The command sent to the device, ignored by the quick, send byte and receive byte operations.
+/// * operation - The operation to execute.
+/// * pec_check - Whether Packet Error Code (PEC) checking is required.
+/// * length - On input, the number of bytes of `buffer`. On output, the number of bytes read or written.
+/// * buffer - The data written to or read from the device.
+/// * @retval - EFI_SUCCESS: The operation completed successfully.
+/// * @retval - EFI_DEVICE_ERROR: The device did not respond or reported an error.
+/// * @retval - EFI_TIMEOUT: The operation did not complete in time.
+/// * @retval - EFI_CRC_ERROR: The PEC checksum of the response was incorrect.
+/// * @retval - EFI_INVALID_PARAMETER: `operation` is not valid, or `length` is not valid for it.
+/// * @retval - EFI_UNSUPPORTED: The operation is not supported by the host controller or device.
+/// * @retval - EFI_BUFFER_TOO_SMALL: `buffer` is too small for the operation.
+pub type EfiSmbusHcExecute = extern "efiapi" fn(
+    this: *const Protocol,
+    slave_address: SmbusDeviceAddress,
+    command: SmbusDeviceCommand,
+    operation: SmbusOperation,
+    pec_check: efi::Boolean,
+    length: *mut usize,
+    buffer: *mut c_void,
+) -> efi::Status;
+
+/// Assigns addresses to the devices on the SMBus with ARP.
+/// * this - The EFI_SMBUS_HC_PROTOCOL instance.
+/// * arp_all - Whether to assign addresses to all devices, or only to the device identified by `smbus_udid`.
+/// * smbus_udid - The device to assign an address to, if `arp_all` is false.
+/// * slave_address - The address to assign, if `arp_all` is false.
+/// * @retval - EFI_SUCCESS: The addresses were assigned.
+/// * @retval - EFI_INVALID_PARAMETER: `smbus_udid` or `slave_address` is null when `arp_all` is false.
+/// * @retval - EFI_OUT_OF_RESOURCES: No more addresses are available.
+/// * @retval - EFI_UNSUPPORTED: ARP is not supported.
+pub type EfiSmbusHcProtocolArpDevice = extern "efiapi" fn(
+    this: *const Protocol,
+    arp_all: efi::Boolean,
+    smbus_udid: *mut SmbusUdid,
+    slave_address: *mut SmbusDeviceAddress,
+) -> efi::Status;
+
+/// Returns the addresses assigned by ARP.
+/// * this - The EFI_SMBUS_HC_PROTOCOL instance.
+/// * length - On output, the size in bytes of the device map.
+/// * smbus_device_map - On output, the device map.
+/// * @retval - EFI_SUCCESS: The device map was returned.
+pub type EfiSmbusHcProtocolGetArpMap = extern "efiapi" fn(
+    this: *const Protocol,
+    length: *mut usize,
+    smbus_device_map: *mut *mut SmbusDeviceMap,
+) -> efi::Status;
+
+/// Called when a device reports `data` with a host notify.
+pub type EfiSmbusNotifyFunction = extern "efiapi" fn(slave_address: SmbusDeviceAddress, data: usize) -> efi::Status;
+
+/// Registers `notify_function` to be called when the device at `slave_address` reports `data` with a host notify.
+/// * this - The EFI_SMBUS_HC_PROTOCOL instance.
+/// * slave_address - The address of the device.
+/// * data - The data reported by the device.
+/// * notify_function - The function to call.
+/// * @retval - EFI_SUCCESS: The function was registered.
+/// * @retval - EFI_UNSUPPORTED: Host notify is not supported.
+pub type EfiSmbusHcProtocolNotify = extern "efiapi" fn(
+    this: *const Protocol,
+    slave_address: SmbusDeviceAddress,
+    data: usize,
+    notify_function: EfiSmbusNotifyFunction,
+) -> efi::Status;
+
+/// Provides SMBus host controller services.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Volume 5, SMBus Host Controller Code Definitions
+#[repr(C)]
+#[derive(Debug)]
+pub struct Protocol {
+    pub execute: EfiSmbusHcExecute,
+    pub arp_device: EfiSmbusHcProtocolArpDevice,
+    pub get_arp_map: EfiSmbusHcProtocolGetArpMap,
+    pub notify: EfiSmbusHcProtocolNotify,
+}
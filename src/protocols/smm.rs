@@ -0,0 +1,39 @@
+//! System Management Mode (SMM) Protocols
+//!
+//! Protocols used by drivers executing in System Management Mode (SMM), which the PI Specification also calls
+//! Management Mode (MM).
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V4_Overview.html>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::ffi::c_void;
+
+use r_efi::efi;
+
+pub mod access2;
+pub mod cpu_io2;
+pub mod sw_dispatch2;
+
+/// Handler invoked by a dispatcher, or by MmiManage() (EFI_MM_HANDLER_ENTRY_POINT).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-3.1
+///
+/// * dispatch_handle - handle returned when the handler was registered
+/// * context - points to the dispatch context of this SMI, e.g. [`sw_dispatch2::SmmSwContext`]
+/// * comm_buffer - buffer passed from a non-SMM environment, if any
+/// * comm_buffer_size - size of the buffer at comm_buffer
+///
+/// * @retval - SUCCESS: the interrupt was handled and quiesced; no other handlers should run
+pub type MmHandlerEntryPoint = extern "efiapi" fn(
+    dispatch_handle: efi::Handle,
+    context: *const c_void,
+    comm_buffer: *mut c_void,
+    comm_buffer_size: *mut usize,
+) -> efi::Status;
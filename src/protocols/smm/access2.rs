@@ -0,0 +1,95 @@
+//! SMM Access 2 Protocol
+//!
+//! Controls the visibility of SMRAM outside of SMM. SMRAM is opened so that the SMM IPL can load the SMM core into
+//! it, then closed, and locked before any code not part of the platform firmware runs so that it cannot be opened
+//! again until the next reset.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V4_SMM_Access_Protocol.html>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use r_efi::efi;
+
+/// SMM Access 2 Protocol GUID
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-5.3
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0xc2702b74, 0x800c, 0x4131, 0x87, 0x46, &[0x8f, 0xb5, 0xb8, 0x9c, 0xe4, 0xac]);
+
+/// The region is visible outside of SMM.
+pub const EFI_SMRAM_OPEN: u64 = 0x0000_0001;
+/// The region is only visible in SMM.
+pub const EFI_SMRAM_CLOSED: u64 = 0x0000_0002;
+/// The region cannot be opened until the next reset.
+pub const EFI_SMRAM_LOCKED: u64 = 0x0000_0004;
+pub const EFI_CACHEABLE: u64 = 0x0000_0008;
+/// The region is in use, e.g. by the SMM core, and is not available for allocation.
+pub const EFI_ALLOCATED: u64 = 0x0000_0010;
+pub const EFI_NEEDS_TESTING: u64 = 0x0000_0020;
+pub const EFI_NEEDS_ECC_INITIALIZATION: u64 = 0x0000_0040;
+
+/// A region of SMRAM (EFI_SMRAM_DESCRIPTOR).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-5.3
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmramDescriptor {
+    /// Address of the region as seen by other agents, such as DMA.
+    pub physical_start: efi::PhysicalAddress,
+    /// Address of the region as seen by the processor in SMM.
+    pub cpu_start: efi::PhysicalAddress,
+    pub physical_size: u64,
+    /// The `EFI_SMRAM_*` state and capability bits of the region.
+    pub region_state: u64,
+}
+
+/// Opens, closes, or locks SMRAM.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-5.3
+///
+/// * @retval - SUCCESS: the operation was successful
+/// * @retval - UNSUPPORTED: the operation is not supported by the platform
+/// * @retval - DEVICE_ERROR: SMRAM cannot be changed, e.g. it is locked
+pub type EfiSmmAccess = extern "efiapi" fn(this: *const Protocol) -> efi::Status;
+
+/// Returns the SMRAM regions.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-5.3
+///
+/// * this - pointer to the protocol
+/// * smram_map_size - size of the buffer at smram_map on input, size of the map on output
+/// * smram_map - receives one descriptor for each region
+///
+/// * @retval - SUCCESS: the map was returned
+/// * @retval - BUFFER_TOO_SMALL: smram_map_size was updated with the size the map needs
+pub type EfiSmmCapabilities = extern "efiapi" fn(
+    this: *const Protocol,
+    smram_map_size: *mut usize,
+    smram_map: *mut SmramDescriptor,
+) -> efi::Status;
+
+/// Controls access to SMRAM.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-5.3
+#[repr(C)]
+#[derive(Debug)]
+pub struct Protocol {
+    pub open: EfiSmmAccess,
+    pub close: EfiSmmAccess,
+    pub lock: EfiSmmAccess,
+    pub get_capabilities: EfiSmmCapabilities,
+    /// Whether SMRAM is locked.
+    pub lock_state: efi::Boolean,
+    /// Whether SMRAM is open.
+    pub open_state: efi::Boolean,
+}
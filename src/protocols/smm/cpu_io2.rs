@@ -0,0 +1,303 @@
+//! SMM CPU I/O 2 Protocol
+//!
+//! Provides the basic memory and I/O interfaces used by drivers executing in System Management Mode (SMM). The
+//! protocol is also available as the `SmmIo` member of the SMM System Table.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V4_MM_System_Table.html#efi-mm-cpu-io-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::ffi::c_void;
+
+use r_efi::efi;
+
+/// SMM CPU I/O 2 Protocol GUID
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-3.2
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x3242a9d8, 0xce70, 0x4aa0, 0x95, 0x5d, &[0x5e, 0x7b, 0x14, 0x0d, 0xe4, 0xd2]);
+
+/// Width of each memory or I/O access (EFI_SMM_IO_WIDTH).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-3.2
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmmIoWidthType {
+    Uint8 = 0,
+    Uint16 = 1,
+    Uint32 = 2,
+    Uint64 = 3,
+}
+
+/// Reads or writes `count` elements of the given width at `address` to or from `buffer`.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-3.2.1
+pub type SmmCpuIo2 = extern "efiapi" fn(
+    *const Protocol,
+    width: SmmIoWidthType,
+    address: u64,
+    count: usize,
+    buffer: *mut c_void,
+) -> efi::Status;
+
+/// The read and write services of one address space (EFI_SMM_IO_ACCESS2).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-3.2
+#[repr(C)]
+#[derive(Debug)]
+pub struct SmmIoAccess {
+    pub read: SmmCpuIo2,
+    pub write: SmmCpuIo2,
+}
+
+/// Provides CPU memory and I/O access in SMM.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-3.2
+#[repr(C)]
+#[derive(Debug)]
+pub struct Protocol {
+    /// Memory space accesses.
+    pub mem: SmmIoAccess,
+    /// I/O space accesses.
+    pub io: SmmIoAccess,
+}
+
+// Values that can be transferred by a single access.
+trait IoValue: Copy + Default {
+    const WIDTH: SmmIoWidthType;
+}
+
+impl IoValue for u8 {
+    const WIDTH: SmmIoWidthType = SmmIoWidthType::Uint8;
+}
+
+impl IoValue for u16 {
+    const WIDTH: SmmIoWidthType = SmmIoWidthType::Uint16;
+}
+
+impl IoValue for u32 {
+    const WIDTH: SmmIoWidthType = SmmIoWidthType::Uint32;
+}
+
+impl IoValue for u64 {
+    const WIDTH: SmmIoWidthType = SmmIoWidthType::Uint64;
+}
+
+fn read<T: IoValue>(cpu_io: &Protocol, access: &SmmIoAccess, address: u64) -> Result<T, efi::Status> {
+    let mut value = T::default();
+    match (access.read)(cpu_io, T::WIDTH, address, 1, &mut value as *mut T as *mut c_void) {
+        efi::Status::SUCCESS => Ok(value),
+        status => Err(status),
+    }
+}
+
+fn write<T: IoValue>(cpu_io: &Protocol, access: &SmmIoAccess, address: u64, mut value: T) -> Result<(), efi::Status> {
+    match (access.write)(cpu_io, T::WIDTH, address, 1, &mut value as *mut T as *mut c_void) {
+        efi::Status::SUCCESS => Ok(()),
+        status => Err(status),
+    }
+}
+
+/// Reads a byte from an I/O port.
+pub fn io_read8(cpu_io: &Protocol, port: u16) -> Result<u8, efi::Status> {
+    read(cpu_io, &cpu_io.io, port.into())
+}
+
+/// Reads a 16-bit value from an I/O port.
+pub fn io_read16(cpu_io: &Protocol, port: u16) -> Result<u16, efi::Status> {
+    read(cpu_io, &cpu_io.io, port.into())
+}
+
+/// Reads a 32-bit value from an I/O port.
+pub fn io_read32(cpu_io: &Protocol, port: u16) -> Result<u32, efi::Status> {
+    read(cpu_io, &cpu_io.io, port.into())
+}
+
+/// Writes a byte to an I/O port.
+pub fn io_write8(cpu_io: &Protocol, port: u16, value: u8) -> Result<(), efi::Status> {
+    write(cpu_io, &cpu_io.io, port.into(), value)
+}
+
+/// Writes a 16-bit value to an I/O port.
+pub fn io_write16(cpu_io: &Protocol, port: u16, value: u16) -> Result<(), efi::Status> {
+    write(cpu_io, &cpu_io.io, port.into(), value)
+}
+
+/// Writes a 32-bit value to an I/O port.
+pub fn io_write32(cpu_io: &Protocol, port: u16, value: u32) -> Result<(), efi::Status> {
+    write(cpu_io, &cpu_io.io, port.into(), value)
+}
+
+/// Reads a byte from memory-mapped I/O.
+pub fn mem_read8(cpu_io: &Protocol, address: u64) -> Result<u8, efi::Status> {
+    read(cpu_io, &cpu_io.mem, address)
+}
+
+/// Reads a 16-bit value from memory-mapped I/O.
+pub fn mem_read16(cpu_io: &Protocol, address: u64) -> Result<u16, efi::Status> {
+    read(cpu_io, &cpu_io.mem, address)
+}
+
+/// Reads a 32-bit value from memory-mapped I/O.
+pub fn mem_read32(cpu_io: &Protocol, address: u64) -> Result<u32, efi::Status> {
+    read(cpu_io, &cpu_io.mem, address)
+}
+
+/// Reads a 64-bit value from memory-mapped I/O.
+pub fn mem_read64(cpu_io: &Protocol, address: u64) -> Result<u64, efi::Status> {
+    read(cpu_io, &cpu_io.mem, address)
+}
+
+/// Writes a byte to memory-mapped I/O.
+pub fn mem_write8(cpu_io: &Protocol, address: u64, value: u8) -> Result<(), efi::Status> {
+    write(cpu_io, &cpu_io.mem, address, value)
+}
+
+/// Writes a 16-bit value to memory-mapped I/O.
+pub fn mem_write16(cpu_io: &Protocol, address: u64, value: u16) -> Result<(), efi::Status> {
+    write(cpu_io, &cpu_io.mem, address, value)
+}
+
+/// Writes a 32-bit value to memory-mapped I/O.
+pub fn mem_write32(cpu_io: &Protocol, address: u64, value: u32) -> Result<(), efi::Status> {
+    write(cpu_io, &cpu_io.mem, address, value)
+}
+
+/// Writes a 64-bit value to memory-mapped I/O.
+pub fn mem_write64(cpu_io: &Protocol, address: u64, value: u64) -> Result<(), efi::Status> {
+    write(cpu_io, &cpu_io.mem, address, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{cell::RefCell, ffi::c_void, mem::size_of};
+
+    use r_efi::efi;
+
+    use super::{
+        io_read16, io_read8, io_write32, io_write8, mem_read64, mem_write16, Protocol, SmmIoAccess, SmmIoWidthType,
+        PROTOCOL_GUID,
+    };
+
+    // (space, read, width, address, count, value written)
+    type Access = (&'static str, bool, SmmIoWidthType, u64, usize, u64);
+
+    std::thread_local! {
+        static ACCESSES: RefCell<Vec<Access>> = RefCell::new(Vec::new());
+    }
+
+    fn record(space: &'static str, read: bool, width: SmmIoWidthType, address: u64, count: usize, buffer: *mut c_void) {
+        let value = match width {
+            SmmIoWidthType::Uint8 => unsafe { *(buffer as *mut u8) as u64 },
+            SmmIoWidthType::Uint16 => unsafe { *(buffer as *mut u16) as u64 },
+            SmmIoWidthType::Uint32 => unsafe { *(buffer as *mut u32) as u64 },
+            SmmIoWidthType::Uint64 => unsafe { *(buffer as *mut u64) },
+        };
+        ACCESSES.with(|accesses| accesses.borrow_mut().push((space, read, width, address, count, value)));
+    }
+
+    // Reads fill the buffer with 0xA5 bytes; port 0xFFFF fails.
+    extern "efiapi" fn io_read(
+        _: *const Protocol,
+        width: SmmIoWidthType,
+        address: u64,
+        count: usize,
+        buffer: *mut c_void,
+    ) -> efi::Status {
+        if address == 0xFFFF {
+            return efi::Status::DEVICE_ERROR;
+        }
+        unsafe { core::ptr::write_bytes(buffer as *mut u8, 0xA5, 1 << width as usize) };
+        record("io", true, width, address, count, buffer);
+        efi::Status::SUCCESS
+    }
+
+    extern "efiapi" fn io_write(
+        _: *const Protocol,
+        width: SmmIoWidthType,
+        address: u64,
+        count: usize,
+        buffer: *mut c_void,
+    ) -> efi::Status {
+        record("io", false, width, address, count, buffer);
+        efi::Status::SUCCESS
+    }
+
+    extern "efiapi" fn mem_read(
+        _: *const Protocol,
+        width: SmmIoWidthType,
+        address: u64,
+        count: usize,
+        buffer: *mut c_void,
+    ) -> efi::Status {
+        unsafe { core::ptr::write_bytes(buffer as *mut u8, 0x5A, 1 << width as usize) };
+        record("mem", true, width, address, count, buffer);
+        efi::Status::SUCCESS
+    }
+
+    extern "efiapi" fn mem_write(
+        _: *const Protocol,
+        width: SmmIoWidthType,
+        address: u64,
+        count: usize,
+        buffer: *mut c_void,
+    ) -> efi::Status {
+        record("mem", false, width, address, count, buffer);
+        efi::Status::SUCCESS
+    }
+
+    fn take_accesses() -> Vec<Access> {
+        ACCESSES.with(|accesses| accesses.borrow_mut().drain(..).collect())
+    }
+
+    #[test]
+    fn guid_and_layout_should_match_spec() {
+        assert_eq!(
+            PROTOCOL_GUID.as_bytes(),
+            &[0xd8, 0xa9, 0x42, 0x32, 0x70, 0xce, 0xa0, 0x4a, 0x95, 0x5d, 0x5e, 0x7b, 0x14, 0x0d, 0xe4, 0xd2]
+        );
+        assert_eq!(offset_of!(Protocol, io), size_of::<SmmIoAccess>());
+        assert_eq!(size_of::<Protocol>(), size_of::<[usize; 4]>());
+        assert_eq!(size_of::<SmmIoWidthType>(), 4);
+    }
+
+    #[test]
+    fn wrappers_should_marshal_width_address_and_value() {
+        let cpu_io = Protocol {
+            mem: SmmIoAccess { read: mem_read, write: mem_write },
+            io: SmmIoAccess { read: io_read, write: io_write },
+        };
+
+        assert_eq!(io_read8(&cpu_io, 0x64), Ok(0xA5));
+        assert_eq!(io_read16(&cpu_io, 0xCFC), Ok(0xA5A5));
+        io_write8(&cpu_io, 0x80, 0x42).unwrap();
+        io_write32(&cpu_io, 0xCF8, 0x8000_F8A0).unwrap();
+        assert_eq!(mem_read64(&cpu_io, 0xFED0_0000), Ok(0x5A5A_5A5A_5A5A_5A5A));
+        mem_write16(&cpu_io, 0xFEC0_0010, 0x1234).unwrap();
+
+        assert_eq!(
+            take_accesses(),
+            vec![
+                ("io", true, SmmIoWidthType::Uint8, 0x64, 1, 0xA5),
+                ("io", true, SmmIoWidthType::Uint16, 0xCFC, 1, 0xA5A5),
+                ("io", false, SmmIoWidthType::Uint8, 0x80, 1, 0x42),
+                ("io", false, SmmIoWidthType::Uint32, 0xCF8, 1, 0x8000_F8A0),
+                ("mem", true, SmmIoWidthType::Uint64, 0xFED0_0000, 1, 0x5A5A_5A5A_5A5A_5A5A),
+                ("mem", false, SmmIoWidthType::Uint16, 0xFEC0_0010, 1, 0x1234),
+            ]
+        );
+
+        assert_eq!(io_read8(&cpu_io, 0xFFFF), Err(efi::Status::DEVICE_ERROR));
+        assert!(take_accesses().is_empty());
+    }
+}
@@ -0,0 +1,98 @@
+//! SMM Software Dispatch 2 Protocol
+//!
+//! Provides the parent dispatch service for software SMIs: drivers register a handler for an SMI input value, and the
+//! handler runs each time software, usually a write to the APM control port, triggers an SMI with that value.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V4_Child_Dispatch_Protocols.html#efi-mm-sw-dispatch-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use r_efi::efi;
+
+use super::MmHandlerEntryPoint;
+
+/// SMM Software Dispatch 2 Protocol GUID
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-6.2
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x18a3c6dc, 0x5eea, 0x48c8, 0xa1, 0xc1, &[0xb5, 0x33, 0x89, 0xf9, 0x89, 0x99]);
+
+/// The SMI input value that asks Register() to pick an unused value and return it in the register context.
+pub const ANY_SW_SMI_INPUT_VALUE: usize = usize::MAX;
+
+/// The value a handler is registered for (EFI_MM_SW_REGISTER_CONTEXT).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-6.2.1
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmmSwRegisterContext {
+    /// The software SMI input value, or [`ANY_SW_SMI_INPUT_VALUE`].
+    pub sw_mm_input_value: usize,
+}
+
+/// The context passed to a handler when its SMI is dispatched (EFI_MM_SW_CONTEXT).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-6.2.1
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmmSwContext {
+    /// Index of the CPU that triggered the SMI.
+    pub swmi_cpu_index: usize,
+    /// Value written to the software SMI command port.
+    pub command_port: u8,
+    /// Value written to the software SMI data port.
+    pub data_port: u8,
+}
+
+/// Registers a handler for a software SMI input value.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-6.2.2
+///
+/// * this - pointer to the protocol
+/// * dispatch_function - handler to run when the SMI is triggered
+/// * register_context - value to register for; [`ANY_SW_SMI_INPUT_VALUE`] is replaced with the value assigned
+/// * dispatch_handle - receives the handle of the registration
+///
+/// * @retval - SUCCESS: the handler was registered
+/// * @retval - INVALID_PARAMETER: the input value is out of range or already in use
+/// * @retval - OUT_OF_RESOURCES: there is not enough memory to register the handler
+pub type EfiMmSwRegister = extern "efiapi" fn(
+    this: *const Protocol,
+    dispatch_function: MmHandlerEntryPoint,
+    register_context: *mut SmmSwRegisterContext,
+    dispatch_handle: *mut efi::Handle,
+) -> efi::Status;
+
+/// Unregisters a handler.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-6.2.3
+///
+/// * this - pointer to the protocol
+/// * dispatch_handle - handle returned by Register()
+///
+/// * @retval - SUCCESS: the handler was unregistered
+/// * @retval - INVALID_PARAMETER: the handle is not valid
+pub type EfiMmSwUnregister = extern "efiapi" fn(this: *const Protocol, dispatch_handle: efi::Handle) -> efi::Status;
+
+/// Registers handlers for software SMIs.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-6.2
+#[repr(C)]
+#[derive(Debug)]
+pub struct Protocol {
+    pub register: EfiMmSwRegister,
+    pub unregister: EfiMmSwUnregister,
+    /// Largest software SMI input value the platform supports.
+    pub maximum_sw_mmi_value: usize,
+}
@@ -13,6 +13,8 @@
 
 use r_efi::efi;
 
+use crate::status_code::{StatusCodeType, StatusCodeValue};
+
 pub const PROTOCOL_GUID: efi::Guid =
     efi::Guid::from_fields(0xD2B2B828, 0x0826, 0x48A7, 0xB3, 0xDF, &[0x98, 0x3C, 0x00, 0x60, 0x24, 0xF0]);
 
@@ -127,6 +129,7 @@ pub const EFI_SW_BS_PC_CREATE_EVENT_EX: u32 = EFI_SUBCLASS_SPECIFIC | 0x0000002A
 /// # Documentation
 /// UEFI Platform Initialization Specification, Release 1.8, Section III-6.6.2.1
 #[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct EfiStatusCodeData {
     pub header_size: u16,
     pub size: u16,
@@ -138,7 +141,7 @@ pub struct EfiStatusCodeData {
 /// # Documentation
 /// UEFI Platform Initialization Specification, Release 1.8, Section II-14.2.1
 pub type ReportStatusCode =
-    extern "efiapi" fn(u32, u32, u32, *const efi::Guid, *const EfiStatusCodeData) -> efi::Status;
+    extern "efiapi" fn(StatusCodeType, StatusCodeValue, u32, *const efi::Guid, *const EfiStatusCodeData) -> efi::Status;
 
 /// Provides the service required to report a status code to the platform firmware.
 /// This protocol must be produced by a runtime DXE driver.
@@ -146,6 +149,7 @@ pub type ReportStatusCode =
 /// # Documentation
 /// UEFI Platform Initialization Specification, Release 1.8, Section II-14.2.1
 #[repr(C)]
+#[derive(Debug)]
 pub struct Protocol {
     pub report_status_code: ReportStatusCode,
 }
@@ -3,6 +3,10 @@
 //! Used to set up a periodic timer interrupt using a platform specific timer, and a processor-specific interrupt
 //! vector. This protocol enables the use of the SetTimer() Boot Service.
 //!
+//! [`StaticTimerProtocol`] implements the protocol with a [`TimerDriver`], and [`TimerArch`] calls an installed
+//! instance. Both express timer periods as [`Duration`]s, converted from and to the 100 ns units of the protocol by
+//! [`duration_to_units`] and [`units_to_duration`].
+//!
 //! See <https://uefi.org/specs/PI/1.8A/V2_DXE_Architectural_Protocols.html#timer-architectural-protocol>
 //!
 //! ## License
@@ -12,8 +16,12 @@
 //! SPDX-License-Identifier: BSD-2-Clause-Patent
 //!
 
+use core::{mem, time::Duration};
+
 use r_efi::efi;
 
+use crate::{protocols::security::AbortOnUnwind, status::StatusExt};
+
 pub const PROTOCOL_GUID: efi::Guid =
     efi::Guid::from_fields(0x26BACCB3, 0x6F42, 0x11D4, 0xBC, 0xE7, &[0x00, 0x80, 0xC7, 0x3C, 0x88, 0x81]);
 
@@ -44,7 +52,7 @@ pub type EfiTimerNotify = extern "efiapi" fn(time: u64);
 ///                     function executes at TPL_HIGH_LEVEL. The DXE Core will
 ///                     register a handler for the timer interrupt, so it can know
 ///                     how much time has passed. This information is used to
-///                     signal timer based events. NULL will unregister the handler.
+///                     signal timer based events. None (NULL) will unregister the handler.
 /// * @retval - EFI_SUCCESS: The timer handler was registered.
 /// * @retval - EFI_UNSUPPORTED: The platform does not support timer interrupts.
 /// * @retval - EFI_ALREADY_STARTED: NotifyFunction is not NULL, and a handler is already
@@ -53,7 +61,7 @@ pub type EfiTimerNotify = extern "efiapi" fn(time: u64);
 ///                                    previously registered.
 /// * @retval - EFI_DEVICE_ERROR: The timer handler could not be registered.
 pub type EfiTimerRegisterHandler =
-    extern "efiapi" fn(this: *mut Protocol, notify_function: EfiTimerNotify) -> efi::Status;
+    extern "efiapi" fn(this: *mut Protocol, notify_function: Option<EfiTimerNotify>) -> efi::Status;
 
 /// This function adjusts the period of timer interrupts to the value specified
 /// by TimerPeriod.  If the timer period is updated, then the selected timer
@@ -109,9 +117,298 @@ pub type EfiTimerGenerateSoftInterrupt = extern "efiapi" fn(this: *mut Protocol)
 /// # Documentation
 /// UEFI Platform Initialization Specification, Release 1.8, Section II-12.10.1
 #[repr(C)]
+#[derive(Debug)]
 pub struct Protocol {
     pub register_handler: EfiTimerRegisterHandler,
     pub set_timer_period: EfiTimerSetTimerPeriod,
     pub get_timer_period: EfiTimerGetTimerPeriod,
     pub generate_soft_interrupt: EfiTimerGenerateSoftInterrupt,
 }
+
+/// The duration of one unit of the timer periods of the protocol.
+pub const UNIT: Duration = Duration::from_nanos(100);
+
+/// Converts `duration` to 100 ns units, rounding up so that a non-zero duration never becomes the 0 that disables the
+/// timer. Returns `None` if the units overflow a `u64`.
+pub fn duration_to_units(duration: Duration) -> Option<u64> {
+    let units = duration.as_nanos().div_ceil(UNIT.as_nanos());
+    u64::try_from(units).ok()
+}
+
+/// Converts 100 ns units to a duration. Every `u64` of units is representable, so the conversion is exact.
+pub fn units_to_duration(units: u64) -> Duration {
+    Duration::new(units / 10_000_000, (units % 10_000_000) as u32 * 100)
+}
+
+/// A timer driver implementing the Timer Architectural Protocol.
+///
+/// Timer periods are converted by [`duration_to_units`] and [`units_to_duration`]: the driver receives the exact
+/// period requested, and a period it reports is rounded up to a whole unit.
+pub trait TimerDriver {
+    /// Registers the handler called with the time since the previous interrupt each time the timer interrupt fires,
+    /// or unregisters it if `handler` is `None`.
+    ///
+    /// Should fail with ALREADY_STARTED when registering while a handler is registered, and with INVALID_PARAMETER
+    /// when unregistering while none is.
+    fn register_handler(&self, handler: Option<EfiTimerNotify>) -> Result<(), efi::Status>;
+
+    /// Sets the period of the timer interrupt, rounded up to a period the hardware supports, or disables the interrupt
+    /// if `period` is zero.
+    fn set_period(&self, period: Duration) -> Result<(), efi::Status>;
+
+    /// Returns the period of the timer interrupt, or zero if the interrupt is disabled.
+    fn period(&self) -> Duration;
+
+    /// Generates a soft timer interrupt, calling the registered handler if the interrupt is enabled. Unsupported
+    /// unless implemented.
+    fn generate_soft_interrupt(&self) -> Result<(), efi::Status> {
+        Err(efi::Status::UNSUPPORTED)
+    }
+}
+
+/// A Timer Architectural Protocol instance implemented by a [`TimerDriver`].
+///
+/// The instance starts with the [`Protocol`], whose functions find the driver from their `this` pointer, so the
+/// protocol must be installed from [`Self::protocol`] and the instance must not move while it is installed.
+///
+/// The functions return INVALID_PARAMETER without calling the driver if `this` or the period pointer is null, and
+/// GetTimerPeriod() reports a period overflowing the units as the largest one. A panic in the driver aborts rather than
+/// unwinding into the DXE Foundation.
+#[repr(C)]
+#[derive(Debug)]
+pub struct StaticTimerProtocol<T: TimerDriver> {
+    protocol: Protocol,
+    driver: T,
+}
+
+impl<T: TimerDriver> StaticTimerProtocol<T> {
+    /// Creates the instance implemented by `driver`.
+    pub const fn new(driver: T) -> Self {
+        Self {
+            protocol: Protocol {
+                register_handler: register_handler::<T>,
+                set_timer_period: set_timer_period::<T>,
+                get_timer_period: get_timer_period::<T>,
+                generate_soft_interrupt: generate_soft_interrupt::<T>,
+            },
+            driver,
+        }
+    }
+
+    /// Returns the protocol to install.
+    pub fn protocol(&self) -> &Protocol {
+        &self.protocol
+    }
+
+    /// Returns the driver.
+    pub fn driver(&self) -> &T {
+        &self.driver
+    }
+}
+
+// Returns the driver of the instance starting with `this`, or None if it is null.
+fn driver<'a, T: TimerDriver>(this: *mut Protocol) -> Option<&'a T> {
+    //Safety: the functions are only installed in the protocol of a StaticTimerProtocol<T>, which starts with it.
+    unsafe { (this as *const StaticTimerProtocol<T>).as_ref() }.map(|instance| &instance.driver)
+}
+
+fn status(result: Result<(), efi::Status>) -> efi::Status {
+    result.err().unwrap_or(efi::Status::SUCCESS)
+}
+
+extern "efiapi" fn register_handler<T: TimerDriver>(
+    this: *mut Protocol,
+    notify_function: Option<EfiTimerNotify>,
+) -> efi::Status {
+    let Some(driver) = driver::<T>(this) else {
+        return efi::Status::INVALID_PARAMETER;
+    };
+    let guard = AbortOnUnwind("timer driver");
+    let result = driver.register_handler(notify_function);
+    mem::forget(guard);
+    status(result)
+}
+
+extern "efiapi" fn set_timer_period<T: TimerDriver>(this: *mut Protocol, timer_period: u64) -> efi::Status {
+    let Some(driver) = driver::<T>(this) else {
+        return efi::Status::INVALID_PARAMETER;
+    };
+    let guard = AbortOnUnwind("timer driver");
+    let result = driver.set_period(units_to_duration(timer_period));
+    mem::forget(guard);
+    status(result)
+}
+
+extern "efiapi" fn get_timer_period<T: TimerDriver>(this: *mut Protocol, timer_period: *mut u64) -> efi::Status {
+    let Some(driver) = driver::<T>(this) else {
+        return efi::Status::INVALID_PARAMETER;
+    };
+    if timer_period.is_null() {
+        return efi::Status::INVALID_PARAMETER;
+    }
+    let guard = AbortOnUnwind("timer driver");
+    let period = driver.period();
+    mem::forget(guard);
+    //Safety: timer_period is non-null and the caller passes a writable u64.
+    unsafe { timer_period.write(duration_to_units(period).unwrap_or(u64::MAX)) };
+    efi::Status::SUCCESS
+}
+
+extern "efiapi" fn generate_soft_interrupt<T: TimerDriver>(this: *mut Protocol) -> efi::Status {
+    let Some(driver) = driver::<T>(this) else {
+        return efi::Status::INVALID_PARAMETER;
+    };
+    let guard = AbortOnUnwind("timer driver");
+    let result = driver.generate_soft_interrupt();
+    mem::forget(guard);
+    status(result)
+}
+
+/// Calls an installed Timer Architectural Protocol instance.
+#[derive(Debug, Clone, Copy)]
+pub struct TimerArch<'a> {
+    protocol: &'a Protocol,
+}
+
+impl<'a> TimerArch<'a> {
+    /// Creates a caller of `protocol`.
+    pub fn new(protocol: &'a Protocol) -> Self {
+        Self { protocol }
+    }
+
+    fn this(&self) -> *mut Protocol {
+        self.protocol as *const Protocol as *mut Protocol
+    }
+
+    /// Sets the period of the timer interrupt, rounded up to 100 ns units, then by the driver to a period the hardware
+    /// supports. A zero period disables the interrupt.
+    ///
+    /// Fails with INVALID_PARAMETER without calling the instance if the period overflows the units (about 58,000
+    /// years), or with the status returned by the instance.
+    pub fn set_period(&self, period: Duration) -> Result<(), efi::Status> {
+        let units = duration_to_units(period).ok_or(efi::Status::INVALID_PARAMETER)?;
+        (self.protocol.set_timer_period)(self.this(), units).ok()
+    }
+
+    /// Returns the period of the timer interrupt, or zero if the interrupt is disabled.
+    pub fn period(&self) -> Result<Duration, efi::Status> {
+        let mut units = 0;
+        (self.protocol.get_timer_period)(self.this(), &mut units).ok()?;
+        Ok(units_to_duration(units))
+    }
+
+    /// Disables the timer interrupt.
+    pub fn disable(&self) -> Result<(), efi::Status> {
+        (self.protocol.set_timer_period)(self.this(), 0).ok()
+    }
+
+    /// Registers the handler called each time the timer interrupt fires, or unregisters it if `handler` is `None`.
+    pub fn register_handler(&self, handler: Option<EfiTimerNotify>) -> Result<(), efi::Status> {
+        (self.protocol.register_handler)(self.this(), handler).ok()
+    }
+
+    /// Generates a soft timer interrupt.
+    pub fn generate_soft_interrupt(&self) -> Result<(), efi::Status> {
+        (self.protocol.generate_soft_interrupt)(self.this()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{cell::Cell, ptr, time::Duration};
+
+    use r_efi::efi;
+
+    use super::{duration_to_units, units_to_duration, EfiTimerNotify, StaticTimerProtocol, TimerArch, TimerDriver};
+
+    #[test]
+    fn durations_should_round_up_to_units() {
+        assert_eq!(duration_to_units(Duration::ZERO), Some(0));
+        assert_eq!(duration_to_units(Duration::from_nanos(1)), Some(1));
+        assert_eq!(duration_to_units(Duration::from_nanos(100)), Some(1));
+        assert_eq!(duration_to_units(Duration::from_nanos(101)), Some(2));
+        assert_eq!(duration_to_units(Duration::from_millis(10)), Some(100_000));
+        assert_eq!(duration_to_units(units_to_duration(u64::MAX)), Some(u64::MAX));
+        assert_eq!(duration_to_units(units_to_duration(u64::MAX) + Duration::from_nanos(1)), None);
+        assert_eq!(duration_to_units(Duration::MAX), None);
+    }
+
+    #[test]
+    fn units_should_convert_exactly() {
+        assert_eq!(units_to_duration(0), Duration::ZERO);
+        assert_eq!(units_to_duration(1), Duration::from_nanos(100));
+        assert_eq!(units_to_duration(12_345_678), Duration::new(1, 234_567_800));
+        assert_eq!(units_to_duration(u64::MAX), Duration::new(u64::MAX / 10_000_000, 955_161_500));
+    }
+
+    // Keeps the period in whole milliseconds, as a timer counting at 1 kHz would.
+    #[derive(Default)]
+    struct Driver {
+        handler: Cell<Option<EfiTimerNotify>>,
+        period: Cell<Duration>,
+        set_period_calls: Cell<usize>,
+    }
+
+    impl TimerDriver for Driver {
+        fn register_handler(&self, handler: Option<EfiTimerNotify>) -> Result<(), efi::Status> {
+            match (self.handler.get(), handler) {
+                (Some(_), Some(_)) => Err(efi::Status::ALREADY_STARTED),
+                (None, None) => Err(efi::Status::INVALID_PARAMETER),
+                (_, handler) => {
+                    self.handler.set(handler);
+                    Ok(())
+                }
+            }
+        }
+
+        fn set_period(&self, period: Duration) -> Result<(), efi::Status> {
+            self.set_period_calls.set(self.set_period_calls.get() + 1);
+            let millis = period.as_nanos().div_ceil(1_000_000);
+            self.period.set(Duration::from_millis(millis.try_into().map_err(|_| efi::Status::UNSUPPORTED)?));
+            Ok(())
+        }
+
+        fn period(&self) -> Duration {
+            self.period.get()
+        }
+    }
+
+    extern "efiapi" fn notify(_time: u64) {}
+
+    #[test]
+    fn timer_arch_should_call_the_driver() {
+        let instance = StaticTimerProtocol::new(Driver::default());
+        let timer = TimerArch::new(instance.protocol());
+
+        assert_eq!(timer.set_period(Duration::from_micros(9_950)), Ok(()));
+        assert_eq!(timer.period(), Ok(Duration::from_millis(10)));
+        assert_eq!(timer.disable(), Ok(()));
+        assert_eq!(timer.period(), Ok(Duration::ZERO));
+
+        assert_eq!(timer.set_period(Duration::MAX), Err(efi::Status::INVALID_PARAMETER));
+        assert_eq!(instance.driver().set_period_calls.get(), 2);
+
+        assert_eq!(timer.register_handler(None), Err(efi::Status::INVALID_PARAMETER));
+        assert_eq!(timer.register_handler(Some(notify)), Ok(()));
+        assert_eq!(timer.register_handler(Some(notify)), Err(efi::Status::ALREADY_STARTED));
+        assert_eq!(timer.register_handler(None), Ok(()));
+
+        assert_eq!(timer.generate_soft_interrupt(), Err(efi::Status::UNSUPPORTED));
+    }
+
+    #[test]
+    fn protocol_should_validate_pointers() {
+        let instance = StaticTimerProtocol::new(Driver::default());
+        let protocol = instance.protocol();
+        let this = protocol as *const _ as *mut _;
+
+        assert_eq!((protocol.set_timer_period)(ptr::null_mut(), 1), efi::Status::INVALID_PARAMETER);
+        assert_eq!((protocol.get_timer_period)(this, ptr::null_mut()), efi::Status::INVALID_PARAMETER);
+
+        // A period the units cannot hold is reported as the largest one.
+        instance.driver().period.set(Duration::MAX);
+        let mut units = 0;
+        assert_eq!((protocol.get_timer_period)(this, &mut units), efi::Status::SUCCESS);
+        assert_eq!(units, u64::MAX);
+    }
+}
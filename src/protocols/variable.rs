@@ -0,0 +1,22 @@
+//! Variable Architectural Protocol
+//!
+//! Provides the services required to get and set environment variables. The protocol has no functions: it is installed
+//! once the GetVariable(), GetNextVariableName() and QueryVariableInfo() runtime services are available.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V2_DXE_Architectural_Protocols.html#variable-architectural-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use r_efi::efi;
+
+/// Variable Architectural Protocol GUID
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section II-12.11.1
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0x1e5668e2, 0x8481, 0x11d4, 0xbc, 0xf1, &[0x00, 0x80, 0xc7, 0x3c, 0x88, 0x81]);
@@ -49,6 +49,7 @@ pub type GetTimerPeriod = extern "efiapi" fn(*const Protocol, *mut u64) -> efi::
 /// # Documentation
 /// UEFI Platform Initialization Specification, Release 1.8, Section II-12.14.1
 #[repr(C)]
+#[derive(Debug)]
 pub struct Protocol {
     pub register_handler: RegisterHandler,
     pub set_timer_period: SetTimerPeriod,
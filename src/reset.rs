@@ -0,0 +1,160 @@
+//! System Reset
+//!
+//! Typed reset requests for the ResetSystem() runtime service and the ResetSystem2() PEI service, which share the same
+//! interface. [`invoke`] builds the reset data of the request and calls the service.
+//!
+//! The reset data of a platform specific reset is a null-terminated UCS-2 string describing the reset, followed by the
+//! GUID identifying the type of reset to perform.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/08_Services_Runtime_Services.html#resetsystem>
+//!
+//! ## Example
+//! ```no_run
+//! use mu_pi::reset::{self, ResetRequest};
+//! use r_efi::efi;
+//!
+//! fn reset_to_recovery(runtime_services: &efi::RuntimeServices, recovery_reset: efi::Guid) -> ! {
+//!     let request = ResetRequest::PlatformSpecific { guid: recovery_reset, message: "Recovery requested" };
+//!     reset::invoke(runtime_services.reset_system, request, efi::Status::SUCCESS)
+//! }
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::{ffi::c_void, ptr};
+
+use r_efi::efi;
+
+/// A reset of the platform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetRequest<'a> {
+    /// Resets all circuitry within the system to its initial state (EfiResetCold).
+    Cold,
+    /// Resets the processors and reinitializes the system, keeping memory contents (EfiResetWarm).
+    Warm,
+    /// Places the system in a power state equivalent to the ACPI G2/S5 or G3 states (EfiResetShutdown).
+    Shutdown,
+    /// Performs the reset identified by `guid`, described by `message` (EfiResetPlatformSpecific).
+    PlatformSpecific { guid: efi::Guid, message: &'a str },
+}
+
+impl ResetRequest<'_> {
+    /// Returns the reset type passed to the reset service.
+    pub fn reset_type(&self) -> efi::ResetType {
+        match self {
+            ResetRequest::Cold => efi::RESET_COLD,
+            ResetRequest::Warm => efi::RESET_WARM,
+            ResetRequest::Shutdown => efi::RESET_SHUTDOWN,
+            ResetRequest::PlatformSpecific { .. } => efi::RESET_PLATFORM_SPECIFIC,
+        }
+    }
+
+    /// Returns the reset data passed to the reset service, empty unless the reset is platform specific.
+    ///
+    /// For a platform specific reset, the message is converted to UCS-2 and terminated with a null character, which
+    /// the GUID follows directly.
+    pub fn reset_data(&self) -> Vec<u8> {
+        match self {
+            ResetRequest::PlatformSpecific { guid, message } => message
+                .encode_utf16()
+                .chain([0])
+                .flat_map(u16::to_le_bytes)
+                .chain(guid.as_bytes().iter().copied())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Resets the platform with `rt_reset`, the ResetSystem() runtime service or the ResetSystem2() PEI service.
+///
+/// `status` is the reason for the reset: SUCCESS for a normal reset, or an error status. Halts should the service
+/// return.
+pub fn invoke(rt_reset: efi::RuntimeResetSystem, req: ResetRequest<'_>, status: efi::Status) -> ! {
+    let mut data = req.reset_data();
+    let (data_size, reset_data) = match data.is_empty() {
+        true => (0, ptr::null_mut()),
+        false => (data.len(), data.as_mut_ptr() as *mut c_void),
+    };
+    rt_reset(req.reset_type(), status, data_size, reset_data);
+
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{cell::RefCell, ffi::c_void, slice};
+    use std::{sync::mpsc, thread, time::Duration};
+
+    use r_efi::efi;
+
+    use super::{invoke, ResetRequest};
+
+    const RESET_GUID: efi::Guid =
+        efi::Guid::from_fields(0x9e2c8e4b, 0x1bd0, 0x4a5f, 0x8b, 0x3f, &[0x27, 0x6d, 0x1c, 0x58, 0x0e, 0x93]);
+
+    type Reset = (efi::ResetType, efi::Status, Vec<u8>);
+
+    thread_local! {
+        static RESETS: RefCell<Option<mpsc::Sender<Reset>>> = const { RefCell::new(None) };
+    }
+
+    // Reports the reset, then blocks the thread forever as the service does not return.
+    extern "efiapi" fn reset_system(reset_type: efi::ResetType, status: efi::Status, size: usize, data: *mut c_void) {
+        let data = match data.is_null() {
+            true => Vec::new(),
+            false => unsafe { slice::from_raw_parts(data as *const u8, size) }.to_vec(),
+        };
+        RESETS.with(|resets| resets.borrow().as_ref().unwrap().send((reset_type, status, data)).unwrap());
+        loop {
+            thread::park();
+        }
+    }
+
+    fn reset(request: ResetRequest<'static>, status: efi::Status) -> Reset {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            RESETS.with(|resets| *resets.borrow_mut() = Some(sender));
+            invoke(reset_system, request, status)
+        });
+        receiver.recv_timeout(Duration::from_secs(10)).unwrap()
+    }
+
+    #[test]
+    fn platform_specific_data_should_terminate_the_message_before_the_guid() {
+        let request = ResetRequest::PlatformSpecific { guid: RESET_GUID, message: "Reset" };
+        let data = request.reset_data();
+        assert_eq!(data.len(), 2 * 6 + 16);
+        assert_eq!(&data[..12], b"R\0e\0s\0e\0t\0\0\0");
+        assert_eq!(&data[12..], RESET_GUID.as_bytes());
+
+        let empty = ResetRequest::PlatformSpecific { guid: RESET_GUID, message: "" };
+        assert_eq!(empty.reset_data(), [&[0, 0][..], RESET_GUID.as_bytes()].concat());
+        assert_eq!(ResetRequest::Warm.reset_data(), []);
+    }
+
+    #[test]
+    fn invoke_should_call_the_reset_service() {
+        assert_eq!(reset(ResetRequest::Cold, efi::Status::SUCCESS), (efi::RESET_COLD, efi::Status::SUCCESS, vec![]));
+        assert_eq!(reset(ResetRequest::Warm, efi::Status::ABORTED), (efi::RESET_WARM, efi::Status::ABORTED, vec![]));
+        assert_eq!(
+            reset(ResetRequest::Shutdown, efi::Status::SUCCESS),
+            (efi::RESET_SHUTDOWN, efi::Status::SUCCESS, vec![])
+        );
+
+        let request = ResetRequest::PlatformSpecific { guid: RESET_GUID, message: "Update" };
+        let (reset_type, status, data) = reset(request, efi::Status::SUCCESS);
+        assert_eq!((reset_type, status), (efi::RESET_PLATFORM_SPECIFIC, efi::Status::SUCCESS));
+        assert_eq!(data, request.reset_data());
+    }
+}
@@ -0,0 +1,15 @@
+//! S3 Resume Support
+//!
+//! Support code for recording the state firmware restores on resume from S3 (suspend to RAM), when the platform is
+//! reinitialized without running the DXE phase. The boot script format itself is defined in [`crate::boot_script`].
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V5_S3_Resume.html>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+pub mod boot_script;
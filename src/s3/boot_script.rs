@@ -0,0 +1,229 @@
+//! S3 Boot Script Recording
+//!
+//! [`BootScriptWriter`] records the common register writes of silicon initialization code as boot script entries,
+//! one typed method per access, so that callers do not handle widths, counts and byte buffers themselves. The entries
+//! are encoded by [`crate::boot_script`], in the format of the S3 Save State Protocol opcodes.
+//!
+//! Scripts that need other opcodes, labels or insertion at a position use [`crate::boot_script::ScriptWriter`].
+//!
+//! ## Example
+//! ```
+//! use mu_pi::{
+//!   boot_script::{ScriptTable, IO_WRITE_OPCODE, STALL_OPCODE, TERMINATE_OPCODE},
+//!   s3::boot_script::BootScriptWriter,
+//! };
+//!
+//! let mut writer = BootScriptWriter::new();
+//! writer.io_write8(0x80, 0x55);
+//! writer.stall(100);
+//! writer.terminate();
+//!
+//! let table = writer.to_table().unwrap();
+//! let opcodes: Vec<u16> = ScriptTable::parse(&table).unwrap().entries().map(|entry| entry.opcode()).collect();
+//! assert_eq!(opcodes, [IO_WRITE_OPCODE, STALL_OPCODE, TERMINATE_OPCODE]);
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use crate::boot_script::{self, BootScriptError, ScriptEntry, TableHeader, Width};
+
+/// The address of a PCI configuration register, as used by the PCI_CONFIG boot script opcodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PciAddress {
+    pub bus: u8,
+    /// The device number, 0 to 31.
+    pub device: u8,
+    /// The function number, 0 to 7.
+    pub function: u8,
+    /// The register offset; offsets from 0x100 are in extended configuration space.
+    pub register: u16,
+}
+
+impl PciAddress {
+    /// Creates the address of `register` of a PCI function.
+    pub const fn new(bus: u8, device: u8, function: u8, register: u16) -> Self {
+        Self { bus, device, function, register }
+    }
+
+    /// Returns the address as encoded in the script (S3_BOOT_SCRIPT_LIB_PCI_ADDRESS): the register in bits 7:0, or
+    /// in bits 43:32 if it is in extended configuration space, the function in bits 10:8, the device in bits 20:16
+    /// and the bus in bits 31:24. Out of range device, function and register numbers are truncated.
+    pub const fn as_u64(self) -> u64 {
+        let register = (self.register & 0xFFF) as u64;
+        let register = if register < 0x100 { register } else { register << 32 };
+        ((self.bus as u64) << 24)
+            | (((self.device & 0x1F) as u64) << 16)
+            | (((self.function & 0x7) as u64) << 8)
+            | register
+    }
+}
+
+impl From<PciAddress> for u64 {
+    fn from(address: PciAddress) -> Self {
+        address.as_u64()
+    }
+}
+
+/// Records boot script entries.
+#[derive(Debug, Default, Clone)]
+pub struct BootScriptWriter {
+    bytes: Vec<u8>,
+}
+
+impl BootScriptWriter {
+    /// Creates an empty script.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records an IO_WRITE of `value` to `port`.
+    pub fn io_write8(&mut self, port: u16, value: u8) {
+        self.record(boot_script::io_write(Width::Uint8, port as u64, 1, &[value]));
+    }
+
+    /// Records an IO_READ_WRITE that writes `(read & mask) | value` back to `port`.
+    pub fn io_read_write8(&mut self, port: u16, mask: u8, value: u8) {
+        self.record(boot_script::io_read_write(Width::Uint8, port as u64, value as u64, mask as u64));
+    }
+
+    /// Records a MEM_WRITE of `value` to `addr`.
+    pub fn mem_write32(&mut self, addr: u64, value: u32) {
+        self.record(boot_script::mem_write(Width::Uint32, addr, 1, &value.to_le_bytes()));
+    }
+
+    /// Records a MEM_READ_WRITE that writes `(read & mask) | value` back to `addr`.
+    pub fn mem_read_write32(&mut self, addr: u64, mask: u32, value: u32) {
+        self.record(boot_script::mem_read_write(Width::Uint32, addr, value as u64, mask as u64));
+    }
+
+    /// Records a PCI_CONFIG_WRITE of `value` to `addr` in PCI segment 0.
+    pub fn pci_config_write16(&mut self, addr: PciAddress, value: u16) {
+        self.record(boot_script::pci_config_write(Width::Uint16, addr.as_u64(), 1, &value.to_le_bytes()));
+    }
+
+    /// Records a STALL of `microseconds`.
+    pub fn stall(&mut self, microseconds: u32) {
+        self.bytes.extend_from_slice(boot_script::stall(microseconds as u64).as_bytes());
+    }
+
+    /// Records the TERMINATE entry that ends the script.
+    pub fn terminate(&mut self) {
+        self.bytes.extend_from_slice(boot_script::terminate().as_bytes());
+    }
+
+    /// Returns the entries recorded so far.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the entries recorded.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Returns the table image: the table header followed by the entries recorded, which should end with
+    /// [`Self::terminate`].
+    pub fn to_table(&self) -> Result<Vec<u8>, BootScriptError> {
+        let length = size_of::<TableHeader>() + self.bytes.len();
+        let table_length = u32::try_from(length).map_err(|_| BootScriptError::TableTooLong)?;
+        let mut table = Vec::with_capacity(length);
+        table.extend_from_slice(boot_script::table_header(table_length).as_bytes());
+        table.extend_from_slice(&self.bytes);
+        Ok(table)
+    }
+
+    fn record(&mut self, entry: Result<ScriptEntry, BootScriptError>) {
+        // The accesses recorded have a single value of the width, which always encodes.
+        let entry = entry.expect("fixed-width boot script entries always encode");
+        self.bytes.extend_from_slice(entry.as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    use super::{BootScriptWriter, PciAddress};
+    use crate::boot_script::{self, Entry, ScriptTable, Width};
+
+    #[test]
+    fn pci_addresses_should_match_edk2_encoding() {
+        assert_eq!(PciAddress::new(0, 0x1F, 3, 0x40).as_u64(), 0x001F_0340);
+        assert_eq!(PciAddress::new(0xFE, 2, 1, 0xFF).as_u64(), 0xFE02_01FF);
+        assert_eq!(u64::from(PciAddress::new(1, 0, 0, 0x100)), 0x100_0100_0000);
+        assert_eq!(PciAddress::new(0, 0x3F, 0xF, 0x1FFF).as_u64(), 0xFFF_001F_0700);
+    }
+
+    #[test]
+    fn entries_should_match_save_state_opcodes() {
+        let mut writer = BootScriptWriter::new();
+        writer.io_write8(0xCF9, 0x06);
+        assert_eq!(writer.as_bytes(), [0x00, 0x00, 20, 0, 0, 0, 0, 1, 0, 0, 0, 0xF9, 0x0C, 0, 0, 0, 0, 0, 0, 0x06]);
+
+        let mut writer = BootScriptWriter::new();
+        writer.stall(0x12345);
+        assert_eq!(writer.as_bytes(), [0x07, 0x00, 11, 0x45, 0x23, 0x01, 0, 0, 0, 0, 0]);
+
+        let mut writer = BootScriptWriter::new();
+        writer.terminate();
+        assert_eq!(writer.as_bytes(), [0xFF, 0x00, 3]);
+
+        // Every method matches the generic encoder with the corresponding width.
+        let mut writer = BootScriptWriter::new();
+        writer.io_read_write8(0x70, 0x7F, 0x80);
+        writer.mem_write32(0xFED4_0044, 0xDEAD_BEEF);
+        writer.mem_read_write32(0xFED1_F404, 0xFFFF_FFFB, 0x4);
+        writer.pci_config_write16(PciAddress::new(0, 0x1F, 0, 0x04), 0x0007);
+        let expected: Vec<u8> = [
+            boot_script::io_read_write(Width::Uint8, 0x70, 0x80, 0x7F).unwrap(),
+            boot_script::mem_write(Width::Uint32, 0xFED4_0044, 1, &0xDEAD_BEEFu32.to_le_bytes()).unwrap(),
+            boot_script::mem_read_write(Width::Uint32, 0xFED1_F404, 0x4, 0xFFFF_FFFB).unwrap(),
+            boot_script::pci_config_write(Width::Uint16, 0x001F_0004, 1, &[0x07, 0x00]).unwrap(),
+        ]
+        .iter()
+        .flat_map(|entry| entry.as_bytes().iter().copied())
+        .collect();
+        assert_eq!(writer.into_bytes(), expected);
+    }
+
+    #[test]
+    fn recorded_table_should_parse() {
+        let mut writer = BootScriptWriter::new();
+        writer.io_write8(0x80, 0x55);
+        writer.io_read_write8(0x61, 0xF0, 0x0C);
+        writer.mem_write32(0xFED0_0000, 1);
+        writer.mem_read_write32(0xFED0_0010, 0xFFFF_FFFC, 0x3);
+        writer.pci_config_write16(PciAddress::new(0, 2, 0, 0x04), 0x0006);
+        writer.stall(50);
+        writer.terminate();
+
+        let bytes = writer.to_table().unwrap();
+        let table = ScriptTable::parse(&bytes).unwrap();
+        assert_eq!(table.len(), bytes.len());
+        let entries: Vec<Entry> = table.entries().collect();
+        assert_eq!(entries.len(), 7);
+        match &entries[1] {
+            Entry::IoReadWrite { entry, data, data_mask } => {
+                assert_eq!({ entry.address }, 0x61);
+                assert_eq!((*data, *data_mask), (0x0C, 0xF0));
+            }
+            entry => panic!("unexpected entry {entry:?}"),
+        }
+        match &entries[4] {
+            Entry::PciConfigWrite { entry, .. } => assert_eq!({ entry.address }, 0x0002_0004),
+            entry => panic!("unexpected entry {entry:?}"),
+        }
+        assert_eq!(entries[6].opcode(), boot_script::TERMINATE_OPCODE);
+    }
+}
@@ -0,0 +1,15 @@
+//! Secure Boot
+//!
+//! Support for the UEFI Secure Boot databases, such as the allowed (`db`) and forbidden (`dbx`) signature databases
+//! used to authenticate images.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/32_Secure_Boot_and_Driver_Signing.html>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+pub mod sig_db;
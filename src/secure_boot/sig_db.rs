@@ -0,0 +1,248 @@
+//! Signature Databases
+//!
+//! Lookups in a signature database, the sequence of EFI_SIGNATURE_LIST structures held by the `db`, `dbx`, `KEK` and
+//! `PK` variables. Each list holds signatures of one type, identified by its signature type GUID, and each signature
+//! is the GUID of its owner followed by the signature data, such as a hash of a forbidden image.
+//!
+//! The database is read from a variable, so it is bounds checked as it is walked. Iteration stops at the first list
+//! that is malformed; [`validate`] reports whether a database is well-formed, for callers that must not miss an
+//! entry, such as a `dbx` check.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/32_Secure_Boot_and_Driver_Signing.html#efi-signature-data>
+//!
+//! ## Example
+//! ```
+//! use mu_pi::secure_boot::sig_db::{self, CERT_SHA256_GUID};
+//!
+//! # let dbx: &[u8] = &[];
+//! # let digest = [0u8; 32];
+//! if sig_db::is_hash_in_db(dbx, &CERT_SHA256_GUID, &digest) {
+//!   // The image is forbidden.
+//! }
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{fmt, mem::size_of, ptr};
+
+use r_efi::efi;
+
+/// Signature type of SHA-1 hashes (EFI_CERT_SHA1_GUID).
+pub const CERT_SHA1_GUID: efi::Guid =
+    efi::Guid::from_fields(0x826ca512, 0xcf10, 0x4ac9, 0xb1, 0x87, &[0xbe, 0x01, 0x49, 0x66, 0x31, 0xbd]);
+
+/// Signature type of SHA-256 hashes (EFI_CERT_SHA256_GUID).
+pub const CERT_SHA256_GUID: efi::Guid =
+    efi::Guid::from_fields(0xc1c41626, 0x504c, 0x4092, 0xac, 0xa9, &[0x41, 0xf9, 0x36, 0x93, 0x43, 0x28]);
+
+/// Signature type of SHA-384 hashes (EFI_CERT_SHA384_GUID).
+pub const CERT_SHA384_GUID: efi::Guid =
+    efi::Guid::from_fields(0xff3e5307, 0x9fd0, 0x48c9, 0x85, 0xf1, &[0x8a, 0xd5, 0x6c, 0x70, 0x1e, 0x01]);
+
+/// Signature type of SHA-512 hashes (EFI_CERT_SHA512_GUID).
+pub const CERT_SHA512_GUID: efi::Guid =
+    efi::Guid::from_fields(0x093e0fae, 0xa6c4, 0x4f50, 0x9f, 0x1b, &[0xd4, 0x1e, 0x2b, 0x89, 0xc1, 0x9a]);
+
+/// Signature type of DER-encoded X.509 certificates (EFI_CERT_X509_GUID).
+pub const CERT_X509_GUID: efi::Guid =
+    efi::Guid::from_fields(0xa5c059a1, 0x94e4, 0x4aa7, 0x87, 0xb5, &[0xab, 0x15, 0x5c, 0x2b, 0xf0, 0x72]);
+
+/// The header of a signature list (EFI_SIGNATURE_LIST). It is followed by `signature_header_size` bytes of header
+/// defined by the signature type, then by signatures of `signature_size` bytes each.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignatureList {
+    pub signature_type: efi::Guid,
+    /// Size in bytes of the list, including this structure.
+    pub signature_list_size: u32,
+    pub signature_header_size: u32,
+    /// Size in bytes of each signature, including its owner GUID.
+    pub signature_size: u32,
+}
+
+/// Errors reported when validating a signature database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SigDbError {
+    /// The signature list at `offset` extends past the end of the database.
+    Truncated { offset: usize },
+    /// The sizes in the signature list at `offset` do not describe a whole number of signatures.
+    InvalidList { offset: usize },
+}
+
+impl fmt::Display for SigDbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SigDbError::Truncated { offset } => write!(f, "signature list at {offset:#x} is truncated"),
+            SigDbError::InvalidList { offset } => write!(f, "signature list at {offset:#x} has invalid sizes"),
+        }
+    }
+}
+
+impl From<SigDbError> for efi::Status {
+    fn from(_: SigDbError) -> Self {
+        efi::Status::SECURITY_VIOLATION
+    }
+}
+
+// Walks the signature lists of a database, yielding each list header and its signatures.
+struct Lists<'a> {
+    db: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Iterator for Lists<'a> {
+    type Item = Result<(SignatureList, &'a [u8]), SigDbError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.db.len() {
+            return None;
+        }
+        let offset = self.offset;
+        let result = list_at(self.db, offset);
+        // A malformed list ends the walk, since the offset of the next list cannot be trusted.
+        self.offset = match result {
+            Ok((list, _)) => offset + list.signature_list_size as usize,
+            Err(_) => self.db.len(),
+        };
+        Some(result)
+    }
+}
+
+// Decodes the signature list at offset.
+fn list_at(db: &[u8], offset: usize) -> Result<(SignatureList, &[u8]), SigDbError> {
+    let bytes = &db[offset..];
+    if bytes.len() < size_of::<SignatureList>() {
+        Err(SigDbError::Truncated { offset })?;
+    }
+    //Safety: bytes holds at least a list header, which is read without assuming alignment.
+    let list = unsafe { ptr::read_unaligned(bytes.as_ptr() as *const SignatureList) };
+    let bytes = bytes.get(..list.signature_list_size as usize).ok_or(SigDbError::Truncated { offset })?;
+    let signatures = bytes
+        .get(size_of::<SignatureList>()..)
+        .and_then(|rest| rest.get(list.signature_header_size as usize..))
+        .ok_or(SigDbError::InvalidList { offset })?;
+    let signature_size = list.signature_size as usize;
+    if signature_size < size_of::<efi::Guid>() || signatures.len() % signature_size != 0 {
+        Err(SigDbError::InvalidList { offset })?;
+    }
+    Ok((list, signatures))
+}
+
+/// Checks that every signature list of `db` is well-formed.
+pub fn validate(db: &[u8]) -> Result<(), SigDbError> {
+    Lists { db, offset: 0 }.try_for_each(|list| list.map(|_| ()))
+}
+
+/// Returns the signature data of every signature of type `hash_guid` in `db`, without the owner GUIDs.
+pub fn all_hashes<'a>(db: &'a [u8], hash_guid: &efi::Guid) -> impl Iterator<Item = &'a [u8]> + 'a {
+    let hash_guid = *hash_guid;
+    Lists { db, offset: 0 }
+        .map_while(Result::ok)
+        .filter(move |(list, _)| list.signature_type == hash_guid)
+        .flat_map(|(list, signatures)| signatures.chunks_exact(list.signature_size as usize))
+        .map(|signature| &signature[size_of::<efi::Guid>()..])
+}
+
+/// Returns whether `db` holds a signature of type `hash_guid` whose data is `hash`.
+pub fn is_hash_in_db(db: &[u8], hash_guid: &efi::Guid, hash: &[u8]) -> bool {
+    all_hashes(db, hash_guid).any(|data| data == hash)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    use r_efi::efi;
+
+    use super::{all_hashes, is_hash_in_db, validate, SigDbError, CERT_SHA256_GUID, CERT_X509_GUID};
+
+    const OWNER: efi::Guid =
+        efi::Guid::from_fields(0x77fa9abd, 0x0359, 0x4d32, 0xbd, 0x60, &[0x28, 0xf4, 0xe7, 0x8f, 0x78, 0x4b]);
+
+    // SHA-256 of "abc" and of the empty string, from FIPS 180-2.
+    const SHA256_ABC: [u8; 32] = [
+        0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae, 0x22, 0x23, 0xb0, 0x03,
+        0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61, 0xf2, 0x00, 0x15, 0xad,
+    ];
+    const SHA256_EMPTY: [u8; 32] = [
+        0xe3, 0xb0, 0xc4, 0x42, 0x98, 0xfc, 0x1c, 0x14, 0x9a, 0xfb, 0xf4, 0xc8, 0x99, 0x6f, 0xb9, 0x24, 0x27, 0xae,
+        0x41, 0xe4, 0x64, 0x9b, 0x93, 0x4c, 0xa4, 0x95, 0x99, 0x1b, 0x78, 0x52, 0xb8, 0x55,
+    ];
+
+    fn signature_list(signature_type: &efi::Guid, header: &[u8], signatures: &[&[u8]]) -> Vec<u8> {
+        let signature_size = 16 + signatures.first().map_or(0, |data| data.len());
+        let list_size = 28 + header.len() + signatures.len() * signature_size;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(signature_type.as_bytes());
+        bytes.extend_from_slice(&(list_size as u32).to_le_bytes());
+        bytes.extend_from_slice(&(header.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(signature_size as u32).to_le_bytes());
+        bytes.extend_from_slice(header);
+        for data in signatures {
+            bytes.extend_from_slice(OWNER.as_bytes());
+            bytes.extend_from_slice(data);
+        }
+        bytes
+    }
+
+    fn dbx() -> Vec<u8> {
+        let mut db = signature_list(&CERT_X509_GUID, &[], &[&[0x30, 0x82, 0x01, 0x0a]]);
+        db.extend(signature_list(&CERT_SHA256_GUID, &[], &[&SHA256_ABC]));
+        db.extend(signature_list(&CERT_SHA256_GUID, &[0xaa; 4], &[&[0x11; 32], &SHA256_EMPTY]));
+        db
+    }
+
+    #[test]
+    fn hashes_should_be_found() {
+        let db = dbx();
+        assert_eq!(validate(&db), Ok(()));
+        assert!(is_hash_in_db(&db, &CERT_SHA256_GUID, &SHA256_ABC));
+        assert!(is_hash_in_db(&db, &CERT_SHA256_GUID, &SHA256_EMPTY));
+        assert!(!is_hash_in_db(&db, &CERT_SHA256_GUID, &[0x22; 32]));
+        // The certificate is not a SHA-256 hash, and a prefix of a hash does not match.
+        assert!(!is_hash_in_db(&db, &CERT_SHA256_GUID, &[0x30, 0x82, 0x01, 0x0a]));
+        assert!(is_hash_in_db(&db, &CERT_X509_GUID, &[0x30, 0x82, 0x01, 0x0a]));
+        assert!(!is_hash_in_db(&db, &CERT_SHA256_GUID, &SHA256_ABC[..16]));
+
+        let hashes: Vec<&[u8]> = all_hashes(&db, &CERT_SHA256_GUID).collect();
+        assert_eq!(hashes, [&SHA256_ABC[..], &[0x11; 32][..], &SHA256_EMPTY[..]]);
+        assert_eq!(all_hashes(&[], &CERT_SHA256_GUID).count(), 0);
+    }
+
+    #[test]
+    fn malformed_lists_should_end_iteration() {
+        let db = dbx();
+        let first = 28 + 16 + 4;
+        let second = first + 28 + 16 + 32;
+
+        // Truncated in the second list: only the certificate list is walked.
+        assert_eq!(validate(&db[..first + 10]), Err(SigDbError::Truncated { offset: first }));
+        assert_eq!(validate(&db[..second - 1]), Err(SigDbError::Truncated { offset: first }));
+        assert!(!is_hash_in_db(&db[..second - 1], &CERT_SHA256_GUID, &SHA256_ABC));
+
+        // A signature size that does not divide the signatures.
+        let mut bad = db.clone();
+        bad[first + 24] = 47;
+        assert_eq!(validate(&bad), Err(SigDbError::InvalidList { offset: first }));
+        assert_eq!(all_hashes(&bad, &CERT_SHA256_GUID).count(), 0);
+
+        // A signature size smaller than the owner GUID, and a header larger than the list.
+        for (field, value) in [(24, 0u32), (24, 8), (20, 0x1000), (16, 27)] {
+            let mut bad = db.clone();
+            bad[first + field..first + field + 4].copy_from_slice(&value.to_le_bytes());
+            assert!(validate(&bad).is_err(), "field {field} = {value}");
+            assert!(!is_hash_in_db(&bad, &CERT_SHA256_GUID, &SHA256_EMPTY));
+        }
+
+        // A list size of u32::MAX must not overflow.
+        let mut bad = db.clone();
+        bad[first + 16..first + 20].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(validate(&bad), Err(SigDbError::Truncated { offset: first }));
+    }
+}
@@ -0,0 +1,20 @@
+//! System Management Mode (SMM) Support
+//!
+//! Support code for drivers executing in System Management Mode (SMM), which the PI Specification also calls
+//! Management Mode (MM). The protocols these drivers consume are defined in [`crate::protocols::smm`].
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V4_Overview.html>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+pub mod handler;
+pub mod notify;
+pub mod smram;
+#[cfg(target_arch = "x86_64")]
+pub mod smrr;
+pub mod system_table;
@@ -0,0 +1,346 @@
+//! SMM Child Dispatch Handlers
+//!
+//! Registrations of SMI handlers with an SMM child dispatch protocol that are unregistered when dropped. The dispatch
+//! context type a handler receives, such as [`SmmSwContext`], selects the dispatch protocol it is registered with.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V4_Child_Dispatch_Protocols.html>
+//!
+//! ## Example
+//! ```no_run
+//! use core::ffi::c_void;
+//! use mu_pi::{protocols::smm::sw_dispatch2::{self, SmmSwContext}, smm::handler::SmmHandlerBuilder};
+//! use r_efi::efi;
+//!
+//! extern "efiapi" fn on_swsmi(
+//!   _handle: efi::Handle,
+//!   context: *const SmmSwContext,
+//!   _comm_buffer: *mut c_void,
+//!   _comm_buffer_size: *mut usize,
+//! ) -> efi::Status {
+//!   efi::Status::SUCCESS
+//! }
+//!
+//! # let sw_dispatch: &sw_dispatch2::Protocol = unsafe { &*core::ptr::null() };
+//! let handler = SmmHandlerBuilder::new(sw_dispatch, on_swsmi).with_input_value(0xA0).build().unwrap();
+//! // ... the handler runs on each software SMI with value 0xA0 until it is dropped.
+//! drop(handler);
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{ffi::c_void, marker::PhantomData, mem::ManuallyDrop, ptr};
+
+use r_efi::efi;
+
+use crate::protocols::smm::{
+    sw_dispatch2::{self, SmmSwContext, SmmSwRegisterContext},
+    MmHandlerEntryPoint,
+};
+
+/// Handler for SMIs delivered with the dispatch context `C`.
+///
+/// This is EFI_MM_HANDLER_ENTRY_POINT with the context pointer typed by the dispatch protocol the handler is
+/// registered with.
+pub type SmmHandlerCallback<C> = extern "efiapi" fn(
+    dispatch_handle: efi::Handle,
+    context: *const C,
+    comm_buffer: *mut c_void,
+    comm_buffer_size: *mut usize,
+) -> efi::Status;
+
+/// The context an SMM child dispatch protocol passes to its handlers.
+pub trait DispatchContext: Sized {
+    /// The dispatch protocol that delivers this context.
+    type Protocol;
+    /// What a handler is registered for, such as the software SMI input value.
+    type RegisterContext: Copy;
+
+    /// Calls the Register() service of `protocol`, which may update `register_context`.
+    fn register(
+        protocol: &Self::Protocol,
+        callback: SmmHandlerCallback<Self>,
+        register_context: &mut Self::RegisterContext,
+        dispatch_handle: &mut efi::Handle,
+    ) -> efi::Status;
+
+    /// Calls the UnRegister() service of `protocol`.
+    fn unregister(protocol: &Self::Protocol, dispatch_handle: efi::Handle) -> efi::Status;
+}
+
+impl DispatchContext for SmmSwContext {
+    type Protocol = sw_dispatch2::Protocol;
+    type RegisterContext = SmmSwRegisterContext;
+
+    fn register(
+        protocol: &Self::Protocol,
+        callback: SmmHandlerCallback<Self>,
+        register_context: &mut Self::RegisterContext,
+        dispatch_handle: &mut efi::Handle,
+    ) -> efi::Status {
+        //Safety: the two function types differ only in the type behind the context pointer.
+        let entry_point = unsafe { core::mem::transmute::<SmmHandlerCallback<Self>, MmHandlerEntryPoint>(callback) };
+        (protocol.register)(protocol, entry_point, register_context, dispatch_handle)
+    }
+
+    fn unregister(protocol: &Self::Protocol, dispatch_handle: efi::Handle) -> efi::Status {
+        (protocol.unregister)(protocol, dispatch_handle)
+    }
+}
+
+/// A handler registered with an SMM child dispatch protocol, unregistered when dropped.
+#[derive(Debug)]
+pub struct SmmHandler<'a, C: DispatchContext> {
+    handle: efi::Handle,
+    protocol: &'a C::Protocol,
+    register_context: C::RegisterContext,
+    _context: PhantomData<C>,
+}
+
+impl<'a, C: DispatchContext> SmmHandler<'a, C> {
+    /// Registers `callback` with `protocol` for `register_context`.
+    pub fn new(
+        protocol: &'a C::Protocol,
+        callback: SmmHandlerCallback<C>,
+        mut register_context: C::RegisterContext,
+    ) -> Result<Self, efi::Status> {
+        let mut handle = ptr::null_mut();
+        match C::register(protocol, callback, &mut register_context, &mut handle) {
+            efi::Status::SUCCESS => Ok(Self { handle, protocol, register_context, _context: PhantomData }),
+            status => Err(status),
+        }
+    }
+
+    /// Returns the dispatch handle of the registration.
+    pub fn handle(&self) -> efi::Handle {
+        self.handle
+    }
+
+    /// Returns the register context as updated by the dispatch protocol, for example with the software SMI input
+    /// value it assigned.
+    pub fn register_context(&self) -> C::RegisterContext {
+        self.register_context
+    }
+
+    /// Unregisters the handler, returning the error reported by the dispatch protocol, which dropping ignores.
+    pub fn unregister(self) -> Result<(), efi::Status> {
+        let this = ManuallyDrop::new(self);
+        match C::unregister(this.protocol, this.handle) {
+            efi::Status::SUCCESS => Ok(()),
+            status => Err(status),
+        }
+    }
+}
+
+impl<C: DispatchContext> Drop for SmmHandler<'_, C> {
+    fn drop(&mut self) {
+        let _ = C::unregister(self.protocol, self.handle);
+    }
+}
+
+/// Builds an [`SmmHandler`].
+#[derive(Debug)]
+pub struct SmmHandlerBuilder<'a, C: DispatchContext> {
+    protocol: &'a C::Protocol,
+    callback: SmmHandlerCallback<C>,
+    register_context: Option<C::RegisterContext>,
+}
+
+impl<'a, C: DispatchContext> SmmHandlerBuilder<'a, C> {
+    /// Starts building the registration of `callback` with `protocol`.
+    pub fn new(protocol: &'a C::Protocol, callback: SmmHandlerCallback<C>) -> Self {
+        Self { protocol, callback, register_context: None }
+    }
+
+    /// Sets what the handler is registered for.
+    pub fn with_register_context(mut self, register_context: C::RegisterContext) -> Self {
+        self.register_context = Some(register_context);
+        self
+    }
+
+    /// Registers the handler. Fails with `INVALID_PARAMETER` if no register context was set.
+    pub fn build(self) -> Result<SmmHandler<'a, C>, efi::Status> {
+        let register_context = self.register_context.ok_or(efi::Status::INVALID_PARAMETER)?;
+        SmmHandler::new(self.protocol, self.callback, register_context)
+    }
+}
+
+impl SmmHandlerBuilder<'_, SmmSwContext> {
+    /// Registers the handler for the software SMI input `value`, or for an unused value chosen by the dispatch
+    /// protocol if `value` is [`sw_dispatch2::ANY_SW_SMI_INPUT_VALUE`].
+    pub fn with_input_value(self, value: usize) -> Self {
+        self.with_register_context(SmmSwRegisterContext { sw_mm_input_value: value })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{cell::RefCell, ffi::c_void, ptr};
+
+    use r_efi::efi;
+
+    use super::{SmmHandler, SmmHandlerBuilder};
+    use crate::protocols::smm::{
+        sw_dispatch2::{Protocol, SmmSwContext, SmmSwRegisterContext, ANY_SW_SMI_INPUT_VALUE},
+        MmHandlerEntryPoint,
+    };
+
+    // (dispatch handle, input value, handler)
+    type Registration = (usize, usize, MmHandlerEntryPoint);
+
+    std::thread_local! {
+        static REGISTRATIONS: RefCell<Vec<Registration>> = RefCell::new(Vec::new());
+        static NEXT_HANDLE: RefCell<usize> = RefCell::new(1);
+        static CALLS: RefCell<Vec<(usize, SmmSwContext)>> = RefCell::new(Vec::new());
+    }
+
+    // Mock software SMI dispatcher: values from 1 to maximum_sw_mmi_value are free unless registered.
+    extern "efiapi" fn mock_register(
+        this: *const Protocol,
+        dispatch_function: MmHandlerEntryPoint,
+        register_context: *mut SmmSwRegisterContext,
+        dispatch_handle: *mut efi::Handle,
+    ) -> efi::Status {
+        let maximum = unsafe { (*this).maximum_sw_mmi_value };
+        let context = unsafe { &mut *register_context };
+        REGISTRATIONS.with(|registrations| {
+            let mut registrations = registrations.borrow_mut();
+            let in_use = |value: usize| registrations.iter().any(|(_, registered, _)| *registered == value);
+            if context.sw_mm_input_value == ANY_SW_SMI_INPUT_VALUE {
+                match (1..=maximum).find(|value| !in_use(*value)) {
+                    Some(value) => context.sw_mm_input_value = value,
+                    None => return efi::Status::OUT_OF_RESOURCES,
+                }
+            } else if context.sw_mm_input_value > maximum || in_use(context.sw_mm_input_value) {
+                return efi::Status::INVALID_PARAMETER;
+            }
+            let handle = NEXT_HANDLE.with(|next| next.replace_with(|next| *next + 1));
+            registrations.push((handle, context.sw_mm_input_value, dispatch_function));
+            unsafe { *dispatch_handle = handle as efi::Handle };
+            efi::Status::SUCCESS
+        })
+    }
+
+    extern "efiapi" fn mock_unregister(_: *const Protocol, dispatch_handle: efi::Handle) -> efi::Status {
+        REGISTRATIONS.with(|registrations| {
+            let mut registrations = registrations.borrow_mut();
+            match registrations.iter().position(|(handle, _, _)| *handle == dispatch_handle as usize) {
+                Some(index) => {
+                    registrations.remove(index);
+                    efi::Status::SUCCESS
+                }
+                None => efi::Status::INVALID_PARAMETER,
+            }
+        })
+    }
+
+    fn mock_protocol(maximum_sw_mmi_value: usize) -> Protocol {
+        Protocol { register: mock_register, unregister: mock_unregister, maximum_sw_mmi_value }
+    }
+
+    fn registered_values() -> Vec<usize> {
+        REGISTRATIONS.with(|registrations| registrations.borrow().iter().map(|(_, value, _)| *value).collect())
+    }
+
+    // Triggers a software SMI as the dispatcher would, returning the status of the handler if one is registered.
+    fn trigger(command_port: u8, data_port: u8) -> Option<efi::Status> {
+        let registration = REGISTRATIONS.with(|registrations| {
+            registrations.borrow().iter().find(|(_, value, _)| *value == command_port as usize).copied()
+        });
+        let (handle, _, handler) = registration?;
+        let context = SmmSwContext { swmi_cpu_index: 1, command_port, data_port };
+        Some(handler(
+            handle as efi::Handle,
+            &context as *const SmmSwContext as *const c_void,
+            ptr::null_mut(),
+            ptr::null_mut(),
+        ))
+    }
+
+    extern "efiapi" fn on_swsmi(
+        dispatch_handle: efi::Handle,
+        context: *const SmmSwContext,
+        _: *mut c_void,
+        _: *mut usize,
+    ) -> efi::Status {
+        CALLS.with(|calls| calls.borrow_mut().push((dispatch_handle as usize, unsafe { *context })));
+        efi::Status::SUCCESS
+    }
+
+    fn take_calls() -> Vec<(usize, SmmSwContext)> {
+        CALLS.with(|calls| calls.borrow_mut().drain(..).collect())
+    }
+
+    #[test]
+    fn handler_should_unregister_when_dropped() {
+        let sw_dispatch = mock_protocol(0xFF);
+        let handler =
+            SmmHandler::new(&sw_dispatch, on_swsmi, SmmSwRegisterContext { sw_mm_input_value: 0xA0 }).unwrap();
+        assert_eq!(registered_values(), vec![0xA0]);
+        assert_eq!(handler.register_context().sw_mm_input_value, 0xA0);
+
+        assert_eq!(trigger(0xA0, 0x5A), Some(efi::Status::SUCCESS));
+        assert_eq!(
+            take_calls(),
+            vec![(handler.handle() as usize, SmmSwContext { swmi_cpu_index: 1, command_port: 0xA0, data_port: 0x5A })]
+        );
+        assert_eq!(trigger(0xA1, 0), None);
+
+        drop(handler);
+        assert!(registered_values().is_empty());
+        assert_eq!(trigger(0xA0, 0), None);
+        assert!(take_calls().is_empty());
+    }
+
+    #[test]
+    fn failed_registration_should_return_status() {
+        let sw_dispatch = mock_protocol(0xFF);
+        let handler = SmmHandlerBuilder::new(&sw_dispatch, on_swsmi).with_input_value(0x10).build().unwrap();
+
+        let in_use = SmmHandlerBuilder::new(&sw_dispatch, on_swsmi).with_input_value(0x10).build();
+        assert_eq!(in_use.err(), Some(efi::Status::INVALID_PARAMETER));
+        let out_of_range = SmmHandlerBuilder::new(&sw_dispatch, on_swsmi).with_input_value(0x100).build();
+        assert_eq!(out_of_range.err(), Some(efi::Status::INVALID_PARAMETER));
+        // Dropping the failed registrations must not unregister the first one.
+        assert_eq!(registered_values(), vec![0x10]);
+
+        assert_eq!(handler.unregister(), Ok(()));
+        assert!(registered_values().is_empty());
+    }
+
+    #[test]
+    fn builder_should_require_register_context() {
+        let sw_dispatch = mock_protocol(0xFF);
+        let missing = SmmHandlerBuilder::<SmmSwContext>::new(&sw_dispatch, on_swsmi).build();
+        assert_eq!(missing.err(), Some(efi::Status::INVALID_PARAMETER));
+        assert!(registered_values().is_empty());
+    }
+
+    #[test]
+    fn builder_should_return_assigned_input_value() {
+        let sw_dispatch = mock_protocol(2);
+        let first =
+            SmmHandlerBuilder::new(&sw_dispatch, on_swsmi).with_input_value(ANY_SW_SMI_INPUT_VALUE).build().unwrap();
+        let second = SmmHandlerBuilder::new(&sw_dispatch, on_swsmi)
+            .with_register_context(SmmSwRegisterContext { sw_mm_input_value: ANY_SW_SMI_INPUT_VALUE })
+            .build()
+            .unwrap();
+        assert_eq!(first.register_context().sw_mm_input_value, 1);
+        assert_eq!(second.register_context().sw_mm_input_value, 2);
+        assert_ne!(first.handle(), second.handle());
+
+        let exhausted = SmmHandlerBuilder::new(&sw_dispatch, on_swsmi).with_input_value(ANY_SW_SMI_INPUT_VALUE).build();
+        assert_eq!(exhausted.err(), Some(efi::Status::OUT_OF_RESOURCES));
+
+        assert_eq!(trigger(2, 0x33), Some(efi::Status::SUCCESS));
+        assert_eq!(take_calls()[0].0, second.handle() as usize);
+        drop(first);
+        assert_eq!(registered_values(), vec![2]);
+        drop(second);
+        assert!(registered_values().is_empty());
+    }
+}
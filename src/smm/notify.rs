@@ -0,0 +1,235 @@
+//! SMM Protocol Notifications
+//!
+//! Registrations of functions called when an interface is installed in the SMM protocol database, made with the
+//! MmRegisterProtocolNotify() service of the [`SmmSystemTable`] and removed when dropped. SMM has no events, so a
+//! driver that needs a protocol installed after it is dispatched registers a notification for it instead.
+//!
+//! The PI Specification has no separate service to unregister a notification: MmRegisterProtocolNotify() called with
+//! no function and an existing registration key removes that registration, which is what dropping a
+//! [`SmmNotifyRegistration`] does.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V4_MM_System_Table.html#mmregisterprotocolnotify>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{ffi::c_void, mem::ManuallyDrop, ptr};
+
+use r_efi::efi;
+
+use super::system_table::{MmNotifyFn, SmmSystemTable};
+
+/// Function called with the protocol GUID, the interface and the handle of each interface installed for the protocol
+/// it is registered for.
+pub type SmmNotifyCallback = MmNotifyFn;
+
+/// A protocol notification registered with the SMM System Table, unregistered when dropped.
+#[derive(Debug)]
+pub struct SmmNotifyRegistration<'a> {
+    smst: &'a SmmSystemTable,
+    protocol: efi::Guid,
+    registration: *mut c_void,
+}
+
+impl SmmNotifyRegistration<'_> {
+    /// Returns the GUID of the protocol the notification is registered for.
+    pub fn protocol(&self) -> efi::Guid {
+        self.protocol
+    }
+
+    /// Returns the registration key returned by MmRegisterProtocolNotify().
+    pub fn registration(&self) -> *mut c_void {
+        self.registration
+    }
+
+    /// Unregisters the notification, returning the error reported by the SMM System Table, which dropping ignores.
+    pub fn unregister(self) -> Result<(), efi::Status> {
+        let mut this = ManuallyDrop::new(self);
+        this.remove()
+    }
+
+    fn remove(&mut self) -> Result<(), efi::Status> {
+        match (self.smst.mm_register_protocol_notify)(&self.protocol, None, &mut self.registration) {
+            efi::Status::SUCCESS => Ok(()),
+            status => Err(status),
+        }
+    }
+}
+
+impl Drop for SmmNotifyRegistration<'_> {
+    fn drop(&mut self) {
+        let _ = self.remove();
+    }
+}
+
+/// Registers `callback` to be called each time an interface is installed for the protocol `guid`.
+pub fn register_notify<'a>(
+    smst: &'a SmmSystemTable,
+    guid: &efi::Guid,
+    callback: SmmNotifyCallback,
+) -> Result<SmmNotifyRegistration<'a>, efi::Status> {
+    let mut registration = ptr::null_mut();
+    match (smst.mm_register_protocol_notify)(guid, Some(callback), &mut registration) {
+        efi::Status::SUCCESS => Ok(SmmNotifyRegistration { smst, protocol: *guid, registration }),
+        status => Err(status),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        cell::RefCell,
+        ffi::c_void,
+        mem::{self, size_of},
+        ptr,
+    };
+
+    use r_efi::efi;
+
+    use super::{register_notify, SmmNotifyCallback};
+    use crate::smm::system_table::{SmmSystemTable, SMM_SYSTEM_TABLE_SIGNATURE};
+
+    const PROTOCOL: efi::Guid =
+        efi::Guid::from_fields(0x18a3c6dc, 0x5eea, 0x48c8, 0xa1, 0xc1, &[0xb5, 0x33, 0x89, 0xf9, 0x89, 0x99]);
+    const FULL_PROTOCOL: efi::Guid = efi::Guid::from_fields(1, 2, 3, 4, 5, &[6, 7, 8, 9, 10, 11]);
+
+    // (registration key, protocol, function)
+    type Notification = (usize, efi::Guid, SmmNotifyCallback);
+
+    std::thread_local! {
+        static NOTIFICATIONS: RefCell<Vec<Notification>> = RefCell::new(Vec::new());
+        static NEXT_KEY: RefCell<usize> = RefCell::new(0x1000);
+        static CALLS: RefCell<Vec<(efi::Guid, usize, usize)>> = RefCell::new(Vec::new());
+    }
+
+    // Mock MmRegisterProtocolNotify(); registrations for FULL_PROTOCOL fail.
+    extern "efiapi" fn mock_register_protocol_notify(
+        protocol: *const efi::Guid,
+        function: Option<SmmNotifyCallback>,
+        registration: *mut *mut c_void,
+    ) -> efi::Status {
+        let protocol = unsafe { *protocol };
+        NOTIFICATIONS.with(|notifications| {
+            let mut notifications = notifications.borrow_mut();
+            match function {
+                Some(_) if protocol == FULL_PROTOCOL => efi::Status::OUT_OF_RESOURCES,
+                Some(function) => {
+                    let key = NEXT_KEY.with(|next| next.replace_with(|next| *next + 0x10));
+                    notifications.push((key, protocol, function));
+                    unsafe { *registration = key as *mut c_void };
+                    efi::Status::SUCCESS
+                }
+                None => {
+                    let key = unsafe { *registration } as usize;
+                    match notifications.iter().position(|(registered, _, _)| *registered == key) {
+                        Some(index) => {
+                            notifications.remove(index);
+                            efi::Status::SUCCESS
+                        }
+                        None => efi::Status::NOT_FOUND,
+                    }
+                }
+            }
+        })
+    }
+
+    extern "efiapi" fn unused() -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+
+    // An SMM System Table whose only service is MmRegisterProtocolNotify(). Every other service pointer is set to a
+    // valid function so that the buffer may be viewed as a table; those services are never called.
+    fn mock_smst() -> [usize; size_of::<SmmSystemTable>() / size_of::<usize>()] {
+        let mut table = [unused as usize; size_of::<SmmSystemTable>() / size_of::<usize>()];
+        let table_ptr = table.as_mut_ptr() as *mut SmmSystemTable;
+        unsafe {
+            ptr::addr_of_mut!((*table_ptr).hdr).write(mem::zeroed());
+            (*table_ptr).hdr.signature = SMM_SYSTEM_TABLE_SIGNATURE;
+            (*table_ptr).mm_register_protocol_notify = mock_register_protocol_notify;
+        }
+        table
+    }
+
+    // Installs an interface as the SMM core would, calling the notifications registered for the protocol.
+    fn install(protocol: efi::Guid, interface: usize, handle: usize) {
+        let notifications: Vec<_> = NOTIFICATIONS.with(|notifications| {
+            notifications.borrow().iter().filter(|(_, registered, _)| *registered == protocol).copied().collect()
+        });
+        for (_, _, function) in notifications {
+            assert_eq!(function(&protocol, interface as *mut c_void, handle as efi::Handle), efi::Status::SUCCESS);
+        }
+    }
+
+    extern "efiapi" fn on_install(
+        protocol: *const efi::Guid,
+        interface: *mut c_void,
+        handle: efi::Handle,
+    ) -> efi::Status {
+        CALLS.with(|calls| calls.borrow_mut().push((unsafe { *protocol }, interface as usize, handle as usize)));
+        efi::Status::SUCCESS
+    }
+
+    fn take_calls() -> Vec<(efi::Guid, usize, usize)> {
+        CALLS.with(|calls| calls.borrow_mut().drain(..).collect())
+    }
+
+    fn registered() -> usize {
+        NOTIFICATIONS.with(|notifications| notifications.borrow().len())
+    }
+
+    #[test]
+    fn notification_should_be_called_until_dropped() {
+        let table = mock_smst();
+        let smst = unsafe { &*(table.as_ptr() as *const SmmSystemTable) };
+
+        let registration = register_notify(smst, &PROTOCOL, on_install).unwrap();
+        assert_eq!(registration.protocol(), PROTOCOL);
+        assert!(!registration.registration().is_null());
+        assert_eq!(registered(), 1);
+
+        install(PROTOCOL, 0xA000, 0xB000);
+        install(FULL_PROTOCOL, 0xA010, 0xB010);
+        assert_eq!(take_calls(), vec![(PROTOCOL, 0xA000, 0xB000)]);
+
+        drop(registration);
+        assert_eq!(registered(), 0);
+        install(PROTOCOL, 0xA020, 0xB020);
+        assert!(take_calls().is_empty());
+    }
+
+    #[test]
+    fn registrations_should_be_removed_independently() {
+        let table = mock_smst();
+        let smst = unsafe { &*(table.as_ptr() as *const SmmSystemTable) };
+
+        let first = register_notify(smst, &PROTOCOL, on_install).unwrap();
+        let second = register_notify(smst, &PROTOCOL, on_install).unwrap();
+        assert_ne!(first.registration(), second.registration());
+        install(PROTOCOL, 1, 2);
+        assert_eq!(take_calls().len(), 2);
+
+        assert_eq!(first.unregister(), Ok(()));
+        install(PROTOCOL, 3, 4);
+        assert_eq!(take_calls(), vec![(PROTOCOL, 3, 4)]);
+        drop(second);
+        assert_eq!(registered(), 0);
+    }
+
+    #[test]
+    fn failures_should_be_reported() {
+        let table = mock_smst();
+        let smst = unsafe { &*(table.as_ptr() as *const SmmSystemTable) };
+
+        assert_eq!(register_notify(smst, &FULL_PROTOCOL, on_install).err(), Some(efi::Status::OUT_OF_RESOURCES));
+        assert_eq!(registered(), 0);
+
+        // A registration the table no longer knows cannot be removed.
+        let registration = register_notify(smst, &PROTOCOL, on_install).unwrap();
+        NOTIFICATIONS.with(|notifications| notifications.borrow_mut().clear());
+        assert_eq!(registration.unregister(), Err(efi::Status::NOT_FOUND));
+    }
+}
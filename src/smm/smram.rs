@@ -0,0 +1,421 @@
+//! SMRAM Ranges
+//!
+//! Tracks the SMRAM ranges reported by the SMM Access 2 Protocol and their state. SMRAM moves from open, where it is
+//! visible outside of SMM, to closed, and finally to locked, after which it cannot be opened or closed until the next
+//! reset. [`SmramManager`] checks each transition before asking the protocol for it, so that a driver learns it is
+//! trying to open locked SMRAM without depending on how the platform reports it.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V4_SMM_Access_Protocol.html>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::{fmt, mem::size_of, ptr};
+
+use r_efi::efi;
+
+use crate::protocols::smm::access2::{self, SmramDescriptor, EFI_SMRAM_CLOSED, EFI_SMRAM_LOCKED, EFI_SMRAM_OPEN};
+
+const STATE_MASK: u64 = EFI_SMRAM_OPEN | EFI_SMRAM_CLOSED | EFI_SMRAM_LOCKED;
+
+/// A range of SMRAM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmramRange {
+    pub base: u64,
+    pub size: u64,
+    /// The `EFI_SMRAM_*` state and capability bits of the range, as in [`SmramDescriptor::region_state`].
+    pub attributes: u64,
+}
+
+impl SmramRange {
+    /// Returns the address just past the end of the range. [`SmramManager`] rejects ranges where this overflows.
+    pub fn end(&self) -> u64 {
+        self.base.wrapping_add(self.size)
+    }
+
+    /// Returns whether `address` is in the range.
+    pub fn contains(&self, address: u64) -> bool {
+        address >= self.base && address - self.base < self.size
+    }
+
+    /// Returns whether the range shares any address with `other`.
+    pub fn overlaps(&self, other: &SmramRange) -> bool {
+        self.base < other.end() && other.base < self.end()
+    }
+
+    /// Returns the state given by the attributes; a locked range is also closed.
+    pub fn state(&self) -> SmramRegionState {
+        if self.attributes & EFI_SMRAM_LOCKED != 0 {
+            SmramRegionState::Locked
+        } else if self.attributes & EFI_SMRAM_OPEN != 0 {
+            SmramRegionState::Open
+        } else {
+            SmramRegionState::Closed
+        }
+    }
+
+    fn set_state(&mut self, state: SmramRegionState) {
+        let bits = match state {
+            SmramRegionState::Open => EFI_SMRAM_OPEN,
+            SmramRegionState::Closed => EFI_SMRAM_CLOSED,
+            SmramRegionState::Locked => EFI_SMRAM_CLOSED | EFI_SMRAM_LOCKED,
+        };
+        self.attributes = (self.attributes & !STATE_MASK) | bits;
+    }
+}
+
+impl From<SmramDescriptor> for SmramRange {
+    fn from(descriptor: SmramDescriptor) -> Self {
+        Self { base: descriptor.physical_start, size: descriptor.physical_size, attributes: descriptor.region_state }
+    }
+}
+
+/// The visibility of an SMRAM range outside of SMM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmramRegionState {
+    Open,
+    Closed,
+    Locked,
+}
+
+/// Errors reported when tracking SMRAM ranges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmramError {
+    /// The range at `base` has size zero, or extends past the end of the address space.
+    InvalidRange { base: u64, size: u64 },
+    /// The ranges at `first` and `second` overlap.
+    OverlappingRanges { first: u64, second: u64 },
+    /// `address` is not inside an SMRAM range, or is at the start of one.
+    NotInRange(u64),
+    /// A range is locked, so SMRAM cannot be opened or closed.
+    Locked,
+    /// A range is open, so SMRAM cannot be locked.
+    Open,
+    /// The SMM Access 2 Protocol reported an error.
+    Access(efi::Status),
+}
+
+impl fmt::Display for SmramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SmramError::InvalidRange { base, size } => write!(f, "invalid SMRAM range {base:#x} size {size:#x}"),
+            SmramError::OverlappingRanges { first, second } => {
+                write!(f, "SMRAM ranges at {first:#x} and {second:#x} overlap")
+            }
+            SmramError::NotInRange(address) => write!(f, "{address:#x} is not inside an SMRAM range"),
+            SmramError::Locked => write!(f, "SMRAM is locked"),
+            SmramError::Open => write!(f, "SMRAM is open"),
+            SmramError::Access(status) => write!(f, "SMM Access 2 Protocol returned {status:?}"),
+        }
+    }
+}
+
+impl From<SmramError> for efi::Status {
+    fn from(error: SmramError) -> Self {
+        match error {
+            SmramError::InvalidRange { .. } | SmramError::OverlappingRanges { .. } | SmramError::NotInRange(_) => {
+                efi::Status::INVALID_PARAMETER
+            }
+            SmramError::Locked | SmramError::Open => efi::Status::ACCESS_DENIED,
+            SmramError::Access(status) => status,
+        }
+    }
+}
+
+/// The SMRAM ranges of the platform, sorted by base address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmramManager {
+    ranges: Vec<SmramRange>,
+}
+
+impl SmramManager {
+    /// Tracks `ranges`, which must not be empty, must end below the top of the address space, and must not overlap.
+    pub fn new(mut ranges: Vec<SmramRange>) -> Result<Self, SmramError> {
+        for range in &ranges {
+            if range.size == 0 || range.base.checked_add(range.size).is_none() {
+                Err(SmramError::InvalidRange { base: range.base, size: range.size })?;
+            }
+        }
+        ranges.sort_by_key(|range| range.base);
+        for pair in ranges.windows(2) {
+            if pair[0].overlaps(&pair[1]) {
+                Err(SmramError::OverlappingRanges { first: pair[0].base, second: pair[1].base })?;
+            }
+        }
+        Ok(Self { ranges })
+    }
+
+    /// Tracks the ranges returned by GetCapabilities() of `access`.
+    pub fn from_access(access: &access2::Protocol) -> Result<Self, SmramError> {
+        let mut size = 0;
+        let mut descriptors: Vec<SmramDescriptor> = Vec::new();
+        loop {
+            match (access.get_capabilities)(access, &mut size, descriptors.as_mut_ptr()) {
+                efi::Status::SUCCESS => break,
+                efi::Status::BUFFER_TOO_SMALL if size > descriptors.capacity() * size_of::<SmramDescriptor>() => {
+                    descriptors.reserve_exact(size.div_ceil(size_of::<SmramDescriptor>()));
+                }
+                status => Err(SmramError::Access(status))?,
+            }
+        }
+        //Safety: GetCapabilities() wrote size bytes of descriptors, which fit in the capacity.
+        unsafe { descriptors.set_len(size / size_of::<SmramDescriptor>()) };
+        Self::new(descriptors.into_iter().map(SmramRange::from).collect())
+    }
+
+    /// Returns the ranges, sorted by base address.
+    pub fn ranges(&self) -> &[SmramRange] {
+        &self.ranges
+    }
+
+    /// Returns the range that contains `address`.
+    pub fn find_range_containing(&self, address: u64) -> Option<&SmramRange> {
+        // The ranges are sorted and do not overlap, so only the last range starting at or below address can match.
+        let index = self.ranges.partition_point(|range| range.base <= address).checked_sub(1)?;
+        Some(&self.ranges[index]).filter(|range| range.contains(address))
+    }
+
+    /// Splits the range containing `address` into the part below it and the part from it on, both with the
+    /// attributes of the original range.
+    pub fn split_at(&mut self, address: u64) -> Result<(), SmramError> {
+        let index = self
+            .ranges
+            .iter()
+            .position(|range| range.contains(address) && range.base != address)
+            .ok_or(SmramError::NotInRange(address))?;
+        let range = &mut self.ranges[index];
+        let upper = SmramRange { base: address, size: range.end() - address, attributes: range.attributes };
+        range.size = address - range.base;
+        self.ranges.insert(index + 1, upper);
+        Ok(())
+    }
+
+    /// Opens SMRAM, making it visible outside of SMM.
+    pub fn open_all(&mut self, access: &access2::Protocol) -> Result<(), SmramError> {
+        self.transition(access, access.open, SmramRegionState::Open)
+    }
+
+    /// Closes SMRAM, making it visible only in SMM.
+    pub fn close_all(&mut self, access: &access2::Protocol) -> Result<(), SmramError> {
+        self.transition(access, access.close, SmramRegionState::Closed)
+    }
+
+    /// Locks SMRAM, which must be closed, so that it cannot be opened until the next reset.
+    pub fn lock_all(&mut self, access: &access2::Protocol) -> Result<(), SmramError> {
+        self.transition(access, access.lock, SmramRegionState::Locked)
+    }
+
+    fn transition(
+        &mut self,
+        access: &access2::Protocol,
+        service: access2::EfiSmmAccess,
+        state: SmramRegionState,
+    ) -> Result<(), SmramError> {
+        for range in &self.ranges {
+            match (range.state(), state) {
+                (SmramRegionState::Locked, SmramRegionState::Open | SmramRegionState::Closed) => {
+                    Err(SmramError::Locked)?
+                }
+                (SmramRegionState::Open, SmramRegionState::Locked) => Err(SmramError::Open)?,
+                _ => (),
+            }
+        }
+        match service(ptr::addr_of!(*access)) {
+            efi::Status::SUCCESS => {
+                self.ranges.iter_mut().for_each(|range| range.set_state(state));
+                Ok(())
+            }
+            status => Err(SmramError::Access(status)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{cell::RefCell, mem::size_of};
+
+    use r_efi::efi;
+
+    use super::{SmramError, SmramManager, SmramRange, SmramRegionState};
+    use crate::protocols::smm::access2::{
+        Protocol, SmramDescriptor, EFI_ALLOCATED, EFI_CACHEABLE, EFI_SMRAM_CLOSED, EFI_SMRAM_LOCKED, EFI_SMRAM_OPEN,
+    };
+
+    std::thread_local! {
+        static CALLS: RefCell<Vec<&'static str>> = RefCell::new(Vec::new());
+        static FAIL: RefCell<bool> = RefCell::new(false);
+    }
+
+    const MAP: [SmramDescriptor; 2] = [
+        SmramDescriptor {
+            physical_start: 0x7f00_0000,
+            cpu_start: 0x7f00_0000,
+            physical_size: 0x1000,
+            region_state: EFI_SMRAM_CLOSED | EFI_CACHEABLE | EFI_ALLOCATED,
+        },
+        SmramDescriptor {
+            physical_start: 0x7f00_1000,
+            cpu_start: 0x7f00_1000,
+            physical_size: 0xff_f000,
+            region_state: EFI_SMRAM_CLOSED | EFI_CACHEABLE,
+        },
+    ];
+
+    fn call(name: &'static str) -> efi::Status {
+        CALLS.with(|calls| calls.borrow_mut().push(name));
+        match FAIL.with(|fail| *fail.borrow()) {
+            true => efi::Status::DEVICE_ERROR,
+            false => efi::Status::SUCCESS,
+        }
+    }
+
+    extern "efiapi" fn open(_: *const Protocol) -> efi::Status {
+        call("open")
+    }
+
+    extern "efiapi" fn close(_: *const Protocol) -> efi::Status {
+        call("close")
+    }
+
+    extern "efiapi" fn lock(_: *const Protocol) -> efi::Status {
+        call("lock")
+    }
+
+    extern "efiapi" fn get_capabilities(
+        _: *const Protocol,
+        smram_map_size: *mut usize,
+        smram_map: *mut SmramDescriptor,
+    ) -> efi::Status {
+        let size = size_of::<[SmramDescriptor; 2]>();
+        let buffer_size = unsafe { smram_map_size.replace(size) };
+        if buffer_size < size {
+            return efi::Status::BUFFER_TOO_SMALL;
+        }
+        // Report the ranges in descending order; the manager sorts them.
+        unsafe {
+            smram_map.write(MAP[1]);
+            smram_map.add(1).write(MAP[0]);
+        }
+        efi::Status::SUCCESS
+    }
+
+    fn mock_access() -> Protocol {
+        Protocol {
+            open,
+            close,
+            lock,
+            get_capabilities,
+            lock_state: efi::Boolean::FALSE,
+            open_state: efi::Boolean::FALSE,
+        }
+    }
+
+    fn take_calls() -> Vec<&'static str> {
+        CALLS.with(|calls| calls.borrow_mut().drain(..).collect())
+    }
+
+    fn range(base: u64, size: u64) -> SmramRange {
+        SmramRange { base, size, attributes: EFI_SMRAM_CLOSED }
+    }
+
+    #[test]
+    fn overlapping_ranges_should_be_rejected() {
+        // Adjacent ranges, in any order, do not overlap.
+        let manager = SmramManager::new(vec![range(0x2000, 0x1000), range(0x1000, 0x1000)]).unwrap();
+        assert_eq!(manager.ranges()[0].base, 0x1000);
+
+        for (first, second) in [
+            (range(0x1000, 0x1000), range(0x1fff, 0x10)),
+            (range(0x1000, 0x1000), range(0x1800, 0x100)),
+            (range(0x1800, 0x100), range(0x1000, 0x1000)),
+            (range(0x1000, 0x1000), range(0x1000, 0x1)),
+        ] {
+            assert!(first.overlaps(&second) && second.overlaps(&first));
+            let error = SmramManager::new(vec![first, second]).unwrap_err();
+            let (low, high) = (first.base.min(second.base), first.base.max(second.base));
+            assert!(
+                matches!(error, SmramError::OverlappingRanges { first, second } if first.min(second) == low && first.max(second) == high),
+                "{error:?}"
+            );
+        }
+        assert_eq!(
+            SmramManager::new(vec![range(0x1000, 0)]).unwrap_err(),
+            SmramError::InvalidRange { base: 0x1000, size: 0 }
+        );
+        assert_eq!(
+            SmramManager::new(vec![range(u64::MAX - 0xfff, 0x1001)]).unwrap_err(),
+            SmramError::InvalidRange { base: u64::MAX - 0xfff, size: 0x1001 }
+        );
+        // Ranges must end below the top of the address space, so that their end is representable.
+        assert!(SmramManager::new(vec![range(u64::MAX - 0xfff, 0x1000)]).is_err());
+        assert!(SmramManager::new(vec![range(u64::MAX - 0x1fff, 0x1000)]).is_ok());
+    }
+
+    #[test]
+    fn addresses_should_map_to_ranges() {
+        let mut manager = SmramManager::new(vec![range(0x1000, 0x1000), range(0x4000, 0x2000)]).unwrap();
+        assert_eq!(manager.find_range_containing(0x1000).map(|range| range.base), Some(0x1000));
+        assert_eq!(manager.find_range_containing(0x1fff).map(|range| range.base), Some(0x1000));
+        assert_eq!(manager.find_range_containing(0x5fff).map(|range| range.base), Some(0x4000));
+        for address in [0, 0xfff, 0x2000, 0x3fff, 0x6000, u64::MAX] {
+            assert!(manager.find_range_containing(address).is_none(), "{address:#x}");
+        }
+
+        manager.split_at(0x5000).unwrap();
+        assert_eq!(manager.ranges(), [range(0x1000, 0x1000), range(0x4000, 0x1000), range(0x5000, 0x1000)]);
+        assert_eq!(manager.find_range_containing(0x5000).map(|range| range.base), Some(0x5000));
+        assert_eq!(manager.split_at(0x5000), Err(SmramError::NotInRange(0x5000)));
+        assert_eq!(manager.split_at(0x3000), Err(SmramError::NotInRange(0x3000)));
+    }
+
+    #[test]
+    fn ranges_should_be_read_from_access_protocol() {
+        let access = mock_access();
+        let manager = SmramManager::from_access(&access).unwrap();
+        let expected: Vec<SmramRange> = MAP.iter().copied().map(SmramRange::from).collect();
+        assert_eq!(manager.ranges(), expected);
+        assert_eq!(manager.ranges()[0].state(), SmramRegionState::Closed);
+        assert_eq!(manager.find_range_containing(0x7f00_2000), Some(&expected[1]));
+    }
+
+    #[test]
+    fn state_transitions_should_be_checked() {
+        let access = mock_access();
+        let mut manager = SmramManager::from_access(&access).unwrap();
+
+        manager.open_all(&access).unwrap();
+        assert!(manager.ranges().iter().all(|range| range.state() == SmramRegionState::Open));
+        // Capability bits are kept.
+        assert_eq!(manager.ranges()[0].attributes, EFI_SMRAM_OPEN | EFI_CACHEABLE | EFI_ALLOCATED);
+        assert_eq!(manager.lock_all(&access), Err(SmramError::Open));
+
+        manager.close_all(&access).unwrap();
+        manager.lock_all(&access).unwrap();
+        assert!(manager.ranges().iter().all(|range| range.state() == SmramRegionState::Locked));
+        assert_eq!(manager.ranges()[1].attributes, EFI_SMRAM_CLOSED | EFI_SMRAM_LOCKED | EFI_CACHEABLE);
+        assert_eq!(manager.open_all(&access), Err(SmramError::Locked));
+        assert_eq!(manager.close_all(&access), Err(SmramError::Locked));
+        manager.lock_all(&access).unwrap();
+
+        // Transitions refused by the manager never reach the protocol.
+        assert_eq!(take_calls(), ["open", "close", "lock", "lock"]);
+    }
+
+    #[test]
+    fn protocol_errors_should_leave_state_unchanged() {
+        let access = mock_access();
+        let mut manager = SmramManager::from_access(&access).unwrap();
+        FAIL.with(|fail| *fail.borrow_mut() = true);
+        assert_eq!(manager.open_all(&access), Err(SmramError::Access(efi::Status::DEVICE_ERROR)));
+        assert!(manager.ranges().iter().all(|range| range.state() == SmramRegionState::Closed));
+        assert_eq!(efi::Status::from(SmramError::Locked), efi::Status::ACCESS_DENIED);
+        assert_eq!(take_calls(), ["open"]);
+    }
+}
@@ -0,0 +1,193 @@
+//! SMM Range Registers (SMRR)
+//!
+//! The SMRR of x86 processors restrict access to a range of SMRAM to code executing in SMM: outside of SMM, reads of
+//! the range return fixed values and writes are dropped, and the range cannot be reached by the processor's cache
+//! from outside SMM. The range is programmed in SMM, once per processor, with the IA32_SMRR_PHYSBASE and
+//! IA32_SMRR_PHYSMASK MSRs; it must be a power of two in size, at least 4 KiB, aligned to its size, and below 4 GiB.
+//!
+//! See Intel 64 and IA-32 Architectures Software Developer's Manual, Volume 3, Section 32.4.2.1.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::fmt;
+
+use r_efi::efi;
+
+/// MSR reporting the MTRR capabilities of the processor.
+pub const IA32_MTRRCAP: u32 = 0xFE;
+/// IA32_MTRRCAP bit set if the processor has SMRR.
+pub const IA32_MTRRCAP_SMRR: u64 = 1 << 11;
+/// MSR holding the base address and memory type of the SMRR range.
+pub const IA32_SMRR_PHYSBASE: u32 = 0x1F2;
+/// MSR holding the address mask and valid bit of the SMRR range.
+pub const IA32_SMRR_PHYSMASK: u32 = 0x1F3;
+/// IA32_SMRR_PHYSMASK bit enabling the SMRR range.
+pub const SMRR_PHYSMASK_VALID: u64 = 1 << 11;
+/// Write-back memory type, with which SMRAM is cached in SMM.
+pub const SMRR_MEMORY_TYPE_WB: u64 = 6;
+/// Smallest SMRR range.
+pub const SMRR_MIN_SIZE: u64 = 0x1000;
+
+// Address bits of IA32_SMRR_PHYSBASE and IA32_SMRR_PHYSMASK.
+const SMRR_ADDRESS_MASK: u64 = 0xFFFF_F000;
+
+/// Errors validating an SMRR range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmrrError {
+    /// The size is not a power of two.
+    SizeNotPowerOfTwo(u64),
+    /// The base is not aligned to the size.
+    BaseNotAligned { base: u64, size: u64 },
+    /// The size is smaller than [`SMRR_MIN_SIZE`].
+    SizeTooSmall(u64),
+    /// The range ends above 4 GiB, which the registers cannot describe.
+    AboveFourGiB { base: u64, size: u64 },
+}
+
+impl fmt::Display for SmrrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SmrrError::SizeNotPowerOfTwo(size) => write!(f, "SMRR size {size:#x} is not a power of two"),
+            SmrrError::BaseNotAligned { base, size } => {
+                write!(f, "SMRR base {base:#x} is not aligned to size {size:#x}")
+            }
+            SmrrError::SizeTooSmall(size) => write!(f, "SMRR size {size:#x} is smaller than {SMRR_MIN_SIZE:#x}"),
+            SmrrError::AboveFourGiB { base, size } => write!(f, "SMRR range {base:#x} size {size:#x} is above 4 GiB"),
+        }
+    }
+}
+
+impl From<SmrrError> for efi::Status {
+    fn from(_error: SmrrError) -> Self {
+        efi::Status::INVALID_PARAMETER
+    }
+}
+
+/// The values of the SMRR MSRs for a range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmrrRange {
+    /// Value of IA32_SMRR_PHYSBASE: the base address and the memory type.
+    pub base: u64,
+    /// Value of IA32_SMRR_PHYSMASK: the address mask and the valid bit.
+    pub mask: u64,
+}
+
+impl SmrrRange {
+    /// Returns the base address of the range.
+    pub fn address(&self) -> u64 {
+        self.base & SMRR_ADDRESS_MASK
+    }
+
+    /// Returns the size of the range.
+    pub fn size(&self) -> u64 {
+        (!self.mask & SMRR_ADDRESS_MASK) + SMRR_MIN_SIZE
+    }
+
+    /// Returns the memory type of the range in SMM.
+    pub fn memory_type(&self) -> u64 {
+        self.base & 0xFF
+    }
+
+    /// Returns whether the range is enabled.
+    pub fn is_valid(&self) -> bool {
+        self.mask & SMRR_PHYSMASK_VALID != 0
+    }
+}
+
+/// Returns the SMRR range covering `size` bytes of write-back SMRAM at `base`.
+pub fn configure_smrr(base: u64, size: u64) -> Result<SmrrRange, SmrrError> {
+    if size < SMRR_MIN_SIZE {
+        Err(SmrrError::SizeTooSmall(size))?;
+    }
+    if !size.is_power_of_two() {
+        Err(SmrrError::SizeNotPowerOfTwo(size))?;
+    }
+    if base & (size - 1) != 0 {
+        Err(SmrrError::BaseNotAligned { base, size })?;
+    }
+    if base.checked_add(size).map_or(true, |end| end > SMRR_ADDRESS_MASK + SMRR_MIN_SIZE) {
+        Err(SmrrError::AboveFourGiB { base, size })?;
+    }
+    Ok(SmrrRange { base: base | SMRR_MEMORY_TYPE_WB, mask: (!(size - 1) & SMRR_ADDRESS_MASK) | SMRR_PHYSMASK_VALID })
+}
+
+/// Programs the SMRR of the executing processor with `range`.
+///
+/// # Safety
+/// Must be called in SMM, on a processor with SMRR (see [`is_smrr_enabled`]), with a range covering SMRAM only.
+/// Every processor must be programmed with the same range.
+pub unsafe fn write_smrr(range: &SmrrRange) {
+    write_msr(IA32_SMRR_PHYSBASE, range.base);
+    write_msr(IA32_SMRR_PHYSMASK, range.mask);
+}
+
+/// Returns whether the processor has SMRR, as reported by IA32_MTRRCAP.
+///
+/// # Safety
+/// Must be called at privilege level 0.
+pub unsafe fn is_smrr_enabled() -> bool {
+    read_msr(IA32_MTRRCAP) & IA32_MTRRCAP_SMRR != 0
+}
+
+unsafe fn read_msr(msr: u32) -> u64 {
+    let (low, high): (u32, u32);
+    core::arch::asm!("rdmsr", in("ecx") msr, out("eax") low, out("edx") high, options(nomem, nostack, preserves_flags));
+    ((high as u64) << 32) | low as u64
+}
+
+unsafe fn write_msr(msr: u32, value: u64) {
+    core::arch::asm!(
+        "wrmsr",
+        in("ecx") msr,
+        in("eax") value as u32,
+        in("edx") (value >> 32) as u32,
+        options(nostack, preserves_flags)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use r_efi::efi;
+
+    use super::{configure_smrr, SmrrError, SmrrRange, SMRR_MEMORY_TYPE_WB};
+
+    #[test]
+    fn aligned_ranges_should_be_configured() {
+        // 8 MiB of TSEG below 2 GiB.
+        let range = configure_smrr(0x7F80_0000, 0x80_0000).unwrap();
+        assert_eq!(range, SmrrRange { base: 0x7F80_0006, mask: 0xFF80_0800 });
+        assert_eq!(range.address(), 0x7F80_0000);
+        assert_eq!(range.size(), 0x80_0000);
+        assert_eq!(range.memory_type(), SMRR_MEMORY_TYPE_WB);
+        assert!(range.is_valid());
+
+        // The smallest and largest ranges.
+        assert_eq!(configure_smrr(0xA_0000, 0x1000).unwrap().mask, 0xFFFF_F800);
+        let range = configure_smrr(0, 0x1_0000_0000).unwrap();
+        assert_eq!((range.address(), range.size(), range.mask), (0, 0x1_0000_0000, 0x800));
+        assert_eq!(configure_smrr(0xFFFF_F000, 0x1000).unwrap().size(), 0x1000);
+    }
+
+    #[test]
+    fn invalid_ranges_should_be_rejected() {
+        assert_eq!(configure_smrr(0x1000, 0), Err(SmrrError::SizeTooSmall(0)));
+        assert_eq!(configure_smrr(0x1000, 0x800), Err(SmrrError::SizeTooSmall(0x800)));
+        assert_eq!(configure_smrr(0x10_0000, 0x3000), Err(SmrrError::SizeNotPowerOfTwo(0x3000)));
+        assert_eq!(
+            configure_smrr(0x7F90_0000, 0x80_0000),
+            Err(SmrrError::BaseNotAligned { base: 0x7F90_0000, size: 0x80_0000 })
+        );
+        assert_eq!(configure_smrr(0x1800, 0x1000), Err(SmrrError::BaseNotAligned { base: 0x1800, size: 0x1000 }));
+        assert_eq!(
+            configure_smrr(0x1_0000_0000, 0x1000),
+            Err(SmrrError::AboveFourGiB { base: 0x1_0000_0000, size: 0x1000 })
+        );
+        assert_eq!(configure_smrr(0, 0x2_0000_0000), Err(SmrrError::AboveFourGiB { base: 0, size: 0x2_0000_0000 }));
+        assert_eq!(efi::Status::from(SmrrError::SizeNotPowerOfTwo(3)), efi::Status::INVALID_PARAMETER);
+    }
+}
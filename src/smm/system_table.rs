@@ -0,0 +1,196 @@
+//! SMM System Table
+//!
+//! The table passed to the entry point of every SMM driver (EFI_SMM_SYSTEM_TABLE2, which has the same layout as the
+//! EFI_MM_SYSTEM_TABLE of later PI Specifications). It provides the services of SMRAM: memory allocation, the SMM
+//! protocol database, and SMI handler management.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V4_MM_System_Table.html>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::ffi::c_void;
+
+use r_efi::{efi, system::TableHeader};
+
+use crate::protocols::smm::{cpu_io2, MmHandlerEntryPoint};
+
+/// Signature of the SMM System Table header ("SMST").
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-3.1
+pub const SMM_SYSTEM_TABLE_SIGNATURE: u64 = 0x54534d53;
+
+/// Revision of the SMM System Table header for this version of the PI Specification.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-3.1
+pub const SMM_SYSTEM_TABLE_REVISION: u32 = (1 << 16) | 80;
+
+/// Adds, updates, or removes an entry of the SMM configuration table.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-3.2.1
+pub type MmInstallConfigurationTable = extern "efiapi" fn(
+    system_table: *const SmmSystemTable,
+    guid: *const efi::Guid,
+    table: *mut c_void,
+    table_size: usize,
+) -> efi::Status;
+
+/// Procedure run on an application processor by MmStartupThisAp().
+pub type MmApProcedure = extern "efiapi" fn(procedure_argument: *mut c_void);
+
+/// Runs a procedure on an application processor while in SMM.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-3.2.2
+pub type MmStartupThisAp =
+    extern "efiapi" fn(procedure: MmApProcedure, cpu_number: usize, procedure_argument: *mut c_void) -> efi::Status;
+
+/// Called when an interface is installed for the protocol a notification was registered for (EFI_MM_NOTIFY_FN).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-3.2.3
+///
+/// * protocol - the protocol GUID
+/// * interface - the installed interface
+/// * handle - the handle the interface was installed on
+pub type MmNotifyFn =
+    extern "efiapi" fn(protocol: *const efi::Guid, interface: *mut c_void, handle: efi::Handle) -> efi::Status;
+
+/// Registers a function to be called when an interface is installed for a protocol, or, with no function and an
+/// existing registration, unregisters it.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-3.2.3
+///
+/// * protocol - the protocol GUID
+/// * function - the function to call, or `None` to remove the registration in `registration`
+/// * registration - receives the registration key, or holds the key to remove
+///
+/// * @retval - SUCCESS: the notification was registered or removed
+/// * @retval - OUT_OF_RESOURCES: there is not enough memory to register the notification
+/// * @retval - NOT_FOUND: `function` is `None` and `registration` was not found
+pub type MmRegisterProtocolNotify = extern "efiapi" fn(
+    protocol: *const efi::Guid,
+    function: Option<MmNotifyFn>,
+    registration: *mut *mut c_void,
+) -> efi::Status;
+
+/// Runs the root SMI handlers registered for a handler type, or the root handlers with no type.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-3.2.4
+pub type MmiManage = extern "efiapi" fn(
+    handler_type: *const efi::Guid,
+    context: *const c_void,
+    comm_buffer: *mut c_void,
+    comm_buffer_size: *mut usize,
+) -> efi::Status;
+
+/// Registers a root SMI handler for a handler type, or for every SMI if the type is null.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-3.2.5
+pub type MmiHandlerRegister = extern "efiapi" fn(
+    handler: MmHandlerEntryPoint,
+    handler_type: *const efi::Guid,
+    dispatch_handle: *mut efi::Handle,
+) -> efi::Status;
+
+/// Unregisters a root SMI handler.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-3.2.6
+pub type MmiHandlerUnRegister = extern "efiapi" fn(dispatch_handle: efi::Handle) -> efi::Status;
+
+/// The services available to SMM drivers.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section IV-3.1
+#[repr(C)]
+#[derive(Debug)]
+pub struct SmmSystemTable {
+    pub hdr: TableHeader,
+    pub mm_firmware_vendor: *mut efi::Char16,
+    pub mm_firmware_revision: u32,
+    pub mm_install_configuration_table: MmInstallConfigurationTable,
+
+    //
+    // I/O Services
+    //
+    pub mm_io: cpu_io2::Protocol,
+
+    //
+    // Runtime memory services
+    //
+    pub mm_allocate_pool: efi::BootAllocatePool,
+    pub mm_free_pool: efi::BootFreePool,
+    pub mm_allocate_pages: efi::BootAllocatePages,
+    pub mm_free_pages: efi::BootFreePages,
+
+    //
+    // MP service
+    //
+    pub mm_startup_this_ap: MmStartupThisAp,
+
+    //
+    // CPU information records
+    //
+    pub currently_executing_cpu: usize,
+    pub number_of_cpus: usize,
+    pub cpu_save_state_size: *mut usize,
+    pub cpu_save_state: *mut *mut c_void,
+
+    //
+    // Extensibility table
+    //
+    pub number_of_table_entries: usize,
+    pub mm_configuration_table: *mut efi::ConfigurationTable,
+
+    //
+    // Protocol services
+    //
+    pub mm_install_protocol_interface: efi::BootInstallProtocolInterface,
+    pub mm_uninstall_protocol_interface: efi::BootUninstallProtocolInterface,
+    pub mm_handle_protocol: efi::BootHandleProtocol,
+    pub mm_register_protocol_notify: MmRegisterProtocolNotify,
+    pub mm_locate_handle: efi::BootLocateHandle,
+    pub mm_locate_protocol: efi::BootLocateProtocol,
+
+    //
+    // SMI Management functions
+    //
+    pub mmi_manage: MmiManage,
+    pub mmi_handler_register: MmiHandlerRegister,
+    pub mmi_handler_unregister: MmiHandlerUnRegister,
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::size_of;
+
+    use super::SmmSystemTable;
+
+    #[test]
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    fn table_layout_should_match_spec() {
+        assert_eq!(offset_of!(SmmSystemTable, mm_firmware_vendor), 24);
+        assert_eq!(offset_of!(SmmSystemTable, mm_install_configuration_table), 40);
+        assert_eq!(offset_of!(SmmSystemTable, mm_io), 48);
+        assert_eq!(offset_of!(SmmSystemTable, mm_allocate_pool), 80);
+        assert_eq!(offset_of!(SmmSystemTable, mm_startup_this_ap), 112);
+        assert_eq!(offset_of!(SmmSystemTable, currently_executing_cpu), 120);
+        assert_eq!(offset_of!(SmmSystemTable, number_of_table_entries), 152);
+        assert_eq!(offset_of!(SmmSystemTable, mm_install_protocol_interface), 168);
+        assert_eq!(offset_of!(SmmSystemTable, mm_register_protocol_notify), 192);
+        assert_eq!(offset_of!(SmmSystemTable, mmi_manage), 216);
+        assert_eq!(offset_of!(SmmSystemTable, mmi_handler_unregister), 232);
+        assert_eq!(size_of::<SmmSystemTable>(), 240);
+    }
+}
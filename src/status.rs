@@ -0,0 +1,197 @@
+//! EFI Status Conversions
+//!
+//! Conversions between `efi::Status` and `Result`, and [`StatusError`], an error type that displays the name of a
+//! status code.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/Apx_D_Status_Codes.html>
+//!
+//! ## Example
+//! ```
+//! use mu_pi::status::{StatusError, StatusExt};
+//! use r_efi::efi;
+//!
+//! assert_eq!(efi::Status::SUCCESS.ok(), Ok(()));
+//! assert_eq!(efi::Status::NOT_FOUND.ok_or_else(|| 42), Err(efi::Status::NOT_FOUND));
+//! assert_eq!(StatusError(efi::Status::NOT_FOUND).to_string(), "Not Found");
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::fmt;
+
+use r_efi::efi;
+
+/// Returns true if `status` is an error code (the high bit is set).
+pub fn is_error(status: efi::Status) -> bool {
+    status.is_error()
+}
+
+/// Returns true if `status` is a warning code (not SUCCESS, and the high bit is clear).
+pub fn is_warning(status: efi::Status) -> bool {
+    status.is_warning()
+}
+
+/// `Result` conversions for `efi::Status`.
+///
+/// Warnings are reported as success, since the operation they are returned from has completed; use
+/// [`is_warning`] to tell them apart from SUCCESS.
+pub trait StatusExt {
+    /// Returns `Ok(())` unless the status is an error, which is returned as the error.
+    fn ok(self) -> Result<(), efi::Status>;
+
+    /// Returns `Ok` with the value returned by `f` unless the status is an error, which is returned as the error. `f`
+    /// is only called on success.
+    fn ok_or_else<T, F: FnOnce() -> T>(self, f: F) -> Result<T, efi::Status>;
+}
+
+impl StatusExt for efi::Status {
+    fn ok(self) -> Result<(), efi::Status> {
+        if self.is_error() {
+            Err(self)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn ok_or_else<T, F: FnOnce() -> T>(self, f: F) -> Result<T, efi::Status> {
+        self.ok().map(|()| f())
+    }
+}
+
+/// An `efi::Status` that displays as the name of the status code, e.g. "Buffer Too Small".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatusError(pub efi::Status);
+
+impl StatusError {
+    /// Returns the name of the status code, or `None` if the code is not defined by the UEFI Specification.
+    pub fn name(&self) -> Option<&'static str> {
+        Some(match self.0 {
+            efi::Status::SUCCESS => "Success",
+            efi::Status::LOAD_ERROR => "Load Error",
+            efi::Status::INVALID_PARAMETER => "Invalid Parameter",
+            efi::Status::UNSUPPORTED => "Unsupported",
+            efi::Status::BAD_BUFFER_SIZE => "Bad Buffer Size",
+            efi::Status::BUFFER_TOO_SMALL => "Buffer Too Small",
+            efi::Status::NOT_READY => "Not Ready",
+            efi::Status::DEVICE_ERROR => "Device Error",
+            efi::Status::WRITE_PROTECTED => "Write Protected",
+            efi::Status::OUT_OF_RESOURCES => "Out of Resources",
+            efi::Status::VOLUME_CORRUPTED => "Volume Corrupt",
+            efi::Status::VOLUME_FULL => "Volume Full",
+            efi::Status::NO_MEDIA => "No Media",
+            efi::Status::MEDIA_CHANGED => "Media Changed",
+            efi::Status::NOT_FOUND => "Not Found",
+            efi::Status::ACCESS_DENIED => "Access Denied",
+            efi::Status::NO_RESPONSE => "No Response",
+            efi::Status::NO_MAPPING => "No Mapping",
+            efi::Status::TIMEOUT => "Time Out",
+            efi::Status::NOT_STARTED => "Not Started",
+            efi::Status::ALREADY_STARTED => "Already Started",
+            efi::Status::ABORTED => "Aborted",
+            efi::Status::ICMP_ERROR => "ICMP Error",
+            efi::Status::TFTP_ERROR => "TFTP Error",
+            efi::Status::PROTOCOL_ERROR => "Protocol Error",
+            efi::Status::INCOMPATIBLE_VERSION => "Incompatible Version",
+            efi::Status::SECURITY_VIOLATION => "Security Violation",
+            efi::Status::CRC_ERROR => "CRC Error",
+            efi::Status::END_OF_MEDIA => "End of Media",
+            efi::Status::END_OF_FILE => "End of File",
+            efi::Status::INVALID_LANGUAGE => "Invalid Language",
+            efi::Status::COMPROMISED_DATA => "Compromised Data",
+            efi::Status::IP_ADDRESS_CONFLICT => "IP Address Conflict",
+            efi::Status::HTTP_ERROR => "HTTP Error",
+            efi::Status::NETWORK_UNREACHABLE => "Network Unreachable",
+            efi::Status::HOST_UNREACHABLE => "Host Unreachable",
+            efi::Status::PROTOCOL_UNREACHABLE => "Protocol Unreachable",
+            efi::Status::PORT_UNREACHABLE => "Port Unreachable",
+            efi::Status::CONNECTION_FIN => "Connection Closed",
+            efi::Status::CONNECTION_RESET => "Connection Reset",
+            efi::Status::CONNECTION_REFUSED => "Connection Refused",
+            efi::Status::WARN_UNKNOWN_GLYPH => "Warning Unknown Glyph",
+            efi::Status::WARN_DELETE_FAILURE => "Warning Delete Failure",
+            efi::Status::WARN_WRITE_FAILURE => "Warning Write Failure",
+            efi::Status::WARN_BUFFER_TOO_SMALL => "Warning Buffer Too Small",
+            efi::Status::WARN_STALE_DATA => "Warning Stale Data",
+            efi::Status::WARN_FILE_SYSTEM => "Warning File System",
+            efi::Status::WARN_RESET_REQUIRED => "Warning Reset Required",
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for StatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => f.write_str(name),
+            None if self.0.is_error() => write!(f, "Error {:#x}", self.0.as_usize()),
+            None => write!(f, "Warning {:#x}", self.0.as_usize()),
+        }
+    }
+}
+
+impl From<efi::Status> for StatusError {
+    fn from(status: efi::Status) -> Self {
+        StatusError(status)
+    }
+}
+
+impl From<StatusError> for efi::Status {
+    fn from(error: StatusError) -> Self {
+        error.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use r_efi::efi;
+
+    use super::{is_error, is_warning, StatusError, StatusExt};
+
+    #[test]
+    fn status_classes_should_follow_high_bit() {
+        assert!(!is_error(efi::Status::SUCCESS));
+        assert!(!is_warning(efi::Status::SUCCESS));
+        assert!(is_error(efi::Status::DEVICE_ERROR));
+        assert!(!is_warning(efi::Status::DEVICE_ERROR));
+        assert!(is_warning(efi::Status::WARN_STALE_DATA));
+        assert!(!is_error(efi::Status::WARN_STALE_DATA));
+    }
+
+    #[test]
+    fn status_should_convert_to_result() {
+        assert_eq!(efi::Status::SUCCESS.ok(), Ok(()));
+        assert_eq!(efi::Status::WARN_BUFFER_TOO_SMALL.ok(), Ok(()));
+        assert_eq!(efi::Status::ACCESS_DENIED.ok(), Err(efi::Status::ACCESS_DENIED));
+
+        assert_eq!(efi::Status::SUCCESS.ok_or_else(|| "value"), Ok("value"));
+        let mut called = false;
+        assert_eq!(efi::Status::TIMEOUT.ok_or_else(|| called = true), Err(efi::Status::TIMEOUT));
+        assert!(!called);
+    }
+
+    #[test]
+    fn status_error_should_display_names() {
+        assert_eq!(StatusError(efi::Status::SUCCESS).to_string(), "Success");
+        assert_eq!(StatusError(efi::Status::VOLUME_CORRUPTED).to_string(), "Volume Corrupt");
+        assert_eq!(StatusError(efi::Status::HTTP_ERROR).to_string(), "HTTP Error");
+        assert_eq!(StatusError(efi::Status::WARN_RESET_REQUIRED).to_string(), "Warning Reset Required");
+        assert_eq!(StatusError(efi::Status::from_usize(0x8000_0000_0000_001d)).to_string(), "Error 0x800000000000001d");
+        assert_eq!(StatusError(efi::Status::from_usize(0x42)).to_string(), "Warning 0x42");
+
+        // Every error and warning code up to the highest defined one, other than the reserved errors 29 and 30, is
+        // named.
+        let named = (1..=35usize)
+            .filter(|code| !matches!(code, 29 | 30))
+            .map(|code| efi::Status::from_usize(code | 1 << (usize::BITS - 1)))
+            .chain((1..=7).map(efi::Status::from_usize))
+            .filter(|status| StatusError(*status).name().is_some())
+            .count();
+        assert_eq!(named, 40);
+        assert_eq!(efi::Status::from(StatusError::from(efi::Status::ABORTED)), efi::Status::ABORTED);
+    }
+}
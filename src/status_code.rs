@@ -0,0 +1,306 @@
+//! Status Codes
+//!
+//! Typed representations of the status code type and value reported through the Status Code Runtime Protocol, the
+//! PEI Status Code PPI, and the MM Status Code Protocol.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V3_Code_Definitions.html#status-code-common-definitions>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::ops::BitOr;
+
+// Defines a set of constants along with a table of (name, value) pairs listing them in definition order, so lookups
+// by value are generated from the same definitions as the constants themselves.
+macro_rules! status_code_table {
+    ($(#[$table_attr:meta])* $table:ident: $ty:ty; $($(#[$attr:meta])* $name:ident = $value:expr;)*) => {
+        $($(#[$attr])* pub const $name: $ty = $value;)*
+
+        $(#[$table_attr])*
+        pub const $table: &[(&str, $ty)] = &[$((stringify!($name), $name),)*];
+    };
+}
+
+mod caller_id;
+pub mod data;
+pub mod debug;
+mod display;
+pub mod error;
+pub mod extended_guids;
+pub mod progress;
+mod reporter;
+pub mod subclass;
+
+pub use caller_id::CallerIdMap;
+pub use display::{ReportDisplay, ValueDisplay};
+pub use reporter::{Reporter, MAX_DATA_SIZE};
+
+/// Status code class values (bits 31:24 of a [`StatusCodeValue`]).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section III-6.3.1
+pub mod class {
+    status_code_table! {
+        /// Every class defined by the specification.
+        ALL: u8;
+        COMPUTING_UNIT = 0x00;
+        PERIPHERAL = 0x01;
+        IO_BUS = 0x02;
+        SOFTWARE = 0x03;
+    }
+}
+
+/// The type of a status code (EFI_STATUS_CODE_TYPE).
+///
+/// The low byte holds the code type (progress, error, or debug) and the high byte holds the severity of error codes.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section III-6.3.1
+#[repr(transparent)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StatusCodeType(pub u32);
+
+impl StatusCodeType {
+    /// Bits holding the code type.
+    pub const TYPE_MASK: u32 = 0x000000FF;
+    /// Bits holding the severity.
+    pub const SEVERITY_MASK: u32 = 0xFF000000;
+    /// Bits reserved by the specification.
+    pub const RESERVED_MASK: u32 = 0x00FFFF00;
+
+    pub const PROGRESS_CODE: u32 = 0x00000001;
+    pub const ERROR_CODE: u32 = 0x00000002;
+    pub const DEBUG_CODE: u32 = 0x00000003;
+
+    // Severities of error codes. Other values are reserved.
+    pub const EFI_ERROR_MINOR: u32 = 0x40000000;
+    pub const EFI_ERROR_MAJOR: u32 = 0x80000000;
+    pub const EFI_ERROR_UNRECOVERED: u32 = 0x90000000;
+    pub const EFI_ERROR_UNCONTAINED: u32 = 0xA0000000;
+
+    /// Returns the type of a progress code.
+    pub const fn progress() -> Self {
+        Self(Self::PROGRESS_CODE)
+    }
+
+    /// Returns the type of an error code with the given severity bits.
+    pub const fn error(severity: u32) -> Self {
+        Self(Self::ERROR_CODE | (severity & Self::SEVERITY_MASK))
+    }
+
+    /// Returns the type of a debug code.
+    pub const fn debug() -> Self {
+        Self(Self::DEBUG_CODE)
+    }
+
+    /// Returns the code type field (one of PROGRESS_CODE, ERROR_CODE, or DEBUG_CODE for valid codes).
+    pub const fn code_type(&self) -> u32 {
+        self.0 & Self::TYPE_MASK
+    }
+
+    /// Returns the severity field.
+    pub const fn severity(&self) -> u32 {
+        self.0 & Self::SEVERITY_MASK
+    }
+
+    /// Returns true if this is a progress code.
+    pub const fn is_progress(&self) -> bool {
+        self.code_type() == Self::PROGRESS_CODE
+    }
+
+    /// Returns true if this is an error code.
+    pub const fn is_error(&self) -> bool {
+        self.code_type() == Self::ERROR_CODE
+    }
+
+    /// Returns true if this is a debug code.
+    pub const fn is_debug(&self) -> bool {
+        self.code_type() == Self::DEBUG_CODE
+    }
+}
+
+impl From<u32> for StatusCodeType {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<StatusCodeType> for u32 {
+    fn from(value: StatusCodeType) -> Self {
+        value.0
+    }
+}
+
+/// The value of a status code (EFI_STATUS_CODE_VALUE).
+///
+/// A value is composed of a class (bits 31:24), a subclass (bits 23:16), and an operation (bits 15:0).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section III-6.3.1
+#[repr(transparent)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StatusCodeValue(pub u32);
+
+impl StatusCodeValue {
+    /// Bits holding the class.
+    pub const CLASS_MASK: u32 = 0xFF000000;
+    /// Bits holding the subclass.
+    pub const SUBCLASS_MASK: u32 = 0x00FF0000;
+    /// Bits holding the operation.
+    pub const OPERATION_MASK: u32 = 0x0000FFFF;
+
+    pub const CLASS_SHIFT: u32 = 24;
+    pub const SUBCLASS_SHIFT: u32 = 16;
+
+    /// Operations 0x0000-0x0FFF are shared by all subclasses of a class.
+    pub const SUBCLASS_SPECIFIC: u16 = 0x1000;
+    /// Operations 0x8000-0xFFFF are OEM specific.
+    pub const OEM_SPECIFIC: u16 = 0x8000;
+
+    /// Composes a status code value from its class, subclass, and operation.
+    pub const fn compose(class: u8, subclass: u8, operation: u16) -> Self {
+        Self(((class as u32) << Self::CLASS_SHIFT) | ((subclass as u32) << Self::SUBCLASS_SHIFT) | operation as u32)
+    }
+
+    /// Returns the class.
+    pub const fn class(&self) -> u8 {
+        ((self.0 & Self::CLASS_MASK) >> Self::CLASS_SHIFT) as u8
+    }
+
+    /// Returns the subclass.
+    pub const fn subclass(&self) -> u8 {
+        ((self.0 & Self::SUBCLASS_MASK) >> Self::SUBCLASS_SHIFT) as u8
+    }
+
+    /// Returns the operation.
+    pub const fn operation(&self) -> u16 {
+        (self.0 & Self::OPERATION_MASK) as u16
+    }
+
+    /// Returns the value with the operation cleared, i.e. the class and subclass of this value.
+    pub const fn class_subclass(&self) -> Self {
+        Self(self.0 & (Self::CLASS_MASK | Self::SUBCLASS_MASK))
+    }
+}
+
+// Composes a value whose operation is in the subclass specific range.
+const fn subclass_specific(class: u8, subclass: u8, operation: u16) -> StatusCodeValue {
+    StatusCodeValue::compose(class, subclass, StatusCodeValue::SUBCLASS_SPECIFIC | operation)
+}
+
+impl BitOr<u16> for StatusCodeValue {
+    type Output = Self;
+
+    /// Sets operation bits, e.g. to combine a subclass with a class-wide operation.
+    fn bitor(self, rhs: u16) -> Self {
+        Self(self.0 | rhs as u32)
+    }
+}
+
+impl From<u32> for StatusCodeValue {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<StatusCodeValue> for u32 {
+    fn from(value: StatusCodeValue) -> Self {
+        value.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::size_of;
+
+    use super::{class, StatusCodeType, StatusCodeValue};
+    use crate::protocols::status_code;
+
+    #[test]
+    fn type_masks_should_match_spec() {
+        assert_eq!(StatusCodeType::TYPE_MASK, 0x000000FF);
+        assert_eq!(StatusCodeType::SEVERITY_MASK, 0xFF000000);
+        assert_eq!(StatusCodeType::RESERVED_MASK, 0x00FFFF00);
+        assert_eq!(StatusCodeType::TYPE_MASK | StatusCodeType::SEVERITY_MASK | StatusCodeType::RESERVED_MASK, u32::MAX);
+
+        assert_eq!(StatusCodeType::PROGRESS_CODE, status_code::EFI_PROGRESS_CODE);
+        assert_eq!(StatusCodeType::ERROR_CODE, status_code::EFI_ERROR_CODE);
+        assert_eq!(StatusCodeType::DEBUG_CODE, status_code::EFI_DEBUG_CODE);
+        assert_eq!(size_of::<StatusCodeType>(), 4);
+    }
+
+    #[test]
+    fn type_constructors_should_set_code_type_and_severity() {
+        assert_eq!(StatusCodeType::progress().0, 0x00000001);
+        assert!(StatusCodeType::progress().is_progress());
+        assert_eq!(StatusCodeType::debug().0, 0x00000003);
+        assert!(StatusCodeType::debug().is_debug());
+
+        let error = StatusCodeType::error(0x80000000);
+        assert_eq!(error.0, 0x80000002);
+        assert_eq!(error.code_type(), StatusCodeType::ERROR_CODE);
+        assert_eq!(error.severity(), 0x80000000);
+        assert!(error.is_error());
+
+        // Severity arguments outside the severity field are discarded.
+        assert_eq!(StatusCodeType::error(0x40000101).0, 0x40000002);
+        assert_eq!(StatusCodeType(0x90001202).code_type(), StatusCodeType::ERROR_CODE);
+        assert_eq!(StatusCodeType(0x90001202).severity(), 0x90000000);
+    }
+
+    #[test]
+    fn severities_should_match_spec() {
+        assert_eq!(StatusCodeType::EFI_ERROR_MINOR, 0x40000000);
+        assert_eq!(StatusCodeType::EFI_ERROR_MAJOR, 0x80000000);
+        assert_eq!(StatusCodeType::EFI_ERROR_UNRECOVERED, 0x90000000);
+        assert_eq!(StatusCodeType::EFI_ERROR_UNCONTAINED, 0xA0000000);
+
+        let error = StatusCodeType::error(StatusCodeType::EFI_ERROR_UNRECOVERED);
+        assert_eq!(error.0, 0x90000002);
+        assert_eq!(error.severity(), StatusCodeType::EFI_ERROR_UNRECOVERED);
+    }
+
+    #[test]
+    fn value_masks_should_match_spec() {
+        assert_eq!(StatusCodeValue::CLASS_MASK, 0xFF000000);
+        assert_eq!(StatusCodeValue::SUBCLASS_MASK, 0x00FF0000);
+        assert_eq!(StatusCodeValue::OPERATION_MASK, 0x0000FFFF);
+        assert_eq!(StatusCodeValue::CLASS_MASK >> StatusCodeValue::CLASS_SHIFT, 0xFF);
+        assert_eq!(StatusCodeValue::SUBCLASS_MASK >> StatusCodeValue::SUBCLASS_SHIFT, 0xFF);
+        assert_eq!(StatusCodeValue::SUBCLASS_SPECIFIC as u32, status_code::EFI_SUBCLASS_SPECIFIC);
+        assert_eq!(StatusCodeValue::OEM_SPECIFIC as u32, status_code::EFI_OEM_SPECIFIC);
+        assert_eq!(size_of::<StatusCodeValue>(), 4);
+    }
+
+    #[test]
+    fn class_values_should_match_spec() {
+        assert_eq!(StatusCodeValue::compose(class::COMPUTING_UNIT, 0, 0).0, status_code::EFI_COMPUTING_UNIT);
+        assert_eq!(StatusCodeValue::compose(class::PERIPHERAL, 0, 0).0, status_code::EFI_PERIPHERAL);
+        assert_eq!(StatusCodeValue::compose(class::IO_BUS, 0, 0).0, status_code::EFI_IO_BUS);
+        assert_eq!(StatusCodeValue::compose(class::SOFTWARE, 0, 0).0, status_code::EFI_SOFTWARE);
+    }
+
+    #[test]
+    fn value_should_decompose() {
+        // EFI_SOFTWARE_DXE_CORE | EFI_SW_DXE_CORE_PC_ENTRY_POINT
+        let value = StatusCodeValue::compose(class::SOFTWARE, 0x04, 0x1000);
+        assert_eq!(value.0, 0x03041000);
+        assert_eq!(value.class(), class::SOFTWARE);
+        assert_eq!(value.subclass(), 0x04);
+        assert_eq!(value.operation(), 0x1000);
+        assert_eq!(value.class_subclass().0, status_code::EFI_SOFTWARE_DXE_CORE);
+
+        let value = StatusCodeValue(0xFFEEDDCC);
+        assert_eq!(value.class(), 0xFF);
+        assert_eq!(value.subclass(), 0xEE);
+        assert_eq!(value.operation(), 0xDDCC);
+        assert_eq!(StatusCodeValue::compose(0xFF, 0xEE, 0xDDCC), value);
+
+        let combined = StatusCodeValue(status_code::EFI_SOFTWARE_EFI_BOOT_SERVICE) | 0x1002;
+        assert_eq!(combined.0, status_code::EFI_SOFTWARE_EFI_BOOT_SERVICE | status_code::EFI_SW_BS_PC_ALLOCATE_PAGES);
+    }
+}
@@ -0,0 +1,173 @@
+//! Caller ID Names
+//!
+//! Maps the caller ID GUIDs passed with reported status codes to the names of the modules that report them, so that
+//! logs can show `[DxeCore]` instead of a GUID. Modules usually use their FFS file name GUID as their caller ID, so a
+//! map can be generated from the user interface sections of the files in a firmware volume.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+extern crate alloc;
+use alloc::{string::String, vec::Vec};
+
+use r_efi::efi;
+
+use crate::fw_fs::{FfsSectionType, FirmwareVolume};
+
+/// Resolves caller ID GUIDs to module names.
+///
+/// A map holds a fixed table of names, typically a `const` slice, and names registered at runtime. Registered names
+/// take precedence over the fixed table.
+///
+/// ## Example
+///```
+/// use mu_pi::status_code::CallerIdMap;
+/// use r_efi::efi;
+///
+/// const DXE_CORE: efi::Guid =
+///     efi::Guid::from_fields(0xD6A2CB7F, 0x6A18, 0x4E2F, 0xB4, 0x3B, &[0x99, 0x20, 0xA7, 0x33, 0x70, 0x0A]);
+/// const NAMES: &[(efi::Guid, &str)] = &[(DXE_CORE, "DxeCore")];
+///
+/// let mut map = CallerIdMap::from_static(NAMES);
+/// assert_eq!(map.resolve(&DXE_CORE), Some("DxeCore"));
+///
+/// map.register(DXE_CORE, "DxeMain");
+/// assert_eq!(map.resolve(&DXE_CORE), Some("DxeMain"));
+///```
+#[derive(Debug, Clone, Default)]
+pub struct CallerIdMap {
+    fixed: &'static [(efi::Guid, &'static str)],
+    registered: Vec<(efi::Guid, String)>,
+}
+
+impl CallerIdMap {
+    /// Creates an empty map.
+    pub const fn new() -> Self {
+        Self::from_static(&[])
+    }
+
+    /// Creates a map that resolves the names in `entries`.
+    pub const fn from_static(entries: &'static [(efi::Guid, &'static str)]) -> Self {
+        Self { fixed: entries, registered: Vec::new() }
+    }
+
+    /// Creates a map from the names of the files in `fv`. See [`Self::register_firmware_volume`].
+    pub fn from_firmware_volume(fv: &FirmwareVolume) -> Result<Self, efi::Status> {
+        let mut map = Self::new();
+        map.register_firmware_volume(fv)?;
+        Ok(map)
+    }
+
+    /// Registers `name` for `guid`, replacing any name previously registered for it.
+    pub fn register(&mut self, guid: efi::Guid, name: impl Into<String>) {
+        let name = name.into();
+        match self.registered.iter_mut().find(|(known, _)| *known == guid) {
+            Some(entry) => entry.1 = name,
+            None => self.registered.push((guid, name)),
+        }
+    }
+
+    /// Registers the file name GUID of each file in `fv` that has a user interface section with the name in that
+    /// section. Encapsulation sections are not extracted, so files whose user interface section is compressed are
+    /// skipped.
+    ///
+    /// Returns the number of names registered, or the error encountered while parsing the firmware volume.
+    pub fn register_firmware_volume(&mut self, fv: &FirmwareVolume) -> Result<usize, efi::Status> {
+        let mut count = 0;
+        for file in fv.file_iter() {
+            let file = file?;
+            for section in file.section_iter() {
+                let section = section?;
+                if section.section_type() == Some(FfsSectionType::UserInterface) {
+                    self.register(file.name(), ui_name(section.section_data()));
+                    count += 1;
+                    break;
+                }
+            }
+        }
+        Ok(count)
+    }
+
+    /// Returns the name of the module with caller ID `guid`.
+    pub fn resolve(&self, guid: &efi::Guid) -> Option<&str> {
+        self.registered
+            .iter()
+            .find(|(known, _)| known == guid)
+            .map(|(_, name)| name.as_str())
+            .or_else(|| self.fixed.iter().find(|(known, _)| known == guid).map(|(_, name)| *name))
+    }
+
+    /// Returns the number of names in the map, counting a GUID in both the fixed table and the registered names once
+    /// for each.
+    pub fn len(&self) -> usize {
+        self.fixed.len() + self.registered.len()
+    }
+
+    /// Returns true if the map holds no names.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// Decodes the null-terminated UCS-2 string of a user interface section.
+fn ui_name(data: &[u8]) -> String {
+    let chars = data.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).take_while(|&c| c != 0);
+    char::decode_utf16(chars).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, error::Error, fs, path::Path};
+
+    use r_efi::efi;
+
+    use super::CallerIdMap;
+    use crate::{
+        fw_fs::FirmwareVolume,
+        status_code::{progress, ReportDisplay, StatusCodeType},
+    };
+
+    const DXE_RUST: efi::Guid =
+        efi::Guid::from_fields(0x23C9322F, 0x2AF2, 0x476A, 0xBC, 0x4C, &[0x26, 0xBC, 0x88, 0x26, 0x6C, 0x71]);
+    const OTHER: efi::Guid = efi::Guid::from_fields(1, 2, 3, 4, 5, &[6, 7, 8, 9, 10, 11]);
+
+    #[test]
+    fn resolve_should_prefer_registered_names() {
+        static NAMES: &[(efi::Guid, &str)] = &[(DXE_RUST, "DxeCore"), (OTHER, "Other")];
+        let mut map = CallerIdMap::from_static(NAMES);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.resolve(&DXE_RUST), Some("DxeCore"));
+
+        map.register(DXE_RUST, "DxeRust");
+        map.register(DXE_RUST, "DxeRust2");
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.resolve(&DXE_RUST), Some("DxeRust2"));
+        assert_eq!(map.resolve(&OTHER), Some("Other"));
+        assert_eq!(CallerIdMap::new().resolve(&OTHER), None);
+        assert!(CallerIdMap::new().is_empty());
+    }
+
+    #[test]
+    fn map_from_firmware_volume_should_resolve_reported_codes() -> Result<(), Box<dyn Error>> {
+        let root = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("test_resources");
+        let fv_bytes = fs::read(root.join("DXEFV.Fv"))?;
+        let fv = FirmwareVolume::new(&fv_bytes).unwrap();
+
+        let map = CallerIdMap::from_firmware_volume(&fv).unwrap();
+        assert!(!map.is_empty());
+        assert_eq!(map.resolve(&DXE_RUST), Some("DxeRust"));
+
+        let report = ReportDisplay::new(
+            StatusCodeType::progress(),
+            progress::software::EFI_SW_DXE_CORE_PC_ENTRY_POINT,
+            Some(DXE_RUST),
+        );
+        assert_eq!(std::format!("{}", report.with_caller_ids(Some(&map))), "[DxeRust] PROGRESS DXE_CORE|ENTRY_POINT");
+        assert_eq!(std::format!("{report}"), "[23C9322F-2AF2-476A-BC4C-26BC88266C71] PROGRESS DXE_CORE|ENTRY_POINT");
+        Ok(())
+    }
+}
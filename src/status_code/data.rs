@@ -0,0 +1,494 @@
+//! Status Code Data
+//!
+//! Checked access to the extended data (EFI_STATUS_CODE_DATA) that may accompany a reported status code.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V3_Code_Definitions.html#efi-status-code-data>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{fmt, mem::size_of, ptr, slice};
+
+use r_efi::{efi, hii};
+
+use crate::device_path::{DevicePathProtocol, DevicePathWalker};
+pub use crate::protocols::status_code::EfiStatusCodeData;
+
+/// Extended data type GUID for [`StatusCodeString`] data (EFI_STATUS_CODE_DATA_TYPE_STRING_GUID).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section III-6.6.2.2
+pub const STRING_DATA_TYPE_GUID: efi::Guid =
+    efi::Guid::from_fields(0x92D11080, 0x496F, 0x4D95, 0xBE, 0x7E, &[0x03, 0x74, 0x88, 0x38, 0x2B, 0x0A]);
+
+/// Extended data type GUID for data specific to the status code value (EFI_STATUS_CODE_SPECIFIC_DATA_GUID).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section III-6.6.2.3
+pub const SPECIFIC_DATA_GUID: efi::Guid =
+    efi::Guid::from_fields(0x335984BD, 0xE805, 0x409A, 0xB8, 0xF8, &[0xD2, 0x7E, 0xCE, 0x5F, 0xF7, 0xA6]);
+
+/// Extended data type GUID for debug data (EFI_STATUS_CODE_DATA_TYPE_DEBUG_GUID).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section III-6.6.2.4
+pub const DEBUG_DATA_TYPE_GUID: efi::Guid =
+    efi::Guid::from_fields(0x9A4E9246, 0xD553, 0x11D5, 0x87, 0xE2, &[0x00, 0x06, 0x29, 0x45, 0xC3, 0xB9]);
+
+// EFI_STRING_TYPE values.
+const STRING_TYPE_ASCII: u32 = 0;
+const STRING_TYPE_UNICODE: u32 = 1;
+const STRING_TYPE_TOKEN: u32 = 2;
+
+// In EFI_STATUS_CODE_STRING_DATA the string union follows the 4-byte string type and is pointer aligned. The header is
+// 20 bytes, so the union starts 4 bytes into the payload on both 32 and 64-bit targets.
+const STRING_OFFSET: usize = 4;
+
+/// Errors reported when creating a [`StatusCodeData`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataError {
+    /// The data pointer is null.
+    Null,
+    /// The header size is smaller than EFI_STATUS_CODE_DATA.
+    InvalidHeaderSize(u16),
+    /// The buffer is smaller than the header and payload sizes require.
+    Truncated,
+}
+
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataError::Null => write!(f, "status code data is null"),
+            DataError::InvalidHeaderSize(size) => write!(f, "invalid status code data header size {size:#x}"),
+            DataError::Truncated => write!(f, "truncated status code data"),
+        }
+    }
+}
+
+/// A string reported as status code extended data (EFI_STATUS_CODE_STRING_DATA).
+///
+/// The string itself is not part of the extended data; only a pointer to it (or an HII token) is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusCodeString {
+    /// Pointer to a null-terminated ASCII string.
+    Ascii(*const u8),
+    /// Pointer to a null-terminated UCS-2 string.
+    Unicode(*const efi::Char16),
+    /// A string in the HII database.
+    Token { handle: hii::Handle, token: hii::StringId },
+}
+
+/// Device path extended data (EFI_DEVICE_PATH_EXTENDED_DATA), reported with [`SPECIFIC_DATA_GUID`] to identify the
+/// device a code applies to.
+///
+/// The device path is variable length; only its first node header is part of this structure.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section III-6.7.2.4
+#[repr(C)]
+#[derive(Debug)]
+pub struct EfiDevicePathExtendedData {
+    pub data_header: EfiStatusCodeData,
+    pub device_path: DevicePathProtocol,
+}
+
+/// Memory error granularity: the error applies to a single memory device.
+pub const EFI_MEMORY_ERROR_DEVICE: u8 = 0x01;
+/// Memory error granularity: the error applies to a memory module.
+pub const EFI_MEMORY_ERROR_MODULE: u8 = 0x02;
+/// Memory error granularity: the error applies to a memory array.
+pub const EFI_MEMORY_ERROR_ARRAY: u8 = 0x03;
+/// Memory error granularity: the error applies to a memory partition.
+pub const EFI_MEMORY_ERROR_PARTITION: u8 = 0x04;
+
+/// Memory error operation: the error occurred during a read.
+pub const EFI_MEMORY_OPERATION_READ: u8 = 0x00;
+/// Memory error operation: the error occurred during a write.
+pub const EFI_MEMORY_OPERATION_WRITE: u8 = 0x01;
+/// Memory error operation: the error occurred during a partial write.
+pub const EFI_MEMORY_OPERATION_PARTIAL_WRITE: u8 = 0x02;
+
+/// Memory extended error data (EFI_MEMORY_EXTENDED_ERROR_DATA), reported with [`SPECIFIC_DATA_GUID`] for memory
+/// errors.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section III-6.7.1.4
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EfiMemoryExtendedErrorData {
+    pub data_header: EfiStatusCodeData,
+    /// The granularity of the error, one of the `EFI_MEMORY_ERROR_*` values.
+    pub granularity: u8,
+    /// The operation that resulted in the error, one of the `EFI_MEMORY_OPERATION_*` values.
+    pub operation: u8,
+    /// The error syndrome, vendor-specific ECC syndrome, or CRC data.
+    pub syndrome: usize,
+    /// The physical address of the error.
+    pub address: efi::PhysicalAddress,
+    /// The range, in bytes, within which the error address can be determined.
+    pub resolution: usize,
+}
+
+/// Identifies a memory device within a memory array (EFI_STATUS_CODE_DIMM_NUMBER).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section III-6.7.1.4
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EfiStatusCodeDimmNumber {
+    pub array: u16,
+    pub device: u16,
+}
+
+/// A validated view of status code extended data: an EFI_STATUS_CODE_DATA header followed by `size` bytes of
+/// payload.
+///
+/// ## Example
+///```
+/// use mu_pi::status_code::data::{StatusCodeData, SPECIFIC_DATA_GUID};
+///
+/// let mut bytes = vec![20, 0, 2, 0];
+/// bytes.extend_from_slice(SPECIFIC_DATA_GUID.as_bytes());
+/// bytes.extend_from_slice(&[0xAA, 0xBB]);
+///
+/// let data = StatusCodeData::from_bytes(&bytes).unwrap();
+/// assert_eq!(data.payload_guid(), SPECIFIC_DATA_GUID);
+/// assert_eq!(data.payload(), &[0xAA, 0xBB]);
+///```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatusCodeData<'a> {
+    bytes: &'a [u8],
+    header_size: usize,
+}
+
+impl<'a> StatusCodeData<'a> {
+    /// Creates a view over `bytes`, which must start with an EFI_STATUS_CODE_DATA header. Bytes beyond the header and
+    /// payload are ignored.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, DataError> {
+        if bytes.len() < size_of::<EfiStatusCodeData>() {
+            Err(DataError::Truncated)?;
+        }
+        let header_size = u16::from_le_bytes([bytes[0], bytes[1]]);
+        let size = u16::from_le_bytes([bytes[2], bytes[3]]) as usize;
+        if (header_size as usize) < size_of::<EfiStatusCodeData>() {
+            Err(DataError::InvalidHeaderSize(header_size))?;
+        }
+        let header_size = header_size as usize;
+        let bytes = bytes.get(..header_size + size).ok_or(DataError::Truncated)?;
+        Ok(Self { bytes, header_size })
+    }
+
+    /// Creates a view over the extended data at `data`, whose length is implied by its header.
+    ///
+    /// ## Safety
+    /// If not null, `data` must point to an EFI_STATUS_CODE_DATA header followed by the number of payload bytes given
+    /// by its `size` field, all valid for reads for the lifetime `'a`.
+    pub unsafe fn from_ptr(data: *const EfiStatusCodeData) -> Result<Self, DataError> {
+        if data.is_null() {
+            Err(DataError::Null)?;
+        }
+        let header = ptr::read_unaligned(data);
+        if (header.header_size as usize) < size_of::<EfiStatusCodeData>() {
+            Err(DataError::InvalidHeaderSize(header.header_size))?;
+        }
+        let len = header.header_size as usize + header.size as usize;
+        Self::from_bytes(slice::from_raw_parts(data as *const u8, len))
+    }
+
+    /// Returns the size of the header, including any bytes between EFI_STATUS_CODE_DATA and the payload.
+    pub fn header_size(&self) -> usize {
+        self.header_size
+    }
+
+    /// Returns the GUID identifying the format of the payload.
+    pub fn payload_guid(&self) -> efi::Guid {
+        let mut guid = [0u8; 16];
+        guid.copy_from_slice(&self.bytes[4..20]);
+        efi::Guid::from_bytes(&guid)
+    }
+
+    /// Returns the payload.
+    pub fn payload(&self) -> &'a [u8] {
+        &self.bytes[self.header_size..]
+    }
+
+    /// Returns the payload if its format is identified by `guid`.
+    pub fn payload_if(&self, guid: &efi::Guid) -> Option<&'a [u8]> {
+        (self.payload_guid() == *guid).then(|| self.payload())
+    }
+
+    /// Returns the string reported by string data, or `None` if this is not valid string data.
+    pub fn string(&self) -> Option<StatusCodeString> {
+        let payload = self.payload_if(&STRING_DATA_TYPE_GUID)?;
+        let string_type = u32::from_le_bytes(payload.get(..4)?.try_into().ok()?);
+        let pointer = read_usize(payload, STRING_OFFSET)?;
+        match string_type {
+            STRING_TYPE_ASCII => Some(StatusCodeString::Ascii(pointer as *const u8)),
+            STRING_TYPE_UNICODE => Some(StatusCodeString::Unicode(pointer as *const efi::Char16)),
+            STRING_TYPE_TOKEN => {
+                let token = payload.get(STRING_OFFSET + size_of::<usize>()..STRING_OFFSET + size_of::<usize>() + 2)?;
+                Some(StatusCodeString::Token {
+                    handle: pointer as hii::Handle,
+                    token: u16::from_ne_bytes([token[0], token[1]]),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the error level and the remaining bytes of debug data, or `None` if this is not debug data.
+    pub fn debug_info(&self) -> Option<(u32, &'a [u8])> {
+        let payload = self.payload_if(&DEBUG_DATA_TYPE_GUID)?;
+        let error_level = u32::from_le_bytes(payload.get(..4)?.try_into().ok()?);
+        Some((error_level, &payload[4..]))
+    }
+
+    /// Returns the device path of device path extended data, or `None` if this is not specific data that holds a valid
+    /// device path.
+    ///
+    /// Specific data is interpreted according to the status code value it is reported with, so callers should only
+    /// use this for codes that are defined to carry EFI_DEVICE_PATH_EXTENDED_DATA.
+    pub fn as_device_path(&self) -> Option<DevicePathWalker<'a>> {
+        let payload = self.payload_if(&SPECIFIC_DATA_GUID)?;
+        DevicePathWalker::from_slice(payload).ok()
+    }
+
+    /// Returns the memory extended error data, or `None` if this is not specific data large enough to hold it.
+    ///
+    /// Specific data is interpreted according to the status code value it is reported with, so callers should only
+    /// use this for codes that are defined to carry EFI_MEMORY_EXTENDED_ERROR_DATA.
+    pub fn as_memory_error(&self) -> Option<EfiMemoryExtendedErrorData> {
+        let payload = self.payload_if(&SPECIFIC_DATA_GUID)?;
+        let payload = payload.get(..size_of::<EfiMemoryExtendedErrorData>() - size_of::<EfiStatusCodeData>())?;
+        let mut data = EfiMemoryExtendedErrorData {
+            data_header: EfiStatusCodeData {
+                header_size: self.header_size as u16,
+                size: payload.len() as u16,
+                r#type: SPECIFIC_DATA_GUID,
+            },
+            granularity: 0,
+            operation: 0,
+            syndrome: 0,
+            address: 0,
+            resolution: 0,
+        };
+        //Safety: the payload is copied over the fields that follow the header (and the padding between them), all of
+        //which are integers for which any bit pattern is valid.
+        unsafe {
+            let fields = (&mut data as *mut EfiMemoryExtendedErrorData as *mut u8).add(size_of::<EfiStatusCodeData>());
+            ptr::copy_nonoverlapping(payload.as_ptr(), fields, payload.len());
+        }
+        Some(data)
+    }
+}
+
+fn read_usize(bytes: &[u8], offset: usize) -> Option<usize> {
+    let bytes = bytes.get(offset..offset + size_of::<usize>())?;
+    Some(usize::from_ne_bytes(bytes.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{mem::size_of, ptr};
+
+    use r_efi::efi;
+
+    use super::{
+        DataError, EfiMemoryExtendedErrorData, EfiStatusCodeData, StatusCodeData, StatusCodeString,
+        DEBUG_DATA_TYPE_GUID, EFI_MEMORY_ERROR_MODULE, EFI_MEMORY_OPERATION_PARTIAL_WRITE, SPECIFIC_DATA_GUID,
+        STRING_DATA_TYPE_GUID,
+    };
+
+    fn data(guid: &efi::Guid, payload: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&20u16.to_le_bytes());
+        bytes.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(guid.as_bytes());
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    fn string_payload(string_type: u32, pointer: usize, token: Option<u16>) -> Vec<u8> {
+        let mut payload = string_type.to_le_bytes().to_vec();
+        payload.extend_from_slice(&pointer.to_ne_bytes());
+        if let Some(token) = token {
+            payload.extend_from_slice(&token.to_ne_bytes());
+        }
+        payload
+    }
+
+    #[test]
+    fn guids_should_match_spec() {
+        assert_eq!(
+            STRING_DATA_TYPE_GUID.as_bytes(),
+            &[0x80, 0x10, 0xD1, 0x92, 0x6F, 0x49, 0x95, 0x4D, 0xBE, 0x7E, 0x03, 0x74, 0x88, 0x38, 0x2B, 0x0A]
+        );
+        assert_eq!(
+            SPECIFIC_DATA_GUID.as_bytes(),
+            &[0xBD, 0x84, 0x59, 0x33, 0x05, 0xE8, 0x9A, 0x40, 0xB8, 0xF8, 0xD2, 0x7E, 0xCE, 0x5F, 0xF7, 0xA6]
+        );
+        assert_eq!(
+            DEBUG_DATA_TYPE_GUID.as_bytes(),
+            &[0x46, 0x92, 0x4E, 0x9A, 0x53, 0xD5, 0xD5, 0x11, 0x87, 0xE2, 0x00, 0x06, 0x29, 0x45, 0xC3, 0xB9]
+        );
+        assert_eq!(size_of::<EfiStatusCodeData>(), 20);
+    }
+
+    #[test]
+    fn from_bytes_should_validate_sizes() {
+        let bytes = data(&SPECIFIC_DATA_GUID, &[1, 2, 3]);
+        let view = StatusCodeData::from_bytes(&bytes).unwrap();
+        assert_eq!(view.header_size(), 20);
+        assert_eq!(view.payload(), &[1, 2, 3]);
+        assert_eq!(view.payload_if(&SPECIFIC_DATA_GUID), Some(&[1u8, 2, 3][..]));
+        assert_eq!(view.payload_if(&DEBUG_DATA_TYPE_GUID), None);
+
+        // trailing bytes are not part of the view.
+        let mut longer = bytes.clone();
+        longer.push(0xFF);
+        assert_eq!(StatusCodeData::from_bytes(&longer).unwrap().payload(), &[1, 2, 3]);
+
+        assert_eq!(StatusCodeData::from_bytes(&bytes[..22]), Err(DataError::Truncated));
+        assert_eq!(StatusCodeData::from_bytes(&bytes[..19]), Err(DataError::Truncated));
+
+        let mut bad = bytes.clone();
+        bad[0] = 16;
+        assert_eq!(StatusCodeData::from_bytes(&bad), Err(DataError::InvalidHeaderSize(16)));
+
+        // a larger header pushes the payload back.
+        let mut bigger = bytes;
+        bigger[0] = 21;
+        bigger[2] = 2;
+        assert_eq!(StatusCodeData::from_bytes(&bigger).unwrap().payload(), &[2, 3]);
+    }
+
+    #[test]
+    fn from_ptr_should_use_implied_length() {
+        let bytes = data(&SPECIFIC_DATA_GUID, &[4, 5]);
+        let view = unsafe { StatusCodeData::from_ptr(bytes.as_ptr() as *const EfiStatusCodeData) }.unwrap();
+        assert_eq!(view.payload_guid(), SPECIFIC_DATA_GUID);
+        assert_eq!(view.payload(), &[4, 5]);
+
+        assert_eq!(unsafe { StatusCodeData::from_ptr(ptr::null()) }, Err(DataError::Null));
+
+        let mut bad = bytes;
+        bad[0] = 0;
+        let result = unsafe { StatusCodeData::from_ptr(bad.as_ptr() as *const EfiStatusCodeData) };
+        assert_eq!(result, Err(DataError::InvalidHeaderSize(0)));
+    }
+
+    #[test]
+    fn string_should_decode_each_string_type() {
+        let ascii = b"hello\0";
+        let bytes = data(&STRING_DATA_TYPE_GUID, &string_payload(0, ascii.as_ptr() as usize, None));
+        let view = StatusCodeData::from_bytes(&bytes).unwrap();
+        assert_eq!(view.string(), Some(StatusCodeString::Ascii(ascii.as_ptr())));
+
+        let unicode = [b'h' as u16, 0];
+        let bytes = data(&STRING_DATA_TYPE_GUID, &string_payload(1, unicode.as_ptr() as usize, None));
+        let view = StatusCodeData::from_bytes(&bytes).unwrap();
+        assert_eq!(view.string(), Some(StatusCodeString::Unicode(unicode.as_ptr())));
+
+        let bytes = data(&STRING_DATA_TYPE_GUID, &string_payload(2, 0x1234, Some(0x55)));
+        let view = StatusCodeData::from_bytes(&bytes).unwrap();
+        assert_eq!(view.string(), Some(StatusCodeString::Token { handle: 0x1234 as *mut _, token: 0x55 }));
+    }
+
+    #[test]
+    fn string_should_reject_invalid_data() {
+        // unknown string type.
+        let bytes = data(&STRING_DATA_TYPE_GUID, &string_payload(3, 0x1000, None));
+        assert_eq!(StatusCodeData::from_bytes(&bytes).unwrap().string(), None);
+
+        // token without string id.
+        let bytes = data(&STRING_DATA_TYPE_GUID, &string_payload(2, 0x1000, None));
+        assert_eq!(StatusCodeData::from_bytes(&bytes).unwrap().string(), None);
+
+        // truncated pointer.
+        let payload = string_payload(0, 0x1000, None);
+        let bytes = data(&STRING_DATA_TYPE_GUID, &payload[..6]);
+        assert_eq!(StatusCodeData::from_bytes(&bytes).unwrap().string(), None);
+
+        // not string data.
+        let bytes = data(&DEBUG_DATA_TYPE_GUID, &string_payload(0, 0x1000, None));
+        assert_eq!(StatusCodeData::from_bytes(&bytes).unwrap().string(), None);
+    }
+
+    #[test]
+    fn debug_info_should_return_error_level() {
+        let mut payload = 0x80000000u32.to_le_bytes().to_vec();
+        payload.extend_from_slice(b"rest");
+        let bytes = data(&DEBUG_DATA_TYPE_GUID, &payload);
+        let view = StatusCodeData::from_bytes(&bytes).unwrap();
+        assert_eq!(view.debug_info(), Some((0x80000000, &b"rest"[..])));
+
+        let bytes = data(&DEBUG_DATA_TYPE_GUID, &[1, 2]);
+        assert_eq!(StatusCodeData::from_bytes(&bytes).unwrap().debug_info(), None);
+
+        let bytes = data(&SPECIFIC_DATA_GUID, &payload);
+        assert_eq!(StatusCodeData::from_bytes(&bytes).unwrap().debug_info(), None);
+    }
+
+    #[test]
+    fn as_device_path_should_walk_payload() {
+        // PCI(0x1F, 0x2) followed by the end node.
+        let path = [0x01, 0x01, 0x06, 0x00, 0x02, 0x1F, 0x7F, 0xFF, 0x04, 0x00];
+        let bytes = data(&SPECIFIC_DATA_GUID, &path);
+        let walker = StatusCodeData::from_bytes(&bytes).unwrap().as_device_path().unwrap();
+        assert_eq!(walker.as_bytes(), &path);
+        let nodes: Vec<_> = walker.iter().map(|node| (node.node_type(), node.sub_type())).collect();
+        assert_eq!(nodes, vec![(0x01, 0x01)]);
+
+        // Missing end node.
+        let bytes = data(&SPECIFIC_DATA_GUID, &path[..6]);
+        assert!(StatusCodeData::from_bytes(&bytes).unwrap().as_device_path().is_none());
+        let bytes = data(&STRING_DATA_TYPE_GUID, &path);
+        assert!(StatusCodeData::from_bytes(&bytes).unwrap().as_device_path().is_none());
+    }
+
+    #[test]
+    fn as_memory_error_should_decode_payload() {
+        let size = size_of::<EfiMemoryExtendedErrorData>();
+        let mut bytes = vec![0u8; size];
+        bytes[0..2].copy_from_slice(&20u16.to_le_bytes());
+        bytes[2..4].copy_from_slice(&((size - 20) as u16).to_le_bytes());
+        bytes[4..20].copy_from_slice(SPECIFIC_DATA_GUID.as_bytes());
+        bytes[offset_of!(EfiMemoryExtendedErrorData, granularity)] = EFI_MEMORY_ERROR_MODULE;
+        bytes[offset_of!(EfiMemoryExtendedErrorData, operation)] = EFI_MEMORY_OPERATION_PARTIAL_WRITE;
+        let syndrome = offset_of!(EfiMemoryExtendedErrorData, syndrome);
+        bytes[syndrome..syndrome + size_of::<usize>()].copy_from_slice(&0x5Ausize.to_ne_bytes());
+        let address = offset_of!(EfiMemoryExtendedErrorData, address);
+        bytes[address..address + 8].copy_from_slice(&0x12345000u64.to_le_bytes());
+        let resolution = offset_of!(EfiMemoryExtendedErrorData, resolution);
+        bytes[resolution..resolution + size_of::<usize>()].copy_from_slice(&0x1000usize.to_ne_bytes());
+
+        let error = StatusCodeData::from_bytes(&bytes).unwrap().as_memory_error().unwrap();
+        assert_eq!(error.data_header.header_size, 20);
+        assert_eq!(error.data_header.size as usize, size - 20);
+        assert_eq!(error.data_header.r#type, SPECIFIC_DATA_GUID);
+        assert_eq!(error.granularity, EFI_MEMORY_ERROR_MODULE);
+        assert_eq!(error.operation, EFI_MEMORY_OPERATION_PARTIAL_WRITE);
+        assert_eq!(error.syndrome, 0x5A);
+        assert_eq!(error.address, 0x12345000);
+        assert_eq!(error.resolution, 0x1000);
+
+        // Truncated payloads are rejected.
+        let bytes = data(&SPECIFIC_DATA_GUID, &bytes[20..size - 1]);
+        assert!(StatusCodeData::from_bytes(&bytes).unwrap().as_memory_error().is_none());
+    }
+
+    #[test]
+    fn memory_error_layout_should_match_spec() {
+        assert_eq!(offset_of!(EfiMemoryExtendedErrorData, granularity), 20);
+        assert_eq!(offset_of!(EfiMemoryExtendedErrorData, operation), 21);
+        assert_eq!(offset_of!(EfiMemoryExtendedErrorData, syndrome), 24);
+        if cfg!(target_pointer_width = "64") {
+            assert_eq!(offset_of!(EfiMemoryExtendedErrorData, address), 32);
+            assert_eq!(size_of::<EfiMemoryExtendedErrorData>(), 48);
+        }
+    }
+}
@@ -0,0 +1,567 @@
+//! Debug Messages
+//!
+//! Decoding and rendering of the EFI_DEBUG_INFO extended data that DEBUG() messages are reported with when the debug
+//! library routes them through the status code infrastructure.
+//!
+//! The payload is an EFI_DEBUG_INFO structure followed by the message arguments, each widened to a u64, in a
+//! fixed-size array of [`DEBUG_INFO_ARGS`] entries, followed by the null-terminated ASCII format string.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+extern crate alloc;
+use alloc::string::String;
+use core::{fmt::Write, mem::size_of};
+
+use r_efi::efi;
+
+use super::data::StatusCodeData;
+pub use super::data::DEBUG_DATA_TYPE_GUID;
+use crate::device_path::write_guid;
+
+/// Number of argument slots that follow EFI_DEBUG_INFO.
+pub const DEBUG_INFO_ARGS: usize = 12;
+
+// Upper bound on the number of characters read through a string argument pointer.
+const MAX_STRING_ARGUMENT: usize = 1024;
+
+// Upper bound on the width and precision of a conversion, so a corrupt format or argument cannot exhaust memory.
+const MAX_FIELD_WIDTH: usize = 256;
+
+/// Debug data header (EFI_DEBUG_INFO).
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section III-6.6.2.4
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EfiDebugInfo {
+    /// The debug error level passed to DEBUG().
+    pub error_level: u32,
+}
+
+/// A decoded DEBUG() message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DebugInfo<'a> {
+    /// The debug error level passed to DEBUG().
+    pub error_level: u32,
+    /// The message arguments, each widened to a u64. Unused slots are unspecified.
+    pub args: [u64; DEBUG_INFO_ARGS],
+    /// The format string, without the null terminator.
+    pub format: &'a [u8],
+}
+
+impl DebugInfo<'_> {
+    /// Renders the message. Pointer arguments (strings and GUIDs) are rendered as their address.
+    pub fn render(&self) -> String {
+        render_debug_message(self.format, &self.args)
+    }
+}
+
+/// Decodes debug data, returning `None` if `data` is not debug data or is too short to hold the argument array.
+///
+/// If the format string is not null-terminated, the rest of the payload is used as the format string.
+pub fn decode_debug_info<'a>(data: &StatusCodeData<'a>) -> Option<DebugInfo<'a>> {
+    let (error_level, rest) = data.debug_info()?;
+    let args_len = DEBUG_INFO_ARGS * size_of::<u64>();
+    if rest.len() < args_len {
+        return None;
+    }
+    let mut args = [0u64; DEBUG_INFO_ARGS];
+    for (arg, bytes) in args.iter_mut().zip(rest.chunks_exact(size_of::<u64>())) {
+        *arg = u64::from_le_bytes(bytes.try_into().ok()?);
+    }
+    let format = &rest[args_len..];
+    let format = &format[..format.iter().position(|&c| c == 0).unwrap_or(format.len())];
+    Some(DebugInfo { error_level, args, format })
+}
+
+/// Renders a DEBUG() message with printf-style `format` and `args`.
+///
+/// Supports the `%a %s %S %c %d %i %u %x %X %p %g %r %%` conversions of the edk2 print library along with the `-`,
+/// `0`, `+`, ` ` and `,` flags, widths (including `*`), precisions, and the `l`/`L` size modifiers. String and GUID
+/// arguments are pointers that cannot be safely followed, so they are rendered as their address.
+///
+/// Widths and precisions are clamped to 256 characters. Rendering stops at the first malformed or unsupported
+/// conversion, or when a conversion would need more arguments than are available.
+///
+/// ## Example
+///```
+/// use mu_pi::status_code::debug::render_debug_message;
+///
+/// let mut args = [0u64; 12];
+/// args[0] = 3;
+/// args[1] = 0x8000000000000005; // EFI_BUFFER_TOO_SMALL
+/// assert_eq!(render_debug_message(b"Found %d handles: %r\n", &args), "Found 3 handles: Buffer Too Small\n");
+///```
+pub fn render_debug_message(format: &[u8], args: &[u64]) -> String {
+    render(format, args, false)
+}
+
+/// Renders a DEBUG() message like [`render_debug_message`], but follows string and GUID pointer arguments.
+///
+/// At most 1024 characters are read through a string argument.
+///
+/// ## Safety
+/// Every `%a`, `%s`, `%S`, and `%g` argument consumed by `format` must be null or a valid pointer to a
+/// null-terminated string of the corresponding type, or to an efi::Guid, in the caller's address space.
+pub unsafe fn render_debug_message_with_pointers(format: &[u8], args: &[u64]) -> String {
+    render(format, args, true)
+}
+
+#[derive(Default)]
+struct Spec {
+    left_justify: bool,
+    zero_pad: bool,
+    sign: bool,
+    blank: bool,
+    comma: bool,
+    long: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+fn render(format: &[u8], args: &[u64], follow_pointers: bool) -> String {
+    let mut out = String::new();
+    let mut args = args.iter().copied();
+    let mut chars = format.iter().copied().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != b'%' {
+            out.push(c as char);
+            continue;
+        }
+
+        let mut spec = Spec::default();
+        // flags
+        while let Some(&flag) = chars.peek() {
+            match flag {
+                b'-' => spec.left_justify = true,
+                b'0' => spec.zero_pad = true,
+                b'+' => spec.sign = true,
+                b' ' => spec.blank = true,
+                b',' => spec.comma = true,
+                _ => break,
+            }
+            chars.next();
+        }
+        // width
+        if chars.peek() == Some(&b'*') {
+            chars.next();
+            match args.next() {
+                Some(width) => spec.width = width.min(MAX_FIELD_WIDTH as u64) as usize,
+                None => return out,
+            }
+        } else {
+            spec.width = parse_number(&mut chars).min(MAX_FIELD_WIDTH);
+        }
+        // precision
+        if chars.peek() == Some(&b'.') {
+            chars.next();
+            if chars.peek() == Some(&b'*') {
+                chars.next();
+                match args.next() {
+                    Some(precision) => spec.precision = Some(precision.min(MAX_FIELD_WIDTH as u64) as usize),
+                    None => return out,
+                }
+            } else {
+                spec.precision = Some(parse_number(&mut chars).min(MAX_FIELD_WIDTH));
+            }
+        }
+        // size
+        while let Some(b'l' | b'L') = chars.peek() {
+            spec.long = true;
+            chars.next();
+        }
+
+        let conversion = match chars.next() {
+            Some(conversion) => conversion,
+            None => return out,
+        };
+        if conversion == b'%' {
+            out.push('%');
+            continue;
+        }
+        if !matches!(conversion, b'a' | b's' | b'S' | b'c' | b'd' | b'i' | b'u' | b'x' | b'X' | b'p' | b'g' | b'r') {
+            return out;
+        }
+        let arg = match args.next() {
+            Some(arg) => arg,
+            None => return out,
+        };
+
+        let mut text = String::new();
+        match conversion {
+            b'a' | b's' | b'S' | b'g' if !follow_pointers || arg == 0 => {
+                if arg == 0 && conversion != b'g' {
+                    text.push_str("<null string>");
+                } else if arg == 0 {
+                    text.push_str("<null guid>");
+                } else {
+                    let _ = write!(text, "<{arg:#X}>");
+                }
+            }
+            //Safety: the caller of render_debug_message_with_pointers guarantees the pointers are valid.
+            b'a' => unsafe { read_string(arg as usize as *const u8, spec.precision, &mut text) },
+            b's' | b'S' => unsafe { read_string(arg as usize as *const u16, spec.precision, &mut text) },
+            b'g' => {
+                let guid = unsafe { core::ptr::read_unaligned(arg as usize as *const efi::Guid) };
+                let _ = write_guid(&mut text, &guid);
+            }
+            b'c' => text.push(char::from_u32(arg as u16 as u32).unwrap_or(char::REPLACEMENT_CHARACTER)),
+            b'r' => write_status(arg, &mut text),
+            _ => {
+                if write_number(conversion, arg, &mut spec, &mut text).is_none() {
+                    return out;
+                }
+            }
+        }
+
+        // strings are truncated to the precision; numbers handle their own precision.
+        if matches!(conversion, b'a' | b's' | b'S') {
+            if let Some(precision) = spec.precision {
+                if let Some((idx, _)) = text.char_indices().nth(precision) {
+                    text.truncate(idx);
+                }
+            }
+        }
+        pad(&mut out, &text, &spec, matches!(conversion, b'd' | b'i' | b'u' | b'x' | b'X' | b'p'));
+    }
+    out
+}
+
+fn parse_number(chars: &mut core::iter::Peekable<impl Iterator<Item = u8>>) -> usize {
+    let mut value = 0usize;
+    while let Some(&digit @ b'0'..=b'9') = chars.peek() {
+        value = value.saturating_mul(10).saturating_add((digit - b'0') as usize);
+        chars.next();
+    }
+    value
+}
+
+fn write_number(conversion: u8, arg: u64, spec: &mut Spec, text: &mut String) -> Option<()> {
+    let mut negative = false;
+    let digits = match conversion {
+        b'd' | b'i' => {
+            let value = if spec.long { arg as i64 } else { arg as u32 as i32 as i64 };
+            negative = value < 0;
+            group(&alloc::format!("{}", value.unsigned_abs()), spec.comma)
+        }
+        b'u' => {
+            let value = if spec.long { arg } else { arg as u32 as u64 };
+            group(&alloc::format!("{value}"), spec.comma)
+        }
+        b'x' | b'X' | b'p' => {
+            if conversion != b'x' {
+                spec.zero_pad = true;
+            }
+            let value = if spec.long || conversion == b'p' { arg } else { arg as u32 as u64 };
+            alloc::format!("{value:X}")
+        }
+        _ => None?,
+    };
+
+    let mut digits = digits;
+    if let Some(precision) = spec.precision {
+        while digits.len() < precision {
+            digits.insert(0, '0');
+        }
+    }
+    if negative {
+        text.push('-');
+    } else if spec.sign && matches!(conversion, b'd' | b'i') {
+        text.push('+');
+    } else if spec.blank && matches!(conversion, b'd' | b'i') {
+        text.push(' ');
+    }
+    text.push_str(&digits);
+    Some(())
+}
+
+fn group(digits: &str, comma: bool) -> String {
+    if !comma {
+        return String::from(digits);
+    }
+    let mut grouped = String::new();
+    for (idx, c) in digits.chars().enumerate() {
+        if idx > 0 && (digits.len() - idx) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    grouped
+}
+
+fn pad(out: &mut String, text: &str, spec: &Spec, numeric: bool) {
+    let len = text.chars().count();
+    let fill = spec.width.saturating_sub(len);
+    if spec.left_justify {
+        out.push_str(text);
+        out.extend(core::iter::repeat(' ').take(fill));
+    } else if spec.zero_pad && numeric && spec.precision.is_none() {
+        // zeros go after any sign.
+        let (sign, digits) = match text.as_bytes().first() {
+            Some(b'-' | b'+' | b' ') => text.split_at(1),
+            _ => ("", text),
+        };
+        out.push_str(sign);
+        out.extend(core::iter::repeat('0').take(fill));
+        out.push_str(digits);
+    } else {
+        out.extend(core::iter::repeat(' ').take(fill));
+        out.push_str(text);
+    }
+}
+
+trait StringChar: Copy {
+    fn is_null(self) -> bool;
+    fn to_char(self) -> char;
+}
+
+impl StringChar for u8 {
+    fn is_null(self) -> bool {
+        self == 0
+    }
+
+    fn to_char(self) -> char {
+        self as char
+    }
+}
+
+impl StringChar for u16 {
+    fn is_null(self) -> bool {
+        self == 0
+    }
+
+    fn to_char(self) -> char {
+        char::from_u32(self as u32).unwrap_or(char::REPLACEMENT_CHARACTER)
+    }
+}
+
+unsafe fn read_string<T: StringChar>(ptr: *const T, precision: Option<usize>, text: &mut String) {
+    let limit = precision.unwrap_or(MAX_STRING_ARGUMENT).min(MAX_STRING_ARGUMENT);
+    for idx in 0..limit {
+        let c = core::ptr::read_unaligned(ptr.add(idx));
+        if c.is_null() {
+            break;
+        }
+        text.push(c.to_char());
+    }
+}
+
+// Status strings used by the edk2 print library for %r, indexed by the status code without its error bit.
+const WARNING_STRINGS: &[&str] = &[
+    "Success",
+    "Warning Unknown Glyph",
+    "Warning Delete Failure",
+    "Warning Write Failure",
+    "Warning Buffer Too Small",
+    "Warning Stale Data",
+    "Warning File System",
+    "Warning Reset Required",
+];
+
+const ERROR_STRINGS: &[&str] = &[
+    "",
+    "Load Error",
+    "Invalid Parameter",
+    "Unsupported",
+    "Bad Buffer Size",
+    "Buffer Too Small",
+    "Not Ready",
+    "Device Error",
+    "Write Protected",
+    "Out of Resources",
+    "Volume Corrupt",
+    "Volume Full",
+    "No Media",
+    "Media changed",
+    "Not Found",
+    "Access Denied",
+    "No Response",
+    "No mapping",
+    "Time out",
+    "Not started",
+    "Already started",
+    "Aborted",
+    "ICMP Error",
+    "TFTP Error",
+    "Protocol Error",
+    "Incompatible Version",
+    "Security Violation",
+    "CRC Error",
+    "End of Media",
+    "Reserved (29)",
+    "Reserved (30)",
+    "End of File",
+    "Invalid Language",
+    "Compromised Data",
+    "IP Address Conflict",
+    "HTTP Error",
+];
+
+fn write_status(arg: u64, text: &mut String) {
+    // the error bit is the top bit of a UINTN, which may have been 32 bits wide when the message was reported.
+    let (is_error, code) = if arg & (1 << 63) != 0 {
+        (true, arg & !(1 << 63))
+    } else if arg <= u32::MAX as u64 && arg & (1 << 31) != 0 {
+        (true, arg & !(1 << 31))
+    } else {
+        (false, arg)
+    };
+    let name = match is_error {
+        false => WARNING_STRINGS.get(code as usize),
+        true if code != 0 => ERROR_STRINGS.get(code as usize),
+        true => None,
+    };
+    match name {
+        Some(name) => text.push_str(name),
+        None => {
+            let _ = write!(text, "{arg:08X}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use r_efi::efi;
+
+    use super::{
+        decode_debug_info, render_debug_message, render_debug_message_with_pointers, DebugInfo, DEBUG_DATA_TYPE_GUID,
+        DEBUG_INFO_ARGS,
+    };
+    use crate::status_code::data::{StatusCodeData, SPECIFIC_DATA_GUID};
+
+    const DEBUG_INFO: u32 = 0x00000040;
+    const DEBUG_ERROR: u32 = 0x80000000;
+
+    // Builds extended data as reported by the edk2 DEBUG() status code path.
+    fn payload(error_level: u32, args: &[u64], format: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&20u16.to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes());
+        data.extend_from_slice(DEBUG_DATA_TYPE_GUID.as_bytes());
+        data.extend_from_slice(&error_level.to_le_bytes());
+        for idx in 0..DEBUG_INFO_ARGS {
+            data.extend_from_slice(&args.get(idx).copied().unwrap_or(0).to_le_bytes());
+        }
+        data.extend_from_slice(format);
+        let size = (data.len() - 20) as u16;
+        data[2..4].copy_from_slice(&size.to_le_bytes());
+        data
+    }
+
+    fn decode(data: &[u8]) -> Option<DebugInfo> {
+        decode_debug_info(&StatusCodeData::from_bytes(data).unwrap())
+    }
+
+    #[test]
+    fn decode_should_extract_captured_payloads() {
+        let data = payload(
+            DEBUG_INFO,
+            &[0x7E5C1000, 0x7E5C1240, 0x7F6B2018],
+            b"Loading driver at 0x%11p EntryPoint=0x%11p %a\n\0",
+        );
+        let info = decode(&data).unwrap();
+        assert_eq!(info.error_level, DEBUG_INFO);
+        assert_eq!(info.args[..3], [0x7E5C1000, 0x7E5C1240, 0x7F6B2018]);
+        assert_eq!(info.format, b"Loading driver at 0x%11p EntryPoint=0x%11p %a\n");
+        assert_eq!(info.render(), "Loading driver at 0x0007E5C1000 EntryPoint=0x0007E5C1240 <0x7F6B2018>\n");
+
+        let data = payload(DEBUG_ERROR, &[0x800000000000000E], b"ConnectController failed: %r\n\0");
+        let info = decode(&data).unwrap();
+        assert_eq!(info.error_level, DEBUG_ERROR);
+        assert_eq!(info.render(), "ConnectController failed: Not Found\n");
+
+        let data = payload(DEBUG_INFO, &[0x1000, 0x20, 0x1020], b"MemoryProtection: [%lx, %x) size %,d\n\0");
+        assert_eq!(decode(&data).unwrap().render(), "MemoryProtection: [1000, 20) size 4,128\n");
+    }
+
+    #[test]
+    fn decode_should_reject_invalid_payloads() {
+        let mut data = payload(DEBUG_INFO, &[], b"\0");
+        // replace the GUID.
+        data[4..20].copy_from_slice(SPECIFIC_DATA_GUID.as_bytes());
+        assert_eq!(decode(&data), None);
+
+        // too short for the argument array.
+        let mut data = payload(DEBUG_INFO, &[], b"");
+        data.truncate(data.len() - 1);
+        data[2] -= 1;
+        assert_eq!(decode(&data), None);
+
+        // a missing terminator uses the rest of the payload.
+        let data = payload(DEBUG_INFO, &[], b"abc");
+        assert_eq!(decode(&data).unwrap().format, b"abc");
+    }
+
+    #[test]
+    fn render_should_format_numbers() {
+        let args = [0xFFFFFFFF, 42, 0x1234ABCD, 7, u64::MAX, 5];
+        assert_eq!(render_debug_message(b"%d %d %x %5d|%-5d|", &args), "-1 42 1234ABCD     7|-1   |");
+        assert_eq!(render_debug_message(b"%ld %lx %u", &[u64::MAX, u64::MAX, args[5]]), "-1 FFFFFFFFFFFFFFFF 5");
+        assert_eq!(
+            render_debug_message(b"%08X %X %8x %08x", &[0xAB, 0xAB, 0xAB, 0xAB]),
+            "000000AB AB       AB 000000AB"
+        );
+        assert_eq!(render_debug_message(b"%+d % d %05d", &[3, 3, (-3i32) as u32 as u64]), "+3  3 -0003");
+        assert_eq!(render_debug_message(b"%p", &[0x7E5C1000]), "7E5C1000");
+        assert_eq!(render_debug_message(b"%*d|%.3d", &[4, 1, 2]), "   1|002");
+    }
+
+    #[test]
+    fn render_should_clamp_widths_and_precisions() {
+        assert_eq!(render_debug_message(b"%*d", &[u64::MAX, 1]), format!("{:>256}", 1));
+        assert_eq!(render_debug_message(b"%.*d", &[u64::MAX, 1]), format!("{:0>256}", 1));
+        assert_eq!(render_debug_message(b"%99999999999999999999999d", &[1]), format!("{:>256}", 1));
+        assert_eq!(render_debug_message(b"%.99999999999999999999999x", &[1]), format!("{:0>256}", 1));
+        assert_eq!(render_debug_message(b"%c%c 100%%", &[b'o' as u64, b'k' as u64]), "ok 100%");
+    }
+
+    #[test]
+    fn render_should_format_statuses() {
+        let render = |status: u64| render_debug_message(b"%r", &[status]);
+        assert_eq!(render(0), "Success");
+        assert_eq!(render(4), "Warning Buffer Too Small");
+        assert_eq!(render(efi::Status::INVALID_PARAMETER.as_usize() as u64), "Invalid Parameter");
+        assert_eq!(render(0x80000003), "Unsupported");
+        assert_eq!(render(0x8000000000000023), "HTTP Error");
+        assert_eq!(render(0x8000000000000024), "8000000000000024");
+        assert_eq!(render(0x80000000), "80000000");
+        assert_eq!(render(0x100), "00000100");
+    }
+
+    #[test]
+    fn render_should_stop_on_malformed_format() {
+        let args = [1u64; DEBUG_INFO_ARGS];
+        assert_eq!(render_debug_message(b"a=%d b=%", &args), "a=1 b=");
+        assert_eq!(render_debug_message(b"a=%d b=%q c=%d", &args), "a=1 b=");
+        assert_eq!(render_debug_message(b"a=%5", &args), "a=");
+        assert_eq!(render_debug_message(b"%d%d", &args[..1]), "1");
+        assert_eq!(render_debug_message(b"%*d", &[]), "");
+
+        // never consumes more than the available arguments.
+        let format = b"%d".repeat(DEBUG_INFO_ARGS + 2);
+        assert_eq!(render_debug_message(&format, &args), "1".repeat(DEBUG_INFO_ARGS));
+    }
+
+    #[test]
+    fn render_should_follow_pointers_when_requested() {
+        let ascii = b"PciBus\0";
+        let unicode: Vec<u16> = "Boot0001\0".encode_utf16().collect();
+        let guid =
+            efi::Guid::from_fields(0x5B1B31A1, 0x9562, 0x11D2, 0x8E, 0x3F, &[0x00, 0xA0, 0xC9, 0x69, 0x72, 0x3B]);
+        let args = [ascii.as_ptr() as u64, unicode.as_ptr() as u64, &guid as *const _ as u64, 0];
+        let format = b"%a %s %g %a|%.3a";
+
+        assert_eq!(
+            unsafe { render_debug_message_with_pointers(format, &[args[0], args[1], args[2], args[3], args[0]]) },
+            "PciBus Boot0001 5B1B31A1-9562-11D2-8E3F-00A0C969723B <null string>|Pci"
+        );
+        let rendered = render_debug_message(b"%a %g", &args[2..]);
+        assert_eq!(rendered, std::format!("<{:#X}> <null guid>", args[2]));
+    }
+}
@@ -0,0 +1,328 @@
+//! Status Code Names
+//!
+//! Symbolic names and `Display` implementations for status code types and values. All names are looked up in the
+//! tables generated alongside the constants in the [`class`](super::class), [`subclass`](super::subclass),
+//! [`progress`](super::progress), and [`error`](super::error) modules. Caller IDs are named with a
+//! [`CallerIdMap`].
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::fmt;
+
+use r_efi::efi;
+
+use super::{class, error, progress, subclass, CallerIdMap, StatusCodeType, StatusCodeValue};
+use crate::device_path::write_guid;
+
+type ValueTable = &'static [(&'static str, StatusCodeValue)];
+type OperationTable = &'static [(&'static str, u16)];
+
+// Tables used to name a value, selected by code type and indexed by class.
+struct Tables {
+    values: [ValueTable; 4],
+    operations: [OperationTable; 4],
+}
+
+const PROGRESS: Tables = Tables {
+    values: [progress::computing_unit::ALL, progress::peripheral::ALL, progress::io_bus::ALL, progress::software::ALL],
+    operations: [
+        progress::computing_unit::OPERATIONS,
+        progress::peripheral::OPERATIONS,
+        progress::io_bus::OPERATIONS,
+        progress::software::OPERATIONS,
+    ],
+};
+
+const ERROR: Tables = Tables {
+    values: [error::computing_unit::ALL, error::peripheral::ALL, error::io_bus::ALL, error::software::ALL],
+    operations: [
+        error::computing_unit::OPERATIONS,
+        error::peripheral::OPERATIONS,
+        error::io_bus::OPERATIONS,
+        error::software::OPERATIONS,
+    ],
+};
+
+const SUBCLASSES: [&[(&str, u8)]; 4] =
+    [subclass::computing_unit::ALL, subclass::peripheral::ALL, subclass::io_bus::ALL, subclass::software::ALL];
+
+const SEVERITIES: &[(&str, u32)] = &[
+    ("EFI_ERROR_MINOR", StatusCodeType::EFI_ERROR_MINOR),
+    ("EFI_ERROR_MAJOR", StatusCodeType::EFI_ERROR_MAJOR),
+    ("EFI_ERROR_UNRECOVERED", StatusCodeType::EFI_ERROR_UNRECOVERED),
+    ("EFI_ERROR_UNCONTAINED", StatusCodeType::EFI_ERROR_UNCONTAINED),
+];
+
+fn lookup<T: PartialEq>(table: &'static [(&'static str, T)], value: T) -> Option<&'static str> {
+    table.iter().find(|(_, v)| *v == value).map(|(name, _)| *name)
+}
+
+fn subclass_name(value: StatusCodeValue) -> Option<&'static str> {
+    lookup(SUBCLASSES.get(value.class() as usize)?, value.subclass())
+}
+
+// Returns the operation part of a constant name, e.g. "ENTRY_POINT" for "EFI_SW_DXE_CORE_PC_ENTRY_POINT" or
+// "PAGE_FAULT" for "EFI_SW_EC_IA32_PAGE_FAULT".
+fn operation_name(name: &'static str, subclass: &str) -> &'static str {
+    let name = name.strip_prefix("EFI_").unwrap_or(name);
+    let name = match [name.find("_PC_"), name.find("_EC_")] {
+        [Some(idx), _] | [None, Some(idx)] => &name[idx + 4..],
+        [None, None] => name.split_once('_').map_or(name, |(_, rest)| rest),
+    };
+    let subclass_prefix = subclass.split('_').next().unwrap_or(subclass);
+    match name.strip_prefix(subclass_prefix) {
+        Some(rest) if rest.starts_with('_') => &rest[1..],
+        _ => name,
+    }
+}
+
+fn value_name(value: StatusCodeValue, tables: &Tables) -> Option<&'static str> {
+    lookup(tables.values.get(value.class() as usize)?, value)
+}
+
+// Returns the subclass and operation names of a value, if both are known.
+fn symbolic(value: StatusCodeValue, tables: &Tables) -> Option<(&'static str, &'static str)> {
+    let subclass = subclass_name(value)?;
+    let name = match value_name(value, tables) {
+        Some(name) => name,
+        None if value.operation() < StatusCodeValue::SUBCLASS_SPECIFIC => {
+            lookup(tables.operations.get(value.class() as usize)?, value.operation())?
+        }
+        None => None?,
+    };
+    Some((subclass, operation_name(name, subclass)))
+}
+
+fn fmt_value(value: StatusCodeValue, tables: &[&Tables], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match tables.iter().find_map(|tables| symbolic(value, tables)) {
+        Some((subclass, operation)) => write!(f, "{subclass}|{operation}"),
+        None => write!(f, "class={:#04x} sub={:#04x} op={:#06x}", value.class(), value.subclass(), value.operation()),
+    }
+}
+
+fn tables_for(code_type: StatusCodeType) -> &'static [&'static Tables] {
+    match code_type.code_type() {
+        StatusCodeType::PROGRESS_CODE => &[&PROGRESS],
+        StatusCodeType::ERROR_CODE => &[&ERROR],
+        _ => &[],
+    }
+}
+
+impl StatusCodeValue {
+    /// Returns the name of the progress or error code constant with this value, e.g.
+    /// `"EFI_SW_DXE_CORE_PC_ENTRY_POINT"`.
+    ///
+    /// Some progress and error codes share a value; progress codes take precedence. Use [`Self::name_for`] when the
+    /// type of the code is known.
+    pub fn name(&self) -> Option<&'static str> {
+        value_name(*self, &PROGRESS).or_else(|| value_name(*self, &ERROR))
+    }
+
+    /// Returns the name of the constant with this value for a code of the given type.
+    pub fn name_for(&self, code_type: StatusCodeType) -> Option<&'static str> {
+        tables_for(code_type).iter().find_map(|tables| value_name(*self, tables))
+    }
+
+    /// Returns the name of the class of this value, e.g. `"SOFTWARE"`.
+    pub fn class_name(&self) -> Option<&'static str> {
+        lookup(class::ALL, self.class())
+    }
+
+    /// Returns the name of the subclass of this value, e.g. `"DXE_CORE"`.
+    pub fn subclass_name(&self) -> Option<&'static str> {
+        subclass_name(*self)
+    }
+
+    /// Returns an object that displays this value as a code of the given type.
+    pub fn display_for(&self, code_type: StatusCodeType) -> ValueDisplay {
+        ValueDisplay { value: *self, code_type }
+    }
+}
+
+/// Displays a known value symbolically as `SUBCLASS|OPERATION` (e.g. `DXE_CORE|ENTRY_POINT`) and an unknown value as
+/// `class=0x03 sub=0x10 op=0x0005`. Progress codes take precedence over error codes with the same value.
+impl fmt::Display for StatusCodeValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_value(*self, &[&PROGRESS, &ERROR], f)
+    }
+}
+
+/// Displays a [`StatusCodeValue`] as a code of a given type. Returned by [`StatusCodeValue::display_for`].
+#[derive(Debug, Clone, Copy)]
+pub struct ValueDisplay {
+    value: StatusCodeValue,
+    code_type: StatusCodeType,
+}
+
+impl fmt::Display for ValueDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_value(self.value, tables_for(self.code_type), f)
+    }
+}
+
+/// Displays a reported status code as `[caller] TYPE VALUE`, e.g. `[DxeCore] PROGRESS DXE_CORE|ENTRY_POINT`, for
+/// status code handlers that log reported codes.
+///
+/// The caller is named with the [`CallerIdMap`] given to [`ReportDisplay::with_caller_ids`], if any, and is displayed
+/// as a GUID otherwise. The caller is omitted if no caller ID was reported.
+#[derive(Debug, Clone, Copy)]
+pub struct ReportDisplay<'a> {
+    code_type: StatusCodeType,
+    value: StatusCodeValue,
+    caller_id: Option<efi::Guid>,
+    caller_ids: Option<&'a CallerIdMap>,
+}
+
+impl<'a> ReportDisplay<'a> {
+    /// Creates an object that displays a code reported with the given type, value, and caller ID.
+    pub fn new(code_type: StatusCodeType, value: StatusCodeValue, caller_id: Option<efi::Guid>) -> Self {
+        Self { code_type, value, caller_id, caller_ids: None }
+    }
+
+    /// Names the caller with `caller_ids`, or displays it as a GUID if `None`.
+    pub fn with_caller_ids(self, caller_ids: Option<&'a CallerIdMap>) -> Self {
+        Self { caller_ids, ..self }
+    }
+}
+
+impl fmt::Display for ReportDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(caller_id) = &self.caller_id {
+            match self.caller_ids.and_then(|caller_ids| caller_ids.resolve(caller_id)) {
+                Some(name) => write!(f, "[{name}] ")?,
+                None => {
+                    f.write_str("[")?;
+                    write_guid(f, caller_id)?;
+                    f.write_str("] ")?;
+                }
+            }
+        }
+        write!(f, "{} {}", self.code_type, self.value.display_for(self.code_type))
+    }
+}
+
+impl StatusCodeType {
+    /// Returns the name of the code type, e.g. `"EFI_ERROR_CODE"`.
+    pub fn name(&self) -> Option<&'static str> {
+        match self.code_type() {
+            Self::PROGRESS_CODE => Some("EFI_PROGRESS_CODE"),
+            Self::ERROR_CODE => Some("EFI_ERROR_CODE"),
+            Self::DEBUG_CODE => Some("EFI_DEBUG_CODE"),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the severity of an error code, e.g. `"EFI_ERROR_MAJOR"`.
+    pub fn severity_name(&self) -> Option<&'static str> {
+        if !self.is_error() {
+            return None;
+        }
+        lookup(SEVERITIES, self.severity())
+    }
+}
+
+/// Displays the code type as `PROGRESS`, `DEBUG`, or `ERROR`, followed for errors by the severity (e.g.
+/// `ERROR|MAJOR`). Unknown types and severities are displayed in hex.
+impl fmt::Display for StatusCodeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.code_type() {
+            Self::PROGRESS_CODE => write!(f, "PROGRESS"),
+            Self::DEBUG_CODE => write!(f, "DEBUG"),
+            Self::ERROR_CODE => match self.severity_name() {
+                Some(name) => write!(f, "ERROR|{}", name.trim_start_matches("EFI_ERROR_")),
+                None => write!(f, "ERROR|severity={:#010x}", self.severity()),
+            },
+            _ => write!(f, "type={:#010x}", self.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{error, progress, StatusCodeType, StatusCodeValue, ERROR, PROGRESS};
+    use crate::status_code::{class, subclass};
+
+    #[test]
+    fn every_constant_should_round_trip_through_name() {
+        for (name, value) in PROGRESS.values.iter().flat_map(|table| table.iter()) {
+            assert_eq!(value.name_for(StatusCodeType::progress()), Some(*name));
+            assert_eq!(value.name(), Some(*name));
+        }
+        let error = StatusCodeType::error(StatusCodeType::EFI_ERROR_MAJOR);
+        for (name, value) in ERROR.values.iter().flat_map(|table| table.iter()) {
+            assert_eq!(value.name_for(error), Some(*name));
+            // progress codes only take precedence when they share the value.
+            if super::value_name(*value, &PROGRESS).is_none() {
+                assert_eq!(value.name(), Some(*name));
+            }
+        }
+        assert_eq!(StatusCodeValue(0x03040FFF).name(), None);
+        assert_eq!(progress::software::EFI_SW_DXE_CORE_PC_ENTRY_POINT.name_for(StatusCodeType::debug()), None);
+    }
+
+    #[test]
+    fn display_should_render_known_values_symbolically() {
+        let render = |value: StatusCodeValue| std::format!("{value}");
+        assert_eq!(render(progress::software::EFI_SW_DXE_CORE_PC_ENTRY_POINT), "DXE_CORE|ENTRY_POINT");
+        assert_eq!(render(progress::software::EFI_SW_PEI_CORE_PC_HANDOFF_TO_NEXT), "PEI_CORE|HANDOFF_TO_NEXT");
+        assert_eq!(render(progress::io_bus::EFI_IOB_PCI_BUS_ENUM), "PCI|BUS_ENUM");
+        assert_eq!(render(progress::io_bus::EFI_IOB_ATA_BUS_SMART_ENABLE), "ATA_ATAPI|BUS_SMART_ENABLE");
+        assert_eq!(render(error::software::EFI_SW_EC_X64_PAGE_FAULT), "X64_EXCEPTION|PAGE_FAULT");
+        // without a type, progress codes take precedence over error codes with the same value.
+        assert_eq!(render(error::computing_unit::EFI_CU_HP_EC_THERMAL), "HOST_PROCESSOR|BSP_RESELECT");
+
+        // shared operations are named with the subclass they were reported for.
+        let value = StatusCodeValue::compose(
+            class::SOFTWARE,
+            subclass::software::SMM_DRIVER,
+            progress::software::EFI_SW_PC_LOAD,
+        );
+        assert_eq!(render(value), "SMM_DRIVER|LOAD");
+    }
+
+    #[test]
+    fn display_for_should_use_type_specific_tables() {
+        let value = error::software::EFI_SW_DXE_CORE_EC_NO_ARCH;
+        let error = StatusCodeType::error(StatusCodeType::EFI_ERROR_UNRECOVERED);
+        assert_eq!(std::format!("{}", value.display_for(error)), "DXE_CORE|NO_ARCH");
+        assert_eq!(std::format!("{}", value.display_for(StatusCodeType::progress())), "DXE_CORE|ENTRY_POINT");
+        assert_eq!(std::format!("{}", value.display_for(StatusCodeType::debug())), "class=0x03 sub=0x04 op=0x1000");
+
+        let value = StatusCodeValue::compose(
+            class::PERIPHERAL,
+            subclass::peripheral::KEYBOARD,
+            error::peripheral::EFI_P_EC_INPUT_ERROR,
+        );
+        assert_eq!(std::format!("{}", value.display_for(error)), "KEYBOARD|INPUT_ERROR");
+    }
+
+    #[test]
+    fn display_should_render_unknown_values_as_hex() {
+        assert_eq!(std::format!("{}", StatusCodeValue(0x037F0005)), "class=0x03 sub=0x7f op=0x0005");
+        assert_eq!(std::format!("{}", StatusCodeValue(0x03101FFF)), "class=0x03 sub=0x10 op=0x1fff");
+        assert_eq!(std::format!("{}", StatusCodeValue(0x7F000000)), "class=0x7f sub=0x00 op=0x0000");
+        assert_eq!(std::format!("{}", StatusCodeValue(0x03048000)), "class=0x03 sub=0x04 op=0x8000");
+        assert_eq!(StatusCodeValue(0x03040000).class_name(), Some("SOFTWARE"));
+        assert_eq!(StatusCodeValue(0x03040000).subclass_name(), Some("DXE_CORE"));
+        assert_eq!(StatusCodeValue(0x04000000).class_name(), None);
+    }
+
+    #[test]
+    fn type_should_display_severity() {
+        assert_eq!(std::format!("{}", StatusCodeType::progress()), "PROGRESS");
+        assert_eq!(std::format!("{}", StatusCodeType::debug()), "DEBUG");
+        let major = StatusCodeType::error(StatusCodeType::EFI_ERROR_MAJOR);
+        assert_eq!(std::format!("{major}"), "ERROR|MAJOR");
+        assert_eq!(major.name(), Some("EFI_ERROR_CODE"));
+        assert_eq!(major.severity_name(), Some("EFI_ERROR_MAJOR"));
+        assert_eq!(std::format!("{}", StatusCodeType::error(0x10000000)), "ERROR|severity=0x10000000");
+        assert_eq!(std::format!("{}", StatusCodeType(0x05)), "type=0x00000005");
+        assert_eq!(StatusCodeType(0x05).name(), None);
+        assert_eq!(StatusCodeType::progress().severity_name(), None);
+    }
+}
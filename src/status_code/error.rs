@@ -0,0 +1,344 @@
+//! Error Codes
+//!
+//! Error code values defined by the specification, in a submodule per class. Operations 0x0000-0x0FFF are shared by
+//! every subclass of a class and are defined in the `OPERATIONS` table of the class as bare operations to be combined
+//! with a subclass; subclass specific operations, and the exception codes of the processor exception subclasses, are
+//! defined in the `ALL` table of the class as complete [`StatusCodeValue`](super::StatusCodeValue)s.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V3_Status_Codes.html>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+/// Error codes of the computing unit class.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section III-6.7.1
+pub mod computing_unit {
+    use crate::status_code::{
+        class::COMPUTING_UNIT,
+        subclass::computing_unit::{CACHE, CHIPSET, FIRMWARE_PROCESSOR, HOST_PROCESSOR, MEMORY},
+        subclass_specific, StatusCodeValue,
+    };
+
+    status_code_table! {
+        /// Error operations shared by every computing unit subclass.
+        OPERATIONS: u16;
+        EFI_CU_EC_NON_SPECIFIC = 0x0000;
+        EFI_CU_EC_DISABLED = 0x0001;
+        EFI_CU_EC_NOT_SUPPORTED = 0x0002;
+        EFI_CU_EC_NOT_DETECTED = 0x0003;
+        EFI_CU_EC_NOT_CONFIGURED = 0x0004;
+    }
+
+    status_code_table! {
+        /// Every subclass specific error code of the computing unit class defined by the specification.
+        ALL: StatusCodeValue;
+        EFI_CU_HP_EC_INVALID_TYPE = subclass_specific(COMPUTING_UNIT, HOST_PROCESSOR, 0x00);
+        EFI_CU_HP_EC_INVALID_SPEED = subclass_specific(COMPUTING_UNIT, HOST_PROCESSOR, 0x01);
+        EFI_CU_HP_EC_MISMATCH = subclass_specific(COMPUTING_UNIT, HOST_PROCESSOR, 0x02);
+        EFI_CU_HP_EC_TIMER_EXPIRED = subclass_specific(COMPUTING_UNIT, HOST_PROCESSOR, 0x03);
+        EFI_CU_HP_EC_SELF_TEST = subclass_specific(COMPUTING_UNIT, HOST_PROCESSOR, 0x04);
+        EFI_CU_HP_EC_INTERNAL = subclass_specific(COMPUTING_UNIT, HOST_PROCESSOR, 0x05);
+        EFI_CU_HP_EC_THERMAL = subclass_specific(COMPUTING_UNIT, HOST_PROCESSOR, 0x06);
+        EFI_CU_HP_EC_LOW_VOLTAGE = subclass_specific(COMPUTING_UNIT, HOST_PROCESSOR, 0x07);
+        EFI_CU_HP_EC_HIGH_VOLTAGE = subclass_specific(COMPUTING_UNIT, HOST_PROCESSOR, 0x08);
+        EFI_CU_HP_EC_CACHE = subclass_specific(COMPUTING_UNIT, HOST_PROCESSOR, 0x09);
+        EFI_CU_HP_EC_MICROCODE_UPDATE = subclass_specific(COMPUTING_UNIT, HOST_PROCESSOR, 0x0A);
+        EFI_CU_HP_EC_CORRECTABLE = subclass_specific(COMPUTING_UNIT, HOST_PROCESSOR, 0x0B);
+        EFI_CU_HP_EC_UNCORRECTABLE = subclass_specific(COMPUTING_UNIT, HOST_PROCESSOR, 0x0C);
+        EFI_CU_HP_EC_NO_MICROCODE_UPDATE = subclass_specific(COMPUTING_UNIT, HOST_PROCESSOR, 0x0D);
+
+        EFI_CU_FP_EC_HARD_FAIL = subclass_specific(COMPUTING_UNIT, FIRMWARE_PROCESSOR, 0x00);
+        EFI_CU_FP_EC_SOFT_FAIL = subclass_specific(COMPUTING_UNIT, FIRMWARE_PROCESSOR, 0x01);
+        EFI_CU_FP_EC_COMM_ERROR = subclass_specific(COMPUTING_UNIT, FIRMWARE_PROCESSOR, 0x02);
+
+        EFI_CU_CACHE_EC_INVALID_TYPE = subclass_specific(COMPUTING_UNIT, CACHE, 0x00);
+        EFI_CU_CACHE_EC_INVALID_SPEED = subclass_specific(COMPUTING_UNIT, CACHE, 0x01);
+        EFI_CU_CACHE_EC_INVALID_SIZE = subclass_specific(COMPUTING_UNIT, CACHE, 0x02);
+        EFI_CU_CACHE_EC_MISMATCH = subclass_specific(COMPUTING_UNIT, CACHE, 0x03);
+
+        EFI_CU_MEMORY_EC_INVALID_TYPE = subclass_specific(COMPUTING_UNIT, MEMORY, 0x00);
+        EFI_CU_MEMORY_EC_INVALID_SPEED = subclass_specific(COMPUTING_UNIT, MEMORY, 0x01);
+        EFI_CU_MEMORY_EC_CORRECTABLE = subclass_specific(COMPUTING_UNIT, MEMORY, 0x02);
+        EFI_CU_MEMORY_EC_UNCORRECTABLE = subclass_specific(COMPUTING_UNIT, MEMORY, 0x03);
+        EFI_CU_MEMORY_EC_SPD_FAIL = subclass_specific(COMPUTING_UNIT, MEMORY, 0x04);
+        EFI_CU_MEMORY_EC_INVALID_SIZE = subclass_specific(COMPUTING_UNIT, MEMORY, 0x05);
+        EFI_CU_MEMORY_EC_MISMATCH = subclass_specific(COMPUTING_UNIT, MEMORY, 0x06);
+        EFI_CU_MEMORY_EC_S3_RESUME_FAIL = subclass_specific(COMPUTING_UNIT, MEMORY, 0x07);
+        EFI_CU_MEMORY_EC_UPDATE_FAIL = subclass_specific(COMPUTING_UNIT, MEMORY, 0x08);
+        EFI_CU_MEMORY_EC_NONE_DETECTED = subclass_specific(COMPUTING_UNIT, MEMORY, 0x09);
+        EFI_CU_MEMORY_EC_NONE_USEFUL = subclass_specific(COMPUTING_UNIT, MEMORY, 0x0A);
+
+        EFI_CHIPSET_EC_BAD_BATTERY = subclass_specific(COMPUTING_UNIT, CHIPSET, 0x00);
+        EFI_CHIPSET_EC_DXE_NB_ERROR = subclass_specific(COMPUTING_UNIT, CHIPSET, 0x01);
+        EFI_CHIPSET_EC_DXE_SB_BAD_COMP = subclass_specific(COMPUTING_UNIT, CHIPSET, 0x02);
+    }
+}
+
+/// Error codes of the peripheral class.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section III-6.7.2
+pub mod peripheral {
+    use crate::status_code::{
+        class::PERIPHERAL,
+        subclass::peripheral::{KEYBOARD, MOUSE},
+        subclass_specific, StatusCodeValue,
+    };
+
+    status_code_table! {
+        /// Error operations shared by every peripheral subclass.
+        OPERATIONS: u16;
+        EFI_P_EC_NON_SPECIFIC = 0x0000;
+        EFI_P_EC_DISABLED = 0x0001;
+        EFI_P_EC_NOT_SUPPORTED = 0x0002;
+        EFI_P_EC_NOT_DETECTED = 0x0003;
+        EFI_P_EC_NOT_CONFIGURED = 0x0004;
+        EFI_P_EC_INTERFACE_ERROR = 0x0005;
+        EFI_P_EC_CONTROLLER_ERROR = 0x0006;
+        EFI_P_EC_INPUT_ERROR = 0x0007;
+        EFI_P_EC_OUTPUT_ERROR = 0x0008;
+        EFI_P_EC_RESOURCE_CONFLICT = 0x0009;
+    }
+
+    status_code_table! {
+        /// Every subclass specific error code of the peripheral class defined by the specification.
+        ALL: StatusCodeValue;
+        EFI_P_KEYBOARD_EC_LOCKED = subclass_specific(PERIPHERAL, KEYBOARD, 0x00);
+        EFI_P_KEYBOARD_EC_STUCK_KEY = subclass_specific(PERIPHERAL, KEYBOARD, 0x01);
+        EFI_P_KEYBOARD_EC_BUFFER_FULL = subclass_specific(PERIPHERAL, KEYBOARD, 0x02);
+
+        EFI_P_MOUSE_EC_LOCKED = subclass_specific(PERIPHERAL, MOUSE, 0x00);
+    }
+}
+
+/// Error codes of the I/O bus class.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section III-6.7.3
+pub mod io_bus {
+    use crate::status_code::{
+        class::IO_BUS,
+        subclass::io_bus::{ATA_ATAPI, PCI},
+        subclass_specific, StatusCodeValue,
+    };
+
+    status_code_table! {
+        /// Error operations shared by every I/O bus subclass.
+        OPERATIONS: u16;
+        EFI_IOB_EC_NON_SPECIFIC = 0x0000;
+        EFI_IOB_EC_DISABLED = 0x0001;
+        EFI_IOB_EC_NOT_SUPPORTED = 0x0002;
+        EFI_IOB_EC_NOT_DETECTED = 0x0003;
+        EFI_IOB_EC_NOT_CONFIGURED = 0x0004;
+        EFI_IOB_EC_INTERFACE_ERROR = 0x0005;
+        EFI_IOB_EC_CONTROLLER_ERROR = 0x0006;
+        EFI_IOB_EC_READ_ERROR = 0x0007;
+        EFI_IOB_EC_WRITE_ERROR = 0x0008;
+        EFI_IOB_EC_RESOURCE_CONFLICT = 0x0009;
+    }
+
+    status_code_table! {
+        /// Every subclass specific error code of the I/O bus class defined by the specification.
+        ALL: StatusCodeValue;
+        EFI_IOB_PCI_EC_PERR = subclass_specific(IO_BUS, PCI, 0x00);
+        EFI_IOB_PCI_EC_SERR = subclass_specific(IO_BUS, PCI, 0x01);
+
+        EFI_IOB_ATA_BUS_SMART_NOTSUPPORTED = subclass_specific(IO_BUS, ATA_ATAPI, 0x00);
+        EFI_IOB_ATA_BUS_SMART_DISABLED = subclass_specific(IO_BUS, ATA_ATAPI, 0x01);
+    }
+}
+
+/// Error codes of the software class.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section III-6.7.4
+pub mod software {
+    use crate::status_code::{
+        class::SOFTWARE,
+        subclass::software::{
+            ARM_EXCEPTION, DXE_BS_DRIVER, DXE_CORE, EBC_EXCEPTION, IA32_EXCEPTION, IPF_EXCEPTION, PEI_CORE, PEI_MODULE,
+            PEI_SERVICE, X64_EXCEPTION,
+        },
+        subclass_specific, StatusCodeValue,
+    };
+
+    status_code_table! {
+        /// Error operations shared by every software subclass.
+        OPERATIONS: u16;
+        EFI_SW_EC_NON_SPECIFIC = 0x0000;
+        EFI_SW_EC_LOAD_ERROR = 0x0001;
+        EFI_SW_EC_INVALID_PARAMETER = 0x0002;
+        EFI_SW_EC_UNSUPPORTED = 0x0003;
+        EFI_SW_EC_INVALID_BUFFER = 0x0004;
+        EFI_SW_EC_OUT_OF_RESOURCES = 0x0005;
+        EFI_SW_EC_ABORTED = 0x0006;
+        EFI_SW_EC_ILLEGAL_SOFTWARE_STATE = 0x0007;
+        EFI_SW_EC_ILLEGAL_HARDWARE_STATE = 0x0008;
+        EFI_SW_EC_START_ERROR = 0x0009;
+        EFI_SW_EC_BAD_DATE_TIME = 0x000A;
+        EFI_SW_EC_CFG_INVALID = 0x000B;
+        EFI_SW_EC_CFG_CLR_REQUEST = 0x000C;
+        EFI_SW_EC_CFG_DEFAULT = 0x000D;
+        EFI_SW_EC_PWD_INVALID = 0x000E;
+        EFI_SW_EC_PWD_CLR_REQUEST = 0x000F;
+        EFI_SW_EC_PWD_CLEARED = 0x0010;
+        EFI_SW_EC_EVENT_LOG_FULL = 0x0011;
+        EFI_SW_EC_WRITE_PROTECTED = 0x0012;
+        EFI_SW_EC_FV_CORRUPTED = 0x0013;
+        EFI_SW_EC_INCONSISTENT_MEMORY_MAP = 0x0014;
+    }
+
+    status_code_table! {
+        /// Every subclass specific error code of the software class defined by the specification.
+        ALL: StatusCodeValue;
+        EFI_SW_PEI_CORE_EC_DXE_CORRUPT = subclass_specific(SOFTWARE, PEI_CORE, 0x00);
+        EFI_SW_PEI_CORE_EC_DXEIPL_NOT_FOUND = subclass_specific(SOFTWARE, PEI_CORE, 0x01);
+        EFI_SW_PEI_CORE_EC_MEMORY_NOT_INSTALLED = subclass_specific(SOFTWARE, PEI_CORE, 0x02);
+
+        EFI_SW_PEI_EC_NO_RECOVERY_CAPSULE = subclass_specific(SOFTWARE, PEI_MODULE, 0x00);
+        EFI_SW_PEI_EC_INVALID_CAPSULE_DESCRIPTOR = subclass_specific(SOFTWARE, PEI_MODULE, 0x01);
+        EFI_SW_PEI_EC_S3_RESUME_PPI_NOT_FOUND = subclass_specific(SOFTWARE, PEI_MODULE, 0x02);
+        EFI_SW_PEI_EC_S3_BOOT_SCRIPT_ERROR = subclass_specific(SOFTWARE, PEI_MODULE, 0x03);
+        EFI_SW_PEI_EC_S3_OS_WAKE_ERROR = subclass_specific(SOFTWARE, PEI_MODULE, 0x04);
+        EFI_SW_PEI_EC_S3_RESUME_FAILED = subclass_specific(SOFTWARE, PEI_MODULE, 0x05);
+        EFI_SW_PEI_EC_RECOVERY_PPI_NOT_FOUND = subclass_specific(SOFTWARE, PEI_MODULE, 0x06);
+        EFI_SW_PEI_EC_RECOVERY_FAILED = subclass_specific(SOFTWARE, PEI_MODULE, 0x07);
+        EFI_SW_PEI_EC_S3_RESUME_ERROR = subclass_specific(SOFTWARE, PEI_MODULE, 0x08);
+        EFI_SW_PEI_EC_INVALID_CAPSULE = subclass_specific(SOFTWARE, PEI_MODULE, 0x09);
+
+        EFI_SW_DXE_CORE_EC_NO_ARCH = subclass_specific(SOFTWARE, DXE_CORE, 0x00);
+
+        EFI_SW_DXE_BS_EC_LEGACY_OPROM_NO_SPACE = subclass_specific(SOFTWARE, DXE_BS_DRIVER, 0x00);
+        EFI_SW_DXE_BS_EC_INVALID_PASSWORD = subclass_specific(SOFTWARE, DXE_BS_DRIVER, 0x01);
+        EFI_SW_DXE_BS_EC_BOOT_OPTION_LOAD_ERROR = subclass_specific(SOFTWARE, DXE_BS_DRIVER, 0x02);
+        EFI_SW_DXE_BS_EC_BOOT_OPTION_FAILED = subclass_specific(SOFTWARE, DXE_BS_DRIVER, 0x03);
+        EFI_SW_DXE_BS_EC_INVALID_IDE_PASSWORD = subclass_specific(SOFTWARE, DXE_BS_DRIVER, 0x04);
+
+        EFI_SW_PS_EC_RESET_NOT_AVAILABLE = subclass_specific(SOFTWARE, PEI_SERVICE, 0x00);
+        EFI_SW_PS_EC_MEMORY_INSTALLED_TWICE = subclass_specific(SOFTWARE, PEI_SERVICE, 0x01);
+
+        EFI_SW_EC_EBC_UNDEFINED = StatusCodeValue::compose(SOFTWARE, EBC_EXCEPTION, 0x0000);
+        EFI_SW_EC_EBC_DIVIDE_ERROR = StatusCodeValue::compose(SOFTWARE, EBC_EXCEPTION, 0x0001);
+        EFI_SW_EC_EBC_DEBUG = StatusCodeValue::compose(SOFTWARE, EBC_EXCEPTION, 0x0002);
+        EFI_SW_EC_EBC_BREAKPOINT = StatusCodeValue::compose(SOFTWARE, EBC_EXCEPTION, 0x0003);
+        EFI_SW_EC_EBC_OVERFLOW = StatusCodeValue::compose(SOFTWARE, EBC_EXCEPTION, 0x0004);
+        EFI_SW_EC_EBC_INVALID_OPCODE = StatusCodeValue::compose(SOFTWARE, EBC_EXCEPTION, 0x0005);
+        EFI_SW_EC_EBC_STACK_FAULT = StatusCodeValue::compose(SOFTWARE, EBC_EXCEPTION, 0x0006);
+        EFI_SW_EC_EBC_ALIGNMENT_CHECK = StatusCodeValue::compose(SOFTWARE, EBC_EXCEPTION, 0x0007);
+        EFI_SW_EC_EBC_INSTRUCTION_ENCODING = StatusCodeValue::compose(SOFTWARE, EBC_EXCEPTION, 0x0008);
+        EFI_SW_EC_EBC_BAD_BREAK = StatusCodeValue::compose(SOFTWARE, EBC_EXCEPTION, 0x0009);
+        EFI_SW_EC_EBC_STEP = StatusCodeValue::compose(SOFTWARE, EBC_EXCEPTION, 0x000A);
+
+        EFI_SW_EC_IA32_DIVIDE_ERROR = StatusCodeValue::compose(SOFTWARE, IA32_EXCEPTION, 0x0000);
+        EFI_SW_EC_IA32_DEBUG = StatusCodeValue::compose(SOFTWARE, IA32_EXCEPTION, 0x0001);
+        EFI_SW_EC_IA32_NMI = StatusCodeValue::compose(SOFTWARE, IA32_EXCEPTION, 0x0002);
+        EFI_SW_EC_IA32_BREAKPOINT = StatusCodeValue::compose(SOFTWARE, IA32_EXCEPTION, 0x0003);
+        EFI_SW_EC_IA32_OVERFLOW = StatusCodeValue::compose(SOFTWARE, IA32_EXCEPTION, 0x0004);
+        EFI_SW_EC_IA32_BOUND = StatusCodeValue::compose(SOFTWARE, IA32_EXCEPTION, 0x0005);
+        EFI_SW_EC_IA32_INVALID_OPCODE = StatusCodeValue::compose(SOFTWARE, IA32_EXCEPTION, 0x0006);
+        EFI_SW_EC_IA32_DOUBLE_FAULT = StatusCodeValue::compose(SOFTWARE, IA32_EXCEPTION, 0x0008);
+        EFI_SW_EC_IA32_INVALID_TSS = StatusCodeValue::compose(SOFTWARE, IA32_EXCEPTION, 0x000A);
+        EFI_SW_EC_IA32_SEG_NOT_PRESENT = StatusCodeValue::compose(SOFTWARE, IA32_EXCEPTION, 0x000B);
+        EFI_SW_EC_IA32_STACK_FAULT = StatusCodeValue::compose(SOFTWARE, IA32_EXCEPTION, 0x000C);
+        EFI_SW_EC_IA32_GP_FAULT = StatusCodeValue::compose(SOFTWARE, IA32_EXCEPTION, 0x000D);
+        EFI_SW_EC_IA32_PAGE_FAULT = StatusCodeValue::compose(SOFTWARE, IA32_EXCEPTION, 0x000E);
+        EFI_SW_EC_IA32_FP_ERROR = StatusCodeValue::compose(SOFTWARE, IA32_EXCEPTION, 0x0010);
+        EFI_SW_EC_IA32_ALIGNMENT_CHECK = StatusCodeValue::compose(SOFTWARE, IA32_EXCEPTION, 0x0011);
+        EFI_SW_EC_IA32_MACHINE_CHECK = StatusCodeValue::compose(SOFTWARE, IA32_EXCEPTION, 0x0012);
+        EFI_SW_EC_IA32_SIMD = StatusCodeValue::compose(SOFTWARE, IA32_EXCEPTION, 0x0013);
+
+        EFI_SW_EC_IPF_ALT_DTLB = StatusCodeValue::compose(SOFTWARE, IPF_EXCEPTION, 0x0C00);
+        EFI_SW_EC_IPF_DNESTED_TLB = StatusCodeValue::compose(SOFTWARE, IPF_EXCEPTION, 0x1400);
+        EFI_SW_EC_IPF_BREAKPOINT = StatusCodeValue::compose(SOFTWARE, IPF_EXCEPTION, 0x2C00);
+        EFI_SW_EC_IPF_EXTERNAL_INTERRUPT = StatusCodeValue::compose(SOFTWARE, IPF_EXCEPTION, 0x3000);
+        EFI_SW_EC_IPF_GEN_EXCEPT = StatusCodeValue::compose(SOFTWARE, IPF_EXCEPTION, 0x5400);
+        EFI_SW_EC_IPF_NAT_CONSUMPTION = StatusCodeValue::compose(SOFTWARE, IPF_EXCEPTION, 0x5600);
+        EFI_SW_EC_IPF_DEBUG_EXCEPT = StatusCodeValue::compose(SOFTWARE, IPF_EXCEPTION, 0x5900);
+        EFI_SW_EC_IPF_UNALIGNED_ACCESS = StatusCodeValue::compose(SOFTWARE, IPF_EXCEPTION, 0x5A00);
+        EFI_SW_EC_IPF_FP_FAULT = StatusCodeValue::compose(SOFTWARE, IPF_EXCEPTION, 0x5C00);
+        EFI_SW_EC_IPF_FP_TRAP = StatusCodeValue::compose(SOFTWARE, IPF_EXCEPTION, 0x5D00);
+        EFI_SW_EC_IPF_TAKEN_BRANCH = StatusCodeValue::compose(SOFTWARE, IPF_EXCEPTION, 0x5F00);
+        EFI_SW_EC_IPF_SINGLE_STEP = StatusCodeValue::compose(SOFTWARE, IPF_EXCEPTION, 0x6000);
+
+        EFI_SW_EC_X64_DIVIDE_ERROR = StatusCodeValue::compose(SOFTWARE, X64_EXCEPTION, 0x0000);
+        EFI_SW_EC_X64_DEBUG = StatusCodeValue::compose(SOFTWARE, X64_EXCEPTION, 0x0001);
+        EFI_SW_EC_X64_NMI = StatusCodeValue::compose(SOFTWARE, X64_EXCEPTION, 0x0002);
+        EFI_SW_EC_X64_BREAKPOINT = StatusCodeValue::compose(SOFTWARE, X64_EXCEPTION, 0x0003);
+        EFI_SW_EC_X64_OVERFLOW = StatusCodeValue::compose(SOFTWARE, X64_EXCEPTION, 0x0004);
+        EFI_SW_EC_X64_BOUND = StatusCodeValue::compose(SOFTWARE, X64_EXCEPTION, 0x0005);
+        EFI_SW_EC_X64_INVALID_OPCODE = StatusCodeValue::compose(SOFTWARE, X64_EXCEPTION, 0x0006);
+        EFI_SW_EC_X64_DOUBLE_FAULT = StatusCodeValue::compose(SOFTWARE, X64_EXCEPTION, 0x0008);
+        EFI_SW_EC_X64_INVALID_TSS = StatusCodeValue::compose(SOFTWARE, X64_EXCEPTION, 0x000A);
+        EFI_SW_EC_X64_SEG_NOT_PRESENT = StatusCodeValue::compose(SOFTWARE, X64_EXCEPTION, 0x000B);
+        EFI_SW_EC_X64_STACK_FAULT = StatusCodeValue::compose(SOFTWARE, X64_EXCEPTION, 0x000C);
+        EFI_SW_EC_X64_GP_FAULT = StatusCodeValue::compose(SOFTWARE, X64_EXCEPTION, 0x000D);
+        EFI_SW_EC_X64_PAGE_FAULT = StatusCodeValue::compose(SOFTWARE, X64_EXCEPTION, 0x000E);
+        EFI_SW_EC_X64_FP_ERROR = StatusCodeValue::compose(SOFTWARE, X64_EXCEPTION, 0x0010);
+        EFI_SW_EC_X64_ALIGNMENT_CHECK = StatusCodeValue::compose(SOFTWARE, X64_EXCEPTION, 0x0011);
+        EFI_SW_EC_X64_MACHINE_CHECK = StatusCodeValue::compose(SOFTWARE, X64_EXCEPTION, 0x0012);
+        EFI_SW_EC_X64_SIMD = StatusCodeValue::compose(SOFTWARE, X64_EXCEPTION, 0x0013);
+
+        EFI_SW_EC_ARM_RESET = StatusCodeValue::compose(SOFTWARE, ARM_EXCEPTION, 0x0000);
+        EFI_SW_EC_ARM_UNDEFINED_INSTRUCTION = StatusCodeValue::compose(SOFTWARE, ARM_EXCEPTION, 0x0001);
+        EFI_SW_EC_ARM_SOFTWARE_INTERRUPT = StatusCodeValue::compose(SOFTWARE, ARM_EXCEPTION, 0x0002);
+        EFI_SW_EC_ARM_PREFETCH_ABORT = StatusCodeValue::compose(SOFTWARE, ARM_EXCEPTION, 0x0003);
+        EFI_SW_EC_ARM_DATA_ABORT = StatusCodeValue::compose(SOFTWARE, ARM_EXCEPTION, 0x0004);
+        EFI_SW_EC_ARM_RESERVED = StatusCodeValue::compose(SOFTWARE, ARM_EXCEPTION, 0x0005);
+        EFI_SW_EC_ARM_IRQ = StatusCodeValue::compose(SOFTWARE, ARM_EXCEPTION, 0x0006);
+        EFI_SW_EC_ARM_FIQ = StatusCodeValue::compose(SOFTWARE, ARM_EXCEPTION, 0x0007);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{computing_unit, io_bus, peripheral, software};
+
+    #[test]
+    fn error_codes_should_match_spec_encoding() {
+        assert_eq!(computing_unit::EFI_CU_HP_EC_SELF_TEST.0, 0x00011004);
+        assert_eq!(computing_unit::EFI_CU_HP_EC_THERMAL.0, 0x00011006);
+        assert_eq!(computing_unit::EFI_CU_HP_EC_NO_MICROCODE_UPDATE.0, 0x0001100D);
+        assert_eq!(computing_unit::EFI_CU_MEMORY_EC_UNCORRECTABLE.0, 0x00051003);
+        assert_eq!(computing_unit::EFI_CU_MEMORY_EC_NONE_USEFUL.0, 0x0005100A);
+        assert_eq!(peripheral::EFI_P_KEYBOARD_EC_STUCK_KEY.0, 0x01011001);
+        assert_eq!(io_bus::EFI_IOB_PCI_EC_SERR.0, 0x02011001);
+        assert_eq!(software::EFI_SW_PEI_CORE_EC_MEMORY_NOT_INSTALLED.0, 0x03021002);
+        assert_eq!(software::EFI_SW_PEI_EC_S3_RESUME_FAILED.0, 0x03031005);
+        assert_eq!(software::EFI_SW_DXE_CORE_EC_NO_ARCH.0, 0x03041000);
+        assert_eq!(software::EFI_SW_DXE_BS_EC_BOOT_OPTION_FAILED.0, 0x03051003);
+        assert_eq!(software::EFI_SW_PS_EC_MEMORY_INSTALLED_TWICE.0, 0x030F1001);
+        assert_eq!(software::EFI_SW_EC_IA32_PAGE_FAULT.0, 0x030D000E);
+        assert_eq!(software::EFI_SW_EC_X64_GP_FAULT.0, 0x0313000D);
+        assert_eq!(software::EFI_SW_EC_IPF_SINGLE_STEP.0, 0x030E6000);
+        assert_eq!(software::EFI_SW_EC_ARM_DATA_ABORT.0, 0x03140004);
+
+        assert_eq!(computing_unit::EFI_CU_EC_NOT_CONFIGURED, 0x0004);
+        assert_eq!(peripheral::EFI_P_EC_RESOURCE_CONFLICT, 0x0009);
+        assert_eq!(io_bus::EFI_IOB_EC_WRITE_ERROR, 0x0008);
+        assert_eq!(software::EFI_SW_EC_ILLEGAL_SOFTWARE_STATE, 0x0007);
+        assert_eq!(software::EFI_SW_EC_INCONSISTENT_MEMORY_MAP, 0x0014);
+    }
+
+    #[test]
+    fn tables_should_not_contain_duplicates() {
+        let tables = [computing_unit::ALL, peripheral::ALL, io_bus::ALL, software::ALL];
+        let values: BTreeSet<_> = tables.iter().flat_map(|table| table.iter()).map(|(_, value)| *value).collect();
+        assert_eq!(values.len(), tables.iter().map(|table| table.len()).sum());
+        for (class, table) in tables.iter().enumerate() {
+            assert!(table.iter().all(|(_, value)| value.class() as usize == class));
+        }
+        for table in [computing_unit::OPERATIONS, peripheral::OPERATIONS, io_bus::OPERATIONS, software::OPERATIONS] {
+            for (idx, (_, operation)) in table.iter().enumerate() {
+                assert_eq!(*operation as usize, idx);
+            }
+        }
+    }
+}
@@ -0,0 +1,49 @@
+//! Status Code Extended Data GUIDs
+//!
+//! GUIDs identifying the format of the extended data (EFI_STATUS_CODE_DATA) that may accompany a reported status
+//! code.
+//!
+//! The typed payloads defined for specific status codes, such as
+//! [`EfiDevicePathExtendedData`](super::data::EfiDevicePathExtendedData) and
+//! [`EfiMemoryExtendedErrorData`](super::data::EfiMemoryExtendedErrorData), do not have GUIDs of their own: they are
+//! all reported with [`SPECIFIC_DATA_GUID`] and interpreted according to the status code value they accompany.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V3_Code_Definitions.html#status-code-data-type-guids>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use r_efi::efi;
+
+pub use super::data::{DEBUG_DATA_TYPE_GUID, SPECIFIC_DATA_GUID, STRING_DATA_TYPE_GUID};
+
+/// Every extended data GUID defined by the specification, with its name.
+pub const ALL: &[(&str, efi::Guid)] = &[
+    ("STRING_DATA_TYPE_GUID", STRING_DATA_TYPE_GUID),
+    ("SPECIFIC_DATA_GUID", SPECIFIC_DATA_GUID),
+    ("DEBUG_DATA_TYPE_GUID", DEBUG_DATA_TYPE_GUID),
+];
+
+/// Returns the name of an extended data GUID defined by the specification.
+pub fn name(guid: &efi::Guid) -> Option<&'static str> {
+    ALL.iter().find(|(_, known)| known == guid).map(|(name, _)| *name)
+}
+
+#[cfg(test)]
+mod tests {
+    use r_efi::efi;
+
+    use super::{name, ALL, DEBUG_DATA_TYPE_GUID, SPECIFIC_DATA_GUID};
+
+    #[test]
+    fn name_should_resolve_known_guids() {
+        assert_eq!(ALL.len(), 3);
+        assert_eq!(name(&SPECIFIC_DATA_GUID), Some("SPECIFIC_DATA_GUID"));
+        assert_eq!(name(&DEBUG_DATA_TYPE_GUID), Some("DEBUG_DATA_TYPE_GUID"));
+        assert_eq!(name(&efi::Guid::from_bytes(&[0; 16])), None);
+    }
+}
@@ -0,0 +1,387 @@
+//! Progress Codes
+//!
+//! Progress code values defined by the specification, in a submodule per class. Operations 0x0000-0x0FFF are shared
+//! by every subclass of a class and are defined in the `OPERATIONS` table of the class as bare operations to be
+//! combined with a subclass; subclass specific operations are defined in the `ALL` table of the class as complete
+//! [`StatusCodeValue`](super::StatusCodeValue)s.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V3_Status_Codes.html>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+/// Progress codes of the computing unit class.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section III-6.7.1
+pub mod computing_unit {
+    use crate::status_code::{
+        class::COMPUTING_UNIT,
+        subclass::computing_unit::{CACHE, CHIPSET, HOST_PROCESSOR, MEMORY},
+        subclass_specific, StatusCodeValue,
+    };
+
+    status_code_table! {
+        /// Progress operations shared by every computing unit subclass.
+        OPERATIONS: u16;
+        EFI_CU_PC_INIT_BEGIN = 0x0000;
+        EFI_CU_PC_INIT_END = 0x0001;
+    }
+
+    status_code_table! {
+        /// Every subclass specific progress code of the computing unit class defined by the specification.
+        ALL: StatusCodeValue;
+        EFI_CU_HP_PC_POWER_ON_INIT = subclass_specific(COMPUTING_UNIT, HOST_PROCESSOR, 0x00);
+        EFI_CU_HP_PC_CACHE_INIT = subclass_specific(COMPUTING_UNIT, HOST_PROCESSOR, 0x01);
+        EFI_CU_HP_PC_RAM_INIT = subclass_specific(COMPUTING_UNIT, HOST_PROCESSOR, 0x02);
+        EFI_CU_HP_PC_MEMORY_CONTROLLER_INIT = subclass_specific(COMPUTING_UNIT, HOST_PROCESSOR, 0x03);
+        EFI_CU_HP_PC_IO_INIT = subclass_specific(COMPUTING_UNIT, HOST_PROCESSOR, 0x04);
+        EFI_CU_HP_PC_BSP_SELECT = subclass_specific(COMPUTING_UNIT, HOST_PROCESSOR, 0x05);
+        EFI_CU_HP_PC_BSP_RESELECT = subclass_specific(COMPUTING_UNIT, HOST_PROCESSOR, 0x06);
+        EFI_CU_HP_PC_AP_INIT = subclass_specific(COMPUTING_UNIT, HOST_PROCESSOR, 0x07);
+        EFI_CU_HP_PC_SMM_INIT = subclass_specific(COMPUTING_UNIT, HOST_PROCESSOR, 0x08);
+
+        EFI_CU_CACHE_PC_PRESENCE_DETECT = subclass_specific(COMPUTING_UNIT, CACHE, 0x00);
+        EFI_CU_CACHE_PC_CONFIGURATION = subclass_specific(COMPUTING_UNIT, CACHE, 0x01);
+
+        EFI_CU_MEMORY_PC_SPD_READ = subclass_specific(COMPUTING_UNIT, MEMORY, 0x00);
+        EFI_CU_MEMORY_PC_PRESENCE_DETECT = subclass_specific(COMPUTING_UNIT, MEMORY, 0x01);
+        EFI_CU_MEMORY_PC_TIMING = subclass_specific(COMPUTING_UNIT, MEMORY, 0x02);
+        EFI_CU_MEMORY_PC_CONFIGURING = subclass_specific(COMPUTING_UNIT, MEMORY, 0x03);
+        EFI_CU_MEMORY_PC_OPTIMIZING = subclass_specific(COMPUTING_UNIT, MEMORY, 0x04);
+        EFI_CU_MEMORY_PC_INIT = subclass_specific(COMPUTING_UNIT, MEMORY, 0x05);
+        EFI_CU_MEMORY_PC_TEST = subclass_specific(COMPUTING_UNIT, MEMORY, 0x06);
+
+        EFI_CHIPSET_PC_PEI_CAR_SB_INIT = subclass_specific(COMPUTING_UNIT, CHIPSET, 0x00);
+        EFI_CHIPSET_PC_PEI_CAR_NB_INIT = subclass_specific(COMPUTING_UNIT, CHIPSET, 0x01);
+        EFI_CHIPSET_PC_PEI_MEM_SB_INIT = subclass_specific(COMPUTING_UNIT, CHIPSET, 0x02);
+        EFI_CHIPSET_PC_PEI_MEM_NB_INIT = subclass_specific(COMPUTING_UNIT, CHIPSET, 0x03);
+        EFI_CHIPSET_PC_DXE_HB_INIT = subclass_specific(COMPUTING_UNIT, CHIPSET, 0x04);
+        EFI_CHIPSET_PC_DXE_NB_INIT = subclass_specific(COMPUTING_UNIT, CHIPSET, 0x05);
+        EFI_CHIPSET_PC_DXE_NB_SMM_INIT = subclass_specific(COMPUTING_UNIT, CHIPSET, 0x06);
+        EFI_CHIPSET_PC_DXE_SB_RT_INIT = subclass_specific(COMPUTING_UNIT, CHIPSET, 0x07);
+        EFI_CHIPSET_PC_DXE_SB_INIT = subclass_specific(COMPUTING_UNIT, CHIPSET, 0x08);
+        EFI_CHIPSET_PC_DXE_SB_SMM_INIT = subclass_specific(COMPUTING_UNIT, CHIPSET, 0x09);
+        EFI_CHIPSET_PC_DXE_SB_DEVICES_INIT = subclass_specific(COMPUTING_UNIT, CHIPSET, 0x0A);
+    }
+}
+
+/// Progress codes of the peripheral class.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section III-6.7.2
+pub mod peripheral {
+    use crate::status_code::{
+        class::PERIPHERAL,
+        subclass::peripheral::{KEYBOARD, MOUSE, SERIAL_PORT},
+        subclass_specific, StatusCodeValue,
+    };
+
+    status_code_table! {
+        /// Progress operations shared by every peripheral subclass.
+        OPERATIONS: u16;
+        EFI_P_PC_INIT = 0x0000;
+        EFI_P_PC_RESET = 0x0001;
+        EFI_P_PC_DISABLE = 0x0002;
+        EFI_P_PC_PRESENCE_DETECT = 0x0003;
+        EFI_P_PC_ENABLE = 0x0004;
+        EFI_P_PC_RECONFIG = 0x0005;
+        EFI_P_PC_DETECTED = 0x0006;
+        EFI_P_PC_REMOVED = 0x0007;
+    }
+
+    status_code_table! {
+        /// Every subclass specific progress code of the peripheral class defined by the specification.
+        ALL: StatusCodeValue;
+        EFI_P_KEYBOARD_PC_CLEAR_BUFFER = subclass_specific(PERIPHERAL, KEYBOARD, 0x00);
+        EFI_P_KEYBOARD_PC_SELF_TEST = subclass_specific(PERIPHERAL, KEYBOARD, 0x01);
+
+        EFI_P_MOUSE_PC_SELF_TEST = subclass_specific(PERIPHERAL, MOUSE, 0x00);
+
+        EFI_P_SERIAL_PORT_PC_CLEAR_BUFFER = subclass_specific(PERIPHERAL, SERIAL_PORT, 0x00);
+    }
+}
+
+/// Progress codes of the I/O bus class.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section III-6.7.3
+pub mod io_bus {
+    use crate::status_code::{
+        class::IO_BUS,
+        subclass::io_bus::{ATA_ATAPI, PCI},
+        subclass_specific, StatusCodeValue,
+    };
+
+    status_code_table! {
+        /// Progress operations shared by every I/O bus subclass.
+        OPERATIONS: u16;
+        EFI_IOB_PC_INIT = 0x0000;
+        EFI_IOB_PC_RESET = 0x0001;
+        EFI_IOB_PC_DISABLE = 0x0002;
+        EFI_IOB_PC_DETECT = 0x0003;
+        EFI_IOB_PC_ENABLE = 0x0004;
+        EFI_IOB_PC_RECONFIG = 0x0005;
+        EFI_IOB_PC_HOTPLUG = 0x0006;
+    }
+
+    status_code_table! {
+        /// Every subclass specific progress code of the I/O bus class defined by the specification.
+        ALL: StatusCodeValue;
+        EFI_IOB_PCI_BUS_ENUM = subclass_specific(IO_BUS, PCI, 0x00);
+        EFI_IOB_PCI_RES_ALLOC = subclass_specific(IO_BUS, PCI, 0x01);
+        EFI_IOB_PCI_HPC_INIT = subclass_specific(IO_BUS, PCI, 0x02);
+
+        EFI_IOB_ATA_BUS_SMART_ENABLE = subclass_specific(IO_BUS, ATA_ATAPI, 0x00);
+        EFI_IOB_ATA_BUS_SMART_DISABLE = subclass_specific(IO_BUS, ATA_ATAPI, 0x01);
+        EFI_IOB_ATA_BUS_SMART_OVERTHRESHOLD = subclass_specific(IO_BUS, ATA_ATAPI, 0x02);
+        EFI_IOB_ATA_BUS_SMART_UNDERTHRESHOLD = subclass_specific(IO_BUS, ATA_ATAPI, 0x03);
+    }
+}
+
+/// Progress codes of the software class.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section III-6.7.4
+pub mod software {
+    use crate::status_code::{
+        class::SOFTWARE,
+        subclass::software::{
+            AL, DXE_BS_DRIVER, DXE_CORE, EFI_BOOT_SERVICE, EFI_DXE_SERVICE, EFI_RUNTIME_SERVICE, PEI_CORE, PEI_MODULE,
+            PEI_SERVICE, RT, SEC,
+        },
+        subclass_specific, StatusCodeValue,
+    };
+
+    status_code_table! {
+        /// Progress operations shared by every software subclass.
+        OPERATIONS: u16;
+        EFI_SW_PC_INIT = 0x0000;
+        EFI_SW_PC_LOAD = 0x0001;
+        EFI_SW_PC_INIT_BEGIN = 0x0002;
+        EFI_SW_PC_INIT_END = 0x0003;
+        EFI_SW_PC_AUTHENTICATE_BEGIN = 0x0004;
+        EFI_SW_PC_AUTHENTICATE_END = 0x0005;
+        EFI_SW_PC_INPUT_WAIT = 0x0006;
+        EFI_SW_PC_USER_SETUP = 0x0007;
+    }
+
+    status_code_table! {
+        /// Every subclass specific progress code of the software class defined by the specification.
+        ALL: StatusCodeValue;
+        EFI_SW_SEC_PC_ENTRY_POINT = subclass_specific(SOFTWARE, SEC, 0x00);
+        EFI_SW_SEC_PC_HANDOFF_TO_NEXT = subclass_specific(SOFTWARE, SEC, 0x01);
+
+        EFI_SW_PEI_CORE_PC_ENTRY_POINT = subclass_specific(SOFTWARE, PEI_CORE, 0x00);
+        EFI_SW_PEI_CORE_PC_HANDOFF_TO_NEXT = subclass_specific(SOFTWARE, PEI_CORE, 0x01);
+        EFI_SW_PEI_CORE_PC_RETURN_TO_LAST = subclass_specific(SOFTWARE, PEI_CORE, 0x02);
+
+        EFI_SW_PEI_PC_RECOVERY_BEGIN = subclass_specific(SOFTWARE, PEI_MODULE, 0x00);
+        EFI_SW_PEI_PC_CAPSULE_LOAD = subclass_specific(SOFTWARE, PEI_MODULE, 0x01);
+        EFI_SW_PEI_PC_CAPSULE_START = subclass_specific(SOFTWARE, PEI_MODULE, 0x02);
+        EFI_SW_PEI_PC_RECOVERY_USER = subclass_specific(SOFTWARE, PEI_MODULE, 0x03);
+        EFI_SW_PEI_PC_RECOVERY_AUTO = subclass_specific(SOFTWARE, PEI_MODULE, 0x04);
+        EFI_SW_PEI_PC_S3_BOOT_SCRIPT = subclass_specific(SOFTWARE, PEI_MODULE, 0x05);
+        EFI_SW_PEI_PC_OS_WAKE = subclass_specific(SOFTWARE, PEI_MODULE, 0x06);
+        EFI_SW_PEI_PC_S3_STARTED = subclass_specific(SOFTWARE, PEI_MODULE, 0x07);
+
+        EFI_SW_DXE_CORE_PC_ENTRY_POINT = subclass_specific(SOFTWARE, DXE_CORE, 0x00);
+        EFI_SW_DXE_CORE_PC_HANDOFF_TO_NEXT = subclass_specific(SOFTWARE, DXE_CORE, 0x01);
+        EFI_SW_DXE_CORE_PC_RETURN_TO_LAST = subclass_specific(SOFTWARE, DXE_CORE, 0x02);
+        EFI_SW_DXE_CORE_PC_START_DRIVER = subclass_specific(SOFTWARE, DXE_CORE, 0x03);
+        EFI_SW_DXE_CORE_PC_ARCH_READY = subclass_specific(SOFTWARE, DXE_CORE, 0x04);
+
+        EFI_SW_DXE_BS_PC_LEGACY_OPROM_INIT = subclass_specific(SOFTWARE, DXE_BS_DRIVER, 0x00);
+        EFI_SW_DXE_BS_PC_READY_TO_BOOT_EVENT = subclass_specific(SOFTWARE, DXE_BS_DRIVER, 0x01);
+        EFI_SW_DXE_BS_PC_LEGACY_BOOT_EVENT = subclass_specific(SOFTWARE, DXE_BS_DRIVER, 0x02);
+        EFI_SW_DXE_BS_PC_EXIT_BOOT_SERVICES_EVENT = subclass_specific(SOFTWARE, DXE_BS_DRIVER, 0x03);
+        EFI_SW_DXE_BS_PC_VIRTUAL_ADDRESS_CHANGE_EVENT = subclass_specific(SOFTWARE, DXE_BS_DRIVER, 0x04);
+        EFI_SW_DXE_BS_PC_VARIABLE_SERVICES_INIT = subclass_specific(SOFTWARE, DXE_BS_DRIVER, 0x05);
+        EFI_SW_DXE_BS_PC_VARIABLE_RECLAIM = subclass_specific(SOFTWARE, DXE_BS_DRIVER, 0x06);
+        EFI_SW_DXE_BS_PC_ATTEMPT_BOOT_ORDER_EVENT = subclass_specific(SOFTWARE, DXE_BS_DRIVER, 0x07);
+        EFI_SW_DXE_BS_PC_CONFIG_RESET = subclass_specific(SOFTWARE, DXE_BS_DRIVER, 0x08);
+        EFI_SW_DXE_BS_PC_CSM_INIT = subclass_specific(SOFTWARE, DXE_BS_DRIVER, 0x09);
+        EFI_SW_DXE_BS_PC_BEGIN_CONNECTING_DRIVERS = subclass_specific(SOFTWARE, DXE_BS_DRIVER, 0x0A);
+        EFI_SW_DXE_BS_PC_VERIFYING_PASSWORD = subclass_specific(SOFTWARE, DXE_BS_DRIVER, 0x0B);
+
+        EFI_SW_RT_PC_ENTRY_POINT = subclass_specific(SOFTWARE, RT, 0x00);
+        EFI_SW_RT_PC_HANDOFF_TO_NEXT = subclass_specific(SOFTWARE, RT, 0x01);
+        EFI_SW_RT_PC_RETURN_TO_LAST = subclass_specific(SOFTWARE, RT, 0x02);
+
+        EFI_SW_AL_PC_ENTRY_POINT = subclass_specific(SOFTWARE, AL, 0x00);
+        EFI_SW_AL_PC_RETURN_TO_LAST = subclass_specific(SOFTWARE, AL, 0x01);
+
+        EFI_SW_PS_PC_INSTALL_PPI = subclass_specific(SOFTWARE, PEI_SERVICE, 0x00);
+        EFI_SW_PS_PC_REINSTALL_PPI = subclass_specific(SOFTWARE, PEI_SERVICE, 0x01);
+        EFI_SW_PS_PC_LOCATE_PPI = subclass_specific(SOFTWARE, PEI_SERVICE, 0x02);
+        EFI_SW_PS_PC_NOTIFY_PPI = subclass_specific(SOFTWARE, PEI_SERVICE, 0x03);
+        EFI_SW_PS_PC_GET_BOOT_MODE = subclass_specific(SOFTWARE, PEI_SERVICE, 0x04);
+        EFI_SW_PS_PC_SET_BOOT_MODE = subclass_specific(SOFTWARE, PEI_SERVICE, 0x05);
+        EFI_SW_PS_PC_GET_HOB_LIST = subclass_specific(SOFTWARE, PEI_SERVICE, 0x06);
+        EFI_SW_PS_PC_CREATE_HOB = subclass_specific(SOFTWARE, PEI_SERVICE, 0x07);
+        EFI_SW_PS_PC_FFS_FIND_NEXT_VOLUME = subclass_specific(SOFTWARE, PEI_SERVICE, 0x08);
+        EFI_SW_PS_PC_FFS_FIND_NEXT_FILE = subclass_specific(SOFTWARE, PEI_SERVICE, 0x09);
+        EFI_SW_PS_PC_FFS_FIND_SECTION_DATA = subclass_specific(SOFTWARE, PEI_SERVICE, 0x0A);
+        EFI_SW_PS_PC_INSTALL_PEI_MEMORY = subclass_specific(SOFTWARE, PEI_SERVICE, 0x0B);
+        EFI_SW_PS_PC_ALLOCATE_PAGES = subclass_specific(SOFTWARE, PEI_SERVICE, 0x0C);
+        EFI_SW_PS_PC_ALLOCATE_POOL = subclass_specific(SOFTWARE, PEI_SERVICE, 0x0D);
+        EFI_SW_PS_PC_COPY_MEM = subclass_specific(SOFTWARE, PEI_SERVICE, 0x0E);
+        EFI_SW_PS_PC_SET_MEM = subclass_specific(SOFTWARE, PEI_SERVICE, 0x0F);
+        EFI_SW_PS_PC_RESET_SYSTEM = subclass_specific(SOFTWARE, PEI_SERVICE, 0x10);
+        EFI_SW_PS_PC_FFS_FIND_FILE_BY_NAME = subclass_specific(SOFTWARE, PEI_SERVICE, 0x11);
+        EFI_SW_PS_PC_FFS_GET_FILE_INFO = subclass_specific(SOFTWARE, PEI_SERVICE, 0x12);
+        EFI_SW_PS_PC_FFS_GET_VOLUME_INFO = subclass_specific(SOFTWARE, PEI_SERVICE, 0x13);
+        EFI_SW_PS_PC_FFS_REGISTER_FOR_SHADOW = subclass_specific(SOFTWARE, PEI_SERVICE, 0x14);
+
+        EFI_SW_BS_PC_RAISE_TPL = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x00);
+        EFI_SW_BS_PC_RESTORE_TPL = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x01);
+        EFI_SW_BS_PC_ALLOCATE_PAGES = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x02);
+        EFI_SW_BS_PC_FREE_PAGES = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x03);
+        EFI_SW_BS_PC_GET_MEMORY_MAP = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x04);
+        EFI_SW_BS_PC_ALLOCATE_POOL = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x05);
+        EFI_SW_BS_PC_FREE_POOL = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x06);
+        EFI_SW_BS_PC_CREATE_EVENT = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x07);
+        EFI_SW_BS_PC_SET_TIMER = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x08);
+        EFI_SW_BS_PC_WAIT_FOR_EVENT = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x09);
+        EFI_SW_BS_PC_SIGNAL_EVENT = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x0A);
+        EFI_SW_BS_PC_CLOSE_EVENT = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x0B);
+        EFI_SW_BS_PC_CHECK_EVENT = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x0C);
+        EFI_SW_BS_PC_INSTALL_PROTOCOL_INTERFACE = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x0D);
+        EFI_SW_BS_PC_REINSTALL_PROTOCOL_INTERFACE = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x0E);
+        EFI_SW_BS_PC_UNINSTALL_PROTOCOL_INTERFACE = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x0F);
+        EFI_SW_BS_PC_HANDLE_PROTOCOL = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x10);
+        EFI_SW_BS_PC_PC_HANDLE_PROTOCOL = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x11);
+        EFI_SW_BS_PC_REGISTER_PROTOCOL_NOTIFY = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x12);
+        EFI_SW_BS_PC_LOCATE_HANDLE = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x13);
+        EFI_SW_BS_PC_INSTALL_CONFIGURATION_TABLE = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x14);
+        EFI_SW_BS_PC_LOAD_IMAGE = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x15);
+        EFI_SW_BS_PC_START_IMAGE = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x16);
+        EFI_SW_BS_PC_EXIT = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x17);
+        EFI_SW_BS_PC_UNLOAD_IMAGE = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x18);
+        EFI_SW_BS_PC_EXIT_BOOT_SERVICES = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x19);
+        EFI_SW_BS_PC_GET_NEXT_MONOTONIC_COUNT = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x1A);
+        EFI_SW_BS_PC_STALL = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x1B);
+        EFI_SW_BS_PC_SET_WATCHDOG_TIMER = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x1C);
+        EFI_SW_BS_PC_CONNECT_CONTROLLER = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x1D);
+        EFI_SW_BS_PC_DISCONNECT_CONTROLLER = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x1E);
+        EFI_SW_BS_PC_OPEN_PROTOCOL = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x1F);
+        EFI_SW_BS_PC_CLOSE_PROTOCOL = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x20);
+        EFI_SW_BS_PC_OPEN_PROTOCOL_INFORMATION = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x21);
+        EFI_SW_BS_PC_PROTOCOLS_PER_HANDLE = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x22);
+        EFI_SW_BS_PC_LOCATE_HANDLE_BUFFER = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x23);
+        EFI_SW_BS_PC_LOCATE_PROTOCOL = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x24);
+        EFI_SW_BS_PC_INSTALL_MULTIPLE_INTERFACES = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x25);
+        EFI_SW_BS_PC_UNINSTALL_MULTIPLE_INTERFACES = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x26);
+        EFI_SW_BS_PC_CALCULATE_CRC_32 = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x27);
+        EFI_SW_BS_PC_COPY_MEM = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x28);
+        EFI_SW_BS_PC_SET_MEM = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x29);
+        EFI_SW_BS_PC_CREATE_EVENT_EX = subclass_specific(SOFTWARE, EFI_BOOT_SERVICE, 0x2A);
+
+        EFI_SW_RS_PC_GET_TIME = subclass_specific(SOFTWARE, EFI_RUNTIME_SERVICE, 0x00);
+        EFI_SW_RS_PC_SET_TIME = subclass_specific(SOFTWARE, EFI_RUNTIME_SERVICE, 0x01);
+        EFI_SW_RS_PC_GET_WAKEUP_TIME = subclass_specific(SOFTWARE, EFI_RUNTIME_SERVICE, 0x02);
+        EFI_SW_RS_PC_SET_WAKEUP_TIME = subclass_specific(SOFTWARE, EFI_RUNTIME_SERVICE, 0x03);
+        EFI_SW_RS_PC_SET_VIRTUAL_ADDRESS_MAP = subclass_specific(SOFTWARE, EFI_RUNTIME_SERVICE, 0x04);
+        EFI_SW_RS_PC_CONVERT_POINTER = subclass_specific(SOFTWARE, EFI_RUNTIME_SERVICE, 0x05);
+        EFI_SW_RS_PC_GET_VARIABLE = subclass_specific(SOFTWARE, EFI_RUNTIME_SERVICE, 0x06);
+        EFI_SW_RS_PC_GET_NEXT_VARIABLE_NAME = subclass_specific(SOFTWARE, EFI_RUNTIME_SERVICE, 0x07);
+        EFI_SW_RS_PC_SET_VARIABLE = subclass_specific(SOFTWARE, EFI_RUNTIME_SERVICE, 0x08);
+        EFI_SW_RS_PC_GET_NEXT_HIGH_MONOTONIC_COUNT = subclass_specific(SOFTWARE, EFI_RUNTIME_SERVICE, 0x09);
+        EFI_SW_RS_PC_RESET_SYSTEM = subclass_specific(SOFTWARE, EFI_RUNTIME_SERVICE, 0x0A);
+        EFI_SW_RS_PC_UPDATE_CAPSULE = subclass_specific(SOFTWARE, EFI_RUNTIME_SERVICE, 0x0B);
+        EFI_SW_RS_PC_QUERY_CAPSULE_CAPABILITIES = subclass_specific(SOFTWARE, EFI_RUNTIME_SERVICE, 0x0C);
+        EFI_SW_RS_PC_QUERY_VARIABLE_INFO = subclass_specific(SOFTWARE, EFI_RUNTIME_SERVICE, 0x0D);
+
+        EFI_SW_DS_PC_ADD_MEMORY_SPACE = subclass_specific(SOFTWARE, EFI_DXE_SERVICE, 0x00);
+        EFI_SW_DS_PC_ALLOCATE_MEMORY_SPACE = subclass_specific(SOFTWARE, EFI_DXE_SERVICE, 0x01);
+        EFI_SW_DS_PC_FREE_MEMORY_SPACE = subclass_specific(SOFTWARE, EFI_DXE_SERVICE, 0x02);
+        EFI_SW_DS_PC_REMOVE_MEMORY_SPACE = subclass_specific(SOFTWARE, EFI_DXE_SERVICE, 0x03);
+        EFI_SW_DS_PC_GET_MEMORY_SPACE_DESCRIPTOR = subclass_specific(SOFTWARE, EFI_DXE_SERVICE, 0x04);
+        EFI_SW_DS_PC_SET_MEMORY_SPACE_ATTRIBUTES = subclass_specific(SOFTWARE, EFI_DXE_SERVICE, 0x05);
+        EFI_SW_DS_PC_GET_MEMORY_SPACE_MAP = subclass_specific(SOFTWARE, EFI_DXE_SERVICE, 0x06);
+        EFI_SW_DS_PC_ADD_IO_SPACE = subclass_specific(SOFTWARE, EFI_DXE_SERVICE, 0x07);
+        EFI_SW_DS_PC_ALLOCATE_IO_SPACE = subclass_specific(SOFTWARE, EFI_DXE_SERVICE, 0x08);
+        EFI_SW_DS_PC_FREE_IO_SPACE = subclass_specific(SOFTWARE, EFI_DXE_SERVICE, 0x09);
+        EFI_SW_DS_PC_REMOVE_IO_SPACE = subclass_specific(SOFTWARE, EFI_DXE_SERVICE, 0x0A);
+        EFI_SW_DS_PC_GET_IO_SPACE_DESCRIPTOR = subclass_specific(SOFTWARE, EFI_DXE_SERVICE, 0x0B);
+        EFI_SW_DS_PC_GET_IO_SPACE_MAP = subclass_specific(SOFTWARE, EFI_DXE_SERVICE, 0x0C);
+        EFI_SW_DS_PC_DISPATCH = subclass_specific(SOFTWARE, EFI_DXE_SERVICE, 0x0D);
+        EFI_SW_DS_PC_SCHEDULE = subclass_specific(SOFTWARE, EFI_DXE_SERVICE, 0x0E);
+        EFI_SW_DS_PC_TRUST = subclass_specific(SOFTWARE, EFI_DXE_SERVICE, 0x0F);
+        EFI_SW_DS_PC_PROCESS_FIRMWARE_VOLUME = subclass_specific(SOFTWARE, EFI_DXE_SERVICE, 0x10);
+        EFI_SW_DS_PC_SET_MEMORY_SPACE_CAPABILITIES = subclass_specific(SOFTWARE, EFI_DXE_SERVICE, 0x11);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{computing_unit, io_bus, peripheral, software};
+    use crate::{protocols::status_code, status_code::StatusCodeValue};
+
+    #[test]
+    fn progress_codes_should_match_spec_encoding() {
+        assert_eq!(computing_unit::EFI_CU_HP_PC_POWER_ON_INIT.0, 0x00011000);
+        assert_eq!(computing_unit::EFI_CU_HP_PC_SMM_INIT.0, 0x00011008);
+        assert_eq!(computing_unit::EFI_CU_MEMORY_PC_TEST.0, 0x00051006);
+        assert_eq!(computing_unit::EFI_CHIPSET_PC_DXE_SB_DEVICES_INIT.0, 0x0006100A);
+        assert_eq!(peripheral::EFI_P_KEYBOARD_PC_SELF_TEST.0, 0x01011001);
+        assert_eq!(peripheral::EFI_P_SERIAL_PORT_PC_CLEAR_BUFFER.0, 0x01051000);
+        assert_eq!(io_bus::EFI_IOB_PCI_RES_ALLOC.0, 0x02011001);
+        assert_eq!(io_bus::EFI_IOB_ATA_BUS_SMART_UNDERTHRESHOLD.0, 0x02081003);
+        assert_eq!(software::EFI_SW_SEC_PC_HANDOFF_TO_NEXT.0, 0x03011001);
+        assert_eq!(software::EFI_SW_PEI_CORE_PC_HANDOFF_TO_NEXT.0, 0x03021001);
+        assert_eq!(software::EFI_SW_PEI_PC_OS_WAKE.0, 0x03031006);
+        assert_eq!(software::EFI_SW_DXE_CORE_PC_ENTRY_POINT.0, 0x03041000);
+        assert_eq!(software::EFI_SW_DXE_CORE_PC_ARCH_READY.0, 0x03041004);
+        assert_eq!(software::EFI_SW_DXE_BS_PC_READY_TO_BOOT_EVENT.0, 0x03051001);
+        assert_eq!(software::EFI_SW_DXE_BS_PC_EXIT_BOOT_SERVICES_EVENT.0, 0x03051003);
+        assert_eq!(software::EFI_SW_PS_PC_FFS_REGISTER_FOR_SHADOW.0, 0x030F1014);
+        assert_eq!(software::EFI_SW_RS_PC_QUERY_VARIABLE_INFO.0, 0x0311100D);
+        assert_eq!(software::EFI_SW_DS_PC_SET_MEMORY_SPACE_CAPABILITIES.0, 0x03121011);
+
+        assert_eq!(computing_unit::EFI_CU_PC_INIT_END, 0x0001);
+        assert_eq!(peripheral::EFI_P_PC_REMOVED, 0x0007);
+        assert_eq!(io_bus::EFI_IOB_PC_HOTPLUG, 0x0006);
+        assert_eq!(software::EFI_SW_PC_USER_SETUP, 0x0007);
+    }
+
+    #[test]
+    fn boot_service_codes_should_match_protocol_definitions() {
+        let boot_services = StatusCodeValue(status_code::EFI_SOFTWARE_EFI_BOOT_SERVICE);
+        assert_eq!(software::EFI_SW_BS_PC_RAISE_TPL, boot_services | status_code::EFI_SW_BS_PC_RAISE_TPL as u16);
+        assert_eq!(
+            software::EFI_SW_BS_PC_PC_HANDLE_PROTOCOL,
+            boot_services | status_code::EFI_SW_BS_PC_PC_HANDLE_PROTOCOL as u16
+        );
+        assert_eq!(
+            software::EFI_SW_BS_PC_CREATE_EVENT_EX,
+            boot_services | status_code::EFI_SW_BS_PC_CREATE_EVENT_EX as u16
+        );
+    }
+
+    #[test]
+    fn tables_should_not_contain_duplicates() {
+        let tables = [computing_unit::ALL, peripheral::ALL, io_bus::ALL, software::ALL];
+        let values: BTreeSet<_> = tables.iter().flat_map(|table| table.iter()).map(|(_, value)| *value).collect();
+        assert_eq!(values.len(), tables.iter().map(|table| table.len()).sum());
+        for (class, table) in tables.iter().enumerate() {
+            for (name, value) in table.iter() {
+                assert!(name.starts_with("EFI_"));
+                assert_eq!(value.class() as usize, class);
+                assert!(value.operation() >= StatusCodeValue::SUBCLASS_SPECIFIC);
+                assert!(value.operation() < StatusCodeValue::OEM_SPECIFIC);
+            }
+        }
+        for table in [computing_unit::OPERATIONS, peripheral::OPERATIONS, io_bus::OPERATIONS, software::OPERATIONS] {
+            for (idx, (_, operation)) in table.iter().enumerate() {
+                assert_eq!(*operation as usize, idx);
+            }
+        }
+    }
+}
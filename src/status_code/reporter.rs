@@ -0,0 +1,362 @@
+//! Status Code Reporter
+//!
+//! A convenience wrapper around a ReportStatusCode() function that supplies the caller ID and instance of the
+//! reporting module and builds the EFI_STATUS_CODE_DATA extended data that accompanies a code.
+//!
+//! See <https://uefi.org/specs/PI/1.8A/V2_DXE_Runtime_Protocols.html#efi-status-code-protocol>
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{
+    mem::{size_of, size_of_val},
+    ptr, slice,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use r_efi::efi;
+
+use super::{
+    class,
+    data::{EfiStatusCodeData, DEBUG_DATA_TYPE_GUID},
+    debug::DEBUG_INFO_ARGS,
+    subclass::software,
+    StatusCodeType, StatusCodeValue,
+};
+use crate::protocols::status_code::ReportStatusCode;
+
+/// Maximum size of the extended data built on the stack, header included (EFI_STATUS_CODE_DATA_MAX_SIZE).
+///
+/// Larger data can be reported through the `_in` variants of the [`Reporter`] methods with a caller-provided buffer.
+pub const MAX_DATA_SIZE: usize = 200;
+
+// Instance numbers start at 1; 0 means the instance is not meaningful.
+static NEXT_INSTANCE: AtomicU32 = AtomicU32::new(1);
+
+const HEADER_SIZE: usize = size_of::<EfiStatusCodeData>();
+
+// EFI_DEBUG_INFO followed by the argument array.
+const DEBUG_INFO_SIZE: usize = size_of::<u32>() + DEBUG_INFO_ARGS * size_of::<u64>();
+
+/// Reports status codes on behalf of one module instance.
+///
+/// Each reporter is assigned its own instance number when it is created, and passes it along with its caller ID to
+/// every code it reports.
+///
+/// ## Example
+///```
+/// use mu_pi::status_code::{progress, Reporter, StatusCodeType, StatusCodeValue};
+/// use r_efi::efi;
+///
+/// extern "efiapi" fn report(
+///     _: StatusCodeType,
+///     _: StatusCodeValue,
+///     _: u32,
+///     _: *const efi::Guid,
+///     _: *const mu_pi::status_code::data::EfiStatusCodeData,
+/// ) -> efi::Status {
+///     efi::Status::SUCCESS
+/// }
+///
+/// let caller_id = efi::Guid::from_fields(0, 0, 0, 0, 0, &[0, 0, 0, 0, 0, 1]);
+/// let reporter = Reporter::new(report, caller_id);
+/// assert_eq!(reporter.progress(progress::software::EFI_SW_DXE_CORE_PC_ENTRY_POINT), efi::Status::SUCCESS);
+/// assert_eq!(reporter.debug(0x40, "Loading driver\n"), efi::Status::SUCCESS);
+///```
+#[derive(Debug)]
+pub struct Reporter {
+    report: ReportStatusCode,
+    caller_id: efi::Guid,
+    instance: u32,
+}
+
+impl Reporter {
+    /// Creates a reporter that invokes `report` with `caller_id` and the next unused instance number.
+    pub fn new(report: ReportStatusCode, caller_id: efi::Guid) -> Self {
+        Self::with_instance(report, caller_id, NEXT_INSTANCE.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Creates a reporter that invokes `report` with `caller_id` and a fixed instance number.
+    pub fn with_instance(report: ReportStatusCode, caller_id: efi::Guid, instance: u32) -> Self {
+        Self { report, caller_id, instance }
+    }
+
+    /// Returns the caller ID passed with every code.
+    pub fn caller_id(&self) -> efi::Guid {
+        self.caller_id
+    }
+
+    /// Returns the instance number passed with every code.
+    pub fn instance(&self) -> u32 {
+        self.instance
+    }
+
+    /// Reports a progress code without extended data.
+    pub fn progress(&self, value: StatusCodeValue) -> efi::Status {
+        self.report(StatusCodeType::progress(), value, ptr::null())
+    }
+
+    /// Reports an error code with the given severity (for example [`StatusCodeType::EFI_ERROR_MAJOR`]) without
+    /// extended data.
+    pub fn error(&self, value: StatusCodeValue, severity: u32) -> efi::Status {
+        self.report(StatusCodeType::error(severity), value, ptr::null())
+    }
+
+    /// Reports a debug message at the given debug error level, as DEBUG() does when routed through the status code
+    /// infrastructure.
+    ///
+    /// `text` is reported literally, with any `%` escaped, and is truncated to fit in [`MAX_DATA_SIZE`].
+    pub fn debug(&self, level: u32, text: &str) -> efi::Status {
+        let mut buffer = [0u64; MAX_DATA_SIZE / size_of::<u64>()];
+        self.debug_in(&mut buffer, level, text)
+    }
+
+    /// Same as [`Reporter::debug`], but builds the extended data in `buffer`. `text` is truncated to fit.
+    ///
+    /// Returns `BUFFER_TOO_SMALL` if `buffer` cannot hold the extended data header and debug arguments.
+    pub fn debug_in(&self, buffer: &mut [u64], level: u32, text: &str) -> efi::Status {
+        let bytes = as_bytes_mut(buffer);
+        let format_offset = HEADER_SIZE + DEBUG_INFO_SIZE;
+        // The format string must be null-terminated.
+        let Some(format_len) = bytes.len().checked_sub(format_offset + 1) else {
+            return efi::Status::BUFFER_TOO_SMALL;
+        };
+        let format_len = format_len.min(u16::MAX as usize - DEBUG_INFO_SIZE - 1);
+
+        let (payload, format) = bytes[HEADER_SIZE..].split_at_mut(DEBUG_INFO_SIZE);
+        payload.fill(0);
+        payload[..size_of::<u32>()].copy_from_slice(&level.to_le_bytes());
+
+        let mut len = 0;
+        for &c in text.as_bytes() {
+            let escaped: &[u8] = if c == b'%' { b"%%" } else { slice::from_ref(&c) };
+            if len + escaped.len() > format_len {
+                break;
+            }
+            format[len..len + escaped.len()].copy_from_slice(escaped);
+            len += escaped.len();
+        }
+        format[len] = 0;
+
+        let value = StatusCodeValue::compose(class::SOFTWARE, software::UNSPECIFIED, 0);
+        write_header(bytes, &DEBUG_DATA_TYPE_GUID, DEBUG_INFO_SIZE + len + 1);
+        self.report(StatusCodeType::debug(), value, bytes.as_ptr() as *const EfiStatusCodeData)
+    }
+
+    /// Reports a code with `data` as its extended data, tagged with the payload type `guid`.
+    ///
+    /// Returns `BUFFER_TOO_SMALL` if the extended data does not fit in [`MAX_DATA_SIZE`].
+    pub fn with_guid_data(
+        &self,
+        code_type: StatusCodeType,
+        value: StatusCodeValue,
+        guid: &efi::Guid,
+        data: &[u8],
+    ) -> efi::Status {
+        let mut buffer = [0u64; MAX_DATA_SIZE / size_of::<u64>()];
+        self.with_guid_data_in(&mut buffer, code_type, value, guid, data)
+    }
+
+    /// Same as [`Reporter::with_guid_data`], but builds the extended data in `buffer`.
+    ///
+    /// Returns `BUFFER_TOO_SMALL` if `buffer` cannot hold the extended data, or `INVALID_PARAMETER` if `data` is
+    /// larger than an extended data payload can describe.
+    pub fn with_guid_data_in(
+        &self,
+        buffer: &mut [u64],
+        code_type: StatusCodeType,
+        value: StatusCodeValue,
+        guid: &efi::Guid,
+        data: &[u8],
+    ) -> efi::Status {
+        if data.len() > u16::MAX as usize {
+            return efi::Status::INVALID_PARAMETER;
+        }
+        let bytes = as_bytes_mut(buffer);
+        let Some(payload) = bytes.get_mut(HEADER_SIZE..HEADER_SIZE + data.len()) else {
+            return efi::Status::BUFFER_TOO_SMALL;
+        };
+        payload.copy_from_slice(data);
+        write_header(bytes, guid, data.len());
+        self.report(code_type, value, bytes.as_ptr() as *const EfiStatusCodeData)
+    }
+
+    fn report(&self, code_type: StatusCodeType, value: StatusCodeValue, data: *const EfiStatusCodeData) -> efi::Status {
+        (self.report)(code_type, value, self.instance, &self.caller_id, data)
+    }
+}
+
+fn as_bytes_mut(buffer: &mut [u64]) -> &mut [u8] {
+    //Safety: any initialized u64 buffer is also a valid byte buffer of eight times the length.
+    unsafe { slice::from_raw_parts_mut(buffer.as_mut_ptr() as *mut u8, size_of_val(buffer)) }
+}
+
+fn write_header(bytes: &mut [u8], guid: &efi::Guid, size: usize) {
+    bytes[0..2].copy_from_slice(&(HEADER_SIZE as u16).to_le_bytes());
+    bytes[2..4].copy_from_slice(&(size as u16).to_le_bytes());
+    bytes[4..HEADER_SIZE].copy_from_slice(guid.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::RefCell;
+
+    use r_efi::efi;
+
+    use super::{Reporter, MAX_DATA_SIZE};
+    use crate::status_code::{
+        data::{EfiStatusCodeData, StatusCodeData, DEBUG_DATA_TYPE_GUID, SPECIFIC_DATA_GUID},
+        debug::decode_debug_info,
+        error, progress, StatusCodeType, StatusCodeValue,
+    };
+
+    struct Report {
+        code_type: StatusCodeType,
+        value: StatusCodeValue,
+        instance: u32,
+        caller_id: efi::Guid,
+        data: Option<Vec<u8>>,
+    }
+
+    thread_local! {
+        static REPORTS: RefCell<Vec<Report>> = const { RefCell::new(Vec::new()) };
+    }
+
+    const CALLER_ID: efi::Guid =
+        efi::Guid::from_fields(0x12345678, 0x9abc, 0xdef0, 0x12, 0x34, &[0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0]);
+
+    extern "efiapi" fn capture(
+        code_type: StatusCodeType,
+        value: StatusCodeValue,
+        instance: u32,
+        caller_id: *const efi::Guid,
+        data: *const EfiStatusCodeData,
+    ) -> efi::Status {
+        let data = (!data.is_null()).then(|| {
+            let view = unsafe { StatusCodeData::from_ptr(data) }.unwrap();
+            let len = view.header_size() + view.payload().len();
+            unsafe { core::slice::from_raw_parts(data as *const u8, len) }.to_vec()
+        });
+        let caller_id = unsafe { *caller_id };
+        REPORTS.with(|reports| reports.borrow_mut().push(Report { code_type, value, instance, caller_id, data }));
+        efi::Status::SUCCESS
+    }
+
+    fn take_reports() -> Vec<Report> {
+        REPORTS.with(|reports| reports.borrow_mut().drain(..).collect())
+    }
+
+    #[test]
+    fn new_should_assign_distinct_instances() {
+        let first = Reporter::new(capture, CALLER_ID);
+        let second = Reporter::new(capture, CALLER_ID);
+        assert_ne!(first.instance(), 0);
+        assert_ne!(second.instance(), 0);
+        assert_ne!(first.instance(), second.instance());
+        assert_eq!(Reporter::with_instance(capture, CALLER_ID, 7).instance(), 7);
+    }
+
+    #[test]
+    fn progress_and_error_should_report_without_data() {
+        let reporter = Reporter::with_instance(capture, CALLER_ID, 3);
+        assert_eq!(reporter.progress(progress::software::EFI_SW_DXE_CORE_PC_ENTRY_POINT), efi::Status::SUCCESS);
+        assert_eq!(
+            reporter.error(error::software::EFI_SW_DXE_CORE_EC_NO_ARCH, StatusCodeType::EFI_ERROR_MAJOR),
+            efi::Status::SUCCESS
+        );
+
+        let reports = take_reports();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].code_type, StatusCodeType::progress());
+        assert_eq!(reports[0].value, progress::software::EFI_SW_DXE_CORE_PC_ENTRY_POINT);
+        assert_eq!(reports[0].instance, 3);
+        assert_eq!(reports[0].caller_id, CALLER_ID);
+        assert!(reports[0].data.is_none());
+        assert_eq!(reports[1].code_type.0, 0x80000002);
+        assert_eq!(reports[1].value, error::software::EFI_SW_DXE_CORE_EC_NO_ARCH);
+        assert!(reports[1].data.is_none());
+    }
+
+    #[test]
+    fn with_guid_data_should_build_header() {
+        let reporter = Reporter::with_instance(capture, CALLER_ID, 1);
+        let status = reporter.with_guid_data(
+            StatusCodeType::progress(),
+            StatusCodeValue(0x01010000),
+            &SPECIFIC_DATA_GUID,
+            &[0xAA, 0xBB, 0xCC],
+        );
+        assert_eq!(status, efi::Status::SUCCESS);
+
+        let reports = take_reports();
+        let bytes = reports[0].data.as_ref().unwrap();
+        assert_eq!(&bytes[..4], &[20, 0, 3, 0]);
+        assert_eq!(&bytes[4..20], SPECIFIC_DATA_GUID.as_bytes());
+        assert_eq!(&bytes[20..], &[0xAA, 0xBB, 0xCC]);
+    }
+
+    #[test]
+    fn with_guid_data_should_reject_oversized_data() {
+        let reporter = Reporter::with_instance(capture, CALLER_ID, 1);
+        let data = [0u8; MAX_DATA_SIZE - 19];
+        let status =
+            reporter.with_guid_data(StatusCodeType::progress(), StatusCodeValue(0), &SPECIFIC_DATA_GUID, &data);
+        assert_eq!(status, efi::Status::BUFFER_TOO_SMALL);
+
+        let mut buffer = [0u64; 64];
+        let status = reporter.with_guid_data_in(
+            &mut buffer,
+            StatusCodeType::progress(),
+            StatusCodeValue(0),
+            &SPECIFIC_DATA_GUID,
+            &data,
+        );
+        assert_eq!(status, efi::Status::SUCCESS);
+        assert_eq!(take_reports().len(), 1);
+    }
+
+    #[test]
+    fn debug_should_build_debug_info() {
+        let reporter = Reporter::with_instance(capture, CALLER_ID, 1);
+        assert_eq!(reporter.debug(0x40, "100% done\n"), efi::Status::SUCCESS);
+
+        let reports = take_reports();
+        assert_eq!(reports[0].code_type, StatusCodeType::debug());
+        assert_eq!(reports[0].value, StatusCodeValue(0x03000000));
+        let bytes = reports[0].data.as_ref().unwrap();
+        let data = StatusCodeData::from_bytes(bytes).unwrap();
+        assert_eq!(data.payload_guid(), DEBUG_DATA_TYPE_GUID);
+
+        let info = decode_debug_info(&data).unwrap();
+        assert_eq!(info.error_level, 0x40);
+        assert_eq!(info.args, [0; 12]);
+        assert_eq!(info.format, b"100%% done\n");
+        assert_eq!(info.render(), "100% done\n");
+    }
+
+    #[test]
+    fn debug_should_truncate_long_text() {
+        let reporter = Reporter::with_instance(capture, CALLER_ID, 1);
+        let text = "x".repeat(500);
+        assert_eq!(reporter.debug(1, &text), efi::Status::SUCCESS);
+        let bytes = take_reports().remove(0).data.unwrap();
+        assert_eq!(bytes.len(), MAX_DATA_SIZE);
+        let data = StatusCodeData::from_bytes(&bytes).unwrap();
+        assert_eq!(decode_debug_info(&data).unwrap().format.len(), MAX_DATA_SIZE - 20 - 100 - 1);
+
+        // Escapes are never split.
+        let text = "%".repeat(500);
+        assert_eq!(reporter.debug(1, &text), efi::Status::SUCCESS);
+        let bytes = take_reports().remove(0).data.unwrap();
+        let data = StatusCodeData::from_bytes(&bytes).unwrap();
+        assert_eq!(decode_debug_info(&data).unwrap().format.len() % 2, 0);
+
+        let mut buffer = [0u64; 4];
+        assert_eq!(reporter.debug_in(&mut buffer, 1, "text"), efi::Status::BUFFER_TOO_SMALL);
+        assert!(take_reports().is_empty());
+    }
+}
@@ -0,0 +1,145 @@
+//! Status Code Subclasses
+//!
+//! Subclass values (bits 23:16 of a [`StatusCodeValue`](super::StatusCodeValue)) defined for each class. Values not
+//! listed here up to 0x7F are reserved by the specification; values 0x80-0xFF are reserved for OEM use.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+/// Computing unit subclasses.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section III-6.7.1.2
+pub mod computing_unit {
+    status_code_table! {
+        /// Every computing unit subclass defined by the specification.
+        ALL: u8;
+        UNSPECIFIED = 0x00;
+        HOST_PROCESSOR = 0x01;
+        FIRMWARE_PROCESSOR = 0x02;
+        IO_PROCESSOR = 0x03;
+        CACHE = 0x04;
+        MEMORY = 0x05;
+        CHIPSET = 0x06;
+        MANAGEABILITY = 0x07;
+    }
+}
+
+/// Peripheral subclasses.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section III-6.7.2.2
+pub mod peripheral {
+    status_code_table! {
+        /// Every peripheral subclass defined by the specification.
+        ALL: u8;
+        UNSPECIFIED = 0x00;
+        KEYBOARD = 0x01;
+        MOUSE = 0x02;
+        LOCAL_CONSOLE = 0x03;
+        REMOTE_CONSOLE = 0x04;
+        SERIAL_PORT = 0x05;
+        PARALLEL_PORT = 0x06;
+        FIXED_MEDIA = 0x07;
+        REMOVABLE_MEDIA = 0x08;
+        AUDIO_INPUT = 0x09;
+        AUDIO_OUTPUT = 0x0A;
+        LCD_DEVICE = 0x0B;
+        NETWORK = 0x0C;
+        DOCKING = 0x0D;
+        TPM = 0x0E;
+    }
+}
+
+/// I/O bus subclasses.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section III-6.7.3.2
+pub mod io_bus {
+    status_code_table! {
+        /// Every I/O bus subclass defined by the specification.
+        ALL: u8;
+        UNSPECIFIED = 0x00;
+        PCI = 0x01;
+        USB = 0x02;
+        IBA = 0x03;
+        AGP = 0x04;
+        PC_CARD = 0x05;
+        LPC = 0x06;
+        SCSI = 0x07;
+        ATA_ATAPI = 0x08;
+        FC = 0x09;
+        IP_NETWORK = 0x0A;
+        SMBUS = 0x0B;
+        I2C = 0x0C;
+    }
+}
+
+/// Software subclasses.
+///
+/// # Documentation
+/// UEFI Platform Initialization Specification, Release 1.8, Section III-6.7.4.2
+pub mod software {
+    status_code_table! {
+        /// Every software subclass defined by the specification.
+        ALL: u8;
+        UNSPECIFIED = 0x00;
+        SEC = 0x01;
+        PEI_CORE = 0x02;
+        PEI_MODULE = 0x03;
+        DXE_CORE = 0x04;
+        DXE_BS_DRIVER = 0x05;
+        DXE_RT_DRIVER = 0x06;
+        SMM_DRIVER = 0x07;
+        EFI_APPLICATION = 0x08;
+        EFI_OS_LOADER = 0x09;
+        RT = 0x0A;
+        AL = 0x0B;
+        EBC_EXCEPTION = 0x0C;
+        IA32_EXCEPTION = 0x0D;
+        IPF_EXCEPTION = 0x0E;
+        PEI_SERVICE = 0x0F;
+        EFI_BOOT_SERVICE = 0x10;
+        EFI_RUNTIME_SERVICE = 0x11;
+        EFI_DXE_SERVICE = 0x12;
+        X64_EXCEPTION = 0x13;
+        ARM_EXCEPTION = 0x14;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{computing_unit, io_bus, peripheral, software};
+    use crate::{
+        protocols::status_code,
+        status_code::{class, StatusCodeValue},
+    };
+
+    #[test]
+    fn software_subclasses_should_match_protocol_definitions() {
+        let compose = |subclass| StatusCodeValue::compose(class::SOFTWARE, subclass, 0).0;
+        assert_eq!(compose(software::UNSPECIFIED), status_code::EFI_SOFTWARE_UNSPECIFIED);
+        assert_eq!(compose(software::SEC), status_code::EFI_SOFTWARE_SEC);
+        assert_eq!(compose(software::DXE_CORE), status_code::EFI_SOFTWARE_DXE_CORE);
+        assert_eq!(compose(software::SMM_DRIVER), status_code::EFI_SOFTWARE_SMM_DRIVER);
+        assert_eq!(compose(software::PEI_SERVICE), status_code::EFI_SOFTWARE_PEI_SERVICE);
+        assert_eq!(compose(software::EFI_BOOT_SERVICE), status_code::EFI_SOFTWARE_EFI_BOOT_SERVICE);
+        assert_eq!(compose(software::ARM_EXCEPTION), status_code::EFI_SOFTWARE_ARM_EXCEPTION);
+    }
+
+    #[test]
+    fn subclass_tables_should_be_sequential() {
+        // every class numbers its subclasses contiguously from zero.
+        for table in [computing_unit::ALL, peripheral::ALL, io_bus::ALL, software::ALL] {
+            for (idx, (_, value)) in table.iter().enumerate() {
+                assert_eq!(*value as usize, idx);
+            }
+        }
+        assert_eq!(peripheral::ALL.last(), Some(&("TPM", 0x0E)));
+        assert_eq!(io_bus::ALL.last(), Some(&("I2C", 0x0C)));
+    }
+}
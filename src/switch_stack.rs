@@ -0,0 +1,238 @@
+//! Stack Switching
+//!
+//! Switches to a new stack and calls an entry point on it, as done when the PEI phase hands off to the DXE core on
+//! the stack described by the DXE IPL, or when code must run on a stack other than the current one.
+//!
+//! ## Example
+//!```no_run
+//! use core::ffi::c_void;
+//! use mu_pi::switch_stack::SwitchStackBuilder;
+//!
+//! extern "efiapi" fn dxe_main(hob_list: *mut c_void) -> ! {
+//!   loop {}
+//! }
+//!
+//! # let (stack_top, stack_size, hob_list) = (0x80000u64, 0x20000usize, core::ptr::null_mut());
+//! let stack = SwitchStackBuilder::new(stack_top, stack_size).with_param(hob_list).build().unwrap();
+//! //Safety: the stack is reserved for the DXE core.
+//! unsafe { stack.switch(dxe_main) }
+//!```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+use core::{ffi::c_void, fmt, ptr};
+
+/// Alignment required of the top of a new stack.
+pub const STACK_ALIGNMENT: u64 = 16;
+
+/// Smallest new stack accepted.
+pub const MIN_STACK_SIZE: usize = 0x1000;
+
+/// Entry point called on the new stack, with the parameter of the switch.
+pub type SwitchStackEntryPoint = extern "efiapi" fn(*mut c_void) -> !;
+
+/// Errors reported when validating a new stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwitchStackError {
+    /// The top of the stack is not aligned to [`STACK_ALIGNMENT`].
+    MisalignedStack(u64),
+    /// The stack is smaller than [`MIN_STACK_SIZE`].
+    StackTooSmall(usize),
+    /// The stack would extend below address zero.
+    InvalidStack { top: u64, size: usize },
+}
+
+impl fmt::Display for SwitchStackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SwitchStackError::MisalignedStack(top) => write!(f, "stack top {top:#x} is not 16-byte aligned"),
+            SwitchStackError::StackTooSmall(size) => write!(f, "stack size {size:#x} is smaller than 0x1000"),
+            SwitchStackError::InvalidStack { top, size } => {
+                write!(f, "stack of size {size:#x} below {top:#x} wraps around")
+            }
+        }
+    }
+}
+
+/// Builds a validated [`SwitchStack`].
+#[derive(Debug, Clone, Copy)]
+pub struct SwitchStackBuilder {
+    top: u64,
+    size: usize,
+    param: *mut c_void,
+}
+
+impl SwitchStackBuilder {
+    /// Starts building a switch to the stack of `new_stack_size` bytes ending at `new_stack_top`.
+    pub fn new(new_stack_top: u64, new_stack_size: usize) -> Self {
+        Self { top: new_stack_top, size: new_stack_size, param: ptr::null_mut() }
+    }
+
+    /// Sets the parameter passed to the entry point, null by default.
+    pub fn with_param(mut self, param: *mut c_void) -> Self {
+        self.param = param;
+        self
+    }
+
+    /// Validates the stack.
+    pub fn build(self) -> Result<SwitchStack, SwitchStackError> {
+        if self.top % STACK_ALIGNMENT != 0 {
+            Err(SwitchStackError::MisalignedStack(self.top))?;
+        }
+        if self.size < MIN_STACK_SIZE {
+            Err(SwitchStackError::StackTooSmall(self.size))?;
+        }
+        if self.top.checked_sub(self.size as u64).is_none() {
+            Err(SwitchStackError::InvalidStack { top: self.top, size: self.size })?;
+        }
+        Ok(SwitchStack { top: self.top, param: self.param })
+    }
+}
+
+/// A validated switch to a new stack.
+#[derive(Debug, Clone, Copy)]
+pub struct SwitchStack {
+    top: u64,
+    param: *mut c_void,
+}
+
+impl SwitchStack {
+    /// Returns the top of the new stack.
+    pub fn stack_top(&self) -> u64 {
+        self.top
+    }
+
+    /// Switches to the new stack and jumps to `entry`, passing the parameter in RCX. The current stack is abandoned.
+    ///
+    /// # Safety
+    /// The new stack must be memory reserved for this use, not in use by any other code, and must remain valid for
+    /// as long as `entry` runs.
+    #[cfg(target_arch = "x86_64")]
+    pub unsafe fn switch(self, entry: SwitchStackEntryPoint) -> ! {
+        core::arch::asm!(
+            "mov rsp, {stack}",
+            // Shadow space for the four register parameters of an efiapi function.
+            "sub rsp, 0x20",
+            // No return address; entry points on a new stack never return.
+            "push 0",
+            "jmp {entry}",
+            stack = in(reg) self.top,
+            entry = in(reg) entry,
+            in("rcx") self.param,
+            options(noreturn),
+        )
+    }
+
+    /// Calls `entry` on the new stack, passing the parameter in RCX, and returns to the current stack once it
+    /// returns. The callee-saved registers and stack pointer of the current stack are preserved across the call.
+    ///
+    /// # Safety
+    /// The new stack must be memory reserved for this use and not in use by any other code.
+    #[cfg(target_arch = "x86_64")]
+    pub unsafe fn call(self, entry: extern "efiapi" fn(*mut c_void)) {
+        core::arch::asm!(
+            // R12 is callee-saved in the efiapi ABI, so it holds the current stack pointer across the call.
+            "mov r12, rsp",
+            "mov rsp, {stack}",
+            "sub rsp, 0x20",
+            "call {entry}",
+            "mov rsp, r12",
+            stack = in(reg) self.top,
+            entry = in(reg) entry,
+            in("rcx") self.param,
+            out("r12") _,
+            clobber_abi("efiapi"),
+        )
+    }
+}
+
+/// Switches to the stack of `new_stack_size` bytes ending at `new_stack_top` and jumps to `entry`, passing `param` in
+/// RCX.
+///
+/// # Safety
+/// See [`SwitchStack::switch`].
+///
+/// # Panics
+/// Panics if the stack is not valid for [`SwitchStackBuilder::build`].
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn switch_stack_and_call(
+    new_stack_top: u64,
+    new_stack_size: usize,
+    entry: SwitchStackEntryPoint,
+    param: *mut c_void,
+) -> ! {
+    match SwitchStackBuilder::new(new_stack_top, new_stack_size).with_param(param).build() {
+        Ok(stack) => stack.switch(entry),
+        Err(err) => panic!("invalid new stack: {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    #[cfg(target_arch = "x86_64")]
+    use alloc::vec;
+    #[cfg(target_arch = "x86_64")]
+    use core::{ffi::c_void, ptr};
+
+    use super::{SwitchStackBuilder, SwitchStackError, MIN_STACK_SIZE};
+
+    #[test]
+    fn builder_should_validate_stack() {
+        assert!(SwitchStackBuilder::new(0x10000, MIN_STACK_SIZE).build().is_ok());
+        assert_eq!(
+            SwitchStackBuilder::new(0x10008, 0x2000).build().unwrap_err(),
+            SwitchStackError::MisalignedStack(0x10008)
+        );
+        assert_eq!(
+            SwitchStackBuilder::new(0x10000, 0xfff).build().unwrap_err(),
+            SwitchStackError::StackTooSmall(0xfff)
+        );
+        assert_eq!(
+            SwitchStackBuilder::new(0x1000, 0x2000).build().unwrap_err(),
+            SwitchStackError::InvalidStack { top: 0x1000, size: 0x2000 }
+        );
+        assert_eq!(SwitchStackBuilder::new(0x10000, 0x2000).build().unwrap().stack_top(), 0x10000);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    struct Call<'a> {
+        closure: &'a mut dyn FnMut(usize),
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    extern "efiapi" fn trampoline(param: *mut c_void) {
+        let call = unsafe { &mut *(param as *mut Call) };
+        let local = 0u8;
+        (call.closure)(ptr::addr_of!(local) as usize);
+    }
+
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    fn call_should_run_on_new_stack() {
+        let mut stack = vec![0u128; 0x1000];
+        let base = stack.as_mut_ptr() as usize;
+        let top = base + stack.len() * 16;
+
+        let mut flag = false;
+        let mut local_address = 0;
+        let mut closure = |address: usize| {
+            flag = true;
+            local_address = address;
+        };
+        let mut call = Call { closure: &mut closure };
+        let switch = SwitchStackBuilder::new(top as u64, top - base)
+            .with_param(&mut call as *mut Call as *mut c_void)
+            .build()
+            .unwrap();
+        unsafe { switch.call(trampoline) };
+
+        assert!(flag);
+        assert!((base..top).contains(&local_address), "{local_address:#x} not in {base:#x}..{top:#x}");
+    }
+}
@@ -0,0 +1,248 @@
+//! Test Doubles
+//!
+//! Scripted instances of protocols, for unit-testing code that calls them. Available in the crate's own tests, and to
+//! other crates with the `testing` feature.
+//!
+//! [`MockSecurity`] and [`MockSecurity2`] produce real Security and Security2 Architectural Protocol structures whose
+//! functions return the verdicts queued in the mock, in order, and record each call that reaches them. A call made
+//! once the queue is empty is denied.
+//!
+//! ## Example
+//! ```
+//! # #[cfg(feature = "testing")]
+//! # {
+//! use mu_pi::{
+//!   protocols::security2::{Caller, ImageVerdict, SecurityError},
+//!   testing::MockSecurity2,
+//! };
+//!
+//! let mock = MockSecurity2::new([ImageVerdict::Allow, ImageVerdict::Deny]);
+//! let caller = Caller::new(mock.protocol());
+//! assert_eq!(caller.authenticate_image(None, b"MZ", false), Ok(()));
+//! assert_eq!(caller.authenticate_image(None, b"MZ", false), Err(SecurityError::Denied));
+//! assert_eq!(mock.calls().len(), 2);
+//! # }
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+extern crate alloc;
+
+use alloc::{collections::VecDeque, vec::Vec};
+use core::cell::RefCell;
+
+use crate::{
+    device_path::DevicePathWalker,
+    fw_fs::ffs::section::AuthStatus,
+    protocols::{
+        security::{self, SecurityPolicy, SecurityVerdict, StaticSecurityProtocol},
+        security2::{self, ImagePolicy, ImageVerdict, StaticSecurity2Protocol},
+    },
+};
+
+/// A call of FileAuthenticationState() recorded by [`MockSecurity`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecurityCall {
+    pub auth_status: AuthStatus,
+    /// The device path of the file, or `None` if it was malformed.
+    pub device_path: Option<Vec<u8>>,
+}
+
+/// A call of FileAuthentication() recorded by [`MockSecurity2`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Security2Call {
+    pub device_path: Option<Vec<u8>>,
+    /// The size of the image, or `None` when drivers were to be connected to the device.
+    pub file_size: Option<usize>,
+    pub boot_policy: bool,
+}
+
+// The verdicts still to return, and the calls received.
+#[derive(Debug)]
+struct Script<V, C> {
+    verdicts: RefCell<VecDeque<V>>,
+    calls: RefCell<Vec<C>>,
+}
+
+impl<V, C> Script<V, C> {
+    fn new(verdicts: impl IntoIterator<Item = V>) -> Self {
+        Self { verdicts: RefCell::new(verdicts.into_iter().collect()), calls: RefCell::new(Vec::new()) }
+    }
+
+    fn call(&self, call: C, empty: V) -> V {
+        self.calls.borrow_mut().push(call);
+        self.verdicts.borrow_mut().pop_front().unwrap_or(empty)
+    }
+}
+
+impl SecurityPolicy for Script<SecurityVerdict, SecurityCall> {
+    fn evaluate(&self, auth_status: AuthStatus, file: Option<&DevicePathWalker>) -> SecurityVerdict {
+        let device_path = file.map(|file| file.as_bytes().to_vec());
+        self.call(SecurityCall { auth_status, device_path }, SecurityVerdict::Deny)
+    }
+}
+
+impl ImagePolicy for Script<ImageVerdict, Security2Call> {
+    fn authenticate(
+        &self,
+        device_path: Option<&DevicePathWalker>,
+        image: Option<&[u8]>,
+        boot_policy: bool,
+    ) -> ImageVerdict {
+        let device_path = device_path.map(|device_path| device_path.as_bytes().to_vec());
+        let file_size = image.map(<[u8]>::len);
+        self.call(Security2Call { device_path, file_size, boot_policy }, ImageVerdict::Deny)
+    }
+}
+
+/// A scripted Security Architectural Protocol instance.
+///
+/// The instance must not move while its protocol is in use.
+#[derive(Debug)]
+pub struct MockSecurity {
+    instance: StaticSecurityProtocol<Script<SecurityVerdict, SecurityCall>>,
+}
+
+impl MockSecurity {
+    /// Creates an instance returning `verdicts` in order.
+    pub fn new(verdicts: impl IntoIterator<Item = SecurityVerdict>) -> Self {
+        Self { instance: StaticSecurityProtocol::new(Script::new(verdicts)) }
+    }
+
+    /// Queues `verdict` after the verdicts not yet returned.
+    pub fn push_verdict(&self, verdict: SecurityVerdict) {
+        self.instance.policy().verdicts.borrow_mut().push_back(verdict);
+    }
+
+    /// Returns the protocol.
+    pub fn protocol(&self) -> &security::Protocol {
+        self.instance.protocol()
+    }
+
+    /// Returns the calls received so far.
+    pub fn calls(&self) -> Vec<SecurityCall> {
+        self.instance.policy().calls.borrow().clone()
+    }
+}
+
+/// A scripted Security2 Architectural Protocol instance.
+///
+/// Calls rejected by the argument validation of [`StaticSecurity2Protocol`] are neither recorded nor consume a
+/// verdict. The instance must not move while its protocol is in use.
+#[derive(Debug)]
+pub struct MockSecurity2 {
+    instance: StaticSecurity2Protocol<Script<ImageVerdict, Security2Call>>,
+}
+
+impl MockSecurity2 {
+    /// Creates an instance returning `verdicts` in order.
+    pub fn new(verdicts: impl IntoIterator<Item = ImageVerdict>) -> Self {
+        Self { instance: StaticSecurity2Protocol::new(Script::new(verdicts)) }
+    }
+
+    /// Queues `verdict` after the verdicts not yet returned.
+    pub fn push_verdict(&self, verdict: ImageVerdict) {
+        self.instance.policy().verdicts.borrow_mut().push_back(verdict);
+    }
+
+    /// Returns the protocol.
+    pub fn protocol(&self) -> &security2::Protocol {
+        self.instance.protocol()
+    }
+
+    /// Returns the calls received so far.
+    pub fn calls(&self) -> Vec<Security2Call> {
+        self.instance.policy().calls.borrow().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use r_efi::efi;
+
+    use super::{MockSecurity, MockSecurity2, Security2Call, SecurityCall};
+    use crate::{
+        device_path::DevicePathProtocol,
+        fw_fs::ffs::section::{auth_status, AuthStatus},
+        protocols::{
+            security::SecurityVerdict,
+            security2::{Caller, ImageVerdict, SecurityError},
+        },
+    };
+
+    // A file path node for "A" followed by the End node.
+    const PATH: [u8; 12] = [0x04, 0x04, 0x08, 0x00, b'A', 0x00, 0x00, 0x00, 0x7F, 0xFF, 0x04, 0x00];
+
+    #[test]
+    fn mock_security_should_follow_its_script() {
+        let mock = MockSecurity::new([SecurityVerdict::Allow, SecurityVerdict::Defer]);
+        let protocol = mock.protocol();
+        let file = PATH.as_ptr() as *const DevicePathProtocol;
+        let call = |status| (protocol.file_authentication_state)(protocol, status, file);
+
+        assert_eq!(call(auth_status::IMAGE_SIGNED), efi::Status::SUCCESS);
+        assert_eq!(call(auth_status::NOT_TESTED), efi::Status::SECURITY_VIOLATION);
+        // The queue is empty.
+        assert_eq!(call(0), efi::Status::ACCESS_DENIED);
+        mock.push_verdict(SecurityVerdict::Allow);
+        assert_eq!(call(0), efi::Status::SUCCESS);
+
+        let calls = mock.calls();
+        assert_eq!(calls.len(), 4);
+        assert_eq!(
+            calls[1],
+            SecurityCall {
+                auth_status: AuthStatus::from_bits(auth_status::NOT_TESTED),
+                device_path: Some(PATH.to_vec())
+            }
+        );
+    }
+
+    // A dispatcher loading each image after asking Security2, as a DXE core would.
+    #[derive(Default)]
+    struct Dispatcher {
+        dispatched: Vec<&'static str>,
+        deferred: Vec<&'static str>,
+        rejected: Vec<&'static str>,
+    }
+
+    impl Dispatcher {
+        fn dispatch(&mut self, security: &Caller, images: &[(&'static str, &[u8])]) {
+            for (name, image) in images {
+                match security.authenticate_image(Some(&PATH), image, false) {
+                    Ok(()) => self.dispatched.push(name),
+                    Err(SecurityError::Deferred) => self.deferred.push(name),
+                    Err(_) => self.rejected.push(name),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dispatcher_should_follow_security2_verdicts() {
+        let mock = MockSecurity2::new([ImageVerdict::Allow, ImageVerdict::Defer, ImageVerdict::Deny]);
+        let mut dispatcher = Dispatcher::default();
+        dispatcher.dispatch(&Caller::new(mock.protocol()), &[("core", b"MZ core"), ("driver", b"MZ"), ("rogue", b"?")]);
+
+        assert_eq!(dispatcher.dispatched, ["core"]);
+        assert_eq!(dispatcher.deferred, ["driver"]);
+        assert_eq!(dispatcher.rejected, ["rogue"]);
+        let calls = mock.calls();
+        assert_eq!(calls.len(), 3);
+        assert_eq!(
+            calls[0],
+            Security2Call { device_path: Some(PATH.to_vec()), file_size: Some(7), boot_policy: false }
+        );
+        assert_eq!(calls[2].file_size, Some(1));
+
+        // The user identity case, for connecting drivers.
+        mock.push_verdict(ImageVerdict::DeferConnect);
+        assert_eq!(Caller::new(mock.protocol()).authorize_connect(&PATH), Err(SecurityError::Deferred));
+        assert_eq!(mock.calls()[3].file_size, None);
+    }
+}
@@ -0,0 +1,560 @@
+//! UEFI Variable Reading
+//!
+//! [`VarReader`] reads UEFI variables through either the
+//! [Read Only Variable2 PPI](crate::protocols::read_only_variable2) in PEI, or the GetVariable() and
+//! GetNextVariableName() runtime services. It converts variable names to and from UCS-2, grows buffers when the
+//! services report BUFFER_TOO_SMALL, and decodes the variable attributes.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/08_Services_Runtime_Services.html#variable-services>
+//!
+//! ## Example
+//! ```no_run
+//! use mu_pi::variables::{VarError, VarReader};
+//! use r_efi::efi;
+//!
+//! fn boot_order(runtime_services: &efi::RuntimeServices, global_variable: &efi::Guid) -> Result<Vec<u16>, VarError> {
+//!     let reader =
+//!         VarReader::from_runtime_services(runtime_services.get_variable, runtime_services.get_next_variable_name);
+//!     let (data, _attributes) = reader.get("BootOrder", global_variable)?;
+//!     Ok(data.chunks_exact(2).map(|option| u16::from_le_bytes([option[0], option[1]])).collect())
+//! }
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation. All rights reserved.
+//!
+//! SPDX-License-Identifier: BSD-2-Clause-Patent
+//!
+
+extern crate alloc;
+
+use alloc::{string::String, vec, vec::Vec};
+use core::{ffi::c_void, ptr};
+
+use r_efi::efi;
+
+use crate::protocols::read_only_variable2;
+
+/// The longest variable name, in UCS-2 characters including the null terminator, that [`VarReader`] reads. Names are
+/// converted on the stack, so reads do not allocate.
+pub const MAX_NAME_LENGTH: usize = 512;
+
+// The name buffer size names() starts with, in UCS-2 characters.
+const INITIAL_NAME_LENGTH: usize = 64;
+
+/// The attributes of a variable (EFI_VARIABLE_*).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct VarAttributes(u32);
+
+impl VarAttributes {
+    /// Creates the attributes from their raw value.
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    /// Returns the raw value of the attributes.
+    pub const fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns whether all the bits of `bits` are set.
+    pub const fn contains(&self, bits: u32) -> bool {
+        self.0 & bits == bits
+    }
+
+    /// Returns whether the variable persists across resets.
+    pub const fn is_non_volatile(&self) -> bool {
+        self.contains(efi::VARIABLE_NON_VOLATILE)
+    }
+
+    /// Returns whether the variable is accessible before ExitBootServices().
+    pub const fn has_boot_service_access(&self) -> bool {
+        self.contains(efi::VARIABLE_BOOTSERVICE_ACCESS)
+    }
+
+    /// Returns whether the variable is accessible after ExitBootServices().
+    pub const fn has_runtime_access(&self) -> bool {
+        self.contains(efi::VARIABLE_RUNTIME_ACCESS)
+    }
+
+    /// Returns whether the variable is a hardware error record.
+    pub const fn is_hardware_error_record(&self) -> bool {
+        self.contains(efi::VARIABLE_HARDWARE_ERROR_RECORD)
+    }
+
+    /// Returns whether writes to the variable must be authenticated, with any of the authentication schemes.
+    pub const fn is_authenticated(&self) -> bool {
+        self.0
+            & (efi::VARIABLE_AUTHENTICATED_WRITE_ACCESS
+                | efi::VARIABLE_TIME_BASED_AUTHENTICATED_WRITE_ACCESS
+                | efi::VARIABLE_ENHANCED_AUTHENTICATED_ACCESS)
+            != 0
+    }
+}
+
+impl From<VarAttributes> for u32 {
+    fn from(attributes: VarAttributes) -> Self {
+        attributes.0
+    }
+}
+
+/// Errors reading variables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VarError {
+    /// The variable does not exist.
+    NotFound,
+    /// The buffer is too small for the data of the variable, which has the given size.
+    BufferTooSmall(usize),
+    /// The name cannot be converted: it has characters outside UCS-2 or null characters, or it is longer than
+    /// [`MAX_NAME_LENGTH`]. Also reported for names returned by the services that are not valid UCS-2.
+    InvalidName,
+    /// The service failed with another status.
+    Status(efi::Status),
+}
+
+impl VarError {
+    fn from_status(status: efi::Status, size: usize) -> Self {
+        match status {
+            efi::Status::NOT_FOUND => VarError::NotFound,
+            efi::Status::BUFFER_TOO_SMALL => VarError::BufferTooSmall(size),
+            status => VarError::Status(status),
+        }
+    }
+}
+
+impl From<VarError> for efi::Status {
+    fn from(error: VarError) -> Self {
+        match error {
+            VarError::NotFound => efi::Status::NOT_FOUND,
+            VarError::BufferTooSmall(_) => efi::Status::BUFFER_TOO_SMALL,
+            VarError::InvalidName => efi::Status::INVALID_PARAMETER,
+            VarError::Status(status) => status,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Services<'a> {
+    Ppi(&'a read_only_variable2::Protocol),
+    RuntimeServices { get_variable: efi::RuntimeGetVariable, get_next_variable_name: efi::RuntimeGetNextVariableName },
+}
+
+/// Reads variables with the Read Only Variable2 PPI or the variable runtime services.
+#[derive(Debug, Clone, Copy)]
+pub struct VarReader<'a>(Services<'a>);
+
+impl<'a> VarReader<'a> {
+    /// Reads variables with the Read Only Variable2 PPI.
+    pub fn from_ppi(ppi: &'a read_only_variable2::Protocol) -> Self {
+        Self(Services::Ppi(ppi))
+    }
+
+    /// Reads variables with the GetVariable() and GetNextVariableName() runtime services.
+    pub fn from_runtime_services(
+        get_variable: efi::RuntimeGetVariable,
+        get_next_variable_name: efi::RuntimeGetNextVariableName,
+    ) -> Self {
+        Self(Services::RuntimeServices { get_variable, get_next_variable_name })
+    }
+
+    fn get_variable(
+        &self,
+        name: &[u16],
+        vendor: &efi::Guid,
+        attributes: &mut u32,
+        data_size: &mut usize,
+        data: *mut c_void,
+    ) -> efi::Status {
+        match self.0 {
+            Services::Ppi(ppi) => (ppi.get_variable)(ppi, name.as_ptr(), vendor, attributes, data_size, data),
+            // The runtime services do not modify the name or vendor GUID, despite the mutable pointers.
+            Services::RuntimeServices { get_variable, .. } => get_variable(
+                name.as_ptr() as *mut u16,
+                vendor as *const efi::Guid as *mut efi::Guid,
+                attributes,
+                data_size,
+                data,
+            ),
+        }
+    }
+
+    fn get_next_variable_name(&self, name_size: &mut usize, name: &mut [u16], vendor: &mut efi::Guid) -> efi::Status {
+        match self.0 {
+            Services::Ppi(ppi) => (ppi.next_variable_name)(ppi, name_size, name.as_mut_ptr(), vendor),
+            Services::RuntimeServices { get_next_variable_name, .. } => {
+                get_next_variable_name(name_size, name.as_mut_ptr(), vendor)
+            }
+        }
+    }
+
+    /// Returns the data and attributes of the variable `name` of `vendor`.
+    pub fn get(&self, name: &str, vendor: &efi::Guid) -> Result<(Vec<u8>, VarAttributes), VarError> {
+        let mut data = Vec::new();
+        loop {
+            match self.get_into(name, vendor, &mut data) {
+                Ok((size, attributes)) => {
+                    data.truncate(size);
+                    return Ok((data, attributes));
+                }
+                // The variable may grow between the calls, in which case its size is queried again.
+                Err(VarError::BufferTooSmall(size)) if size > data.len() => data.resize(size, 0),
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Reads the data of the variable `name` of `vendor` into `buffer`, returning the size of the data and the
+    /// attributes of the variable. Does not allocate.
+    ///
+    /// Fails with [`VarError::BufferTooSmall`] holding the size of the data if it does not fit in `buffer`.
+    pub fn get_into(
+        &self,
+        name: &str,
+        vendor: &efi::Guid,
+        buffer: &mut [u8],
+    ) -> Result<(usize, VarAttributes), VarError> {
+        let mut name_buffer = [0u16; MAX_NAME_LENGTH];
+        let name = encode_name(name, &mut name_buffer)?;
+
+        let mut attributes = 0u32;
+        let mut size = buffer.len();
+        let data = match buffer.is_empty() {
+            true => ptr::null_mut(),
+            false => buffer.as_mut_ptr() as *mut c_void,
+        };
+        match self.get_variable(name, vendor, &mut attributes, &mut size, data) {
+            efi::Status::SUCCESS => Ok((size, VarAttributes(attributes))),
+            status => Err(VarError::from_status(status, size)),
+        }
+    }
+
+    /// Returns an iterator over the names and vendor GUIDs of the variables, only those of `vendor_filter` if given.
+    ///
+    /// The iteration ends after an error from the services, except for names that are not valid UCS-2, which are
+    /// reported as [`VarError::InvalidName`] before continuing with the next variable.
+    pub fn names(&self, vendor_filter: Option<&efi::Guid>) -> VariableNames<'a> {
+        VariableNames {
+            reader: *self,
+            vendor_filter: vendor_filter.copied(),
+            name: vec![0; INITIAL_NAME_LENGTH],
+            vendor: efi::Guid::from_bytes(&[0; 16]),
+            done: false,
+        }
+    }
+}
+
+/// An iterator over the names and vendor GUIDs of the variables, created by [`VarReader::names`].
+#[derive(Debug)]
+pub struct VariableNames<'a> {
+    reader: VarReader<'a>,
+    vendor_filter: Option<efi::Guid>,
+    name: Vec<u16>,
+    vendor: efi::Guid,
+    done: bool,
+}
+
+impl Iterator for VariableNames<'_> {
+    type Item = Result<(String, efi::Guid), VarError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let mut size = self.name.len() * 2;
+            match self.reader.get_next_variable_name(&mut size, &mut self.name, &mut self.vendor) {
+                efi::Status::SUCCESS => {
+                    if self.vendor_filter.map_or(true, |vendor| vendor == self.vendor) {
+                        return Some(decode_name(&self.name).map(|name| (name, self.vendor)));
+                    }
+                }
+                // The buffer keeps the previous name, which the services need to find the next one.
+                efi::Status::BUFFER_TOO_SMALL if size.div_ceil(2) > self.name.len() => {
+                    self.name.resize(size.div_ceil(2), 0)
+                }
+                efi::Status::NOT_FOUND => self.done = true,
+                status => {
+                    self.done = true;
+                    return Some(Err(VarError::from_status(status, size)));
+                }
+            }
+        }
+        None
+    }
+}
+
+// Converts the name to null-terminated UCS-2 in `buffer`.
+fn encode_name<'b>(name: &str, buffer: &'b mut [u16]) -> Result<&'b [u16], VarError> {
+    let mut length = 0;
+    for character in name.chars() {
+        if character == '\0' || character.len_utf16() != 1 || length + 1 >= buffer.len() {
+            Err(VarError::InvalidName)?;
+        }
+        buffer[length] = character as u16;
+        length += 1;
+    }
+    buffer[length] = 0;
+    Ok(&buffer[..=length])
+}
+
+// Converts the null-terminated UCS-2 name in `buffer`.
+fn decode_name(buffer: &[u16]) -> Result<String, VarError> {
+    let length = buffer.iter().position(|&character| character == 0).unwrap_or(buffer.len());
+    char::decode_utf16(buffer[..length].iter().copied())
+        .collect::<Result<String, _>>()
+        .map_err(|_| VarError::InvalidName)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{cell::RefCell, ffi::c_void, ptr, slice};
+
+    use r_efi::efi;
+
+    use super::{VarAttributes, VarError, VarReader, MAX_NAME_LENGTH};
+    use crate::protocols::read_only_variable2;
+
+    const VENDOR: efi::Guid =
+        efi::Guid::from_fields(0x4c2a7f3e, 0x91b4, 0x4d6a, 0xa2, 0x3c, &[0x5e, 0x18, 0xf0, 0x7b, 0x9d, 0x41]);
+    const OTHER_VENDOR: efi::Guid =
+        efi::Guid::from_fields(0x0b6e5233, 0xa65c, 0x44c9, 0x94, 0x07, &[0xd9, 0xab, 0x83, 0xbf, 0xc8, 0xbd]);
+
+    const ATTRIBUTES: u32 = efi::VARIABLE_NON_VOLATILE | efi::VARIABLE_BOOTSERVICE_ACCESS;
+
+    struct Variable {
+        name: Vec<u16>,
+        vendor: efi::Guid,
+        attributes: u32,
+        data: Vec<u8>,
+    }
+
+    thread_local! {
+        static STORE: RefCell<Vec<Variable>> = const { RefCell::new(Vec::new()) };
+    }
+
+    fn long_name() -> String {
+        "LongVariableName".repeat(7)
+    }
+
+    // Fills the store. Reading "Broken" fails with DEVICE_ERROR.
+    fn fill_store() {
+        let variables = [
+            ("Boot0000", VENDOR, ATTRIBUTES | efi::VARIABLE_RUNTIME_ACCESS, vec![0x01, 0x00, 0x00, 0x00]),
+            ("Empty", VENDOR, ATTRIBUTES, vec![]),
+            ("Other", OTHER_VENDOR, ATTRIBUTES, vec![0x5a; 4]),
+            (&long_name(), VENDOR, ATTRIBUTES, vec![0xa5; 0x100]),
+            ("Broken", VENDOR, ATTRIBUTES, vec![]),
+        ]
+        .into_iter()
+        .map(|(name, vendor, attributes, data)| Variable {
+            name: name.encode_utf16().collect(),
+            vendor,
+            attributes,
+            data,
+        })
+        .collect();
+        STORE.with(|store| *store.borrow_mut() = variables);
+    }
+
+    fn ucs2(name: *const u16) -> Vec<u16> {
+        let length = (0..).position(|index| unsafe { *name.add(index) } == 0).unwrap();
+        unsafe { slice::from_raw_parts(name, length) }.to_vec()
+    }
+
+    fn get_variable(
+        name: *const u16,
+        vendor: *const efi::Guid,
+        attributes: *mut u32,
+        data_size: *mut usize,
+        data: *mut c_void,
+    ) -> efi::Status {
+        let name = ucs2(name);
+        if name == "Broken".encode_utf16().collect::<Vec<_>>() {
+            return efi::Status::DEVICE_ERROR;
+        }
+        STORE.with(|store| {
+            let store = store.borrow();
+            let Some(variable) =
+                store.iter().find(|variable| variable.name == name && variable.vendor == unsafe { *vendor })
+            else {
+                return efi::Status::NOT_FOUND;
+            };
+            unsafe {
+                if !attributes.is_null() {
+                    *attributes = variable.attributes;
+                }
+                let size = *data_size;
+                *data_size = variable.data.len();
+                if size < variable.data.len() {
+                    return efi::Status::BUFFER_TOO_SMALL;
+                }
+                if !variable.data.is_empty() {
+                    ptr::copy_nonoverlapping(variable.data.as_ptr(), data as *mut u8, variable.data.len());
+                }
+            }
+            efi::Status::SUCCESS
+        })
+    }
+
+    fn get_next_variable_name(name_size: *mut usize, name: *mut u16, vendor: *mut efi::Guid) -> efi::Status {
+        let current = ucs2(name);
+        STORE.with(|store| {
+            let store = store.borrow();
+            let index = match current.is_empty() {
+                true => 0,
+                false => match store
+                    .iter()
+                    .position(|variable| variable.name == current && variable.vendor == unsafe { *vendor })
+                {
+                    Some(index) => index + 1,
+                    None => return efi::Status::INVALID_PARAMETER,
+                },
+            };
+            let Some(variable) = store.get(index) else {
+                return efi::Status::NOT_FOUND;
+            };
+            let size = (variable.name.len() + 1) * 2;
+            unsafe {
+                if *name_size < size {
+                    *name_size = size;
+                    return efi::Status::BUFFER_TOO_SMALL;
+                }
+                *name_size = size;
+                ptr::copy_nonoverlapping(variable.name.as_ptr(), name, variable.name.len());
+                *name.add(variable.name.len()) = 0;
+                *vendor = variable.vendor;
+            }
+            efi::Status::SUCCESS
+        })
+    }
+
+    extern "efiapi" fn ppi_get_variable(
+        _: *const read_only_variable2::Protocol,
+        name: *const u16,
+        vendor: *const efi::Guid,
+        attributes: *mut u32,
+        data_size: *mut usize,
+        data: *mut c_void,
+    ) -> efi::Status {
+        get_variable(name, vendor, attributes, data_size, data)
+    }
+
+    extern "efiapi" fn ppi_next_variable_name(
+        _: *const read_only_variable2::Protocol,
+        name_size: *mut usize,
+        name: *mut u16,
+        vendor: *mut efi::Guid,
+    ) -> efi::Status {
+        get_next_variable_name(name_size, name, vendor)
+    }
+
+    extern "efiapi" fn runtime_get_variable(
+        name: *mut u16,
+        vendor: *mut efi::Guid,
+        attributes: *mut u32,
+        data_size: *mut usize,
+        data: *mut c_void,
+    ) -> efi::Status {
+        get_variable(name, vendor, attributes, data_size, data)
+    }
+
+    extern "efiapi" fn runtime_get_next_variable_name(
+        name_size: *mut usize,
+        name: *mut u16,
+        vendor: *mut efi::Guid,
+    ) -> efi::Status {
+        get_next_variable_name(name_size, name, vendor)
+    }
+
+    static PPI: read_only_variable2::Protocol =
+        read_only_variable2::Protocol { get_variable: ppi_get_variable, next_variable_name: ppi_next_variable_name };
+
+    fn readers() -> [VarReader<'static>; 2] {
+        fill_store();
+        [
+            VarReader::from_ppi(&PPI),
+            VarReader::from_runtime_services(runtime_get_variable, runtime_get_next_variable_name),
+        ]
+    }
+
+    #[test]
+    fn get_should_read_the_data_and_attributes() {
+        for reader in readers() {
+            let (data, attributes) = reader.get("Boot0000", &VENDOR).unwrap();
+            assert_eq!(data, [0x01, 0x00, 0x00, 0x00]);
+            assert!(
+                attributes.is_non_volatile() && attributes.has_boot_service_access() && attributes.has_runtime_access()
+            );
+
+            assert_eq!(
+                reader.get(&long_name(), &VENDOR),
+                Ok((vec![0xa5; 0x100], VarAttributes::from_bits(ATTRIBUTES)))
+            );
+            assert_eq!(reader.get("Empty", &VENDOR), Ok((vec![], VarAttributes::from_bits(ATTRIBUTES))));
+            assert_eq!(reader.get("Boot0000", &OTHER_VENDOR), Err(VarError::NotFound));
+            assert_eq!(reader.get("Broken", &VENDOR), Err(VarError::Status(efi::Status::DEVICE_ERROR)));
+        }
+    }
+
+    #[test]
+    fn get_into_should_report_the_size_needed() {
+        for reader in readers() {
+            let mut buffer = [0u8; 8];
+            assert_eq!(reader.get_into("Boot0000", &VENDOR, &mut buffer[..2]), Err(VarError::BufferTooSmall(4)));
+            let (size, attributes) = reader.get_into("Boot0000", &VENDOR, &mut buffer).unwrap();
+            assert_eq!(&buffer[..size], [0x01, 0x00, 0x00, 0x00]);
+            assert_eq!(attributes.bits(), ATTRIBUTES | efi::VARIABLE_RUNTIME_ACCESS);
+            assert_eq!(reader.get_into("Missing", &VENDOR, &mut buffer), Err(VarError::NotFound));
+        }
+    }
+
+    #[test]
+    fn names_should_grow_the_name_buffer_and_filter_vendors() {
+        for reader in readers() {
+            let names: Vec<_> = reader.names(None).collect();
+            assert_eq!(
+                names,
+                [
+                    Ok(("Boot0000".into(), VENDOR)),
+                    Ok(("Empty".into(), VENDOR)),
+                    Ok(("Other".into(), OTHER_VENDOR)),
+                    Ok((long_name(), VENDOR)),
+                    Ok(("Broken".into(), VENDOR)),
+                ]
+            );
+
+            let names: Vec<_> = reader.names(Some(&OTHER_VENDOR)).collect();
+            assert_eq!(names, [Ok(("Other".into(), OTHER_VENDOR))]);
+        }
+    }
+
+    #[test]
+    fn names_should_stop_after_an_error() {
+        let [reader, _] = readers();
+        STORE.with(|store| store.borrow_mut()[1].name = vec![0xd800]);
+        let names: Vec<_> = reader.names(None).take(3).collect();
+        assert_eq!(
+            names,
+            [Ok(("Boot0000".into(), VENDOR)), Err(VarError::InvalidName), Ok(("Other".into(), OTHER_VENDOR))]
+        );
+
+        STORE.with(|store| store.borrow_mut().clear());
+        assert_eq!(reader.names(None).count(), 0);
+    }
+
+    #[test]
+    fn invalid_names_should_be_rejected() {
+        for reader in readers() {
+            assert_eq!(reader.get("Boot\u{0}", &VENDOR), Err(VarError::InvalidName));
+            assert_eq!(reader.get("Boot\u{1F600}", &VENDOR), Err(VarError::InvalidName));
+            assert_eq!(reader.get(&"A".repeat(MAX_NAME_LENGTH), &VENDOR), Err(VarError::InvalidName));
+            assert_eq!(reader.get(&"A".repeat(MAX_NAME_LENGTH - 1), &VENDOR), Err(VarError::NotFound));
+        }
+    }
+
+    #[test]
+    fn attributes_should_decode_authentication() {
+        assert!(!VarAttributes::from_bits(ATTRIBUTES).is_authenticated());
+        assert!(VarAttributes::from_bits(efi::VARIABLE_TIME_BASED_AUTHENTICATED_WRITE_ACCESS).is_authenticated());
+        assert!(VarAttributes::from_bits(efi::VARIABLE_HARDWARE_ERROR_RECORD).is_hardware_error_record());
+        assert_eq!(efi::Status::from(VarError::BufferTooSmall(4)), efi::Status::BUFFER_TOO_SMALL);
+    }
+}